@@ -0,0 +1,142 @@
+//! 原子写入与输出目录锁
+//!
+//! 生成的文件先写入同一目录下的临时文件再重命名为目标文件，崩溃、Ctrl+C 或并发构建都只会看到
+//! 旧文件或完整的新文件，不会读到截断的头文件
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use tracing::{info, warn};
+
+/// 输出目录中锁文件的名称
+pub const LOCK_FILE_NAME: &str = ".rplc.lock";
+
+/// 等待其他进程释放输出锁时的轮询间隔
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 同一进程内临时文件的序号，监视模式下多个线程可能同时写入同一目录
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// 当前进程持有的锁文件，进程通过 `ExitStatus::exit` 提前退出时据此删除
+static HELD_LOCK: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// 先写入 `.<文件名>.<pid>.<序号>.tmp` 并落盘，再重命名为 `path`；失败时删除临时文件
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "输出路径没有文件名"))?;
+    let temp = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        file.write_all(content.as_ref())?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// 输出目录锁：共享的网络构建目录中，同一时刻只有一个 rplc 进程写入
+///
+/// 锁文件以 `create_new` 创建，在 NFS 与 SMB 上同样是原子的；其中记录持有者的主机与进程号，
+/// 方便清理异常退出后遗留的锁
+#[derive(Debug)]
+pub struct OutputLock {
+    path: PathBuf,
+}
+
+impl OutputLock {
+    /// 在 `dir` 中创建锁文件，已被占用时等待至多 `timeout`
+    pub fn acquire(dir: &Path, timeout: Duration) -> io::Result<OutputLock> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(LOCK_FILE_NAME);
+        let start = Instant::now();
+        let mut reported = false;
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    writeln!(file, "host={}\npid={}", host_name(), process::id())?;
+                    *HELD_LOCK.lock().unwrap_or_else(|e| e.into_inner()) = Some(path.clone());
+                    return Ok(OutputLock { path });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    if start.elapsed() >= timeout {
+                        let holder = fs::read_to_string(&path).unwrap_or_default();
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!(
+                                "等待 {} s 后仍被占用（{}），若持有者已退出请删除 {:?}",
+                                timeout.as_secs(),
+                                holder.trim().replace('\n', ", "),
+                                path
+                            ),
+                        ));
+                    }
+                    if !reported {
+                        info!("输出目录被其他 rplc 进程锁定，等待释放: {:?}", path);
+                        reported = true;
+                    }
+                    thread::sleep(LOCK_POLL_INTERVAL);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for OutputLock {
+    fn drop(&mut self) {
+        let mut held = HELD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        if held.as_ref() == Some(&self.path) {
+            *held = None;
+            remove_lock(&self.path);
+        }
+    }
+}
+
+/// 删除当前进程持有的锁文件；`process::exit` 不会运行析构函数，提前退出前需要显式调用
+pub fn release_held_lock() {
+    let held = HELD_LOCK.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(path) = held {
+        remove_lock(&path);
+    }
+}
+
+fn remove_lock(path: &Path) {
+    if let Err(e) = fs::remove_file(path) {
+        warn!("无法删除输出锁 {:?}: {}", path, e);
+    }
+}
+
+fn host_name() -> String {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|key| std::env::var(key).ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
@@ -0,0 +1,708 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use clap::{Subcommand, ValueEnum};
+use miette::{NamedSource, Report};
+use rplc_core::{
+    BenchBaseline, CURRENT_SCHEMA, ColumnMap, Config, ConfigOrArray, DEFAULT_HEADER_TEMPLATE,
+    DiagnosticBaseline, HeaderImport, RplcDiagnostic, Severity, TableImport, ValidateOptions,
+    bench_workloads, compare_bench, compute_layout, config_schema, explain, explained_codes,
+    format_definition, import_header, import_table, layout_breaks, layout_table,
+    migrate_definition, new_packet_set_template, new_packet_template, validate, validate_multiple,
+    validate_multiple_with_options, validate_with_options,
+};
+use tracing::{error, info, warn};
+
+use crate::atomic::write_atomic;
+use crate::exit::{ExitStatus, OrExit};
+use crate::{project_config, read_definition, read_source};
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Create a new packet definition from the built-in template
+    Init {
+        /// Packet name, a PascalCase C++ identifier
+        #[arg(value_name = "NAME")]
+        name: String,
+
+        /// Command ID, decimal or 0x-prefixed hex
+        #[arg(long, value_name = "ID", default_value = "0x0001")]
+        cmd: String,
+
+        /// Create a packet set with `protocol_version` instead of a single packet
+        #[arg(long)]
+        set: bool,
+
+        /// Output file, defaults to <NAME>.json in the current directory
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Overwrite the output file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Validate a definition without generating code, optionally hiding diagnostics recorded in a baseline
+    Check {
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Only report diagnostics that are not recorded in this baseline
+        #[arg(long, value_name = "FILE", conflicts_with = "write_baseline")]
+        baseline: Option<PathBuf>,
+
+        /// Record every current diagnostic (by code, packet and field) as a baseline and exit successfully
+        #[arg(long, value_name = "FILE")]
+        write_baseline: Option<PathBuf>,
+
+        /// Treat warnings as errors: exit with code 1 instead of 4
+        #[arg(long, conflicts_with = "write_baseline")]
+        deny_warnings: bool,
+    },
+
+    /// Compare two versions of a definition and fail if a packet no longer matches deployed firmware
+    Compat {
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+
+        /// What must stay the same for every packet in OLD
+        #[arg(long, value_enum, value_name = "LEVEL", default_value = "layout")]
+        require: Requirement,
+    },
+
+    /// Print each packet's field offsets, sizes and padding as computed by rplc, for checking against a protocol spec
+    Layout {
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Print the layouts as JSON instead of tables
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Rewrite definitions in canonical key order and formatting
+    Fmt {
+        #[arg(value_name = "FILE", required = true)]
+        files: Vec<PathBuf>,
+
+        /// Do not write files; exit with status 1 if any file is not formatted
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Convert a CSV field table exported from a spreadsheet into a packet definition
+    Import {
+        #[arg(value_name = "TABLE")]
+        table: PathBuf,
+
+        /// Packet name, a PascalCase C++ identifier
+        #[arg(long, value_name = "NAME")]
+        name: String,
+
+        /// Command ID, decimal or 0x-prefixed hex
+        #[arg(long, value_name = "ID", default_value = "0x0001")]
+        cmd: String,
+
+        /// Columns for name, type, comment and bit_field, by header text or column letter
+        #[arg(long, value_name = "MAP", default_value = "name=A,type=B,comment=C")]
+        map: String,
+
+        /// The first row is a field, not a header
+        #[arg(long)]
+        no_header: bool,
+
+        /// Output file, defaults to <NAME>.json in the current directory
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Overwrite the output file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Convert packed structs in a hand-written C header into packet definitions
+    ImportHeader {
+        #[arg(value_name = "HEADER")]
+        header: PathBuf,
+
+        /// First command ID for structs without a <NAME>_CMD macro, decimal or 0x-prefixed hex
+        #[arg(long, value_name = "ID", default_value = "0x0001")]
+        cmd_start: String,
+
+        /// Output file, defaults to the header name with a .json extension
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Overwrite the output file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Upgrade definitions to the current `rplc_schema` format version
+    Migrate {
+        #[arg(value_name = "FILE", required = true)]
+        files: Vec<PathBuf>,
+
+        /// Do not write files; exit with status 1 if any file needs migration
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Measure throughput of the built-in workloads and compare it against a stored baseline
+    Bench {
+        /// Baseline written by --save; exit with status 1 if a workload got slower than --tolerance
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<PathBuf>,
+
+        /// Write the measured timings as a new baseline
+        #[arg(long, value_name = "FILE")]
+        save: Option<PathBuf>,
+
+        /// Allowed slowdown relative to the baseline, in percent
+        #[arg(long, value_name = "PERCENT", default_value_t = 10.0)]
+        tolerance: f64,
+
+        /// Measuring time per workload, in milliseconds
+        #[arg(long, value_name = "MS", default_value_t = 1000)]
+        measure_ms: u64,
+    },
+
+    /// Show a detailed explanation for a diagnostic code, e.g. bit_field::length_overflow
+    Explain {
+        /// Diagnostic code, with or without the `rplc::` prefix; omit to list all codes
+        #[arg(value_name = "CODE")]
+        code: Option<String>,
+    },
+
+    /// Print a JSON Schema for definition files, for editor autocompletion and validation
+    Schema {
+        /// Write the schema to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Print the built-in C++ header template, as a starting point for --template
+    Template {
+        /// Write the template to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// `rplc compat` 要求保持不变的内容
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Requirement {
+    /// Each packet keeps its command ID and wire layout (member types, offsets, sizes and byte order)
+    Layout,
+}
+
+impl Command {
+    pub fn run(self) {
+        match self {
+            Command::Init {
+                name,
+                cmd,
+                set,
+                output,
+                force,
+            } => init(&name, &cmd, set, output, force),
+            Command::Import {
+                table,
+                name,
+                cmd,
+                map,
+                no_header,
+                output,
+                force,
+            } => import(&table, name, cmd, &map, !no_header, output, force),
+            Command::ImportHeader {
+                header,
+                cmd_start,
+                output,
+                force,
+            } => import_c_header(&header, cmd_start, output, force),
+            Command::Check {
+                file,
+                baseline,
+                write_baseline,
+                deny_warnings,
+            } => check(
+                &file,
+                baseline.as_deref(),
+                write_baseline.as_deref(),
+                deny_warnings,
+            ),
+            Command::Compat { old, new, require } => compat(&old, &new, require),
+            Command::Layout { file, json } => print_layout(&file, json),
+            Command::Fmt { files, check } => fmt(&files, check),
+            Command::Migrate { files, check } => migrate(&files, check),
+            Command::Bench {
+                baseline,
+                save,
+                tolerance,
+                measure_ms,
+            } => bench(baseline.as_deref(), save.as_deref(), tolerance, measure_ms),
+            Command::Explain { code } => explain_code(code.as_deref()),
+            Command::Schema { output } => print_or_write(&config_schema(), output.as_deref()),
+            Command::Template { output } => {
+                print_or_write(DEFAULT_HEADER_TEMPLATE, output.as_deref())
+            }
+        }
+    }
+}
+
+fn init(name: &str, cmd: &str, set: bool, output: Option<PathBuf>, force: bool) {
+    let template = if set {
+        new_packet_set_template(name, cmd)
+    } else {
+        new_packet_template(name, cmd)
+    }
+    .or_exit(ExitStatus::Usage, || "无法创建包定义".to_string());
+
+    let output_path = output.unwrap_or_else(|| PathBuf::from(format!("{}.json", name.trim())));
+    // 避免覆盖已有的协议定义
+    if output_path.exists() && !force {
+        error!("文件已存在: {:?}，使用 --force 覆盖", output_path);
+        ExitStatus::Usage.exit();
+    }
+    write_atomic(&output_path, template).or_exit(ExitStatus::Usage, || {
+        format!("无法写入文件: {:?}", output_path)
+    });
+    info!("已创建: {:?}", output_path);
+}
+
+fn import(
+    table: &Path,
+    name: String,
+    cmd: String,
+    map: &str,
+    header: bool,
+    output: Option<PathBuf>,
+    force: bool,
+) {
+    let columns: ColumnMap = map
+        .parse()
+        .or_exit(ExitStatus::Usage, || "无法解析列映射".to_string());
+    let output_path = output.unwrap_or_else(|| PathBuf::from(format!("{}.json", name.trim())));
+    if output_path.exists() && !force {
+        error!("文件已存在: {:?}，使用 --force 覆盖", output_path);
+        ExitStatus::Usage.exit();
+    }
+
+    let options = TableImport {
+        packet_name: name,
+        command_id: cmd,
+        columns,
+        header,
+    };
+    let json = import_table(&read_source(table), &options)
+        .or_exit(ExitStatus::Usage, || format!("无法导入表格: {:?}", table));
+    write_atomic(&output_path, &json).or_exit(ExitStatus::Usage, || {
+        format!("无法写入文件: {:?}", output_path)
+    });
+    info!("已导入: {:?} → {:?}", table, output_path);
+
+    // 立即校验，表格中的类型拼写错误等问题在导入时就能发现
+    report_imported(&output_path, json, validate);
+}
+
+fn import_c_header(header: &Path, cmd_start: String, output: Option<PathBuf>, force: bool) {
+    let output_path = output.unwrap_or_else(|| header.with_extension("json"));
+    if output_path.exists() && !force {
+        error!("文件已存在: {:?}，使用 --force 覆盖", output_path);
+        ExitStatus::Usage.exit();
+    }
+
+    let options = HeaderImport {
+        first_command_id: cmd_start,
+    };
+    let imported = import_header(&read_source(header), &options).or_exit(ExitStatus::Usage, || {
+        format!("无法导入头文件: {:?}", header)
+    });
+    for note in &imported.notes {
+        warn!("{}", note);
+    }
+    write_atomic(&output_path, &imported.json).or_exit(ExitStatus::Usage, || {
+        format!("无法写入文件: {:?}", output_path)
+    });
+    info!(
+        "已导入 {} 个包: {:?} → {:?}",
+        imported.packets.len(),
+        header,
+        output_path
+    );
+    report_imported(&output_path, imported.json, validate_multiple);
+}
+
+/// 打印导入结果的校验诊断，存在错误时以校验失败退出
+fn report_imported(output_path: &Path, json: String, validate: fn(&str) -> Vec<RplcDiagnostic>) {
+    let diags = validate(&json);
+    let has_errors = diags.iter().any(|diag| diag.severity == Severity::Error);
+    let source_code = NamedSource::new(output_path.to_string_lossy(), json);
+    for diag in diags {
+        println!(
+            "{:?}",
+            Report::new(diag).with_source_code(source_code.clone())
+        );
+    }
+    if has_errors {
+        ExitStatus::Validation.exit();
+    }
+}
+
+/// 与 `rplc --check` 的退出码相同：只有警告时为 4，`--deny-warnings` 时为 1
+fn check(file: &Path, baseline: Option<&Path>, write_baseline: Option<&Path>, deny_warnings: bool) {
+    let project =
+        project_config(file).or_exit(ExitStatus::Usage, || "无法加载项目配置".to_string());
+    let options = ValidateOptions {
+        allowed_types: project.allowed_types,
+        compiler_profiles: project.compiler_profiles,
+        ..ValidateOptions::default()
+    };
+    let (source, transcript) = read_definition(file);
+    let json = transcript.json();
+    let diags = if ConfigOrArray::is_multi_input(json) {
+        validate_multiple_with_options(json, &options)
+    } else {
+        validate_with_options(json, &options)
+    };
+
+    if let Some(path) = write_baseline {
+        let baseline = DiagnosticBaseline::new(json, &diags);
+        let content = serde_json::to_string_pretty(&baseline)
+            .or_exit(ExitStatus::Internal, || "无法序列化基线".to_string());
+        write_atomic(path, content + "\n")
+            .or_exit(ExitStatus::Usage, || format!("无法写入文件: {:?}", path));
+        info!(
+            "已将 {} 个诊断记录到基线: {:?}",
+            baseline.diagnostics.len(),
+            path
+        );
+        return;
+    }
+
+    // 基线按代码、包名与字段名匹配，位置取自转写后的 JSON
+    let diags = match baseline {
+        Some(path) => {
+            let baseline: DiagnosticBaseline = serde_json::from_str(&read_source(path))
+                .or_exit(ExitStatus::Usage, || {
+                    format!("无法解析基线文件: {:?}", path)
+                });
+            let total = diags.len();
+            let new = baseline.suppress(json, diags);
+            let suppressed = total - new.len();
+            info!("基线中的 {} 个已知问题未报告", suppressed);
+            let fixed = baseline.diagnostics.len() - suppressed;
+            if fixed > 0 {
+                info!(
+                    "基线中有 {} 个问题已经修复，可以用 --write-baseline 重新生成基线",
+                    fixed
+                );
+            }
+            new
+        }
+        None => diags,
+    };
+
+    let has_errors = diags.iter().any(|diag| diag.severity == Severity::Error);
+    let warnings = diags
+        .iter()
+        .filter(|diag| diag.severity == Severity::Warning)
+        .count();
+    let source_code = NamedSource::new(file.to_string_lossy(), source);
+    for diag in transcript.map_diagnostics(diags) {
+        println!(
+            "{:?}",
+            Report::new(diag).with_source_code(source_code.clone())
+        );
+    }
+    match ExitStatus::for_check(has_errors, warnings, deny_warnings) {
+        Some(ExitStatus::Warnings) => {
+            info!("校验通过，但有 {} 个警告: {:?}", warnings, file);
+            ExitStatus::Warnings.exit();
+        }
+        Some(status) => {
+            if !has_errors {
+                error!("存在 {} 个警告，--deny-warnings 将其视为错误", warnings);
+            }
+            error!("校验未通过: {:?}", file);
+            status.exit();
+        }
+        None => info!("校验通过: {:?}", file),
+    }
+}
+
+fn compat(old: &Path, new: &Path, require: Requirement) {
+    let Requirement::Layout = require;
+    let (old_packets, new_packets) = (read_packets(old), read_packets(new));
+    let breaks = layout_breaks(&old_packets, &new_packets);
+    for broken in &breaks {
+        error!("{}", broken);
+    }
+    if !breaks.is_empty() {
+        error!(
+            "{} 个包的线上布局与 {:?} 不同，已部署的固件无法解析",
+            breaks.len(),
+            old
+        );
+        ExitStatus::Validation.exit();
+    }
+    info!("{:?} 中的 {} 个包线上布局均未改变", old, old_packets.len());
+}
+
+/// 打印每个包的布局表，`--layout` 与 `rplc layout` 共用
+pub(crate) fn print_layout(file: &Path, json: bool) {
+    let packets = read_packets(file);
+    let mut layouts = Vec::with_capacity(packets.len());
+    for packet in &packets {
+        let Some(layout) = compute_layout(packet) else {
+            error!(
+                "无法计算包 '{}' 的布局，请先用 rplc check 检查定义",
+                packet.packet_name
+            );
+            ExitStatus::Validation.exit();
+        };
+        layouts.push((packet, layout));
+    }
+
+    if json {
+        let records: Vec<_> = layouts
+            .iter()
+            .map(|(packet, layout)| {
+                serde_json::json!({
+                    "packet": packet.packet_name,
+                    "command_id": packet.command_id,
+                    "layout": layout,
+                })
+            })
+            .collect();
+        let output = serde_json::to_string_pretty(&records)
+            .or_exit(ExitStatus::Internal, || "无法序列化布局".to_string());
+        println!("{}", output);
+        return;
+    }
+    for (index, (packet, _)) in layouts.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        // 布局已经计算成功，表格不会为空
+        print!("{}", layout_table(packet).unwrap_or_default());
+    }
+}
+
+/// 读取定义文件中的全部包，引用其他包的字段附带被引用的包
+fn read_packets(path: &Path) -> Vec<Config> {
+    let (_, transcript) = read_definition(path);
+    serde_json::from_str::<ConfigOrArray>(transcript.json())
+        .or_exit(ExitStatus::Validation, || {
+            format!("无法解析文件: {:?}", path)
+        })
+        .into_packets()
+}
+
+fn fmt(files: &[PathBuf], check: bool) {
+    let mut failed = false;
+    for path in files {
+        let content = read_source(path);
+        let formatted = match format_definition(&content) {
+            Ok(formatted) => formatted,
+            Err(diags) => {
+                let source_code = NamedSource::new(path.to_string_lossy(), content.clone());
+                for diag in diags {
+                    println!(
+                        "{:?}",
+                        Report::new(diag).with_source_code(source_code.clone())
+                    );
+                }
+                failed = true;
+                continue;
+            }
+        };
+
+        if formatted == content {
+            continue;
+        }
+        if check {
+            error!("未格式化: {:?}", path);
+            failed = true;
+        } else {
+            write_atomic(path, formatted)
+                .or_exit(ExitStatus::Usage, || format!("无法写入文件: {:?}", path));
+            info!("已格式化: {:?}", path);
+        }
+    }
+
+    if failed {
+        ExitStatus::Validation.exit();
+    }
+}
+
+fn migrate(files: &[PathBuf], check: bool) {
+    let mut failed = false;
+    for path in files {
+        let content = read_source(path);
+        let migration = match migrate_definition(&content) {
+            Ok(migration) => migration,
+            Err(diags) => {
+                let source_code = NamedSource::new(path.to_string_lossy(), content.clone());
+                for diag in diags {
+                    println!(
+                        "{:?}",
+                        Report::new(diag).with_source_code(source_code.clone())
+                    );
+                }
+                failed = true;
+                continue;
+            }
+        };
+
+        if migration.from == CURRENT_SCHEMA {
+            continue;
+        }
+        if check {
+            error!(
+                "需要迁移: {:?}（rplc_schema {} → {}）",
+                path, migration.from, CURRENT_SCHEMA
+            );
+            failed = true;
+        } else {
+            write_atomic(path, migration.output)
+                .or_exit(ExitStatus::Usage, || format!("无法写入文件: {:?}", path));
+            info!(
+                "已迁移: {:?}（rplc_schema {} → {}）",
+                path, migration.from, CURRENT_SCHEMA
+            );
+        }
+    }
+
+    if failed {
+        ExitStatus::Validation.exit();
+    }
+}
+
+/// 每项工作负载的采样次数，取最快一次作为结果，减少调度与频率波动的影响
+const BENCH_SAMPLES: u32 = 10;
+
+fn bench(baseline: Option<&Path>, save: Option<&Path>, tolerance: f64, measure_ms: u64) {
+    if cfg!(debug_assertions) {
+        warn!("当前是调试构建，耗时不能与 release 构建的基线比较");
+    }
+    let baseline = baseline.map(|path| {
+        let content = read_source(path);
+        let baseline: BenchBaseline = serde_json::from_str(&content)
+            .or_exit(ExitStatus::Usage, || {
+                format!("无法解析基线文件: {:?}", path)
+            });
+        if baseline.version != env!("CARGO_PKG_VERSION") {
+            info!("基线由 rplc {} 生成", baseline.version);
+        }
+        baseline
+    });
+
+    let sample_time = Duration::from_millis(measure_ms.max(1)) / BENCH_SAMPLES;
+    let mut ns_per_iter = BTreeMap::new();
+    for workload in bench_workloads() {
+        // 预热一次，同时估计每个样本需要的迭代次数
+        let start = Instant::now();
+        workload.run();
+        let once = start.elapsed().max(Duration::from_nanos(1));
+        let iterations =
+            (sample_time.as_nanos() / once.as_nanos()).clamp(1, u32::MAX as u128) as u32;
+
+        let fastest = (0..BENCH_SAMPLES)
+            .map(|_| {
+                let start = Instant::now();
+                for _ in 0..iterations {
+                    workload.run();
+                }
+                start.elapsed().as_nanos() as f64 / f64::from(iterations)
+            })
+            .fold(f64::INFINITY, f64::min);
+        println!(
+            "{:<14} {:>14}  {}",
+            workload.name,
+            format_duration(fastest),
+            workload.description
+        );
+        ns_per_iter.insert(workload.name.to_string(), fastest);
+    }
+    let current = BenchBaseline::new(ns_per_iter);
+
+    if let Some(path) = save {
+        let json = serde_json::to_string_pretty(&current)
+            .or_exit(ExitStatus::Internal, || "无法序列化基线".to_string());
+        write_atomic(path, json + "\n")
+            .or_exit(ExitStatus::Usage, || format!("无法写入文件: {:?}", path));
+        info!("已保存基线: {:?}", path);
+    }
+
+    let Some(baseline) = baseline else {
+        return;
+    };
+    let mut regressed = false;
+    for comparison in compare_bench(&baseline, &current) {
+        let Some(change) = comparison.change() else {
+            info!("基线中没有 '{}'，跳过比较", comparison.name);
+            continue;
+        };
+        if comparison.is_regression(tolerance / 100.0) {
+            error!(
+                "性能回退: '{}' {} → {}（{:+.1}%，允许 {}%）",
+                comparison.name,
+                format_duration(comparison.baseline.unwrap_or_default()),
+                format_duration(comparison.current),
+                change * 100.0,
+                tolerance
+            );
+            regressed = true;
+        } else {
+            info!("'{}' 相对基线 {:+.1}%", comparison.name, change * 100.0);
+        }
+    }
+    if regressed {
+        ExitStatus::Validation.exit();
+    }
+}
+
+fn format_duration(ns: f64) -> String {
+    if ns >= 1e6 {
+        format!("{:.2} ms", ns / 1e6)
+    } else if ns >= 1e3 {
+        format!("{:.2} µs", ns / 1e3)
+    } else {
+        format!("{:.0} ns", ns)
+    }
+}
+
+fn explain_code(code: Option<&str>) {
+    let Some(code) = code else {
+        for code in explained_codes() {
+            println!("{}", code);
+        }
+        return;
+    };
+    match explain(code) {
+        Some(text) => println!("{}", text),
+        None => {
+            error!(
+                "未知的诊断代码: {}，不带参数运行 explain 可列出所有代码",
+                code
+            );
+            ExitStatus::Usage.exit();
+        }
+    }
+}
+
+/// 输出到标准输出，指定 `-o` 时写入文件
+fn print_or_write(content: &str, output: Option<&Path>) {
+    let Some(path) = output else {
+        print!("{}", content);
+        return;
+    };
+    write_atomic(path, content).or_exit(ExitStatus::Usage, || format!("无法写入文件: {:?}", path));
+    info!("已写入: {:?}", path);
+}
@@ -0,0 +1,109 @@
+//! `--format jsonl` 的事件流：运行过程中每个事件输出为标准输出中的一行 JSON，
+//! IDE 与图形界面可以据此显示实时进度，而不必解析给人看的输出
+
+use std::{
+    io::{self, Write},
+    path::Path,
+    sync::OnceLock,
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use rplc_core::RplcDiagnostic;
+use serde::Serialize;
+
+use crate::report::{DiagnosticRecord, OutputRecord};
+
+/// 标准输出的格式
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Diagnostics with source snippets, followed by a summary
+    #[default]
+    Human,
+    /// One JSON event per line
+    Jsonl,
+}
+
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// 设置本次运行的输出格式，只在启动时调用一次
+pub fn init(format: OutputFormat) {
+    let _ = FORMAT.set(format);
+}
+
+/// 是否输出 JSON Lines 事件；为 true 时给人看的诊断与汇总不再写到标准输出
+pub fn enabled() -> bool {
+    FORMAT.get() == Some(&OutputFormat::Jsonl)
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    Diagnostic {
+        file: &'a Path,
+        #[serde(flatten)]
+        record: DiagnosticRecord,
+        /// 源文件中的字节偏移与长度
+        span: Option<(usize, usize)>,
+        /// 从 1 开始的行号与列号（按字符计）
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    FileWritten(&'a OutputRecord),
+    Summary {
+        success: bool,
+        packets: usize,
+        warnings: usize,
+        errors: usize,
+        elapsed_ms: f64,
+    },
+}
+
+fn emit(event: &Event) {
+    if !enabled() {
+        return;
+    }
+    // 序列化只涉及字符串与数字，不会失败
+    let line = serde_json::to_string(event).expect("事件序列化失败");
+    let mut stdout = io::stdout().lock();
+    // 逐行刷新，读取方不必等到进程结束
+    let _ = writeln!(stdout, "{}", line).and_then(|_| stdout.flush());
+}
+
+/// 报告一条诊断，`source` 为诊断位置所在的源文件内容
+pub fn diagnostic(file: &Path, source: &str, diag: &RplcDiagnostic) {
+    let position = diag
+        .span
+        .and_then(|(offset, _)| line_column(source, offset));
+    emit(&Event::Diagnostic {
+        file,
+        record: DiagnosticRecord::new(diag),
+        span: diag.span,
+        line: position.map(|(line, _)| line),
+        column: position.map(|(_, column)| column),
+    });
+}
+
+pub fn file_written(record: &OutputRecord) {
+    emit(&Event::FileWritten(record));
+}
+
+pub fn summary(success: bool, packets: usize, warnings: usize, errors: usize, elapsed: Duration) {
+    emit(&Event::Summary {
+        success,
+        packets,
+        warnings,
+        errors,
+        elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+    });
+}
+
+/// 字节偏移所在的行号与列号，偏移超出文本或不在字符边界上时返回 None
+fn line_column(source: &str, offset: usize) -> Option<(usize, usize)> {
+    let before = source.get(..offset)?;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Some((
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    ))
+}
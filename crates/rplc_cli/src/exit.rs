@@ -0,0 +1,68 @@
+use std::{fmt::Display, panic, process};
+
+use tracing::error;
+
+/// 非零的进程退出码，供构建脚本区分“协议文件有误”与“rplc 崩溃”
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// 协议文件校验失败（含 JSON 解析失败、CI 版本检查与警告预算失败）
+    Validation = 1,
+    /// 命令行用法错误或文件读写失败
+    Usage = 2,
+    /// rplc 内部错误，例如 panic 或非确定性输出
+    Internal = 3,
+    /// `--check` 只发现了警告，没有错误
+    Warnings = 4,
+}
+
+/// 附加在 `--help` 末尾的退出码说明
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  success
+  1  the protocol definition failed validation
+  2  usage error or file I/O failure
+  3  internal error in rplc
+  4  --check or `rplc check` found warnings but no errors (1 with --deny-warnings)";
+
+impl ExitStatus {
+    /// 只校验时的结果：有错误、或 `--deny-warnings` 下有警告为 `Validation`，只有警告为 `Warnings`，
+    /// 没有问题时为 `None`；`rplc --check` 与 `rplc check` 共用
+    pub fn for_check(has_errors: bool, warnings: usize, deny_warnings: bool) -> Option<ExitStatus> {
+        if has_errors || deny_warnings && warnings > 0 {
+            Some(ExitStatus::Validation)
+        } else if warnings > 0 {
+            Some(ExitStatus::Warnings)
+        } else {
+            None
+        }
+    }
+
+    pub fn exit(self) -> ! {
+        crate::atomic::release_held_lock();
+        process::exit(self as i32)
+    }
+}
+
+/// 在 miette 的 panic hook 之后以内部错误退出，而不是默认的 101
+pub fn install_panic_hook() {
+    miette::set_panic_hook();
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        hook(info);
+        ExitStatus::Internal.exit();
+    }));
+}
+
+pub trait OrExit<T> {
+    /// 出错时记录错误信息并以指定的退出码退出
+    fn or_exit(self, status: ExitStatus, context: impl FnOnce() -> String) -> T;
+}
+
+impl<T, E: Display> OrExit<T> for Result<T, E> {
+    fn or_exit(self, status: ExitStatus, context: impl FnOnce() -> String) -> T {
+        self.unwrap_or_else(|e| {
+            error!("{}: {}", context(), e);
+            status.exit()
+        })
+    }
+}
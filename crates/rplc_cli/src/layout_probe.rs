@@ -0,0 +1,68 @@
+//! `--verify-layout`：编译并运行布局探测程序
+
+use std::{fs, path::Path, process::Command};
+
+use rplc_core::{GenerateOptions, LayoutMismatch, check_layout_probe, generate_layout_probe};
+use tracing::{debug, error};
+
+use crate::exit::{ExitStatus, OrExit};
+
+/// 用 `compiler` 编译探测程序并运行，返回与布局引擎预测不一致之处
+///
+/// `compiler` 可以带参数，例如 `"clang -m32"`；编译器需要能生成在本机运行的程序，
+/// 参数按 GCC/Clang 的 `<源文件> -o <输出>` 形式传递
+pub fn verify_layout(
+    src_content: &str,
+    options: &GenerateOptions,
+    compiler: &str,
+) -> Vec<LayoutMismatch> {
+    let probe = generate_layout_probe(src_content, options).or_exit(ExitStatus::Validation, || {
+        "布局探测程序生成失败".to_string()
+    });
+
+    let dir = std::env::temp_dir().join(format!("rplc-layout-probe-{}", std::process::id()));
+    fs::create_dir_all(&dir).or_exit(ExitStatus::Usage, || format!("无法创建目录: {:?}", dir));
+    let output = run_probe(&dir, &probe, compiler);
+    // 临时目录删除失败不影响检查结果
+    let _ = fs::remove_dir_all(&dir);
+
+    check_layout_probe(src_content, &output)
+        .or_exit(ExitStatus::Validation, || "布局检查失败".to_string())
+}
+
+fn run_probe(dir: &Path, probe: &str, compiler: &str) -> String {
+    let source = dir.join("rplc_layout_probe.c");
+    let binary = dir.join(format!("rplc_layout_probe{}", std::env::consts::EXE_SUFFIX));
+    fs::write(&source, probe).or_exit(ExitStatus::Usage, || format!("无法写入文件: {:?}", source));
+
+    let mut words = compiler.split_whitespace();
+    let Some(program) = words.next() else {
+        error!("--verify-layout 需要编译器命令");
+        ExitStatus::Usage.exit();
+    };
+    let compiled = Command::new(program)
+        .args(words)
+        .arg(&source)
+        .arg("-o")
+        .arg(&binary)
+        .output()
+        .or_exit(ExitStatus::Usage, || format!("无法运行编译器: {}", program));
+    if !compiled.status.success() {
+        error!(
+            "探测程序编译失败:\n{}",
+            String::from_utf8_lossy(&compiled.stderr)
+        );
+        ExitStatus::Usage.exit();
+    }
+
+    let ran = Command::new(&binary)
+        .output()
+        .or_exit(ExitStatus::Usage, || "无法运行探测程序".to_string());
+    if !ran.status.success() {
+        error!("探测程序运行失败: {}", ran.status);
+        ExitStatus::Usage.exit();
+    }
+    let output = String::from_utf8_lossy(&ran.stdout).into_owned();
+    debug!("探测程序输出:\n{}", output);
+    output
+}
@@ -1,49 +1,602 @@
-use std::{fs, path::PathBuf, process};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
-use anyhow::Result;
-use clap::Parser;
-use miette::{Context, IntoDiagnostic, NamedSource, Report};
-use rplc_core::{Severity, generate, generate_multiple, validate, validate_multiple};
+mod atomic;
+mod commands;
+mod events;
+mod exit;
+mod layout_probe;
+mod plugin;
+mod progress;
+mod report;
+mod watch;
+
+use atomic::{OutputLock, write_atomic};
+use clap::{Parser, ValueEnum};
+use commands::Command;
+use events::OutputFormat;
+use exit::{EXIT_CODES_HELP, ExitStatus, OrExit, install_panic_hook};
+use miette::{NamedSource, Report};
+use plugin::Plugin;
+use progress::{RunSummary, Verbosity, init_logging};
+use report::{Artifact, ArtifactKind, BuildReport};
+use rplc_core::{
+    Backend, BraceStyle, Compiler, ConfigOrArray, CppStandard, FileNameCase, GenerateError,
+    GenerateOptions, HeaderTemplate, InputFormat, LatexColumn, OutputLayout, OutputPaths,
+    PROJECT_CONFIG_FILE, ProjectConfig, ProtocolVersion, Severity, Target, Transcript,
+    ValidateOptions, WarningBudget, audit_determinism, check_version_bump, decode_source, diff,
+    generate_compat_shims, generate_protocol_info, generate_python_bridge, generate_registry,
+    generate_umbrella, generate_with_options, include_path, is_plugin_name, plugin_request,
+    rules_markdown, validate_multiple_with_options, validate_with_options,
+};
+use tracing::{error, info, info_span};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    after_help = EXIT_CODES_HELP,
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
 struct Args {
-    #[arg(value_name = "FILE")]
-    input: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(value_name = "FILE", required_unless_present = "emit")]
+    input: Option<PathBuf>,
 
     #[arg(short, long, value_name = "DIR")]
     output: Option<PathBuf>,
 
-    /// Enable multi-packet mode to generate separate files for each packet
+    /// Case of generated file names; collisions are always detected case-insensitively
+    #[arg(long, value_enum, default_value_t = FileNameCaseArg::Preserve)]
+    file_name_case: FileNameCaseArg,
+
+    /// Place outputs under the output directory by template, e.g. "{target}/{namespace_path}/{file}";
+    /// overrides output.layout from the nearest rplc.toml
+    #[arg(long, value_name = "TEMPLATE")]
+    output_layout: Option<String>,
+
+    /// Print each packet's field offsets, sizes and padding instead of generating code; same as `rplc layout FILE`
+    #[arg(long, conflicts_with_all = [
+        "output", "output_layout", "check", "watch", "compat_shims", "audit_determinism", "verify_layout", "report",
+        "manifest", "output_lock",
+    ])]
+    layout: bool,
+
+    /// Force multi-packet mode; arrays and packet sets are detected automatically
     #[arg(long)]
     multi: bool,
+
+    /// Previous release of the definitions, used to report protocol changes
+    #[arg(long, value_name = "FILE")]
+    previous: Option<PathBuf>,
+
+    /// CI mode: require `protocol_version` bumps that match the changes against --previous,
+    /// and fail when a rule exceeds its warning budget from [warnings] in rplc.toml
+    #[arg(long)]
+    ci: bool,
+
+    /// Generate upgrade/downgrade shims for packets that only gained trailing fields since --previous
+    #[arg(long, requires = "previous")]
+    compat_shims: bool,
+
+    /// Number of spaces per indentation level
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    indent_width: usize,
+
+    /// Indent with tabs instead of spaces
+    #[arg(long)]
+    use_tabs: bool,
+
+    /// Placement of opening braces for structs and functions
+    #[arg(long, value_enum, default_value_t = BraceArg::NextLine)]
+    brace_style: BraceArg,
+
+    /// Align field types, names and trailing comments into columns
+    #[arg(long)]
+    align_fields: bool,
+
+    /// Also write an extern "C" header (<NAME>.h) next to each C++ header
+    #[arg(long)]
+    c_header: bool,
+
+    /// Write only the C99 header (<NAME>.h) for C-only firmware, without any C++ headers
+    #[arg(long, conflicts_with_all = ["c_header", "namespace_alias", "protocol_info", "compat_shims"])]
+    c_only: bool,
+
+    /// Also write a Python module (<NAME>.py) with a dataclass and struct-based pack()/unpack()
+    #[arg(long)]
+    python: bool,
+
+    /// Also write a MATLAB class (<NAME>.m) with a Simulink bus definition and a decode() for logged payloads
+    #[arg(long)]
+    matlab: bool,
+
+    /// Also write an ImHex pattern (<NAME>.hexpat) for exploring captured binary logs in a hex editor
+    #[arg(long)]
+    imhex: bool,
+
+    /// Also write LaTeX tables (<NAME>.tex) of the packet's fields for technical reports; needs the booktabs and longtable packages
+    #[arg(long)]
+    latex: bool,
+
+    /// Columns of the LaTeX tables, in order
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "COLUMNS",
+        requires = "latex"
+    )]
+    latex_columns: Option<Vec<LatexColumnArg>>,
+
+    /// Also write a byte/bit layout diagram (<NAME>.svg) with colored fields, offsets and bit lanes for documentation
+    #[arg(long)]
+    svg: bool,
+
+    /// Also write a Rust module (<NAME>.rs) with #[repr(C)] structs implementing rplc_runtime::Packet
+    #[arg(long)]
+    rust: bool,
+
+    /// Also write a Markdown layout table (<NAME>.md) with each field's offset, size and comment
+    #[arg(long)]
+    markdown: bool,
+
+    /// Also write a ground-station bridge (<FILE>_bridge.py) that decodes frames from UDP, TCP or a serial port and republishes them as JSON over WebSocket
+    #[arg(long, requires = "python")]
+    bridge: bool,
+
+    /// Emit `namespace <NAME> = <namespace>;` after each namespaced packet, plus an umbrella header (<FILE>_packets.hpp) in multi-packet mode
+    #[arg(long, value_name = "NAME")]
+    namespace_alias: Option<String>,
+
+    /// Language of localized comments in generated code, e.g. `en` (defaults to `zh`)
+    #[arg(long, value_name = "LANG")]
+    comment_lang: Option<String>,
+
+    /// Also write a ProtocolInfo handshake packet (<FILE>_protocol_info.hpp) using this command ID
+    #[arg(long, value_name = "CMD")]
+    protocol_info: Option<String>,
+
+    /// Compiler profile that selects the packed syntax and compiler-specific bit-field checks
+    #[arg(long, value_enum, value_name = "COMPILER", default_value_t = CompilerArg::Gcc)]
+    compiler: CompilerArg,
+
+    /// C++ standard the generated headers must compile with (14, 17, 20 or 23); older standards get equivalent fallbacks
+    #[arg(long, value_name = "STD", value_parser = parse_cpp_standard, default_value = "20")]
+    cpp_standard: CppStandard,
+
+    /// Render C++ headers with this minijinja template instead of the built-in one; `rplc template` prints the default
+    #[arg(long, value_name = "FILE", conflicts_with = "c_only")]
+    template: Option<PathBuf>,
+
+    /// Do not include <cstdint>; emit guarded fixed-width typedef fallbacks instead (packets declaring `includes` use those headers).
+    /// <array>, <tuple> and the RPL traits headers are still included when the generated code uses them
+    #[arg(long)]
+    freestanding: bool,
+
+    /// Generate twice and fail if the outputs differ
+    #[arg(long)]
+    audit_determinism: bool,
+
+    /// With --audit-determinism, process packets in reverse order on the second run
+    #[arg(long, requires = "audit_determinism")]
+    shuffle: bool,
+
+    /// Compile and run a sizeof/offsetof probe with this C compiler (e.g. `gcc`) and fail if the layout differs from rplc's model
+    #[arg(long, value_name = "CC")]
+    verify_layout: Option<String>,
+
+    /// Warn when more than N bit fields share a single storage unit
+    #[arg(long, value_name = "N", default_value_t = ValidateOptions::default().max_bit_fields_per_unit)]
+    max_bit_fields_per_unit: usize,
+
+    /// Warn when a packet with float fields has more than N bit fields of at most 4 bits
+    #[arg(long, value_name = "N", default_value_t = ValidateOptions::default().max_tiny_bit_fields_with_float)]
+    max_tiny_bit_fields_with_float: usize,
+
+    /// Maximum payload size in bytes allowed by the link
+    #[arg(long, value_name = "BYTES")]
+    max_payload_size: Option<u32>,
+
+    /// Reject inputs larger than this many bytes with a diagnostic instead of parsing them
+    #[arg(long, value_name = "BYTES")]
+    max_input_size: Option<usize>,
+
+    /// Target languages whose type mappings every field must satisfy; any other NAME runs the rplc-backend-NAME plugin found on PATH
+    #[arg(long = "target", value_name = "LANG", value_parser = parse_target, default_value = "cpp")]
+    targets: Vec<TargetSpec>,
+
+    /// Write a machine-readable build report (inputs, options, diagnostics, layouts, outputs)
+    #[arg(long, value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    /// Write the validator rule reference (codes, default severities, help) as Markdown, e.g. rules.md
+    #[arg(long, value_name = "FILE")]
+    emit: Option<PathBuf>,
+
+    /// Write a manifest listing every generated file with its packet, target, size and SHA-256
+    #[arg(long, value_name = "FILE")]
+    manifest: Option<PathBuf>,
+
+    /// Hold a lock file (.rplc.lock) in the output directory while writing, for build directories shared between machines
+    #[arg(long)]
+    output_lock: bool,
+
+    /// With --output-lock, give up after waiting this many seconds for another rplc process to release the lock
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value_t = 60,
+        requires = "output_lock"
+    )]
+    output_lock_timeout: u64,
+
+    /// Standard output format; `jsonl` streams one JSON object per line for each diagnostic, written file and the final summary
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+
+    /// Only validate FILE and write nothing; exits 0 when clean, 4 when there are only warnings and 1 on errors
+    #[arg(long, conflicts_with_all = [
+        "output", "compat_shims", "audit_determinism", "verify_layout", "report", "manifest", "output_lock", "watch",
+    ])]
+    check: bool,
+
+    /// Treat warnings as errors: fail with exit code 1 before anything is generated
+    #[arg(long)]
+    deny_warnings: bool,
+
+    /// Regenerate whenever FILE is saved, until interrupted
+    #[arg(long, conflicts_with_all = [
+        "previous", "ci", "compat_shims", "audit_determinism", "verify_layout", "report", "manifest", "format",
+    ])]
+    watch: bool,
+
+    /// With --watch, wait until FILE has been unchanged for this many milliseconds before regenerating
+    #[arg(long, value_name = "MS", default_value_t = 200, requires = "watch")]
+    debounce: u64,
+
+    /// Only print errors and the final summary
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print per-packet generation timing
+    #[arg(short, long)]
+    verbose: bool,
 }
 
-fn main() -> Result<()> {
-    miette::set_panic_hook();
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum TargetArg {
+    Cpp,
+    C,
+    Rust,
+    Python,
+    #[value(name = "ts")]
+    TypeScript,
+    Matlab,
+}
 
-    let args = Args::parse();
+/// `--target` 的值：内置目标检查字段的类型映射，其余名称由外部插件生成
+#[derive(Clone, Debug)]
+enum TargetSpec {
+    Builtin(TargetArg),
+    Plugin(String),
+}
 
-    let src_content = fs::read_to_string(&args.input)
-        .into_diagnostic()
-        .with_context(|| format!("无法读取文件: {:?}", args.input))
-        .unwrap();
+fn parse_cpp_standard(value: &str) -> Result<CppStandard, String> {
+    let year = value.trim_start_matches("c++").trim_start_matches("C++");
+    year.parse()
+        .ok()
+        .and_then(CppStandard::from_year)
+        .ok_or_else(|| "支持的 C++ 标准为 14、17、20 与 23".to_string())
+}
 
-    // Use appropriate validation based on multi mode
-    let diagnostics = if args.multi {
-        validate_multiple(&src_content)
+fn parse_target(value: &str) -> Result<TargetSpec, String> {
+    if let Ok(target) = TargetArg::from_str(value, true) {
+        return Ok(TargetSpec::Builtin(target));
+    }
+    if is_plugin_name(value) {
+        Ok(TargetSpec::Plugin(value.to_string()))
     } else {
-        validate(&src_content)
+        Err("插件名称只能包含 ASCII 字母、数字、'-' 与 '_'".to_string())
+    }
+}
+
+impl From<TargetArg> for Target {
+    fn from(target: TargetArg) -> Self {
+        match target {
+            TargetArg::Cpp => Target::Cpp,
+            TargetArg::C => Target::C,
+            TargetArg::Rust => Target::Rust,
+            TargetArg::Python => Target::Python,
+            TargetArg::TypeScript => Target::TypeScript,
+            TargetArg::Matlab => Target::Matlab,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CompilerArg {
+    Gcc,
+    Armclang,
+    Iar,
+    Msvc,
+    Armcc,
+    ArmccPragma,
+}
+
+impl From<CompilerArg> for Compiler {
+    fn from(compiler: CompilerArg) -> Self {
+        match compiler {
+            CompilerArg::Gcc => Compiler::Gcc,
+            CompilerArg::Armclang => Compiler::Armclang,
+            CompilerArg::Iar => Compiler::Iar,
+            CompilerArg::Msvc => Compiler::Msvc,
+            CompilerArg::Armcc => Compiler::Armcc,
+            CompilerArg::ArmccPragma => Compiler::ArmccPragma,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FileNameCaseArg {
+    Preserve,
+    Lower,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LatexColumnArg {
+    Name,
+    Type,
+    Offset,
+    Bits,
+    Unit,
+    Range,
+    Description,
+}
+
+impl From<LatexColumnArg> for LatexColumn {
+    fn from(column: LatexColumnArg) -> Self {
+        match column {
+            LatexColumnArg::Name => LatexColumn::Name,
+            LatexColumnArg::Type => LatexColumn::Type,
+            LatexColumnArg::Offset => LatexColumn::Offset,
+            LatexColumnArg::Bits => LatexColumn::Bits,
+            LatexColumnArg::Unit => LatexColumn::Unit,
+            LatexColumnArg::Range => LatexColumn::Range,
+            LatexColumnArg::Description => LatexColumn::Description,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum BraceArg {
+    NextLine,
+    SameLine,
+}
+
+impl Args {
+    fn verbosity(&self) -> Verbosity {
+        if self.quiet {
+            Verbosity::Quiet
+        } else if self.verbose {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        }
+    }
+
+    /// 命令行参数，以及 rplc.toml 中的类型列表
+    fn validate_options(&self, project: &ProjectConfig) -> ValidateOptions {
+        ValidateOptions {
+            max_bit_fields_per_unit: self.max_bit_fields_per_unit,
+            max_tiny_bit_fields_with_float: self.max_tiny_bit_fields_with_float,
+            max_payload_size: self.max_payload_size,
+            targets: self.validation_targets(),
+            compiler: self.compiler.into(),
+            compiler_profiles: project.compiler_profiles.clone(),
+            cpp_standard: self.cpp_standard,
+            max_input_size: self.max_input_size,
+            allowed_types: project.allowed_types.clone(),
+        }
+    }
+
+    /// `--target` 中的内置目标，加上启用的后端对应的目标，使无法表示的定义在写出任何文件之前报告
+    fn validation_targets(&self) -> Vec<Target> {
+        let mut targets: Vec<Target> = self
+            .targets
+            .iter()
+            .filter_map(|target| match target {
+                TargetSpec::Builtin(target) => Some(Target::from(*target)),
+                TargetSpec::Plugin(_) => None,
+            })
+            .collect();
+        let enabled = [
+            (self.c_header || self.c_only, Target::C),
+            (self.python || self.bridge, Target::Python),
+            (self.matlab, Target::Matlab),
+            (self.rust, Target::Rust),
+        ];
+        for (enabled, target) in enabled {
+            if enabled && !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        targets
+    }
+
+    /// `--target` 中的插件，在生成任何文件之前确认它们都能找到
+    fn plugins(&self) -> Result<Vec<Plugin>, String> {
+        let mut plugins: Vec<Plugin> = Vec::new();
+        for target in &self.targets {
+            if let TargetSpec::Plugin(name) = target
+                && plugins.iter().all(|plugin| plugin.name != *name)
+            {
+                plugins.push(Plugin::discover(name)?);
+            }
+        }
+        Ok(plugins)
+    }
+
+    fn generate_options(&self) -> Result<GenerateOptions, String> {
+        Ok(GenerateOptions {
+            indent_width: self.indent_width,
+            use_tabs: self.use_tabs,
+            brace_style: match self.brace_style {
+                BraceArg::NextLine => BraceStyle::NextLine,
+                BraceArg::SameLine => BraceStyle::SameLine,
+            },
+            align_fields: self.align_fields,
+            compiler: self.compiler.into(),
+            cpp_standard: self.cpp_standard,
+            freestanding: self.freestanding,
+            namespace_alias: self.namespace_alias.clone(),
+            comment_language: self.comment_lang.clone(),
+            latex_columns: self
+                .latex_columns
+                .as_ref()
+                .map(|columns| columns.iter().copied().map(LatexColumn::from).collect()),
+            header_template: self.header_template()?,
+            ..GenerateOptions::default()
+        })
+    }
+
+    /// `--template` 指定的头文件模板；语法错误按模板文件中的位置报告
+    fn header_template(&self) -> Result<Option<HeaderTemplate>, String> {
+        let Some(path) = &self.template else {
+            return Ok(None);
+        };
+        let source =
+            fs::read_to_string(path).map_err(|e| format!("无法读取模板 {:?}: {}", path, e))?;
+        HeaderTemplate::new(path.to_string_lossy(), source)
+            .map(Some)
+            .map_err(|e| {
+                println!("{:?}", Report::new(e));
+                format!("模板 {:?} 有语法错误", path)
+            })
+    }
+
+    /// 布局模板取自 `--output-layout`，未指定时取自项目配置
+    fn output_paths(&self, input: &Path, project: &ProjectConfig) -> Result<OutputPaths, String> {
+        let case = match self.file_name_case {
+            FileNameCaseArg::Preserve => FileNameCase::Preserve,
+            FileNameCaseArg::Lower => FileNameCase::Lower,
+        };
+        let layout = match &self.output_layout {
+            Some(template) => Some(
+                OutputLayout::parse(template)
+                    .map_err(|e| format!("--output-layout 无效：{}", e))?,
+            ),
+            None => project.output_layout.clone(),
+        };
+        Ok(OutputPaths::new(input, self.output.as_deref(), case).with_layout(layout))
+    }
+
+    /// 未启用 `--output-lock` 时为 `None`；锁文件放在输出目录，未指定时放在输入文件所在目录
+    fn lock_output(&self, input: &Path) -> std::io::Result<Option<OutputLock>> {
+        if !self.output_lock {
+            return Ok(None);
+        }
+        let dir = match (&self.output, input.parent()) {
+            (Some(output), _) => output.as_path(),
+            (None, Some(parent)) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        OutputLock::acquire(dir, Duration::from_secs(self.output_lock_timeout)).map(Some)
+    }
+}
+
+fn main() {
+    install_panic_hook();
+
+    let mut args = Args::parse();
+    init_logging(args.verbosity());
+    events::init(args.format);
+    if let Some(command) = args.command.take() {
+        command.run();
+        return;
+    }
+    if let Some(path) = &args.emit {
+        write_atomic(path, rules_markdown()).or_exit(ExitStatus::Usage, || {
+            format!("无法写入规则文档: {:?}", path)
+        });
+        info!("校验规则: {:?}", path);
+    }
+    // 没有子命令时，除了只输出规则文档，clap 保证 FILE 存在
+    let Some(input) = args.input.clone() else {
+        return;
     };
+    if args.layout {
+        commands::print_layout(&input, false);
+        return;
+    }
+    if args.watch {
+        watch::watch(&args, &input);
+    }
+    let mut summary = RunSummary::new(args.verbosity(), args.check);
+    let project =
+        project_config(&input).or_exit(ExitStatus::Usage, || "无法加载项目配置".to_string());
+
+    let _span = info_span!("run", input = %input.display()).entered();
+    let (source, transcript) = read_definition(&input);
+    // 后续的校验与生成都使用转写后的 JSON，诊断位置再换算回源文件
+    let src_content = transcript.json().to_string();
+
+    // 未显式指定 --multi 时按顶层结构自动选择单包或多包模式
+    let multi = args.multi || ConfigOrArray::is_multi_input(&src_content);
+    if multi && !args.multi {
+        info!("检测到多包定义，使用多包模式");
+    }
+
+    // Use appropriate validation based on multi mode
+    let validate_options = args.validate_options(&project);
+    let options = args
+        .generate_options()
+        .or_exit(ExitStatus::Usage, || "无法加载头文件模板".to_string());
+
+    let mut build_report = BuildReport::new(multi, options.clone(), validate_options.clone());
+    build_report.add_input(&input, &source);
+    if let Some(template) = &options.header_template {
+        build_report.add_input(Path::new(template.name()), template.source());
+    }
+    build_report.add_packets(&src_content);
+
+    let diagnostics = transcript.map_diagnostics(if multi {
+        validate_multiple_with_options(&src_content, &validate_options)
+    } else {
+        validate_with_options(&src_content, &validate_options)
+    });
+
+    // CI 模式下按 rplc.toml 的 [warnings] 限制各条规则的警告数量
+    let budget_usage = args.ci.then(|| project.warning_budget_usage(&diagnostics));
 
     let mut has_errors = false;
+    let warnings = diagnostics
+        .iter()
+        .filter(|diag| diag.severity == Severity::Warning)
+        .count();
 
     if !diagnostics.is_empty() {
-        let source_code = NamedSource::new(args.input.to_string_lossy(), src_content.clone());
-        println!("检测到 {} 个问题:", diagnostics.len());
+        let source_code = NamedSource::new(input.to_string_lossy(), source.clone());
+        info!("检测到 {} 个问题:", diagnostics.len());
         for diag in diagnostics {
+            summary.record_diagnostic(diag.severity);
+            if events::enabled() {
+                events::diagnostic(&input, &source, &diag);
+                has_errors |= diag.severity == Severity::Error;
+                continue;
+            }
             if diag.severity == Severity::Error {
                 has_errors = true;
+            } else if summary.is_quiet() {
+                continue;
             }
 
             let report = Report::new(diag).with_source_code(source_code.clone());
@@ -52,78 +605,667 @@ fn main() -> Result<()> {
         }
     }
 
+    if args.deny_warnings && warnings > 0 {
+        error!("存在 {} 个警告，--deny-warnings 将其视为错误", warnings);
+        has_errors = true;
+    }
+
     if has_errors {
-        eprintln!("\n 生成终止");
-        process::exit(1);
+        error!(
+            "{}",
+            if args.check {
+                "校验未通过"
+            } else {
+                "生成终止"
+            }
+        );
+        summary.finish(false);
+        exit_with_report(
+            &build_report,
+            args.report.as_deref(),
+            ExitStatus::Validation,
+        );
     }
 
-    println!("\n正在生成代码...");
+    if let Some(usage) = budget_usage {
+        check_warning_budgets(&usage, &build_report, args.report.as_deref());
+    }
 
-    if args.multi {
-        // Handle multi-packet generation
-        let results = generate_multiple(&src_content)
-            .map_err(|e| anyhow::anyhow!("多包代码生成失败: {}", e))
-            .unwrap();
+    let previous_content = args.previous.as_ref().map(|previous| {
+        check_previous(
+            previous,
+            &src_content,
+            args.ci,
+            &mut build_report,
+            args.report.as_deref(),
+        )
+    });
 
-        for (packet_name, cpp_output) in results {
-            let output_path =
-                determine_output_path_for_packet(&args.input, &packet_name, args.output.as_ref());
+    if args.check {
+        summary.finish(true);
+        if let Some(status) = ExitStatus::for_check(false, warnings, args.deny_warnings) {
+            info!("校验通过，但有 {} 个警告: {:?}", warnings, input);
+            status.exit();
+        }
+        info!("校验通过: {:?}", input);
+        return;
+    }
+
+    let plugins = args
+        .plugins()
+        .or_exit(ExitStatus::Usage, || "无法找到后端插件".to_string());
+
+    info!("正在生成代码...");
+    let mut output_paths = args
+        .output_paths(&input, &project)
+        .or_exit(ExitStatus::Usage, || "无法确定输出路径".to_string());
+    let _output_lock = args
+        .lock_output(&input)
+        .or_exit(ExitStatus::Usage, || "无法获取输出锁".to_string());
 
-            if let Some(parent) = output_path.parent() {
-                fs::create_dir_all(parent)
-                    .into_diagnostic()
-                    .with_context(|| format!("无法创建目录: {:?}", parent))
-                    .unwrap();
+    if args.audit_determinism {
+        let mismatches = audit_determinism(&src_content, &options, args.shuffle)
+            .or_exit(ExitStatus::Validation, || "确定性检查失败".to_string());
+        if !mismatches.is_empty() {
+            error!("检测到 {} 处非确定性输出:", mismatches.len());
+            for mismatch in mismatches {
+                error!("{}", mismatch);
             }
-            fs::write(&output_path, cpp_output)
-                .into_diagnostic()
-                .with_context(|| format!("无法写入文件: {:?}", output_path))
-                .unwrap();
-            println!("生成成功: {:?}", output_path);
+            exit_with_report(&build_report, args.report.as_deref(), ExitStatus::Internal);
+        }
+        info!("确定性检查通过");
+    }
+
+    if let Some(compiler) = &args.verify_layout {
+        let mismatches = layout_probe::verify_layout(&src_content, &options, compiler);
+        if !mismatches.is_empty() {
+            error!("编译器的实际布局有 {} 处与预测不一致:", mismatches.len());
+            for mismatch in mismatches {
+                error!("{}", mismatch);
+            }
+            exit_with_report(&build_report, args.report.as_deref(), ExitStatus::Internal);
+        }
+        info!("布局检查通过: {}", compiler);
+    }
+
+    if multi {
+        // Handle multi-packet generation, one packet at a time to report progress
+        let packets = serde_json::from_str::<ConfigOrArray>(&src_content)
+            .or_exit(ExitStatus::Validation, || "多包代码生成失败".to_string())
+            .into_packets();
+        let total = packets.len();
+        // 先分配各包 C++ 头文件的路径，注册表与汇总头文件按实际位置包含它们
+        let cpp_paths: Vec<PathBuf> = packets
+            .iter()
+            .map(|config| {
+                output_paths.for_packet(
+                    &config.packet_name,
+                    Backend::Cpp,
+                    config.namespace.as_deref(),
+                )
+            })
+            .collect();
+        let python_paths: Vec<PathBuf> = if args.python {
+            packets
+                .iter()
+                .map(|config| {
+                    output_paths.for_packet(
+                        &config.packet_name,
+                        Backend::Python,
+                        config.namespace.as_deref(),
+                    )
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let registry_path = output_paths.registry();
+        let umbrella_path = output_paths.umbrella();
+        let including = |file: &Path| GenerateOptions {
+            packet_includes: packets
+                .iter()
+                .zip(&cpp_paths)
+                .map(|(config, path)| (config.packet_name.clone(), include_path(file, path)))
+                .collect(),
+            ..options.clone()
+        };
+        // 别名冲突时在写入任何文件之前失败
+        let umbrella = generate_umbrella(&src_content, &including(&umbrella_path))
+            .or_exit(ExitStatus::Validation, || "汇总头文件生成失败".to_string());
+        // 注册表是 C++ 的类型列表，只输出 C 头文件时不生成
+        let registry = if args.c_only {
+            None
+        } else {
+            generate_registry(&src_content, &including(&registry_path))
+                .or_exit(ExitStatus::Validation, || "注册表生成失败".to_string())
+        };
+
+        let bridge = args.bridge.then(|| {
+            let path = output_paths.bridge();
+            let bridge_options = GenerateOptions {
+                packet_includes: packets
+                    .iter()
+                    .zip(&python_paths)
+                    .map(|(config, module)| {
+                        (config.packet_name.clone(), include_path(&path, module))
+                    })
+                    .collect(),
+                ..options.clone()
+            };
+            let output = generate_python_bridge(&src_content, &bridge_options)
+                .or_exit(ExitStatus::Validation, || "遥测桥生成失败".to_string());
+            (path, output)
+        });
+
+        for ((index, config), output_path) in packets.iter().enumerate().zip(&cpp_paths) {
+            let _span = info_span!("packet", name = %config.packet_name).entered();
+            let start = Instant::now();
+            let config_json = serde_json::to_string(&config)
+                .or_exit(ExitStatus::Internal, || "多包代码生成失败".to_string());
+            let namespace = config.namespace.as_deref();
+            if let Some(python_path) = python_paths.get(index) {
+                write_backend(
+                    Backend::Python,
+                    &config_json,
+                    &options,
+                    python_path,
+                    Artifact::packet(&config.packet_name, Backend::Python),
+                    &mut build_report,
+                );
+            }
+            for (enabled, backend) in [
+                (args.matlab, Backend::Matlab),
+                (args.rust, Backend::Rust),
+                (args.markdown, Backend::Markdown),
+                (args.imhex, Backend::ImHex),
+                (args.latex, Backend::Latex),
+                (args.svg, Backend::Svg),
+            ] {
+                if enabled {
+                    write_backend(
+                        backend,
+                        &config_json,
+                        &options,
+                        &output_paths.for_packet(&config.packet_name, backend, namespace),
+                        Artifact::packet(&config.packet_name, backend),
+                        &mut build_report,
+                    );
+                }
+            }
+            let c_path = output_paths.for_packet(&config.packet_name, Backend::C, namespace);
+            let c_artifact = Artifact::packet(&config.packet_name, Backend::C);
+            if args.c_only {
+                write_backend(
+                    Backend::C,
+                    &config_json,
+                    &options,
+                    &c_path,
+                    c_artifact,
+                    &mut build_report,
+                );
+                summary.packet_generated(index + 1, total, &c_path, start.elapsed());
+                continue;
+            }
+            // 引用其他包的字段按头文件的实际位置包含被引用的包
+            let cpp_output =
+                generate_cpp(&config_json, &including(output_path), "多包代码生成失败");
+            let elapsed = start.elapsed();
+
+            let artifact = Artifact::packet(&config.packet_name, Backend::Cpp);
+            write_output(output_path, &cpp_output, artifact, &mut build_report);
+            if args.c_header {
+                write_backend(
+                    Backend::C,
+                    &config_json,
+                    &options,
+                    &c_path,
+                    c_artifact,
+                    &mut build_report,
+                );
+            }
+            summary.packet_generated(index + 1, total, output_path, elapsed);
+        }
+
+        if let Some(registry_output) = registry {
+            let artifact = Artifact::shared(ArtifactKind::Registry);
+            write_output(
+                &registry_path,
+                &registry_output,
+                artifact,
+                &mut build_report,
+            );
+            info!("生成成功: {:?}", registry_path);
+        }
+        if let Some(umbrella_output) = umbrella {
+            let artifact = Artifact::shared(ArtifactKind::Umbrella);
+            write_output(
+                &umbrella_path,
+                &umbrella_output,
+                artifact,
+                &mut build_report,
+            );
+            info!("生成成功: {:?}", umbrella_path);
+        }
+        if let Some((bridge_path, bridge_output)) = bridge {
+            write_output(
+                &bridge_path,
+                &bridge_output,
+                Artifact::bridge(),
+                &mut build_report,
+            );
+            info!("生成成功: {:?}", bridge_path);
         }
     } else {
         // Handle single packet generation (existing behavior)
-        let cpp_output = generate(&src_content)
-            .map_err(|e| anyhow::anyhow!("代码生成失败: {}", e))
-            .unwrap();
+        let start = Instant::now();
+        let packet = serde_json::from_str::<ConfigOrArray>(&src_content)
+            .ok()
+            .and_then(|packets| packets.into_packets().pop());
+        let packet_name = packet.as_ref().map(|config| config.packet_name.clone());
+        let namespace = packet
+            .as_ref()
+            .and_then(|config| config.namespace.as_deref());
+        let c_path = output_paths.for_input(Backend::C, namespace);
+        let c_artifact = Artifact {
+            packet: packet_name.as_deref(),
+            backend: Backend::C,
+            kind: ArtifactKind::Packet,
+        };
+        if args.python {
+            let artifact = Artifact {
+                backend: Backend::Python,
+                ..c_artifact
+            };
+            let python_path = output_paths.for_input(Backend::Python, namespace);
+            write_backend(
+                Backend::Python,
+                &src_content,
+                &options,
+                &python_path,
+                artifact,
+                &mut build_report,
+            );
+            if args.bridge {
+                let bridge_path = output_paths.bridge();
+                let bridge_options = GenerateOptions {
+                    packet_includes: packet_name
+                        .iter()
+                        .map(|name| (name.clone(), include_path(&bridge_path, &python_path)))
+                        .collect(),
+                    ..options.clone()
+                };
+                let bridge_output = generate_python_bridge(&src_content, &bridge_options)
+                    .or_exit(ExitStatus::Validation, || "遥测桥生成失败".to_string());
+                write_output(
+                    &bridge_path,
+                    &bridge_output,
+                    Artifact::bridge(),
+                    &mut build_report,
+                );
+                info!("生成成功: {:?}", bridge_path);
+            }
+        }
+        if args.matlab {
+            let artifact = Artifact {
+                backend: Backend::Matlab,
+                ..c_artifact
+            };
+            // 文件名必须与类名相同，因此以包名而不是输入文件名命名
+            let matlab_path = match &packet_name {
+                Some(name) => output_paths.for_packet(name, Backend::Matlab, namespace),
+                None => output_paths.for_input(Backend::Matlab, namespace),
+            };
+            write_backend(
+                Backend::Matlab,
+                &src_content,
+                &options,
+                &matlab_path,
+                artifact,
+                &mut build_report,
+            );
+        }
+        for (enabled, backend) in [
+            (args.rust, Backend::Rust),
+            (args.markdown, Backend::Markdown),
+            (args.imhex, Backend::ImHex),
+            (args.latex, Backend::Latex),
+            (args.svg, Backend::Svg),
+        ] {
+            if enabled {
+                let artifact = Artifact {
+                    backend,
+                    ..c_artifact
+                };
+                write_backend(
+                    backend,
+                    &src_content,
+                    &options,
+                    &output_paths.for_input(backend, namespace),
+                    artifact,
+                    &mut build_report,
+                );
+            }
+        }
+        if args.c_only {
+            write_backend(
+                Backend::C,
+                &src_content,
+                &options,
+                &c_path,
+                c_artifact,
+                &mut build_report,
+            );
+            summary.packet_generated(1, 1, &c_path, start.elapsed());
+        } else {
+            let cpp_output = generate_cpp(&src_content, &options, "代码生成失败");
+            let elapsed = start.elapsed();
+
+            let output_path = output_paths.for_input(Backend::Cpp, namespace);
+            let artifact = Artifact {
+                backend: Backend::Cpp,
+                ..c_artifact
+            };
+            write_output(&output_path, &cpp_output, artifact, &mut build_report);
+            if args.c_header {
+                write_backend(
+                    Backend::C,
+                    &src_content,
+                    &options,
+                    &c_path,
+                    c_artifact,
+                    &mut build_report,
+                );
+            }
+            summary.packet_generated(1, 1, &output_path, elapsed);
+        }
+    }
+
+    if let Some(command_id) = &args.protocol_info {
+        let info_output = generate_protocol_info(&src_content, command_id, &options)
+            .or_exit(ExitStatus::Validation, || "握手包生成失败".to_string());
+        let output_path = output_paths.protocol_info();
+        let artifact = Artifact::shared(ArtifactKind::ProtocolInfo);
+        write_output(&output_path, &info_output, artifact, &mut build_report);
+        info!("生成成功: {:?}", output_path);
+    }
+
+    if args.compat_shims
+        && let Some(previous_content) = &previous_content
+    {
+        let shims = generate_compat_shims(previous_content, &src_content, &options)
+            .or_exit(ExitStatus::Validation, || {
+                "兼容转换代码生成失败".to_string()
+            });
+
+        // 转换代码以 `<包名>.hpp` 包含新的头文件，因此与它放在同一目录中
+        let namespaces: Vec<_> = serde_json::from_str::<ConfigOrArray>(&src_content)
+            .map(ConfigOrArray::into_packets)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|config| (config.packet_name, config.namespace))
+            .collect();
+        for (packet_name, shim_output) in shims {
+            let namespace = namespaces
+                .iter()
+                .find(|(name, _)| *name == packet_name)
+                .and_then(|(_, namespace)| namespace.as_deref());
+            let output_path =
+                output_paths.for_packet(&format!("{}Compat", packet_name), Backend::Cpp, namespace);
+            let artifact = Artifact {
+                kind: ArtifactKind::CompatShim,
+                ..Artifact::packet(&packet_name, Backend::Cpp)
+            };
+            write_output(&output_path, &shim_output, artifact, &mut build_report);
+            info!("生成成功: {:?}", output_path);
+        }
+    }
+
+    for plugin in &plugins {
+        let request = plugin_request(&src_content, &plugin.name, &options)
+            .or_exit(ExitStatus::Validation, || {
+                format!("插件 {} 的请求生成失败", plugin.name)
+            });
+        let files = plugin
+            .run(request)
+            .or_exit(ExitStatus::Validation, || "插件生成失败".to_string());
+        for file in files {
+            let output_path = output_paths.plugin(&file.path).unwrap_or_else(|| {
+                error!(
+                    "插件 {} 返回的 {} 与其他输出文件重名",
+                    plugin.name, file.path
+                );
+                ExitStatus::Validation.exit()
+            });
+            write_file(&output_path, &file.content);
+            events::file_written(build_report.add_plugin_output(
+                &output_path,
+                &file.content,
+                &plugin.name,
+                file.packet.as_deref(),
+            ));
+            info!("生成成功: {:?}", output_path);
+        }
+    }
+
+    build_report.success = true;
+    if let Some(report_path) = &args.report {
+        write_report(&build_report, report_path);
+    }
+    if let Some(manifest_path) = &args.manifest {
+        build_report
+            .write_manifest(manifest_path)
+            .or_exit(ExitStatus::Usage, || {
+                format!("无法写入产物清单: {:?}", manifest_path)
+            });
+        info!("产物清单: {:?}", manifest_path);
+    }
+    summary.finish(true);
+}
+
+/// 输入文件所在目录及其上级目录中最近的 rplc.toml，没有时使用默认配置
+fn project_config(input: &Path) -> Result<ProjectConfig, String> {
+    let Some(path) = ProjectConfig::discover(input) else {
+        return Ok(ProjectConfig::default());
+    };
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("无法读取项目配置 {:?}: {}", path, e))?;
+    let config =
+        ProjectConfig::parse(&content).map_err(|e| format!("项目配置 {:?} 无效：{}", path, e))?;
+    info!("使用项目配置: {:?}", path);
+    Ok(config)
+}
+
+/// 读取协议定义文件并去掉 UTF-8 BOM；UTF-16 等编码问题按校验失败处理
+fn read_source(path: &Path) -> String {
+    let bytes = fs::read(path).or_exit(ExitStatus::Usage, || format!("无法读取文件: {:?}", path));
+    decode_source(bytes).unwrap_or_else(|diags| {
+        for diag in diags {
+            if events::enabled() {
+                events::diagnostic(path, "", &diag);
+            } else {
+                println!("{:?}", Report::new(diag));
+            }
+        }
+        error!("无法解码文件: {:?}", path);
+        ExitStatus::Validation.exit()
+    })
+}
 
-        let output_path = determine_output_path(&args.input, args.output.as_ref());
+/// 读取协议定义文件并按扩展名转写为 JSON，返回源文件内容与转写结果
+fn read_definition(path: &Path) -> (String, Transcript) {
+    let source = read_source(path);
+    let transcript = InputFormat::from_path(path)
+        .transcribe(&source)
+        .unwrap_or_else(|diags| {
+            let source_code = NamedSource::new(path.to_string_lossy(), source.clone());
+            for diag in diags {
+                if events::enabled() {
+                    events::diagnostic(path, &source, &diag);
+                } else {
+                    println!(
+                        "{:?}",
+                        Report::new(diag).with_source_code(source_code.clone())
+                    );
+                }
+            }
+            error!("无法解析文件: {:?}", path);
+            ExitStatus::Validation.exit()
+        });
+    (source, transcript)
+}
 
-        if let Some(parent) = output_path.parent() {
-            fs::create_dir_all(parent)
-                .into_diagnostic()
-                .with_context(|| format!("无法创建目录: {:?}", parent))
-                .unwrap();
+/// 生成 C++ 头文件；头文件模板出错时按模板文件中的位置报告
+fn generate_cpp(json: &str, options: &GenerateOptions, context: &str) -> String {
+    generate_with_options(json, options).unwrap_or_else(|e| {
+        if let GenerateError::Template(e) = e {
+            println!("{:?}", Report::new(e));
+            error!("{}: 头文件模板有误", context);
+            ExitStatus::Usage.exit();
         }
-        fs::write(&output_path, cpp_output)
-            .into_diagnostic()
-            .with_context(|| format!("无法写入文件: {:?}", output_path))
-            .unwrap();
-        println!("生成成功: {:?}", output_path);
+        error!("{}: {}", context, e);
+        ExitStatus::Validation.exit()
+    })
+}
+
+/// 写入生成的文件并记录到构建报告
+fn write_output(
+    output_path: &Path,
+    content: &str,
+    artifact: Artifact,
+    build_report: &mut BuildReport,
+) {
+    write_file(output_path, content);
+    events::file_written(build_report.add_output(output_path, content, artifact));
+}
+
+/// 按需创建所在目录后写入文件
+fn write_file(output_path: &Path, content: &str) {
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)
+            .or_exit(ExitStatus::Usage, || format!("无法创建目录: {:?}", parent));
     }
+    write_atomic(output_path, content).or_exit(ExitStatus::Usage, || {
+        format!("无法写入文件: {:?}", output_path)
+    });
+}
 
-    Ok(())
+/// 用 C++ 以外的后端生成并写入单个包的输出
+fn write_backend(
+    backend: Backend,
+    json: &str,
+    options: &GenerateOptions,
+    output_path: &Path,
+    artifact: Artifact,
+    build_report: &mut BuildReport,
+) {
+    let output = backend
+        .generate(json, options)
+        .or_exit(ExitStatus::Validation, || {
+            format!("{} 输出生成失败", backend)
+        });
+    write_output(output_path, &output, artifact, build_report);
 }
 
-fn determine_output_path(input: &PathBuf, output_dir: Option<&PathBuf>) -> PathBuf {
-    let file_stem = input.file_stem().unwrap_or_default();
-    let new_filename = format!("{}.hpp", file_stem.to_string_lossy());
+fn write_report(build_report: &BuildReport, report_path: &Path) {
+    build_report
+        .write(report_path)
+        .or_exit(ExitStatus::Usage, || {
+            format!("无法写入构建报告: {:?}", report_path)
+        });
+    info!("构建报告: {:?}", report_path);
+}
 
-    match output_dir {
-        Some(dir) => dir.join(new_filename),
-        None => input.with_file_name(new_filename),
+/// 失败时仍然输出构建报告，然后以非零状态退出
+fn exit_with_report(
+    build_report: &BuildReport,
+    report_path: Option<&Path>,
+    status: ExitStatus,
+) -> ! {
+    if let Some(report_path) = report_path {
+        write_report(build_report, report_path);
+    }
+    status.exit();
+}
+
+/// 有警告超出预算时终止；数量已低于预算的规则提示收紧预算，让清理的进度不会倒退
+fn check_warning_budgets(
+    usage: &[WarningBudget],
+    build_report: &BuildReport,
+    report_path: Option<&Path>,
+) {
+    for budget in usage {
+        if budget.exceeded() {
+            error!(
+                "规则 {} 有 {} 个警告，超过了 {} 中允许的 {} 个",
+                budget.code, budget.count, PROJECT_CONFIG_FILE, budget.allowed
+            );
+        } else if budget.count < budget.allowed {
+            info!(
+                "规则 {} 只剩 {} 个警告，可以把 {} 中的预算从 {} 降到 {}",
+                budget.code, budget.count, PROJECT_CONFIG_FILE, budget.allowed, budget.count
+            );
+        }
+    }
+    if usage.iter().any(WarningBudget::exceeded) {
+        exit_with_report(build_report, report_path, ExitStatus::Validation);
     }
 }
 
-fn determine_output_path_for_packet(
-    input: &PathBuf,
-    packet_name: &str,
-    output_dir: Option<&PathBuf>,
-) -> PathBuf {
-    let new_filename = format!("{}.hpp", packet_name);
+/// 与上一版本的定义比较并打印变更；CI 模式下要求版本号提升覆盖变更
+/// 返回上一版本定义的内容
+fn check_previous(
+    previous: &Path,
+    src_content: &str,
+    ci: bool,
+    build_report: &mut BuildReport,
+    report_path: Option<&Path>,
+) -> String {
+    let (previous_source, transcript) = read_definition(previous);
+    build_report.add_input(previous, &previous_source);
+    let previous_content = transcript.json().to_string();
 
-    match output_dir {
-        Some(dir) => dir.join(new_filename),
-        None => input.with_file_name(new_filename),
+    let report = diff(&previous_content, src_content)
+        .or_exit(ExitStatus::Validation, || "协议比较失败".to_string());
+
+    if report.is_empty() {
+        info!("与 {:?} 相比没有协议变更", previous);
+    } else {
+        info!(
+            "与 {:?} 相比检测到 {} 处协议变更:",
+            previous,
+            report.changes.len()
+        );
+        for change in &report.changes {
+            info!("  {}", change);
+        }
     }
+
+    if !ci {
+        return previous_content;
+    }
+
+    let old_version = read_protocol_version(&previous_content);
+    let new_version = read_protocol_version(src_content);
+    match (old_version, new_version) {
+        (Some(old), Some(new)) => {
+            if let Err(e) = check_version_bump(old, new, &report) {
+                error!("{}", e);
+                exit_with_report(build_report, report_path, ExitStatus::Validation);
+            }
+        }
+        _ => {
+            error!("CI 模式下新旧定义都必须声明合法的 protocol_version");
+            exit_with_report(build_report, report_path, ExitStatus::Validation);
+        }
+    }
+
+    previous_content
+}
+
+fn read_protocol_version(content: &str) -> Option<ProtocolVersion> {
+    serde_json::from_str::<ConfigOrArray>(content)
+        .ok()?
+        .protocol_version()?
+        .parse()
+        .ok()
 }
@@ -0,0 +1,69 @@
+//! 查找并调用 `rplc-backend-<名称>` 外部后端插件，协议见 `rplc_core::plugin_request`
+
+use std::{
+    env,
+    io::{self, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+};
+
+use rplc_core::{PLUGIN_PREFIX, PluginFile, parse_plugin_response};
+
+/// 在 PATH 中找到的插件
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Plugin {
+    /// 在 PATH 的各个目录中按顺序查找 `rplc-backend-<名称>`
+    pub fn discover(name: &str) -> Result<Self, String> {
+        let file_name = format!("{}{}{}", PLUGIN_PREFIX, name, env::consts::EXE_SUFFIX);
+        env::var_os("PATH")
+            .iter()
+            .flat_map(env::split_paths)
+            .map(|dir| dir.join(&file_name))
+            .find(|path| path.is_file())
+            .map(|path| Self {
+                name: name.to_string(),
+                path,
+            })
+            .ok_or_else(|| {
+                format!(
+                    "'{}' 不是内置目标，PATH 中也没有找到插件 {}",
+                    name, file_name
+                )
+            })
+    }
+
+    /// 把请求写到插件的标准输入，返回插件要写出的文件；插件的标准错误直接输出到终端
+    pub fn run(&self, request: String) -> Result<Vec<PluginFile>, String> {
+        let mut child = Command::new(&self.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| format!("无法启动插件 {:?}: {}", self.path, e))?;
+        // 写入与读取同时进行，请求较大时双方不会因管道缓冲区写满而互相等待
+        let mut stdin = child.stdin.take().expect("标准输入已设置为管道");
+        let writer = thread::spawn(move || stdin.write_all(request.as_bytes()));
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("插件 {} 运行失败: {}", self.name, e))?;
+        if !output.status.success() {
+            return Err(format!("插件 {} 以 {} 退出", self.name, output.status));
+        }
+        // 插件不读取请求就正常退出时写入会遇到断开的管道，这不算错误
+        match writer.join().expect("写入线程不会 panic") {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                return Err(format!("无法把请求写给插件 {}: {}", self.name, e));
+            }
+            _ => {}
+        }
+        let stdout = String::from_utf8(output.stdout)
+            .map_err(|_| format!("插件 {} 的输出不是 UTF-8 文本", self.name))?;
+        parse_plugin_response(&stdout).map_err(|e| format!("插件 {}: {}", self.name, e))
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use rplc_core::Severity;
+
+use crate::events;
+use tracing::{debug, info};
+use tracing_subscriber::{EnvFilter, fmt::format::FmtSpan};
+
+/// 输出详细程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    /// 仅输出错误与最终汇总
+    Quiet,
+    Normal,
+    /// 额外输出每个包的耗时与各阶段的 span
+    Verbose,
+}
+
+/// 初始化日志输出：`RUST_LOG` 优先，否则按详细程度选择默认级别
+pub fn init_logging(verbosity: Verbosity) {
+    let default_level = match verbosity {
+        Verbosity::Quiet => "warn",
+        Verbosity::Normal => "info",
+        Verbosity::Verbose => "debug",
+    };
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false)
+        .with_writer(io::stderr);
+
+    // 详细模式下保留计时器，span 结束时输出 time.busy / time.idle
+    match verbosity {
+        Verbosity::Verbose => builder.with_span_events(FmtSpan::CLOSE).init(),
+        _ => builder.without_time().init(),
+    }
+}
+
+/// 运行进度与最终汇总
+pub struct RunSummary {
+    verbosity: Verbosity,
+    /// `--check` 只校验，汇总中不报告生成的包数
+    check: bool,
+    start: Instant,
+    packets: usize,
+    warnings: usize,
+    errors: usize,
+}
+
+impl RunSummary {
+    pub fn new(verbosity: Verbosity, check: bool) -> Self {
+        Self {
+            verbosity,
+            check,
+            start: Instant::now(),
+            packets: 0,
+            warnings: 0,
+            errors: 0,
+        }
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        self.verbosity == Verbosity::Quiet
+    }
+
+    pub fn record_diagnostic(&mut self, severity: Severity) {
+        match severity {
+            Severity::Error => self.errors += 1,
+            Severity::Warning => self.warnings += 1,
+        }
+    }
+
+    /// 记录一个生成完成的包并输出进度
+    pub fn packet_generated(
+        &mut self,
+        index: usize,
+        total: usize,
+        output_path: &Path,
+        elapsed: Duration,
+    ) {
+        self.packets += 1;
+        // 单个包时不显示进度前缀
+        let prefix = if total > 1 {
+            format!("[{}/{}] ", index, total)
+        } else {
+            String::new()
+        };
+        info!("{}生成成功: {:?}", prefix, output_path);
+        debug!(
+            elapsed_ms = elapsed.as_secs_f64() * 1000.0,
+            "{:?} 生成耗时", output_path
+        );
+    }
+
+    /// 运行结束时输出汇总；`--format jsonl` 时输出 summary 事件
+    pub fn finish(&self, success: bool) {
+        if events::enabled() {
+            events::summary(
+                success,
+                self.packets,
+                self.warnings,
+                self.errors,
+                self.start.elapsed(),
+            );
+            return;
+        }
+        if !self.is_quiet() {
+            println!();
+        }
+        if self.check {
+            println!(
+                "汇总: 校验完成，{} 个警告，{} 个错误，耗时 {:.2} s",
+                self.warnings,
+                self.errors,
+                self.start.elapsed().as_secs_f64()
+            );
+            return;
+        }
+        println!(
+            "汇总: 生成 {} 个包，{} 个警告，{} 个错误，耗时 {:.2} s",
+            self.packets,
+            self.warnings,
+            self.errors,
+            self.start.elapsed().as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use std::path::Path;
+
+use miette::Diagnostic;
+use rplc_core::{
+    Backend, ConfigOrArray, GenerateOptions, PacketLayout, RplcDiagnostic, Severity,
+    ValidateOptions, canonical_command_id, compute_layout, validate_with_options,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::atomic::write_atomic;
+
+/// 一次运行的构建报告，供 CI 看板与赛前追溯使用
+#[derive(Debug, Serialize)]
+pub struct BuildReport {
+    pub rplc_version: &'static str,
+    pub inputs: Vec<FileRecord>,
+    pub multi: bool,
+    pub generate_options: GenerateOptions,
+    pub validate_options: ValidateOptions,
+    pub packets: Vec<PacketRecord>,
+    pub outputs: Vec<OutputRecord>,
+    pub success: bool,
+}
+
+/// 产物清单：本次运行写出的所有文件，构建系统与打包脚本可以直接读取而不必遍历输出目录
+#[derive(Debug, Serialize)]
+pub struct Manifest<'a> {
+    pub rplc_version: &'static str,
+    pub files: &'a [OutputRecord],
+}
+
+/// 输入或输出文件及其内容的 SHA-256
+#[derive(Debug, Serialize)]
+pub struct FileRecord {
+    pub path: String,
+    pub sha256: String,
+}
+
+/// 写出的文件：所属的包、目标语言、大小与内容的 SHA-256
+#[derive(Debug, Serialize)]
+pub struct OutputRecord {
+    pub path: String,
+    /// 注册表、汇总头文件等不属于单个包的文件为 None
+    pub packet: Option<String>,
+    /// 内置后端的名称，或外部插件的名称
+    pub target: String,
+    pub kind: ArtifactKind,
+    pub size: usize,
+    pub sha256: String,
+}
+
+/// 生成的文件属于哪一类产物
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    /// 单个包的头文件
+    Packet,
+    /// 多包模式的注册表
+    Registry,
+    /// 命名空间别名的汇总头文件
+    Umbrella,
+    /// 握手包
+    ProtocolInfo,
+    /// 与上一版本之间的兼容转换代码
+    CompatShim,
+    /// 地面站遥测桥脚本
+    Bridge,
+    /// 外部插件生成的文件
+    Plugin,
+}
+
+/// 要写出的文件的来源
+#[derive(Debug, Clone, Copy)]
+pub struct Artifact<'a> {
+    pub packet: Option<&'a str>,
+    pub backend: Backend,
+    pub kind: ArtifactKind,
+}
+
+impl<'a> Artifact<'a> {
+    pub fn packet(name: &'a str, backend: Backend) -> Self {
+        Self {
+            packet: Some(name),
+            backend,
+            kind: ArtifactKind::Packet,
+        }
+    }
+
+    /// 不属于单个包的 C++ 头文件
+    pub fn shared(kind: ArtifactKind) -> Self {
+        Self {
+            packet: None,
+            backend: Backend::Cpp,
+            kind,
+        }
+    }
+
+    /// 不属于单个包的遥测桥脚本
+    pub fn bridge() -> Self {
+        Self {
+            packet: None,
+            backend: Backend::Python,
+            kind: ArtifactKind::Bridge,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PacketRecord {
+    pub name: String,
+    pub command_id: String,
+    pub diagnostics: Vec<DiagnosticRecord>,
+    pub layout: Option<PacketLayout>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticRecord {
+    pub severity: Severity,
+    pub code: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+}
+
+impl BuildReport {
+    pub fn new(
+        multi: bool,
+        generate_options: GenerateOptions,
+        validate_options: ValidateOptions,
+    ) -> Self {
+        Self {
+            rplc_version: env!("CARGO_PKG_VERSION"),
+            inputs: Vec::new(),
+            multi,
+            generate_options,
+            validate_options,
+            packets: Vec::new(),
+            outputs: Vec::new(),
+            success: false,
+        }
+    }
+
+    pub fn add_input(&mut self, path: &Path, content: &str) {
+        self.inputs.push(FileRecord::new(path, content));
+    }
+
+    pub fn add_output(&mut self, path: &Path, content: &str, artifact: Artifact) -> &OutputRecord {
+        self.push_output(
+            path,
+            content,
+            artifact.packet,
+            artifact.backend.name(),
+            artifact.kind,
+        )
+    }
+
+    /// 记录外部插件写出的文件
+    pub fn add_plugin_output(
+        &mut self,
+        path: &Path,
+        content: &str,
+        plugin: &str,
+        packet: Option<&str>,
+    ) -> &OutputRecord {
+        self.push_output(path, content, packet, plugin, ArtifactKind::Plugin)
+    }
+
+    fn push_output(
+        &mut self,
+        path: &Path,
+        content: &str,
+        packet: Option<&str>,
+        target: &str,
+        kind: ArtifactKind,
+    ) -> &OutputRecord {
+        let FileRecord { path, sha256 } = FileRecord::new(path, content);
+        self.outputs.push(OutputRecord {
+            path,
+            packet: packet.map(str::to_string),
+            target: target.to_string(),
+            kind,
+            size: content.len(),
+            sha256,
+        });
+        self.outputs.last().expect("刚写入的记录")
+    }
+
+    /// 逐包记录诊断信息与计算得到的布局
+    pub fn add_packets(&mut self, src_content: &str) {
+        let Ok(configs) = serde_json::from_str::<ConfigOrArray>(src_content) else {
+            return;
+        };
+
+        for config in configs.into_packets() {
+            let config_json = serde_json::to_string(&config).unwrap_or_default();
+            let diagnostics = validate_with_options(&config_json, &self.validate_options)
+                .iter()
+                .map(DiagnosticRecord::new)
+                .collect();
+
+            // 记录规范写法，无法解析时保留原文
+            let command_id =
+                canonical_command_id(&config.command_id).unwrap_or(config.command_id.clone());
+            self.packets.push(PacketRecord {
+                layout: compute_layout(&config),
+                name: config.packet_name,
+                command_id,
+                diagnostics,
+            });
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        write_atomic(path, json)
+    }
+
+    pub fn write_manifest(&self, path: &Path) -> std::io::Result<()> {
+        let manifest = Manifest {
+            rplc_version: self.rplc_version,
+            files: &self.outputs,
+        };
+        let json = serde_json::to_string_pretty(&manifest).map_err(std::io::Error::other)?;
+        write_atomic(path, json)
+    }
+}
+
+impl DiagnosticRecord {
+    pub fn new(diag: &RplcDiagnostic) -> Self {
+        Self {
+            severity: diag.severity,
+            code: diag.code.code().map(|code| code.to_string()),
+            message: diag.code.to_string(),
+            help: diag.help.clone(),
+        }
+    }
+}
+
+impl FileRecord {
+    fn new(path: &Path, content: &str) -> Self {
+        let hash = Sha256::digest(content.as_bytes());
+        Self {
+            path: path.to_string_lossy().into_owned(),
+            sha256: hash.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        }
+    }
+}
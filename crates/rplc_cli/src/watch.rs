@@ -0,0 +1,526 @@
+//! `--watch`：输入文件保存后自动重新生成
+//!
+//! - 去抖：文件在安静期内没有再变化才开始生成，编辑器连续保存两次只触发一次
+//! - 取消：生成过程中文件再次变化时，进行中的生成在下一个包之前停止，不再写出任何文件
+//! - 串行写入：同一个输出文件的写入由锁串行化，持锁后确认未被取消才写入，
+//!   因此被取消的旧生成不会覆盖新生成写出的内容，也不会与之交错写入同一个文件
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use miette::{NamedSource, Report};
+use rplc_core::{
+    Backend, ConfigOrArray, GenerateOptions, InputFormat, RplcDiagnostic, Severity, decode_source,
+    generate_protocol_info, generate_registry, generate_umbrella, include_path,
+    validate_multiple_with_options, validate_with_options,
+};
+use tracing::{error, info, warn};
+
+use crate::atomic::write_atomic;
+use crate::{Args, project_config};
+
+/// 检查输入文件是否变化的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 输入文件的修改时间与大小，任一变化即视为一次保存；文件暂时不存在时为 `None`
+#[derive(Debug, PartialEq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+fn stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some(FileStamp {
+        modified: metadata.modified().ok(),
+        len: metadata.len(),
+    })
+}
+
+/// 记录最后一次变化的时刻，安静期过后才允许触发
+struct Debouncer {
+    quiet: Duration,
+    last_change: Option<Instant>,
+}
+
+impl Debouncer {
+    fn new(quiet: Duration) -> Self {
+        Self {
+            quiet,
+            last_change: None,
+        }
+    }
+
+    fn changed(&mut self, now: Instant) {
+        self.last_change = Some(now);
+    }
+
+    /// 有待处理的变化且安静期已过时返回 true，并清除待处理状态
+    fn ready(&mut self, now: Instant) -> bool {
+        match self.last_change {
+            Some(last) if now.duration_since(last) >= self.quiet => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// 监视循环一次轮询后要执行的操作
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Decision {
+    /// 取消进行中的生成
+    cancel: bool,
+    /// 开始一次新的生成
+    start: bool,
+}
+
+/// 去抖与取消的决策，只依据是否变化与当前时刻，由监视循环执行返回的操作
+struct Scheduler {
+    debouncer: Debouncer,
+    /// 启动时立即生成一次，启动后的第一次变化之前有效
+    pending: bool,
+    /// 已开始的生成尚未被新的变化取代
+    in_flight: bool,
+}
+
+impl Scheduler {
+    fn new(quiet: Duration) -> Self {
+        Self {
+            debouncer: Debouncer::new(quiet),
+            pending: true,
+            in_flight: false,
+        }
+    }
+
+    /// `changed` 表示自上次轮询以来输入文件发生了变化
+    fn poll(&mut self, changed: bool, now: Instant) -> Decision {
+        let mut decision = Decision::default();
+        if changed {
+            self.debouncer.changed(now);
+            self.pending = false;
+            // 新的变化使进行中的生成失效
+            decision.cancel = std::mem::take(&mut self.in_flight);
+        }
+        if self.pending || self.debouncer.ready(now) {
+            self.pending = false;
+            self.in_flight = true;
+            decision.start = true;
+        }
+        decision
+    }
+}
+
+/// 一次生成的取消标记，由监视循环设置，生成线程在每个包与每次写入之前检查
+#[derive(Debug, Clone, Default)]
+struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// 每个输出文件一把锁，跨多次生成共享
+#[derive(Default)]
+struct WriteLocks {
+    locks: Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>,
+}
+
+impl WriteLocks {
+    /// 持有该文件的锁写入；已被取消时不写入并返回 false
+    fn write(&self, path: &Path, content: &str, cancel: &CancelToken) -> io::Result<bool> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(path.to_path_buf())
+            .or_default()
+            .clone();
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        if cancel.is_cancelled() {
+            return Ok(false);
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_atomic(path, content)?;
+        Ok(true)
+    }
+}
+
+/// 监视输入文件并在每次保存后重新生成，直到进程被终止
+pub fn watch(args: &Args, input: &Path) -> ! {
+    let locks = WriteLocks::default();
+    let mut scheduler = Scheduler::new(Duration::from_millis(args.debounce));
+    info!("正在监视 {:?}，按 Ctrl+C 退出", input);
+
+    thread::scope(|scope| {
+        let mut last_stamp = stamp(input);
+        let mut in_flight: Option<CancelToken> = None;
+        loop {
+            let current = stamp(input);
+            let changed = current != last_stamp;
+            last_stamp = current;
+            let decision = scheduler.poll(changed, Instant::now());
+            if decision.cancel
+                && let Some(token) = in_flight.take()
+            {
+                token.cancel();
+            }
+            if decision.start {
+                let token = CancelToken::default();
+                in_flight = Some(token.clone());
+                let locks = &locks;
+                scope.spawn(move || run_once(args, input, &token, locks));
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    })
+}
+
+/// 完成一次生成；被取消时已写出的文件保持不变，其余文件留给下一次生成
+fn run_once(args: &Args, input: &Path, cancel: &CancelToken, locks: &WriteLocks) {
+    let start = Instant::now();
+    let outputs = match plan_outputs(args, input, cancel) {
+        Ok(Some(outputs)) => outputs,
+        Ok(None) => {
+            info!("输入已再次变化，取消本次生成");
+            return;
+        }
+        Err(message) => {
+            error!("{}，等待下一次保存", message);
+            return;
+        }
+    };
+    let _output_lock = match args.lock_output(input) {
+        Ok(lock) => lock,
+        Err(e) => {
+            error!("无法获取输出锁: {}", e);
+            return;
+        }
+    };
+    for (path, content) in &outputs {
+        match locks.write(path, content, cancel) {
+            Ok(true) => info!("生成成功: {:?}", path),
+            Ok(false) => {
+                info!("输入已再次变化，取消本次生成");
+                return;
+            }
+            Err(e) => {
+                error!("无法写入文件 {:?}: {}", path, e);
+                return;
+            }
+        }
+    }
+    info!(
+        "重新生成 {} 个文件，耗时 {:.2} s",
+        outputs.len(),
+        start.elapsed().as_secs_f64()
+    );
+}
+
+/// 读取、校验并在内存中生成所有输出；被取消时返回 `Ok(None)`，出错时返回错误信息
+fn plan_outputs(
+    args: &Args,
+    input: &Path,
+    cancel: &CancelToken,
+) -> Result<Option<Vec<(PathBuf, String)>>, String> {
+    let bytes = fs::read(input).map_err(|e| format!("无法读取文件 {:?}: {}", input, e))?;
+    let source = decode_source(bytes).map_err(|diags| {
+        print_diagnostics(input, "", diags);
+        format!("无法解码文件: {:?}", input)
+    })?;
+    let transcript = InputFormat::from_path(input)
+        .transcribe(&source)
+        .map_err(|diags| {
+            print_diagnostics(input, &source, diags);
+            format!("无法解析文件: {:?}", input)
+        })?;
+    let src_content = transcript.json();
+
+    let project = project_config(input)?;
+    let multi = args.multi || ConfigOrArray::is_multi_input(src_content);
+    let validate_options = args.validate_options(&project);
+    let diagnostics = transcript.map_diagnostics(if multi {
+        validate_multiple_with_options(src_content, &validate_options)
+    } else {
+        validate_with_options(src_content, &validate_options)
+    });
+    let has_errors = diagnostics.iter().any(|d| {
+        d.severity == Severity::Error || args.deny_warnings && d.severity == Severity::Warning
+    });
+    print_diagnostics(input, &source, diagnostics);
+    if has_errors {
+        return Err("校验未通过".to_string());
+    }
+
+    let options = args.generate_options()?;
+    let mut output_paths = args.output_paths(input, &project)?;
+    let mut backends = Vec::new();
+    if !args.c_only {
+        backends.push(Backend::Cpp);
+    }
+    if args.c_only || args.c_header {
+        backends.push(Backend::C);
+    }
+    if args.python {
+        backends.push(Backend::Python);
+    }
+    if args.matlab {
+        backends.push(Backend::Matlab);
+    }
+    if args.rust {
+        backends.push(Backend::Rust);
+    }
+    if args.markdown {
+        backends.push(Backend::Markdown);
+    }
+    if args.imhex {
+        backends.push(Backend::ImHex);
+    }
+    if args.latex {
+        backends.push(Backend::Latex);
+    }
+    if args.svg {
+        backends.push(Backend::Svg);
+    }
+
+    let mut outputs = Vec::new();
+    if multi {
+        let packets = serde_json::from_str::<ConfigOrArray>(src_content)
+            .map_err(|e| format!("多包代码生成失败: {}", e))?
+            .into_packets();
+        // 与单次生成相同：先分配 C++ 头文件的路径，注册表与汇总头文件按实际位置包含它们
+        let cpp_paths: Vec<PathBuf> = packets
+            .iter()
+            .map(|config| {
+                output_paths.for_packet(
+                    &config.packet_name,
+                    Backend::Cpp,
+                    config.namespace.as_deref(),
+                )
+            })
+            .collect();
+        let registry_path = output_paths.registry();
+        let umbrella_path = output_paths.umbrella();
+        let including = |file: &Path| GenerateOptions {
+            packet_includes: packets
+                .iter()
+                .zip(&cpp_paths)
+                .map(|(config, path)| (config.packet_name.clone(), include_path(file, path)))
+                .collect(),
+            ..options.clone()
+        };
+
+        for (config, cpp_path) in packets.iter().zip(&cpp_paths) {
+            if cancel.is_cancelled() {
+                return Ok(None);
+            }
+            let config_json =
+                serde_json::to_string(config).map_err(|e| format!("多包代码生成失败: {}", e))?;
+            // 引用其他包的字段按头文件的实际位置包含被引用的包
+            let packet_options = including(cpp_path);
+            for &backend in &backends {
+                let output = backend
+                    .generate(&config_json, &packet_options)
+                    .map_err(|e| format!("{} 输出生成失败: {}", backend, e))?;
+                let path = match backend {
+                    Backend::Cpp => cpp_path.clone(),
+                    _ => output_paths.for_packet(
+                        &config.packet_name,
+                        backend,
+                        config.namespace.as_deref(),
+                    ),
+                };
+                outputs.push((path, output));
+            }
+        }
+        if !args.c_only
+            && let Some(registry) = generate_registry(src_content, &including(&registry_path))
+                .map_err(|e| format!("注册表生成失败: {}", e))?
+        {
+            outputs.push((registry_path, registry));
+        }
+        if let Some(umbrella) = generate_umbrella(src_content, &including(&umbrella_path))
+            .map_err(|e| format!("汇总头文件生成失败: {}", e))?
+        {
+            outputs.push((umbrella_path, umbrella));
+        }
+    } else {
+        let packet = serde_json::from_str::<ConfigOrArray>(src_content)
+            .ok()
+            .and_then(|packets| packets.into_packets().pop());
+        let namespace = packet
+            .as_ref()
+            .and_then(|config| config.namespace.as_deref());
+        for &backend in &backends {
+            let output = backend
+                .generate(src_content, &options)
+                .map_err(|e| format!("{} 输出生成失败: {}", backend, e))?;
+            // MATLAB 的文件名必须与类名相同
+            let path = match (backend, &packet) {
+                (Backend::Matlab, Some(config)) => {
+                    output_paths.for_packet(&config.packet_name, backend, namespace)
+                }
+                _ => output_paths.for_input(backend, namespace),
+            };
+            outputs.push((path, output));
+        }
+    }
+
+    if let Some(command_id) = &args.protocol_info {
+        let info_output = generate_protocol_info(src_content, command_id, &options)
+            .map_err(|e| format!("握手包生成失败: {}", e))?;
+        outputs.push((output_paths.protocol_info(), info_output));
+    }
+    Ok((!cancel.is_cancelled()).then_some(outputs))
+}
+
+fn print_diagnostics(input: &Path, source: &str, diagnostics: Vec<RplcDiagnostic>) {
+    if diagnostics.is_empty() {
+        return;
+    }
+    let source_code = NamedSource::new(input.to_string_lossy(), source.to_string());
+    warn!("检测到 {} 个问题:", diagnostics.len());
+    for diag in diagnostics {
+        println!(
+            "{:?}",
+            Report::new(diag).with_source_code(source_code.clone())
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUIET: Duration = Duration::from_millis(100);
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    /// 启动时的一次生成之后的调度器
+    fn started(t0: Instant) -> Scheduler {
+        let mut scheduler = Scheduler::new(QUIET);
+        assert_eq!(
+            scheduler.poll(false, t0),
+            Decision {
+                cancel: false,
+                start: true
+            }
+        );
+        scheduler
+    }
+
+    #[test]
+    fn test_two_saves_within_debounce_trigger_one_run() {
+        let t0 = Instant::now();
+        let mut scheduler = started(t0);
+        assert_eq!(scheduler.poll(false, t0 + ms(500)), Decision::default());
+
+        // 编辑器连续保存两次，第二次保存重新开始安静期
+        let first = t0 + ms(1000);
+        let mut starts = 0;
+        for (changed, at) in [
+            (true, first),
+            (false, first + ms(50)),
+            (true, first + ms(60)),
+            (false, first + ms(120)),
+            (false, first + ms(160)),
+            (false, first + ms(200)),
+            (false, first + ms(1000)),
+        ] {
+            let decision = scheduler.poll(changed, at);
+            if decision.start {
+                assert_eq!(at, first + ms(160));
+                starts += 1;
+            }
+        }
+        assert_eq!(starts, 1);
+    }
+
+    #[test]
+    fn test_newer_change_cancels_in_flight_run() {
+        let t0 = Instant::now();
+        let mut scheduler = started(t0);
+
+        // 启动时的生成尚在进行，文件再次变化
+        assert_eq!(
+            scheduler.poll(true, t0 + ms(10)),
+            Decision {
+                cancel: true,
+                start: false
+            }
+        );
+        // 安静期内的再次变化没有进行中的生成可取消
+        assert_eq!(scheduler.poll(true, t0 + ms(50)), Decision::default());
+        assert_eq!(
+            scheduler.poll(false, t0 + ms(150)),
+            Decision {
+                cancel: false,
+                start: true
+            }
+        );
+        // 新的生成同样会被之后的变化取消
+        assert_eq!(
+            scheduler.poll(true, t0 + ms(200)),
+            Decision {
+                cancel: true,
+                start: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_change_before_startup_run_is_debounced() {
+        let t0 = Instant::now();
+        let mut scheduler = Scheduler::new(QUIET);
+        assert_eq!(scheduler.poll(true, t0), Decision::default());
+        assert_eq!(scheduler.poll(false, t0 + ms(50)), Decision::default());
+        assert!(scheduler.poll(false, t0 + ms(100)).start);
+    }
+
+    #[test]
+    fn test_cancelled_run_does_not_write() {
+        let dir = std::env::temp_dir().join(format!("rplc_watch_{}", std::process::id()));
+        let path = dir.join("out").join("Packet.hpp");
+        let locks = WriteLocks::default();
+
+        let cancelled = CancelToken::default();
+        cancelled.cancel();
+        assert!(!locks.write(&path, "old", &cancelled).unwrap());
+        assert!(!path.exists());
+
+        // 持锁期间被取消的写入在拿到锁后放弃，不会覆盖新生成写出的内容
+        let token = CancelToken::default();
+        assert!(locks.write(&path, "new", &token).unwrap());
+        let lock = locks.locks.lock().unwrap()[&path].clone();
+        let held = lock.lock().unwrap();
+        let stale = CancelToken::default();
+        thread::scope(|scope| {
+            let writer = scope.spawn(|| locks.write(&path, "stale", &stale).unwrap());
+            thread::sleep(ms(20));
+            stale.cancel();
+            drop(held);
+            assert!(!writer.join().unwrap());
+        });
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
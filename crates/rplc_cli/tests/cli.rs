@@ -0,0 +1,489 @@
+//! 运行 rplc 可执行文件，检查退出码与写出的文件
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CLEAN: &str = r#"{"packet_name":"Gimbal","command_id":"0x0104","fields":[
+    {"name":"yaw","type":"float","comment":"偏航角"}]}"#;
+
+/// 字段缺少注释，只有 `rplc::doc::missing` 警告
+const WARNING: &str = r#"{"packet_name":"Gimbal","command_id":"0x0104","fields":[
+    {"name":"yaw","type":"float"}]}"#;
+
+const ERROR: &str = r#"{"packet_name":"Gimbal","command_id":"not a number","fields":[
+    {"name":"yaw","type":"float","comment":"偏航角"}]}"#;
+
+/// 每个测试独立的临时目录，结束时删除
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("rplc_cli_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    fn write(&self, name: &str, content: &str) -> PathBuf {
+        let path = self.0.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// 目录中的全部文件名，按名称排序
+    fn files(&self, dir: &str) -> Vec<String> {
+        let mut names: Vec<String> = match fs::read_dir(self.0.join(dir)) {
+            Ok(entries) => entries
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        names.sort();
+        names
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn rplc(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rplc_cli"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn exit_code(output: &Output) -> i32 {
+    output.status.code().expect("rplc 被信号终止")
+}
+
+#[test]
+fn test_generate_exit_codes() {
+    let dir = TempDir::new("generate");
+    dir.write("clean.json", CLEAN);
+    dir.write("warning.json", WARNING);
+    dir.write("error.json", ERROR);
+
+    assert_eq!(exit_code(&rplc(&dir.0, &["clean.json", "-o", "clean"])), 0);
+    assert_eq!(dir.files("clean"), ["clean.hpp"]);
+
+    // 生成时警告不影响退出码，--deny-warnings 在写出任何文件之前失败
+    assert_eq!(
+        exit_code(&rplc(&dir.0, &["warning.json", "-o", "warning"])),
+        0
+    );
+    assert_eq!(dir.files("warning"), ["warning.hpp"]);
+    let output = rplc(&dir.0, &["warning.json", "-o", "denied", "--deny-warnings"]);
+    assert_eq!(exit_code(&output), 1);
+    assert!(dir.files("denied").is_empty());
+
+    assert_eq!(exit_code(&rplc(&dir.0, &["error.json", "-o", "error"])), 1);
+    assert!(dir.files("error").is_empty());
+}
+
+#[test]
+fn test_usage_exit_codes() {
+    let dir = TempDir::new("usage");
+    dir.write("clean.json", CLEAN);
+
+    assert_eq!(
+        exit_code(&rplc(&dir.0, &["clean.json", "--no-such-flag"])),
+        2
+    );
+    assert_eq!(exit_code(&rplc(&dir.0, &["missing.json"])), 2);
+
+    // --help 列出全部退出码
+    let output = rplc(&dir.0, &["--help"]);
+    assert_eq!(exit_code(&output), 0);
+    let help = String::from_utf8_lossy(&output.stdout);
+    for line in [
+        "0  success",
+        "1  the protocol definition failed validation",
+        "2  usage error or file I/O failure",
+        "3  internal error in rplc",
+    ] {
+        assert!(help.contains(line), "{}", line);
+    }
+}
+
+#[test]
+fn test_failed_generation_leaves_no_partial_file() {
+    let dir = TempDir::new("partial");
+    // B 含位域，Python 后端无法生成；校验阶段不检查位域，失败发生在写出 A 之后
+    dir.write(
+        "set.json",
+        r#"{"protocol_version":"1.0.0","packets":[
+            {"packet_name":"A","command_id":"0x0101","fields":[
+                {"name":"yaw","type":"float","comment":"Y"}]},
+            {"packet_name":"B","command_id":"0x0102","fields":[
+                {"name":"flags","type":"uint8_t","bit_field":3,"comment":"F"}]}]}"#,
+    );
+
+    let output = rplc(&dir.0, &["set.json", "--python", "-o", "out"]);
+    assert_eq!(exit_code(&output), 1);
+    // 已经完成的包保持完整，失败的包一个文件也不写，也不遗留临时文件
+    assert_eq!(dir.files("out"), ["A.hpp", "A.py"]);
+    let header = fs::read_to_string(dir.0.join("out/A.hpp")).unwrap();
+    assert!(header.trim_end().ends_with("#endif // RPL_A_HPP"));
+}
+
+/// 带 `--output-lock` 生成 clean.json，等待锁至多 `timeout` 秒
+fn rplc_locked(dir: &Path, timeout: &str) -> Output {
+    rplc(
+        dir,
+        &[
+            "clean.json",
+            "-o",
+            "out",
+            "--output-lock",
+            "--output-lock-timeout",
+            timeout,
+        ],
+    )
+}
+
+#[test]
+fn test_held_output_lock_is_refused() {
+    let dir = TempDir::new("lock");
+    dir.write("clean.json", CLEAN);
+    fs::create_dir_all(dir.0.join("out")).unwrap();
+    let holder = "host=build-server\npid=1\n";
+    dir.write("out/.rplc.lock", holder);
+
+    let output = rplc_locked(&dir.0, "0");
+    assert_eq!(exit_code(&output), 2);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("build-server"));
+    // 别人的锁原样保留，输出目录中没有写入任何文件
+    assert_eq!(dir.files("out"), [".rplc.lock"]);
+    assert_eq!(
+        fs::read_to_string(dir.0.join("out/.rplc.lock")).unwrap(),
+        holder
+    );
+
+    // 等到超时才放弃
+    let start = Instant::now();
+    assert_eq!(exit_code(&rplc_locked(&dir.0, "1")), 2);
+    assert!(start.elapsed() >= Duration::from_secs(1));
+
+    // 等待期间锁被释放时继续生成，写完删除自己的锁
+    let root = dir.0.clone();
+    let waiting = thread::spawn(move || rplc_locked(&root, "30"));
+    thread::sleep(Duration::from_millis(300));
+    fs::remove_file(dir.0.join("out/.rplc.lock")).unwrap();
+    assert_eq!(exit_code(&waiting.join().unwrap()), 0);
+    assert_eq!(dir.files("out"), ["clean.hpp"]);
+}
+
+#[test]
+fn test_check_exit_codes() {
+    let dir = TempDir::new("check");
+    dir.write("clean.json", CLEAN);
+    dir.write("warning.json", WARNING);
+    dir.write("error.json", ERROR);
+    dir.write("malformed.json", r#"{"packet_name": 1,"#);
+
+    // --check 与 rplc check 使用相同的退出码，JSON 语法错误同样是校验错误
+    for (file, code, denied) in [
+        ("clean.json", 0, 0),
+        ("warning.json", 4, 1),
+        ("error.json", 1, 1),
+        ("malformed.json", 1, 1),
+    ] {
+        assert_eq!(
+            exit_code(&rplc(&dir.0, &[file, "--check"])),
+            code,
+            "{}",
+            file
+        );
+        assert_eq!(exit_code(&rplc(&dir.0, &["check", file])), code, "{}", file);
+        let output = rplc(&dir.0, &[file, "--check", "--deny-warnings"]);
+        assert_eq!(exit_code(&output), denied, "{}", file);
+        let output = rplc(&dir.0, &["check", file, "--deny-warnings"]);
+        assert_eq!(exit_code(&output), denied, "{}", file);
+    }
+    // 只校验，不写出任何文件
+    assert_eq!(
+        dir.files(""),
+        ["clean.json", "error.json", "malformed.json", "warning.json"]
+    );
+}
+
+#[test]
+fn test_check_summary() {
+    let dir = TempDir::new("check_summary");
+    dir.write("warning.json", WARNING);
+
+    let output = rplc(&dir.0, &["warning.json", "--check"]);
+    assert_eq!(exit_code(&output), 4);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("rplc::doc::missing"), "{}", stdout);
+    assert!(
+        stdout.contains("汇总: 校验完成，1 个警告，0 个错误"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("生成 0 个包"), "{}", stdout);
+
+    let output = rplc(&dir.0, &["warning.json", "--check", "--deny-warnings"]);
+    assert_eq!(exit_code(&output), 1);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("汇总: 校验完成，1 个警告，0 个错误"),
+        "{}",
+        stdout
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--deny-warnings"));
+}
+
+/// 读取 JSON 文件，并确认对象包含 `keys` 中的全部键
+fn read_json_with_keys(path: &Path, keys: &[&str]) -> serde_json::Value {
+    let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).unwrap())
+        .unwrap_or_else(|e| panic!("{:?} 不是有效的 JSON: {}", path, e));
+    assert_has_keys(&value, keys);
+    value
+}
+
+fn assert_has_keys(value: &serde_json::Value, keys: &[&str]) {
+    for key in keys {
+        assert!(value.get(key).is_some(), "缺少键 '{}': {}", key, value);
+    }
+}
+
+#[test]
+fn test_build_report() {
+    let dir = TempDir::new("report");
+    dir.write("warning.json", WARNING);
+    dir.write("error.json", ERROR);
+
+    let output = rplc(
+        &dir.0,
+        &["warning.json", "-o", "out", "--report", "report.json"],
+    );
+    assert_eq!(exit_code(&output), 0);
+    let report = read_json_with_keys(
+        &dir.0.join("report.json"),
+        &[
+            "rplc_version",
+            "inputs",
+            "multi",
+            "generate_options",
+            "validate_options",
+            "packets",
+            "outputs",
+            "success",
+        ],
+    );
+    assert_eq!(report["success"], true);
+    assert_eq!(report["multi"], false);
+    assert_has_keys(&report["inputs"][0], &["path", "sha256"]);
+    assert_eq!(report["inputs"][0]["path"], "warning.json");
+
+    let packet = &report["packets"][0];
+    assert_has_keys(packet, &["name", "command_id", "diagnostics", "layout"]);
+    assert_eq!(packet["name"], "Gimbal");
+    assert_eq!(packet["command_id"], "0x0104");
+    let diagnostic = &packet["diagnostics"][0];
+    assert_has_keys(diagnostic, &["severity", "code", "message"]);
+    assert_eq!(diagnostic["severity"], "Warning");
+    assert_eq!(diagnostic["code"], "rplc::doc::missing");
+    assert_eq!(packet["layout"]["size"], 4);
+
+    let file = &report["outputs"][0];
+    assert_has_keys(
+        file,
+        &["path", "packet", "target", "kind", "size", "sha256"],
+    );
+    assert_eq!(file["path"], "out/warning.hpp");
+    assert_eq!(file["target"], "cpp");
+
+    // 校验失败时同样写出报告，没有产物
+    let output = rplc(
+        &dir.0,
+        &["error.json", "-o", "out", "--report", "failed.json"],
+    );
+    assert_eq!(exit_code(&output), 1);
+    let report = read_json_with_keys(&dir.0.join("failed.json"), &["packets", "success"]);
+    assert_eq!(report["success"], false);
+    assert_eq!(report["outputs"], serde_json::json!([]));
+    assert_eq!(report["packets"][0]["diagnostics"][0]["severity"], "Error");
+}
+
+#[test]
+fn test_manifest() {
+    use sha2::{Digest, Sha256};
+
+    let dir = TempDir::new("manifest");
+    dir.write("clean.json", CLEAN);
+
+    let output = rplc(
+        &dir.0,
+        &[
+            "clean.json",
+            "--python",
+            "-o",
+            "out",
+            "--manifest",
+            "manifest.json",
+        ],
+    );
+    assert_eq!(exit_code(&output), 0);
+    let manifest = read_json_with_keys(&dir.0.join("manifest.json"), &["rplc_version", "files"]);
+    let files = manifest["files"].as_array().unwrap();
+    let mut targets: Vec<&str> = files
+        .iter()
+        .map(|f| f["target"].as_str().unwrap())
+        .collect();
+    targets.sort();
+    assert_eq!(targets, ["cpp", "python"]);
+
+    // 清单中的大小与哈希与写出的文件一致
+    for file in files {
+        assert_has_keys(
+            file,
+            &["path", "packet", "target", "kind", "size", "sha256"],
+        );
+        assert_eq!(file["packet"], "Gimbal");
+        assert_eq!(file["kind"], "packet");
+        let content = fs::read(dir.0.join(file["path"].as_str().unwrap())).unwrap();
+        assert_eq!(file["size"], content.len());
+        let hash: String = Sha256::digest(&content)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert_eq!(file["sha256"], hash);
+    }
+}
+
+#[test]
+fn test_jsonl_events() {
+    let dir = TempDir::new("jsonl");
+    dir.write("warning.json", WARNING);
+    dir.write("error.json", ERROR);
+
+    // 标准输出的每一行都是一个事件，日志只写到标准错误
+    let parse_events = |output: &Output| -> Vec<serde_json::Value> {
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| {
+                serde_json::from_str(line)
+                    .unwrap_or_else(|e| panic!("不是 JSON 事件: {:?}: {}", line, e))
+            })
+            .collect()
+    };
+
+    let output = rplc(&dir.0, &["warning.json", "-o", "out", "--format", "jsonl"]);
+    assert_eq!(exit_code(&output), 0);
+    let events = parse_events(&output);
+    let kinds: Vec<&str> = events
+        .iter()
+        .map(|e| e["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["diagnostic", "file_written", "summary"]);
+    assert_has_keys(
+        &events[0],
+        &[
+            "file", "severity", "code", "message", "span", "line", "column",
+        ],
+    );
+    assert_eq!(events[0]["code"], "rplc::doc::missing");
+    assert_eq!(events[0]["severity"], "Warning");
+    assert_eq!(events[0]["line"], 2);
+    assert_has_keys(
+        &events[1],
+        &["path", "packet", "target", "kind", "size", "sha256"],
+    );
+    assert_eq!(events[1]["path"], "out/warning.hpp");
+    assert_has_keys(
+        &events[2],
+        &["success", "packets", "warnings", "errors", "elapsed_ms"],
+    );
+    assert_eq!(events[2]["success"], true);
+    assert_eq!(events[2]["packets"], 1);
+    assert_eq!(events[2]["warnings"], 1);
+
+    let output = rplc(&dir.0, &["error.json", "-o", "out", "--format", "jsonl"]);
+    assert_eq!(exit_code(&output), 1);
+    let events = parse_events(&output);
+    let last = events.last().unwrap();
+    assert_eq!(last["event"], "summary");
+    assert_eq!(last["success"], false);
+    assert_eq!(last["errors"], 1);
+    assert_eq!(events[0]["severity"], "Error");
+}
+
+#[test]
+fn test_tracing_spans_and_env_filter() {
+    let dir = TempDir::new("tracing");
+    dir.write("clean.json", CLEAN);
+    let run = |env: &[(&str, &str)], args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rplc_cli"))
+            .current_dir(&dir.0)
+            .env_remove("RUST_LOG")
+            .env("NO_COLOR", "1")
+            .envs(env.iter().copied())
+            .args(args)
+            .output()
+            .unwrap();
+        assert_eq!(exit_code(&output), 0);
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    // 详细模式在各阶段的 span 结束时输出耗时
+    let stderr = run(&[], &["clean.json", "-o", "out", "-v"]);
+    for span in [
+        "run{input=clean.json}:validate: close",
+        "run{input=clean.json}:generate:parse: close",
+        "run{input=clean.json}:generate: close",
+    ] {
+        assert!(stderr.contains(span), "缺少 {}:\n{}", span, stderr);
+    }
+
+    // 默认输出 info 级别，RUST_LOG 优先于命令行的详细程度
+    assert!(run(&[], &["clean.json", "-o", "out"]).contains("生成成功"));
+    let stderr = run(&[("RUST_LOG", "warn")], &["clean.json", "-o", "out", "-v"]);
+    assert!(!stderr.contains("生成成功"), "{}", stderr);
+    assert!(!stderr.contains("close"), "{}", stderr);
+}
+
+#[test]
+fn test_c_only() {
+    let dir = TempDir::new("c_only");
+    dir.write("clean.json", CLEAN);
+    dir.write(
+        "set.json",
+        r#"{"protocol_version":"1.0.0","packets":[
+            {"packet_name":"A","command_id":"0x0101","fields":[
+                {"name":"yaw","type":"float","comment":"Y"}]},
+            {"packet_name":"B","command_id":"0x0102","fields":[
+                {"name":"x","type":"uint8_t","comment":"X"}]}]}"#,
+    );
+
+    assert_eq!(
+        exit_code(&rplc(&dir.0, &["clean.json", "--c-only", "-o", "single"])),
+        0
+    );
+    assert_eq!(dir.files("single"), ["clean.h"]);
+    let header = fs::read_to_string(dir.0.join("single/clean.h")).unwrap();
+    assert!(header.contains("typedef struct"));
+    assert!(header.contains("#pragma pack(push, 1)"));
+    assert!(header.contains("#define GIMBAL_CMD 0x0104"));
+
+    // 多包模式只写出各包的 C 头文件，不生成 C++ 注册表
+    assert_eq!(
+        exit_code(&rplc(&dir.0, &["set.json", "--c-only", "-o", "multi"])),
+        0
+    );
+    assert_eq!(dir.files("multi"), ["A.h", "B.h"]);
+
+    // 与 C++ 专有的选项互斥
+    let output = rplc(&dir.0, &["clean.json", "--c-only", "--c-header", "-o", "x"]);
+    assert_eq!(exit_code(&output), 2);
+    assert!(dir.files("x").is_empty());
+}
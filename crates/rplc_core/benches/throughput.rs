@@ -0,0 +1,16 @@
+//! `cargo bench -p rplc_core` 运行的吞吐量基准，工作负载与 `rplc_cli bench` 相同
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use rplc_core::bench_workloads;
+
+fn throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rplc");
+    for workload in bench_workloads() {
+        group.throughput(Throughput::Bytes(workload.input().len() as u64));
+        group.bench_function(workload.name, |b| b.iter(|| workload.run()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, throughput);
+criterion_main!(benches);
@@ -0,0 +1,138 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::config::ConfigOrArray;
+use crate::generator::{GenerateOptions, MultiGenerateError, generate_multiple_with_options};
+
+/// 两次生成结果不一致的位置
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DeterminismMismatch {
+    pub packet: String,
+    /// 第一个不一致的行号（从 1 开始）
+    pub line: usize,
+    pub first: Option<String>,
+    pub second: Option<String>,
+}
+
+impl fmt::Display for DeterminismMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "包 '{}' 第 {} 行不一致:\n  第一次: {}\n  第二次: {}",
+            self.packet,
+            self.line,
+            self.first.as_deref().unwrap_or("<缺失>"),
+            self.second.as_deref().unwrap_or("<缺失>")
+        )
+    }
+}
+
+/// 将同一输入生成两次并逐包比较结果，返回所有不一致之处
+///
+/// `shuffle` 为 true 时，第二次生成会以逆序处理各个包，
+/// 用于发现依赖包处理顺序的非确定性输出
+pub fn audit_determinism(
+    json_input: &str,
+    options: &GenerateOptions,
+    shuffle: bool,
+) -> Result<Vec<DeterminismMismatch>, MultiGenerateError> {
+    let first = generate_multiple_with_options(json_input, options)?;
+
+    let second = if shuffle {
+        let mut packets = serde_json::from_str::<ConfigOrArray>(json_input)?.into_packets();
+        packets.reverse();
+        let shuffled = serde_json::to_string(&packets)?;
+        generate_multiple_with_options(&shuffled, options)?
+    } else {
+        generate_multiple_with_options(json_input, options)?
+    };
+
+    let mut mismatches = Vec::new();
+    for (packet, first_output) in &first {
+        let second_output = second
+            .iter()
+            .find(|(name, _)| name == packet)
+            .map(|(_, output)| output.as_str())
+            .unwrap_or_default();
+        if let Some(mismatch) = compare_outputs(packet, first_output, second_output) {
+            mismatches.push(mismatch);
+        }
+    }
+    Ok(mismatches)
+}
+
+fn compare_outputs(packet: &str, first: &str, second: &str) -> Option<DeterminismMismatch> {
+    if first == second {
+        return None;
+    }
+    let mut first_lines = first.lines();
+    let mut second_lines = second.lines();
+    let mut line = 1;
+    loop {
+        let (a, b) = (first_lines.next(), second_lines.next());
+        if a != b {
+            return Some(DeterminismMismatch {
+                packet: packet.to_string(),
+                line,
+                first: a.map(str::to_string),
+                second: b.map(str::to_string),
+            });
+        }
+        if a.is_none() {
+            // 仅有行尾差异
+            return Some(DeterminismMismatch {
+                packet: packet.to_string(),
+                line,
+                first: None,
+                second: None,
+            });
+        }
+        line += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_determinism_clean() {
+        let json = r#"[
+            {
+                "packet_name": "PacketA",
+                "command_id": "0x0101",
+                "fields": [ { "name": "a", "type": "uint8_t", "comment": "A" } ]
+            },
+            {
+                "packet_name": "PacketB",
+                "command_id": "0x0102",
+                "fields": [ { "name": "b", "type": "uint32_t", "bit_field": 3, "comment": "B" } ]
+            }
+        ]"#;
+
+        let options = GenerateOptions::default();
+        assert!(audit_determinism(json, &options, false).unwrap().is_empty());
+        assert!(audit_determinism(json, &options, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compare_outputs_reports_first_difference() {
+        let mismatch = compare_outputs("P", "a\nb\nc\n", "a\nx\nc\n").unwrap();
+        assert_eq!(mismatch.line, 2);
+        assert_eq!(mismatch.first.as_deref(), Some("b"));
+        assert_eq!(mismatch.second.as_deref(), Some("x"));
+
+        let mismatch = compare_outputs("P", "a\n", "a\nb\n").unwrap();
+        assert_eq!(mismatch.line, 2);
+        assert_eq!(mismatch.first, None);
+
+        assert!(compare_outputs("P", "a\n", "a\n").is_none());
+    }
+
+    #[test]
+    fn test_audit_determinism_invalid_input() {
+        let options = GenerateOptions::default();
+        assert!(audit_determinism("not json", &options, false).is_err());
+    }
+}
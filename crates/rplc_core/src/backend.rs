@@ -0,0 +1,253 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::diagnostics::{Severity, ValidationCode};
+use crate::generator::{GenerateError, GenerateOptions, generate_with_options};
+use crate::target::Target;
+use crate::validator::{ValidateOptions, validate_with_options};
+use crate::{
+    c_backend, imhex_backend, latex_backend, markdown, matlab_backend, python_backend,
+    rust_backend, svg_backend,
+};
+
+/// 代码生成后端，Playground 可以一次生成多个后端的输出
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// RPL 的 C++ 头文件
+    Cpp,
+    /// `extern "C"` 包裹的 C 头文件
+    C,
+    /// `#[repr(C)]` 结构体
+    Rust,
+    /// 字段布局说明文档
+    Markdown,
+    /// dataclass 与 `struct` 格式字符串
+    Python,
+    /// classdef，含 Simulink 总线定义与解码方法
+    Matlab,
+    /// ImHex 十六进制编辑器的模式语言
+    ImHex,
+    /// 技术报告用的 LaTeX 字段表格
+    Latex,
+    /// 文档与演示用的字节与位布局图
+    Svg,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 9] = [
+        Backend::Cpp,
+        Backend::C,
+        Backend::Rust,
+        Backend::Markdown,
+        Backend::Python,
+        Backend::Matlab,
+        Backend::ImHex,
+        Backend::Latex,
+        Backend::Svg,
+    ];
+
+    /// 后端的短名称，用于命令行参数与 WASM 接口
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Cpp => "cpp",
+            Backend::C => "c",
+            Backend::Rust => "rust",
+            Backend::Markdown => "markdown",
+            Backend::Python => "python",
+            Backend::Matlab => "matlab",
+            Backend::ImHex => "imhex",
+            Backend::Latex => "latex",
+            Backend::Svg => "svg",
+        }
+    }
+
+    /// 生成文件的扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            Backend::Cpp => "hpp",
+            Backend::C => "h",
+            Backend::Rust => "rs",
+            Backend::Markdown => "md",
+            Backend::Python => "py",
+            Backend::Matlab => "m",
+            Backend::ImHex => "hexpat",
+            Backend::Latex => "tex",
+            Backend::Svg => "svg",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Backend> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "cpp" | "c++" => Some(Backend::Cpp),
+            "c" => Some(Backend::C),
+            "rust" | "rs" => Some(Backend::Rust),
+            "markdown" | "md" => Some(Backend::Markdown),
+            "python" | "py" => Some(Backend::Python),
+            "matlab" | "m" => Some(Backend::Matlab),
+            "imhex" | "hexpat" => Some(Backend::ImHex),
+            "latex" | "tex" => Some(Backend::Latex),
+            "svg" => Some(Backend::Svg),
+            _ => None,
+        }
+    }
+
+    /// 生成单个包的代码，校验规则与 `generate` 相同，并额外检查后端的类型映射
+    pub fn generate(
+        self,
+        json_input: &str,
+        options: &GenerateOptions,
+    ) -> Result<String, GenerateError> {
+        match self {
+            Backend::Cpp => generate_with_options(json_input, options),
+            Backend::C => {
+                let mut config = parse_validated(json_input, Target::C)?;
+                config.localize(options.comment_language.as_deref());
+                c_backend::generate_c(&config, options)
+            }
+            Backend::Rust => {
+                let mut config = parse_validated(json_input, Target::Rust)?;
+                config.localize(options.comment_language.as_deref());
+                rust_backend::generate_rust(&config, options)
+            }
+            Backend::Markdown => {
+                let config = parse_validated(json_input, Target::Cpp)?;
+                Ok(markdown::generate_markdown(
+                    &config,
+                    options.comment_language.as_deref(),
+                ))
+            }
+            Backend::Python => {
+                let mut config = parse_validated(json_input, Target::Python)?;
+                config.localize(options.comment_language.as_deref());
+                python_backend::generate_python(&config, options)
+            }
+            Backend::Matlab => {
+                let mut config = parse_validated(json_input, Target::Matlab)?;
+                config.localize(options.comment_language.as_deref());
+                matlab_backend::generate_matlab(&config, options)
+            }
+            Backend::ImHex => {
+                let mut config = parse_validated(json_input, Target::Cpp)?;
+                config.localize(options.comment_language.as_deref());
+                imhex_backend::generate_imhex(&config, options)
+            }
+            Backend::Latex => {
+                let mut config = parse_validated(json_input, Target::Cpp)?;
+                config.localize(options.comment_language.as_deref());
+                latex_backend::generate_latex(&config, options)
+            }
+            Backend::Svg => {
+                let mut config = parse_validated(json_input, Target::Cpp)?;
+                config.localize(options.comment_language.as_deref());
+                svg_backend::generate_svg(&config, options)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// 用同一份定义生成多个后端的输出，各后端的结果相互独立
+pub fn generate_backends(
+    json_input: &str,
+    backends: &[Backend],
+    options: &GenerateOptions,
+) -> Vec<(Backend, Result<String, GenerateError>)> {
+    backends
+        .iter()
+        .map(|&backend| (backend, backend.generate(json_input, options)))
+        .collect()
+}
+
+fn parse_validated(json_input: &str, target: Target) -> Result<Config, GenerateError> {
+    let options = ValidateOptions {
+        targets: vec![target],
+        ..Default::default()
+    };
+    let diags = validate_with_options(json_input, &options);
+    let (unsupported, others): (Vec<_>, Vec<_>) = diags
+        .iter()
+        .filter(|diag| diag.severity == Severity::Error)
+        .partition(|diag| matches!(diag.code, ValidationCode::UnsupportedTypeForTarget(..)));
+    if !others.is_empty() {
+        return Err(GenerateError::ValidationError);
+    }
+    // 定义本身有效、只是目标语言无法表示时说明原因，而不是笼统的校验失败
+    if let Some(diag) = unsupported.first() {
+        return Err(GenerateError::Unsupported {
+            backend: target.name(),
+            reason: diag.code.to_string(),
+        });
+    }
+    Ok(serde_json::from_str(json_input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"{
+        "packet_name": "GimbalCommand",
+        "command_id": "0x0104",
+        "packed": true,
+        "fields": [
+            { "name": "yaw", "type": "float", "comment": "偏航角" },
+            { "name": "gains", "type": "int16_t[3]" }
+        ]
+    }"#;
+
+    #[test]
+    fn test_backend_names_round_trip() {
+        for backend in Backend::ALL {
+            assert_eq!(Backend::from_name(backend.name()), Some(backend));
+        }
+        assert_eq!(Backend::from_name(" C++ "), Some(Backend::Cpp));
+        assert_eq!(Backend::from_name("py"), Some(Backend::Python));
+        assert_eq!(Backend::from_name("java"), None);
+    }
+
+    #[test]
+    fn test_generate_backends_keeps_order() {
+        let results = generate_backends(
+            JSON,
+            &[Backend::Markdown, Backend::Cpp, Backend::Rust],
+            &GenerateOptions::default(),
+        );
+        let backends: Vec<_> = results.iter().map(|(backend, _)| *backend).collect();
+        assert_eq!(backends, [Backend::Markdown, Backend::Cpp, Backend::Rust]);
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        assert!(
+            results[1]
+                .1
+                .as_ref()
+                .unwrap()
+                .contains("struct GimbalCommand")
+        );
+    }
+
+    #[test]
+    fn test_backend_checks_target_types() {
+        let json = r#"{"packet_name":"A","command_id":"1",
+            "fields":[{"name":"x","type":"long double"}]}"#;
+        let options = GenerateOptions::default();
+        assert!(Backend::Cpp.generate(json, &options).is_ok());
+        assert!(matches!(
+            Backend::Rust.generate(json, &options),
+            Err(GenerateError::Unsupported {
+                backend: "Rust",
+                ..
+            })
+        ));
+        let invalid = r#"{"packet_name":"A","command_id":"bad","fields":[]}"#;
+        assert!(matches!(
+            Backend::C.generate(invalid, &options),
+            Err(GenerateError::ValidationError)
+        ));
+    }
+}
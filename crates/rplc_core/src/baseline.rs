@@ -0,0 +1,219 @@
+//! 诊断基线：记录遗留定义中已有的问题，之后的检查只报告新出现的诊断
+//!
+//! 条目按诊断代码、包名与字段名匹配，与行列位置无关，编辑文件的其他部分不会使基线失效
+
+use json_spanned_value as jsv;
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::{RplcDiagnostic, Span};
+use crate::validator::parse_spanned;
+
+/// 基线中的一条诊断
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub code: String,
+    /// 诊断所在的包，位于包之外（如包集合的元数据）时为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packet: Option<String>,
+    /// 诊断所在的字段或嵌套结构体，位于包级别的键上时为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticBaseline {
+    /// 生成基线的 rplc 版本，仅供参考
+    pub version: String,
+    /// 按代码、包名与字段名排序；同一位置的同一问题出现几次就记录几条
+    pub diagnostics: Vec<BaselineEntry>,
+}
+
+impl DiagnosticBaseline {
+    /// 记录 `json_input` 的全部诊断；诊断的位置必须指向 `json_input` 本身
+    pub fn new(json_input: &str, diagnostics: &[RplcDiagnostic]) -> DiagnosticBaseline {
+        let root = parse_root(json_input);
+        let mut entries: Vec<BaselineEntry> = diagnostics
+            .iter()
+            .filter_map(|diag| baseline_entry(root.as_ref(), diag))
+            .collect();
+        entries.sort();
+        DiagnosticBaseline {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            diagnostics: entries,
+        }
+    }
+
+    /// 去掉基线中已有的诊断，返回新出现的诊断；同一条目在基线中只抵消相同次数的诊断
+    pub fn suppress(
+        &self,
+        json_input: &str,
+        diagnostics: Vec<RplcDiagnostic>,
+    ) -> Vec<RplcDiagnostic> {
+        let root = parse_root(json_input);
+        let mut remaining = self.diagnostics.clone();
+        diagnostics
+            .into_iter()
+            .filter(|diag| {
+                let Some(entry) = baseline_entry(root.as_ref(), diag) else {
+                    return true;
+                };
+                match remaining.iter().position(|known| *known == entry) {
+                    Some(index) => {
+                        remaining.swap_remove(index);
+                        false
+                    }
+                    None => true,
+                }
+            })
+            .collect()
+    }
+}
+
+/// 解析一次定义文件，供同一批诊断查找位置；无法解析时所有诊断都没有位置
+fn parse_root(json_input: &str) -> Option<jsv::Spanned<jsv::Value>> {
+    parse_spanned(json_input).ok()
+}
+
+fn baseline_entry(
+    root: Option<&jsv::Spanned<jsv::Value>>,
+    diag: &RplcDiagnostic,
+) -> Option<BaselineEntry> {
+    let (packet, field) = match root {
+        Some(root) => location_in(root, diag.span),
+        None => (None, None),
+    };
+    Some(BaselineEntry {
+        code: diag.code.code()?.to_string(),
+        packet,
+        field,
+    })
+}
+
+/// 诊断所在的包名与字段名，按位置查找包含它的包对象与最内层的字段条目
+pub fn diagnostic_location(
+    json_input: &str,
+    span: Option<Span>,
+) -> (Option<String>, Option<String>) {
+    match parse_root(json_input) {
+        Some(root) => location_in(&root, span),
+        None => (None, None),
+    }
+}
+
+fn location_in(
+    root: &jsv::Spanned<jsv::Value>,
+    span: Option<Span>,
+) -> (Option<String>, Option<String>) {
+    let Some((offset, _)) = span else {
+        return (None, None);
+    };
+    let packets: Vec<&jsv::Spanned<jsv::Value>> = match root.get_ref() {
+        jsv::Value::Array(packets) => packets.iter().collect(),
+        jsv::Value::Object(map) => match map.get("packets").and_then(|p| p.as_array()) {
+            Some(packets) => packets.iter().collect(),
+            None => vec![root],
+        },
+        _ => Vec::new(),
+    };
+    let Some(packet) = packets
+        .into_iter()
+        .find(|packet| contains(packet, offset))
+        .and_then(|packet| packet.as_object())
+    else {
+        return (None, None);
+    };
+
+    let mut field = None;
+    let mut entries = packet.get("fields").and_then(|fields| fields.as_array());
+    while let Some(entry) = entries
+        .into_iter()
+        .flatten()
+        .find(|entry| contains(entry, offset))
+        .and_then(|entry| entry.as_object())
+    {
+        if let Some(name) = entry.get("name").and_then(|name| name.as_string()) {
+            field = Some(name.to_string());
+        }
+        entries = entry.get("group").and_then(|group| group.as_array());
+    }
+    let packet_name = packet
+        .get("packet_name")
+        .and_then(|name| name.as_string())
+        .map(str::to_string);
+    (packet_name, field)
+}
+
+fn contains(node: &jsv::Spanned<jsv::Value>, offset: usize) -> bool {
+    let (start, end) = node.span();
+    start <= offset && offset < end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::validate_multiple;
+
+    const LEGACY: &str = r#"{
+        "protocol_version": "1.0.0",
+        "packets": [
+            { "packet_name": "Gimbal", "command_id": "0x0101", "comment": "G", "fields": [
+                { "name": "yaw", "type": "float" },
+                { "name": "imu", "comment": "I", "group": [
+                    { "name": "gyro", "type": "float" }
+                ] }
+            ] },
+            { "packet_name": "Chassis", "command_id": "0x0102", "comment": "C", "fields": [
+                { "name": "speed", "type": "int16_t", "comment": "S" }
+            ] }
+        ]
+    }"#;
+
+    #[test]
+    fn test_diagnostic_location() {
+        let at = |needle: &str| {
+            let start = LEGACY.find(needle).unwrap();
+            diagnostic_location(LEGACY, Some((start, needle.len())))
+        };
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(at(r#""yaw""#), (some("Gimbal"), some("yaw")));
+        assert_eq!(at(r#""gyro""#), (some("Gimbal"), some("gyro")));
+        assert_eq!(at(r#""I""#), (some("Gimbal"), some("imu")));
+        assert_eq!(at(r#""0x0102""#), (some("Chassis"), None));
+        assert_eq!(at(r#""1.0.0""#), (None, None));
+        assert_eq!(diagnostic_location(LEGACY, None), (None, None));
+    }
+
+    #[test]
+    fn test_baseline_suppresses_known_diagnostics() {
+        let diags = validate_multiple(LEGACY);
+        assert_eq!(diags.len(), 2);
+        let baseline = DiagnosticBaseline::new(LEGACY, &diags);
+        let entry = |packet: &str, field: &str| BaselineEntry {
+            code: "rplc::doc::missing".to_string(),
+            packet: Some(packet.to_string()),
+            field: Some(field.to_string()),
+        };
+        assert_eq!(
+            baseline.diagnostics,
+            vec![entry("Gimbal", "gyro"), entry("Gimbal", "yaw")]
+        );
+        assert!(baseline.suppress(LEGACY, diags).is_empty());
+
+        // 位置改变不影响匹配，新的问题照常报告
+        let edited = format!("\n\n{}", LEGACY.replace(r#", "comment": "S""#, ""));
+        let new = baseline.suppress(&edited, validate_multiple(&edited));
+        let root = parse_root(&edited);
+        let locations: Vec<_> = new
+            .iter()
+            .map(|diag| baseline_entry(root.as_ref(), diag).unwrap())
+            .collect();
+        assert_eq!(locations, vec![entry("Chassis", "speed")]);
+
+        let json = serde_json::to_string(&baseline).unwrap();
+        assert_eq!(
+            serde_json::from_str::<DiagnosticBaseline>(&json).unwrap(),
+            baseline
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! 吞吐量基准的工作负载与基线比较
+//!
+//! `cargo bench` 与 `rplc_cli bench` 使用同一组输入，基线文件中的耗时可以直接与 criterion 的结果对照
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{Backend, generate_backends};
+use crate::config::ConfigOrArray;
+use crate::generator::{GenerateOptions, generate, generate_multiple};
+use crate::validator::validate;
+
+/// 一项基准：固定的输入与对它执行的操作
+pub struct Workload {
+    pub name: &'static str,
+    pub description: &'static str,
+    input: String,
+    run: fn(&str),
+}
+
+impl Workload {
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// 执行一次操作，结果经过 `black_box`，不会被优化掉
+    pub fn run(&self) {
+        (self.run)(black_box(&self.input))
+    }
+}
+
+/// 所有工作负载，名称在基线文件中作为键使用，改名会使旧基线中的对应项失效
+pub fn bench_workloads() -> Vec<Workload> {
+    let packet = packet_json("GimbalStatus", 0x0104, 12);
+    vec![
+        Workload {
+            name: "parse",
+            description: "解析 12 个字段的单包定义",
+            input: packet.clone(),
+            run: |input| {
+                black_box(serde_json::from_str::<ConfigOrArray>(input).ok());
+            },
+        },
+        Workload {
+            name: "validate",
+            description: "校验 12 个字段的单包定义",
+            input: packet.clone(),
+            run: |input| {
+                black_box(validate(input));
+            },
+        },
+        Workload {
+            name: "generate",
+            description: "生成 12 个字段的单包 C++ 头文件",
+            input: packet,
+            run: |input| {
+                black_box(generate(input).ok());
+            },
+        },
+        Workload {
+            name: "multi_1000",
+            description: "生成 1000 个包的多包定义",
+            input: packet_array_json(1000),
+            run: |input| {
+                black_box(generate_multiple(input).ok());
+            },
+        },
+        Workload {
+            name: "wasm_compile",
+            description: "Playground 规模的输入：64 个字段的单包，一次生成所有后端",
+            input: packet_json("PlaygroundPacket", 0x0201, 64),
+            run: |input| {
+                black_box(generate_backends(
+                    input,
+                    &Backend::ALL,
+                    &GenerateOptions::default(),
+                ));
+            },
+        },
+    ]
+}
+
+/// 每个字段依次使用的类型与位宽，覆盖普通字段、数组与位域
+const FIELD_SHAPES: [(&str, Option<u32>); 6] = [
+    ("float", None),
+    ("int16_t", None),
+    ("uint8_t", Some(3)),
+    ("uint8_t", Some(5)),
+    ("uint8_t[4]", None),
+    ("uint32_t", None),
+];
+
+fn packet_value(name: &str, command_id: u16, field_count: usize) -> serde_json::Value {
+    let fields: Vec<_> = (0..field_count)
+        .map(|index| {
+            let (ty, bits) = FIELD_SHAPES[index % FIELD_SHAPES.len()];
+            let mut field = serde_json::json!({
+                "name": format!("field_{}", index),
+                "type": ty,
+                "comment": format!("第 {} 个字段", index),
+            });
+            if let Some(bits) = bits {
+                field["bit_field"] = bits.into();
+            }
+            field
+        })
+        .collect();
+    serde_json::json!({
+        "packet_name": name,
+        "command_id": format!("0x{:04X}", command_id),
+        "namespace": "Robot::Bench",
+        "comment": format!("基准测试包 {}", name),
+        "fields": fields,
+    })
+}
+
+fn packet_json(name: &str, command_id: u16, field_count: usize) -> String {
+    serde_json::to_string_pretty(&packet_value(name, command_id, field_count))
+        .expect("基准输入可以序列化")
+}
+
+fn packet_array_json(count: usize) -> String {
+    let packets: Vec<_> = (0..count)
+        .map(|index| packet_value(&format!("Packet{}", index), 0x0100 + index as u16, 8))
+        .collect();
+    serde_json::to_string_pretty(&packets).expect("基准输入可以序列化")
+}
+
+/// 各工作负载每次执行的平均耗时（纳秒），保存为基线文件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BenchBaseline {
+    /// 生成基线的 rplc 版本，仅供参考
+    pub version: String,
+    pub ns_per_iter: BTreeMap<String, f64>,
+}
+
+impl BenchBaseline {
+    pub fn new(ns_per_iter: BTreeMap<String, f64>) -> BenchBaseline {
+        BenchBaseline {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            ns_per_iter,
+        }
+    }
+}
+
+/// 一项工作负载当前耗时与基线的比较
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchComparison {
+    pub name: String,
+    /// 基线中没有该项时为 `None`
+    pub baseline: Option<f64>,
+    pub current: f64,
+}
+
+impl BenchComparison {
+    /// 相对基线的变化，`0.1` 表示慢了 10%
+    pub fn change(&self) -> Option<f64> {
+        self.baseline
+            .filter(|baseline| *baseline > 0.0)
+            .map(|baseline| self.current / baseline - 1.0)
+    }
+
+    /// 比基线慢的幅度超过 `tolerance`（如 `0.1`）时视为性能回退
+    pub fn is_regression(&self, tolerance: f64) -> bool {
+        self.change().is_some_and(|change| change > tolerance)
+    }
+}
+
+/// 按当前结果中的工作负载逐项与基线比较，基线中多出的项被忽略
+pub fn compare_bench(baseline: &BenchBaseline, current: &BenchBaseline) -> Vec<BenchComparison> {
+    current
+        .ns_per_iter
+        .iter()
+        .map(|(name, current)| BenchComparison {
+            name: name.clone(),
+            baseline: baseline.ns_per_iter.get(name).copied(),
+            current: *current,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Severity;
+    use crate::validator::validate_multiple;
+
+    #[test]
+    fn test_workload_inputs_are_valid() {
+        let workloads = bench_workloads();
+        let mut names: Vec<_> = workloads.iter().map(|w| w.name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), workloads.len());
+
+        for workload in &workloads {
+            let diags = if workload.name == "multi_1000" {
+                validate_multiple(workload.input())
+            } else {
+                validate(workload.input())
+            };
+            assert!(
+                diags.iter().all(|d| d.severity != Severity::Error),
+                "{}: {:?}",
+                workload.name,
+                diags
+            );
+            workload.run();
+        }
+        assert_eq!(generate_multiple(&packet_array_json(3)).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_compare_bench() {
+        let baseline = BenchBaseline::new(BTreeMap::from([
+            ("parse".to_string(), 100.0),
+            ("generate".to_string(), 200.0),
+            ("removed".to_string(), 50.0),
+        ]));
+        let current = BenchBaseline::new(BTreeMap::from([
+            ("parse".to_string(), 105.0),
+            ("generate".to_string(), 260.0),
+            ("added".to_string(), 10.0),
+        ]));
+        let comparisons = compare_bench(&baseline, &current);
+        assert_eq!(comparisons.len(), 3);
+
+        let find = |name: &str| comparisons.iter().find(|c| c.name == name).unwrap();
+        assert!(!find("parse").is_regression(0.1));
+        assert!(find("generate").is_regression(0.1));
+        assert!(!find("generate").is_regression(0.5));
+        assert_eq!(find("added").change(), None);
+        assert!(!find("added").is_regression(0.0));
+    }
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let baseline = BenchBaseline::new(BTreeMap::from([("parse".to_string(), 1234.5)]));
+        let json = serde_json::to_string(&baseline).unwrap();
+        assert_eq!(
+            serde_json::from_str::<BenchBaseline>(&json).unwrap(),
+            baseline
+        );
+        assert_eq!(baseline.version, env!("CARGO_PKG_VERSION"));
+    }
+}
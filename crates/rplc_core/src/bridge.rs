@@ -0,0 +1,375 @@
+//! 生成地面站用的遥测桥：从 UDP、TCP 或串口接收裁判系统帧，用 Python 后端生成的类解码后
+//! 以 JSON 通过 WebSocket 推送给前端
+//!
+//! 生成的脚本只依赖 Python 标准库（asyncio），WebSocket 只实现服务端推送所需的部分
+
+use crate::config::ConfigOrArray;
+use crate::generator::{GenerateOptions, MultiGenerateError};
+use crate::python_backend::generate_python;
+
+/// 包模块的导入表之前的部分
+const BRIDGE_HEAD: &str = r#"# 由 rplc 生成，请勿手动修改
+"""遥测桥：接收裁判系统串口协议的帧，解码后以 JSON 推送给所有 WebSocket 客户端
+
+数据源三选一：
+    --udp HOST:PORT     在该地址接收 UDP 数据报
+    --tcp HOST:PORT     连接 TCP 串口服务器（例如 ser2net），断开后自动重连
+    --serial DEVICE     直接读取串口设备，波特率需事先设置，例如 stty -F /dev/ttyUSB0 115200 raw
+
+每一帧推送一条消息：{"packet": 包名, "cmd": 命令码, "seq": 包序号, "time": 接收时间, "data": 字段}；
+命令码未注册或数据段长度不符时 packet 为 null，data 为数据段的十六进制字符串
+"""
+
+from __future__ import annotations
+
+import argparse
+import asyncio
+import base64
+import dataclasses
+import hashlib
+import importlib.util
+import json
+import os
+import sys
+import time
+from pathlib import Path
+
+_HERE = Path(__file__).resolve().parent
+
+
+def _load(name: str, path: str) -> type:
+    """按相对于本文件的路径加载生成的包模块"""
+    spec = importlib.util.spec_from_file_location(f"rplc_{name}", _HERE / path)
+    module = importlib.util.module_from_spec(spec)
+    # dataclass 在类定义时要从 sys.modules 中找到所在的模块
+    sys.modules[spec.name] = module
+    spec.loader.exec_module(module)
+    return getattr(module, name)
+
+
+"#;
+
+/// 包模块的导入表之后的部分
+const BRIDGE_BODY: &str = r#"
+SOF = 0xA5
+HEADER_SIZE = 5
+CMD_SIZE = 2
+TAIL_SIZE = 2
+
+
+def crc8(data: bytes, crc: int = 0xFF) -> int:
+    """CRC8（多项式 0x31，按位反转），用于帧头校验"""
+    for byte in data:
+        crc ^= byte
+        for _ in range(8):
+            crc = (crc >> 1) ^ 0x8C if crc & 1 else crc >> 1
+    return crc
+
+
+def crc16(data: bytes, crc: int = 0xFFFF) -> int:
+    """CRC16（多项式 0x1021，按位反转），用于整帧校验"""
+    for byte in data:
+        crc ^= byte
+        for _ in range(8):
+            crc = (crc >> 1) ^ 0x8408 if crc & 1 else crc >> 1
+    return crc
+
+
+class FrameParser:
+    """把收到的字节切分为经过校验的帧，校验失败时从下一个 SOF 重新同步"""
+
+    def __init__(self) -> None:
+        self.buffer = bytearray()
+        self.errors = 0
+
+    def feed(self, data: bytes) -> list[tuple[int, int, bytes]]:
+        """返回 data 补全的所有帧，每帧为 (包序号, 命令码, 数据段)"""
+        self.buffer += data
+        frames = []
+        while True:
+            start = self.buffer.find(SOF)
+            if start < 0:
+                self.buffer.clear()
+                return frames
+            del self.buffer[:start]
+            if len(self.buffer) < HEADER_SIZE:
+                return frames
+            if crc8(self.buffer[:HEADER_SIZE - 1]) != self.buffer[HEADER_SIZE - 1]:
+                self.errors += 1
+                del self.buffer[:1]
+                continue
+            total = HEADER_SIZE + CMD_SIZE + int.from_bytes(self.buffer[1:3], "little") + TAIL_SIZE
+            if len(self.buffer) < total:
+                return frames
+            frame = bytes(self.buffer[:total])
+            if crc16(frame[:-TAIL_SIZE]) != int.from_bytes(frame[-TAIL_SIZE:], "little"):
+                self.errors += 1
+                del self.buffer[:1]
+                continue
+            del self.buffer[:total]
+            cmd = int.from_bytes(frame[HEADER_SIZE:HEADER_SIZE + CMD_SIZE], "little")
+            frames.append((frame[3], cmd, frame[HEADER_SIZE + CMD_SIZE:-TAIL_SIZE]))
+
+
+def decode(seq: int, cmd: int, payload: bytes) -> dict:
+    """把一帧转换为推送的消息"""
+    message = {"packet": None, "cmd": cmd, "seq": seq, "time": time.time(), "data": payload.hex()}
+    packet = PACKETS.get(cmd)
+    if packet is not None and len(payload) == packet.SIZE:
+        message["packet"] = packet.__name__
+        message["data"] = dataclasses.asdict(packet.unpack(payload))
+    return message
+
+
+class WebSocketHub:
+    """最小的 WebSocket 服务端（RFC 6455），只向客户端推送文本消息"""
+
+    GUID = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11"
+
+    def __init__(self) -> None:
+        self.clients: set[asyncio.StreamWriter] = set()
+
+    async def handle(self, reader: asyncio.StreamReader, writer: asyncio.StreamWriter) -> None:
+        try:
+            request = await reader.readuntil(b"\r\n\r\n")
+        except (asyncio.IncompleteReadError, asyncio.LimitOverrunError):
+            writer.close()
+            return
+        key = None
+        for line in request.decode("latin-1").split("\r\n")[1:]:
+            name, _, value = line.partition(":")
+            if name.strip().lower() == "sec-websocket-key":
+                key = value.strip()
+        if key is None:
+            writer.write(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")
+            writer.close()
+            return
+        accept = base64.b64encode(hashlib.sha1((key + self.GUID).encode()).digest()).decode()
+        writer.write(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n"
+            f"Sec-WebSocket-Accept: {accept}\r\n\r\n".encode()
+        )
+        self.clients.add(writer)
+        try:
+            # 客户端发来的数据只用于发现关闭帧（opcode 8）与断开
+            while True:
+                data = await reader.read(4096)
+                if not data or data[0] & 0x0F == 0x8:
+                    break
+        except ConnectionError:
+            pass
+        finally:
+            self.clients.discard(writer)
+            writer.close()
+
+    def broadcast(self, text: str) -> None:
+        """以不分片、不加掩码的文本帧发送给所有客户端"""
+        payload = text.encode()
+        if len(payload) < 126:
+            header = bytes([0x81, len(payload)])
+        elif len(payload) < 1 << 16:
+            header = bytes([0x81, 126]) + len(payload).to_bytes(2, "big")
+        else:
+            header = bytes([0x81, 127]) + len(payload).to_bytes(8, "big")
+        for writer in list(self.clients):
+            if writer.is_closing():
+                self.clients.discard(writer)
+                continue
+            writer.write(header + payload)
+
+
+class Bridge:
+    """把数据源收到的字节解析为帧并推送"""
+
+    def __init__(self, hub: WebSocketHub, echo: bool) -> None:
+        self.parser = FrameParser()
+        self.hub = hub
+        self.echo = echo
+
+    def receive(self, data: bytes) -> None:
+        for seq, cmd, payload in self.parser.feed(data):
+            text = json.dumps(decode(seq, cmd, payload), ensure_ascii=False)
+            self.hub.broadcast(text)
+            if self.echo:
+                print(text, flush=True)
+
+
+class _UdpInput(asyncio.DatagramProtocol):
+    def __init__(self, bridge: Bridge) -> None:
+        self.bridge = bridge
+
+    def datagram_received(self, data: bytes, addr: tuple) -> None:
+        self.bridge.receive(data)
+
+
+async def read_udp(bridge: Bridge, host: str, port: int) -> None:
+    loop = asyncio.get_running_loop()
+    await loop.create_datagram_endpoint(lambda: _UdpInput(bridge), local_addr=(host, port))
+    await asyncio.Event().wait()
+
+
+async def read_tcp(bridge: Bridge, host: str, port: int) -> None:
+    """连接 TCP 串口服务器，连接失败或断开后每秒重试一次"""
+    while True:
+        try:
+            reader, writer = await asyncio.open_connection(host, port)
+        except OSError as error:
+            print(f"无法连接 {host}:{port}: {error}", file=sys.stderr)
+            await asyncio.sleep(1)
+            continue
+        try:
+            while data := await reader.read(4096):
+                bridge.receive(data)
+        except ConnectionError:
+            pass
+        writer.close()
+        await asyncio.sleep(1)
+
+
+async def read_serial(bridge: Bridge, path: str) -> None:
+    """串口设备按普通文件读取，阻塞的读操作放在线程中执行"""
+    fd = os.open(path, os.O_RDONLY | getattr(os, "O_NOCTTY", 0))
+    try:
+        while data := await asyncio.to_thread(os.read, fd, 4096):
+            bridge.receive(data)
+    finally:
+        os.close(fd)
+
+
+def _address(text: str) -> tuple[str, int]:
+    host, _, port = text.rpartition(":")
+    return host or "0.0.0.0", int(port)
+
+
+async def main() -> None:
+    parser = argparse.ArgumentParser(description="接收裁判系统帧并以 JSON 推送给 WebSocket 客户端")
+    source = parser.add_mutually_exclusive_group(required=True)
+    source.add_argument("--udp", metavar="HOST:PORT", help="在该地址接收 UDP 数据报")
+    source.add_argument("--tcp", metavar="HOST:PORT", help="连接该地址的 TCP 串口服务器")
+    source.add_argument("--serial", metavar="DEVICE", help="直接读取串口设备")
+    parser.add_argument("--ws", metavar="HOST:PORT", default="127.0.0.1:8765", help="WebSocket 监听地址")
+    parser.add_argument("--echo", action="store_true", help="同时把每条消息打印到标准输出")
+    args = parser.parse_args()
+
+    hub = WebSocketHub()
+    bridge = Bridge(hub, args.echo)
+    host, port = _address(args.ws)
+    server = await asyncio.start_server(hub.handle, host, port)
+    print(f"WebSocket: ws://{host}:{port}", file=sys.stderr)
+    async with server:
+        if args.udp:
+            await read_udp(bridge, *_address(args.udp))
+        elif args.tcp:
+            await read_tcp(bridge, *_address(args.tcp))
+        else:
+            await read_serial(bridge, args.serial)
+
+
+if __name__ == "__main__":
+    try:
+        asyncio.run(main())
+    except KeyboardInterrupt:
+        pass
+"#;
+
+/// 生成遥测桥脚本
+///
+/// 脚本按 `options.packet_includes` 中记录的路径（相对于脚本所在目录）加载各包的 Python 模块，
+/// 没有记录的包加载 `<包名>.py`。Python 后端无法生成的包会导致整个桥生成失败
+pub fn generate_python_bridge(
+    json_input: &str,
+    options: &GenerateOptions,
+) -> Result<String, MultiGenerateError> {
+    let parsed: ConfigOrArray = serde_json::from_str(json_input)?;
+    let packets = parsed.into_packets();
+    for packet in &packets {
+        generate_python(packet, options)?;
+    }
+
+    let mut out = reindent(BRIDGE_HEAD, options);
+    out.push_str("# 命令码到包类的映射\n");
+    out.push_str("PACKETS = {\n");
+    out.push_str(&format!("{}packet.CMD: packet\n", options.indent(1)));
+    out.push_str(&format!("{}for packet in (\n", options.indent(1)));
+    for packet in &packets {
+        let path = options
+            .packet_includes
+            .get(&packet.packet_name)
+            .cloned()
+            .unwrap_or_else(|| format!("{}.py", packet.packet_name));
+        out.push_str(&format!(
+            "{}_load({:?}, {:?}),\n",
+            options.indent(2),
+            packet.packet_name,
+            path
+        ));
+    }
+    out.push_str(&format!("{})\n", options.indent(1)));
+    out.push_str("}\n\n");
+    out.push_str(&reindent(BRIDGE_BODY, options));
+    Ok(out)
+}
+
+/// 模板按 4 个空格缩进书写，换成选项指定的缩进
+fn reindent(template: &str, options: &GenerateOptions) -> String {
+    let mut out = String::with_capacity(template.len());
+    for line in template.lines() {
+        let spaces = line.len() - line.trim_start_matches(' ').len();
+        out.push_str(&options.indent(spaces / 4));
+        out.push_str(&line[spaces - spaces % 4..]);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    const SET: &str = r#"[
+        { "packet_name": "Gimbal", "command_id": "0x0104", "namespace": null, "packed": true,
+          "header_guard": "RPL_GIMBAL_H", "comment": "云台",
+          "fields": [{ "name": "mode", "type": "uint8_t", "comment": "模式" },
+                     { "name": "yaw", "type": "float", "comment": "偏航" }] },
+        { "packet_name": "Heartbeat", "command_id": "0x0001", "namespace": null, "packed": true,
+          "header_guard": "RPL_HEARTBEAT_H", "comment": "心跳",
+          "fields": [{ "name": "counter", "type": "uint32_t", "comment": "计数" }] }
+    ]"#;
+
+    #[test]
+    fn test_generate_python_bridge() {
+        let options = GenerateOptions {
+            packet_includes: BTreeMap::from([(
+                "Heartbeat".to_string(),
+                "common/Heartbeat.py".to_string(),
+            )]),
+            ..GenerateOptions::default()
+        };
+        let out = generate_python_bridge(SET, &options).unwrap();
+        assert!(out.starts_with("# 由 rplc 生成，请勿手动修改\n"));
+        assert!(out.contains("        _load(\"Gimbal\", \"Gimbal.py\"),\n"));
+        assert!(out.contains("        _load(\"Heartbeat\", \"common/Heartbeat.py\"),\n"));
+        assert!(out.contains("def crc16(data: bytes, crc: int = 0xFFFF) -> int:\n"));
+
+        let tabs = GenerateOptions {
+            use_tabs: true,
+            ..GenerateOptions::default()
+        };
+        let out = generate_python_bridge(SET, &tabs).unwrap();
+        assert!(out.contains("\tdef feed(self, data: bytes) -> list[tuple[int, int, bytes]]:\n"));
+        assert!(out.contains("\t\t_load(\"Gimbal\", \"Gimbal.py\"),\n"));
+        assert!(!out.lines().any(|line| line.starts_with(' ')));
+    }
+
+    #[test]
+    fn test_generate_python_bridge_unsupported() {
+        let json = r#"{ "packet_name": "Flags", "command_id": "0x0002", "namespace": null,
+            "packed": true, "header_guard": "RPL_FLAGS_H",
+            "fields": [{ "name": "a", "type": "uint8_t", "bit_field": 1 }] }"#;
+        assert!(matches!(
+            generate_python_bridge(json, &GenerateOptions::default()),
+            Err(MultiGenerateError::GenerateError(_))
+        ));
+    }
+}
@@ -0,0 +1,559 @@
+//! 生成与 C++ 头文件布局一致的 C 头文件，供 bootloader 等 C 代码使用
+
+use crate::compiler::Packing;
+use crate::config::{Config, FieldGroup};
+use crate::fingerprint::{format_layout_hash, layout_hash};
+use crate::generator::{
+    BraceStyle, GenerateError, GenerateOptions, c_string_literal, case_literal, limit_literal,
+    packet_doc_block, push_fallback_typedefs, push_group_end, push_group_start,
+    push_integer_includes, push_section_banner,
+};
+use crate::layout::{compute_layout, with_reserved_gaps};
+use crate::target::Target;
+use crate::validator::{format_command_id, parse_array_type, parse_command_id};
+
+const BACKEND: &str = "C";
+
+/// C 头文件：`typedef` 的结构体 `<包名>_t` 与 `<包名>_CMD` / `<包名>_SIZE` / `<包名>_LAYOUT_HASH` 宏
+///
+/// 结构体名带 `_t` 后缀，与 C++ 头文件同时包含时不会重复定义；C 没有命名空间，`namespace` 被忽略。
+/// 紧凑结构体使用 `#pragma pack`，不依赖 GNU 扩展；ARMCC 5 仍按 `--compiler` 使用 `__packed` 或其专用的 pragma
+pub(crate) fn generate_c(
+    config: &Config,
+    options: &GenerateOptions,
+) -> Result<String, GenerateError> {
+    let config = &with_reserved_gaps(config);
+    for field in &config.fields {
+        let (base_type, _) = parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+        if config.dependency(base_type).is_some() {
+            return Err(unsupported(format!(
+                "字段 '{}' 引用了包 '{}'，引用其他包的字段只支持 C++",
+                field.name, base_type
+            )));
+        }
+        Target::C
+            .map_type(base_type)
+            .map_err(|reason| unsupported(format!("字段 '{}': {}", field.name, reason)))?;
+    }
+    let cmd_id =
+        parse_command_id(&config.command_id).map_err(|_| GenerateError::ValidationError)?;
+    let name = &config.packet_name;
+    let prefix = upper_snake(name);
+    let guard = format!("RPL_{}_H", name.to_uppercase());
+    let type_name = format!("{}_t", name);
+    let layout = compute_layout(config).ok_or(GenerateError::ValidationError)?;
+
+    let mut out = String::new();
+    out.push_str(&format!("#ifndef {}\n", guard));
+    out.push_str(&format!("#define {}\n\n", guard));
+    push_integer_includes(&mut out, config.includes.as_deref(), "stdint.h", options);
+    if config.fields.iter().any(|f| f.ty.starts_with("bool")) {
+        out.push_str("#include <stdbool.h>\n");
+    }
+    out.push('\n');
+    push_fallback_typedefs(
+        &mut out,
+        config.includes.as_deref(),
+        "_Static_assert",
+        options,
+    );
+    out.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+
+    out.push_str(&format!(
+        "#define {}_CMD {}\n",
+        prefix,
+        format_command_id(cmd_id)
+    ));
+    // 含位域时按 BitLayout 位流计算大小，与 C++ 的 PacketTraits::size 一致
+    if layout.bitstream {
+        out.push_str(&format!("#define {}_SIZE {}\n", prefix, layout.size));
+    } else {
+        out.push_str(&format!("#define {}_SIZE sizeof({})\n", prefix, type_name));
+    }
+    out.push_str(&format!(
+        "#define {}_LAYOUT_HASH {}u\n",
+        prefix,
+        format_layout_hash(layout_hash(config))
+    ));
+    // 范围标注：GIMBAL_COMMAND_YAW_MAX 等
+    for field in &config.fields {
+        let (base_type, _) = parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+        for (key, value) in field.limits() {
+            let literal = limit_literal(base_type, value);
+            let literal = if literal.starts_with('-') {
+                format!("({})", literal)
+            } else {
+                literal
+            };
+            out.push_str(&format!(
+                "#define {}_{}_{} {}\n",
+                prefix,
+                field.name.to_uppercase(),
+                key.to_uppercase(),
+                literal
+            ));
+        }
+    }
+    out.push('\n');
+    push_enum_constants(&mut out, config, &prefix);
+
+    // `#pragma pack(push, 1)` 被 GCC、Clang、armclang、IAR 与 MSVC 共同支持，位域的排布与 packed 属性相同
+    let packing = config.packed.then(|| match options.compiler.packing() {
+        Packing::Attribute => Packing::Pragma,
+        packing => packing,
+    });
+    match packing {
+        Some(Packing::Pragma) => out.push_str("#pragma pack(push, 1)\n"),
+        Some(Packing::ArmccPragma) => out.push_str("#pragma push\n#pragma pack(1)\n"),
+        _ => {}
+    }
+    out.push_str(&packet_doc_block(config, cmd_id));
+    let qualifier = match packing {
+        Some(Packing::Attribute) => "struct __attribute__((packed))",
+        Some(Packing::Keyword) => "__packed struct",
+        _ => "struct",
+    };
+    out.push_str(&format!("typedef {}", qualifier));
+    out.push_str(match options.brace_style {
+        BraceStyle::NextLine => "\n{\n",
+        BraceStyle::SameLine => " {\n",
+    });
+
+    let indent = options.indent(1);
+    let member_indent = options.indent(2);
+    let mut open_group: Option<&FieldGroup> = None;
+    for (index, field) in config.fields.iter().enumerate() {
+        if open_group.map(|g| g.id) != field.group.as_ref().map(|g| g.id) {
+            if let Some(group) = open_group.take() {
+                push_group_end(&mut out, group, &indent);
+            }
+            push_section_banner(&mut out, field, &indent);
+            if let Some(group) = &field.group {
+                push_group_start(&mut out, group, packing, &indent, options);
+                open_group = Some(group);
+            }
+        } else {
+            push_section_banner(&mut out, field, &indent);
+        }
+        let (base_type, array_len) =
+            parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+        let mut decl = String::new();
+        // C 的 _Alignas 不能修饰结构体类型，放在首个成员上可以得到相同的对齐与尾部填充
+        if index == 0
+            && let Some(align) = config.align
+        {
+            if field.bit_field.is_some() {
+                return Err(GenerateError::Unsupported {
+                    backend: BACKEND,
+                    reason: format!(
+                        "首个字段 '{}' 是位域，无法用 _Alignas 实现 align",
+                        field.name
+                    ),
+                });
+            }
+            decl.push_str(&format!("_Alignas({}) ", align));
+        }
+        decl.push_str(&format!("{} {}", base_type, field.name));
+        match (array_len, field.bit_field) {
+            (Some(len), _) => decl.push_str(&format!("[{}]", len)),
+            (None, Some(bits)) => decl.push_str(&format!(" : {}", bits)),
+            (None, None) => {}
+        }
+        decl.push(';');
+        let indent = if open_group.is_some() {
+            &member_indent
+        } else {
+            &indent
+        };
+        match &field.comment {
+            Some(comment) => out.push_str(&format!("{}{} ///< {}\n", indent, decl, comment)),
+            None => out.push_str(&format!("{}{}\n", indent, decl)),
+        }
+    }
+    if let Some(group) = open_group {
+        push_group_end(&mut out, group, &indent);
+    }
+    out.push_str(&format!("}} {};\n", type_name));
+    match packing {
+        Some(Packing::Pragma) => out.push_str("#pragma pack(pop)\n"),
+        Some(Packing::ArmccPragma) => out.push_str("#pragma pop\n"),
+        _ => {}
+    }
+    push_value_lookups(&mut out, config, &prefix.to_lowercase(), options)?;
+
+    out.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n");
+    out.push_str(&format!("#endif // {}\n", guard));
+    Ok(out)
+}
+
+/// 具名枚举写成 `<包名>_<枚举名>_<枚举项>` 宏：C 的枚举类型总是 int 宽度，成员仍使用底层类型
+fn push_enum_constants(out: &mut String, config: &Config, prefix: &str) {
+    for def in &config.enums {
+        match &def.comment {
+            Some(comment) => out.push_str(&format!("/** {}：{} */\n", def.name, comment)),
+            None => out.push_str(&format!("/** {} */\n", def.name)),
+        }
+        for item in &def.values {
+            let literal = item.value.to_string();
+            let literal = if literal.starts_with('-') {
+                format!("({})", literal)
+            } else {
+                literal
+            };
+            out.push_str(&format!(
+                "#define {}_{}_{} {}",
+                prefix,
+                upper_snake(&def.name),
+                item.name.to_uppercase(),
+                literal
+            ));
+            match &item.comment {
+                Some(comment) => out.push_str(&format!(" ///< {}\n", comment)),
+                None => out.push('\n'),
+            }
+        }
+        out.push('\n');
+    }
+}
+
+/// 取值显示名称的查找函数 `<包名>_<字段>_name`，未列出的值返回空字符串
+fn push_value_lookups(
+    out: &mut String,
+    config: &Config,
+    prefix: &str,
+    options: &GenerateOptions,
+) -> Result<(), GenerateError> {
+    let i1 = options.indent(1);
+    for field in &config.fields {
+        let names = field.value_names();
+        if names.is_empty() {
+            continue;
+        }
+        let (base_type, _) = parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+        out.push_str(&format!(
+            "\n/** {} 的显示名称，未列出的值返回空字符串 */\nstatic inline const char *{}_{}_name({} value)",
+            field.name, prefix, field.name, base_type
+        ));
+        match options.brace_style {
+            BraceStyle::NextLine => out.push_str(&format!("\n{{\n{i1}switch (value)\n{i1}{{\n")),
+            BraceStyle::SameLine => out.push_str(&format!(" {{\n{i1}switch (value) {{\n")),
+        }
+        for (value, name) in names {
+            out.push_str(&format!(
+                "{i1}case {}: return {};\n",
+                case_literal(value),
+                c_string_literal(name.as_str())
+            ));
+        }
+        out.push_str(&format!("{i1}default: return \"\";\n{i1}}}\n}}\n"));
+    }
+    Ok(())
+}
+
+/// GimbalCommand → GIMBAL_COMMAND，用作宏名前缀
+pub(crate) fn upper_snake(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    let mut prev: Option<char> = None;
+    for c in name.chars() {
+        if c.is_ascii_uppercase()
+            && prev.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+        {
+            out.push('_');
+        }
+        out.push(c.to_ascii_uppercase());
+        prev = Some(c);
+    }
+    out
+}
+
+fn unsupported(reason: String) -> GenerateError {
+    GenerateError::Unsupported {
+        backend: BACKEND,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::Compiler;
+
+    fn generate(json: &str, options: &GenerateOptions) -> Result<String, GenerateError> {
+        let config: Config = serde_json::from_str(json).unwrap();
+        generate_c(&config, options)
+    }
+
+    #[test]
+    fn test_upper_snake() {
+        assert_eq!(upper_snake("GimbalCommand"), "GIMBAL_COMMAND");
+        assert_eq!(upper_snake("IMUData2"), "IMUDATA2");
+        assert_eq!(upper_snake("robot_status"), "ROBOT_STATUS");
+        assert_eq!(upper_snake("Motor3Feedback"), "MOTOR3_FEEDBACK");
+    }
+
+    #[test]
+    fn test_generate_c_header() {
+        let code = generate(
+            r#"{"packet_name":"GimbalCommand","command_id":"260","comment":"云台控制",
+            "namespace":"Robot","fields":[
+                {"name":"yaw","type":"float","comment":"偏航角"},
+                {"name":"gains","type":"int16_t[3]"},
+                {"name":"enabled","type":"bool"}
+            ]}"#,
+            &GenerateOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            code,
+            r#"#ifndef RPL_GIMBALCOMMAND_H
+#define RPL_GIMBALCOMMAND_H
+
+#include <stdint.h>
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define GIMBAL_COMMAND_CMD 0x0104
+#define GIMBAL_COMMAND_SIZE sizeof(GimbalCommand_t)
+#define GIMBAL_COMMAND_LAYOUT_HASH 0x3EE185C5u
+
+#pragma pack(push, 1)
+/**
+ * @brief 云台控制
+ * @details cmd = 0x0104, size = 11 bytes
+ */
+typedef struct
+{
+    float yaw; ///< 偏航角
+    int16_t gains[3];
+    bool enabled;
+} GimbalCommand_t;
+#pragma pack(pop)
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // RPL_GIMBALCOMMAND_H
+"#
+        );
+    }
+
+    #[test]
+    fn test_generate_c_bit_fields_and_align() {
+        let bit_fields = r#"{"packet_name":"Flags","command_id":"1","fields":[
+            {"name":"mode","type":"uint8_t","bit_field":3},
+            {"name":"level","type":"uint16_t","bit_field":9}]}"#;
+        let code = generate(bit_fields, &GenerateOptions::default()).unwrap();
+        assert!(code.contains("#define FLAGS_SIZE 2\n"));
+        assert!(code.contains("    uint8_t mode : 3;\n    uint16_t level : 9;\n"));
+
+        let aligned = r#"{"packet_name":"A","command_id":"1","packed":false,"align":8,
+            "fields":[{"name":"x","type":"uint32_t"}]}"#;
+        let code = generate(aligned, &GenerateOptions::default()).unwrap();
+        assert!(code.contains("typedef struct\n{\n    _Alignas(8) uint32_t x;\n} A_t;\n"));
+
+        let aligned_bit_field = r#"{"packet_name":"A","command_id":"1","align":8,
+            "fields":[{"name":"x","type":"uint32_t","bit_field":3}]}"#;
+        assert!(matches!(
+            generate(aligned_bit_field, &GenerateOptions::default()),
+            Err(GenerateError::Unsupported { backend: "C", .. })
+        ));
+    }
+
+    #[test]
+    fn test_generate_c_limit_macros() {
+        let json = r#"{"packet_name":"GimbalCommand","command_id":"1","fields":[
+            {"name":"yaw","type":"float","min":-3.14,"max":3.14},
+            {"name":"speed","type":"int16_t","min":-300,"typical":0}]}"#;
+        let code = generate(json, &GenerateOptions::default()).unwrap();
+        assert!(code.contains(
+            "#define GIMBAL_COMMAND_YAW_MIN (-3.14f)
+#define GIMBAL_COMMAND_YAW_MAX 3.14f
+#define GIMBAL_COMMAND_SPEED_MIN (-300)
+#define GIMBAL_COMMAND_SPEED_TYPICAL 0
+
+"
+        ));
+    }
+
+    #[test]
+    fn test_generate_c_value_lookups() {
+        let json = r#"{"packet_name":"GameStatus","command_id":"1","fields":[
+            {"name":"stage","type":"int8_t","values":{"-1":"未知","1":"准备"}}]}"#;
+        let code = generate(json, &GenerateOptions::default()).unwrap();
+        assert!(code.contains(
+            "} GameStatus_t;
+#pragma pack(pop)
+
+/** stage 的显示名称，未列出的值返回空字符串 */
+static inline const char *game_status_stage_name(int8_t value)
+{
+    switch (value)
+    {
+    case -1: return \"未知\";
+    case 1: return \"准备\";
+    default: return \"\";
+    }
+}
+"
+        ));
+    }
+
+    #[test]
+    fn test_generate_c_enums() {
+        let json = r#"{"packet_name":"ChassisState","command_id":"1",
+            "enums":[{"name":"DriveMode","type":"int8_t","comment":"底盘模式","values":[
+                {"name":"idle","value":0,"comment":"空闲"},{"name":"reverse","value":-1}]}],
+            "fields":[{"name":"mode","type":"DriveMode","comment":"当前模式"},
+                {"name":"history","type":"DriveMode[2]"}]}"#;
+        let code = generate(json, &GenerateOptions::default()).unwrap();
+        assert!(code.contains(
+            "/** DriveMode：底盘模式 */
+#define CHASSIS_STATE_DRIVE_MODE_IDLE 0 ///< 空闲
+#define CHASSIS_STATE_DRIVE_MODE_REVERSE (-1)
+
+"
+        ));
+        // 成员保持底层类型，大小与 C++ 的 enum class 相同
+        assert!(code.contains("    int8_t mode; ///< 当前模式\n    int8_t history[2];\n"));
+    }
+
+    #[test]
+    fn test_generate_c_integer_type_includes() {
+        let json = r#"{"packet_name":"A","command_id":"1","includes":["board_types.h"],
+            "fields":[{"name":"x","type":"uint8_t"}]}"#;
+        let code = generate(json, &GenerateOptions::default()).unwrap();
+        assert!(
+            code.contains("#define RPL_A_H\n\n#include \"board_types.h\"\n\n#ifdef __cplusplus")
+        );
+
+        let options = GenerateOptions {
+            freestanding: true,
+            ..Default::default()
+        };
+        let plain = json.replace(r#""includes":["board_types.h"],"#, "");
+        let code = generate(&plain, &options).unwrap();
+        assert!(!code.contains("<stdint.h>"));
+        assert!(code.contains("_Static_assert(sizeof(int64_t) == 8, "));
+        assert!(code.contains("#endif // RPLC_FIXED_WIDTH_TYPES\n\n#ifdef __cplusplus"));
+    }
+
+    #[test]
+    fn test_generate_c_follows_compiler_profile() {
+        let json =
+            r#"{"packet_name":"A","command_id":"1","fields":[{"name":"x","type":"uint8_t"}]}"#;
+        let generate_for = |compiler| {
+            let options = GenerateOptions {
+                compiler,
+                ..Default::default()
+            };
+            generate(json, &options).unwrap()
+        };
+        for compiler in [
+            Compiler::Gcc,
+            Compiler::Armclang,
+            Compiler::Iar,
+            Compiler::Msvc,
+        ] {
+            let code = generate_for(compiler);
+            assert!(code.contains("#pragma pack(push, 1)\n/**"));
+            assert!(code.contains("typedef struct\n{"));
+            assert!(code.contains("} A_t;\n#pragma pack(pop)\n"));
+            assert!(!code.contains("__attribute__"));
+        }
+        assert!(generate_for(Compiler::Armcc).contains("typedef __packed struct\n"));
+    }
+
+    #[test]
+    fn test_generate_c_unsupported() {
+        let mut configs: Vec<Config> = serde_json::from_str(
+            r#"[
+                {"packet_name":"Pose","command_id":"1","fields":[{"name":"x","type":"float"}]},
+                {"packet_name":"Cmd","command_id":"2","fields":[{"name":"pose","type":"Pose"}]}
+            ]"#,
+        )
+        .unwrap();
+        crate::config::attach_dependencies(&mut configs);
+        let err = generate_c(&configs[1], &GenerateOptions::default()).unwrap_err();
+        assert!(
+            matches!(&err, GenerateError::Unsupported { backend: "C", reason } if reason.contains("'Pose'")),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_generate_c_is_valid_c99() {
+        use std::process::Command;
+
+        // 没有 C 编译器的环境只检查生成本身
+        if Command::new("cc").arg("--version").output().is_err() {
+            eprintln!("未找到 cc，跳过 C99 编译检查");
+            return;
+        }
+        let packets = [
+            r#"{"packet_name":"GimbalCommand","command_id":"0x0104","comment":"云台控制",
+                "fields":[
+                    {"name":"yaw","type":"float","comment":"偏航角","min":-3.14,"max":3.14},
+                    {"name":"gains","type":"int16_t[3]"},
+                    {"name":"enabled","type":"bool"}]}"#,
+            r#"{"packet_name":"ChassisState","command_id":"0x0107",
+                "enums":[{"name":"DriveMode","type":"int8_t","values":[
+                    {"name":"idle","value":0},{"name":"reverse","value":-1}]}],
+                "fields":[{"name":"mode","type":"DriveMode"}]}"#,
+            r#"{"packet_name":"Flags","command_id":"0x0105","fields":[
+                {"name":"mode","type":"uint8_t","bit_field":3},
+                {"name":"level","type":"uint16_t","bit_field":9}]}"#,
+            r#"{"packet_name":"GameStatus","command_id":"0x0106","fields":[
+                {"section":"比赛"},
+                {"name":"stage","type":"int8_t","values":{"-1":"未知","1":"准备"}},
+                {"group":[{"name":"x","type":"int16_t"},{"name":"y","type":"int16_t"}],
+                 "name":"pos","comment":"位置"},
+                {"reserved":2}]}"#,
+        ];
+        let dir = std::env::temp_dir().join(format!("rplc_c99_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut main = String::new();
+        for json in packets {
+            let config: Config = serde_json::from_str(json).unwrap();
+            let header = format!("{}.h", config.packet_name);
+            let code = generate_c(&config, &GenerateOptions::default()).unwrap();
+            std::fs::write(dir.join(&header), code).unwrap();
+            // 同一个头文件包含两次，检查头文件保护
+            main.push_str(&format!("#include \"{0}\"\n#include \"{0}\"\n", header));
+        }
+        // 紧凑布局的大小与宏一致，不成立时数组长度为负，编译失败
+        main.push_str(
+            "typedef char gimbal_size[(sizeof(GimbalCommand_t) == 11) ? 1 : -1];
+typedef char gimbal_cmd[(GIMBAL_COMMAND_CMD == 0x0104 && GIMBAL_COMMAND_SIZE == 11) ? 1 : -1];
+typedef char flags_size[(FLAGS_SIZE == 2) ? 1 : -1];
+typedef char game_size[(sizeof(GameStatus_t) == 7) ? 1 : -1];
+typedef char drive_mode[(CHASSIS_STATE_DRIVE_MODE_REVERSE == -1) ? 1 : -1];
+const char *stage_name(void) { return game_status_stage_name(-1); }
+",
+        );
+        std::fs::write(dir.join("main.c"), main).unwrap();
+
+        let output = Command::new("cc")
+            .current_dir(&dir)
+            .args([
+                "-std=c99",
+                "-pedantic-errors",
+                "-Wall",
+                "-Werror",
+                "-fsyntax-only",
+                "main.c",
+            ])
+            .output()
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
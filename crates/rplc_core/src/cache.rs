@@ -0,0 +1,194 @@
+//! 按内容哈希缓存的校验结果
+//!
+//! 监视模式与编辑器集成会在每次修改后重新校验整个文档，而大型多包文档中通常只有一个包发生了变化
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use tracing::debug;
+
+use crate::diagnostics::RplcDiagnostic;
+use crate::validator::{
+    PacketSource, ValidateOptions, check_input_size, validate_multiple_with, validate_packet,
+};
+
+/// (包内容与继承的格式版本的哈希, 校验选项的哈希)
+type CacheKey = (u64, u64);
+
+/// 缓存的累计命中情况
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// 直接复用缓存结果的包数
+    pub hits: usize,
+    /// 重新校验的包数
+    pub misses: usize,
+}
+
+/// 校验结果缓存，以包内容与校验选项的哈希为键
+///
+/// 每次校验后只保留本次用到的项，缓存大小不会随编辑次数增长
+#[derive(Debug, Default)]
+pub struct ValidationCache {
+    entries: HashMap<CacheKey, Vec<RplcDiagnostic>>,
+    stats: CacheStats,
+}
+
+impl ValidationCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 与 `validate_with_options` 相同，整个文档作为一项缓存
+    pub fn validate(&mut self, json_input: &str, options: &ValidateOptions) -> Vec<RplcDiagnostic> {
+        if let Some(diag) = check_input_size(json_input, options.max_input_size) {
+            return vec![diag];
+        }
+        self.run(options, |validate_packet| {
+            validate_packet(PacketSource {
+                text: json_input,
+                start: 0,
+                inherited_schema: None,
+            })
+        })
+    }
+
+    /// 与 `validate_multiple_with_options` 相同，每个包分别缓存
+    pub fn validate_multiple(
+        &mut self,
+        json_input: &str,
+        options: &ValidateOptions,
+    ) -> Vec<RplcDiagnostic> {
+        if let Some(diag) = check_input_size(json_input, options.max_input_size) {
+            return vec![diag];
+        }
+        self.run(options, |validate_packet| {
+            validate_multiple_with(json_input, validate_packet)
+        })
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn run(
+        &mut self,
+        options: &ValidateOptions,
+        validate_document: impl FnOnce(
+            &mut dyn FnMut(PacketSource) -> Vec<RplcDiagnostic>,
+        ) -> Vec<RplcDiagnostic>,
+    ) -> Vec<RplcDiagnostic> {
+        let options_hash = hash_of(options);
+        let mut previous = std::mem::take(&mut self.entries);
+        let mut current = HashMap::new();
+        let mut run = CacheStats::default();
+
+        let diags = validate_document(&mut |packet| {
+            let key = (
+                hash_of(&(packet.text, packet.inherited_schema)),
+                options_hash,
+            );
+            let cached = current.get(&key).cloned().or_else(|| previous.remove(&key));
+            let diags = match cached {
+                Some(diags) => {
+                    run.hits += 1;
+                    diags
+                }
+                None => {
+                    run.misses += 1;
+                    validate_packet(packet.text, options, packet.inherited_schema)
+                }
+            };
+            current.insert(key, diags.clone());
+            diags
+        });
+
+        debug!(hits = run.hits, misses = run.misses, "校验缓存");
+        self.entries = current;
+        self.stats.hits += run.hits;
+        self.stats.misses += run.misses;
+        diags
+    }
+}
+
+fn hash_of(value: &(impl Hash + ?Sized)) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::{validate_multiple_with_options, validate_with_options};
+
+    fn packet(name: &str, cmd: &str, ty: &str) -> String {
+        format!(
+            r#"{{"packet_name":"{}","command_id":"{}","fields":[{{"name":"a","type":"{}"}}]}}"#,
+            name, cmd, ty
+        )
+    }
+
+    fn document(packets: &[String]) -> String {
+        format!(
+            r#"{{"protocol_version":"1.0.0","packets":[{}]}}"#,
+            packets.join(",")
+        )
+    }
+
+    #[test]
+    fn test_cache_reuses_unchanged_packets() {
+        let options = ValidateOptions::default();
+        let mut packets: Vec<String> = (1..=4)
+            .map(|i| packet(&format!("P{}", i), &format!("0x010{}", i), "uint8_t"))
+            .collect();
+        let mut cache = ValidationCache::new();
+
+        let json = document(&packets);
+        assert_eq!(
+            cache.validate_multiple(&json, &options),
+            validate_multiple_with_options(&json, &options)
+        );
+        assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 4 });
+
+        // 只修改一个包，其余三个直接复用
+        packets[2] = packet("P3", "0x0103", "uint42_t");
+        let json = document(&packets);
+        let diags = cache.validate_multiple(&json, &options);
+        assert_eq!(diags, validate_multiple_with_options(&json, &options));
+        assert!(!diags.is_empty());
+        assert_eq!(cache.stats(), CacheStats { hits: 3, misses: 5 });
+
+        // 旧版本的包不再保留
+        assert_eq!(cache.entries.len(), 4);
+    }
+
+    #[test]
+    fn test_cache_keyed_by_options() {
+        let json = packet("P1", "0x0101", "char");
+        let cpp = ValidateOptions::default();
+        let mut cache = ValidationCache::new();
+
+        let expected = validate_with_options(&json, &cpp);
+        assert_eq!(cache.validate(&json, &cpp), expected);
+        assert_eq!(cache.validate(&json, &cpp), expected);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+
+        let rust = ValidateOptions {
+            targets: vec![crate::Target::Cpp, crate::Target::Rust],
+            ..ValidateOptions::default()
+        };
+        assert_eq!(
+            cache.validate(&json, &rust),
+            validate_with_options(&json, &rust)
+        );
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+
+        cache.clear();
+        cache.validate(&json, &rust);
+        assert_eq!(cache.stats().misses, 3);
+    }
+}
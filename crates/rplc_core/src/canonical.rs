@@ -0,0 +1,104 @@
+//! 包定义的规范形式：写法不同但含义相同的定义规范化后完全相等
+
+use crate::config::Config;
+use crate::validator::{canonical_command_id, parse_array_type};
+
+/// 与更常见的写法等价的类型别名
+///
+/// `signed char` 与 `char` 不等价（`char` 的符号由编译器决定），因此不在其中
+const TYPE_ALIASES: &[(&str, &str)] = &[
+    ("_Bool", "bool"),
+    ("signed int", "int"),
+    ("signed short", "short"),
+    ("signed long", "long"),
+    ("signed long long", "long long"),
+];
+
+/// 将包定义转换为规范形式，供格式化、比较与哈希使用：
+/// - 命令 ID 改写为 `0x0104` 形式
+/// - 未指定的头文件保护宏填入生成时使用的默认值
+/// - 类型别名替换为规范写法，命名空间去掉首尾空白，空命名空间视为未指定
+///
+/// 无法解析的值保持原样，交给 `validate` 报告
+pub fn canonicalize(mut config: Config) -> Config {
+    if let Some(id) = canonical_command_id(&config.command_id) {
+        config.command_id = id;
+    }
+    if config.header_guard.is_none() {
+        config.header_guard = Some(default_header_guard(&config.packet_name));
+    }
+    config.namespace = config
+        .namespace
+        .map(|ns| ns.trim().to_string())
+        .filter(|ns| !ns.is_empty());
+    for field in &mut config.fields {
+        field.ty = canonical_type(&field.ty);
+    }
+    config
+}
+
+/// 生成的头文件默认使用的保护宏
+pub(crate) fn default_header_guard(packet_name: &str) -> String {
+    format!("RPL_{}_HPP", packet_name.to_uppercase())
+}
+
+/// 类型的规范写法，数组类型保留长度只替换元素类型
+pub(crate) fn canonical_type(ty: &str) -> String {
+    let resolve = |base: &str| {
+        TYPE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == base)
+            .map_or(base, |(_, canonical)| canonical)
+            .to_string()
+    };
+    match parse_array_type(ty.trim()) {
+        Some((base, Some(len))) => format!("{}[{}]", resolve(base), len),
+        Some((base, None)) => resolve(base),
+        None => ty.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Config {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_canonicalize_equivalent_definitions() {
+        let a = parse(
+            r#"{"packet_name":"Status","command_id":"257","namespace":" Robot ","fields":[
+                {"name":"a","type":"signed int"},{"name":"b","type":"_Bool[2]","bit_field":null}]}"#,
+        );
+        let b = parse(
+            r#"{"packet_name":"Status","command_id":"0x01_01","namespace":"Robot",
+                "header_guard":"RPL_STATUS_HPP","fields":[
+                {"name":"a","type":"int"},{"name":"b","type":"bool[2]"}]}"#,
+        );
+        assert_ne!(a, b);
+
+        let a = canonicalize(a);
+        assert_eq!(a, canonicalize(b));
+        assert_eq!(a.command_id, "0x0101");
+        assert_eq!(a.header_guard.as_deref(), Some("RPL_STATUS_HPP"));
+        assert_eq!(a.namespace.as_deref(), Some("Robot"));
+        assert_eq!(a.fields[1].ty, "bool[2]");
+
+        // 规范化是幂等的
+        assert_eq!(canonicalize(a.clone()), a);
+    }
+
+    #[test]
+    fn test_canonicalize_keeps_invalid_values() {
+        let config = canonicalize(parse(
+            r#"{"packet_name":"Status","command_id":"oops","namespace":"  ","fields":[
+                {"name":"a","type":"signed char"},{"name":"b","type":"uint8_t[]"}]}"#,
+        ));
+        assert_eq!(config.command_id, "oops");
+        assert_eq!(config.namespace, None);
+        assert_eq!(config.fields[0].ty, "signed char");
+        assert_eq!(config.fields[1].ty, "uint8_t[]");
+    }
+}
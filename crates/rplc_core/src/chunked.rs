@@ -0,0 +1,178 @@
+//! 分批进行的多包校验
+//!
+//! 浏览器中一次校验很大的文档会长时间占用主线程，分批校验让调用方可以在两批之间让出控制权并显示已有的诊断
+
+use crate::diagnostics::RplcDiagnostic;
+use crate::validator::{
+    PacketDocument, ValidateOptions, check_input_size, cross_packet_diagnostics, validate_packet,
+};
+
+/// 一批校验结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosticBatch {
+    /// 本批新产生的诊断，位置相对于整个文档
+    pub diagnostics: Vec<RplcDiagnostic>,
+    /// 到本批为止已校验的包数
+    pub validated: usize,
+    /// 文档中的包数
+    pub total: usize,
+}
+
+/// 分批校验多包文档，每批最多校验 `chunk_size` 个包
+///
+/// 所有批次的诊断按顺序拼接后与 `validate_multiple_with_options` 的结果相同：
+/// 第一批带有重复键与包集合头部的诊断，跨包检查的诊断在最后一批中给出。
+/// 文档中没有包时也会产生一批
+#[derive(Debug)]
+pub struct ChunkedValidation {
+    json_input: String,
+    options: ValidateOptions,
+    document: PacketDocument,
+    chunk_size: usize,
+    validated: usize,
+    finished: bool,
+}
+
+impl ChunkedValidation {
+    /// `chunk_size` 为 0 时按 1 处理
+    pub fn new(json_input: impl Into<String>, options: ValidateOptions, chunk_size: usize) -> Self {
+        let json_input = json_input.into();
+        // 超过大小上限的文档不拆分，唯一的一批只有大小错误
+        let document = match check_input_size(&json_input, options.max_input_size) {
+            Some(diag) => PacketDocument {
+                preamble: vec![diag],
+                ..PacketDocument::default()
+            },
+            None => PacketDocument::split(&json_input),
+        };
+        Self {
+            json_input,
+            options,
+            document,
+            chunk_size: chunk_size.max(1),
+            validated: 0,
+            finished: false,
+        }
+    }
+
+    /// 文档中的包数
+    pub fn total(&self) -> usize {
+        self.document.packets.len()
+    }
+}
+
+impl Iterator for ChunkedValidation {
+    type Item = DiagnosticBatch;
+
+    fn next(&mut self) -> Option<DiagnosticBatch> {
+        if self.finished {
+            return None;
+        }
+        let mut diagnostics = if self.validated == 0 {
+            std::mem::take(&mut self.document.preamble)
+        } else {
+            Vec::new()
+        };
+
+        let end = (self.validated + self.chunk_size).min(self.total());
+        let options = &self.options;
+        for index in self.validated..end {
+            diagnostics.extend(
+                self.document
+                    .validate(&self.json_input, index, &mut |packet| {
+                        validate_packet(packet.text, options, packet.inherited_schema)
+                    }),
+            );
+        }
+        self.validated = end;
+
+        if self.validated == self.total() {
+            self.finished = true;
+            diagnostics.extend(cross_packet_diagnostics(&self.document.identities));
+        }
+        Some(DiagnosticBatch {
+            diagnostics,
+            validated: self.validated,
+            total: self.total(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::validate_multiple_with_options;
+
+    fn document(count: usize) -> String {
+        let packets: Vec<String> = (1..=count)
+            .map(|i| {
+                // 第 2 个包的类型错误，第 5 个包与第 1 个包命令 ID 重复
+                let ty = if i == 2 { "uint42_t" } else { "uint8_t" };
+                let cmd = if i == 5 { 1 } else { i };
+                format!(
+                    r#"{{"packet_name":"P{}","command_id":"0x{:04X}","comment":"c","fields":[{{"name":"a","type":"{}","comment":"c"}}]}}"#,
+                    i, cmd, ty
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"protocol_version":"1.0.0","protocol_version":"1.0.1","packets":[{}]}}"#,
+            packets.join(",")
+        )
+    }
+
+    #[test]
+    fn test_chunked_matches_full_validation() {
+        let json = document(5);
+        let options = ValidateOptions::default();
+        let batches: Vec<_> = ChunkedValidation::new(json.as_str(), options.clone(), 2).collect();
+
+        assert_eq!(
+            batches
+                .iter()
+                .map(|b| (b.validated, b.total))
+                .collect::<Vec<_>>(),
+            vec![(2, 5), (4, 5), (5, 5)]
+        );
+        // 重复键在第一批，跨包检查在最后一批
+        assert!(!batches[0].diagnostics.is_empty());
+        assert!(batches[1].diagnostics.is_empty());
+        assert!(!batches[2].diagnostics.is_empty());
+
+        let all: Vec<_> = batches.into_iter().flat_map(|b| b.diagnostics).collect();
+        assert_eq!(all, validate_multiple_with_options(&json, &options));
+    }
+
+    #[test]
+    fn test_chunked_edge_cases() {
+        let options = ValidateOptions::default();
+        for json in [
+            "[]",
+            "{",
+            r#"{"packet_name":"A","command_id":"0x0101","fields":[]}"#,
+        ] {
+            let batches: Vec<_> = ChunkedValidation::new(json, options.clone(), 0).collect();
+            assert_eq!(batches.len(), 1, "{}", json);
+            assert_eq!(
+                batches[0].diagnostics,
+                validate_multiple_with_options(json, &options)
+            );
+        }
+    }
+
+    #[test]
+    fn test_chunked_input_too_large() {
+        let json = document(5);
+        let options = ValidateOptions {
+            max_input_size: Some(100),
+            ..Default::default()
+        };
+        let batches: Vec<_> = ChunkedValidation::new(json.as_str(), options.clone(), 2).collect();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].total, 0);
+        assert_eq!(
+            batches[0].diagnostics,
+            validate_multiple_with_options(&json, &options)
+        );
+    }
+}
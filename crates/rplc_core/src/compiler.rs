@@ -0,0 +1,158 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::config::BitOrder;
+
+/// 生成代码的目标编译器，决定紧凑结构体的写法与位域相关的检查
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash, Default)]
+pub enum Compiler {
+    /// GCC 与 Clang（默认）
+    #[default]
+    Gcc,
+    /// Arm Compiler 6（基于 Clang）
+    Armclang,
+    /// IAR Embedded Workbench
+    Iar,
+    /// Microsoft Visual C++
+    Msvc,
+    /// Keil MDK 中的 ARM Compiler 5，使用 `__packed` 关键字
+    Armcc,
+    /// ARM Compiler 5，使用 `#pragma push` / `#pragma pack(1)` / `#pragma pop`
+    ArmccPragma,
+}
+
+/// 紧凑结构体的实现方式，头文件模板中为 `attribute`、`pragma` 等小写名称
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Packing {
+    /// 结构体之后的 `__attribute__((packed))`
+    Attribute,
+    /// 结构体前后的 `#pragma pack(push, 1)` / `#pragma pack(pop)`
+    Pragma,
+    /// 结构体声明前的 `__packed` 关键字
+    Keyword,
+    /// ARMCC 的 `#pragma push` 与 `#pragma pack(1)`，之后用 `#pragma pop` 恢复
+    ArmccPragma,
+}
+
+impl Compiler {
+    pub const ALL: [Compiler; 6] = [
+        Compiler::Gcc,
+        Compiler::Armclang,
+        Compiler::Iar,
+        Compiler::Msvc,
+        Compiler::Armcc,
+        Compiler::ArmccPragma,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Compiler::Gcc => "GCC",
+            Compiler::Armclang => "armclang",
+            Compiler::Iar => "IAR",
+            Compiler::Msvc => "MSVC",
+            Compiler::Armcc | Compiler::ArmccPragma => "ARMCC 5",
+        }
+    }
+
+    /// 命令行与 rplc.toml 中的写法，例如 `armcc-pragma`
+    pub fn id(self) -> &'static str {
+        match self {
+            Compiler::Gcc => "gcc",
+            Compiler::Armclang => "armclang",
+            Compiler::Iar => "iar",
+            Compiler::Msvc => "msvc",
+            Compiler::Armcc => "armcc",
+            Compiler::ArmccPragma => "armcc-pragma",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|compiler| compiler.id() == id)
+    }
+
+    pub(crate) fn packing(self) -> Packing {
+        match self {
+            Compiler::Gcc | Compiler::Armclang => Packing::Attribute,
+            Compiler::Iar | Compiler::Msvc => Packing::Pragma,
+            Compiler::Armcc => Packing::Keyword,
+            Compiler::ArmccPragma => Packing::ArmccPragma,
+        }
+    }
+
+    /// 基础类型大小不同的相邻位域能否共享存储单元
+    ///
+    /// MSVC 遇到大小不同的基础类型时总是开始新的存储单元，即使结构体是紧凑的
+    pub fn merges_mixed_bit_fields(self) -> bool {
+        self != Compiler::Msvc
+    }
+
+    /// 未显式声明符号的 `int`、`short` 等位域是否被当作无符号
+    ///
+    /// ARMCC 5 默认如此，除非使用 `--signed_bitfields` 编译
+    pub fn plain_bit_fields_unsigned(self) -> bool {
+        matches!(self, Compiler::Armcc | Compiler::ArmccPragma)
+    }
+
+    /// 原生位域在存储单元内的位序
+    ///
+    /// 支持的编译器都面向小端目标，位域从存储单元的最低位开始分配
+    pub fn bit_field_order(self) -> BitOrder {
+        match self {
+            Compiler::Gcc
+            | Compiler::Armclang
+            | Compiler::Iar
+            | Compiler::Msvc
+            | Compiler::Armcc
+            | Compiler::ArmccPragma => BitOrder::LsbFirst,
+        }
+    }
+}
+
+impl fmt::Display for Compiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// 生成的 C++ 代码遵循的语言标准，输出中不会出现更高标准才有的特性
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum CppStandard {
+    /// 嵌套命名空间逐层展开，`constexpr` 变量不带 `inline`，取值名称返回 `const char*`；不支持 `serialize`
+    Cpp14,
+    /// `deserialize` 接受指针与长度，字节序转换使用 `std::memcpy`
+    Cpp17,
+    /// `deserialize` 接受 `std::span`，字节序转换使用 `std::bit_cast`（默认）
+    #[default]
+    Cpp20,
+    Cpp23,
+}
+
+impl CppStandard {
+    pub fn name(self) -> &'static str {
+        match self {
+            CppStandard::Cpp14 => "C++14",
+            CppStandard::Cpp17 => "C++17",
+            CppStandard::Cpp20 => "C++20",
+            CppStandard::Cpp23 => "C++23",
+        }
+    }
+
+    /// 按年份的后两位查找，例如 `17`
+    pub fn from_year(year: u32) -> Option<Self> {
+        match year {
+            14 => Some(CppStandard::Cpp14),
+            17 => Some(CppStandard::Cpp17),
+            20 => Some(CppStandard::Cpp20),
+            23 => Some(CppStandard::Cpp23),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CppStandard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
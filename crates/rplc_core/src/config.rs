@@ -1,54 +1,1151 @@
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Serialize, Deserialize)]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Field {
     pub name: String,
+    /// 稳定的字段 ID，日志等按 ID 而不是按位置识别字段的格式使用；删除字段后其 ID 记入包的 `retired_field_ids`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
     #[serde(rename = "type")]
     pub ty: String,
+    /// 数组长度，`{"type": "uint8_t", "array_len": 16}` 与 `uint8_t[16]` 等价；
+    /// 读取时合并到 `ty` 中，此后总是为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub array_len: Option<u32>,
+    /// 字段引用的枚举名；读取时 `ty` 被替换为枚举的底层类型（数组保留长度），写出时还原
+    #[serde(skip)]
+    pub enum_type: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_bit_field")]
     pub bit_field: Option<u8>,
-    pub comment: Option<String>,
+    pub comment: Option<Comment>,
+    /// 物理单位，例如 `rad`、`m/s`，只写入文档
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// 遥测范围的最小值，写入文档并生成 `k_<字段>_min` 常量，不影响布局
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<serde_json::Number>,
+    /// 遥测范围的最大值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<serde_json::Number>,
+    /// 正常工作时的典型值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typical: Option<serde_json::Number>,
+    /// 取值的显示名称，键为十进制或 `0x` 开头的十六进制整数，生成 `<字段>_name` 查找函数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<BTreeMap<String, Comment>>,
+    /// 固定的字节偏移，与前一字段之间的空隙由保留字节填充
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+    /// 该字段在线上数据中的字节序，覆盖包的 `endianness`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endianness: Option<Endianness>,
+    /// 保留字段的来源，普通字段为 `None`
+    #[serde(skip)]
+    pub reserved: Option<ReservedKind>,
+    /// 从该字段开始的分组标题，来自 fields 中位于它之前的 `{"section": ...}` 条目
+    #[serde(skip)]
+    pub section: Option<String>,
+    /// 字段所属的嵌套结构体，来自 `{"group": [...]}` 条目；同一结构体中的字段相邻且 `id` 相同
+    #[serde(skip)]
+    pub group: Option<FieldGroup>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Field {
+    /// 写出的范围标注，按 min、max、typical 的顺序
+    pub fn limits(&self) -> impl Iterator<Item = (&'static str, &serde_json::Number)> {
+        [
+            ("min", &self.min),
+            ("max", &self.max),
+            ("typical", &self.typical),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value.as_ref()?)))
+    }
+
+    /// 按数值排列的取值与显示名称，无法解析的键被跳过
+    pub fn value_names(&self) -> Vec<(i128, &Comment)> {
+        let mut names: Vec<(i128, &Comment)> = self
+            .values
+            .iter()
+            .flatten()
+            .filter_map(|(key, name)| Some((parse_value_key(key)?, name)))
+            .collect();
+        names.sort_by_key(|(value, _)| *value);
+        names
+    }
+
+    /// 在生成的结构体中访问该字段的表达式，具名嵌套结构体中的字段为 `group.name`
+    pub fn member_path(&self) -> String {
+        match self.group.as_ref().and_then(|g| g.name.as_deref()) {
+            Some(group) => format!("{}.{}", group, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// 解析 `values` 的键：十进制整数（可带负号），或以 `0x`/`0X` 开头的十六进制
+pub fn parse_value_key(key: &str) -> Option<i128> {
+    let key = key.trim();
+    match key.strip_prefix("0x").or_else(|| key.strip_prefix("0X")) {
+        Some(hex) => i128::from_str_radix(hex, 16).ok(),
+        None => key.parse().ok(),
+    }
+}
+
+/// 保留字段的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReservedKind {
+    /// 为填充 `offset` 之前的空隙而自动插入的 `reserved_<起始字节>`，不参与布局哈希
+    Gap,
+    /// fields 中的 `{"reserved": n}` 条目，按出现顺序命名为 `_reserved<序号>`
+    Explicit,
+}
+
+/// 保留条目没有注释时使用的注释
+pub(crate) const RESERVED_COMMENT: &str = "保留";
+
+/// 嵌套结构体：只用于组织代码，紧凑布局与把其中的字段直接写在包中相同
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldGroup {
+    /// 在包内唯一的序号，用于区分相邻的两个嵌套结构体
+    pub id: usize,
+    /// 成员名称，为 `None` 时生成匿名结构体
+    pub name: Option<String>,
+    pub comment: Option<Comment>,
+}
+
+/// 具名枚举，字段以枚举名作为类型，生成 `enum class 名称 : 底层类型`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EnumDef {
+    pub name: String,
+    /// 底层整数类型，决定字段的大小
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<Comment>,
+    pub values: Vec<Enumerator>,
+}
+
+/// 枚举中的一项，取值必须显式写出
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Enumerator {
+    pub name: String,
+    pub value: serde_json::Number,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<Comment>,
+}
+
+/// 没有指定语言，或注释中没有所选语言时使用的语言
+pub const DEFAULT_COMMENT_LANGUAGE: &str = "zh";
+
+/// 位域的位序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BitOrder {
+    /// 从字节的最低位开始填充，先放值的低位（GCC、Clang、MSVC 等在小端目标上的做法）
+    LsbFirst,
+    /// 从字节的最高位开始填充，先放值的高位（常见于大端目标与网络协议）
+    MsbFirst,
+}
+
+impl BitOrder {
+    /// 定义文件中的写法
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BitOrder::LsbFirst => "lsb_first",
+            BitOrder::MsbFirst => "msb_first",
+        }
+    }
+}
+
+/// 多字节标量在线上数据中的字节序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Endianness {
+    /// 低字节在前，与 STM32 等小端 MCU 的内存布局一致
+    #[default]
+    Little,
+    /// 高字节在前（网络字节序）
+    Big,
+}
+
+impl Endianness {
+    /// 定义文件中的写法
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Endianness::Little => "little",
+            Endianness::Big => "big",
+        }
+    }
+}
+
+/// 帧校验方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrcKind {
+    /// DJI 裁判系统串口协议：帧头 CRC8 与整帧 CRC16
+    Dji,
+}
+
+impl CrcKind {
+    /// 定义文件中的写法
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CrcKind::Dji => "dji",
+        }
+    }
+}
+
+/// 注释：单一文本，或按语言代码给出的多语言文本，例如 `{"zh": "偏航角", "en": "Yaw angle"}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Comment {
+    Text(String),
+    Localized(BTreeMap<String, String>),
+}
+
+impl Comment {
+    /// 指定语言的文本；单一文本总是原样返回，多语言注释中缺少该语言时依次退回
+    /// `DEFAULT_COMMENT_LANGUAGE` 与按语言代码排序的第一项
+    pub fn text(&self, language: Option<&str>) -> &str {
+        match self {
+            Comment::Text(text) => text,
+            Comment::Localized(texts) => language
+                .and_then(|language| texts.get(language))
+                .or_else(|| texts.get(DEFAULT_COMMENT_LANGUAGE))
+                .or_else(|| texts.values().next())
+                .map_or("", String::as_str),
+        }
+    }
+
+    /// 默认语言的文本，见 `text`
+    pub fn as_str(&self) -> &str {
+        self.text(None)
+    }
+
+    /// 多语言注释中出现的语言代码，单一文本没有语言
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        let texts = match self {
+            Comment::Text(_) => None,
+            Comment::Localized(texts) => Some(texts),
+        };
+        texts
+            .into_iter()
+            .flat_map(|texts| texts.keys().map(String::as_str))
+    }
+
+    /// 只保留指定语言的文本
+    pub fn localize(&mut self, language: Option<&str>) {
+        if let Comment::Localized(_) = self {
+            *self = Comment::Text(self.text(language).to_string());
+        }
+    }
+}
+
+impl fmt::Display for Comment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for Comment {
+    fn from(text: &str) -> Self {
+        Comment::Text(text.to_string())
+    }
+}
+
+impl From<String> for Comment {
+    fn from(text: String) -> Self {
+        Comment::Text(text)
+    }
+}
+
+/// 当前的定义文件格式版本，`rplc migrate` 把旧文件升级到该版本
+pub const CURRENT_SCHEMA: u32 = 2;
+
+/// 没有写 `rplc_schema` 的文件按此版本处理
+pub const LEGACY_SCHEMA: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct Config {
+    /// 定义文件的格式版本，省略时为 `LEGACY_SCHEMA`
+    #[serde(default)]
+    pub rplc_schema: Option<u32>,
     pub packet_name: String,
     pub command_id: String,
     pub namespace: Option<String>,
     #[serde(default = "default_packet")]
     pub packed: bool,
     pub header_guard: Option<String>,
+    /// 替代 `<cstdint>` 提供定宽整数类型的头文件，`<...>` 形式按原样包含，其余写成 `"..."`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub includes: Option<Vec<String>>,
+    /// 结构体的对齐要求（字节），生成 `alignas(N)`
+    pub align: Option<u32>,
+    /// 期望的结构体字节数，生成 `static_assert` 并在校验时与计算出的布局比较
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_size: Option<u32>,
+    /// 额外生成 volatile 视图与按字节读取的访问函数，用于直接读取 DMA 缓冲区的包
+    #[serde(default)]
+    pub volatile_view: bool,
+    /// 额外生成按线上字节序读写缓冲区的 `serialize` 与 `deserialize` 函数
+    #[serde(default)]
+    pub serialize: bool,
+    /// 线上数据的字节序，省略时为小端；只影响 `serialize` 与 `deserialize`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endianness: Option<Endianness>,
+    /// 帧校验方式；声明后额外生成按该协议打包整帧的 `pack_frame_<包名>` 与校验函数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc: Option<CrcKind>,
+    /// 为 true 时 CRC16 追加在数据之后；为 false 时由包的最后一个字段 `crc` 承载帧尾
+    #[serde(default = "default_crc_append")]
+    pub crc_append: bool,
+    /// 为 false 时不生成 `RPL::Meta::PacketTraits` 特化，也不引用 RPL 的头文件
+    #[serde(default = "default_emit_traits")]
+    pub emit_traits: bool,
+    /// 位域在线上字节中的位序；声明后额外生成按该位序读写字节的 `<包名>Wire` 访问函数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitfield_layout: Option<BitOrder>,
+    /// 替代默认 `PacketTraits` 特化的代码模板，支持 `${name}`、`${cmd}`、`${size}`、`${hash}` 占位符
+    pub traits_template: Option<String>,
     #[serde(default = "default_comment")]
-    pub comment: Option<String>,
+    pub comment: Option<Comment>,
+    /// 包内定义的具名枚举，字段的类型可以写成枚举名
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enums: Vec<EnumDef>,
+    /// 已删除字段曾使用的 ID，不能再分配给其他字段
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retired_field_ids: Vec<u32>,
+    /// 字段与分组条目，分组标题记录在其后第一个字段的 `section` 中
+    #[serde(with = "field_entries")]
     pub fields: Vec<Field>,
+    /// 字段直接或间接引用的其他包，被依赖的在前；多包模式下拆分为单个包时由 rplc 填入，
+    /// 使单个包的定义可以独立校验、计算布局与生成代码
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<Config>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            rplc_schema: None,
+            packet_name: String::new(),
+            command_id: String::new(),
+            namespace: None,
+            packed: default_packet(),
+            header_guard: None,
+            includes: None,
+            align: None,
+            expected_size: None,
+            volatile_view: false,
+            serialize: false,
+            endianness: None,
+            crc: None,
+            crc_append: default_crc_append(),
+            emit_traits: default_emit_traits(),
+            bitfield_layout: None,
+            traits_template: None,
+            comment: default_comment(),
+            enums: Vec::new(),
+            retired_field_ids: Vec::new(),
+            fields: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+}
+
+/// 读取后把引用枚举的字段类型替换为底层类型，布局与各后端只需处理整数类型
+impl<'de> Deserialize<'de> for Config {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut config = Config::deserialize(deserializer)?;
+        config.resolve_enum_types();
+        Ok(config)
+    }
+}
+
+/// 写出时字段类型还原为枚举名
+impl Serialize for Config {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.fields.iter().all(|field| field.enum_type.is_none()) {
+            return Config::serialize(self, serializer);
+        }
+        let mut config = self.clone();
+        for field in &mut config.fields {
+            if let Some(name) = field.enum_type.take() {
+                let len = crate::validator::parse_array_type(&field.ty).and_then(|(_, len)| len);
+                field.ty = match len {
+                    Some(len) => format!("{}[{}]", name, len),
+                    None => name,
+                };
+            }
+        }
+        Config::serialize(&config, serializer)
+    }
+}
+
+impl Config {
+    fn resolve_enum_types(&mut self) {
+        for field in &mut self.fields {
+            let Some((base, len)) = crate::validator::parse_array_type(&field.ty) else {
+                continue;
+            };
+            let Some(def) = self.enums.iter().find(|def| def.name == base) else {
+                continue;
+            };
+            field.enum_type = Some(base.to_string());
+            field.ty = match len {
+                Some(len) => format!("{}[{}]", def.ty, len),
+                None => def.ty.clone(),
+            };
+        }
+    }
+
+    /// 字段类型引用的包名（数组取元素类型），按首次出现的顺序去重
+    pub fn referenced_packets(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for field in &self.fields {
+            if let Some((base, _)) = crate::validator::parse_array_type(&field.ty)
+                && crate::layout::c_type_size(base).is_none()
+                && !names.contains(&base)
+            {
+                names.push(base);
+            }
+        }
+        names
+    }
+
+    /// 字段引用的包的定义
+    pub fn dependency(&self, name: &str) -> Option<&Config> {
+        self.dependencies
+            .iter()
+            .find(|packet| packet.packet_name == name)
+    }
+
+    /// 字段在线上数据中的字节序：字段自身的声明优先，其次是包的声明，默认小端
+    pub fn field_endianness(&self, field: &Field) -> Endianness {
+        field.endianness.or(self.endianness).unwrap_or_default()
+    }
+
+    /// 字段引用的枚举定义
+    pub fn field_enum(&self, field: &Field) -> Option<&EnumDef> {
+        let name = field.enum_type.as_deref()?;
+        self.enums.iter().find(|def| def.name == name)
+    }
+
+    /// 包、字段与嵌套结构体的所有注释，以及取值的显示名称
+    fn comments_mut(&mut self) -> impl Iterator<Item = &mut Comment> {
+        let fields = self.fields.iter_mut().flat_map(|field| {
+            let group = field.group.as_mut().and_then(|g| g.comment.as_mut());
+            let values = field
+                .values
+                .iter_mut()
+                .flat_map(|values| values.values_mut());
+            field
+                .comment
+                .as_mut()
+                .into_iter()
+                .chain(group)
+                .chain(values)
+        });
+        let enums = self.enums.iter_mut().flat_map(|def| {
+            def.comment.as_mut().into_iter().chain(
+                def.values
+                    .iter_mut()
+                    .filter_map(|value| value.comment.as_mut()),
+            )
+        });
+        self.comment.as_mut().into_iter().chain(enums).chain(fields)
+    }
+
+    /// 所有注释只保留指定语言的文本，供只输出一种语言的后端使用
+    pub fn localize(&mut self, language: Option<&str>) {
+        for comment in self.comments_mut() {
+            comment.localize(language);
+        }
+    }
+
+    /// 注释中出现的语言代码，`DEFAULT_COMMENT_LANGUAGE` 在前，其余按代码排序
+    pub fn comment_languages(&self) -> Vec<String> {
+        let fields = self.fields.iter().flat_map(|field| {
+            let group = field.group.as_ref().and_then(|g| g.comment.as_ref());
+            let values = field.values.iter().flat_map(|values| values.values());
+            field
+                .comment
+                .as_ref()
+                .into_iter()
+                .chain(group)
+                .chain(values)
+        });
+        let enums = self.enums.iter().flat_map(|def| {
+            def.comment
+                .iter()
+                .chain(def.values.iter().filter_map(|value| value.comment.as_ref()))
+        });
+        let mut languages: Vec<String> = self
+            .comment
+            .iter()
+            .chain(enums)
+            .chain(fields)
+            .flat_map(Comment::languages)
+            .map(str::to_string)
+            .collect();
+        languages.sort_by_key(|language| (language != DEFAULT_COMMENT_LANGUAGE, language.clone()));
+        languages.dedup();
+        languages
+    }
+}
+
+/// 位域既可以是整数，也可以是字符串形式的整数（如从表格导出的 `"4"`）
+fn deserialize_bit_field<'de, D>(deserializer: D) -> Result<Option<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BitField {
+        Number(u8),
+        Text(String),
+    }
+
+    match Option::<BitField>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(BitField::Number(bits)) => Ok(Some(bits)),
+        Some(BitField::Text(text)) => text
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| serde::de::Error::custom(format!("位域 '{}' 不是有效的整数", text))),
+    }
+}
+
+/// fields 数组中的条目：字段，或只用于组织代码与文档的分组标题
+mod field_entries {
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Comment, Field, FieldGroup, RESERVED_COMMENT, ReservedKind};
+
+    #[derive(Serialize, Deserialize)]
+    struct Section<T> {
+        section: T,
+    }
+
+    /// 保留条目：只有字节数与可选的注释
+    #[derive(Serialize, Deserialize)]
+    struct Reserved<C> {
+        reserved: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        comment: Option<C>,
+    }
+
+    /// 字段，或嵌套结构体中的保留条目
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Member {
+        Reserved(Reserved<Comment>),
+        Field(Box<Field>),
+    }
+
+    /// 按条目写出字段：保留条目写回 `{"reserved": n}`
+    struct MemberRef<'a>(&'a Field);
+
+    impl Serialize for MemberRef<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let field = self.0;
+            if field.reserved != Some(ReservedKind::Explicit) {
+                return field.serialize(serializer);
+            }
+            let bytes = crate::validator::parse_array_type(&field.ty)
+                .and_then(|(_, len)| len)
+                .unwrap_or(1);
+            let comment = field
+                .comment
+                .as_ref()
+                .filter(|c| **c != Comment::from(RESERVED_COMMENT));
+            Reserved {
+                reserved: bytes,
+                comment,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    /// 第 `index` 个保留条目展开得到的字段
+    fn reserved_field(index: usize, reserved: Reserved<Comment>) -> Field {
+        Field {
+            name: format!("_reserved{}", index),
+            ty: format!("uint8_t[{}]", reserved.reserved),
+            comment: Some(
+                reserved
+                    .comment
+                    .unwrap_or_else(|| Comment::from(RESERVED_COMMENT)),
+            ),
+            reserved: Some(ReservedKind::Explicit),
+            ..Default::default()
+        }
+    }
+
+    /// 把 `array_len` 合并为 `Type[N]`，生成器只需处理一种写法
+    fn with_array_len(mut field: Field) -> Field {
+        if let Some(len) = field.array_len.take() {
+            field.ty = format!("{}[{}]", field.ty, len);
+        }
+        field
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Group<N, C, F> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<N>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        comment: Option<C>,
+        group: Vec<F>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Entry {
+        Section(Section<String>),
+        Group(Group<String, Comment, Member>),
+        Reserved(Reserved<Comment>),
+        Field(Box<Field>),
+    }
+
+    pub fn serialize<S: Serializer>(fields: &[Field], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        let mut rest = fields;
+        while let Some(field) = rest.first() {
+            if let Some(section) = &field.section {
+                seq.serialize_element(&Section { section })?;
+            }
+            let Some(group) = &field.group else {
+                seq.serialize_element(&MemberRef(field))?;
+                rest = &rest[1..];
+                continue;
+            };
+            let len = rest
+                .iter()
+                .take_while(|f| f.group.as_ref().map(|g| g.id) == Some(group.id))
+                .count();
+            seq.serialize_element(&Group {
+                name: group.name.as_deref(),
+                comment: group.comment.as_ref(),
+                group: rest[..len].iter().map(MemberRef).collect(),
+            })?;
+            rest = &rest[len..];
+        }
+        seq.end()
+    }
+
+    /// 没有后续字段的分组标题被丢弃，由 validate 给出警告
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Field>, D::Error> {
+        let mut fields: Vec<Field> = Vec::new();
+        let mut pending = None;
+        let mut reserved_count = 0;
+        let mut member = |member: Member| match member {
+            Member::Reserved(reserved) => {
+                reserved_count += 1;
+                reserved_field(reserved_count - 1, reserved)
+            }
+            Member::Field(field) => with_array_len(*field),
+        };
+        for (id, entry) in Vec::<Entry>::deserialize(deserializer)?
+            .into_iter()
+            .enumerate()
+        {
+            match entry {
+                Entry::Section(Section { section }) => pending = Some(section),
+                Entry::Group(Group {
+                    name,
+                    comment,
+                    group,
+                }) => {
+                    let info = FieldGroup { id, name, comment };
+                    let start = fields.len();
+                    fields.extend(group.into_iter().map(|m| {
+                        let mut field = member(m);
+                        field.group = Some(info.clone());
+                        field
+                    }));
+                    if let Some(first) = fields.get_mut(start) {
+                        first.section = pending.take();
+                    }
+                }
+                Entry::Reserved(reserved) => {
+                    let mut field = member(Member::Reserved(reserved));
+                    field.section = pending.take();
+                    fields.push(field);
+                }
+                Entry::Field(field) => {
+                    let mut field = with_array_len(*field);
+                    field.section = pending.take();
+                    fields.push(field);
+                }
+            }
+        }
+        Ok(fields)
+    }
 }
 
 fn default_packet() -> bool {
     true
 }
 
-fn default_comment() -> Option<String> {
+fn default_emit_traits() -> bool {
+    true
+}
+
+fn default_crc_append() -> bool {
+    true
+}
+
+/// `traits_template` 支持的占位符：包名、规范写法的命令 ID、包的字节数表达式与布局哈希
+pub(crate) const TRAITS_PLACEHOLDERS: &[&str] = &["name", "cmd", "size", "hash"];
+
+/// 模板中的一个 `${...}` 占位符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Placeholder<'a> {
+    pub name: &'a str,
+    /// `${` 在模板中的字节偏移
+    pub start: usize,
+    /// 占位符之后的字节偏移；未闭合时为模板末尾
+    pub end: usize,
+    pub closed: bool,
+}
+
+/// 模板中所有 `${...}` 占位符，未闭合的 `${` 以其后的全部内容为名称
+pub(crate) fn template_placeholders(template: &str) -> Vec<Placeholder<'_>> {
+    let mut placeholders = Vec::new();
+    let mut offset = 0;
+    while let Some(found) = template[offset..].find("${") {
+        let start = offset + found;
+        let after = &template[start + 2..];
+        match after.find('}') {
+            Some(len) => {
+                offset = start + 2 + len + 1;
+                placeholders.push(Placeholder {
+                    name: &after[..len],
+                    start,
+                    end: offset,
+                    closed: true,
+                });
+            }
+            None => {
+                placeholders.push(Placeholder {
+                    name: after,
+                    start,
+                    end: template.len(),
+                    closed: false,
+                });
+                break;
+            }
+        }
+    }
+    placeholders
+}
+
+/// 用包的信息替换模板中的占位符
+///
+/// 模板只做一次文本替换：替换结果不会再被展开，没有循环、文件访问或随时间变化的值，
+/// 输出只由包的定义决定，耗时与模板长度成正比
+pub(crate) fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut copied = 0;
+    for placeholder in template_placeholders(template) {
+        let value = values
+            .iter()
+            .find(|(name, _)| placeholder.closed && *name == placeholder.name);
+        if let Some((_, value)) = value {
+            out.push_str(&template[copied..placeholder.start]);
+            out.push_str(value);
+            copied = placeholder.end;
+        }
+    }
+    out.push_str(&template[copied..]);
+    out
+}
+
+fn default_comment() -> Option<Comment> {
     None
 }
 
+/// 带有集合级元数据的多包定义
+/// 例如: { "protocol_version": "2.3.0", "packets": [...] }
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PacketSet {
+    /// 集合中各个包的默认格式版本
+    #[serde(default)]
+    pub rplc_schema: Option<u32>,
+    pub protocol_version: Option<String>,
+    pub packets: Vec<Config>,
+}
+
 // New functionality to support multiple configurations
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ConfigOrArray {
-    Single(Config),
+    Single(Box<Config>),
     Multiple(Vec<Config>),
+    Set(PacketSet),
+}
+
+impl ConfigOrArray {
+    /// 根据顶层结构判断输入是否为多包定义：数组，或带 `packets` 的包集合
+    ///
+    /// 无法解析的输入视为单包，由单包模式报告具体错误
+    pub fn is_multi_input(json_input: &str) -> bool {
+        match serde_json::from_str::<serde_json::Value>(json_input) {
+            Ok(serde_json::Value::Array(_)) => true,
+            Ok(serde_json::Value::Object(map)) => map.contains_key("packets"),
+            _ => false,
+        }
+    }
+
+    /// 包集合的协议版本，仅 `PacketSet` 形式可以携带
+    pub fn protocol_version(&self) -> Option<&str> {
+        match self {
+            ConfigOrArray::Set(set) => set.protocol_version.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// 拆分为各个包，引用其他包的包带上它的依赖，见 [`Config::dependencies`]
+    pub fn into_packets(self) -> Vec<Config> {
+        let mut packets = match self {
+            ConfigOrArray::Single(config) => vec![*config],
+            ConfigOrArray::Multiple(configs) => configs,
+            ConfigOrArray::Set(set) => set.packets,
+        };
+        attach_dependencies(&mut packets);
+        packets
+    }
+}
+
+/// 为每个包填入它直接或间接引用的包，按被依赖的在前排列；
+/// 引用不存在的包或引用成环时跳过，由校验报告
+pub(crate) fn attach_dependencies(packets: &mut [Config]) {
+    fn visit<'a>(
+        packets: &'a [Config],
+        name: &'a str,
+        visiting: &mut Vec<&'a str>,
+        order: &mut Vec<&'a str>,
+    ) {
+        if visiting.contains(&name) || order.contains(&name) {
+            return;
+        }
+        let Some(packet) = packets.iter().find(|p| p.packet_name == name) else {
+            return;
+        };
+        visiting.push(name);
+        for reference in packet.referenced_packets() {
+            visit(packets, reference, visiting, order);
+        }
+        visiting.pop();
+        order.push(name);
+    }
+
+    let dependencies: Vec<Vec<Config>> = packets
+        .iter()
+        .map(|packet| {
+            let mut order = Vec::new();
+            let mut visiting = vec![packet.packet_name.as_str()];
+            for reference in packet.referenced_packets() {
+                visit(packets, reference, &mut visiting, &mut order);
+            }
+            order
+                .into_iter()
+                .filter_map(|name| packets.iter().find(|p| p.packet_name == name))
+                .map(|dependency| Config {
+                    dependencies: Vec::new(),
+                    ..dependency.clone()
+                })
+                .collect()
+        })
+        .collect();
+    for (packet, dependencies) in packets.iter_mut().zip(dependencies) {
+        packet.dependencies = dependencies;
+    }
+}
+
+/// 协议语义化版本号 `MAJOR.MINOR.PATCH`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ProtocolVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('.');
+        let mut next = || -> Result<u16, ()> {
+            let part = parts.next().ok_or(())?;
+            if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+                return Err(());
+            }
+            part.parse::<u16>().map_err(|_| ())
+        };
+        let version = ProtocolVersion {
+            major: next()?,
+            minor: next()?,
+            patch: next()?,
+        };
+        if parts.next().is_some() {
+            return Err(());
+        }
+        Ok(version)
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_template_placeholders() {
+        let names = |template| {
+            template_placeholders(template)
+                .iter()
+                .map(|p| p.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names("X<${name}> { id = ${cmd}; n = ${size}; }"),
+            vec!["name", "cmd", "size"]
+        );
+        assert_eq!(
+            template_placeholders("${name} ${oops"),
+            vec![
+                Placeholder {
+                    name: "name",
+                    start: 0,
+                    end: 7,
+                    closed: true
+                },
+                Placeholder {
+                    name: "oops",
+                    start: 8,
+                    end: 14,
+                    closed: false
+                },
+            ]
+        );
+        assert!(names("{ $name }").is_empty());
+        // 替换结果中的占位符不会再被展开
+        assert_eq!(
+            render_template("${name}", &[("name", "${cmd}"), ("cmd", "0x0001")]),
+            "${cmd}"
+        );
+        assert_eq!(
+            render_template(
+                "${name}::${name} = ${cmd}",
+                &[("name", "A"), ("cmd", "0x0001")]
+            ),
+            "A::A = 0x0001"
+        );
+    }
+
+    #[test]
+    fn test_sections_round_trip() {
+        let json = r#"{"packet_name":"A","command_id":"1","fields":[
+            {"section":"IMU"},
+            {"name":"x","type":"float"},
+            {"name":"y","type":"float"},
+            {"section":"Unused"},
+            {"section":"Flags"},
+            {"name":"z","type":"uint8_t"},
+            {"section":"Trailing"}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let sections: Vec<_> = config.fields.iter().map(|f| f.section.as_deref()).collect();
+        assert_eq!(sections, [Some("IMU"), None, Some("Flags")]);
+
+        let serialized = serde_json::to_string(&config).unwrap();
+        assert!(serialized.contains(r#""fields":[{"section":"IMU"},{"name":"x""#));
+        let reparsed: Config = serde_json::from_str(&serialized).unwrap();
+        let reparsed: Vec<_> = reparsed.fields.iter().map(|f| f.section.clone()).collect();
+        assert_eq!(
+            reparsed,
+            [Some("IMU".to_string()), None, Some("Flags".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_groups_round_trip() {
+        let json = r#"{"packet_name":"A","command_id":"1","fields":[
+            {"name":"seq","type":"uint8_t"},
+            {"section":"IMU"},
+            {"name":"imu","comment":"IMU","group":[
+                {"name":"x","type":"float"},
+                {"name":"y","type":"float"}]},
+            {"group":[{"name":"z","type":"uint8_t"}]}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let paths: Vec<_> = config.fields.iter().map(Field::member_path).collect();
+        assert_eq!(paths, ["seq", "imu.x", "imu.y", "z"]);
+        assert_eq!(config.fields[1].section.as_deref(), Some("IMU"));
+        assert_eq!(config.fields[1].group, config.fields[2].group);
+        assert_ne!(config.fields[2].group, config.fields[3].group);
+
+        let serialized = serde_json::to_string(&config).unwrap();
+        assert!(
+            serialized.contains(
+                r#"{"section":"IMU"},{"name":"imu","comment":"IMU","group":[{"name":"x""#
+            )
+        );
+        assert!(serialized.contains(r#"{"group":[{"name":"z""#));
+        let reparsed: Config = serde_json::from_str(&serialized).unwrap();
+        let reparsed: Vec<_> = reparsed.fields.iter().map(Field::member_path).collect();
+        assert_eq!(reparsed, paths);
+    }
+
+    #[test]
+    fn test_reserved_entries_round_trip() {
+        let json = r#"{"packet_name":"A","command_id":"1","fields":[
+            {"name":"seq","type":"uint8_t"},
+            {"reserved":3},
+            {"group":[{"name":"x","type":"uint8_t"},{"reserved":1,"comment":"对齐"}]}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let fields: Vec<_> = config
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.ty.as_str(), f.reserved))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("seq", "uint8_t", None),
+                ("_reserved0", "uint8_t[3]", Some(ReservedKind::Explicit)),
+                ("x", "uint8_t", None),
+                ("_reserved1", "uint8_t[1]", Some(ReservedKind::Explicit)),
+            ]
+        );
+        assert_eq!(config.fields[1].comment, Some(Comment::from("保留")));
+        assert_eq!(config.fields[3].group, config.fields[2].group);
+
+        let serialized = serde_json::to_string(&config).unwrap();
+        assert!(serialized.contains(r#"{"reserved":3},{"group":[{"name":"x""#));
+        assert!(serialized.contains(r#"{"reserved":1,"comment":"对齐"}]}"#));
+        let reparsed: Config = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(reparsed, config);
+    }
+
+    #[test]
+    fn test_array_len_folds_into_type() {
+        let json = r#"{"packet_name":"A","command_id":"1","fields":[
+            {"name":"data","type":"uint8_t","array_len":16},
+            {"group":[{"name":"gains","type":"float","array_len":3}]}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let fields: Vec<_> = config
+            .fields
+            .iter()
+            .map(|f| (f.ty.as_str(), f.array_len))
+            .collect();
+        assert_eq!(fields, [("uint8_t[16]", None), ("float[3]", None)]);
+
+        let serialized = serde_json::to_string(&config).unwrap();
+        assert!(serialized.contains(r#""type":"uint8_t[16]""#));
+        assert!(!serialized.contains("array_len"));
+    }
+
+    #[test]
+    fn test_enum_field_types() {
+        let json = r#"{"packet_name":"A","command_id":"1",
+            "enums":[{"name":"Mode","type":"uint8_t","comment":"模式","values":[
+                {"name":"idle","value":0,"comment":"空闲"},{"name":"run","value":1}]}],
+            "fields":[{"name":"mode","type":"Mode"},{"name":"history","type":"Mode[4]"},
+                {"name":"id","type":"uint16_t"}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let fields: Vec<_> = config
+            .fields
+            .iter()
+            .map(|f| (f.ty.as_str(), f.enum_type.as_deref()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("uint8_t", Some("Mode")),
+                ("uint8_t[4]", Some("Mode")),
+                ("uint16_t", None)
+            ]
+        );
+        assert_eq!(
+            config.field_enum(&config.fields[1]).unwrap().values.len(),
+            2
+        );
+        assert!(config.field_enum(&config.fields[2]).is_none());
+
+        // 序列化时还原枚举名，往返后不变
+        let serialized = serde_json::to_string(&config).unwrap();
+        assert!(serialized.contains(r#""type":"Mode[4]""#));
+        let reparsed: Config = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(reparsed, config);
+    }
+
+    #[test]
+    fn test_attach_dependencies() {
+        let json = r#"[
+            {"packet_name":"Gimbal","command_id":"1","fields":[
+                {"name":"imu","type":"Imu[2]"},{"name":"pose","type":"Pose"}]},
+            {"packet_name":"Imu","command_id":"2","fields":[{"name":"pose","type":"Pose"}]},
+            {"packet_name":"Pose","command_id":"3","fields":[{"name":"yaw","type":"float"}]},
+            {"packet_name":"Loop","command_id":"4","fields":[{"name":"next","type":"Loop"}]}
+        ]"#;
+        let packets = serde_json::from_str::<ConfigOrArray>(json)
+            .unwrap()
+            .into_packets();
+        let names = |packet: &Config| -> Vec<String> {
+            packet
+                .dependencies
+                .iter()
+                .map(|d| d.packet_name.clone())
+                .collect()
+        };
+        assert_eq!(packets[0].referenced_packets(), ["Imu", "Pose"]);
+        // 被依赖的包在前，依赖本身不再嵌套依赖
+        assert_eq!(names(&packets[0]), ["Pose", "Imu"]);
+        assert!(packets[0].dependencies[1].dependencies.is_empty());
+        assert_eq!(names(&packets[1]), ["Pose"]);
+        assert!(packets[2].dependencies.is_empty());
+        assert!(packets[3].dependencies.is_empty());
+
+        // 依赖随包一起序列化，单个包的 JSON 可以独立使用
+        let serialized = serde_json::to_string(&packets[1]).unwrap();
+        let reparsed: Config = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(reparsed.dependency("Pose"), packets[1].dependency("Pose"));
+    }
+
     #[test]
     fn test_field_serialization() {
         let field = Field {
             name: "temperature".to_string(),
             ty: "float".to_string(),
+            array_len: None,
+            enum_type: None,
             bit_field: None,
-            comment: Some("温度值(摄氏度)".to_string()),
+            comment: Some("温度值(摄氏度)".into()),
+            unit: None,
+            id: None,
+            min: None,
+            max: None,
+            typical: None,
+            values: None,
+            offset: None,
+            endianness: None,
+            reserved: None,
+            section: None,
+            group: None,
         };
 
         let json = serde_json::to_string(&field).unwrap();
@@ -59,7 +1156,7 @@ mod tests {
         let parsed: Field = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.name, "temperature");
         assert_eq!(parsed.ty, "float");
-        assert_eq!(parsed.comment, Some("温度值(摄氏度)".to_string()));
+        assert_eq!(parsed.comment, Some("温度值(摄氏度)".into()));
     }
 
     #[test]
@@ -67,8 +1164,21 @@ mod tests {
         let field = Field {
             name: "sensor_id".to_string(),
             ty: "uint8_t".to_string(),
+            array_len: None,
+            enum_type: None,
             bit_field: Some(3),
             comment: None,
+            unit: None,
+            id: None,
+            min: None,
+            max: None,
+            typical: None,
+            values: None,
+            offset: None,
+            endianness: None,
+            reserved: None,
+            section: None,
+            group: None,
         };
 
         let json = serde_json::to_string(&field).unwrap();
@@ -94,16 +1204,43 @@ mod tests {
                 Field {
                     name: "sensor_id".to_string(),
                     ty: "uint8_t".to_string(),
+                    array_len: None,
+                    enum_type: None,
                     bit_field: Some(3),
-                    comment: Some("传感器ID".to_string()),
+                    comment: Some("传感器ID".into()),
+                    unit: None,
+                    id: None,
+                    min: None,
+                    max: None,
+                    typical: None,
+                    values: None,
+                    offset: None,
+                    endianness: None,
+                    reserved: None,
+                    section: None,
+                    group: None,
                 },
                 Field {
                     name: "temperature".to_string(),
                     ty: "float".to_string(),
+                    array_len: None,
+                    enum_type: None,
                     bit_field: None,
-                    comment: Some("温度值(摄氏度)".to_string()),
+                    comment: Some("温度值(摄氏度)".into()),
+                    unit: None,
+                    id: None,
+                    min: None,
+                    max: None,
+                    typical: None,
+                    values: None,
+                    offset: None,
+                    endianness: None,
+                    reserved: None,
+                    section: None,
+                    group: None,
                 },
             ],
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -116,7 +1253,7 @@ mod tests {
         assert_eq!(parsed.packet_name, "SensorDataPacket");
         assert_eq!(parsed.command_id, "0x0104");
         assert_eq!(parsed.namespace, None);
-        assert_eq!(parsed.packed, true);
+        assert!(parsed.packed);
         assert_eq!(
             parsed.header_guard,
             Some("RPL_SENSORDATAPACKET_HPP".to_string())
@@ -138,9 +1275,23 @@ mod tests {
             fields: vec![Field {
                 name: "robot_id".to_string(),
                 ty: "uint16_t".to_string(),
+                array_len: None,
+                enum_type: None,
                 bit_field: None,
-                comment: Some("机器人ID".to_string()),
+                comment: Some("机器人ID".into()),
+                unit: None,
+                id: None,
+                min: None,
+                max: None,
+                typical: None,
+                values: None,
+                offset: None,
+                endianness: None,
+                reserved: None,
+                section: None,
+                group: None,
             }],
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -163,7 +1314,7 @@ mod tests {
         }"#;
 
         let config: Config = serde_json::from_str(json).unwrap();
-        assert_eq!(config.packed, true); // Should default to true
+        assert!(config.packed); // Should default to true
     }
 
     #[test]
@@ -176,11 +1327,12 @@ mod tests {
             header_guard: None,
             comment: None,
             fields: vec![],
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&config).unwrap();
         let parsed: Config = serde_json::from_str(&json).unwrap();
-        assert_eq!(parsed.packed, false);
+        assert!(!parsed.packed);
     }
 
     #[test]
@@ -188,8 +1340,21 @@ mod tests {
         let field = Field {
             name: "status_flag".to_string(),
             ty: "uint8_t".to_string(),
+            array_len: None,
+            enum_type: None,
             bit_field: Some(3),
-            comment: Some("状态标志".to_string()),
+            comment: Some("状态标志".into()),
+            unit: None,
+            id: None,
+            min: None,
+            max: None,
+            typical: None,
+            values: None,
+            offset: None,
+            endianness: None,
+            reserved: None,
+            section: None,
+            group: None,
         };
 
         let json = serde_json::to_string(&field).unwrap();
@@ -199,7 +1364,7 @@ mod tests {
         assert_eq!(parsed.name, "status_flag");
         assert_eq!(parsed.ty, "uint8_t");
         assert_eq!(parsed.bit_field, Some(3));
-        assert_eq!(parsed.comment, Some("状态标志".to_string()));
+        assert_eq!(parsed.comment, Some("状态标志".into()));
     }
 
     #[test]
@@ -207,8 +1372,21 @@ mod tests {
         let field = Field {
             name: "temperature".to_string(),
             ty: "float".to_string(),
+            array_len: None,
+            enum_type: None,
             bit_field: None,
-            comment: Some("温度值".to_string()),
+            comment: Some("温度值".into()),
+            unit: None,
+            id: None,
+            min: None,
+            max: None,
+            typical: None,
+            values: None,
+            offset: None,
+            endianness: None,
+            reserved: None,
+            section: None,
+            group: None,
         };
 
         let json = serde_json::to_string(&field).unwrap();
@@ -217,7 +1395,26 @@ mod tests {
         assert_eq!(parsed.name, "temperature");
         assert_eq!(parsed.ty, "float");
         assert_eq!(parsed.bit_field, None);
-        assert_eq!(parsed.comment, Some("温度值".to_string()));
+        assert_eq!(parsed.comment, Some("温度值".into()));
+    }
+
+    #[test]
+    fn test_field_bit_field_from_string() {
+        let field: Field =
+            serde_json::from_str(r#"{ "name": "flags", "type": "uint8_t", "bit_field": "4" }"#)
+                .unwrap();
+        assert_eq!(field.bit_field, Some(4));
+
+        let field: Field =
+            serde_json::from_str(r#"{ "name": "flags", "type": "uint8_t" }"#).unwrap();
+        assert_eq!(field.bit_field, None);
+
+        assert!(
+            serde_json::from_str::<Field>(
+                r#"{ "name": "flags", "type": "uint8_t", "bit_field": "four" }"#
+            )
+            .is_err()
+        );
     }
 
     #[test]
@@ -228,33 +1425,86 @@ mod tests {
             namespace: None,
             packed: true,
             header_guard: Some("RPL_SENSORSTATUS_HPP".to_string()),
-            comment: Some("传感器状态包".to_string()),
+            comment: Some("传感器状态包".into()),
             fields: vec![
                 Field {
                     name: "sensor_id".to_string(),
                     ty: "uint8_t".to_string(),
+                    array_len: None,
+                    enum_type: None,
                     bit_field: Some(4),
-                    comment: Some("传感器ID".to_string()),
+                    comment: Some("传感器ID".into()),
+                    unit: None,
+                    id: None,
+                    min: None,
+                    max: None,
+                    typical: None,
+                    values: None,
+                    offset: None,
+                    endianness: None,
+                    reserved: None,
+                    section: None,
+                    group: None,
                 },
                 Field {
                     name: "status_flag".to_string(),
                     ty: "uint8_t".to_string(),
+                    array_len: None,
+                    enum_type: None,
                     bit_field: Some(3),
-                    comment: Some("状态标志".to_string()),
+                    comment: Some("状态标志".into()),
+                    unit: None,
+                    id: None,
+                    min: None,
+                    max: None,
+                    typical: None,
+                    values: None,
+                    offset: None,
+                    endianness: None,
+                    reserved: None,
+                    section: None,
+                    group: None,
                 },
                 Field {
                     name: "reserved".to_string(),
                     ty: "uint8_t".to_string(),
+                    array_len: None,
+                    enum_type: None,
                     bit_field: Some(1),
-                    comment: Some("保留位".to_string()),
+                    comment: Some("保留位".into()),
+                    unit: None,
+                    id: None,
+                    min: None,
+                    max: None,
+                    typical: None,
+                    values: None,
+                    offset: None,
+                    endianness: None,
+                    reserved: None,
+                    section: None,
+                    group: None,
                 },
                 Field {
                     name: "temperature".to_string(),
                     ty: "float".to_string(),
+                    array_len: None,
+                    enum_type: None,
                     bit_field: None,
-                    comment: Some("温度值".to_string()),
+                    comment: Some("温度值".into()),
+                    unit: None,
+                    id: None,
+                    min: None,
+                    max: None,
+                    typical: None,
+                    values: None,
+                    offset: None,
+                    endianness: None,
+                    reserved: None,
+                    section: None,
+                    group: None,
                 },
             ],
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -265,7 +1515,7 @@ mod tests {
 
         let parsed: Config = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.packet_name, "SensorStatus");
-        assert_eq!(parsed.comment, Some("传感器状态包".to_string()));
+        assert_eq!(parsed.comment, Some("传感器状态包".into()));
         assert_eq!(parsed.fields.len(), 4);
         assert_eq!(parsed.fields[0].bit_field, Some(4));
         assert_eq!(parsed.fields[1].bit_field, Some(3));
@@ -281,13 +1531,27 @@ mod tests {
             namespace: None,
             packed: true,
             header_guard: Some("RPL_SENSORDATAPACKET_HPP".to_string()),
-            comment: Some("传感器数据包".to_string()),
+            comment: Some("传感器数据包".into()),
             fields: vec![Field {
                 name: "sensor_id".to_string(),
                 ty: "uint8_t".to_string(),
+                array_len: None,
+                enum_type: None,
                 bit_field: None,
-                comment: Some("传感器ID".to_string()),
+                comment: Some("传感器ID".into()),
+                unit: None,
+                id: None,
+                min: None,
+                max: None,
+                typical: None,
+                values: None,
+                offset: None,
+                endianness: None,
+                reserved: None,
+                section: None,
+                group: None,
             }],
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -298,7 +1562,7 @@ mod tests {
         let parsed: Config = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.packet_name, "SensorDataPacket");
         assert_eq!(parsed.command_id, "0x0104");
-        assert_eq!(parsed.comment, Some("传感器数据包".to_string()));
+        assert_eq!(parsed.comment, Some("传感器数据包".into()));
         assert_eq!(parsed.fields.len(), 1);
         assert_eq!(parsed.fields[0].name, "sensor_id");
     }
@@ -317,4 +1581,80 @@ mod tests {
         let config: Config = serde_json::from_str(json).unwrap();
         assert_eq!(config.comment, None); // Should default to None
     }
+
+    #[test]
+    fn test_packet_set_deserialization() {
+        let json = r#"{
+            "protocol_version": "2.3.0",
+            "packets": [
+                { "packet_name": "PacketA", "command_id": "0x0101", "fields": [] },
+                { "packet_name": "PacketB", "command_id": "0x0102", "fields": [] }
+            ]
+        }"#;
+
+        let parsed: ConfigOrArray = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.protocol_version(), Some("2.3.0"));
+        let packets = parsed.into_packets();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[1].packet_name, "PacketB");
+    }
+
+    #[test]
+    fn test_is_multi_input() {
+        assert!(ConfigOrArray::is_multi_input(
+            r#"[{ "packet_name": "A", "command_id": "1", "fields": [] }]"#
+        ));
+        assert!(ConfigOrArray::is_multi_input(
+            r#"{ "protocol_version": "1.0.0", "packets": [] }"#
+        ));
+        assert!(!ConfigOrArray::is_multi_input(
+            r#"{ "packet_name": "A", "command_id": "1", "fields": [] }"#
+        ));
+        assert!(!ConfigOrArray::is_multi_input("[ 1, 2"));
+    }
+
+    #[test]
+    fn test_localized_comment() {
+        let json = r#"{"packet_name":"Gimbal","command_id":"1","comment":{"en":"Gimbal","zh":"云台"},
+            "fields":[{"name":"yaw","type":"float","comment":{"fr":"Lacet","en":"Yaw"}},
+                {"name":"mode","type":"uint8_t","comment":"模式"}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let comment = config.comment.as_ref().unwrap();
+        assert_eq!(comment.text(Some("en")), "Gimbal");
+        assert_eq!(comment.text(Some("ja")), "云台");
+        assert_eq!(comment.as_str(), "云台");
+        // 没有默认语言时退回按代码排序的第一项
+        assert_eq!(config.fields[0].comment.as_ref().unwrap().as_str(), "Yaw");
+        assert_eq!(config.comment_languages(), vec!["zh", "en", "fr"]);
+
+        let mut localized = config.clone();
+        localized.localize(Some("fr"));
+        assert_eq!(localized.comment, Some(Comment::from("云台")));
+        assert_eq!(localized.fields[0].comment, Some(Comment::from("Lacet")));
+        assert_eq!(localized.fields[1].comment, Some(Comment::from("模式")));
+        assert!(localized.comment_languages().is_empty());
+
+        // 单一文本与多语言注释都原样写回
+        let value = serde_json::to_value(&config).unwrap();
+        assert_eq!(value["comment"]["zh"], "云台");
+        assert_eq!(value["fields"][1]["comment"], "模式");
+    }
+
+    #[test]
+    fn test_protocol_version_parse() {
+        let v: ProtocolVersion = "2.3.0".parse().unwrap();
+        assert_eq!(
+            v,
+            ProtocolVersion {
+                major: 2,
+                minor: 3,
+                patch: 0
+            }
+        );
+        assert_eq!(v.to_string(), "2.3.0");
+        assert!("2.3".parse::<ProtocolVersion>().is_err());
+        assert!("2.3.0.1".parse::<ProtocolVersion>().is_err());
+        assert!("v2.3.0".parse::<ProtocolVersion>().is_err());
+        assert!("2.-3.0".parse::<ProtocolVersion>().is_err());
+    }
 }
@@ -0,0 +1,87 @@
+//! 生成的 C++ 头文件所包含的头文件：按生成的代码实际用到的名称决定，而不是固定的前言
+
+/// 标准库头文件及其声明的、生成代码会用到的名称，按包含的顺序排列
+const STD_HEADERS: &[(&str, &[&str])] = &[
+    ("cstddef", &["std::size_t"]),
+    ("array", &["std::array"]),
+    ("bit", &["std::bit_cast", "std::endian"]),
+    ("cstring", &["std::memcpy", "std::memset"]),
+    ("optional", &["std::optional", "std::nullopt"]),
+    ("span", &["std::span"]),
+    ("type_traits", &["std::is_same_v"]),
+    ("string_view", &["std::string_view"]),
+    ("tuple", &["std::tuple"]),
+];
+
+/// 一个头文件需要的 `#include`：标准库头文件按 `STD_HEADERS` 的顺序，
+/// 其他头文件按加入的顺序排在其后
+#[derive(Debug, Default)]
+pub(crate) struct CppIncludes {
+    std: Vec<bool>,
+    system: Vec<&'static str>,
+    local: Vec<String>,
+}
+
+impl CppIncludes {
+    /// 按代码中出现的标准库名称加入对应的头文件；代码中的注释同样计入，多包含一个头文件不影响编译
+    pub fn scan(&mut self, code: &str) {
+        self.std.resize(STD_HEADERS.len(), false);
+        for (needed, (_, names)) in self.std.iter_mut().zip(STD_HEADERS) {
+            *needed |= names.iter().any(|name| code.contains(name));
+        }
+    }
+
+    /// 以 `<...>` 包含的非标准库头文件，例如 RPL 的 traits
+    pub fn system(&mut self, header: &'static str) {
+        if !self.system.contains(&header) {
+            self.system.push(header);
+        }
+    }
+
+    /// 以 `"..."` 包含的头文件，例如其他包生成的头文件
+    pub fn local(&mut self, path: String) {
+        if !self.local.contains(&path) {
+            self.local.push(path);
+        }
+    }
+
+    pub fn render(&self, out: &mut String) {
+        let std = STD_HEADERS
+            .iter()
+            .zip(&self.std)
+            .filter(|(_, needed)| **needed)
+            .map(|((header, _), _)| *header);
+        for header in std.chain(self.system.iter().copied()) {
+            out.push_str(&format!("#include <{}>\n", header));
+        }
+        for path in &self.local {
+            out.push_str(&format!("#include \"{}\"\n", path));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpp_includes() {
+        let mut includes = CppIncludes::default();
+        includes.local("Pose.hpp".to_string());
+        includes.system("RPL/Meta/PacketTraits.hpp");
+        includes.scan("using BitLayout = std::tuple<Field<std::array<uint8_t, 2>, 16>>;");
+        includes.scan("std::size_t n; std::array<float, 3> v;");
+        includes.local("Pose.hpp".to_string());
+        let mut out = String::new();
+        includes.render(&mut out);
+        assert_eq!(
+            out,
+            "#include <cstddef>\n#include <array>\n#include <tuple>\n\
+             #include <RPL/Meta/PacketTraits.hpp>\n#include \"Pose.hpp\"\n"
+        );
+
+        let mut out = String::new();
+        CppIncludes::default().render(&mut out);
+        assert_eq!(out, "");
+    }
+}
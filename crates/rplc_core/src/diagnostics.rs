@@ -34,19 +34,43 @@ pub enum ValidationCode {
     )]
     KeywordCollision(String),
 
+    #[error("字段名 '{0}' 与 {1} 生成代码中的同名成员冲突")]
+    #[diagnostic(
+        code(rplc::generated_name_collision),
+        help("请给字段换一个名称，例如 '{2}'")
+    )]
+    GeneratedNameCollision(String, String, String),
+
     #[error("字段名 '{0}' 重复定义")]
     #[diagnostic(code(rplc::duplicate_field))]
     DuplicateFieldName(String),
 
+    #[error("Packet名称 '{0}' 在文档中重复出现")]
+    #[diagnostic(
+        code(rplc::duplicate_packet),
+        help("多包文档中的包名必须唯一，否则生成的结构体与头文件会互相覆盖")
+    )]
+    DuplicatePacketName(String),
+
+    #[error("Command ID {0} 已被包 '{1}' 使用")]
+    #[diagnostic(
+        code(rplc::duplicate_cmd_id),
+        help("接收端按 Command ID 分发数据，每个包必须使用不同的 ID")
+    )]
+    DuplicateCommandId(String, String),
+
     #[error("Command ID '{0}' 格式错误，必须是 0-65535 的整数或十六进制")]
     #[diagnostic(
         code(rplc::invalid_cmd_id),
-        help("ID 必须是 0-65535 的整数，或 0x 开头的十六进制")
+        help("ID 必须是 0-65535 的整数，或 0x 开头的十六进制（数字之间可用 _ 分隔，如 0x01_04）")
     )]
     InvalidCommandId(String),
 
     #[error("'{0}' 的 Type 无效")]
-    #[diagnostic(code(rplc::invalid_field_type), help("请为字段指定合法的C/C++类型"))]
+    #[diagnostic(
+        code(rplc::invalid_field_type),
+        help("请为字段指定合法的C/C++类型、本包的枚举名或同一文件中其他包的名称")
+    )]
     InvalidFieldType(String),
 
     #[error("'{0}' 的位域限定符无效")]
@@ -65,7 +89,7 @@ pub enum ValidationCode {
         code(rplc::bit_field::length_overflow),
         help("位域限定符长度不能超过其类型本身")
     )]
-    BitFieldLengthOverflow(String, u8, u8),
+    BitFieldLengthOverflow(String, u64, u8),
 
     #[error("位域字段 '{0}' 和 '{1}' 存在跨存储单元行为({2} + {3} > {4})，且内存布局非紧凑")]
     #[diagnostic(
@@ -88,6 +112,403 @@ pub enum ValidationCode {
     )]
     BitFieldOnArray(String),
 
+    #[error("字段 '{0}' 的 array_len 无效")]
+    #[diagnostic(
+        code(rplc::array_len::invalid),
+        help("array_len 必须是正整数，例如 \"array_len\": 16")
+    )]
+    InvalidArrayLen(String),
+
+    #[error("字段 '{0}' 同时设置了 array_len 与数组类型 '{1}'")]
+    #[diagnostic(
+        code(rplc::array_len::conflict),
+        help("array_len 与 'Type[N]' 写法等价，只能选择其中一种；不支持多维数组")
+    )]
+    ArrayLenConflict(String, String),
+
+    #[error("枚举 '{0}' 无效：{1}")]
+    #[diagnostic(
+        code(rplc::enums::invalid),
+        help(
+            "enums 中的每一项需要 name（C++ 标识符）、type（整数类型，例如 uint8_t）与非空的 values 数组"
+        )
+    )]
+    InvalidEnum(String, String),
+
+    #[error("枚举 '{0}' 重复定义")]
+    #[diagnostic(code(rplc::enums::duplicate), help("同一个包中的枚举名称必须唯一"))]
+    DuplicateEnum(String),
+
+    #[error("枚举 '{0}' 的枚举项 '{1}' 无效：{2}")]
+    #[diagnostic(
+        code(rplc::enums::invalid_enumerator),
+        help("枚举项写作 {{ \"name\": \"idle\", \"value\": 0 }}，名称是 C++ 标识符，取值是整数")
+    )]
+    InvalidEnumerator(String, String, String),
+
+    #[error("枚举 '{0}' 中的枚举项 '{1}' 重复")]
+    #[diagnostic(
+        code(rplc::enums::duplicate_enumerator),
+        help("同一个枚举中的枚举项名称必须唯一")
+    )]
+    DuplicateEnumerator(String, String),
+
+    #[error("枚举 '{0}' 中 '{1}' 的取值 {2} 超出了底层类型 {3} 的范围")]
+    #[diagnostic(
+        code(rplc::enums::value_out_of_range),
+        help("换用更宽的底层类型，或修改枚举项的取值")
+    )]
+    EnumValueOutOfRange(String, String, String, String),
+
+    #[error("枚举 '{0}' 中 '{1}' 与 '{2}' 的取值相同")]
+    #[diagnostic(
+        code(rplc::enums::duplicate_value),
+        help("取值相同的枚举项在线上无法区分；如果是有意的别名，可以忽略这条警告")
+    )]
+    DuplicateEnumValue(String, String, String),
+
+    #[error("包之间的引用成环: {0}")]
+    #[diagnostic(
+        code(rplc::packet_ref::cycle),
+        help("结构体不能直接或间接地包含自身，请把环上的某个字段改为基础类型")
+    )]
+    PacketReferenceCycle(String),
+
+    #[error("字段 '{0}' 引用了其他包，但所在的包含有位域")]
+    #[diagnostic(
+        code(rplc::packet_ref::bitstream),
+        help("含位域的包按位流排列，请把引用的包拆成基础类型字段，或把位域移到被引用的包中")
+    )]
+    PacketReferenceInBitstream(String),
+
+    #[error("字段 '{0}' 的 {1} 无效：{2}")]
+    #[diagnostic(
+        code(rplc::limit::invalid),
+        help("min、max 与 typical 必须是该字段类型可以表示的数，并满足 min ≤ typical ≤ max")
+    )]
+    InvalidFieldLimit(String, String, String),
+
+    #[error("字段 '{0}' 的 values 无效：{1}")]
+    #[diagnostic(
+        code(rplc::values::invalid),
+        help("values 的键是该字段类型可以表示的整数（十进制或 0x 十六进制），值是非空的显示名称")
+    )]
+    InvalidFieldValues(String, String),
+
+    #[error("字段 '{0}' 的偏移无效：{1}")]
+    #[diagnostic(
+        code(rplc::offset::invalid),
+        help("offset 是字段在包中的起始字节，必须按字段顺序递增，空隙会由保留字节填充")
+    )]
+    InvalidFieldOffset(String, String),
+
+    #[error("保留条目无效：{0}")]
+    #[diagnostic(
+        code(rplc::reserved::invalid),
+        help(
+            "保留条目写作 {{\"reserved\": 字节数}}，可以附带 comment，展开为匿名的 uint8_t 数组 _reserved<序号>"
+        )
+    )]
+    InvalidReserved(String),
+
+    #[error("协议版本号 '{0}' 格式错误")]
+    #[diagnostic(
+        code(rplc::invalid_protocol_version),
+        help("协议版本号应为语义化版本格式 'MAJOR.MINOR.PATCH'，例如 '2.3.0'")
+    )]
+    InvalidProtocolVersion(String),
+
+    #[error("数组字段 '{0}' 使包的载荷达到 {1} 字节，超出了载荷上限 {2} 字节")]
+    #[diagnostic(
+        code(rplc::array::payload_budget),
+        help(
+            "请减小数组长度或将数据拆分到多个包中；帧头的载荷长度字段为 uint16_t，单包载荷不能超过 65535 字节"
+        )
+    )]
+    ArrayExceedsPayloadBudget(String, u64, u32),
+
+    #[error("JSON 语法错误: {0}")]
+    #[diagnostic(code(rplc::json::syntax))]
+    InvalidJson(String),
+
+    #[error("YAML 语法错误: {0}")]
+    #[diagnostic(
+        code(rplc::yaml::syntax),
+        help(
+            "YAML 定义只能包含一个文档，映射的键必须是标量；十进制写法的 command_id 等字符串值需要加引号"
+        )
+    )]
+    InvalidYaml(String),
+
+    #[error("TOML 语法错误: {0}")]
+    #[diagnostic(
+        code(rplc::toml::syntax),
+        help("字段列表可以写成 [[fields]] 表数组或内联表数组；TOML 不允许重复定义同一个键")
+    )]
+    InvalidToml(String),
+
+    #[error("键 '{0}' 重复定义，第一次出现在第 {1} 行")]
+    #[diagnostic(
+        code(rplc::json::duplicate_key),
+        help("JSON 解析时只会保留最后一个值，重复的键通常是合并冲突留下的；请删除多余的一个")
+    )]
+    DuplicateKey(String, usize),
+
+    #[error("rplc_schema '{0}' 不受支持")]
+    #[diagnostic(
+        code(rplc::schema::unsupported),
+        help(
+            "rplc_schema 必须是 1 到 {1} 之间的整数，更新的格式需要升级 rplc；省略时按版本 1 处理"
+        )
+    )]
+    UnsupportedSchemaVersion(String, u32),
+
+    #[error("{0}，rplc_schema {1} 不再支持这种写法")]
+    #[diagnostic(
+        code(rplc::schema::removed),
+        help("运行 `rplc migrate <FILE>` 自动升级定义文件")
+    )]
+    RemovedInSchema(String, u32),
+
+    #[error("输入文件是 {0} 编码")]
+    #[diagnostic(
+        code(rplc::input::utf16),
+        help("rplc 只接受 UTF-8 编码的 JSON（可以带 BOM）；请在编辑器中将文件另存为 UTF-8")
+    )]
+    Utf16Input(String),
+
+    #[error("输入文件不是有效的 UTF-8：第 {0} 字节处的字节序列无效")]
+    #[diagnostic(
+        code(rplc::input::invalid_utf8),
+        help("请确认文件以 UTF-8 保存；GBK 等本地编码的中文注释需要先转换为 UTF-8")
+    )]
+    InvalidUtf8(usize),
+
+    #[error("输入大小为 {0} 字节，超过了 {1} 字节的上限")]
+    #[diagnostic(
+        code(rplc::input::too_large),
+        help("将定义拆分为多个文件，或调高输入大小上限（命令行的 --max-input-size）")
+    )]
+    InputTooLarge(usize, usize),
+
+    #[error("此文件包含 {0} 个包，但当前为单包模式")]
+    #[diagnostic(
+        code(rplc::multi_packet_input),
+        help(
+            "请传入 --multi 或去掉单包设置以自动检测；通过库调用时请使用 validate_multiple / generate_multiple"
+        )
+    )]
+    MultiPacketInput(usize),
+
+    #[error("fields 必须是字段对象组成的数组")]
+    #[diagnostic(code(rplc::fields::invalid))]
+    InvalidFields,
+
+    #[error("对齐值 '{0}' 无效")]
+    #[diagnostic(
+        code(rplc::align::invalid),
+        help("align 必须是不超过 4096 的 2 的幂，例如 4 或 8")
+    )]
+    InvalidAlign(String),
+
+    #[error("位序 '{0}' 无效")]
+    #[diagnostic(
+        code(rplc::bit_field::invalid_layout),
+        help("bitfield_layout 必须是 \"lsb_first\" 或 \"msb_first\"")
+    )]
+    InvalidBitFieldLayout(String),
+
+    #[error("字节序 '{0}' 无效")]
+    #[diagnostic(
+        code(rplc::endianness::invalid),
+        help("endianness 必须是 \"little\" 或 \"big\"")
+    )]
+    InvalidEndianness(String),
+
+    #[error("字段 '{0}' 不能声明 endianness：{1}")]
+    #[diagnostic(
+        code(rplc::endianness::unsupported_field),
+        help("endianness 只适用于多字节的标量与数组字段，删除该字段的 endianness")
+    )]
+    FieldEndiannessUnsupported(String, String),
+
+    #[error("字段 '{0}' 的类型 {1} 只有一个字节，endianness 不起作用")]
+    #[diagnostic(
+        code(rplc::endianness::redundant),
+        help("单字节字段没有字节序之分，删除该字段的 endianness")
+    )]
+    RedundantEndianness(String, String),
+
+    #[error("字段 '{0}' 不支持生成 serialize 函数: {1}")]
+    #[diagnostic(
+        code(rplc::serialize::unsupported_field),
+        help(
+            "serialize 逐个标量按字节序读写；位域请改用 bitfield_layout 生成的 Wire 访问函数，long double 请改用 double"
+        )
+    )]
+    SerializeUnsupportedField(String, String),
+
+    #[error("{0} 至少需要 {1}，当前选择的是 {2}")]
+    #[diagnostic(
+        code(rplc::cpp_standard::unsupported),
+        help("请提高 --cpp-standard，或不使用 {0}")
+    )]
+    CppStandardTooOld(String, String, String),
+
+    #[error("帧校验方式 '{0}' 无效")]
+    #[diagnostic(
+        code(rplc::crc::invalid),
+        help("crc 目前只支持 \"dji\"（裁判系统协议的帧头 CRC8 与整帧 CRC16）")
+    )]
+    InvalidCrc(String),
+
+    #[error("包 '{0}' 的数据段为 {1} 字节，超过了裁判系统帧 {2} 字节的数据段上限")]
+    #[diagnostic(
+        code(rplc::crc::payload_too_large),
+        help(
+            "裁判系统一帧最长 128 字节，去掉帧头、命令码与 CRC16 后数据段最多 119 字节；请拆分为多个包"
+        )
+    )]
+    CrcPayloadTooLarge(String, u64, u32),
+
+    #[error("校验字段 '{0}' 必须是包的最后一个字段")]
+    #[diagnostic(
+        code(rplc::crc::field_not_last),
+        help("CRC16 位于帧尾，请把校验字段移到 fields 的末尾")
+    )]
+    ChecksumFieldNotLast(String),
+
+    #[error("校验字段 '{0}' 的类型 '{1}' 与 CRC16 的宽度不一致")]
+    #[diagnostic(
+        code(rplc::crc::field_width),
+        help("DJI 协议的帧尾是 16 位 CRC，校验字段的类型必须是 uint16_t")
+    )]
+    ChecksumFieldWidth(String, String),
+
+    #[error("校验字段 '{0}' 与自动追加的 CRC16 重复")]
+    #[diagnostic(
+        code(rplc::crc::field_duplicated),
+        help(
+            "crc_append 为 true 时帧尾会再追加一个 CRC16；由字段承载帧尾时请设置 \"crc_append\": false，否则删除该字段"
+        )
+    )]
+    ChecksumFieldDuplicated(String),
+
+    #[error("包 '{0}' 设置了 \"crc_append\": false，但没有承载 CRC16 的校验字段")]
+    #[diagnostic(
+        code(rplc::crc::field_missing),
+        help(
+            "在 fields 末尾添加 {{ \"name\": \"crc\", \"type\": \"uint16_t\" }}，或删除 crc_append"
+        )
+    )]
+    ChecksumFieldMissing(String),
+
+    #[error("对齐值 {0} 小于结构体的自然对齐 {1}")]
+    #[diagnostic(
+        code(rplc::align::below_natural),
+        help("非紧凑结构体的 alignas 不能降低对齐要求，请增大 align 或启用 packed")
+    )]
+    AlignBelowNatural(u64, u32),
+
+    #[error("期望大小 '{0}' 无效")]
+    #[diagnostic(
+        code(rplc::expected_size::invalid),
+        help("expected_size 必须是正整数，单位为字节")
+    )]
+    InvalidExpectedSize(String),
+
+    #[error("包 '{0}' 的大小为 {2} 字节，与 expected_size 声明的 {1} 字节不符")]
+    #[diagnostic(
+        code(rplc::expected_size::mismatch),
+        help("检查字段的类型与数组长度、packed 以及对齐填充，或用 rplc layout 查看各字段的偏移")
+    )]
+    ExpectedSizeMismatch(String, u32, u32),
+
+    #[error("includes 中的 '{0}' 无效: {1}")]
+    #[diagnostic(
+        code(rplc::includes::invalid),
+        help(
+            "includes 是头文件的数组，\"my_types.h\" 生成 #include \"my_types.h\"，\"<stdint.h>\" 按原样生成 #include <stdint.h>"
+        )
+    )]
+    InvalidInclude(String, String),
+
+    #[error("字段 ID '{0}' 无效")]
+    #[diagnostic(
+        code(rplc::field_id::invalid),
+        help("id 与 retired_field_ids 中的 ID 必须是 0 到 4294967295 之间的整数")
+    )]
+    InvalidFieldId(String),
+
+    #[error("字段 '{0}' 的 ID {1} 已被字段 '{2}' 使用")]
+    #[diagnostic(
+        code(rplc::field_id::duplicate),
+        help("同一个包中每个字段的 ID 必须唯一，请为新字段分配一个未使用过的 ID")
+    )]
+    DuplicateFieldId(String, u32, String),
+
+    #[error("字段 '{0}' 使用了已停用的 ID {1}")]
+    #[diagnostic(
+        code(rplc::field_id::retired),
+        help("retired_field_ids 中的 ID 属于已删除的字段，旧日志仍按它解析，不能再分配给其他字段")
+    )]
+    RetiredFieldIdReused(String, u32),
+
+    #[error("包中的其他字段都有 id，字段 '{0}' 缺少 id")]
+    #[diagnostic(
+        code(rplc::field_id::missing),
+        help("按 ID 记录的日志无法识别没有 ID 的字段，请为它分配一个未使用过的 ID")
+    )]
+    MissingFieldId(String),
+
+    #[error("traits_template 无效: {0}")]
+    #[diagnostic(
+        code(rplc::traits_template::invalid),
+        help(
+            "traits_template 必须是字符串，可用的占位符为 ${{name}}、${{cmd}}、${{size}} 与 ${{hash}}"
+        )
+    )]
+    InvalidTraitsTemplate(String),
+
+    #[error("分组条目无效: {0}")]
+    #[diagnostic(
+        code(rplc::section::invalid),
+        help("分组条目写作 {{ \"section\": \"标题\" }}，不能与字段的键混用")
+    )]
+    InvalidSection(String),
+
+    #[error("嵌套结构体 '{0}' 无效: {1}")]
+    #[diagnostic(
+        code(rplc::group::invalid),
+        help(
+            "嵌套结构体写作 {{ \"name\": \"成员名\", \"group\": [字段...] }}，省略 name 时生成匿名结构体"
+        )
+    )]
+    InvalidGroup(String, String),
+
+    #[error("嵌套结构体 '{0}' 的边界位于第 {1} 位，不在字节边界上")]
+    #[diagnostic(
+        code(rplc::group::bit_field_boundary),
+        help(
+            "位域不能跨越嵌套结构体的边界；请调整位宽使结构体前后的位域凑满整字节，或把这些位域移到同一个结构体中"
+        )
+    )]
+    GroupBitFieldBoundary(String, u64),
+
+    #[error("字段 '{0}' 的类型 '{1}' 无法在目标 {2} 中表示: {3}")]
+    #[diagnostic(
+        code(rplc::target::unsupported_type),
+        help("请改用所有目标语言都支持的类型，或从目标列表中移除 {2}")
+    )]
+    UnsupportedTypeForTarget(String, String, String, String),
+
+    #[error("字段 '{0}' 的类型 '{1}' 不在项目允许的类型列表中")]
+    #[diagnostic(
+        code(rplc::field_type::not_allowed),
+        help("请改用 rplc.toml 中 [types] allowed 列出的类型，或把该类型加入列表")
+    )]
+    TypeNotAllowed(String, String),
+
     // ---- Warnings ----
     #[error("Packet名称 '{0}' 建议使用大驼峰命名法 (PascalCase)")]
     #[diagnostic(
@@ -136,9 +557,206 @@ pub enum ValidationCode {
         help("注释不应为空，请添加有意义的描述")
     )]
     EmptyComment(String),
+
+    #[error("多语言注释的条目 '{0}' 不合法")]
+    #[diagnostic(
+        code(rplc::doc::localized_comment),
+        help("多语言注释的键应为语言代码（如 zh、en、zh-TW），值应为字符串")
+    )]
+    InvalidLocalizedComment(String),
+
+    #[error("从字段 '{0}' 开始的存储单元内有 {1} 个位域，超过了阈值 {2}")]
+    #[diagnostic(
+        severity(Warning),
+        code(rplc::bit_field::too_many_per_unit),
+        help(
+            "同一存储单元内的位域过多会在 Cortex-M 等平台上生成大量读-改-写代码，建议拆分为多个字段或使用整型掩码"
+        )
+    )]
+    TooManyBitFieldsPerUnit(String, usize, usize),
+
+    #[error("包中有 {0} 个窄位域与浮点字段混用，超过了阈值 {1}")]
+    #[diagnostic(
+        severity(Warning),
+        code(rplc::bit_field::mixed_with_float),
+        help("大量窄位域与浮点字段混用会导致访问路径变慢，建议将标志位集中到单独的包或整型字段中")
+    )]
+    TinyBitFieldsWithFloat(usize, usize),
+
+    #[error("紧凑结构体中的浮点字段 '{0}' 位于未对齐的偏移处（第 {1} 位）")]
+    #[diagnostic(
+        severity(Warning),
+        code(rplc::packed::unaligned_float),
+        help(
+            "在部分 ARM 内核上通过指针解引用未对齐的浮点成员会触发硬件异常；建议启用 volatile_view 通过 load 函数按字节访问，或手动插入填充字段使其按 {2} 字节对齐"
+        )
+    )]
+    UnalignedFloatInPacked(String, u64, u32),
+
+    #[error("字段 '{0}' 的位域限定符写成了字符串")]
+    #[diagnostic(
+        severity(Warning),
+        code(rplc::bit_field::string_value),
+        help("建议改为数字形式: \"bit_field\": {1}")
+    )]
+    BitFieldAsString(String, i64),
+
+    #[error("位域 '{0}' 与 '{1}' 的基础类型大小不同，{2} 会为 '{1}' 分配新的存储单元")]
+    #[diagnostic(
+        severity(Warning),
+        code(rplc::compiler::mixed_bit_field_types),
+        help("结构体布局将与其他编译器不一致，建议相邻位域使用相同大小的基础类型")
+    )]
+    MixedBitFieldTypes(String, String, String),
+
+    #[error("位域 '{0}' 的类型 '{1}' 未显式声明符号，{2} 默认将其视为无符号")]
+    #[diagnostic(
+        severity(Warning),
+        code(rplc::compiler::plain_bit_field_signedness),
+        help("改用 int32_t、int16_t 等显式带符号的类型，或在编译选项中加入 --signed_bitfields")
+    )]
+    PlainBitFieldUnsigned(String, String, String),
+
+    #[error("包 '{0}' 声明的位序为 {1}，而 {2} 的原生位域按 {3} 分配")]
+    #[diagnostic(
+        severity(Warning),
+        code(rplc::compiler::bit_field_order),
+        help("结构体中的原生位域成员不符合声明的位序，请通过生成的 {0}Wire 访问函数读写线上数据")
+    )]
+    BitFieldOrderMismatch(String, String, String, String),
+
+    #[error("包 '{0}' 由 {1} 共同使用，原生位域的布局由各编译器自行决定")]
+    #[diagnostic(
+        severity(Warning),
+        code(rplc::compiler::native_bit_fields),
+        help(
+            "声明 bitfield_layout 并通过生成的 {0}Wire 访问函数按掩码读写，详见 `rplc explain rplc::compiler::native_bit_fields`"
+        )
+    )]
+    NativeBitFieldsAcrossCompilers(String, String),
+
+    #[error("包 '{0}' 关闭了 emit_traits，traits_template 不会被使用")]
+    #[diagnostic(
+        severity(Warning),
+        code(rplc::traits_template::unused),
+        help("删除 traits_template，或将 emit_traits 设为 true")
+    )]
+    UnusedTraitsTemplate(String),
+
+    #[error("分组 '{0}' 之后没有字段")]
+    #[diagnostic(
+        severity(Warning),
+        code(rplc::section::empty),
+        help("分组标题会被忽略，请删除该条目或在其后添加字段")
+    )]
+    EmptySection(String),
+
+    #[error("未知的键 '{0}'")]
+    #[diagnostic(
+        severity(Warning),
+        code(rplc::unknown_key),
+        help("未知的键会被忽略，请检查拼写")
+    )]
+    UnknownKey(String),
+}
+
+impl ValidationCode {
+    /// 校验器报告该诊断时使用的严重级别
+    pub fn default_severity(&self) -> Severity {
+        use ValidationCode::*;
+        match self {
+            InvalidPacketName(..)
+            | InvalidFieldName(..)
+            | KeywordCollision(..)
+            | GeneratedNameCollision(..)
+            | DuplicateFieldName(..)
+            | DuplicatePacketName(..)
+            | DuplicateCommandId(..)
+            | InvalidCommandId(..)
+            | InvalidFieldType(..)
+            | InvalidBitField(..)
+            | BitFieldOnInvalidType(..)
+            | BitFieldLengthOverflow(..)
+            | BitFieldStraddleBoundaryWithoutPacked(..)
+            | InvalidArrayType(..)
+            | BitFieldOnArray(..)
+            | InvalidArrayLen(..)
+            | ArrayLenConflict(..)
+            | InvalidEnum(..)
+            | DuplicateEnum(..)
+            | InvalidEnumerator(..)
+            | DuplicateEnumerator(..)
+            | EnumValueOutOfRange(..)
+            | PacketReferenceCycle(..)
+            | PacketReferenceInBitstream(..)
+            | InvalidFieldLimit(..)
+            | InvalidFieldValues(..)
+            | InvalidFieldOffset(..)
+            | InvalidReserved(..)
+            | InvalidProtocolVersion(..)
+            | ArrayExceedsPayloadBudget(..)
+            | InvalidJson(..)
+            | InvalidYaml(..)
+            | InvalidToml(..)
+            | DuplicateKey(..)
+            | UnsupportedSchemaVersion(..)
+            | RemovedInSchema(..)
+            | Utf16Input(..)
+            | InvalidUtf8(..)
+            | InputTooLarge(..)
+            | MultiPacketInput(..)
+            | InvalidFields
+            | InvalidAlign(..)
+            | InvalidBitFieldLayout(..)
+            | InvalidEndianness(..)
+            | FieldEndiannessUnsupported(..)
+            | SerializeUnsupportedField(..)
+            | CppStandardTooOld(..)
+            | InvalidCrc(..)
+            | ChecksumFieldNotLast(..)
+            | ChecksumFieldWidth(..)
+            | ChecksumFieldMissing(..)
+            | AlignBelowNatural(..)
+            | InvalidExpectedSize(..)
+            | ExpectedSizeMismatch(..)
+            | InvalidInclude(..)
+            | InvalidFieldId(..)
+            | DuplicateFieldId(..)
+            | RetiredFieldIdReused(..)
+            | InvalidTraitsTemplate(..)
+            | InvalidSection(..)
+            | InvalidGroup(..)
+            | GroupBitFieldBoundary(..)
+            | UnsupportedTypeForTarget(..)
+            | TypeNotAllowed(..)
+            | InvalidLocalizedComment(..) => Severity::Error,
+            NamingConventionPacket(..)
+            | NamingConventionField(..)
+            | MissingComment(..)
+            | BitFieldMissingPackedAttr(..)
+            | BitFieldStraddleBoundary(..)
+            | EmptyComment(..)
+            | TooManyBitFieldsPerUnit(..)
+            | TinyBitFieldsWithFloat(..)
+            | UnalignedFloatInPacked(..)
+            | BitFieldAsString(..)
+            | MixedBitFieldTypes(..)
+            | PlainBitFieldUnsigned(..)
+            | BitFieldOrderMismatch(..)
+            | NativeBitFieldsAcrossCompilers(..)
+            | UnusedTraitsTemplate(..)
+            | EmptySection(..)
+            | CrcPayloadTooLarge(..)
+            | ChecksumFieldDuplicated(..)
+            | DuplicateEnumValue(..)
+            | RedundantEndianness(..)
+            | MissingFieldId(..)
+            | UnknownKey(..) => Severity::Warning,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Error, Diagnostic, Serialize)]
+#[derive(Debug, Clone, PartialEq, Error, Diagnostic, Serialize)]
 #[error("{code}")]
 pub struct RplcDiagnostic {
     #[source]
@@ -147,6 +765,21 @@ pub struct RplcDiagnostic {
 
     pub severity: Severity,
     pub span: Option<Span>,
+
+    /// 针对这一处问题的建议，例如拼写错误时最接近的合法写法
+    #[help]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+}
+
+impl RplcDiagnostic {
+    /// 位置向后移动 `offset` 字节，用于把相对于包源文本的诊断换算到整个文档
+    pub(crate) fn offset(mut self, offset: usize) -> Self {
+        if let Some((start, _)) = &mut self.span {
+            *start += offset;
+        }
+        self
+    }
 }
 
 #[cfg(test)]
@@ -177,6 +810,15 @@ mod tests {
             ValidationCode::KeywordCollision("class".to_string()).to_string(),
             "字段名 'class' 是 C++ 保留关键字"
         );
+        assert_eq!(
+            ValidationCode::GeneratedNameCollision(
+                "k_yaw_max".to_string(),
+                "C++".to_string(),
+                "k_yaw_max_value".to_string()
+            )
+            .to_string(),
+            "字段名 'k_yaw_max' 与 C++ 生成代码中的同名成员冲突"
+        );
         assert_eq!(
             ValidationCode::DuplicateFieldName("duplicate".to_string()).to_string(),
             "字段名 'duplicate' 重复定义"
@@ -217,6 +859,7 @@ mod tests {
             code: ValidationCode::InvalidPacketName("BadName".to_string()),
             severity: Severity::Error,
             span: None,
+            help: None,
         };
         assert_eq!(error_diag.severity, Severity::Error);
         assert_eq!(
@@ -228,6 +871,7 @@ mod tests {
             code: ValidationCode::NamingConventionField("BadName".to_string()),
             severity: Severity::Warning,
             span: Some((0, 10)),
+            help: None,
         };
         assert_eq!(warning_diag.severity, Severity::Warning);
         assert_eq!(
@@ -242,6 +886,7 @@ mod tests {
             code: ValidationCode::InvalidFieldName("test_field".to_string()),
             severity: Severity::Error,
             span: Some((5, 15)),
+            help: None,
         };
         let cloned = original.clone();
 
@@ -307,6 +952,7 @@ mod tests {
             code: ValidationCode::InvalidBitField("bad_field".to_string()),
             severity: Severity::Error,
             span: None,
+            help: None,
         };
         assert_eq!(error_diag.severity, Severity::Error);
         assert_eq!(
@@ -318,6 +964,7 @@ mod tests {
             code: ValidationCode::BitFieldMissingPackedAttr("warn_field".to_string()),
             severity: Severity::Warning,
             span: Some((10, 20)),
+            help: None,
         };
         assert_eq!(warning_diag.severity, Severity::Warning);
         assert_eq!(
@@ -340,6 +987,7 @@ mod tests {
             code: ValidationCode::EmptyComment("test_packet".to_string()),
             severity: Severity::Warning,
             span: Some((0, 5)),
+            help: None,
         };
         assert_eq!(warning_diag.severity, Severity::Warning);
         assert_eq!(
@@ -361,6 +1009,440 @@ mod tests {
             ValidationCode::BitFieldOnArray("array_field".to_string()).to_string(),
             "数组字段 'array_field' 不能使用位域限定符"
         );
+
+        assert_eq!(
+            ValidationCode::InvalidArrayLen("data".to_string()).to_string(),
+            "字段 'data' 的 array_len 无效"
+        );
+        let code = ValidationCode::ArrayLenConflict("data".to_string(), "uint8_t[4]".to_string());
+        assert_eq!(code.default_severity(), Severity::Error);
+        assert_eq!(
+            code.to_string(),
+            "字段 'data' 同时设置了 array_len 与数组类型 'uint8_t[4]'"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_packet_reference_errors() {
+        let code = ValidationCode::PacketReferenceCycle("Gimbal → Imu → Gimbal".to_string());
+        assert_eq!(code.default_severity(), Severity::Error);
+        assert_eq!(code.to_string(), "包之间的引用成环: Gimbal → Imu → Gimbal");
+        let code = ValidationCode::PacketReferenceInBitstream("imu".to_string());
+        assert_eq!(code.default_severity(), Severity::Error);
+        assert_eq!(
+            code.to_string(),
+            "字段 'imu' 引用了其他包，但所在的包含有位域"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_enum_errors() {
+        let code = ValidationCode::EnumValueOutOfRange(
+            "Mode".to_string(),
+            "fast".to_string(),
+            "300".to_string(),
+            "uint8_t".to_string(),
+        );
+        assert_eq!(code.default_severity(), Severity::Error);
+        assert_eq!(
+            code.to_string(),
+            "枚举 'Mode' 中 'fast' 的取值 300 超出了底层类型 uint8_t 的范围"
+        );
+        let code = ValidationCode::DuplicateEnumValue(
+            "Mode".to_string(),
+            "run".to_string(),
+            "start".to_string(),
+        );
+        assert_eq!(code.default_severity(), Severity::Warning);
+        assert_eq!(
+            code.to_string(),
+            "枚举 'Mode' 中 'run' 与 'start' 的取值相同"
+        );
+        assert_eq!(
+            ValidationCode::InvalidEnumerator(
+                "Mode".to_string(),
+                "1st".to_string(),
+                "名称必须是合法的 C++ 标识符".to_string()
+            )
+            .to_string(),
+            "枚举 'Mode' 的枚举项 '1st' 无效：名称必须是合法的 C++ 标识符"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_bit_field_density_warnings() {
+        assert_eq!(
+            ValidationCode::TooManyBitFieldsPerUnit("flag0".to_string(), 10, 8).to_string(),
+            "从字段 'flag0' 开始的存储单元内有 10 个位域，超过了阈值 8"
+        );
+        assert_eq!(
+            ValidationCode::TinyBitFieldsWithFloat(6, 4).to_string(),
+            "包中有 6 个窄位域与浮点字段混用，超过了阈值 4"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_array_exceeds_payload_budget() {
+        assert_eq!(
+            ValidationCode::ArrayExceedsPayloadBudget("samples".to_string(), 300, 256).to_string(),
+            "数组字段 'samples' 使包的载荷达到 300 字节，超出了载荷上限 256 字节"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_invalid_json() {
+        assert_eq!(
+            ValidationCode::InvalidJson("EOF while parsing".to_string()).to_string(),
+            "JSON 语法错误: EOF while parsing"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_multi_packet_input() {
+        assert_eq!(
+            ValidationCode::MultiPacketInput(3).to_string(),
+            "此文件包含 3 个包，但当前为单包模式"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_invalid_fields() {
+        assert_eq!(
+            ValidationCode::InvalidFields.to_string(),
+            "fields 必须是字段对象组成的数组"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_align_errors() {
+        assert_eq!(
+            ValidationCode::InvalidAlign("3".to_string()).to_string(),
+            "对齐值 '3' 无效"
+        );
+        assert_eq!(
+            ValidationCode::AlignBelowNatural(2, 4).to_string(),
+            "对齐值 2 小于结构体的自然对齐 4"
+        );
+        assert_eq!(
+            ValidationCode::ExpectedSizeMismatch("Imu".to_string(), 24, 28).to_string(),
+            "包 'Imu' 的大小为 28 字节，与 expected_size 声明的 24 字节不符"
+        );
+        assert_eq!(
+            ValidationCode::DuplicateFieldId("pitch".to_string(), 2, "yaw".to_string()).to_string(),
+            "字段 'pitch' 的 ID 2 已被字段 'yaw' 使用"
+        );
+        assert_eq!(
+            ValidationCode::MissingFieldId("pitch".to_string()).default_severity(),
+            Severity::Warning
+        );
+    }
+
+    #[test]
+    fn test_validation_code_unaligned_float_in_packed() {
+        assert_eq!(
+            ValidationCode::UnalignedFloatInPacked("yaw".to_string(), 8, 4).to_string(),
+            "紧凑结构体中的浮点字段 'yaw' 位于未对齐的偏移处（第 8 位）"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_bit_field_as_string() {
+        let code = ValidationCode::BitFieldAsString("flags".to_string(), 4);
+        assert_eq!(code.to_string(), "字段 'flags' 的位域限定符写成了字符串");
+        assert_eq!(
+            code.help().unwrap().to_string(),
+            "建议改为数字形式: \"bit_field\": 4"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_mixed_bit_field_types() {
+        assert_eq!(
+            ValidationCode::MixedBitFieldTypes(
+                "mode".to_string(),
+                "level".to_string(),
+                "MSVC".to_string()
+            )
+            .to_string(),
+            "位域 'mode' 与 'level' 的基础类型大小不同，MSVC 会为 'level' 分配新的存储单元"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_invalid_field_limit() {
+        let code = ValidationCode::InvalidFieldLimit(
+            "speed".to_string(),
+            "max".to_string(),
+            "300 超出了 uint8_t 的取值范围 0..=255".to_string(),
+        );
+        assert_eq!(
+            code.to_string(),
+            "字段 'speed' 的 max 无效：300 超出了 uint8_t 的取值范围 0..=255"
+        );
+        assert_eq!(code.code().unwrap().to_string(), "rplc::limit::invalid");
+    }
+
+    #[test]
+    fn test_validation_code_invalid_field_values() {
+        let code =
+            ValidationCode::InvalidFieldValues("mode".to_string(), "'idle' 不是整数".to_string());
+        assert_eq!(
+            code.to_string(),
+            "字段 'mode' 的 values 无效：'idle' 不是整数"
+        );
+        assert_eq!(code.code().unwrap().to_string(), "rplc::values::invalid");
+    }
+
+    #[test]
+    fn test_validation_code_plain_bit_field_unsigned() {
+        assert_eq!(
+            ValidationCode::PlainBitFieldUnsigned(
+                "offset".to_string(),
+                "int".to_string(),
+                "ARMCC 5".to_string()
+            )
+            .to_string(),
+            "位域 'offset' 的类型 'int' 未显式声明符号，ARMCC 5 默认将其视为无符号"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_traits_template() {
+        let code = ValidationCode::InvalidTraitsTemplate("未知的占位符 ${id}".to_string());
+        assert_eq!(code.to_string(), "traits_template 无效: 未知的占位符 ${id}");
+        assert_eq!(
+            code.help().unwrap().to_string(),
+            "traits_template 必须是字符串，可用的占位符为 ${name}、${cmd}、${size} 与 ${hash}"
+        );
+        assert_eq!(
+            ValidationCode::UnusedTraitsTemplate("Status".to_string()).to_string(),
+            "包 'Status' 关闭了 emit_traits，traits_template 不会被使用"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_sections() {
+        let code = ValidationCode::InvalidSection("分组标题必须是非空字符串".to_string());
+        assert_eq!(code.to_string(), "分组条目无效: 分组标题必须是非空字符串");
+        assert_eq!(
+            code.help().unwrap().to_string(),
+            "分组条目写作 { \"section\": \"标题\" }，不能与字段的键混用"
+        );
+        assert_eq!(
+            ValidationCode::EmptySection("IMU".to_string()).to_string(),
+            "分组 'IMU' 之后没有字段"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_cross_packet() {
+        let code = ValidationCode::DuplicatePacketName("Status".to_string());
+        assert_eq!(code.to_string(), "Packet名称 'Status' 在文档中重复出现");
+        assert_eq!(code.code().unwrap().to_string(), "rplc::duplicate_packet");
+
+        let code = ValidationCode::DuplicateCommandId("0x0101".to_string(), "Status".to_string());
+        assert_eq!(code.to_string(), "Command ID 0x0101 已被包 'Status' 使用");
+        assert_eq!(code.code().unwrap().to_string(), "rplc::duplicate_cmd_id");
+    }
+
+    #[test]
+    fn test_validation_code_duplicate_key() {
+        let code = ValidationCode::DuplicateKey("type".to_string(), 4);
+        assert_eq!(code.to_string(), "键 'type' 重复定义，第一次出现在第 4 行");
+        assert_eq!(
+            code.code().unwrap().to_string(),
+            "rplc::json::duplicate_key"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_schema() {
+        let code = ValidationCode::UnsupportedSchemaVersion("3".to_string(), 2);
+        assert_eq!(code.to_string(), "rplc_schema '3' 不受支持");
+        assert_eq!(
+            code.help().unwrap().to_string(),
+            "rplc_schema 必须是 1 到 2 之间的整数，更新的格式需要升级 rplc；省略时按版本 1 处理"
+        );
+        assert_eq!(
+            ValidationCode::RemovedInSchema("字段 'mode' 的位域写成了字符串".to_string(), 2)
+                .to_string(),
+            "字段 'mode' 的位域写成了字符串，rplc_schema 2 不再支持这种写法"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_input_encoding() {
+        let code = ValidationCode::Utf16Input("UTF-16 LE".to_string());
+        assert_eq!(code.to_string(), "输入文件是 UTF-16 LE 编码");
+        assert_eq!(
+            code.help().unwrap().to_string(),
+            "rplc 只接受 UTF-8 编码的 JSON（可以带 BOM）；请在编辑器中将文件另存为 UTF-8"
+        );
+        assert_eq!(
+            ValidationCode::InvalidUtf8(12).to_string(),
+            "输入文件不是有效的 UTF-8：第 12 字节处的字节序列无效"
+        );
+        assert_eq!(
+            ValidationCode::InputTooLarge(2_000_000, 1_048_576).to_string(),
+            "输入大小为 2000000 字节，超过了 1048576 字节的上限"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_field_endianness() {
+        let code = ValidationCode::RedundantEndianness("mode".to_string(), "uint8_t".to_string());
+        assert_eq!(code.default_severity(), Severity::Warning);
+        assert_eq!(
+            code.to_string(),
+            "字段 'mode' 的类型 uint8_t 只有一个字节，endianness 不起作用"
+        );
+        let code = ValidationCode::FieldEndiannessUnsupported(
+            "flag".to_string(),
+            "位域成员没有独立的字节位置".to_string(),
+        );
+        assert_eq!(code.default_severity(), Severity::Error);
+        assert_eq!(
+            code.to_string(),
+            "字段 'flag' 不能声明 endianness：位域成员没有独立的字节位置"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_serialize() {
+        assert_eq!(
+            ValidationCode::InvalidEndianness("network".to_string()).to_string(),
+            "字节序 'network' 无效"
+        );
+        let code = ValidationCode::SerializeUnsupportedField(
+            "mode".to_string(),
+            "位域成员没有独立的字节位置".to_string(),
+        );
+        assert_eq!(
+            code.to_string(),
+            "字段 'mode' 不支持生成 serialize 函数: 位域成员没有独立的字节位置"
+        );
+        assert_eq!(code.default_severity(), Severity::Error);
+    }
+
+    #[test]
+    fn test_validation_code_crc() {
+        let code = ValidationCode::CrcPayloadTooLarge("MapData".to_string(), 120, 119);
+        assert_eq!(
+            code.to_string(),
+            "包 'MapData' 的数据段为 120 字节，超过了裁判系统帧 119 字节的数据段上限"
+        );
+        assert_eq!(code.default_severity(), Severity::Warning);
+        assert_eq!(
+            ValidationCode::ChecksumFieldWidth("crc".to_string(), "uint8_t".to_string())
+                .to_string(),
+            "校验字段 'crc' 的类型 'uint8_t' 与 CRC16 的宽度不一致"
+        );
+        assert_eq!(
+            ValidationCode::ChecksumFieldDuplicated("crc".to_string()).default_severity(),
+            Severity::Warning
+        );
+        assert_eq!(
+            ValidationCode::ChecksumFieldMissing("Status".to_string()).default_severity(),
+            Severity::Error
+        );
+    }
+
+    #[test]
+    fn test_validation_code_groups() {
+        let code =
+            ValidationCode::InvalidGroup("imu".to_string(), "只能用于紧凑结构体".to_string());
+        assert_eq!(
+            code.to_string(),
+            "嵌套结构体 'imu' 无效: 只能用于紧凑结构体"
+        );
+        assert_eq!(
+            code.help().unwrap().to_string(),
+            "嵌套结构体写作 { \"name\": \"成员名\", \"group\": [字段...] }，省略 name 时生成匿名结构体"
+        );
+        assert_eq!(
+            ValidationCode::GroupBitFieldBoundary("匿名".to_string(), 3).to_string(),
+            "嵌套结构体 '匿名' 的边界位于第 3 位，不在字节边界上"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_invalid_include() {
+        let code =
+            ValidationCode::InvalidInclude("<stdint.h".to_string(), "尖括号不成对".to_string());
+        assert_eq!(
+            code.to_string(),
+            "includes 中的 '<stdint.h' 无效: 尖括号不成对"
+        );
+        assert_eq!(code.default_severity(), Severity::Error);
+    }
+
+    #[test]
+    fn test_validation_code_native_bit_fields_across_compilers() {
+        let code = ValidationCode::NativeBitFieldsAcrossCompilers(
+            "Status".to_string(),
+            "ARMCC 5、MSVC".to_string(),
+        );
+        assert_eq!(
+            code.to_string(),
+            "包 'Status' 由 ARMCC 5、MSVC 共同使用，原生位域的布局由各编译器自行决定"
+        );
+        assert_eq!(code.default_severity(), Severity::Warning);
+        assert!(
+            code.help()
+                .unwrap()
+                .to_string()
+                .contains("rplc explain rplc::compiler::native_bit_fields")
+        );
+    }
+
+    #[test]
+    fn test_validation_code_cpp_standard_too_old() {
+        let code = ValidationCode::CppStandardTooOld(
+            "serialize".to_string(),
+            "C++17".to_string(),
+            "C++14".to_string(),
+        );
+        assert_eq!(
+            code.to_string(),
+            "serialize 至少需要 C++17，当前选择的是 C++14"
+        );
+        assert_eq!(code.default_severity(), Severity::Error);
+    }
+
+    #[test]
+    fn test_validation_code_unsupported_type_for_target() {
+        assert_eq!(
+            ValidationCode::UnsupportedTypeForTarget(
+                "value".to_string(),
+                "long double".to_string(),
+                "Python".to_string(),
+                "struct 模块没有 long double 对应的格式字符".to_string()
+            )
+            .to_string(),
+            "字段 'value' 的类型 'long double' 无法在目标 Python 中表示: struct 模块没有 long double 对应的格式字符"
+        );
+    }
+
+    #[test]
+    fn test_validation_code_type_not_allowed() {
+        let code = ValidationCode::TypeNotAllowed("yaw".to_string(), "double".to_string());
+        assert_eq!(
+            code.to_string(),
+            "字段 'yaw' 的类型 'double' 不在项目允许的类型列表中"
+        );
+        assert_eq!(
+            code.code().unwrap().to_string(),
+            "rplc::field_type::not_allowed"
+        );
+        assert_eq!(code.default_severity(), Severity::Error);
+    }
+
+    #[test]
+    fn test_validation_code_invalid_protocol_version() {
+        assert_eq!(
+            ValidationCode::InvalidProtocolVersion("2.x".to_string()).to_string(),
+            "协议版本号 '2.x' 格式错误"
+        );
     }
 
     #[test]
@@ -369,6 +1451,7 @@ mod tests {
             code: ValidationCode::InvalidArrayType("bad_array".to_string()),
             severity: Severity::Error,
             span: Some((0, 10)),
+            help: None,
         };
         assert_eq!(invalid_array_diag.severity, Severity::Error);
         assert_eq!(
@@ -380,6 +1463,7 @@ mod tests {
             code: ValidationCode::BitFieldOnArray("array_field".to_string()),
             severity: Severity::Error,
             span: Some((15, 25)),
+            help: None,
         };
         assert_eq!(bitfield_on_array_diag.severity, Severity::Error);
         assert_eq!(
@@ -0,0 +1,977 @@
+use std::fmt;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::canonical::canonicalize;
+use crate::config::{BitOrder, Config, ConfigOrArray, Field, ProtocolVersion};
+use crate::fingerprint::layout_hash;
+use crate::layout::{PacketLayout, c_type_size, compute_layout};
+use crate::validator::parse_command_id;
+
+/// 单个变更对线上协议兼容性的影响
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Compatibility {
+    /// 不影响内存布局，例如注释修改
+    Cosmetic,
+    /// 仅在包尾部追加内容，旧版本可以按前缀解析
+    Additive,
+    /// 破坏线上布局，新旧版本无法互通
+    Breaking,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub enum ChangeKind {
+    PacketAdded,
+    PacketRemoved,
+    CommandIdChanged {
+        old: String,
+        new: String,
+    },
+    PackedChanged {
+        old: bool,
+        new: bool,
+    },
+    FieldAdded,
+    FieldRemoved,
+    FieldMoved {
+        old_index: usize,
+        new_index: usize,
+    },
+    FieldTypeChanged {
+        old: String,
+        new: String,
+    },
+    FieldBitFieldChanged {
+        old: Option<u8>,
+        new: Option<u8>,
+    },
+    FieldOffsetChanged {
+        old: Option<u32>,
+        new: Option<u32>,
+    },
+    FieldIdChanged {
+        old: Option<u32>,
+        new: Option<u32>,
+    },
+    /// 字段使用了上一版本中属于其他字段或已停用的 ID，`previous` 为原来的字段名
+    FieldIdReused {
+        id: u32,
+        previous: Option<String>,
+    },
+    /// 删除的字段或上一版本停用的 ID 没有记入 `retired_field_ids`
+    FieldIdNotRetired {
+        id: u32,
+    },
+    CommentChanged,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Change {
+    pub packet: String,
+    pub field: Option<String>,
+    pub kind: ChangeKind,
+    pub compatibility: Compatibility,
+}
+
+/// 两个版本的包定义之间的变更报告
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ChangeReport {
+    pub changes: Vec<Change>,
+}
+
+/// 变更所要求的最小版本号提升
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VersionBump {
+    None,
+    Minor,
+    Major,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum VersionBumpError {
+    #[error("协议存在破坏性变更，需要提升主版本号 ({old} -> {new})")]
+    MajorBumpRequired {
+        old: ProtocolVersion,
+        new: ProtocolVersion,
+    },
+    #[error("协议存在新增内容，至少需要提升次版本号 ({old} -> {new})")]
+    MinorBumpRequired {
+        old: ProtocolVersion,
+        new: ProtocolVersion,
+    },
+    #[error("协议版本号不能回退 ({old} -> {new})")]
+    VersionDecreased {
+        old: ProtocolVersion,
+        new: ProtocolVersion,
+    },
+}
+
+impl ChangeReport {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn has_breaking_changes(&self) -> bool {
+        self.changes
+            .iter()
+            .any(|c| c.compatibility == Compatibility::Breaking)
+    }
+
+    pub fn required_bump(&self) -> VersionBump {
+        match self.changes.iter().map(|c| c.compatibility).max() {
+            Some(Compatibility::Breaking) => VersionBump::Major,
+            Some(Compatibility::Additive) => VersionBump::Minor,
+            _ => VersionBump::None,
+        }
+    }
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compatibility::Cosmetic => write!(f, "兼容"),
+            Compatibility::Additive => write!(f, "新增"),
+            Compatibility::Breaking => write!(f, "破坏性"),
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] 包 '{}'", self.compatibility, self.packet)?;
+        if let Some(field) = &self.field {
+            write!(f, " 字段 '{}'", field)?;
+        }
+        match &self.kind {
+            ChangeKind::PacketAdded => write!(f, ": 新增"),
+            ChangeKind::PacketRemoved => write!(f, ": 被删除"),
+            ChangeKind::CommandIdChanged { old, new } => {
+                write!(f, ": Command ID 从 {} 变为 {}", old, new)
+            }
+            ChangeKind::PackedChanged { old, new } => {
+                write!(f, ": packed 从 {} 变为 {}", old, new)
+            }
+            ChangeKind::FieldAdded => write!(f, ": 新增"),
+            ChangeKind::FieldRemoved => write!(f, ": 被删除"),
+            ChangeKind::FieldMoved {
+                old_index,
+                new_index,
+            } => write!(f, ": 位置从 {} 移动到 {}", old_index, new_index),
+            ChangeKind::FieldTypeChanged { old, new } => {
+                write!(f, ": 类型从 {} 变为 {}", old, new)
+            }
+            ChangeKind::FieldBitFieldChanged { old, new } => write!(
+                f,
+                ": 位域从 {} 变为 {}",
+                old.map(|b| b.to_string())
+                    .unwrap_or_else(|| "无".to_string()),
+                new.map(|b| b.to_string())
+                    .unwrap_or_else(|| "无".to_string())
+            ),
+            ChangeKind::FieldOffsetChanged { old, new } => write!(
+                f,
+                ": 固定偏移从 {} 变为 {}",
+                old.map(|o| o.to_string())
+                    .unwrap_or_else(|| "无".to_string()),
+                new.map(|o| o.to_string())
+                    .unwrap_or_else(|| "无".to_string())
+            ),
+            ChangeKind::FieldIdChanged { old, new } => write!(
+                f,
+                ": ID 从 {} 变为 {}",
+                old.map(|id| id.to_string())
+                    .unwrap_or_else(|| "无".to_string()),
+                new.map(|id| id.to_string())
+                    .unwrap_or_else(|| "无".to_string())
+            ),
+            ChangeKind::FieldIdReused { id, previous } => match previous {
+                Some(previous) => write!(f, ": 使用了上一版本中字段 '{}' 的 ID {}", previous, id),
+                None => write!(f, ": 使用了已停用的 ID {}", id),
+            },
+            ChangeKind::FieldIdNotRetired { id } => {
+                write!(f, ": ID {} 没有记入 retired_field_ids", id)
+            }
+            ChangeKind::CommentChanged => write!(f, ": 注释修改"),
+        }
+    }
+}
+
+/// 比较两个 JSON 包定义（单包、多包数组或包集合）
+pub fn diff(old_json: &str, new_json: &str) -> Result<ChangeReport, serde_json::Error> {
+    let old = serde_json::from_str::<ConfigOrArray>(old_json)?.into_packets();
+    let new = serde_json::from_str::<ConfigOrArray>(new_json)?.into_packets();
+    Ok(diff_configs(&old, &new))
+}
+
+/// 按包名匹配包，按字段名匹配字段，计算变更报告
+///
+/// 比较前先规范化两侧的定义，命令 ID 或类型别名的不同写法不算变更
+pub fn diff_configs(old: &[Config], new: &[Config]) -> ChangeReport {
+    let mut report = ChangeReport::default();
+    let old: Vec<Config> = old.iter().cloned().map(canonicalize).collect();
+    let new: Vec<Config> = new.iter().cloned().map(canonicalize).collect();
+    let (old, new) = (old.as_slice(), new.as_slice());
+
+    for old_packet in old {
+        match new.iter().find(|p| p.packet_name == old_packet.packet_name) {
+            Some(new_packet) => diff_packet(old_packet, new_packet, &mut report.changes),
+            None => report.changes.push(Change {
+                packet: old_packet.packet_name.clone(),
+                field: None,
+                kind: ChangeKind::PacketRemoved,
+                compatibility: Compatibility::Breaking,
+            }),
+        }
+    }
+
+    for new_packet in new {
+        if !old.iter().any(|p| p.packet_name == new_packet.packet_name) {
+            report.changes.push(Change {
+                packet: new_packet.packet_name.clone(),
+                field: None,
+                kind: ChangeKind::PacketAdded,
+                compatibility: Compatibility::Additive,
+            });
+        }
+    }
+
+    report
+}
+
+/// 字段在包中的位偏移，布局无法计算时为 `None`
+fn field_offset_bits(layout: &Option<PacketLayout>, name: &str) -> Option<u32> {
+    layout
+        .as_ref()?
+        .fields
+        .iter()
+        .find(|field| field.name == name)
+        .map(|field| field.offset_bits)
+}
+
+fn diff_packet(old: &Config, new: &Config, changes: &mut Vec<Change>) {
+    let packet = new.packet_name.clone();
+    let mut push = |field: Option<&str>, kind, compatibility| {
+        changes.push(Change {
+            packet: packet.clone(),
+            field: field.map(str::to_string),
+            kind,
+            compatibility,
+        })
+    };
+
+    let same_cmd = match (
+        parse_command_id(&old.command_id),
+        parse_command_id(&new.command_id),
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => old.command_id.trim() == new.command_id.trim(),
+    };
+    if !same_cmd {
+        push(
+            None,
+            ChangeKind::CommandIdChanged {
+                old: old.command_id.clone(),
+                new: new.command_id.clone(),
+            },
+            Compatibility::Breaking,
+        );
+    }
+
+    if old.packed != new.packed {
+        push(
+            None,
+            ChangeKind::PackedChanged {
+                old: old.packed,
+                new: new.packed,
+            },
+            Compatibility::Breaking,
+        );
+    }
+
+    if old.comment != new.comment {
+        push(None, ChangeKind::CommentChanged, Compatibility::Cosmetic);
+    }
+
+    let (old_layout, new_layout) = (compute_layout(old), compute_layout(new));
+
+    // 旧字段全部按原顺序保留在新定义的开头时，尾部新增字段才是兼容的
+    let old_is_prefix = old.fields.len() <= new.fields.len()
+        && old
+            .fields
+            .iter()
+            .zip(&new.fields)
+            .all(|(a, b)| a.name == b.name);
+
+    for (old_index, old_field) in old.fields.iter().enumerate() {
+        let Some((new_index, new_field)) = find_field(&new.fields, &old_field.name) else {
+            push(
+                Some(&old_field.name),
+                ChangeKind::FieldRemoved,
+                Compatibility::Breaking,
+            );
+            continue;
+        };
+
+        if old_index != new_index && !old_is_prefix {
+            push(
+                Some(&old_field.name),
+                ChangeKind::FieldMoved {
+                    old_index,
+                    new_index,
+                },
+                Compatibility::Breaking,
+            );
+        }
+        if old_field.ty != new_field.ty {
+            push(
+                Some(&old_field.name),
+                ChangeKind::FieldTypeChanged {
+                    old: old_field.ty.clone(),
+                    new: new_field.ty.clone(),
+                },
+                Compatibility::Breaking,
+            );
+        }
+        if old_field.bit_field != new_field.bit_field {
+            push(
+                Some(&old_field.name),
+                ChangeKind::FieldBitFieldChanged {
+                    old: old_field.bit_field,
+                    new: new_field.bit_field,
+                },
+                Compatibility::Breaking,
+            );
+        }
+        if old_field.offset != new_field.offset {
+            // 只把字段原本所在的位置写成固定偏移时布局不变
+            let moved = field_offset_bits(&old_layout, &old_field.name)
+                != field_offset_bits(&new_layout, &new_field.name);
+            push(
+                Some(&old_field.name),
+                ChangeKind::FieldOffsetChanged {
+                    old: old_field.offset,
+                    new: new_field.offset,
+                },
+                if moved {
+                    Compatibility::Breaking
+                } else {
+                    Compatibility::Cosmetic
+                },
+            );
+        }
+        if old_field.id != new_field.id {
+            // 首次分配 ID 不影响已有的数据，修改或去掉 ID 会使按 ID 记录的旧数据无法识别
+            push(
+                Some(&old_field.name),
+                ChangeKind::FieldIdChanged {
+                    old: old_field.id,
+                    new: new_field.id,
+                },
+                if old_field.id.is_none() {
+                    Compatibility::Cosmetic
+                } else {
+                    Compatibility::Breaking
+                },
+            );
+        }
+        if old_field.comment != new_field.comment {
+            push(
+                Some(&old_field.name),
+                ChangeKind::CommentChanged,
+                Compatibility::Cosmetic,
+            );
+        }
+    }
+
+    for (new_index, new_field) in new.fields.iter().enumerate() {
+        if find_field(&old.fields, &new_field.name).is_none() {
+            let compatibility = if old_is_prefix && new_index >= old.fields.len() {
+                Compatibility::Additive
+            } else {
+                Compatibility::Breaking
+            };
+            push(Some(&new_field.name), ChangeKind::FieldAdded, compatibility);
+        }
+    }
+
+    diff_field_ids(old, new, &mut push);
+}
+
+/// 检查字段 ID 没有被重新分配：上一版本中属于其他字段或已停用的 ID 不能出现在新字段上，
+/// 删除的字段的 ID 必须记入 `retired_field_ids`
+fn diff_field_ids(
+    old: &Config,
+    new: &Config,
+    push: &mut impl FnMut(Option<&str>, ChangeKind, Compatibility),
+) {
+    for new_field in &new.fields {
+        let Some(id) = new_field.id else {
+            continue;
+        };
+        let previous = old
+            .fields
+            .iter()
+            .find(|field| field.id == Some(id) && field.name != new_field.name);
+        if previous.is_some() || old.retired_field_ids.contains(&id) {
+            push(
+                Some(&new_field.name),
+                ChangeKind::FieldIdReused {
+                    id,
+                    previous: previous.map(|field| field.name.clone()),
+                },
+                Compatibility::Breaking,
+            );
+        }
+    }
+
+    let in_use = |id: u32| new.fields.iter().any(|field| field.id == Some(id));
+    for old_field in &old.fields {
+        if let Some(id) = old_field.id
+            && find_field(&new.fields, &old_field.name).is_none()
+            && !in_use(id)
+            && !new.retired_field_ids.contains(&id)
+        {
+            push(
+                Some(&old_field.name),
+                ChangeKind::FieldIdNotRetired { id },
+                Compatibility::Breaking,
+            );
+        }
+    }
+    for &id in &old.retired_field_ids {
+        if !in_use(id) && !new.retired_field_ids.contains(&id) {
+            push(
+                None,
+                ChangeKind::FieldIdNotRetired { id },
+                Compatibility::Breaking,
+            );
+        }
+    }
+}
+
+fn find_field<'a>(fields: &'a [Field], name: &str) -> Option<(usize, &'a Field)> {
+    fields.iter().enumerate().find(|(_, f)| f.name == name)
+}
+
+/// CI 模式下检查协议版本号的提升是否覆盖了实际的变更
+pub fn check_version_bump(
+    old: ProtocolVersion,
+    new: ProtocolVersion,
+    report: &ChangeReport,
+) -> Result<(), VersionBumpError> {
+    if new < old {
+        return Err(VersionBumpError::VersionDecreased { old, new });
+    }
+    match report.required_bump() {
+        VersionBump::Major if new.major <= old.major => {
+            Err(VersionBumpError::MajorBumpRequired { old, new })
+        }
+        VersionBump::Minor if new.major == old.major && new.minor <= old.minor => {
+            Err(VersionBumpError::MinorBumpRequired { old, new })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 线上布局被改变的包，见 [`layout_breaks`]
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LayoutBreak {
+    pub packet: String,
+    pub kind: LayoutBreakKind,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub enum LayoutBreakKind {
+    PacketRemoved,
+    CommandIdChanged {
+        old: String,
+        new: String,
+    },
+    BitOrderChanged,
+    SizeChanged {
+        old: u32,
+        new: u32,
+    },
+    /// 第 `index` 个线上成员的类型、位置、长度或字节序不同，两侧缺少该成员时为 `None`
+    MemberChanged {
+        index: usize,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// 任意一侧的布局无法计算，不能确认线上数据相同
+    LayoutUnknown,
+}
+
+impl fmt::Display for LayoutBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "包 '{}'", self.packet)?;
+        match &self.kind {
+            LayoutBreakKind::PacketRemoved => write!(f, " 被删除"),
+            LayoutBreakKind::CommandIdChanged { old, new } => {
+                write!(f, " 的 Command ID 从 {} 变为 {}", old, new)
+            }
+            LayoutBreakKind::BitOrderChanged => write!(f, " 的位域位序改变"),
+            LayoutBreakKind::SizeChanged { old, new } => {
+                write!(f, " 的大小从 {} 字节变为 {} 字节", old, new)
+            }
+            LayoutBreakKind::MemberChanged { index, old, new } => write!(
+                f,
+                " 的第 {} 个成员从 {} 变为 {}",
+                index + 1,
+                old.as_deref().unwrap_or("无"),
+                new.as_deref().unwrap_or("无")
+            ),
+            LayoutBreakKind::LayoutUnknown => write!(f, " 的布局无法计算"),
+        }
+    }
+}
+
+/// 检查旧版本的每个包在新版本中的线上布局是否保持不变，用于无法升级固件的发布场景
+///
+/// 规范化后的布局哈希相同即视为不变；哈希不同时（例如字段改名、注释以外的元数据变化）
+/// 再按布局引擎逐个比较成员的类型、位偏移、位长与字节序，字段名不参与比较。
+/// 新增的包不影响已有固件，不算破坏
+pub fn layout_breaks(old: &[Config], new: &[Config]) -> Vec<LayoutBreak> {
+    let mut breaks = Vec::new();
+    for old_packet in old {
+        let packet = old_packet.packet_name.clone();
+        let Some(new_packet) = new.iter().find(|p| p.packet_name == packet) else {
+            breaks.push(LayoutBreak {
+                packet,
+                kind: LayoutBreakKind::PacketRemoved,
+            });
+            continue;
+        };
+        if layout_hash(old_packet) == layout_hash(new_packet) {
+            continue;
+        }
+        if let Some(kind) = wire_difference(
+            &canonicalize(old_packet.clone()),
+            &canonicalize(new_packet.clone()),
+        ) {
+            breaks.push(LayoutBreak { packet, kind });
+        }
+    }
+    breaks
+}
+
+/// 两个同名包在线上的第一处差异
+fn wire_difference(old: &Config, new: &Config) -> Option<LayoutBreakKind> {
+    let same_cmd = match (
+        parse_command_id(&old.command_id),
+        parse_command_id(&new.command_id),
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => old.command_id.trim() == new.command_id.trim(),
+    };
+    if !same_cmd {
+        return Some(LayoutBreakKind::CommandIdChanged {
+            old: old.command_id.clone(),
+            new: new.command_id.clone(),
+        });
+    }
+
+    let (Some(old_layout), Some(new_layout)) = (compute_layout(old), compute_layout(new)) else {
+        return Some(LayoutBreakKind::LayoutUnknown);
+    };
+    if old_layout.bitstream
+        && old.bitfield_layout.unwrap_or(BitOrder::LsbFirst)
+            != new.bitfield_layout.unwrap_or(BitOrder::LsbFirst)
+    {
+        return Some(LayoutBreakKind::BitOrderChanged);
+    }
+    if old_layout.size != new_layout.size {
+        return Some(LayoutBreakKind::SizeChanged {
+            old: old_layout.size,
+            new: new_layout.size,
+        });
+    }
+
+    let (old_members, new_members) = (
+        wire_members(old, &old_layout),
+        wire_members(new, &new_layout),
+    );
+    let count = old_members.len().max(new_members.len());
+    (0..count).find_map(|index| {
+        let (a, b) = (old_members.get(index), new_members.get(index));
+        (a != b).then(|| LayoutBreakKind::MemberChanged {
+            index,
+            old: a.cloned(),
+            new: b.cloned(),
+        })
+    })
+}
+
+/// 按布局列出线上成员，例如 `uint16_t@8+16 little`，多字节标量附带字节序
+fn wire_members(config: &Config, layout: &PacketLayout) -> Vec<String> {
+    config
+        .fields
+        .iter()
+        .zip(&layout.fields)
+        .map(|(field, placed)| {
+            let mut member = format!("{}@{}+{}", placed.ty, placed.offset_bits, placed.size_bits);
+            if placed.bit_field.is_none() && c_type_size(&placed.ty).is_some_and(|size| size > 1) {
+                member.push_str(&format!(" {}", config.field_endianness(field).as_str()));
+            }
+            member
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"{
+        "packet_name": "SensorData",
+        "command_id": "0x0104",
+        "fields": [
+            { "name": "sensor_id", "type": "uint8_t", "comment": "传感器ID" },
+            { "name": "temperature", "type": "float", "comment": "温度值" }
+        ]
+    }"#;
+
+    fn version(s: &str) -> ProtocolVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let report = diff(BASE, BASE).unwrap();
+        assert!(report.is_empty());
+        assert_eq!(report.required_bump(), VersionBump::None);
+    }
+
+    #[test]
+    fn test_diff_ignores_equivalent_spellings() {
+        let new = r#"{
+            "packet_name": "SensorData",
+            "command_id": "260",
+            "header_guard": "RPL_SENSORDATA_HPP",
+            "fields": [
+                { "name": "sensor_id", "type": "uint8_t", "comment": "传感器ID" },
+                { "name": "temperature", "type": "float", "comment": "温度值" }
+            ]
+        }"#;
+        assert!(diff(BASE, new).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_appended_field_is_additive() {
+        let new = r#"{
+            "packet_name": "SensorData",
+            "command_id": "0x0104",
+            "fields": [
+                { "name": "sensor_id", "type": "uint8_t", "comment": "传感器ID" },
+                { "name": "temperature", "type": "float", "comment": "温度值" },
+                { "name": "humidity", "type": "float", "comment": "湿度" }
+            ]
+        }"#;
+
+        let report = diff(BASE, new).unwrap();
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(report.changes[0].kind, ChangeKind::FieldAdded);
+        assert_eq!(report.changes[0].compatibility, Compatibility::Additive);
+        assert_eq!(report.required_bump(), VersionBump::Minor);
+    }
+
+    #[test]
+    fn test_diff_inserted_field_is_breaking() {
+        let new = r#"{
+            "packet_name": "SensorData",
+            "command_id": "0x0104",
+            "fields": [
+                { "name": "sensor_id", "type": "uint8_t", "comment": "传感器ID" },
+                { "name": "humidity", "type": "float", "comment": "湿度" },
+                { "name": "temperature", "type": "float", "comment": "温度值" }
+            ]
+        }"#;
+
+        let report = diff(BASE, new).unwrap();
+        assert!(report.has_breaking_changes());
+        assert!(report.changes.iter().any(|c| c.kind
+            == ChangeKind::FieldMoved {
+                old_index: 1,
+                new_index: 2
+            }));
+        assert_eq!(report.required_bump(), VersionBump::Major);
+    }
+
+    #[test]
+    fn test_diff_type_and_cmd_changes() {
+        let new = r#"{
+            "packet_name": "SensorData",
+            "command_id": "260",
+            "fields": [
+                { "name": "sensor_id", "type": "uint16_t", "comment": "传感器ID" },
+                { "name": "temperature", "type": "float", "comment": "温度(摄氏度)" }
+            ]
+        }"#;
+
+        let report = diff(BASE, new).unwrap();
+        // "260" == 0x0104, so the command ID is unchanged
+        assert_eq!(report.changes.len(), 2);
+        assert_eq!(
+            report.changes[0].kind,
+            ChangeKind::FieldTypeChanged {
+                old: "uint8_t".to_string(),
+                new: "uint16_t".to_string()
+            }
+        );
+        assert_eq!(report.changes[1].kind, ChangeKind::CommentChanged);
+        assert_eq!(report.changes[1].compatibility, Compatibility::Cosmetic);
+    }
+
+    #[test]
+    fn test_diff_field_offset_changes() {
+        let with_offset = |offset: u32| {
+            BASE.replace(
+                r#""type": "float","#,
+                &format!(r#""type": "float", "offset": {},"#, offset),
+            )
+        };
+
+        // 写明字段原本所在的位置不改变布局
+        let report = diff(BASE, &with_offset(1)).unwrap();
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(
+            report.changes[0].kind,
+            ChangeKind::FieldOffsetChanged {
+                old: None,
+                new: Some(1)
+            }
+        );
+        assert_eq!(report.changes[0].compatibility, Compatibility::Cosmetic);
+
+        let report = diff(&with_offset(1), &with_offset(4)).unwrap();
+        assert_eq!(report.changes[0].compatibility, Compatibility::Breaking);
+        assert_eq!(
+            report.changes[0].to_string(),
+            "[破坏性] 包 'SensorData' 字段 'temperature': 固定偏移从 1 变为 4"
+        );
+    }
+
+    #[test]
+    fn test_diff_field_ids() {
+        let packet = |retired: &str, fields: &str| {
+            format!(
+                r#"{{ "packet_name": "Gimbal", "command_id": "0x0101",
+                    "retired_field_ids": {}, "fields": [{}] }}"#,
+                retired, fields
+            )
+        };
+        let yaw = r#"{ "name": "yaw", "type": "float", "id": 1 }"#;
+        let pitch = r#"{ "name": "pitch", "type": "float", "id": 2 }"#;
+        let old = packet("[]", &format!("{}, {}", yaw, pitch));
+        let kinds = |new: &str| {
+            diff(&old, new)
+                .unwrap()
+                .changes
+                .into_iter()
+                .map(|change| (change.field, change.kind, change.compatibility))
+                .collect::<Vec<_>>()
+        };
+
+        // 首次分配 ID 不算破坏性变更
+        let report = diff(
+            &packet("[]", r#"{ "name": "yaw", "type": "float" }"#),
+            &packet("[]", yaw),
+        )
+        .unwrap();
+        assert_eq!(report.changes[0].compatibility, Compatibility::Cosmetic);
+
+        // 删除 pitch 并停用它的 ID 后，只有删除本身是破坏性的
+        let removed = kinds(&packet("[2]", yaw));
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].1, ChangeKind::FieldRemoved);
+
+        let forgotten = kinds(&packet("[]", yaw));
+        assert_eq!(
+            forgotten[1],
+            (
+                Some("pitch".to_string()),
+                ChangeKind::FieldIdNotRetired { id: 2 },
+                Compatibility::Breaking
+            )
+        );
+
+        // 新字段沿用了被删除字段的 ID
+        let roll = r#"{ "name": "roll", "type": "float", "id": 2 }"#;
+        let reused = kinds(&packet("[]", &format!("{}, {}", yaw, roll)));
+        assert!(reused.contains(&(
+            Some("roll".to_string()),
+            ChangeKind::FieldIdReused {
+                id: 2,
+                previous: Some("pitch".to_string())
+            },
+            Compatibility::Breaking
+        )));
+        assert!(
+            !reused
+                .iter()
+                .any(|(_, kind, _)| matches!(kind, ChangeKind::FieldIdNotRetired { .. }))
+        );
+
+        let changed = diff(
+            &old,
+            &packet("[]", &format!("{}, {}", yaw, pitch.replace("2", "3"))),
+        )
+        .unwrap();
+        assert_eq!(
+            changed.changes[0].to_string(),
+            "[破坏性] 包 'Gimbal' 字段 'pitch': ID 从 2 变为 3"
+        );
+
+        // 停用的 ID 在之后的版本中同样不能重新分配或移出列表
+        let retired = packet("[2]", yaw);
+        let report = diff(&retired, &packet("[]", &format!("{}, {}", yaw, roll))).unwrap();
+        assert_eq!(
+            report
+                .changes
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>(),
+            [
+                "[新增] 包 'Gimbal' 字段 'roll': 新增",
+                "[破坏性] 包 'Gimbal' 字段 'roll': 使用了已停用的 ID 2",
+            ]
+        );
+        let report = diff(&retired, &packet("[]", yaw)).unwrap();
+        assert_eq!(
+            report.changes[0].to_string(),
+            "[破坏性] 包 'Gimbal': ID 2 没有记入 retired_field_ids"
+        );
+    }
+
+    #[test]
+    fn test_diff_packet_added_and_removed() {
+        let old = format!("[{}]", BASE);
+        let new = r#"{
+            "protocol_version": "2.0.0",
+            "packets": [
+                { "packet_name": "Other", "command_id": "0x0105", "fields": [] }
+            ]
+        }"#;
+
+        let report = diff(&old, new).unwrap();
+        assert_eq!(report.changes.len(), 2);
+        assert_eq!(report.changes[0].kind, ChangeKind::PacketRemoved);
+        assert_eq!(report.changes[1].kind, ChangeKind::PacketAdded);
+        assert_eq!(
+            report.changes[0].to_string(),
+            "[破坏性] 包 'SensorData': 被删除"
+        );
+    }
+
+    #[test]
+    fn test_check_version_bump() {
+        let breaking = ChangeReport {
+            changes: vec![Change {
+                packet: "P".to_string(),
+                field: None,
+                kind: ChangeKind::PacketRemoved,
+                compatibility: Compatibility::Breaking,
+            }],
+        };
+        assert!(check_version_bump(version("2.3.0"), version("3.0.0"), &breaking).is_ok());
+        assert_eq!(
+            check_version_bump(version("2.3.0"), version("2.4.0"), &breaking),
+            Err(VersionBumpError::MajorBumpRequired {
+                old: version("2.3.0"),
+                new: version("2.4.0")
+            })
+        );
+
+        let additive = ChangeReport {
+            changes: vec![Change {
+                packet: "P".to_string(),
+                field: None,
+                kind: ChangeKind::PacketAdded,
+                compatibility: Compatibility::Additive,
+            }],
+        };
+        assert!(check_version_bump(version("2.3.0"), version("2.4.0"), &additive).is_ok());
+        assert!(check_version_bump(version("2.3.0"), version("2.3.1"), &additive).is_err());
+
+        assert!(
+            check_version_bump(version("2.3.0"), version("2.2.0"), &ChangeReport::default())
+                .is_err()
+        );
+    }
+
+    fn packets(json: &str) -> Vec<Config> {
+        serde_json::from_str::<ConfigOrArray>(json)
+            .unwrap()
+            .into_packets()
+    }
+
+    #[test]
+    fn test_layout_breaks() {
+        let old = packets(&format!("[{}]", BASE));
+        assert!(layout_breaks(&old, &old).is_empty());
+
+        // 改名、改注释与新增包不改变已有包的线上布局
+        let renamed = packets(
+            r#"[{"packet_name":"SensorData","command_id":"260","fields":[
+                {"name":"id","type":"uint8_t","comment":"编号"},
+                {"name":"temp","type":"float"}]},
+            {"packet_name":"Extra","command_id":"0x0105","fields":[{"name":"a","type":"uint8_t"}]}]"#,
+        );
+        assert!(layout_breaks(&old, &renamed).is_empty());
+
+        let appended = packets(
+            r#"[{"packet_name":"SensorData","command_id":"0x0104","fields":[
+                {"name":"sensor_id","type":"uint8_t"},{"name":"temperature","type":"float"},
+                {"name":"humidity","type":"float"}]}]"#,
+        );
+        assert_eq!(
+            layout_breaks(&old, &appended),
+            vec![LayoutBreak {
+                packet: "SensorData".to_string(),
+                kind: LayoutBreakKind::SizeChanged { old: 5, new: 9 },
+            }]
+        );
+
+        let retyped = packets(
+            r#"[{"packet_name":"SensorData","command_id":"0x0104","fields":[
+                {"name":"sensor_id","type":"uint8_t"},{"name":"temperature","type":"int32_t"}]}]"#,
+        );
+        let breaks = layout_breaks(&old, &retyped);
+        assert_eq!(
+            breaks[0].kind,
+            LayoutBreakKind::MemberChanged {
+                index: 1,
+                old: Some("float@8+32 little".to_string()),
+                new: Some("int32_t@8+32 little".to_string()),
+            }
+        );
+        assert_eq!(
+            breaks[0].to_string(),
+            "包 'SensorData' 的第 2 个成员从 float@8+32 little 变为 int32_t@8+32 little"
+        );
+
+        let big_endian = packets(
+            r#"[{"packet_name":"SensorData","command_id":"0x0104","endianness":"big","fields":[
+                {"name":"sensor_id","type":"uint8_t"},{"name":"temperature","type":"float"}]}]"#,
+        );
+        assert!(matches!(
+            layout_breaks(&old, &big_endian)[0].kind,
+            LayoutBreakKind::MemberChanged { index: 1, .. }
+        ));
+
+        let moved = packets(
+            r#"[{"packet_name":"SensorData","command_id":"0x0204","fields":[
+                {"name":"sensor_id","type":"uint8_t"},{"name":"temperature","type":"float"}]}]"#,
+        );
+        assert!(matches!(
+            layout_breaks(&old, &moved)[0].kind,
+            LayoutBreakKind::CommandIdChanged { .. }
+        ));
+
+        assert_eq!(
+            layout_breaks(&old, &[])[0].kind,
+            LayoutBreakKind::PacketRemoved
+        );
+    }
+}
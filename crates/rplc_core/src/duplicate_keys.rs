@@ -0,0 +1,155 @@
+//! 检查 JSON 源文本中同一对象内重复的键
+//!
+//! serde 解析时静默保留最后一个值，重复的键通常是合并冲突留下的，只能在源文本上发现
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+use crate::diagnostics::{RplcDiagnostic, Severity, ValidationCode};
+
+enum Frame<'a> {
+    /// 键到其首次出现位置的映射；`expect_key` 表示下一个字符串是键而不是值
+    Object {
+        /// 不含转义的键直接借用源文本，大文档中不必为每个键分配字符串
+        keys: HashMap<Cow<'a, str>, usize>,
+        expect_key: bool,
+    },
+    Array,
+}
+
+/// 对每个重复出现的键报告一个错误，位置指向后出现的那个键
+///
+/// 调用方应保证输入是合法的 JSON，非法输入只会得到不完整的结果
+pub(crate) fn duplicate_key_diagnostics(json_input: &str) -> Vec<RplcDiagnostic> {
+    let bytes = json_input.as_bytes();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut diags = Vec::new();
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => stack.push(Frame::Object {
+                keys: HashMap::new(),
+                expect_key: true,
+            }),
+            b'[' => stack.push(Frame::Array),
+            b'}' | b']' => {
+                stack.pop();
+            }
+            b',' | b':' => {
+                if let Some(Frame::Object { expect_key, .. }) = stack.last_mut() {
+                    *expect_key = bytes[i] == b',';
+                }
+            }
+            b'"' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    // 跳过转义字符，避免把 \" 当作字符串结尾
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                let end = (i + 1).min(bytes.len());
+                if let Some(Frame::Object {
+                    keys,
+                    expect_key: true,
+                }) = stack.last_mut()
+                {
+                    let literal = &json_input[start..end];
+                    let key = match literal.get(1..literal.len().saturating_sub(1)) {
+                        Some(inner) if literal.len() >= 2 && !inner.contains('\\') => {
+                            Cow::Borrowed(inner)
+                        }
+                        _ => Cow::Owned(
+                            serde_json::from_str::<String>(literal)
+                                .unwrap_or_else(|_| literal.to_string()),
+                        ),
+                    };
+                    match keys.entry(key) {
+                        Entry::Occupied(first) => diags.push(RplcDiagnostic {
+                            code: ValidationCode::DuplicateKey(
+                                first.key().to_string(),
+                                line_of(json_input, *first.get()),
+                            ),
+                            severity: Severity::Error,
+                            span: Some((start, end - start)),
+                            help: None,
+                        }),
+                        Entry::Vacant(entry) => {
+                            entry.insert(start);
+                        }
+                    }
+                }
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    diags
+}
+
+/// 字节偏移所在的行号，从 1 开始
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duplicates(json: &str) -> Vec<(ValidationCode, &str)> {
+        duplicate_key_diagnostics(json)
+            .into_iter()
+            .map(|d| {
+                let (offset, len) = d.span.unwrap();
+                (d.code, &json[offset..offset + len])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_duplicate_keys_in_packet_and_field() {
+        let json = r#"{
+  "packet_name": "Status",
+  "fields": [
+    { "name": "mode", "type": "uint8_t", "type": "uint16_t" }
+  ],
+  "packet_name": "Status2"
+}"#;
+        assert_eq!(
+            duplicates(json),
+            vec![
+                (
+                    ValidationCode::DuplicateKey("type".to_string(), 4),
+                    r#""type""#
+                ),
+                (
+                    ValidationCode::DuplicateKey("packet_name".to_string(), 2),
+                    r#""packet_name""#
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_keys_ignores_values_and_sibling_objects() {
+        // 与键相同的值、不同对象中的同名键、字符串中的引号都不算重复
+        let json = r#"[
+  { "name": "name", "comment": "a \"name\": b" },
+  { "name": "other", "x": ["name", "name"] }
+]"#;
+        assert!(duplicates(json).is_empty());
+
+        // 转义写法与原样写法是同一个键
+        let json = r#"{ "name": "a", "n\u0061me": "b" }"#;
+        assert_eq!(
+            duplicates(json),
+            vec![(
+                ValidationCode::DuplicateKey("name".to_string(), 1),
+                r#""n\u0061me""#
+            )]
+        );
+    }
+}
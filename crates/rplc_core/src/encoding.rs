@@ -0,0 +1,96 @@
+//! 将协议定义文件的原始字节解码为文本
+
+use crate::diagnostics::{RplcDiagnostic, Severity, ValidationCode};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// 解码协议定义文件：
+/// - 去掉 UTF-8 BOM，诊断的位置因此相对于去掉 BOM 之后的文本
+/// - 带 BOM 或以 ASCII 字符开头的 UTF-16 文件给出明确的编码错误，而不是 JSON 语法错误
+/// - 其他无效的 UTF-8 字节报告其所在的偏移
+pub fn decode_source(mut bytes: Vec<u8>) -> Result<String, Vec<RplcDiagnostic>> {
+    let error = |code| {
+        vec![RplcDiagnostic {
+            code,
+            severity: Severity::Error,
+            span: None,
+            help: None,
+        }]
+    };
+
+    if bytes.starts_with(UTF8_BOM) {
+        bytes.drain(..UTF8_BOM.len());
+    } else if let Some(encoding) = utf16_encoding(&bytes) {
+        return Err(error(ValidationCode::Utf16Input(encoding.to_string())));
+    }
+
+    String::from_utf8(bytes)
+        .map_err(|e| error(ValidationCode::InvalidUtf8(e.utf8_error().valid_up_to())))
+}
+
+/// Windows 记事本的“Unicode”即带 BOM 的 UTF-16 LE；
+/// 没有 BOM 时 JSON 文本以 ASCII 字符开头，其高字节为 0
+fn utf16_encoding(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0xFF, 0xFE, ..] => Some("UTF-16 LE"),
+        [0xFE, 0xFF, ..] => Some("UTF-16 BE"),
+        [c, 0, ..] if c.is_ascii() && *c != 0 => Some("UTF-16 LE"),
+        [0, c, ..] if c.is_ascii() && *c != 0 => Some("UTF-16 BE"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::validate;
+
+    const PACKET: &str = r#"{"packet_name":"Status","command_id":"0x0101","comment":"S",
+        "fields":[{"name":"mode","type":"uint8_t","comment":"模式"}]}"#;
+
+    fn utf16(text: &str, bom: &[u8], to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        let mut bytes = bom.to_vec();
+        bytes.extend(text.encode_utf16().flat_map(to_bytes));
+        bytes
+    }
+
+    #[test]
+    fn test_decode_plain_and_bom_utf8() {
+        assert_eq!(decode_source(PACKET.as_bytes().to_vec()).unwrap(), PACKET);
+
+        let mut bytes = UTF8_BOM.to_vec();
+        bytes.extend_from_slice(PACKET.as_bytes());
+        let decoded = decode_source(bytes).unwrap();
+        assert_eq!(decoded, PACKET);
+        assert!(validate(&decoded).is_empty());
+    }
+
+    #[test]
+    fn test_decode_rejects_utf16() {
+        let cases = [
+            (utf16(PACKET, &[0xFF, 0xFE], u16::to_le_bytes), "UTF-16 LE"),
+            (utf16(PACKET, &[0xFE, 0xFF], u16::to_be_bytes), "UTF-16 BE"),
+            (utf16(PACKET, &[], u16::to_le_bytes), "UTF-16 LE"),
+            (utf16(PACKET, &[], u16::to_be_bytes), "UTF-16 BE"),
+        ];
+        for (bytes, encoding) in cases {
+            let diags = decode_source(bytes).unwrap_err();
+            assert_eq!(diags.len(), 1);
+            assert_eq!(
+                diags[0].code,
+                ValidationCode::Utf16Input(encoding.to_string())
+            );
+            assert_eq!(diags[0].severity, Severity::Error);
+        }
+    }
+
+    #[test]
+    fn test_decode_reports_invalid_utf8_offset() {
+        // GBK 编码的“中文”
+        let mut bytes = br#"{"comment":""#.to_vec();
+        let offset = bytes.len();
+        bytes.extend_from_slice(&[0xD6, 0xD0, 0xCE, 0xC4, b'"', b'}']);
+        let diags = decode_source(bytes).unwrap_err();
+        assert_eq!(diags[0].code, ValidationCode::InvalidUtf8(offset));
+    }
+}
@@ -0,0 +1,627 @@
+//! 诊断代码的详细说明，供 `rplc explain` 与 Playground 的“了解更多”面板使用
+
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "rplc::invalid_packet_name",
+        "packet_name 会直接作为生成的 C++ 结构体名称，因此必须是合法的标识符：\
+以字母或下划线开头，只包含字母、数字和下划线。
+
+错误示例: \"packet_name\": \"gimbal-cmd\"
+正确示例: \"packet_name\": \"GimbalCommand\"",
+    ),
+    (
+        "rplc::invalid_field_name",
+        "字段名会作为结构体成员名称，必须是合法的 C++ 标识符：\
+以字母或下划线开头，只包含字母、数字和下划线。
+
+错误示例: \"name\": \"2nd-speed\"
+正确示例: \"name\": \"second_speed\"",
+    ),
+    (
+        "rplc::keyword_collision",
+        "字段名与 C++ 关键字相同（如 class、int、switch）时生成的代码无法编译。\
+请换用其他名称，例如在名称后加上含义更明确的后缀。
+
+错误示例: \"name\": \"class\"
+正确示例: \"name\": \"target_class\"",
+    ),
+    (
+        "rplc::generated_name_collision",
+        "生成代码会在字段所在的作用域中加入辅助成员：C++ 结构体中的 k_<字段>_max 等范围常量、\
+k_<字段>_id 与 <字段>_name()，位流访问结构体中的 read_bits、write_bits、sign_extend 与 set_<字段>()，\
+Python 类中的 CMD、SIZE、FORMAT、LAYOUT_HASH、pack、unpack 与 <字段>_name()。\
+字段与这些成员同名时生成的代码无法编译或会被覆盖，请给字段换一个名称。
+
+错误示例: 字段 yaw 声明了 max，另一个字段又命名为 k_yaw_max
+正确示例: \"name\": \"yaw_limit\"",
+    ),
+    (
+        "rplc::duplicate_field",
+        "同一个包中的字段名必须唯一，否则生成的结构体会出现重复的成员。\
+请检查是否误复制了字段定义。",
+    ),
+    (
+        "rplc::duplicate_packet",
+        "多包文档（数组或包集合）中的每个包都会生成以包名命名的结构体与头文件，\
+包名重复时生成的代码会互相覆盖或重复定义。诊断指向后出现的包名。",
+    ),
+    (
+        "rplc::duplicate_cmd_id",
+        "接收端按 command_id 把帧分发给对应的包，同一文档中两个包使用相同的 ID 时无法区分。\
+比较的是解析后的数值，\"260\" 与 \"0x0104\" 视为相同。诊断指向后出现的包，消息中给出先使用该 ID 的包。",
+    ),
+    (
+        "rplc::invalid_cmd_id",
+        "command_id 是 16 位无符号整数，用于在帧中区分不同的包。\
+可以写成十进制（如 \"260\"），或以 0x 开头的十六进制（如 \"0x0104\"），\
+十六进制数字之间可以用下划线分隔（如 \"0x01_04\"）。超出 0-65535 的值会被拒绝。",
+    ),
+    (
+        "rplc::invalid_field_type",
+        "字段的 type 必须是受支持的 C/C++ 基础类型（如 uint8_t、int16_t、float、double），\
+或形如 \"float[3]\" 的定长数组，也可以是本包 enums 中定义的枚举名，\
+以及多包文件中另一个包的名称（只支持 C++）。不支持指针、引用与其他自定义类型。",
+    ),
+    (
+        "rplc::bit_field::invalid",
+        "bit_field 表示该字段占用的位数，必须是正整数。0、负数、小数以及无法解析为整数的字符串都会被拒绝。
+
+错误示例: \"bit_field\": 0
+正确示例: \"bit_field\": 3",
+    ),
+    (
+        "rplc::bit_field::invalid_type",
+        "位域只能用于整数类型（包括 bool 与 char 系列）。float、double 等浮点类型不能声明位域。",
+    ),
+    (
+        "rplc::bit_field::length_overflow",
+        "位域宽度不能超过其基础类型的位数，例如 uint8_t 最多 8 位、uint16_t 最多 16 位。\
+需要更宽的位域时，请换用更大的整数类型。
+
+错误示例: { \"type\": \"uint8_t\", \"bit_field\": 12 }
+正确示例: { \"type\": \"uint16_t\", \"bit_field\": 12 }",
+    ),
+    (
+        "rplc::bit_field::straddle_boundary_without_packed",
+        "相邻的两个位域加起来超过了一个存储单元，而结构体没有启用 packed。\
+此时编译器会把后一个位域放到新的存储单元中，实际布局与按位连续排列的预期不一致。\
+请启用 \"packed\": true，或调整位域宽度使其不跨越存储单元。",
+    ),
+    (
+        "rplc::invalid_array_type",
+        "数组类型必须写成 \"基础类型[长度]\" 的形式，长度为正整数，例如 \"uint8_t[16]\"。\
+不支持多维数组与空长度。",
+    ),
+    (
+        "rplc::bit_field_on_array",
+        "数组字段不能同时声明位域。如果需要一组紧凑的标志位，请拆分为多个位域字段。",
+    ),
+    (
+        "rplc::array_len::invalid",
+        "array_len 是数组长度的另一种写法，{ \"type\": \"uint8_t\", \"array_len\": 16 } 与 \"uint8_t[16]\" 等价。\
+长度必须是正整数。
+
+错误示例: { \"name\": \"data\", \"type\": \"uint8_t\", \"array_len\": 0 }
+正确示例: { \"name\": \"data\", \"type\": \"uint8_t\", \"array_len\": 16 }",
+    ),
+    (
+        "rplc::array_len::conflict",
+        "字段的类型已经写成了 \"Type[N]\"，又设置了 array_len。两种写法等价，同时使用时无法判断哪一个是本意，\
+也不会组合成多维数组；请只保留其中一种。",
+    ),
+    (
+        "rplc::enums::invalid",
+        "包的 enums 定义具名枚举，字段的 type 可以直接写枚举名，C++ 中生成 enum class，C、Python、Rust 与 Markdown 输出枚举常量或枚举类型，其余语言使用底层类型。\
+每个枚举需要合法的 C++ 标识符作为名称（不能与基础类型同名），底层类型必须是整数类型（不能是 bool 或浮点），\
+values 是非空的枚举项数组。
+
+错误示例: { \"name\": \"Mode\", \"type\": \"float\", \"values\": [] }
+正确示例: { \"name\": \"Mode\", \"type\": \"uint8_t\", \"values\": [{ \"name\": \"idle\", \"value\": 0 }] }",
+    ),
+    (
+        "rplc::enums::duplicate",
+        "同一个包中的两个枚举使用了相同的名称，字段引用时无法区分。",
+    ),
+    (
+        "rplc::enums::invalid_enumerator",
+        "枚举项是带有 name 与 value 的对象，可以附带 comment。名称必须是合法的 C++ 标识符且不能是关键字，\
+取值必须是整数。",
+    ),
+    (
+        "rplc::enums::duplicate_enumerator",
+        "同一个枚举中的两个枚举项使用了相同的名称，生成的 enum class 无法编译。",
+    ),
+    (
+        "rplc::enums::value_out_of_range",
+        "枚举项的取值必须能用枚举的底层类型表示，否则 enum class 无法编译，线上数据也会被截断。\
+例如 uint8_t 的取值范围是 0 到 255。",
+    ),
+    (
+        "rplc::enums::duplicate_value",
+        "两个枚举项的取值相同。C++ 允许这样的别名，但接收方只能把线上的值还原为其中一个名称；\
+如果不是有意的别名，通常是复制粘贴时忘了修改取值。",
+    ),
+    (
+        "rplc::packet_ref::cycle",
+        "多包文件中字段的 type 可以写同一文件中另一个包的名称，生成的结构体把该包作为成员。\
+引用不能成环：A 包含 B、B 又包含 A 时两个结构体都没有确定的大小，C++ 也无法编译。
+
+错误示例: Gimbal 的字段 imu 的类型是 Imu，Imu 的字段 owner 的类型又是 Gimbal",
+    ),
+    (
+        "rplc::packet_ref::bitstream",
+        "含位域的包按位流排列，BitLayout 逐个描述标量成员的位宽，无法描述作为成员的结构体。\
+可以把被引用包的字段直接写进这个包，或者把位域移到单独的包中再引用。",
+    ),
+    (
+        "rplc::limit::invalid",
+        "字段的 min、max 与 typical 是遥测范围标注，会写入文档并生成 k_<字段>_min 之类的常量，\
+供界面仪表与固件限幅共用。它们必须是数字：整数字段只能写该类型（位域则为该位宽）可以表示的整数，\
+布尔字段不能标注范围，并且需要满足 min ≤ typical ≤ max。
+
+错误示例: { \"name\": \"heat\", \"type\": \"uint8_t\", \"max\": 300 }
+正确示例: { \"name\": \"heat\", \"type\": \"uint16_t\", \"min\": 0, \"max\": 300, \"typical\": 120 }",
+    ),
+    (
+        "rplc::values::invalid",
+        "字段的 values 列出各个取值的显示名称，生成 <字段>_name 查找函数，供界面把状态码显示为文字。\
+只有整数字段可以列出取值；键是十进制或 0x 开头的十六进制整数，必须是该字段类型（位域则为该位宽）\
+可以表示的值且互不重复；显示名称是非空字符串，或与 comment 相同的多语言对象。
+
+错误示例: { \"name\": \"mode\", \"type\": \"uint8_t\", \"values\": { \"idle\": \"空闲\" } }
+正确示例: { \"name\": \"mode\", \"type\": \"uint8_t\", \"values\": { \"0\": \"空闲\", \"1\": \"运行\" } }",
+    ),
+    (
+        "rplc::offset::invalid",
+        "字段的 offset 把它固定在包中的某个字节处，用于照搬按绝对偏移描述的第三方协议。\
+与前一字段之间的空隙会自动填充为 reserved_<起始字节> 保留字段。offset 只能用于紧凑结构体中的非位域字段，\
+不能小于前面字段的结束位置，之前的位域必须填满整字节，且不能超出包的载荷上限；\
+自动生成的保留字段也不能与已有字段重名。
+
+错误示例: { \"name\": \"a\", \"type\": \"uint32_t\" }, { \"name\": \"b\", \"type\": \"uint8_t\", \"offset\": 2 }
+正确示例: { \"name\": \"a\", \"type\": \"uint32_t\" }, { \"name\": \"b\", \"type\": \"uint8_t\", \"offset\": 8 }",
+    ),
+    (
+        "rplc::reserved::invalid",
+        "fields 中的 {\"reserved\": n} 条目表示 n 个有意留空的字节，生成匿名的 uint8_t 数组成员，\
+按出现顺序命名为 _reserved0、_reserved1……，不需要注释，也不会报告缺少注释。保留条目只能包含 reserved 与可选的 comment，\
+不能使用位域；n 必须是正整数，展开得到的字段名不能与已有字段重复。
+
+错误示例: { \"reserved\": 1, \"bit_field\": 4 }
+正确示例: { \"reserved\": 3 }",
+    ),
+    (
+        "rplc::invalid_protocol_version",
+        "包集合的 protocol_version 必须是 MAJOR.MINOR.PATCH 形式的语义化版本号，\
+每一段都是非负整数，例如 \"2.3.0\"。CI 模式会根据它检查版本号提升是否覆盖了协议变更。",
+    ),
+    (
+        "rplc::array::payload_budget",
+        "帧头中的载荷长度字段为 uint16_t，单个包的载荷不能超过 65535 字节；\
+通过 --max-payload-size 可以为链路设置更小的上限。\
+该错误标记的是使载荷超出上限的数组字段，请减小数组长度或将数据拆分到多个包中。",
+    ),
+    (
+        "rplc::json::syntax",
+        "输入不是合法的 JSON。常见原因包括多余或缺少的逗号、未加引号的键、注释以及未闭合的括号。\
+诊断位置指向解析器停止的地方，实际问题通常在它之前不远处。",
+    ),
+    (
+        "rplc::yaml::syntax",
+        "YAML 定义无法转写为 JSON。除了 YAML 本身的语法错误，rplc 还要求文件中只有一个文档，且映射的键是标量。\
+普通标量按 YAML 1.2 核心模式识别：null、true/false 与十进制数字会变成对应的 JSON 值，其余都是字符串，\
+因此 command_id: 0x0104 会被当作字符串，而 command_id: 260 是数字，需要写成 command_id: '260'。
+
+错误示例: fields: [{ name: x, type: uint8_t }\n---\npacket_name: B
+正确示例: fields: [{ name: x, type: uint8_t }]",
+    ),
+    (
+        "rplc::toml::syntax",
+        "TOML 定义无法转写为 JSON。TOML 本身禁止重复的键，因此重复定义在这里报告，诊断指向后出现的一处。\
+浮点数 inf 与 nan 没有对应的 JSON 值，同样报告为此错误。字段列表可以写成 [[fields]] 表数组，每个字段一节，\
+也可以写成 fields = [{ name = \"x\", type = \"uint8_t\" }] 这样的内联表数组。
+
+错误示例: [[fields]]\nname = \"x\"\nname = \"y\"
+正确示例: [[fields]]\nname = \"x\"\ntype = \"uint8_t\"",
+    ),
+    (
+        "rplc::json::duplicate_key",
+        "同一个 JSON 对象中出现了两个相同的键，例如一个字段写了两个 type。JSON 解析器会静默保留最后一个值，\
+而这几乎总是合并冲突或复制粘贴留下的错误，因此 rplc 将其视为错误。诊断指向后出现的键，消息中给出第一次出现的行号。
+
+错误示例: { \"name\": \"mode\", \"type\": \"uint8_t\", \"type\": \"uint16_t\" }",
+    ),
+    (
+        "rplc::schema::unsupported",
+        "rplc_schema 声明定义文件使用的格式版本。省略时按版本 1 处理，当前版本为 2。\
+比当前 rplc 更新的版本无法被正确理解，需要升级 rplc；旧版本的文件可以用 `rplc migrate` 升级。
+
+错误示例: \"rplc_schema\": \"2\"
+正确示例: \"rplc_schema\": 2",
+    ),
+    (
+        "rplc::schema::removed",
+        "声明了较新 rplc_schema 的文件不再接受旧格式中的兼容写法，例如版本 2 要求位域写成数字。\
+`rplc migrate` 会自动改写这些写法并更新 rplc_schema；未声明版本的旧文件仍然只给出警告。
+
+错误示例: \"rplc_schema\": 2, ... \"bit_field\": \"4\"
+正确示例: \"rplc_schema\": 2, ... \"bit_field\": 4",
+    ),
+    (
+        "rplc::input::utf16",
+        "rplc 只接受 UTF-8 编码的输入。Windows 记事本中的“Unicode”以及部分编辑器导出的文件是 UTF-16，\
+每个 ASCII 字符后面都跟着一个零字节，无法作为 JSON 解析。请将文件另存为 UTF-8；带 BOM 的 UTF-8 可以直接使用。",
+    ),
+    (
+        "rplc::input::invalid_utf8",
+        "输入中存在不是 UTF-8 的字节序列，通常是用 GBK 等本地编码保存的中文注释。\
+诊断给出第一个无效字节的偏移，请用编辑器将文件转换为 UTF-8 后重新保存。",
+    ),
+    (
+        "rplc::input::too_large",
+        "输入超过了设定的大小上限，rplc 不再解析它，以免在 Playground 等内存受限的环境中耗尽内存。\
+命令行默认不限制大小，可以用 --max-input-size 设置；库调用方通过 ValidateOptions::max_input_size 设置，\
+Playground 限制为 4 MiB。一个文件中放入过多的包时，可以按子系统拆分为多个文件。",
+    ),
+    (
+        "rplc::multi_packet_input",
+        "文件顶层是数组或带有 packets 的包集合，但当前按单个包校验。\
+命令行会根据顶层结构自动选择多包模式，也可以显式传入 --multi；\
+通过库调用时请使用 validate_multiple / generate_multiple。",
+    ),
+    (
+        "rplc::fields::invalid",
+        "fields 必须是由字段对象组成的数组，即使只有一个字段也需要写成数组。
+
+错误示例: \"fields\": { \"name\": \"speed\", \"type\": \"float\" }
+正确示例: \"fields\": [{ \"name\": \"speed\", \"type\": \"float\" }]",
+    ),
+    (
+        "rplc::align::invalid",
+        "align 会生成 alignas(N)，N 必须是不超过 4096 的 2 的幂，例如 4、8 或 32。",
+    ),
+    (
+        "rplc::bit_field::invalid_layout",
+        "bitfield_layout 声明位域在线上字节中的位序，只能是 \"lsb_first\"（从字节最低位开始，先放值的低位）\
+或 \"msb_first\"（从字节最高位开始，先放值的高位）。\
+声明后会额外生成按该位序逐位读写字节的 <包名>Wire 访问函数。
+
+错误示例: \"bitfield_layout\": \"big_endian\"
+正确示例: \"bitfield_layout\": \"msb_first\"",
+    ),
+    (
+        "rplc::crc::invalid",
+        "crc 声明整帧的校验方式，目前只支持 \"dji\"：按 DJI 裁判系统串口协议生成帧头 CRC8 与整帧 CRC16 的校验函数，\
+以及打包整帧的 pack_frame_<包名>。
+
+错误示例: \"crc\": \"crc16\"
+正确示例: \"crc\": \"dji\"",
+    ),
+    (
+        "rplc::crc::payload_too_large",
+        "裁判系统串口协议中一帧最长 128 字节：5 字节帧头、2 字节命令码与 2 字节 CRC16 之外，数据段最多 119 字节。\
+数据段按紧凑布局计算，超过上限的包无法通过裁判系统发送，请拆分为多个包。",
+    ),
+    (
+        "rplc::crc::field_not_last",
+        "名为 crc、crc16 或 checksum 的字段被视为承载帧尾 CRC16 的校验字段，它必须位于数据的末尾，\
+否则 CRC 覆盖的范围与协议不一致。",
+    ),
+    (
+        "rplc::crc::field_width",
+        "DJI 协议的帧尾是 16 位 CRC，校验字段的类型必须是 uint16_t。
+
+错误示例: { \"name\": \"crc\", \"type\": \"uint8_t\" }
+正确示例: { \"name\": \"crc\", \"type\": \"uint16_t\" }",
+    ),
+    (
+        "rplc::crc::field_duplicated",
+        "crc_append 默认为 true，pack_frame_<包名> 会在数据之后追加 CRC16。包中同时声明了校验字段时，\
+帧尾会出现两个 CRC16。若结构体需要包含帧尾，请设置 \"crc_append\": false，由该字段承载 CRC16；否则删除该字段。",
+    ),
+    (
+        "rplc::crc::field_missing",
+        "\"crc_append\": false 表示包的最后一个字段就是帧尾的 CRC16，此时必须声明名为 crc、crc16 或 checksum 的 uint16_t 字段。",
+    ),
+    (
+        "rplc::endianness::invalid",
+        "endianness 声明多字节标量在线上数据中的字节序，只能是 \"little\"（低字节在前，省略时的默认值）\
+或 \"big\"（高字节在前，即网络字节序）。包与字段都可以声明，字段的声明覆盖包的声明。\
+它影响 serialize 生成的 serialize 与 deserialize 函数，以及把结构体当作线上字节读写的 <包名>Endian 访问函数；\
+结构体的成员声明本身不变。
+
+错误示例: \"endianness\": \"network\"
+正确示例: \"endianness\": \"big\"",
+    ),
+    (
+        "rplc::endianness::unsupported_field",
+        "字段的 endianness 决定该字段的字节在线上的排列顺序。位域成员没有独立的字节位置，\
+引用其他包的字段由被引用的包自己声明字节序，这两种字段都不能声明 endianness。",
+    ),
+    (
+        "rplc::endianness::redundant",
+        "uint8_t、int8_t、bool、char 以及它们的数组每个元素只有一个字节，不存在字节序。\
+在这样的字段上声明 endianness 不影响生成的代码，通常是复制粘贴留下的，可以删除。",
+    ),
+    (
+        "rplc::serialize::unsupported_field",
+        "serialize 为 true 时，生成的函数按字段在结构体中的偏移逐个读写标量并转换字节序。\
+位域成员没有独立的字节位置，long double 的大小与表示随平台变化，两者都无法这样读写。
+
+位域请去掉 serialize，改用 bitfield_layout 生成的 <包名>Wire 访问函数；long double 请改用 double。",
+    ),
+    (
+        "rplc::cpp_standard::unsupported",
+        "--cpp-standard 选择的 C++ 标准缺少该选项生成的代码所需的库特性。\
+serialize 生成的 deserialize 返回 std::optional，C++14 中没有这个类型。\
+其余特性会按所选标准换用等价的写法，例如 C++17 中 deserialize 接受指针与长度而不是 std::span。
+
+请使用 --cpp-standard 17 或更高，或去掉 serialize 并改用 endianness 生成的 <包名>Endian 访问函数。",
+    ),
+    (
+        "rplc::align::below_natural",
+        "非紧凑结构体的对齐不能小于其成员的自然对齐（最大成员对齐），\
+否则 alignas 会被编译器拒绝。请增大 align，或启用 packed。",
+    ),
+    (
+        "rplc::expected_size::invalid",
+        "expected_size 声明结构体应有的字节数，必须是正整数。
+
+错误示例: \"expected_size\": \"24\"
+正确示例: \"expected_size\": 24",
+    ),
+    (
+        "rplc::expected_size::mismatch",
+        "按字段计算出的包大小与 expected_size 不同，通常是字段类型、数组长度写错，\
+或者非紧凑结构体中的对齐填充使大小超出预期。\
+rplc layout 可以列出每个字段的偏移与填充；确认协议文档中的大小后修改字段，或启用 packed。
+
+生成的头文件中同样有 static_assert(sizeof(包名) == N)，编译器的实际布局与预期不符时编译失败。",
+    ),
+    (
+        "rplc::includes::invalid",
+        "includes 列出替代 <cstdint> 提供 uint8_t 等定宽整数类型的头文件，每一项写入一条 #include。\
+写成 \"my_types.h\" 时生成 #include \"my_types.h\"，写成 \"<stdint.h>\" 时按原样生成 #include <stdint.h>。\
+路径不能为空，不能包含引号或换行，尖括号必须成对。
+
+错误示例: \"includes\": \"my_types.h\"
+正确示例: \"includes\": [\"my_types.h\"]",
+    ),
+    (
+        "rplc::field_id::invalid",
+        "字段的 id 与包的 retired_field_ids 中的每一项都是字段 ID，必须是 0 到 4294967295 之间的整数。
+
+错误示例: { \"name\": \"yaw\", \"type\": \"float\", \"id\": \"3\" }
+正确示例: { \"name\": \"yaw\", \"type\": \"float\", \"id\": 3 }",
+    ),
+    (
+        "rplc::field_id::duplicate",
+        "字段 ID 用于在日志等格式中识别字段，与字段的位置无关，\
+因此同一个包中两个字段的 ID 相同时，读取方无法区分它们。请为后添加的字段分配一个新的 ID。",
+    ),
+    (
+        "rplc::field_id::retired",
+        "删除带有 ID 的字段后，旧版本写出的日志中仍然有这个 ID 的数据。\
+ID 被分配给新字段时，读取旧日志的工具会把旧数据当作新字段解析。\
+因此已删除字段的 ID 记在包的 retired_field_ids 中，永远不再使用。
+
+错误示例: \"retired_field_ids\": [2], \"fields\": [{ \"name\": \"pitch\", \"type\": \"float\", \"id\": 2 }]
+正确示例: \"retired_field_ids\": [2], \"fields\": [{ \"name\": \"pitch\", \"type\": \"float\", \"id\": 4 }]",
+    ),
+    (
+        "rplc::field_id::missing",
+        "包中有字段声明了 id，说明这个包的数据按 ID 记录，没有 ID 的字段在这样的格式中无法识别。\
+请为它分配一个未使用过、也不在 retired_field_ids 中的 ID。保留条目与 offset 留下的空隙不需要 ID。",
+    ),
+    (
+        "rplc::target::unsupported_type",
+        "通过 --target 指定的某个目标语言无法表示该字段的类型。\
+例如 long double 的宽度依赖平台，Rust、Python 的 struct 模块与 TypeScript 的 DataView 都没有对应类型。\
+请换用 double，或从 --target 中移除该目标。",
+    ),
+    (
+        "rplc::field_type::not_allowed",
+        "项目的 rplc.toml 用 [types] allowed 限定了字段可以使用的基础类型，该字段的类型不在其中。\
+等价的写法视为同一类型，例如允许 int 时 signed int 也可以使用；本包的枚举与其他包的引用不受限制。\
+诊断会给出列表中最接近的类型。
+
+[types]
+allowed = [\"uint8_t\", \"int16_t\", \"int32_t\", \"float\"]",
+    ),
+    (
+        "rplc::traits_template::invalid",
+        "traits_template 用于替代默认的 RPL::Meta::PacketTraits 特化，必须是字符串，\
+其中只能使用 ${name}（包名）、${cmd}（规范写法的命令 ID）、${size}（包的字节数表达式）与 ${hash}（布局哈希）四个占位符，\
+每个 ${ 都需要对应的 }。诊断指向模板中出错的占位符。
+
+错误示例: \"traits_template\": \"MY_PACKET(${packet_name})\"
+正确示例: \"traits_template\": \"MY_PACKET(${name}, ${cmd}, ${size})\"",
+    ),
+    (
+        "rplc::traits_template::unused",
+        "emit_traits 为 false 时不会生成任何 traits，traits_template 因此被忽略。\
+如果需要自定义的 traits，请保留 emit_traits 的默认值 true。",
+    ),
+    (
+        "rplc::unknown_key",
+        "包、字段、嵌套结构体与包集合对象中出现了格式中没有定义的键。生成器会忽略这些键，\
+因此拼写错误的键（如 comand_id、bit_feild）不会报错，却会让对应的设置悄悄失效。\
+与某个合法的键足够接近时，诊断会给出建议的写法。
+
+错误示例: { \"name\": \"mode\", \"type\": \"uint8_t\", \"bit_feild\": 4 }
+正确示例: { \"name\": \"mode\", \"type\": \"uint8_t\", \"bit_field\": 4 }",
+    ),
+    (
+        "rplc::section::invalid",
+        "fields 中可以插入 { \"section\": \"标题\" } 形式的分组条目，在生成的结构体中输出分隔注释，\
+并在 Markdown 文档中作为小节标题。分组条目不占用布局，标题必须是非空字符串，且不能与 name、type 等字段的键写在同一个对象中。
+
+错误示例: { \"section\": \"IMU\", \"name\": \"gyro\", \"type\": \"float\" }
+正确示例: { \"section\": \"IMU\" }, { \"name\": \"gyro\", \"type\": \"float\" }",
+    ),
+    (
+        "rplc::section::empty",
+        "分组标题附着在它之后的第一个字段上。紧接着另一个分组或位于 fields 末尾的分组没有字段，\
+生成时会被忽略。",
+    ),
+    (
+        "rplc::group::invalid",
+        "fields 中可以写 { \"name\": \"imu\", \"group\": [...] } 形式的条目，把一组字段生成到嵌套结构体中，\
+通过 packet.imu.gyro_x 访问；省略 name 时生成匿名结构体，成员仍可直接访问。嵌套结构体只用于组织代码，\
+布局与把这些字段直接写在 fields 中完全相同，因此只能用于紧凑结构体。group 必须是非空的字段数组，\
+其中不能再嵌套 group 或 section，条目本身也不能带有 type 或 bit_field。
+
+错误示例: { \"name\": \"imu\", \"type\": \"float\", \"group\": [] }
+正确示例: { \"name\": \"imu\", \"group\": [{ \"name\": \"gyro_x\", \"type\": \"float\" }] }",
+    ),
+    (
+        "rplc::group::bit_field_boundary",
+        "位域不能跨越结构体成员的边界。嵌套结构体开始或结束时，前面的位域必须恰好凑满整字节，\
+否则编译器会为嵌套结构体重新开始分配存储单元，实际布局将与 rplc 计算的连续位流不一致。
+
+错误示例: 嵌套结构体之前只有一个 3 位的位域
+正确示例: 在嵌套结构体之前补齐 5 位保留位域，或把位域移入嵌套结构体内部",
+    ),
+    (
+        "rplc::style::packet",
+        "包名建议使用大驼峰命名法（PascalCase），与生成的 C++ 类型命名保持一致，例如 GimbalCommand。",
+    ),
+    (
+        "rplc::style::field",
+        "字段名建议使用蛇形命名法（snake_case），例如 yaw_speed。",
+    ),
+    (
+        "rplc::doc::missing",
+        "字段注释会生成到结构体成员的 Doxygen 注释中。为每个字段写明单位与取值范围，\
+可以避免通信双方对数据含义产生分歧。",
+    ),
+    (
+        "rplc::bit_field::missing_packed_attr",
+        "使用位域的结构体没有启用 packed 时，位域的布局由编译器决定，不同编译器之间可能不一致。\
+建议启用 \"packed\": true。",
+    ),
+    (
+        "rplc::bit_field::straddle_boundary",
+        "位域跨越了存储单元边界。紧凑结构体中这是合法的，但读取时需要拼接两个存储单元，\
+部分编译器生成的代码效率较低。可以调整字段顺序使位域落在同一个存储单元内。",
+    ),
+    (
+        "rplc::doc::empty_comment",
+        "注释为空或只包含空白字符，不会为生成的代码提供任何信息。请补充说明或删除该注释。",
+    ),
+    (
+        "rplc::doc::localized_comment",
+        "comment 可以写成按语言代码给出的对象，生成代码时按 --comment-lang 选择其中一种语言，\
+文档后端则为每种语言各输出一列说明。对象的键必须是语言代码（两到三个小写字母，可带 -TW 之类的地区后缀），值必须是字符串。
+
+错误示例: \"comment\": {\"Chinese\": \"偏航角\", \"en\": 1}
+正确示例: \"comment\": {\"zh\": \"偏航角\", \"en\": \"Yaw angle\"}",
+    ),
+    (
+        "rplc::bit_field::too_many_per_unit",
+        "同一个存储单元中的位域过多时，每次访问都需要移位与掩码运算，在高频读写的包中会影响性能。\
+阈值可通过 --max-bit-fields-per-unit 调整。",
+    ),
+    (
+        "rplc::bit_field::mixed_with_float",
+        "大量窄位域（不超过 4 位）与浮点字段混在同一个包中，会让整个包按位流访问，浮点字段的读写也随之变慢。\
+建议将标志位集中到单独的包或整型字段中。阈值可通过 --max-tiny-bit-fields-with-float 调整。",
+    ),
+    (
+        "rplc::packed::unaligned_float",
+        "紧凑结构体中的浮点字段位于未对齐的偏移处。在部分 ARM 内核上通过指针解引用未对齐的浮点成员会触发硬件异常。\
+建议启用 volatile_view 通过 load 函数按字节访问，或手动插入填充字段使其对齐。",
+    ),
+    (
+        "rplc::bit_field::string_value",
+        "bit_field 写成了字符串形式的整数（常见于从表格导出的配置）。rplc 会按数字处理，\
+但建议改为数字形式，避免其他工具读取时出错。
+
+当前写法: \"bit_field\": \"4\"
+建议写法: \"bit_field\": 4",
+    ),
+    (
+        "rplc::compiler::mixed_bit_field_types",
+        "MSVC 只把基础类型大小相同的相邻位域放进同一个存储单元，遇到 uint8_t 之后的 uint16_t 位域时会开始新的存储单元，\
+即使启用了 #pragma pack。此时结构体的大小与 GCC、armclang 不同。\
+该警告只在 --compiler msvc 时出现，建议让相邻位域使用相同大小的基础类型。
+
+错误示例: { \"type\": \"uint8_t\", \"bit_field\": 3 }, { \"type\": \"uint16_t\", \"bit_field\": 9 }
+正确示例: { \"type\": \"uint16_t\", \"bit_field\": 3 }, { \"type\": \"uint16_t\", \"bit_field\": 9 }",
+    ),
+    (
+        "rplc::compiler::plain_bit_field_signedness",
+        "ARM Compiler 5（Keil MDK 中的 armcc）默认把没有显式写出 signed 的 int、short、long 位域当作无符号，\
+与 GCC、armclang 的行为相反，负数会被读成很大的正数。该警告只在 --compiler armcc 或 armcc-pragma 时出现。\
+int32_t 等定宽类型显式带有符号，不受影响。
+
+错误示例: { \"type\": \"int\", \"bit_field\": 5 }
+正确示例: { \"type\": \"int32_t\", \"bit_field\": 5 }",
+    ),
+    (
+        "rplc::compiler::bit_field_order",
+        "原生 C/C++ 位域的位序由编译器决定：GCC、armclang、IAR、MSVC 与 ARMCC 5 在小端目标上都从存储单元的最低位开始分配（lsb_first）。\
+包通过 bitfield_layout 声明了不同的位序时，结构体中的位域成员与线上数据的排布不一致，直接对缓冲区做类型转换会读出错误的值。\
+生成的 <包名>Wire 访问函数按声明的位序逐位读写字节，与编译器无关，请用它们代替位域成员。\
+该警告的判断依据是 --compiler 选择的编译器。
+
+错误示例: \"bitfield_layout\": \"msb_first\" 并直接读取结构体的位域成员
+正确示例: 通过 StatusWire::mode(buffer) 读取，或改为 \"bitfield_layout\": \"lsb_first\"",
+    ),
+    (
+        "rplc::compiler::native_bit_fields",
+        "C/C++ 标准没有规定原生位域的位序、存储单元的大小以及位域能否跨越存储单元，这些都由编译器与目标架构决定。\
+同一个结构体在 rplc.toml 的 [compilers] 中列出的多个编译器上编译时（例如下位机用 armcc、上位机用 MSVC），\
+对缓冲区直接做类型转换可能在一端读出错误的值，而单个编译器的检查无法发现这种差异。\
+声明 bitfield_layout 后会额外生成 <包名>Wire 访问函数，按声明的位序用移位与掩码逐位读写字节，结果与编译器无关。\
+这是一条建议：确认各编译器的布局一致时，可以在 [warnings] 中为它设置预算。
+
+[compilers]
+profiles = [\"armcc\", \"msvc\"]
+
+当前写法: 含 bit_field 的包直接读写结构体的位域成员
+建议写法: \"bitfield_layout\": \"lsb_first\"，并通过 StatusWire::mode(buffer) 读取",
+    ),
+];
+
+/// 诊断代码的详细说明，代码可以省略 `rplc::` 前缀
+pub fn explain(code: &str) -> Option<&'static str> {
+    let code = code.trim();
+    let code = code.strip_prefix("rplc::").unwrap_or(code);
+    EXPLANATIONS
+        .iter()
+        .find(|(known, _)| known.strip_prefix("rplc::") == Some(code))
+        .map(|(_, text)| *text)
+}
+
+/// 所有带有详细说明的诊断代码
+pub fn explained_codes() -> impl Iterator<Item = &'static str> {
+    EXPLANATIONS.iter().map(|(code, _)| *code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miette::Diagnostic;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_every_validation_code_is_explained() {
+        let codes = crate::rules::registry();
+        let codes: HashSet<String> = codes
+            .iter()
+            .map(|code| code.code().unwrap().to_string())
+            .collect();
+        let explained: HashSet<String> = explained_codes().map(str::to_string).collect();
+        assert_eq!(codes, explained);
+        assert_eq!(explained.len(), EXPLANATIONS.len());
+    }
+
+    #[test]
+    fn test_explain_accepts_short_code() {
+        let full = explain("rplc::bit_field::length_overflow").unwrap();
+        assert_eq!(explain("bit_field::length_overflow"), Some(full));
+        assert!(full.contains("uint16_t"));
+        assert_eq!(explain("rplc::no_such_code"), None);
+    }
+}
@@ -0,0 +1,157 @@
+//! 包布局的指纹，通信双方据此确认双方的代码生成自同一份协议定义
+
+use crate::canonical::canonicalize;
+use crate::config::{BitOrder, Config, Endianness, ReservedKind};
+
+/// 描述格式的版本，改变描述内容时需要同时修改，避免新旧哈希意外相等
+const DESCRIPTOR_VERSION: &str = "rplc-layout-1";
+
+/// 包布局的 32 位哈希（FNV-1a），由规范化后的定义计算：
+/// - 参与计算：包名、命令 ID、`packed`、`align`、`msb_first` 位序、大端字节序，以及每个字段的名称、类型、位宽、固定偏移与不同于包的字节序；
+///   字段引用其他包时，被引用的包的 `packed`、`align` 与字段也参与计算
+/// - 不参与计算：注释、命名空间、头文件保护宏、分组与嵌套结构体等只影响代码组织的内容
+pub fn layout_hash(config: &Config) -> u32 {
+    fnv1a(layout_descriptor(&canonicalize(config.clone())).as_bytes())
+}
+
+/// 生成代码中哈希的写法，例如 `0x1A2B3C4D`
+pub(crate) fn format_layout_hash(hash: u32) -> String {
+    format!("0x{:08X}", hash)
+}
+
+fn layout_descriptor(config: &Config) -> String {
+    let mut out = format!(
+        "{}\n{}\n{}\npacked={}\nalign={}\n",
+        DESCRIPTOR_VERSION,
+        config.packet_name,
+        config.command_id,
+        config.packed,
+        config.align.unwrap_or(0)
+    );
+    // 只在与默认位序不同时写入，已有包的哈希保持不变
+    if config.bitfield_layout == Some(BitOrder::MsbFirst) {
+        out.push_str("bit_order=msb_first\n");
+    }
+    if config.endianness == Some(Endianness::Big) {
+        out.push_str("endianness=big\n");
+    }
+    push_field_descriptors(&mut out, config);
+    // 没有引用其他包时不写入，已有包的哈希保持不变
+    for dependency in &config.dependencies {
+        let dependency = canonicalize(dependency.clone());
+        out.push_str(&format!(
+            "struct {}\npacked={}\nalign={}\n",
+            dependency.packet_name,
+            dependency.packed,
+            dependency.align.unwrap_or(0)
+        ));
+        push_field_descriptors(&mut out, &dependency);
+    }
+    out
+}
+
+fn push_field_descriptors(out: &mut String, config: &Config) {
+    for field in config
+        .fields
+        .iter()
+        .filter(|field| field.reserved != Some(ReservedKind::Gap))
+    {
+        let bits = field
+            .bit_field
+            .map_or_else(|| "-".to_string(), |bits| bits.to_string());
+        match field.offset {
+            Some(offset) => {
+                out.push_str(&format!("{}:{}:{}@{}", field.name, field.ty, bits, offset))
+            }
+            None => out.push_str(&format!("{}:{}:{}", field.name, field.ty, bits)),
+        }
+        // 只在字段的字节序与包不同时写入，与包相同的声明不改变哈希
+        let endianness = config.field_endianness(field);
+        if endianness != config.endianness.unwrap_or_default() {
+            out.push_str(&format!("!{}", endianness.as_str()));
+        }
+        out.push('\n');
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811C_9DC5, |hash, &byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(json: &str) -> u32 {
+        layout_hash(&serde_json::from_str(json).unwrap())
+    }
+
+    const BASE: &str = r#"{"packet_name":"Status","command_id":"0x0101","fields":[
+        {"name":"mode","type":"uint8_t","bit_field":4,"comment":"模式"},
+        {"name":"speed","type":"int","bit_field":12}]}"#;
+
+    #[test]
+    fn test_fnv1a_reference_values() {
+        assert_eq!(fnv1a(b""), 0x811C_9DC5);
+        assert_eq!(fnv1a(b"a"), 0xE40C_292C);
+        assert_eq!(fnv1a(b"foobar"), 0xBF9C_F968);
+    }
+
+    #[test]
+    fn test_layout_hash_ignores_spelling_and_comments() {
+        let same = r#"{"packet_name":"Status","command_id":"257","namespace":"Robot",
+            "fields":[{"section":"Flags"},{"name":"mode","type":"uint8_t","bit_field":"4"},
+            {"name":"speed","type":"signed int","bit_field":12,"comment":"速度"}]}"#;
+        assert_eq!(hash(BASE), hash(same));
+        assert_eq!(format_layout_hash(hash(BASE)).len(), 10);
+    }
+
+    #[test]
+    fn test_layout_hash_ignores_default_bit_order() {
+        let lsb = BASE.replace(r#""fields""#, r#""bitfield_layout":"lsb_first","fields""#);
+        assert_eq!(hash(&lsb), hash(BASE));
+        let little = BASE.replace(r#""fields""#, r#""endianness":"little","fields""#);
+        assert_eq!(hash(&little), hash(BASE));
+        // 字段声明与包相同的字节序
+        let field = BASE.replace(
+            r#""name":"speed""#,
+            r#""name":"speed","endianness":"little""#,
+        );
+        assert_eq!(hash(&field), hash(BASE));
+    }
+
+    #[test]
+    fn test_layout_hash_changes_with_layout() {
+        let base = hash(BASE);
+        let variants = [
+            BASE.replace("0x0101", "0x0102"),
+            BASE.replace(r#""bit_field":4"#, r#""bit_field":5"#),
+            BASE.replace("uint8_t", "uint16_t"),
+            BASE.replace("speed", "velocity"),
+            BASE.replace(r#""fields""#, r#""packed":false,"fields""#),
+            BASE.replace(r#""fields""#, r#""bitfield_layout":"msb_first","fields""#),
+            BASE.replace(r#""bit_field":12"#, r#""bit_field":12,"offset":2"#),
+            BASE.replace(r#""fields""#, r#""endianness":"big","fields""#),
+            BASE.replace(r#""name":"speed""#, r#""name":"speed","endianness":"big""#),
+            BASE.replace(r#""fields":["#, r#""fields":[{"reserved":2},"#),
+        ];
+        for variant in variants {
+            assert_ne!(hash(&variant), base, "{}", variant);
+        }
+    }
+
+    #[test]
+    fn test_layout_hash_follows_referenced_packets() {
+        let gimbal = |imu_type: &str| {
+            format!(
+                r#"{{"packet_name":"Gimbal","command_id":"2","fields":[{{"name":"imu","type":"Imu"}}],
+                "dependencies":[{{"packet_name":"Imu","command_id":"1","comment":"IMU",
+                    "fields":[{{"name":"yaw","type":"{}"}}]}}]}}"#,
+                imu_type
+            )
+        };
+        assert_ne!(hash(&gimbal("float")), hash(&gimbal("double")));
+    }
+}
@@ -0,0 +1,368 @@
+use serde_json::Value;
+
+use crate::canonical::canonical_type;
+use crate::diagnostics::{RplcDiagnostic, Severity, ValidationCode};
+use crate::validator::canonical_command_id;
+
+const PACKET_SET_KEYS: &[&str] = &["rplc_schema", "protocol_version", "packets"];
+
+const PACKET_KEYS: &[&str] = &[
+    "rplc_schema",
+    "packet_name",
+    "command_id",
+    "namespace",
+    "header_guard",
+    "includes",
+    "packed",
+    "align",
+    "expected_size",
+    "bitfield_layout",
+    "volatile_view",
+    "serialize",
+    "endianness",
+    "crc",
+    "crc_append",
+    "emit_traits",
+    "traits_template",
+    "comment",
+    "enums",
+    "retired_field_ids",
+    "fields",
+    "dependencies",
+];
+
+const ENUM_KEYS: &[&str] = &["name", "type", "comment", "values"];
+
+const ENUMERATOR_KEYS: &[&str] = &["name", "value", "comment"];
+
+const FIELD_KEYS: &[&str] = &[
+    "section",
+    "reserved",
+    "name",
+    "id",
+    "type",
+    "array_len",
+    "bit_field",
+    "offset",
+    "endianness",
+    "comment",
+    "unit",
+    "min",
+    "max",
+    "typical",
+    "values",
+    "group",
+];
+
+/// 将协议定义格式化为规范形式：
+/// - 按文档中的顺序排列已知的键，未知的键按字母序排在其后
+/// - 命令 ID 与类型别名使用与 `canonicalize` 相同的规范写法，字符串形式的位域转换为数字
+/// - 两空格缩进，文件以换行结尾
+///
+/// 只有 JSON 语法错误会导致失败，语义问题由 `validate` 报告
+pub fn format_definition(json_input: &str) -> Result<String, Vec<RplcDiagnostic>> {
+    let mut root: Value = serde_json::from_str(json_input)
+        .map_err(|e| vec![json_syntax_diagnostic(json_input, &e)])?;
+
+    match &mut root {
+        Value::Array(packets) => packets.iter_mut().for_each(normalize_packet),
+        Value::Object(map) if map.contains_key("packets") => {
+            if let Some(Value::Array(packets)) = map.get_mut("packets") {
+                packets.iter_mut().for_each(normalize_packet);
+            }
+        }
+        packet => normalize_packet(packet),
+    }
+
+    Ok(write_definition(&root))
+}
+
+/// 按规范的键顺序与缩进输出定义，不改动其中的值
+pub(crate) fn write_definition(root: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, root, 0, Shape::Root);
+    out.push('\n');
+    out
+}
+
+pub(crate) fn json_syntax_diagnostic(
+    json_input: &str,
+    error: &serde_json::Error,
+) -> RplcDiagnostic {
+    // serde_json 的行列号从 1 开始，列号按字节计数
+    let offset = json_input
+        .split_inclusive('\n')
+        .take(error.line().saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>()
+        + error.column().saturating_sub(1);
+    let offset = offset.min(json_input.len());
+    RplcDiagnostic {
+        code: ValidationCode::InvalidJson(error.to_string()),
+        severity: Severity::Error,
+        span: Some((offset, 0)),
+        help: None,
+    }
+}
+
+fn normalize_packet(packet: &mut Value) {
+    let Value::Object(map) = packet else {
+        return;
+    };
+    if let Some(Value::String(id)) = map.get_mut("command_id")
+        && let Some(canonical) = canonical_command_id(id)
+    {
+        *id = canonical;
+    }
+    if let Some(Value::Array(enums)) = map.get_mut("enums") {
+        for def in enums {
+            if let Some(Value::String(ty)) = def.get_mut("type") {
+                *ty = canonical_type(ty);
+            }
+        }
+    }
+    if let Some(Value::Array(fields)) = map.get_mut("fields") {
+        normalize_fields(fields);
+    }
+}
+
+pub(crate) fn normalize_fields(fields: &mut [Value]) {
+    for field in fields {
+        let Value::Object(field) = field else {
+            continue;
+        };
+        if let Some(Value::Array(members)) = field.get_mut("group") {
+            normalize_fields(members);
+        }
+        if let Some(Value::String(ty)) = field.get_mut("type") {
+            *ty = canonical_type(ty);
+        }
+        if let Some(bit_field) = field.get_mut("bit_field")
+            && let Some(bits) = bit_field
+                .as_str()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+        {
+            *bit_field = Value::from(bits);
+        }
+    }
+}
+
+/// 值在定义中所处的位置，决定对象的键顺序与子元素的位置
+#[derive(Clone, Copy)]
+enum Shape {
+    /// 顶层：单个包、包数组或包集合
+    Root,
+    PacketSet,
+    Packets,
+    Packet,
+    Fields,
+    Field,
+    Enums,
+    Enum,
+    Enumerators,
+    Enumerator,
+    Other,
+}
+
+impl Shape {
+    fn key_order(self) -> &'static [&'static str] {
+        match self {
+            Shape::PacketSet => PACKET_SET_KEYS,
+            Shape::Packet => PACKET_KEYS,
+            Shape::Field => FIELD_KEYS,
+            Shape::Enum => ENUM_KEYS,
+            Shape::Enumerator => ENUMERATOR_KEYS,
+            _ => &[],
+        }
+    }
+
+    fn element(self) -> Shape {
+        match self {
+            Shape::Root | Shape::Packets => Shape::Packet,
+            Shape::Fields => Shape::Field,
+            Shape::Enums => Shape::Enum,
+            Shape::Enumerators => Shape::Enumerator,
+            _ => Shape::Other,
+        }
+    }
+
+    fn member(self, key: &str) -> Shape {
+        match (self, key) {
+            (Shape::PacketSet, "packets") => Shape::Packets,
+            (Shape::Packet, "fields") | (Shape::Field, "group") => Shape::Fields,
+            (Shape::Packet, "enums") => Shape::Enums,
+            (Shape::Enum, "values") => Shape::Enumerators,
+            _ => Shape::Other,
+        }
+    }
+}
+
+fn write_value(out: &mut String, value: &Value, depth: usize, shape: Shape) {
+    match value {
+        Value::Array(items) if !items.is_empty() => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                push_indent(out, depth + 1);
+                write_value(out, item, depth + 1, shape.element());
+                out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            push_indent(out, depth);
+            out.push(']');
+        }
+        Value::Object(map) if !map.is_empty() => {
+            let shape = match shape {
+                Shape::Root if map.contains_key("packets") => Shape::PacketSet,
+                Shape::Root => Shape::Packet,
+                other => other,
+            };
+            let order = shape.key_order();
+            let known = order.iter().filter_map(|key| map.get_key_value(*key));
+            let unknown = map.iter().filter(|(key, _)| !order.contains(&key.as_str()));
+            let entries: Vec<_> = known.chain(unknown).collect();
+
+            out.push_str("{\n");
+            for (i, (key, item)) in entries.iter().enumerate() {
+                push_indent(out, depth + 1);
+                out.push_str(&Value::from(key.as_str()).to_string());
+                out.push_str(": ");
+                write_value(out, item, depth + 1, shape.member(key));
+                out.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+            }
+            push_indent(out, depth);
+            out.push('}');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+fn push_indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_orders_keys_and_normalizes_values() {
+        let json = r#"{"fields":[{"comment":"F","bit_field":"3","type":"uint8_t","name":"flags"}],
+            "command_id":"0x01_0a","packet_name":"Status","x_owner":"vision"}"#;
+        let formatted = format_definition(json).unwrap();
+        assert_eq!(
+            formatted,
+            r#"{
+  "packet_name": "Status",
+  "command_id": "0x010A",
+  "fields": [
+    {
+      "name": "flags",
+      "type": "uint8_t",
+      "bit_field": 3,
+      "comment": "F"
+    }
+  ],
+  "x_owner": "vision"
+}
+"#
+        );
+        // 格式化是幂等的
+        assert_eq!(format_definition(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_nested_group() {
+        let json = r#"{"packet_name":"A","command_id":"1","fields":[
+            {"group":[{"type":"uint8_t","bit_field":"3","name":"a"}],"name":"imu"}]}"#;
+        let formatted = format_definition(json).unwrap();
+        assert!(formatted.contains(
+            r#"    {
+      "name": "imu",
+      "group": [
+        {
+          "name": "a",
+          "type": "uint8_t",
+          "bit_field": 3
+        }
+      ]
+    }"#
+        ));
+    }
+
+    #[test]
+    fn test_format_enums() {
+        let json = r#"{"fields":[{"type":"Mode","name":"mode"}],"packet_name":"A","command_id":"1",
+            "enums":[{"values":[{"value":0,"name":"idle"}],"type":"signed int","name":"Mode"}]}"#;
+        let formatted = format_definition(json).unwrap();
+        assert!(formatted.contains(
+            r#"  "enums": [
+    {
+      "name": "Mode",
+      "type": "int",
+      "values": [
+        {
+          "name": "idle",
+          "value": 0
+        }
+      ]
+    }
+  ],
+  "fields": [
+    {
+      "name": "mode",
+      "type": "Mode"
+    }
+  ]"#
+        ));
+    }
+
+    #[test]
+    fn test_format_resolves_type_aliases() {
+        let json = r#"{"packet_name":"A","command_id":"1","fields":[
+            {"name":"a","type":"signed int[2]"},{"name":"b","type":"signed char"}]}"#;
+        let formatted = format_definition(json).unwrap();
+        assert!(formatted.contains(r#""type": "int[2]""#));
+        assert!(formatted.contains(r#""type": "signed char""#));
+    }
+
+    #[test]
+    fn test_format_packet_set() {
+        let json = r#"{"packets":[{"fields":[],"command_id":"258","packet_name":"A"}],
+            "protocol_version":"1.0.0"}"#;
+        let formatted = format_definition(json).unwrap();
+        assert_eq!(
+            formatted,
+            r#"{
+  "protocol_version": "1.0.0",
+  "packets": [
+    {
+      "packet_name": "A",
+      "command_id": "0x0102",
+      "fields": []
+    }
+  ]
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_format_array_keeps_invalid_values() {
+        // 无法解析的命令 ID 保持原样，交给 validate 报告
+        let json = r#"[{"command_id":"oops","packet_name":"A","fields":[]}]"#;
+        let formatted = format_definition(json).unwrap();
+        assert!(formatted.starts_with("[\n  {\n    \"packet_name\": \"A\",\n"));
+        assert!(formatted.contains(r#""command_id": "oops""#));
+    }
+
+    #[test]
+    fn test_format_syntax_error_has_span() {
+        let json = "{\n  \"packet_name\": \"A\",\n  oops\n}";
+        let diags = format_definition(json).unwrap_err();
+        assert_eq!(diags.len(), 1);
+        assert!(matches!(diags[0].code, ValidationCode::InvalidJson(_)));
+        let (offset, _) = diags[0].span.unwrap();
+        assert_eq!(&json[offset..offset + 4], "oops");
+    }
+}
@@ -1,7 +1,26 @@
-use crate::config::Config;
+use crate::canonical::default_header_guard;
+use crate::compiler::{Compiler, CppStandard, Packing};
+use crate::config::{
+    BitOrder, Comment, Config, ConfigOrArray, CrcKind, Endianness, Field, FieldGroup, PacketSet,
+    ProtocolVersion, attach_dependencies, render_template,
+};
+use crate::cpp_includes::CppIncludes;
 use crate::diagnostics::Severity;
-use crate::validator::{c_type_to_bit_field_size, parse_array_type, parse_command_id, validate};
+use crate::diff::{ChangeKind, VersionBump, diff_configs};
+use crate::fingerprint::{format_layout_hash, layout_hash};
+use crate::header_template::{
+    HeaderContext, HeaderTemplate, PacketContext, TemplateError, render_header,
+};
+use crate::latex_backend::LatexColumn;
+use crate::layout::{c_type_size, compute_layout, with_reserved_gaps};
+use crate::validator::{
+    c_type_to_bit_field_size, format_command_id, is_cpp_keyword, is_identifier, parse_array_type,
+    parse_command_id, validate, validate_multiple,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use thiserror::Error;
+use tracing::debug_span;
 
 #[derive(Debug, Error)]
 pub enum GenerateError {
@@ -9,19 +28,152 @@ pub enum GenerateError {
     JsonError(#[from] serde_json::Error),
     #[error("配置验证未通过，请检查错误信息")]
     ValidationError,
+    #[error("{backend} 后端不支持该定义: {reason}")]
+    Unsupported {
+        backend: &'static str,
+        reason: String,
+    },
+    #[error("命名空间别名 '{0}' 不是有效的 C++ 标识符")]
+    InvalidNamespaceAlias(String),
+    #[error(transparent)]
+    Template(#[from] TemplateError),
 }
 
-#[derive(Debug, Clone)]
-struct BitLayoutField {
+/// 大括号位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum BraceStyle {
+    /// 大括号另起一行（默认）
+    #[default]
+    NextLine,
+    /// 大括号与声明在同一行
+    SameLine,
+}
+
+/// 代码生成选项
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GenerateOptions {
+    /// 每级缩进的空格数，`use_tabs` 为 true 时忽略
+    pub indent_width: usize,
+    /// 使用制表符缩进
+    pub use_tabs: bool,
+    pub brace_style: BraceStyle,
+    /// 将字段的类型、名称与尾注释按列对齐
+    pub align_fields: bool,
+    /// 目标编译器，决定 packed 的写法
+    pub compiler: Compiler,
+    /// 生成的 C++ 代码遵循的语言标准
+    pub cpp_standard: CppStandard,
+    /// 不包含 `<cstdint>`，改为写出定宽整数类型的后备 typedef，用于标准头文件受限的裸机工程；
+    /// 包声明了 `includes` 时以其为准。数组字段的 `<array>`、位域包的 `<tuple>` 与 traits 的
+    /// `<RPL/Meta/PacketTraits.hpp>` 仍按需包含：前两者是纯模板头文件，后者由包的 `emit_traits` 控制
+    pub freestanding: bool,
+    /// 在包所在的命名空间之后额外生成的命名空间别名，例如 `rpl_pkts`
+    pub namespace_alias: Option<String>,
+    /// 多语言注释输出的语言，例如 `en`；未指定时使用 `zh`，文档后端则为每种语言各输出一列
+    pub comment_language: Option<String>,
+    /// 注册表与汇总头文件中包含各包头文件的路径，键为包名；没有记录的包包含 `<包名>.hpp`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub packet_includes: BTreeMap<String, String>,
+    /// LaTeX 表格输出的列；未指定时输出字段、类型、位数、单位与说明
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latex_columns: Option<Vec<LatexColumn>>,
+    /// 替代内置模板渲染 C++ 头文件的模板；注册表、汇总头文件与其他后端不受影响
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header_template: Option<HeaderTemplate>,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            use_tabs: false,
+            brace_style: BraceStyle::NextLine,
+            align_fields: false,
+            compiler: Compiler::Gcc,
+            cpp_standard: CppStandard::default(),
+            freestanding: false,
+            namespace_alias: None,
+            comment_language: None,
+            packet_includes: BTreeMap::new(),
+            latex_columns: None,
+            header_template: None,
+        }
+    }
+}
+
+impl GenerateOptions {
+    /// 指定层级的缩进字符串
+    pub(crate) fn indent(&self, level: usize) -> String {
+        if self.use_tabs {
+            "\t".repeat(level)
+        } else {
+            " ".repeat(self.indent_width * level)
+        }
+    }
+
+    /// 包含包 `name` 的头文件时使用的路径
+    pub(crate) fn packet_include(&self, name: &str) -> String {
+        self.packet_includes
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| format!("{}.hpp", name))
+    }
+
+    /// 头文件中 constexpr 变量的写法；C++17 之前没有 inline 变量，每个翻译单元各有一份副本
+    pub(crate) fn constexpr_variable(&self) -> &'static str {
+        if self.cpp_standard >= CppStandard::Cpp17 {
+            "inline constexpr"
+        } else {
+            "constexpr"
+        }
+    }
+
+    /// 打开命名空间 `ns`；C++17 之前不能写 `namespace A::B`，逐层展开
+    pub(crate) fn namespace_open(&self, ns: &str) -> String {
+        if self.cpp_standard >= CppStandard::Cpp17 {
+            format!("namespace {} {{", ns)
+        } else {
+            ns.split("::")
+                .map(|part| format!("namespace {} {{", part))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+    }
+
+    /// 与 `namespace_open` 对应的右大括号及注释
+    pub(crate) fn namespace_close(&self, ns: &str) -> String {
+        let depth = if self.cpp_standard >= CppStandard::Cpp17 {
+            1
+        } else {
+            ns.split("::").count()
+        };
+        format!("{} // namespace {}", vec!["}"; depth].join(" "), ns)
+    }
+
+    /// 声明之后的左大括号（含换行）
+    pub(crate) fn open_brace(&self) -> &'static str {
+        match self.brace_style {
+            BraceStyle::NextLine => "\n{\n",
+            BraceStyle::SameLine => " {\n",
+        }
+    }
+}
+
+/// `BitLayout` 中的一项，头文件模板中为 `packet.bit_layout` 的元素
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct BitLayoutField {
+    #[serde(rename = "type")]
     ty: String,
     bits: u32,
+    #[serde(skip)]
     is_array: bool,
+    #[serde(rename = "array_len")]
     array_size: Option<u32>,
 }
 
 #[derive(Debug, Clone)]
-struct BitLayoutPlan {
-    fields: Vec<BitLayoutField>,
+pub(crate) struct BitLayoutPlan {
+    pub fields: Vec<BitLayoutField>,
     total_bits: u32,
 }
 
@@ -45,7 +197,6 @@ fn analyze_bit_layout(config: &Config) -> Option<BitLayoutPlan> {
 
         let (field_bits, is_array, array_size) = if let Some(arr_size) = arr_size {
             // 数组字段
-            let arr_size = arr_size as u32;
             let field_bits = base_bits * arr_size;
             (field_bits, true, Some(arr_size))
         } else if let Some(bit_width) = field.bit_field {
@@ -78,269 +229,2835 @@ fn bytes_from_bits(bits: u32) -> u32 {
     bits.div_ceil(8)
 }
 
-pub fn generate(json_input: &str) -> Result<String, GenerateError> {
-    let config: Config = serde_json::from_str(json_input)?;
-    let diags = validate(json_input);
-    for diag in diags {
-        if diag.severity == Severity::Error {
-            return Err(GenerateError::ValidationError);
-        }
+/// 包的 Doxygen 注释：包注释作为 @brief，并附带 cmd 与计算得到的大小
+pub(crate) fn packet_doc_block(config: &Config, cmd_id: u16) -> String {
+    let mut doc = String::from("/**\n");
+    if let Some(comment) = &config.comment {
+        doc.push_str(&format!(" * @brief {}\n", comment));
     }
-    let cmd_id = parse_command_id(&config.command_id).unwrap();
-    let guard = config
-        .header_guard
-        .clone()
-        .unwrap_or_else(|| format!("RPL_{}_HPP", config.packet_name.to_uppercase()));
-    let bit_layout_plan = analyze_bit_layout(&config);
-
-    let mut out = String::new();
-    // Header Guard
-    out.push_str(&format!("#ifndef {}\n", guard));
-    out.push_str(&format!("#define {}\n\n", guard));
+    match compute_layout(config) {
+        Some(layout) => doc.push_str(&format!(
+            " * @details cmd = {}, size = {} bytes\n",
+            format_command_id(cmd_id),
+            layout.size
+        )),
+        None => doc.push_str(&format!(
+            " * @details cmd = {}\n",
+            format_command_id(cmd_id)
+        )),
+    }
+    doc.push_str(" */\n");
+    doc
+}
 
-    // Includes
-    out.push_str("#include <cstdint>\n");
-    out.push_str("#include <array>\n");
-    if bit_layout_plan.is_some() {
-        out.push_str("#include <tuple>\n");
-        out.push_str("#include <RPL/Meta/BitstreamTraits.hpp>\n");
+/// 字段所在分组的分隔注释，非首个分组之前空一行；C、C++ 与 Rust 的注释语法相同
+pub(crate) fn push_section_banner(out: &mut String, field: &Field, indent: &str) {
+    if let Some(section) = &field.section {
+        if !out.ends_with("{\n") {
+            out.push('\n');
+        }
+        out.push_str(&format!("{}// ---- {} ----\n", indent, section));
     }
-    out.push_str("#include <RPL/Meta/PacketTraits.hpp>\n\n");
+}
 
-    // Namespace
-    if let Some(ns) = &config.namespace {
-        out.push_str(&format!("namespace {} {{\n\n", ns));
+/// 输出结构体定义（注释、字段与 packed 属性）
+///
+/// 使用 `#pragma pack` 的编译器把注释放在 pragma 之后，保证注释紧贴结构体
+pub(crate) fn push_struct(
+    out: &mut String,
+    config: &Config,
+    struct_name: &str,
+    doc: &str,
+    options: &GenerateOptions,
+) {
+    let packing = config.packed.then(|| options.compiler.packing());
+    match packing {
+        Some(Packing::Pragma) => out.push_str("#pragma pack(push, 1)\n"),
+        Some(Packing::ArmccPragma) => out.push_str("#pragma push\n#pragma pack(1)\n"),
+        _ => {}
+    }
+    out.push_str(doc);
+
+    let alignas = config
+        .align
+        .map(|align| format!("alignas({}) ", align))
+        .unwrap_or_default();
+    let keyword = if packing == Some(Packing::Keyword) {
+        "__packed "
+    } else {
+        ""
+    };
+    out.push_str(&format!(
+        "{}struct {}{}{}",
+        keyword,
+        alignas,
+        struct_name,
+        options.open_brace()
+    ));
+    let members = struct_members(config, packing, options);
+    for line in &members.lines {
+        line.push_to(out);
+    }
+    out.push_str(&members.tail);
+
+    match packing {
+        Some(Packing::Attribute) => out.push_str("} __attribute__((packed));\n\n"),
+        Some(Packing::Pragma) => out.push_str("};\n#pragma pack(pop)\n\n"),
+        Some(Packing::ArmccPragma) => out.push_str("};\n#pragma pop\n\n"),
+        Some(Packing::Keyword) => out.push_str("};\n\n"),
+        None => out.push_str("} ;\n\n"),
     }
+}
 
-    // Add Doxygen-style comment if provided
-    if let Some(comment) = &config.comment {
-        out.push_str(&format!("/**\n * @brief {}\n */\n", comment));
+/// 结构体中一个字段的成员行，头文件模板逐个字段输出
+#[derive(Debug, Serialize)]
+pub(crate) struct MemberLine {
+    /// 字段之前的分组分隔注释与嵌套结构体的开闭行，每行以换行结尾
+    pub preamble: String,
+    /// 成员行的缩进，位于嵌套结构体中时多一级
+    pub indent: String,
+    /// C++ 类型，例如 `std::array<float, 3>`；`--align-fields` 时已补齐到同一列
+    pub cpp_type: String,
+    /// 成员名、位域宽度与分号，例如 `flags : 3;`；`--align-fields` 且有注释时已补齐到同一列
+    pub declarator: String,
+    #[serde(skip)]
+    comment: Option<String>,
+}
+
+impl MemberLine {
+    fn push_to(&self, out: &mut String) {
+        out.push_str(&self.preamble);
+        out.push_str(&format!("{}{} ", self.indent, self.cpp_type));
+        match &self.comment {
+            Some(comment) => out.push_str(&format!("{} ///< {}", self.declarator, comment)),
+            None => out.push_str(&self.declarator),
+        }
+        out.push('\n');
     }
-    out.push_str(&format!("struct {}\n{{\n", config.packet_name));
+}
 
-    // Fields
-    for field in &config.fields {
-        // 解析数组类型
-        if let Some((base_type, arr_size)) = parse_array_type(&field.ty) {
-            if let Some(size) = arr_size {
+/// 结构体花括号之间的内容：每个字段的成员行，以及之后的嵌套结构体结尾、常量与取值查找函数
+pub(crate) struct StructMembers {
+    pub lines: Vec<MemberLine>,
+    pub tail: String,
+}
+
+pub(crate) fn struct_members(
+    config: &Config,
+    packing: Option<Packing>,
+    options: &GenerateOptions,
+) -> StructMembers {
+    let indent = options.indent(1);
+
+    // Fields: 每个字段拆分为 (类型, 声明, 注释) 三列
+    let rows: Vec<(String, String, Option<&str>)> = config
+        .fields
+        .iter()
+        .map(|field| {
+            let (ty, is_array) = match parse_array_type(&field.ty) {
                 // 数组类型: std::array<type, size> name;
-                out.push_str(&format!("    std::array<{}, {}> {};", base_type, size, field.name));
-                if let Some(cmt) = &field.comment {
-                    out.push_str(&format!(" ///< {}", cmt));
-                }
-                out.push('\n');
-            } else {
-                // 非数组类型: type name;
-                out.push_str(&format!("    {} {}", field.ty, field.name));
-                if let Some(bf) = field.bit_field {
-                    out.push_str(&format!(" : {};", bf));
-                } else {
-                    out.push(';');
-                }
-                if let Some(cmt) = &field.comment {
-                    out.push_str(&format!(" ///< {}", cmt));
-                }
-                out.push('\n');
+                Some((base_type, Some(size))) => (
+                    format!(
+                        "std::array<{}, {}>",
+                        struct_member_type(config, field, base_type),
+                        size
+                    ),
+                    true,
+                ),
+                Some((base_type, None)) => (struct_member_type(config, field, base_type), false),
+                // 解析失败，使用原始类型
+                None => (field.ty.clone(), false),
+            };
+            let decl = match field.bit_field {
+                Some(bf) if !is_array => format!("{} : {};", field.name, bf),
+                _ => format!("{};", field.name),
+            };
+            (ty, decl, field.comment.as_ref().map(Comment::as_str))
+        })
+        .collect();
+
+    let (ty_width, decl_width) = if options.align_fields {
+        (
+            rows.iter().map(|r| r.0.chars().count()).max().unwrap_or(0),
+            rows.iter()
+                .filter(|r| r.2.is_some())
+                .map(|r| r.1.chars().count())
+                .max()
+                .unwrap_or(0),
+        )
+    } else {
+        (0, 0)
+    };
+
+    // 分隔注释根据之前的输出决定是否空行，因此在完整的结构体文本上生成，再切分到各字段
+    let mut out = String::from("{\n");
+    let mut lines = Vec::with_capacity(rows.len());
+    let member_indent = options.indent(2);
+    let mut open_group: Option<&FieldGroup> = None;
+    for (field, (ty, decl, comment)) in config.fields.iter().zip(rows) {
+        let start = out.len();
+        if open_group.map(|g| g.id) != field.group.as_ref().map(|g| g.id) {
+            if let Some(group) = open_group.take() {
+                push_group_end(&mut out, group, &indent);
+            }
+            push_section_banner(&mut out, field, &indent);
+            if let Some(group) = &field.group {
+                push_group_start(&mut out, group, packing, &indent, options);
+                open_group = Some(group);
             }
         } else {
-            // 解析失败，使用原始类型
-            out.push_str(&format!("    {} {}", field.ty, field.name));
-            if let Some(bf) = field.bit_field {
-                out.push_str(&format!(" : {};", bf));
+            push_section_banner(&mut out, field, &indent);
+        }
+
+        let line = MemberLine {
+            preamble: out[start..].to_string(),
+            indent: if open_group.is_some() {
+                member_indent.clone()
             } else {
-                out.push(';');
+                indent.clone()
+            },
+            cpp_type: format!("{:<ty_width$}", ty),
+            declarator: match comment {
+                Some(_) => format!("{:<decl_width$}", decl),
+                None => decl,
+            },
+            comment: comment.map(str::to_string),
+        };
+        line.push_to(&mut out);
+        lines.push(line);
+    }
+    let start = out.len();
+    if let Some(group) = open_group {
+        push_group_end(&mut out, group, &indent);
+    }
+    push_limit_constants(&mut out, config, &indent);
+    push_field_id_constants(&mut out, config, &indent);
+    push_value_lookups(&mut out, config, options);
+    StructMembers {
+        lines,
+        tail: out[start..].to_string(),
+    }
+}
+
+/// 成员声明使用的元素类型：引用枚举的字段使用枚举名，其余使用基础类型
+fn member_type<'a>(field: &'a Field, base_type: &'a str) -> &'a str {
+    field.enum_type.as_deref().unwrap_or(base_type)
+}
+
+/// 结构体成员的元素类型：引用的包位于其他命名空间时写出从全局命名空间开始的限定名
+fn struct_member_type(config: &Config, field: &Field, base_type: &str) -> String {
+    match config.dependency(base_type) {
+        Some(packet) if packet.namespace != config.namespace => match &packet.namespace {
+            Some(ns) => format!("::{}::{}", ns, base_type),
+            None => format!("::{}", base_type),
+        },
+        _ => member_type(field, base_type).to_string(),
+    }
+}
+
+/// 输出包定义的具名枚举，使用 `enum class` 并显式指定底层类型，保证与线上宽度一致
+fn push_enums(out: &mut String, config: &Config, options: &GenerateOptions) {
+    let indent = options.indent(1);
+    for def in &config.enums {
+        if let Some(comment) = &def.comment {
+            out.push_str(&format!("/// {}\n", comment.as_str()));
+        }
+        out.push_str(&format!(
+            "enum class {} : {}{}",
+            def.name,
+            def.ty,
+            options.open_brace()
+        ));
+        for item in &def.values {
+            out.push_str(&format!("{}{} = {},", indent, item.name, item.value));
+            if let Some(comment) = &item.comment {
+                out.push_str(&format!(" ///< {}", comment.as_str()));
             }
-            if let Some(cmt) = &field.comment {
-                out.push_str(&format!(" ///< {}", cmt));
+            out.push('\n');
+        }
+        out.push_str("};\n\n");
+    }
+}
+
+/// 范围标注生成的 `static constexpr` 成员，例如 `k_yaw_max`；静态成员不影响结构体布局
+fn push_limit_constants(out: &mut String, config: &Config, indent: &str) {
+    let mut first = true;
+    for field in &config.fields {
+        let Some((base_type, _)) = parse_array_type(&field.ty) else {
+            continue;
+        };
+        for (key, value) in field.limits() {
+            if first {
+                out.push('\n');
+                first = false;
             }
+            out.push_str(&format!(
+                "{}static constexpr {} k_{}_{} = {};\n",
+                indent,
+                base_type,
+                field.name,
+                key,
+                limit_literal(base_type, value)
+            ));
+        }
+    }
+}
+
+/// 稳定字段 ID 生成的 `static constexpr` 成员，例如 `k_yaw_id`，日志代码按它写出字段
+fn push_field_id_constants(out: &mut String, config: &Config, indent: &str) {
+    let mut first = true;
+    for field in &config.fields {
+        let Some(id) = field.id else {
+            continue;
+        };
+        if first {
             out.push('\n');
+            first = false;
         }
+        out.push_str(&format!(
+            "{}static constexpr uint32_t k_{}_id = {};\n",
+            indent, field.name, id
+        ));
     }
+}
 
-    let packed = if config.packed {
-        "__attribute__((packed))"
+/// 取值显示名称的查找函数 `<字段>_name`，用 switch 实现以支持不连续的取值
+fn push_value_lookups(out: &mut String, config: &Config, options: &GenerateOptions) {
+    let (i1, i2) = (options.indent(1), options.indent(2));
+    // C++17 之前没有 std::string_view
+    let (string_type, empty) = if options.cpp_standard >= CppStandard::Cpp17 {
+        ("std::string_view", "{}")
     } else {
-        ""
+        ("const char*", "\"\"")
     };
+    for field in &config.fields {
+        let names = field.value_names();
+        let Some((base_type, _)) = parse_array_type(&field.ty).filter(|_| !names.is_empty()) else {
+            continue;
+        };
+        out.push_str(&format!(
+            "\n{i1}/// {} 的显示名称，未列出的值返回空字符串\n",
+            field.name
+        ));
+        out.push_str(&format!(
+            "{i1}static constexpr {} {}_name({} value)",
+            string_type, field.name, base_type
+        ));
+        match options.brace_style {
+            BraceStyle::NextLine => {
+                out.push_str(&format!("\n{i1}{{\n{i2}switch (value)\n{i2}{{\n"))
+            }
+            BraceStyle::SameLine => out.push_str(&format!(" {{\n{i2}switch (value) {{\n")),
+        }
+        for (value, name) in names {
+            out.push_str(&format!(
+                "{i2}case {}: return {};\n",
+                case_literal(value),
+                c_string_literal(name.as_str())
+            ));
+        }
+        out.push_str(&format!("{i2}default: return {};\n{i2}}}\n{i1}}}\n", empty));
+    }
+}
 
-    out.push_str(&format!("}} {};\n\n", packed));
+/// case 标签中的整数，超出 `long long` 的值带 `ULL` 后缀
+pub(crate) fn case_literal(value: i128) -> String {
+    if value > i128::from(i64::MAX) {
+        format!("{}ULL", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// C/C++ 字符串字面量；非 ASCII 字符按 UTF-8 原样输出，控制字符用八进制转义
+pub(crate) fn c_string_literal(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_ascii_control() => out.push_str(&format!("\\{:03o}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// 范围标注在 C/C++ 中的字面量：float 带 `f` 后缀，超出 `long long` 的整数带 `ULL` 后缀
+pub(crate) fn limit_literal(base_type: &str, value: &serde_json::Number) -> String {
+    match base_type {
+        "float" => format!("{:?}f", value.as_f64().unwrap_or_default()),
+        "double" | "long double" => format!("{:?}", value.as_f64().unwrap_or_default()),
+        _ if value.as_i64().is_none() && value.is_u64() => format!("{}ULL", value),
+        _ => value.to_string(),
+    }
+}
+
+/// 嵌套结构体的开头；使用属性或关键字的编译器需要为内层结构体单独标记 packed
+pub(crate) fn push_group_start(
+    out: &mut String,
+    group: &FieldGroup,
+    packing: Option<Packing>,
+    indent: &str,
+    options: &GenerateOptions,
+) {
+    if let Some(comment) = &group.comment {
+        out.push_str(&format!("{}/// {}\n", indent, comment));
+    }
+    let head = match packing {
+        Some(Packing::Attribute) => "struct __attribute__((packed))",
+        Some(Packing::Keyword) => "__packed struct",
+        _ => "struct",
+    };
+    let brace = match options.brace_style {
+        BraceStyle::NextLine => format!("\n{indent}{{\n"),
+        BraceStyle::SameLine => " {\n".to_string(),
+    };
+    out.push_str(&format!("{}{}{}", indent, head, brace));
+}
+
+pub(crate) fn push_group_end(out: &mut String, group: &FieldGroup, indent: &str) {
+    match &group.name {
+        Some(name) => out.push_str(&format!("{}}} {};\n", indent, name)),
+        None => out.push_str(&format!("{}}};\n", indent)),
+    }
+}
+
+/// 输出 volatile 视图与快照读取函数：
+/// - `view_Name` 将 DMA 缓冲区解释为 volatile 结构体，避免手写强制转换
+/// - `load_Name` 逐字节拷贝出普通结构体，便于访问 std::array 等不支持 volatile 的成员
+fn push_volatile_accessors(out: &mut String, config: &Config, options: &GenerateOptions) {
+    let name = &config.packet_name;
+    let i1 = options.indent(1);
+    let i2 = options.indent(2);
+    let brace = options.open_brace();
 
-    // Traits
-    out.push_str("template <>\n");
     out.push_str(&format!(
-        "struct RPL::Meta::PacketTraits<{}> : PacketTraitsBase<PacketTraits<{}>>\n",
-        config.packet_name, config.packet_name
+        "inline volatile {name}& view_{name}(volatile void* buffer){brace}"
     ));
-    out.push_str("{\n");
     out.push_str(&format!(
-        "    static constexpr uint16_t cmd = 0x{:04X};\n",
-        cmd_id
+        "{i1}return *static_cast<volatile {name}*>(buffer);\n}}\n\n"
     ));
+
     out.push_str(&format!(
-        "    static constexpr size_t size = {};\n",
-        bit_layout_plan
-            .as_ref()
-            .map(|plan| bytes_from_bits(plan.total_bits))
-            .map(|size| size.to_string())
-            .unwrap_or_else(|| format!("sizeof({})", config.packet_name))
+        "inline {name} load_{name}(const volatile void* buffer){brace}"
     ));
-    if let Some(plan) = &bit_layout_plan {
-        out.push_str("    using BitLayout = std::tuple<\n");
-        for (idx, field) in plan.fields.iter().enumerate() {
-            let suffix = if idx + 1 == plan.fields.len() {
-                ""
-            } else {
-                ","
-            };
+    out.push_str(&format!("{i1}{name} packet;\n"));
+    out.push_str(&format!(
+        "{i1}const volatile uint8_t* src = static_cast<const volatile uint8_t*>(buffer);\n"
+    ));
+    out.push_str(&format!(
+        "{i1}uint8_t* dst = reinterpret_cast<uint8_t*>(&packet);\n"
+    ));
+    let loop_brace = match options.brace_style {
+        BraceStyle::NextLine => format!("\n{i1}{{\n"),
+        BraceStyle::SameLine => " {\n".to_string(),
+    };
+    out.push_str(&format!(
+        "{i1}for (std::size_t i = 0; i < sizeof({name}); ++i){loop_brace}"
+    ));
+    out.push_str(&format!("{i2}dst[i] = src[i];\n{i1}}}\n"));
+    out.push_str(&format!("{i1}return packet;\n}}\n\n"));
+}
 
-            // 根据是否为数组字段生成不同的格式
-            if field.is_array {
-                if let Some(arr_size) = field.array_size {
-                    // 数组字段：Field<std::array<元素类型, 元素个数>, 总位数>
-                    out.push_str(&format!(
-                        "        Field<std::array<{}>, {}>{}\n",
-                        format!("{}, {}", field.ty, arr_size),
-                        field.bits,
-                        suffix
-                    ));
+/// 定宽整数类型的来源：包声明的 `includes`，`freestanding` 时不包含（见 [`push_fallback_typedefs`]），
+/// 否则为标准头文件 `header`
+pub(crate) fn push_integer_includes(
+    out: &mut String,
+    includes: Option<&[String]>,
+    header: &str,
+    options: &GenerateOptions,
+) {
+    match includes {
+        Some(includes) => {
+            for include in includes {
+                if include.starts_with('<') {
+                    out.push_str(&format!("#include {}\n", include));
                 } else {
-                    // 理论上不应该到这里
-                    out.push_str(&format!(
-                        "        Field<{}, {}>{}\n",
-                        field.ty, field.bits, suffix
-                    ));
+                    out.push_str(&format!("#include \"{}\"\n", include));
                 }
-            } else {
-                // 非数组字段：Field<类型, 位数>
-                out.push_str(&format!(
-                    "        Field<{}, {}>{}\n",
-                    field.ty, field.bits, suffix
-                ));
             }
         }
-        out.push_str("    >;\n");
+        None if options.freestanding => {}
+        None => out.push_str(&format!("#include <{}>\n", header)),
+    }
+}
+
+/// `freestanding` 且包没有声明 `includes` 时写出定宽整数类型的后备 typedef
+///
+/// 以宏保护只定义一次；已经包含了 `<stdint.h>` 时（定义了 `INT8_MAX`）不再定义。
+/// 各类型的宽度按常见的 32 位与 64 位目标选择，用 `assert`（`static_assert` 或 `_Static_assert`）检查
+pub(crate) fn push_fallback_typedefs(
+    out: &mut String,
+    includes: Option<&[String]>,
+    assert: &str,
+    options: &GenerateOptions,
+) {
+    if includes.is_some() || !options.freestanding {
+        return;
+    }
+    out.push_str("#if !defined(RPLC_FIXED_WIDTH_TYPES) && !defined(INT8_MAX)\n");
+    out.push_str("#define RPLC_FIXED_WIDTH_TYPES\n");
+    out.push_str("/// 工具链没有提供定宽整数类型时的后备定义\n");
+    for (ty, base) in [
+        ("int8_t", "signed char"),
+        ("uint8_t", "unsigned char"),
+        ("int16_t", "short"),
+        ("uint16_t", "unsigned short"),
+        ("int32_t", "int"),
+        ("uint32_t", "unsigned int"),
+        ("int64_t", "long long"),
+        ("uint64_t", "unsigned long long"),
+    ] {
+        out.push_str(&format!("typedef {} {};\n", base, ty));
+    }
+    for (ty, size) in [("int16_t", 2), ("int32_t", 4), ("int64_t", 8)] {
+        out.push_str(&format!(
+            "{assert}(sizeof({ty}) == {size}, \"{ty} 应为 {size} 字节，请通过 includes 提供定宽整数类型\");\n"
+        ));
+    }
+    out.push_str("#endif // RPLC_FIXED_WIDTH_TYPES\n\n");
+}
+
+/// 多包共用的头文件中替代 `<cstdint>` 的头文件：各包声明的 `includes` 按出现顺序合并，都没有声明时为 None
+pub(crate) fn shared_includes(packets: &[Config]) -> Option<Vec<String>> {
+    let mut includes: Vec<String> = Vec::new();
+    for include in packets.iter().filter_map(|p| p.includes.as_ref()).flatten() {
+        if !includes.contains(include) {
+            includes.push(include.clone());
+        }
+    }
+    packets
+        .iter()
+        .any(|p| p.includes.is_some())
+        .then_some(includes)
+}
+
+/// 按 `bitfield_layout` 声明的位序直接读写线上字节的 `<包名>Wire` 访问函数
+///
+/// 位置取自 `BitLayout` 的位流偏移，逐位读写，因此与编译器的原生位域实现无关；
+/// 只为位域成员生成取值与赋值函数
+fn push_wire_accessors(
+    out: &mut String,
+    config: &Config,
+    plan: &BitLayoutPlan,
+    order: BitOrder,
+    options: &GenerateOptions,
+) {
+    let name = &config.packet_name;
+    let (i1, i2, i3) = (options.indent(1), options.indent(2), options.indent(3));
+    let brace = options.open_brace();
+    let block = |indent: &str| match options.brace_style {
+        BraceStyle::NextLine => format!("\n{indent}{{\n"),
+        BraceStyle::SameLine => " {\n".to_string(),
+    };
+
+    // (字段, 类型, 位流偏移, 位宽)
+    let mut offset = 0;
+    let mut members = Vec::new();
+    for (field, layout) in config.fields.iter().zip(&plan.fields) {
+        if field.bit_field.is_some() && !layout.is_array {
+            members.push((field, layout.ty.as_str(), offset, layout.bits));
+        }
+        offset += layout.bits;
+    }
+    let is_bool = |ty: &str| matches!(ty, "bool" | "_Bool");
+    let is_signed = |ty: &str| {
+        !(is_bool(ty) || ty == "char" || ty.starts_with("uint") || ty.starts_with("unsigned"))
+    };
+
+    out.push_str(&format!(
+        "/// {name} 的线上字节访问函数，位域按 {} 位序逐位读写\n",
+        order.as_str()
+    ));
+    out.push_str(&format!("struct {name}Wire{brace}"));
+
+    out.push_str(&format!("{i1}/// 从位流的第 offset 位开始读取 bits 位\n"));
+    out.push_str(&format!(
+        "{i1}static constexpr uint64_t read_bits(const uint8_t* data, uint32_t offset, uint32_t bits){}",
+        block(&i1)
+    ));
+    out.push_str(&format!("{i2}uint64_t value = 0;\n"));
+    out.push_str(&format!(
+        "{i2}for (uint32_t i = 0; i < bits; ++i){}",
+        block(&i2)
+    ));
+    out.push_str(&format!("{i3}const uint32_t pos = offset + i;\n"));
+    out.push_str(&match order {
+        BitOrder::LsbFirst => {
+            format!("{i3}value |= static_cast<uint64_t>((data[pos / 8] >> (pos % 8)) & 1u) << i;\n")
+        }
+        BitOrder::MsbFirst => {
+            format!("{i3}value = (value << 1) | ((data[pos / 8] >> (7 - pos % 8)) & 1u);\n")
+        }
+    });
+    out.push_str(&format!("{i2}}}\n{i2}return value;\n{i1}}}\n\n"));
+
+    out.push_str(&format!(
+        "{i1}/// 从位流的第 offset 位开始写入 value 的低 bits 位\n"
+    ));
+    out.push_str(&format!(
+        "{i1}static constexpr void write_bits(uint8_t* data, uint32_t offset, uint32_t bits, uint64_t value){}",
+        block(&i1)
+    ));
+    out.push_str(&format!(
+        "{i2}for (uint32_t i = 0; i < bits; ++i){}",
+        block(&i2)
+    ));
+    out.push_str(&format!("{i3}const uint32_t pos = offset + i;\n"));
+    out.push_str(&match order {
+        BitOrder::LsbFirst => format!(
+            "{i3}const uint8_t mask = static_cast<uint8_t>(1u << (pos % 8));\n\
+             {i3}const bool bit = (value >> i) & 1u;\n"
+        ),
+        BitOrder::MsbFirst => format!(
+            "{i3}const uint8_t mask = static_cast<uint8_t>(0x80u >> (pos % 8));\n\
+             {i3}const bool bit = (value >> (bits - 1 - i)) & 1u;\n"
+        ),
+    });
+    out.push_str(&format!(
+        "{i3}data[pos / 8] = static_cast<uint8_t>(bit ? (data[pos / 8] | mask) : (data[pos / 8] & ~mask));\n"
+    ));
+    out.push_str(&format!("{i2}}}\n{i1}}}\n"));
+
+    if members.iter().any(|(_, ty, _, _)| is_signed(ty)) {
+        out.push_str(&format!("\n{i1}/// 把 bits 位的补码扩展为 64 位\n"));
+        out.push_str(&format!(
+            "{i1}static constexpr uint64_t sign_extend(uint64_t value, uint32_t bits){}",
+            block(&i1)
+        ));
+        out.push_str(&format!(
+            "{i2}const uint64_t sign = uint64_t{{1}} << (bits - 1);\n{i2}return (value ^ sign) - sign;\n{i1}}}\n"
+        ));
+    }
+
+    for (field, ty, offset, bits) in members {
+        let read = format!("read_bits(data, {offset}, {bits})");
+        let value = if is_bool(ty) {
+            format!("{read} != 0")
+        } else if is_signed(ty) {
+            format!("static_cast<{ty}>(sign_extend({read}, {bits}))")
+        } else {
+            format!("static_cast<{ty}>({read})")
+        };
+        out.push('\n');
+        if let Some(comment) = &field.comment {
+            out.push_str(&format!("{i1}/// {}\n", comment.as_str()));
+        }
+        out.push_str(&format!(
+            "{i1}static constexpr {ty} {}(const uint8_t* data){}",
+            field.name,
+            block(&i1)
+        ));
+        out.push_str(&format!("{i2}return {value};\n{i1}}}\n\n"));
+        out.push_str(&format!(
+            "{i1}static constexpr void set_{}(uint8_t* data, {ty} value){}",
+            field.name,
+            block(&i1)
+        ));
+        out.push_str(&format!(
+            "{i2}write_bits(data, {offset}, {bits}, static_cast<uint64_t>(value));\n{i1}}}\n"
+        ));
+    }
+    out.push_str("};\n\n");
+}
+
+/// `serialize` 与 `deserialize` 共用的标量读写函数，放在全局命名空间并以宏保护，
+/// 多个生成的头文件被同一翻译单元包含时只定义一次
+fn push_wire_helpers(out: &mut String, options: &GenerateOptions) {
+    let (i1, i2) = (options.indent(1), options.indent(2));
+    // C++20 之前没有 std::bit_cast，经 std::memcpy 复制对象表示
+    let bit_cast = options.cpp_standard >= CppStandard::Cpp20;
+    let brace = options.open_brace();
+    let block = |indent: &str| match options.brace_style {
+        BraceStyle::NextLine => format!("\n{indent}{{\n"),
+        BraceStyle::SameLine => " {\n".to_string(),
+    };
+
+    out.push_str("#ifndef RPLC_WIRE_HELPERS\n#define RPLC_WIRE_HELPERS\n\n");
+    out.push_str("/// serialize 与 deserialize 按字节序读写标量的辅助函数，与主机字节序无关\n");
+    out.push_str(&format!("namespace rplc_wire{brace}"));
+    out.push_str("template <std::size_t N>\nstruct Unsigned;\n");
+    for (size, ty) in [
+        (1, "uint8_t"),
+        (2, "uint16_t"),
+        (4, "uint32_t"),
+        (8, "uint64_t"),
+    ] {
+        out.push_str(&format!(
+            "template <>\nstruct Unsigned<{size}> {{ using type = {ty}; }};\n"
+        ));
+    }
+
+    out.push_str("\n/// 把 value 的各字节按字节序写入 buf\n");
+    out.push_str(&format!(
+        "template <typename T>\ninline void store(uint8_t* buf, T value, bool big_endian){brace}"
+    ));
+    if bit_cast {
+        out.push_str(&format!(
+            "{i1}const auto bits = std::bit_cast<typename Unsigned<sizeof(T)>::type>(value);\n"
+        ));
+    } else {
+        out.push_str(&format!(
+            "{i1}typename Unsigned<sizeof(T)>::type bits;\n{i1}std::memcpy(&bits, &value, sizeof(T));\n"
+        ));
+    }
+    out.push_str(&format!(
+        "{i1}for (std::size_t i = 0; i < sizeof(T); ++i){}",
+        block(&i1)
+    ));
+    out.push_str(&format!(
+        "{i2}const std::size_t shift = 8 * (big_endian ? sizeof(T) - 1 - i : i);\n"
+    ));
+    out.push_str(&format!(
+        "{i2}buf[i] = static_cast<uint8_t>(bits >> shift);\n{i1}}}\n}}\n"
+    ));
+
+    out.push_str("\n/// 从 buf 按字节序读出一个 T\n");
+    out.push_str(&format!(
+        "template <typename T>\ninline T load(const uint8_t* buf, bool big_endian){brace}"
+    ));
+    out.push_str(&format!(
+        "{i1}using U = typename Unsigned<sizeof(T)>::type;\n{i1}U bits = 0;\n"
+    ));
+    out.push_str(&format!(
+        "{i1}for (std::size_t i = 0; i < sizeof(T); ++i){}",
+        block(&i1)
+    ));
+    out.push_str(&format!(
+        "{i2}const std::size_t shift = 8 * (big_endian ? sizeof(T) - 1 - i : i);\n"
+    ));
+    out.push_str(&format!(
+        "{i2}bits = static_cast<U>(bits | (static_cast<U>(buf[i]) << shift));\n{i1}}}\n"
+    ));
+    if bit_cast {
+        out.push_str(&format!(
+            "{i1}if constexpr (std::is_same_v<T, bool>){}",
+            block(&i1)
+        ));
+        out.push_str(&format!("{i2}return bits != 0;\n{i1}}}\n"));
+        out.push_str(&format!("{i1}else{}", block(&i1)));
+        out.push_str(&format!("{i2}return std::bit_cast<T>(bits);\n{i1}}}\n}}\n"));
+    } else {
+        out.push_str(&format!(
+            "{i1}T value;\n{i1}std::memcpy(&value, &bits, sizeof(T));\n{i1}return value;\n}}\n"
+        ));
+        // 不是 0 或 1 的字节复制到 bool 中是未定义行为，单独处理
+        out.push_str(&format!(
+            "\ntemplate <>\ninline bool load<bool>(const uint8_t* buf, bool){brace}"
+        ));
+        out.push_str(&format!("{i1}return buf[0] != 0;\n}}\n"));
+    }
+    out.push_str("} // namespace rplc_wire\n\n#endif // RPLC_WIRE_HELPERS\n\n");
+}
+
+/// DJI 裁判系统协议的帧常量与 CRC 函数，放在全局命名空间并以宏保护
+///
+/// CRC 逐位计算，与官方查表实现的结果相同，省去两张 256 项的表
+fn push_dji_crc_helpers(out: &mut String, options: &GenerateOptions) {
+    let (i1, i2, i3) = (options.indent(1), options.indent(2), options.indent(3));
+    let brace = options.open_brace();
+    let block = |indent: &str| match options.brace_style {
+        BraceStyle::NextLine => format!("\n{indent}{{\n"),
+        BraceStyle::SameLine => " {\n".to_string(),
+    };
+
+    out.push_str("#ifndef RPLC_DJI_CRC\n#define RPLC_DJI_CRC\n\n");
+    out.push_str("/// DJI 裁判系统串口协议的帧格式与 CRC 校验\n");
+    out.push_str(&format!("namespace rplc_dji{brace}"));
+    let var = options.constexpr_variable();
+    out.push_str(&format!("/// 帧起始字节\n{var} uint8_t SOF = 0xA5;\n"));
+    out.push_str(&format!(
+        "/// 帧头长度：SOF、数据段长度（2 字节）、包序号与 CRC8\n{var} std::size_t HEADER_SIZE = 5;\n"
+    ));
+    out.push_str(&format!(
+        "/// 命令码长度\n{var} std::size_t CMD_SIZE = 2;\n"
+    ));
+    out.push_str(&format!(
+        "/// 帧尾 CRC16 长度\n{var} std::size_t TAIL_SIZE = 2;\n"
+    ));
+    out.push_str(&format!("{var} uint8_t CRC8_INIT = 0xFF;\n"));
+    out.push_str(&format!("{var} uint16_t CRC16_INIT = 0xFFFF;\n"));
+
+    for (bits, ty, poly, doc) in [
+        (
+            8,
+            "uint8_t",
+            "0x8Cu",
+            "CRC8（多项式 0x31，按位反转），用于帧头校验",
+        ),
+        (
+            16,
+            "uint16_t",
+            "0x8408u",
+            "CRC16（多项式 0x1021，按位反转），用于整帧校验",
+        ),
+    ] {
+        out.push_str(&format!("\n/// {doc}\n"));
+        out.push_str(&format!(
+            "inline constexpr {ty} crc{bits}(const uint8_t* data, std::size_t length, {ty} crc = CRC{bits}_INIT){brace}"
+        ));
+        out.push_str(&format!(
+            "{i1}for (std::size_t i = 0; i < length; ++i){}",
+            block(&i1)
+        ));
+        out.push_str(&format!("{i2}crc = static_cast<{ty}>(crc ^ data[i]);\n"));
+        out.push_str(&format!(
+            "{i2}for (int bit = 0; bit < 8; ++bit){}",
+            block(&i2)
+        ));
+        out.push_str(&format!(
+            "{i3}crc = static_cast<{ty}>((crc & 1u) ? (crc >> 1) ^ {poly} : crc >> 1);\n"
+        ));
+        out.push_str(&format!("{i2}}}\n{i1}}}\n{i1}return crc;\n}}\n"));
+    }
+
+    out.push_str("\n/// 计算 frame 前 length - 1 字节的 CRC8 并写入最后一个字节\n");
+    out.push_str(&format!(
+        "inline void append_crc8(uint8_t* frame, std::size_t length){brace}"
+    ));
+    out.push_str(&format!(
+        "{i1}frame[length - 1] = crc8(frame, length - 1);\n}}\n"
+    ));
+    out.push_str("\n/// frame 的最后一个字节是否为前 length - 1 字节的 CRC8\n");
+    out.push_str(&format!(
+        "inline bool verify_crc8(const uint8_t* frame, std::size_t length){brace}"
+    ));
+    out.push_str(&format!(
+        "{i1}return length > 1 && crc8(frame, length - 1) == frame[length - 1];\n}}\n"
+    ));
+    out.push_str("\n/// 计算 frame 前 length - 2 字节的 CRC16 并按小端写入最后两个字节\n");
+    out.push_str(&format!(
+        "inline void append_crc16(uint8_t* frame, std::size_t length){brace}"
+    ));
+    out.push_str(&format!(
+        "{i1}const uint16_t crc = crc16(frame, length - 2);\n\
+         {i1}frame[length - 2] = static_cast<uint8_t>(crc);\n\
+         {i1}frame[length - 1] = static_cast<uint8_t>(crc >> 8);\n}}\n"
+    ));
+    out.push_str("\n/// frame 的最后两个字节是否为前 length - 2 字节的 CRC16（小端）\n");
+    out.push_str(&format!(
+        "inline bool verify_crc16(const uint8_t* frame, std::size_t length){brace}"
+    ));
+    out.push_str(&format!("{i1}if (length <= 2){}", block(&i1)));
+    out.push_str(&format!("{i2}return false;\n{i1}}}\n"));
+    out.push_str(&format!(
+        "{i1}const uint16_t crc = crc16(frame, length - 2);\n\
+         {i1}return frame[length - 2] == static_cast<uint8_t>(crc) && frame[length - 1] == static_cast<uint8_t>(crc >> 8);\n}}\n"
+    ));
+    out.push_str("\n/// 检查整帧：起始字节、帧头 CRC8、数据段长度与 length 一致，以及帧尾 CRC16\n");
+    out.push_str(&format!(
+        "inline bool verify_frame(const uint8_t* frame, std::size_t length){brace}"
+    ));
+    out.push_str(&format!(
+        "{i1}if (length < HEADER_SIZE + CMD_SIZE + TAIL_SIZE || frame[0] != SOF || !verify_crc8(frame, HEADER_SIZE)){}",
+        block(&i1)
+    ));
+    out.push_str(&format!("{i2}return false;\n{i1}}}\n"));
+    out.push_str(&format!(
+        "{i1}const std::size_t data_length = frame[1] | (frame[2] << 8);\n\
+         {i1}return length == HEADER_SIZE + CMD_SIZE + data_length + TAIL_SIZE && verify_crc16(frame, length);\n}}\n"
+    ));
+    out.push_str("} // namespace rplc_dji\n\n#endif // RPLC_DJI_CRC\n\n");
+}
+
+/// 把包打包为裁判系统整帧的 `pack_frame_<包名>` 与帧长常量 `frame_size_<包名>`
+///
+/// `crc_append` 为 false 时包的最后一个字段承载帧尾，数据段长度不含这两个字节
+fn push_frame_packer(
+    out: &mut String,
+    config: &Config,
+    cmd_id: u16,
+    size: &str,
+    options: &GenerateOptions,
+) {
+    let name = &config.packet_name;
+    let i1 = options.indent(1);
+    let brace = options.open_brace();
+
+    let (frame_size, length) = if config.crc_append {
+        (
+            format!("rplc_dji::HEADER_SIZE + rplc_dji::CMD_SIZE + {size} + rplc_dji::TAIL_SIZE"),
+            format!("static_cast<uint16_t>({size})"),
+        )
+    } else {
+        (
+            format!("rplc_dji::HEADER_SIZE + rplc_dji::CMD_SIZE + {size}"),
+            format!("static_cast<uint16_t>({size} - rplc_dji::TAIL_SIZE)"),
+        )
+    };
+    out.push_str(&format!("/// {name} 按 DJI 裁判系统协议打包后的整帧长度\n"));
+    out.push_str(&format!(
+        "{} std::size_t frame_size_{name} = {frame_size};\n\n",
+        options.constexpr_variable()
+    ));
+    out.push_str(&format!(
+        "/// 把 {name} 打包为完整的裁判系统帧（帧头、命令码、数据与 CRC16），frame 至少需要 frame_size_{name} 字节\n"
+    ));
+    out.push_str(&format!(
+        "inline void pack_frame_{name}(const {name}& packet, uint8_t seq, uint8_t* frame){brace}"
+    ));
+    out.push_str(&format!("{i1}constexpr uint16_t length = {length};\n"));
+    out.push_str(&format!(
+        "{i1}frame[0] = rplc_dji::SOF;\n\
+         {i1}frame[1] = static_cast<uint8_t>(length);\n\
+         {i1}frame[2] = static_cast<uint8_t>(length >> 8);\n\
+         {i1}frame[3] = seq;\n\
+         {i1}rplc_dji::append_crc8(frame, rplc_dji::HEADER_SIZE);\n"
+    ));
+    out.push_str(&format!(
+        "{i1}frame[5] = 0x{:02X};\n{i1}frame[6] = 0x{:02X};\n",
+        cmd_id & 0xFF,
+        cmd_id >> 8
+    ));
+    if config.serialize {
+        out.push_str(&format!("{i1}serialize(packet, frame + 7);\n"));
+    } else {
+        out.push_str(&format!("{i1}std::memcpy(frame + 7, &packet, {size});\n"));
+    }
+    out.push_str(&format!(
+        "{i1}rplc_dji::append_crc16(frame, frame_size_{name});\n}}\n\n"
+    ));
+}
+
+/// 注释中字节序的写法
+fn endianness_label(endianness: Endianness) -> &'static str {
+    match endianness {
+        Endianness::Little => "小端",
+        Endianness::Big => "大端",
+    }
+}
+
+/// 生成 `<包名>Endian` 访问函数的字段：多字节的标量或数组，返回 (元素类型, 元素大小, 数组长度)
+pub(crate) fn endian_accessor_field(field: &Field) -> Option<(&str, u32, Option<u32>)> {
+    let (base_type, array_len) = parse_array_type(&field.ty)?;
+    let elem_size = c_type_size(base_type).filter(|size| *size > 1)?;
+    (field.bit_field.is_none() && field.reserved.is_none())
+        .then(|| (member_type(field, base_type), elem_size, array_len))
+}
+
+/// 包或字段声明了 `endianness` 且有需要转换的字段；
+/// 启用 `serialize` 时结构体按主机字节序存放，含位域的包按位流排列并使用 `Wire` 访问函数，两者都不生成
+pub(crate) fn wants_endian_accessors(config: &Config) -> bool {
+    (config.endianness.is_some() || config.fields.iter().any(|f| f.endianness.is_some()))
+        && !config.serialize
+        && config.fields.iter().all(|f| f.bit_field.is_none())
+        && config
+            .fields
+            .iter()
+            .any(|field| endian_accessor_field(field).is_some())
+}
+
+/// `<包名>Endian` 访问函数：按字段在结构体中的字节偏移，经 `rplc_wire` 在声明的字节序与
+/// 主机字节序之间转换，小端主机上转换大端字段，大端主机上转换小端字段
+fn push_endian_accessors(
+    out: &mut String,
+    config: &Config,
+    options: &GenerateOptions,
+) -> Result<(), GenerateError> {
+    let layout = compute_layout(config).ok_or(GenerateError::ValidationError)?;
+    let name = &config.packet_name;
+    let (i1, i2) = (options.indent(1), options.indent(2));
+    let brace = options.open_brace();
+    let block = |indent: &str| match options.brace_style {
+        BraceStyle::NextLine => format!("\n{indent}{{\n"),
+        BraceStyle::SameLine => " {\n".to_string(),
+    };
+
+    out.push_str(&format!(
+        "/// {name} 中多字节字段的访问函数，在声明的字节序与主机字节序之间转换\n"
+    ));
+    out.push_str(&format!("struct {name}Endian{brace}"));
+    let mut first = true;
+    for (field, field_layout) in config.fields.iter().zip(&layout.fields) {
+        let Some((ty, elem_size, array_len)) = endian_accessor_field(field) else {
+            continue;
+        };
+        let endianness = config.field_endianness(field);
+        let big = endianness == Endianness::Big;
+        let offset = field_layout.offset_bits / 8;
+        let (index_param, position) = match array_len {
+            Some(_) => (", std::size_t i", format!("{offset} + {elem_size} * i")),
+            None => ("", offset.to_string()),
+        };
+        if !first {
+            out.push('\n');
+        }
+        first = false;
+        let comment = field
+            .comment
+            .as_ref()
+            .map_or_else(|| field.name.clone(), |c| c.as_str().to_string());
+        out.push_str(&format!(
+            "{i1}/// {}（{}）\n",
+            comment,
+            endianness_label(endianness)
+        ));
+        out.push_str(&format!(
+            "{i1}static {ty} {}(const {name}& packet{index_param}){}",
+            field.name,
+            block(&i1)
+        ));
+        out.push_str(&format!(
+            "{i2}return rplc_wire::load<{ty}>(reinterpret_cast<const uint8_t*>(&packet) + {position}, {big});\n{i1}}}\n\n"
+        ));
+        out.push_str(&format!(
+            "{i1}static void set_{}({name}& packet{index_param}, {ty} value){}",
+            field.name,
+            block(&i1)
+        ));
+        out.push_str(&format!(
+            "{i2}rplc_wire::store(reinterpret_cast<uint8_t*>(&packet) + {position}, value, {big});\n{i1}}}\n"
+        ));
+    }
+    out.push_str("};\n\n");
+    Ok(())
+}
+
+/// 按 `endianness` 逐个标量读写线上字节的 `serialize` 与 `deserialize<包名>` 函数
+///
+/// 偏移取自 `compute_layout`，线上格式与结构体的内存布局相同，填充字节写为 0
+fn push_serializers(
+    out: &mut String,
+    config: &Config,
+    options: &GenerateOptions,
+) -> Result<(), GenerateError> {
+    let layout = compute_layout(config).ok_or(GenerateError::ValidationError)?;
+    let name = &config.packet_name;
+    let size = layout.size;
+    // 字段各自声明字节序时，注释中的字节序以包的声明为准
+    let label = endianness_label(config.endianness.unwrap_or_default());
+    let (i1, i2) = (options.indent(1), options.indent(2));
+    let brace = options.open_brace();
+    let block = |indent: &str| match options.brace_style {
+        BraceStyle::NextLine => format!("\n{indent}{{\n"),
+        BraceStyle::SameLine => " {\n".to_string(),
+    };
+
+    // (成员路径, 基础类型, 字节偏移, 元素大小, 数组长度, 是否大端)
+    let mut members = Vec::with_capacity(config.fields.len());
+    for (field, field_layout) in config.fields.iter().zip(&layout.fields) {
+        let (base_type, array_len) =
+            parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+        let elem_size = c_type_size(base_type).ok_or(GenerateError::ValidationError)?;
+        members.push((
+            field.member_path(),
+            member_type(field, base_type),
+            field_layout.offset_bits / 8,
+            elem_size,
+            array_len,
+            config.field_endianness(field) == Endianness::Big,
+        ));
+    }
+    let payload: u32 = layout.fields.iter().map(|f| f.size_bits / 8).sum();
+
+    out.push_str(&format!(
+        "/// 按{label}字节序将 {name} 写入 buf，buf 至少需要 {size} 字节\n"
+    ));
+    out.push_str(&format!(
+        "inline void serialize(const {name}& packet, uint8_t* buf){brace}"
+    ));
+    if payload < size {
+        out.push_str(&format!("{i1}std::memset(buf, 0, {size});\n"));
+    }
+    // 数组元素的字节位置，单字节元素省略步长
+    let element = |offset: u32, elem_size: u32| match elem_size {
+        1 => format!("{offset} + i"),
+        _ => format!("{offset} + {elem_size} * i"),
+    };
+    for (path, _, offset, elem_size, array_len, big) in &members {
+        match array_len {
+            None => out.push_str(&format!(
+                "{i1}rplc_wire::store(buf + {offset}, packet.{path}, {big});\n"
+            )),
+            Some(len) => {
+                out.push_str(&format!(
+                    "{i1}for (std::size_t i = 0; i < {len}; ++i){}",
+                    block(&i1)
+                ));
+                out.push_str(&format!(
+                    "{i2}rplc_wire::store(buf + {}, packet.{path}[i], {big});\n{i1}}}\n",
+                    element(*offset, *elem_size)
+                ));
+            }
+        }
+    }
+    out.push_str("}\n\n");
+
+    // C++20 之前没有 std::span，改为接受指针与长度
+    let (params, length, bytes) = if options.cpp_standard >= CppStandard::Cpp20 {
+        (
+            "std::span<const uint8_t> data",
+            "data.size()",
+            "data.data()",
+        )
+    } else {
+        ("const uint8_t* data, std::size_t size", "size", "data")
+    };
+    out.push_str(&format!(
+        "template <typename T>\nstd::optional<T> deserialize({params});\n\n"
+    ));
+    out.push_str(&format!(
+        "/// 从 data 按{label}字节序解析 {name}，长度不足 {size} 字节时返回 std::nullopt\n"
+    ));
+    out.push_str(&format!(
+        "template <>\ninline std::optional<{name}> deserialize<{name}>({params}){brace}"
+    ));
+    out.push_str(&format!("{i1}if ({length} < {size}){}", block(&i1)));
+    out.push_str(&format!("{i2}return std::nullopt;\n{i1}}}\n"));
+    out.push_str(&format!("{i1}{name} packet{{}};\n"));
+    for (path, base_type, offset, elem_size, array_len, big) in &members {
+        match array_len {
+            None => out.push_str(&format!(
+                "{i1}packet.{path} = rplc_wire::load<{base_type}>({bytes} + {offset}, {big});\n"
+            )),
+            Some(len) => {
+                out.push_str(&format!(
+                    "{i1}for (std::size_t i = 0; i < {len}; ++i){}",
+                    block(&i1)
+                ));
+                out.push_str(&format!(
+                    "{i2}packet.{path}[i] = rplc_wire::load<{base_type}>({bytes} + {}, {big});\n{i1}}}\n",
+                    element(*offset, *elem_size)
+                ));
+            }
+        }
+    }
+    out.push_str(&format!("{i1}return packet;\n}}\n\n"));
+    Ok(())
+}
+
+pub fn generate(json_input: &str) -> Result<String, GenerateError> {
+    generate_with_options(json_input, &GenerateOptions::default())
+}
+
+#[tracing::instrument(level = "debug", name = "generate", skip_all)]
+pub fn generate_with_options(
+    json_input: &str,
+    options: &GenerateOptions,
+) -> Result<String, GenerateError> {
+    // JSON 语法错误直接报告；其余先校验再反序列化，使多包输入等结构错误得到诊断而不是 serde 报错
+    serde_json::from_str::<serde::de::IgnoredAny>(json_input)?;
+    let diags = validate(json_input);
+    for diag in diags {
+        if diag.severity == Severity::Error {
+            return Err(GenerateError::ValidationError);
+        }
+    }
+    if let Some(alias) = &options.namespace_alias
+        && !is_alias_name(alias)
+    {
+        return Err(GenerateError::InvalidNamespaceAlias(alias.clone()));
+    }
+    let mut config: Config = debug_span!("parse").in_scope(|| serde_json::from_str(json_input))?;
+    config.localize(options.comment_language.as_deref());
+    if config.serialize && options.cpp_standard < CppStandard::Cpp17 {
+        return Err(GenerateError::Unsupported {
+            backend: "C++",
+            reason: format!(
+                "serialize 生成的 deserialize 返回 std::optional，至少需要 C++17，当前为 {}",
+                options.cpp_standard
+            ),
+        });
+    }
+    let config = with_reserved_gaps(&config);
+    let cmd_id =
+        parse_command_id(&config.command_id).map_err(|_| GenerateError::ValidationError)?;
+    let guard = config
+        .header_guard
+        .clone()
+        .unwrap_or_else(|| default_header_guard(&config.packet_name));
+    let bit_layout_plan = analyze_bit_layout(&config);
+
+    let endian_accessors = wants_endian_accessors(&config);
+    let blocks = packet_body(
+        &config,
+        cmd_id,
+        bit_layout_plan.as_ref(),
+        endian_accessors,
+        options,
+    )?;
+
+    // 需要的头文件由生成的代码决定：数组、span、traits 等只在用到时包含
+    let mut includes = CppIncludes::default();
+    includes.scan(&blocks.concat());
+    // 自定义模板与关闭 traits 时不依赖 RPL::Meta
+    if config.emit_traits && config.traits_template.is_none() {
+        if bit_layout_plan.is_some() {
+            includes.system("RPL/Meta/BitstreamTraits.hpp");
+        }
+        includes.system("RPL/Meta/PacketTraits.hpp");
+    }
+    // 字段引用的其他包，各自生成在独立的头文件中
+    for name in config.referenced_packets() {
+        if config.dependency(name).is_some() {
+            includes.local(options.packet_include(name));
+        }
+    }
+
+    let mut header_includes = String::new();
+    push_integer_includes(
+        &mut header_includes,
+        config.includes.as_deref(),
+        "cstdint",
+        options,
+    );
+    includes.render(&mut header_includes);
+    let mut typedefs = String::new();
+    push_fallback_typedefs(
+        &mut typedefs,
+        config.includes.as_deref(),
+        "static_assert",
+        options,
+    );
+    let size = packet_size(&config, bit_layout_plan.as_ref());
+    let context = HeaderContext {
+        guard: &guard,
+        includes: header_includes,
+        typedefs,
+        blocks,
+        indent: options.indent(1),
+        open_brace: options.open_brace(),
+        packet: PacketContext::new(&config, cmd_id, size, bit_layout_plan.as_ref(), options),
+    };
+    Ok(render_header(options.header_template.as_ref(), &context)?)
+}
+
+/// 头文件中 include 之后、`#endif` 之前的各段代码，默认模板按字段顺序依次拼接
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct HeaderBlocks {
+    /// 字节序与 CRC 的辅助函数
+    pub helpers: String,
+    pub namespace_begin: String,
+    pub enums: String,
+    /// 包的结构体及其 Doxygen 注释
+    pub definition: String,
+    /// `expected_size` 生成的 static_assert
+    pub size_assert: String,
+    /// volatile 视图、位序与字节序访问函数、序列化与整帧打包函数
+    pub accessors: String,
+    /// `PacketTraits` 特化或 `traits_template` 的渲染结果
+    pub traits: String,
+    pub namespace_end: String,
+}
+
+impl HeaderBlocks {
+    fn concat(&self) -> String {
+        [
+            &self.helpers,
+            &self.namespace_begin,
+            &self.enums,
+            &self.definition,
+            &self.size_assert,
+            &self.accessors,
+            &self.traits,
+            &self.namespace_end,
+        ]
+        .into_iter()
+        .map(String::as_str)
+        .collect()
+    }
+}
+
+/// 头文件中 include 之后、`#endif` 之前的全部内容
+fn packet_body(
+    config: &Config,
+    cmd_id: u16,
+    bit_layout_plan: Option<&BitLayoutPlan>,
+    endian_accessors: bool,
+    options: &GenerateOptions,
+) -> Result<HeaderBlocks, GenerateError> {
+    let mut blocks = HeaderBlocks::default();
+    if config.serialize || endian_accessors {
+        push_wire_helpers(&mut blocks.helpers, options);
+    }
+    if config.crc == Some(CrcKind::Dji) {
+        push_dji_crc_helpers(&mut blocks.helpers, options);
+    }
+
+    // Namespace
+    if let Some(ns) = &config.namespace {
+        blocks.namespace_begin = format!("{}\n\n", options.namespace_open(ns));
+    }
+    push_namespace_end(&mut blocks.namespace_end, config, options);
+
+    // Doxygen-style comment, repeated above the traits so IDE hovers show cmd and size
+    let doc_block = packet_doc_block(config, cmd_id);
+    push_enums(&mut blocks.enums, config, options);
+    push_struct(
+        &mut blocks.definition,
+        config,
+        &config.packet_name,
+        &doc_block,
+        options,
+    );
+    // 含位域的包按位流计算大小，与结构体的 sizeof 无关，只由校验检查
+    if let Some(expected) = config.expected_size
+        && bit_layout_plan.is_none()
+    {
+        blocks.size_assert = format!(
+            "static_assert(sizeof({name}) == {expected}, \"{name} 的大小应为 {expected} 字节\");\n\n",
+            name = config.packet_name
+        );
+    }
+    let out = &mut blocks.accessors;
+    if config.volatile_view {
+        push_volatile_accessors(out, config, options);
+    }
+    if let (Some(order), Some(plan)) = (config.bitfield_layout, bit_layout_plan) {
+        push_wire_accessors(out, config, plan, order, options);
+    }
+    if endian_accessors {
+        push_endian_accessors(out, config, options)?;
+    }
+    if config.serialize {
+        push_serializers(out, config, options)?;
+    }
+
+    let size = packet_size(config, bit_layout_plan);
+    if config.crc == Some(CrcKind::Dji) {
+        push_frame_packer(out, config, cmd_id, &size, options);
+    }
+
+    // Traits
+    if !config.emit_traits {
+        return Ok(blocks);
+    }
+    let out = &mut blocks.traits;
+    let hash = format_layout_hash(layout_hash(config));
+    if let Some(traits) = custom_traits(config, cmd_id, &size) {
+        out.push_str(&doc_block);
+        out.push_str(&traits);
+        return Ok(blocks);
+    }
+
+    let indent = options.indent(1);
+    out.push_str(&doc_block);
+    out.push_str("template <>\n");
+    out.push_str(&format!(
+        "struct RPL::Meta::PacketTraits<{}> : PacketTraitsBase<PacketTraits<{}>>{}",
+        config.packet_name,
+        config.packet_name,
+        options.open_brace()
+    ));
+    out.push_str(&format!(
+        "{}static constexpr uint16_t cmd = {};\n",
+        indent,
+        format_command_id(cmd_id)
+    ));
+    out.push_str(&format!(
+        "{}static constexpr size_t size = {};\n",
+        indent, size
+    ));
+    out.push_str(&format!(
+        "{}static constexpr uint32_t layout_hash = {};\n",
+        indent, hash
+    ));
+    if let Some(plan) = bit_layout_plan {
+        let field_indent = options.indent(2);
+        out.push_str(&format!("{}using BitLayout = std::tuple<\n", indent));
+        for (idx, field) in plan.fields.iter().enumerate() {
+            let suffix = if idx + 1 == plan.fields.len() {
+                ""
+            } else {
+                ","
+            };
+
+            // 根据是否为数组字段生成不同的格式
+            if field.is_array {
+                if let Some(arr_size) = field.array_size {
+                    // 数组字段：Field<std::array<元素类型, 元素个数>, 总位数>
+                    out.push_str(&format!(
+                        "{}Field<std::array<{}, {}>, {}>{}\n",
+                        field_indent, field.ty, arr_size, field.bits, suffix
+                    ));
+                } else {
+                    // 理论上不应该到这里
+                    out.push_str(&format!(
+                        "{}Field<{}, {}>{}\n",
+                        field_indent, field.ty, field.bits, suffix
+                    ));
+                }
+            } else {
+                // 非数组字段：Field<类型, 位数>
+                out.push_str(&format!(
+                    "{}Field<{}, {}>{}\n",
+                    field_indent, field.ty, field.bits, suffix
+                ));
+            }
+        }
+        out.push_str(&format!("{}>;\n", indent));
+    }
+    out.push_str("};\n");
+    Ok(blocks)
+}
+
+/// `traits_template` 替换占位符后的结果，以换行结尾
+pub(crate) fn custom_traits(config: &Config, cmd_id: u16, size: &str) -> Option<String> {
+    let template = config.traits_template.as_ref()?;
+    let cmd = format_command_id(cmd_id);
+    let hash = format_layout_hash(layout_hash(config));
+    let mut out = render_template(
+        template,
+        &[
+            ("name", config.packet_name.as_str()),
+            ("cmd", cmd.as_str()),
+            ("size", size),
+            ("hash", hash.as_str()),
+        ],
+    );
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    Some(out)
+}
+
+/// traits 中 size 的写法：含位域时为位流的字节数，否则为结构体的 sizeof
+fn packet_size(config: &Config, bit_layout_plan: Option<&BitLayoutPlan>) -> String {
+    bit_layout_plan
+        .map(|plan| bytes_from_bits(plan.total_bits))
+        .map(|size| size.to_string())
+        .unwrap_or_else(|| format!("sizeof({})", config.packet_name))
+}
+
+fn push_namespace_end(out: &mut String, config: &Config, options: &GenerateOptions) {
+    if let Some(ns) = &config.namespace {
+        out.push_str(&format!("{}\n\n", options.namespace_close(ns)));
+        if let Some(alias) = &options.namespace_alias {
+            out.push_str(&format!("namespace {} = {};\n\n", alias, ns));
+        }
+    }
+}
+
+/// 别名只能是单个标识符，不能是 `A::B` 形式的限定名
+fn is_alias_name(name: &str) -> bool {
+    is_identifier(name) && !is_cpp_keyword(name)
+}
+
+// New functionality to support generating multiple packets
+#[derive(Debug, Error)]
+pub enum MultiGenerateError {
+    #[error("JSON解析失败: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("配置验证未通过，请检查错误信息")]
+    ValidationError,
+    #[error("代码生成失败: {0}")]
+    GenerateError(#[from] GenerateError),
+    #[error("握手包的命令 ID '{0}' 无效")]
+    InvalidHandshakeCommand(String),
+    #[error("握手包的命令 ID {0} 与包 '{1}' 相同")]
+    HandshakeCommandConflict(String, String),
+    #[error("命名空间别名 '{0}' 不能同时指向 '{1}' 与 '{2}'")]
+    NamespaceAliasConflict(String, String, String),
+}
+
+pub fn generate_multiple(json_input: &str) -> Result<Vec<(String, String)>, MultiGenerateError> {
+    generate_multiple_with_options(json_input, &GenerateOptions::default())
+}
+
+pub fn generate_multiple_with_options(
+    json_input: &str,
+    options: &GenerateOptions,
+) -> Result<Vec<(String, String)>, MultiGenerateError> {
+    // Try to parse as a single config first (for backward compatibility)
+    if let Ok(single_config) = serde_json::from_str::<Config>(json_input) {
+        let diags = validate(json_input);
+        for diag in diags {
+            if diag.severity == Severity::Error {
+                return Err(MultiGenerateError::ValidationError);
+            }
+        }
+        let output = generate_with_options(json_input, options)?;
+        return Ok(vec![(single_config.packet_name, output)]);
+    }
+
+    // Packet set with set-level metadata, otherwise an array of configs
+    let mut configs: Vec<Config> = match serde_json::from_str::<PacketSet>(json_input) {
+        Ok(set) => {
+            if validate_multiple(json_input)
+                .iter()
+                .any(|d| d.severity == Severity::Error)
+            {
+                return Err(MultiGenerateError::ValidationError);
+            }
+            set.packets
+        }
+        Err(_) => serde_json::from_str(json_input)?,
+    };
+    attach_dependencies(&mut configs);
+    aliased_namespace(&configs, options)?;
+    let mut results = Vec::new();
+
+    for config in configs {
+        // Create JSON for each individual config to validate
+        let config_json = serde_json::to_string(&config)?;
+        let diags = validate(&config_json);
+        for diag in diags {
+            if diag.severity == Severity::Error {
+                return Err(MultiGenerateError::ValidationError);
+            }
+        }
+
+        // Generate output for this config
+        let output = generate_with_options(&config_json, options)?;
+        results.push((config.packet_name, output));
+    }
+
+    Ok(results)
+}
+
+/// 为带有 `protocol_version` 的包集合生成注册表头文件
+/// 其中包含版本常量以及所有包头文件的引用；未声明版本时返回 `None`
+pub fn generate_registry(
+    json_input: &str,
+    options: &GenerateOptions,
+) -> Result<Option<String>, MultiGenerateError> {
+    let parsed: ConfigOrArray = serde_json::from_str(json_input)?;
+    let Some(version_str) = parsed.protocol_version() else {
+        return Ok(None);
+    };
+    let version: ProtocolVersion = version_str
+        .parse()
+        .map_err(|_| MultiGenerateError::ValidationError)?;
+    let packets = parsed.into_packets();
+
+    let guard = "RPL_PROTOCOL_REGISTRY_HPP";
+    let namespace = "RPL::Protocol";
+    let mut out = String::new();
+    out.push_str(&format!("#ifndef {}\n", guard));
+    out.push_str(&format!("#define {}\n\n", guard));
+
+    // freestanding 时后备 typedef 由随后包含的各包头文件提供
+    let includes = shared_includes(&packets);
+    push_integer_includes(&mut out, includes.as_deref(), "cstdint", options);
+    out.push_str("#include <tuple>\n");
+    for packet in &packets {
+        out.push_str(&format!(
+            "#include \"{}\"\n",
+            options.packet_include(&packet.packet_name)
+        ));
+    }
+    out.push('\n');
+
+    out.push_str(&format!("{}\n\n", options.namespace_open(namespace)));
+    let var = options.constexpr_variable();
+    out.push_str(&format!(
+        "{var} uint16_t version_major = {};\n",
+        version.major
+    ));
+    out.push_str(&format!(
+        "{var} uint16_t version_minor = {};\n",
+        version.minor
+    ));
+    out.push_str(&format!(
+        "{var} uint16_t version_patch = {};\n",
+        version.patch
+    ));
+    out.push_str(&format!(
+        "{var} const char* version_string = \"{}\";\n\n",
+        version
+    ));
+
+    out.push_str("using Packets = std::tuple<\n");
+    for (idx, packet) in packets.iter().enumerate() {
+        let suffix = if idx + 1 == packets.len() { "" } else { "," };
+        let qualified = match &packet.namespace {
+            Some(ns) => format!("{}::{}", ns, packet.packet_name),
+            None => packet.packet_name.clone(),
+        };
+        out.push_str(&format!("{}{}{}\n", options.indent(1), qualified, suffix));
+    }
+    out.push_str(">;\n\n");
+    out.push_str(&format!("{}\n\n", options.namespace_close(namespace)));
+
+    out.push_str(&format!("#endif // {}\n", guard));
+    Ok(Some(out))
+}
+
+/// 设置了命名空间别名时生成汇总头文件：包含所有包的头文件并定义别名，
+/// 下游代码只需包含这一个文件即可通过别名访问全部包；未设置别名时返回 `None`
+pub fn generate_umbrella(
+    json_input: &str,
+    options: &GenerateOptions,
+) -> Result<Option<String>, MultiGenerateError> {
+    let Some(alias) = &options.namespace_alias else {
+        return Ok(None);
+    };
+    if !is_alias_name(alias) {
+        return Err(GenerateError::InvalidNamespaceAlias(alias.clone()).into());
+    }
+    let packets = serde_json::from_str::<ConfigOrArray>(json_input)?.into_packets();
+    let namespace = aliased_namespace(&packets, options)?;
+
+    let guard = format!("RPL_{}_HPP", alias.to_uppercase());
+    let mut out = String::new();
+    out.push_str(&format!("#ifndef {}\n", guard));
+    out.push_str(&format!("#define {}\n\n", guard));
+    for packet in &packets {
+        out.push_str(&format!(
+            "#include \"{}\"\n",
+            options.packet_include(&packet.packet_name)
+        ));
+    }
+    out.push('\n');
+    if let Some(ns) = namespace {
+        out.push_str(&format!("namespace {} = {};\n\n", alias, ns));
+    }
+    out.push_str(&format!("#endif // {}\n", guard));
+    Ok(Some(out))
+}
+
+/// 设置了命名空间别名时，带命名空间的包必须位于同一个命名空间，否则同时包含时别名会重复定义
+fn aliased_namespace<'a>(
+    packets: &'a [Config],
+    options: &GenerateOptions,
+) -> Result<Option<&'a str>, MultiGenerateError> {
+    let Some(alias) = &options.namespace_alias else {
+        return Ok(None);
+    };
+    let mut namespaces = packets.iter().filter_map(|p| p.namespace.as_deref());
+    let Some(first) = namespaces.next() else {
+        return Ok(None);
+    };
+    match namespaces.find(|ns| *ns != first) {
+        Some(other) => Err(MultiGenerateError::NamespaceAliasConflict(
+            alias.clone(),
+            first.to_string(),
+            other.to_string(),
+        )),
+        None => Ok(Some(first)),
+    }
+}
+
+/// 对只在包尾部新增字段的包，生成新旧版本结构体之间的转换函数
+/// 返回 (包名, 兼容头文件内容) 列表，没有新增变更的包不会生成
+pub fn generate_compat_shims(
+    old_json: &str,
+    new_json: &str,
+    options: &GenerateOptions,
+) -> Result<Vec<(String, String)>, MultiGenerateError> {
+    if validate_multiple(new_json)
+        .iter()
+        .any(|d| d.severity == Severity::Error)
+    {
+        return Err(MultiGenerateError::ValidationError);
+    }
+    let old_packets = serde_json::from_str::<ConfigOrArray>(old_json)?.into_packets();
+    let new_packets = serde_json::from_str::<ConfigOrArray>(new_json)?.into_packets();
+
+    let mut results = Vec::new();
+    for new_config in &new_packets {
+        let Some(old_config) = old_packets
+            .iter()
+            .find(|p| p.packet_name == new_config.packet_name)
+        else {
+            continue;
+        };
+
+        let report = diff_configs(
+            std::slice::from_ref(old_config),
+            std::slice::from_ref(new_config),
+        );
+        let is_additive = report.required_bump() == VersionBump::Minor
+            && report
+                .changes
+                .iter()
+                .any(|c| c.kind == ChangeKind::FieldAdded);
+        if !is_additive {
+            continue;
+        }
+
+        results.push((
+            new_config.packet_name.clone(),
+            generate_compat_shim(old_config, new_config, options),
+        ));
+    }
+
+    Ok(results)
+}
+
+fn generate_compat_shim(
+    old_config: &Config,
+    new_config: &Config,
+    options: &GenerateOptions,
+) -> String {
+    let indent = options.indent(1);
+    let name = &new_config.packet_name;
+    let previous_name = format!("{}Previous", name);
+    let guard = format!("RPL_{}_COMPAT_HPP", name.to_uppercase());
+
+    let mut out = String::new();
+    out.push_str(&format!("#ifndef {}\n", guard));
+    out.push_str(&format!("#define {}\n\n", guard));
+    out.push_str(&format!("#include \"{}.hpp\"\n\n", name));
+
+    if let Some(ns) = &new_config.namespace {
+        out.push_str(&format!("{}\n\n", options.namespace_open(ns)));
+    }
+
+    let doc = format!("/**\n * @brief {} 的上一版本布局\n */\n", name);
+    push_struct(
+        &mut out,
+        &with_reserved_gaps(old_config),
+        &previous_name,
+        &doc,
+        options,
+    );
+
+    out.push_str("/// 旧版本转换为新版本，新增字段填零\n");
+    out.push_str(&format!(
+        "inline {} upgrade(const {}& previous){}",
+        name,
+        previous_name,
+        options.open_brace()
+    ));
+    out.push_str(&format!("{}{} current{{}};\n", indent, name));
+    // 字段可能在新版本中移入或移出了嵌套结构体，按字段名对应
+    let paths: Vec<(String, String)> = old_config
+        .fields
+        .iter()
+        .map(|old| {
+            let new_path = new_config
+                .fields
+                .iter()
+                .find(|new| new.name == old.name)
+                .map_or_else(|| old.member_path(), Field::member_path);
+            (old.member_path(), new_path)
+        })
+        .collect();
+    for (old_path, new_path) in &paths {
+        out.push_str(&format!(
+            "{}current.{} = previous.{};\n",
+            indent, new_path, old_path
+        ));
+    }
+    out.push_str(&format!("{}return current;\n}}\n\n", indent));
+
+    out.push_str("/// 新版本转换为旧版本，丢弃新增字段\n");
+    out.push_str(&format!(
+        "inline {} downgrade(const {}& current){}",
+        previous_name,
+        name,
+        options.open_brace()
+    ));
+    out.push_str(&format!("{}{} previous{{}};\n", indent, previous_name));
+    for (old_path, new_path) in &paths {
+        out.push_str(&format!(
+            "{}previous.{} = current.{};\n",
+            indent, old_path, new_path
+        ));
+    }
+    out.push_str(&format!("{}return previous;\n}}\n", indent));
+
+    if let Some(ns) = &new_config.namespace {
+        out.push_str(&format!("\n{}\n", options.namespace_close(ns)));
+    }
+
+    out.push_str(&format!("\n#endif // {}\n", guard));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Backend;
+
+    #[test]
+    fn test_generate_basic_packet() {
+        let json = r#"{
+            "packet_name": "BasicPacket",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": "RPL_BASICPACKET_HPP",
+            "fields": [
+                {
+                    "name": "field1",
+                    "type": "uint8_t",
+                    "comment": "First field"
+                },
+                {
+                    "name": "field2",
+                    "type": "float",
+                    "comment": "Second field"
+                }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+
+        assert!(result.contains("#ifndef RPL_BASICPACKET_HPP"));
+        assert!(result.contains("#define RPL_BASICPACKET_HPP"));
+        assert!(result.contains("struct BasicPacket"));
+        assert!(result.contains("} __attribute__((packed));"));
+        assert!(result.contains("uint8_t field1; ///< First field"));
+        assert!(result.contains("float field2; ///< Second field"));
+        assert!(result.contains("static constexpr uint16_t cmd = 0x0104;"));
+        assert!(result.contains("static constexpr size_t size = sizeof(BasicPacket)"));
+        let hash = layout_hash(&serde_json::from_str(json).unwrap());
+        assert!(result.contains(&format!(
+            "static constexpr uint32_t layout_hash = 0x{:08X};",
+            hash
+        )));
+        assert!(result.contains("#endif // RPL_BASICPACKET_HPP"));
+    }
+
+    #[test]
+    fn test_generate_doc_block_on_struct_and_traits() {
+        let json = r#"{
+            "packet_name": "DocPacket",
+            "command_id": "0x0104",
+            "comment": "传感器数据包",
+            "fields": [
+                { "name": "sensor_id", "type": "uint8_t", "comment": "传感器ID" },
+                { "name": "temperature", "type": "float", "comment": "温度值" }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+        let doc = "/**\n * @brief 传感器数据包\n * @details cmd = 0x0104, size = 5 bytes\n */\n";
+        assert!(result.contains(&format!("{}struct DocPacket\n", doc)));
+        assert!(result.contains(&format!(
+            "{}template <>\nstruct RPL::Meta::PacketTraits<DocPacket>",
+            doc
+        )));
+    }
+
+    #[test]
+    fn test_generate_doc_block_without_packet_comment() {
+        let json = r#"{
+            "packet_name": "NoDocPacket",
+            "command_id": "0x0201",
+            "packed": false,
+            "fields": [
+                { "name": "a", "type": "uint8_t", "comment": "A" },
+                { "name": "b", "type": "uint32_t", "comment": "B" }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+        assert!(!result.contains("@brief"));
+        assert!(
+            result
+                .contains("/**\n * @details cmd = 0x0201, size = 8 bytes\n */\nstruct NoDocPacket")
+        );
+    }
+
+    #[test]
+    fn test_generate_with_indent_and_brace_options() {
+        let json = r#"{
+            "packet_name": "StylePacket",
+            "command_id": "0x0104",
+            "fields": [
+                { "name": "a", "type": "uint8_t", "bit_field": 4, "comment": "A" },
+                { "name": "b", "type": "uint8_t", "bit_field": 4, "comment": "B" }
+            ]
+        }"#;
+
+        let options = GenerateOptions {
+            indent_width: 2,
+            brace_style: BraceStyle::SameLine,
+            ..GenerateOptions::default()
+        };
+        let result = generate_with_options(json, &options).unwrap();
+        assert!(result.contains("struct StylePacket {\n  uint8_t a : 4; ///< A\n"));
+        assert!(result.contains("PacketTraitsBase<PacketTraits<StylePacket>> {\n"));
+        assert!(result.contains("\n  static constexpr uint16_t cmd = 0x0104;\n"));
+        assert!(result.contains("\n  using BitLayout = std::tuple<\n    Field<uint8_t, 4>,\n"));
+        assert!(result.contains("\n  >;\n};\n"));
+
+        let options = GenerateOptions {
+            use_tabs: true,
+            ..GenerateOptions::default()
+        };
+        let result = generate_with_options(json, &options).unwrap();
+        assert!(result.contains("struct StylePacket\n{\n\tuint8_t a : 4; ///< A\n"));
+        assert!(result.contains("\n\t\tField<uint8_t, 4>\n\t>;\n"));
+    }
+
+    #[test]
+    fn test_generate_localized_comments() {
+        let json = r#"{
+            "packet_name": "Gimbal",
+            "command_id": "0x0104",
+            "comment": { "zh": "云台控制", "en": "Gimbal control" },
+            "fields": [
+                { "name": "yaw", "type": "float", "comment": { "zh": "偏航角", "en": "Yaw angle" } },
+                { "name": "mode", "type": "uint8_t", "comment": "0/1" }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+        assert!(result.contains(" * @brief 云台控制\n"));
+        assert!(result.contains("float yaw; ///< 偏航角\n"));
+
+        let options = GenerateOptions {
+            comment_language: Some("en".to_string()),
+            ..GenerateOptions::default()
+        };
+        for backend in [Backend::Cpp, Backend::C, Backend::Rust] {
+            let result = backend.generate(json, &options).unwrap();
+            assert!(result.contains("Gimbal control"), "{}", backend);
+            assert!(result.contains("Yaw angle"), "{}", backend);
+            assert!(!result.contains("偏航角"), "{}", backend);
+        }
+    }
+
+    #[test]
+    fn test_generate_with_aligned_fields() {
+        let json = r#"{
+            "packet_name": "AlignedPacket",
+            "command_id": "0x0104",
+            "fields": [
+                { "name": "id", "type": "uint8_t", "bit_field": 4, "comment": "ID" },
+                { "name": "mode", "type": "uint8_t", "bit_field": 4 },
+                { "name": "temperature", "type": "float[3]", "comment": "温度" },
+                { "name": "t", "type": "uint64_t", "comment": "时间戳" }
+            ]
+        }"#;
+
+        let options = GenerateOptions {
+            align_fields: true,
+            ..GenerateOptions::default()
+        };
+        let result = generate_with_options(json, &options).unwrap();
+        assert!(result.contains(concat!(
+            "    uint8_t              id : 4;      ///< ID\n",
+            "    uint8_t              mode : 4;\n",
+            "    std::array<float, 3> temperature; ///< 温度\n",
+            "    uint64_t             t;           ///< 时间戳\n",
+        )));
+
+        // 默认不对齐
+        let result = generate(json).unwrap();
+        assert!(result.contains("    uint8_t id : 4; ///< ID\n    uint8_t mode : 4;\n"));
+    }
+
+    #[test]
+    fn test_generate_with_namespace() {
+        let json = r#"{
+            "packet_name": "NamespacePacket",
+            "command_id": "0xABCD",
+            "namespace": "Robot::Sensors",
+            "packed": true,
+            "header_guard": "RPL_NAMESPACEPACKET_HPP",
+            "fields": [
+                {
+                    "name": "sensor_id",
+                    "type": "uint16_t",
+                    "comment": "Sensor identifier"
+                }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+
+        assert!(result.contains("namespace Robot::Sensors {"));
+        assert!(result.contains("struct NamespacePacket"));
+        assert!(result.contains("} __attribute__((packed))"));
+        assert!(result.contains("uint16_t sensor_id; ///< Sensor identifier"));
+        assert!(result.contains("// namespace Robot::Sensors"));
+        assert!(result.contains("static constexpr uint16_t cmd = 0xABCD;"));
+    }
+
+    #[test]
+    fn test_generate_with_namespace_alias() {
+        let json = r#"{
+            "packet_name": "Status",
+            "command_id": "0x0101",
+            "namespace": "Very::Long::Team",
+            "fields": [{ "name": "mode", "type": "uint8_t" }]
+        }"#;
+        let alias = |alias: &str| GenerateOptions {
+            namespace_alias: Some(alias.to_string()),
+            ..GenerateOptions::default()
+        };
+
+        let output = generate_with_options(json, &alias("rpl_pkts")).unwrap();
+        assert!(output.ends_with(
+            "} // namespace Very::Long::Team\n\nnamespace rpl_pkts = Very::Long::Team;\n\n#endif // RPL_STATUS_HPP\n"
+        ));
+
+        // 没有命名空间的包不需要别名
+        let global = json.replace(r#""Very::Long::Team""#, "null");
+        let output = generate_with_options(&global, &alias("rpl_pkts")).unwrap();
+        assert!(!output.contains("rpl_pkts"));
+
+        for invalid in ["Team::pkts", "1pkts", "namespace", ""] {
+            assert!(matches!(
+                generate_with_options(json, &alias(invalid)),
+                Err(GenerateError::InvalidNamespaceAlias(name)) if name == invalid
+            ));
+        }
+    }
+
+    #[test]
+    fn test_generate_with_align() {
+        let json = r#"{
+            "packet_name": "DmaPacket",
+            "command_id": "0x0105",
+            "align": 4,
+            "fields": [
+                { "name": "a", "type": "uint8_t", "comment": "A" },
+                { "name": "b", "type": "uint16_t", "comment": "B" }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+        assert!(result.contains("struct alignas(4) DmaPacket\n{"));
+        assert!(result.contains("} __attribute__((packed));"));
+        assert!(result.contains("size = 4 bytes"));
+    }
+
+    #[test]
+    fn test_generate_expected_size_assert() {
+        let json = r#"{
+            "packet_name": "DmaPacket",
+            "command_id": "0x0105",
+            "namespace": "robot",
+            "expected_size": 3,
+            "fields": [
+                { "name": "a", "type": "uint8_t", "comment": "A" },
+                { "name": "b", "type": "uint16_t", "comment": "B" }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+        assert!(result.contains(
+            "} __attribute__((packed));\n\nstatic_assert(sizeof(DmaPacket) == 3, \"DmaPacket 的大小应为 3 字节\");\n\n"
+        ));
+        assert!(result.find("static_assert") < result.find("} // namespace robot"));
+
+        // 位流中的包没有与线上大小对应的 sizeof
+        let bits = json.replace(
+            r#""type": "uint16_t""#,
+            r#""type": "uint16_t", "bit_field": 12"#,
+        );
+        assert!(!generate(&bits).unwrap().contains("static_assert"));
+    }
+
+    #[test]
+    fn test_generate_volatile_view() {
+        let json = r#"{
+            "packet_name": "DmaPacket",
+            "command_id": "0x0105",
+            "volatile_view": true,
+            "fields": [
+                { "name": "a", "type": "uint8_t", "comment": "A" },
+                { "name": "b", "type": "float[2]", "comment": "B" }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+        assert!(result.contains("#include <cstddef>"));
+        assert!(result.contains(
+            "inline volatile DmaPacket& view_DmaPacket(volatile void* buffer)\n{\n    return *static_cast<volatile DmaPacket*>(buffer);\n}"
+        ));
+        assert!(result.contains("inline DmaPacket load_DmaPacket(const volatile void* buffer)"));
+        assert!(result.contains("for (std::size_t i = 0; i < sizeof(DmaPacket); ++i)\n    {\n        dst[i] = src[i];\n    }"));
+
+        // 访问函数位于结构体之后、PacketTraits 之前
+        let view_pos = result.find("view_DmaPacket").unwrap();
+        assert!(view_pos > result.find("} __attribute__((packed));").unwrap());
+        assert!(view_pos < result.find("template <>").unwrap());
+    }
+
+    #[test]
+    fn test_generate_endian_accessors() {
+        let json = r#"{
+            "packet_name": "Motor",
+            "command_id": "0x0201",
+            "endianness": "big",
+            "fields": [
+                { "name": "id", "type": "uint8_t", "comment": "编号" },
+                { "name": "speed", "type": "int16_t", "comment": "转速" },
+                { "name": "current", "type": "int16_t[2]", "endianness": "little" }
+            ]
+        }"#;
+        let result = generate(json).unwrap();
+        for header in ["<cstddef>", "<bit>", "<type_traits>"] {
+            assert!(
+                result.contains(&format!("#include {}\n", header)),
+                "{}",
+                header
+            );
+        }
+        assert!(result.contains("#ifndef RPLC_WIRE_HELPERS"));
+        assert!(result.contains(
+            "/// Motor 中多字节字段的访问函数，在声明的字节序与主机字节序之间转换\n\
+             struct MotorEndian\n{\n\
+             \x20   /// 转速（大端）\n\
+             \x20   static int16_t speed(const Motor& packet)\n    {\n\
+             \x20       return rplc_wire::load<int16_t>(reinterpret_cast<const uint8_t*>(&packet) + 1, true);\n    }\n\n\
+             \x20   static void set_speed(Motor& packet, int16_t value)\n    {\n\
+             \x20       rplc_wire::store(reinterpret_cast<uint8_t*>(&packet) + 1, value, true);\n    }\n\n\
+             \x20   /// current（小端）\n\
+             \x20   static int16_t current(const Motor& packet, std::size_t i)\n    {\n\
+             \x20       return rplc_wire::load<int16_t>(reinterpret_cast<const uint8_t*>(&packet) + 3 + 2 * i, false);\n    }\n"
+        ));
+        assert!(
+            result.contains("static void set_current(Motor& packet, std::size_t i, int16_t value)")
+        );
+        // 单字节字段不需要转换
+        assert!(!result.contains("set_id"));
+
+        // 没有声明字节序、启用 serialize 或含位域时不生成
+        for json in [
+            json.replace(r#""endianness": "big","#, "")
+                .replace(r#", "endianness": "little""#, ""),
+            json.replace(
+                r#""endianness": "big","#,
+                r#""endianness": "big", "serialize": true,"#,
+            ),
+            json.replace(
+                r#""type": "uint8_t","#,
+                r#""type": "uint8_t", "bit_field": 8,"#,
+            ),
+        ] {
+            assert!(
+                !generate(&json).unwrap().contains("MotorEndian"),
+                "{}",
+                json
+            );
+        }
+
+        // serialize 按各字段的字节序读写
+        let serialized = generate(&json.replace(
+            r#""endianness": "big","#,
+            r#""endianness": "big", "serialize": true,"#,
+        ))
+        .unwrap();
+        assert!(serialized.contains("rplc_wire::store(buf + 1, packet.speed, true);"));
+        assert!(
+            serialized.contains("rplc_wire::store(buf + 3 + 2 * i, packet.current[i], false);")
+        );
+    }
+
+    #[test]
+    fn test_generate_serializers() {
+        let json = r#"{
+            "packet_name": "GimbalCommand",
+            "command_id": "0x0102",
+            "namespace": "Robot",
+            "serialize": true,
+            "endianness": "big",
+            "fields": [
+                { "name": "yaw", "type": "float", "comment": "Y" },
+                { "name": "gains", "type": "int16_t[3]", "comment": "G" },
+                { "name": "mode", "type": "uint8_t", "offset": 12, "comment": "M" }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+        for header in ["<bit>", "<optional>", "<span>", "<type_traits>"] {
+            assert!(
+                result.contains(&format!("#include {}\n", header)),
+                "{}",
+                header
+            );
+        }
+        // C++20 下以 std::bit_cast 读写，不需要 memcpy
+        assert!(!result.contains("#include <cstring>"));
+        // 辅助函数位于全局命名空间，以宏保护只定义一次
+        let helpers = result.find("#ifndef RPLC_WIRE_HELPERS").unwrap();
+        assert!(helpers < result.find("namespace Robot {").unwrap());
+        assert!(result.contains("inline void store(uint8_t* buf, T value, bool big_endian)"));
+
+        assert!(result.contains(
+            "/// 按大端字节序将 GimbalCommand 写入 buf，buf 至少需要 13 字节\n\
+             inline void serialize(const GimbalCommand& packet, uint8_t* buf)\n{\n\
+             \x20   rplc_wire::store(buf + 0, packet.yaw, true);\n\
+             \x20   for (std::size_t i = 0; i < 3; ++i)\n    {\n\
+             \x20       rplc_wire::store(buf + 4 + 2 * i, packet.gains[i], true);\n    }\n\
+             \x20   for (std::size_t i = 0; i < 2; ++i)\n    {\n\
+             \x20       rplc_wire::store(buf + 10 + i, packet.reserved_10[i], true);\n    }\n\
+             \x20   rplc_wire::store(buf + 12, packet.mode, true);\n}\n"
+        ));
+        assert!(result.contains(
+            "template <>\ninline std::optional<GimbalCommand> deserialize<GimbalCommand>(std::span<const uint8_t> data)\n{\n\
+             \x20   if (data.size() < 13)\n    {\n        return std::nullopt;\n    }\n\
+             \x20   GimbalCommand packet{};\n\
+             \x20   packet.yaw = rplc_wire::load<float>(data.data() + 0, true);\n"
+        ));
+        assert!(result.contains(
+            "packet.mode = rplc_wire::load<uint8_t>(data.data() + 12, true);\n    return packet;\n}"
+        ));
+        // 紧凑结构体没有填充字节，不需要先清零
+        assert!(!result.contains("std::memset"));
+        assert!(
+            result.find("inline void serialize").unwrap()
+                < result.find("struct RPL::Meta").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_generate_cpp_standard() {
+        let json = r#"{
+            "packet_name": "Status",
+            "command_id": "0x0102",
+            "namespace": "Robot::Chassis",
+            "serialize": true,
+            "endianness": "big",
+            "crc": "dji",
+            "fields": [
+                { "name": "mode", "type": "uint8_t", "comment": "M", "values": { "0": "idle" } },
+                { "name": "speed", "type": "float", "comment": "S" },
+                { "name": "crc", "type": "uint16_t", "comment": "C" }
+            ]
+        }"#;
+        let with = |cpp_standard| GenerateOptions {
+            cpp_standard,
+            ..Default::default()
+        };
+
+        let cpp17 = generate_with_options(json, &with(CppStandard::Cpp17)).unwrap();
+        assert!(
+            cpp17.contains("#include <cstring>\n#include <optional>\n#include <string_view>\n")
+        );
+        for feature in ["std::span", "std::bit_cast", "<bit>", "<type_traits>"] {
+            assert!(!cpp17.contains(feature), "{}", feature);
+        }
+        assert!(cpp17.contains(
+            "template <>\ninline std::optional<Status> deserialize<Status>(const uint8_t* data, std::size_t size)\n{\n\
+             \x20   if (size < 7)\n"
+        ));
+        assert!(cpp17.contains("packet.speed = rplc_wire::load<float>(data + 1, true);"));
+        assert!(cpp17.contains("    std::memcpy(&bits, &value, sizeof(T));\n"));
+        assert!(cpp17.contains(
+            "inline bool load<bool>(const uint8_t* buf, bool)\n{\n    return buf[0] != 0;\n}\n"
+        ));
+        assert!(cpp17.contains("namespace Robot::Chassis {\n"));
+        assert!(cpp17.contains("inline constexpr std::size_t frame_size_Status"));
+        assert_eq!(
+            cpp17,
+            generate_with_options(json, &with(CppStandard::Cpp17)).unwrap()
+        );
+
+        // C++14 没有 std::optional，serialize 无法生成
+        assert!(matches!(
+            generate_with_options(json, &with(CppStandard::Cpp14)),
+            Err(GenerateError::Unsupported { backend: "C++", .. })
+        ));
+        let cpp14 = generate_with_options(
+            &json.replace(r#""serialize": true,"#, ""),
+            &with(CppStandard::Cpp14),
+        )
+        .unwrap();
+        assert!(cpp14.contains("namespace Robot { namespace Chassis {\n"));
+        assert!(cpp14.contains("} } // namespace Robot::Chassis\n"));
+        assert!(cpp14.contains("\nconstexpr uint8_t SOF = 0xA5;\n"));
+        assert!(cpp14.contains("\nconstexpr std::size_t frame_size_Status"));
+        assert!(cpp14.contains("static constexpr const char* mode_name(uint8_t value)"));
+        assert!(cpp14.contains("default: return \"\";"));
+        for feature in [
+            "inline constexpr std::size_t",
+            "string_view",
+            "if constexpr",
+            "std::bit_cast",
+        ] {
+            assert!(!cpp14.contains(feature), "{}", feature);
+        }
+
+        // C++23 与默认的 C++20 输出相同
+        assert_eq!(
+            generate_with_options(json, &with(CppStandard::Cpp23)).unwrap(),
+            generate(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_generate_serializers_defaults() {
+        let json = r#"{
+            "packet_name": "Loose",
+            "command_id": "0x0103",
+            "packed": false,
+            "serialize": true,
+            "fields": [
+                { "name": "a", "type": "uint8_t", "comment": "A" },
+                { "name": "b", "type": "uint32_t", "comment": "B" }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+        assert!(result.contains("/// 按小端字节序将 Loose 写入 buf，buf 至少需要 8 字节\n"));
+        assert!(result.contains("    std::memset(buf, 0, 8);\n    rplc_wire::store(buf + 0, packet.a, false);\n    rplc_wire::store(buf + 4, packet.b, false);\n"));
+
+        let plain = generate(&json.replace(r#""serialize": true,"#, "")).unwrap();
+        assert!(!plain.contains("rplc_wire"));
+        assert!(!plain.contains("#include <span>"));
+    }
+
+    #[test]
+    fn test_generate_dji_crc() {
+        let json = r#"{
+            "packet_name": "RobotHurt",
+            "command_id": "0x0206",
+            "namespace": "Referee",
+            "crc": "dji",
+            "fields": [
+                { "name": "armor_id", "type": "uint8_t", "comment": "A" },
+                { "name": "hp_delta", "type": "uint16_t", "comment": "H" }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+        assert!(result.contains("#include <cstddef>\n"));
+        assert!(result.contains("#include <cstring>\n"));
+        // 帧常量与 CRC 函数位于全局命名空间，以宏保护只定义一次
+        let helpers = result.find("#ifndef RPLC_DJI_CRC").unwrap();
+        assert!(helpers < result.find("namespace Referee {").unwrap());
+        assert_eq!(result.matches("namespace rplc_dji\n{").count(), 1);
+        assert!(
+            result.contains(
+                "crc = static_cast<uint8_t>((crc & 1u) ? (crc >> 1) ^ 0x8Cu : crc >> 1);"
+            )
+        );
+        assert!(result.contains(
+            "crc = static_cast<uint16_t>((crc & 1u) ? (crc >> 1) ^ 0x8408u : crc >> 1);"
+        ));
+        assert!(
+            result.contains("inline bool verify_frame(const uint8_t* frame, std::size_t length)")
+        );
+
+        assert!(result.contains(
+            "inline constexpr std::size_t frame_size_RobotHurt = rplc_dji::HEADER_SIZE + rplc_dji::CMD_SIZE + sizeof(RobotHurt) + rplc_dji::TAIL_SIZE;\n"
+        ));
+        assert!(result.contains(
+            "inline void pack_frame_RobotHurt(const RobotHurt& packet, uint8_t seq, uint8_t* frame)\n{\n\
+             \x20   constexpr uint16_t length = static_cast<uint16_t>(sizeof(RobotHurt));\n"
+        ));
+        assert!(result.contains("    frame[5] = 0x06;\n    frame[6] = 0x02;\n"));
+        assert!(result.contains("    std::memcpy(frame + 7, &packet, sizeof(RobotHurt));\n"));
+        assert!(result.contains("    rplc_dji::append_crc16(frame, frame_size_RobotHurt);\n}\n"));
+        // 打包函数位于特化之前
+        assert!(
+            result.find("pack_frame_RobotHurt").unwrap()
+                < result
+                    .find("struct RPL::Meta::PacketTraits<RobotHurt>")
+                    .unwrap()
+        );
+
+        let plain = generate(&json.replace(r#""crc": "dji","#, "")).unwrap();
+        assert!(!plain.contains("rplc_dji"));
+        assert!(!plain.contains("#include <cstring>"));
+    }
+
+    #[test]
+    fn test_generate_dji_crc_field_tail() {
+        let json = r#"{
+            "packet_name": "Tail",
+            "command_id": "0x0301",
+            "crc": "dji",
+            "crc_append": false,
+            "serialize": true,
+            "fields": [
+                { "name": "value", "type": "uint8_t", "comment": "V" },
+                { "name": "crc", "type": "uint16_t", "comment": "C" }
+            ]
+        }"#;
+
+        let result = generate(json).unwrap();
+        // 帧尾由 crc 字段承载：整帧不再追加两个字节，数据段长度不含帧尾
+        assert!(result.contains(
+            "inline constexpr std::size_t frame_size_Tail = rplc_dji::HEADER_SIZE + rplc_dji::CMD_SIZE + sizeof(Tail);\n"
+        ));
+        assert!(result.contains(
+            "    constexpr uint16_t length = static_cast<uint16_t>(sizeof(Tail) - rplc_dji::TAIL_SIZE);\n"
+        ));
+        assert!(result.contains("    serialize(packet, frame + 7);\n"));
+    }
+
+    #[test]
+    fn test_generate_without_volatile_view() {
+        let json = r#"{
+            "packet_name": "PlainPacket",
+            "command_id": "0x0105",
+            "fields": [ { "name": "a", "type": "uint8_t", "comment": "A" } ]
+        }"#;
+
+        let result = generate(json).unwrap();
+        assert!(!result.contains("#include <cstddef>"));
+        assert!(!result.contains("volatile"));
+    }
+
+    #[test]
+    fn test_generate_packed_per_compiler() {
+        let json = r#"{
+            "packet_name": "Status",
+            "command_id": "0x0101",
+            "comment": "状态",
+            "fields": [{ "name": "mode", "type": "uint8_t" }]
+        }"#;
+        let generate_for = |compiler| {
+            let options = GenerateOptions {
+                compiler,
+                ..Default::default()
+            };
+            generate_with_options(json, &options).unwrap()
+        };
+
+        for compiler in [Compiler::Gcc, Compiler::Armclang] {
+            let output = generate_for(compiler);
+            assert!(output.contains("} __attribute__((packed));"));
+            assert!(!output.contains("#pragma pack"));
+        }
+        for compiler in [Compiler::Iar, Compiler::Msvc] {
+            let output = generate_for(compiler);
+            assert!(!output.contains("__attribute__"));
+            // pragma 位于注释之前，注释紧贴结构体
+            assert!(output.contains("#pragma pack(push, 1)\n/**\n * @brief 状态\n"));
+            assert!(output.contains(" */\nstruct Status\n"));
+            assert!(output.contains("    uint8_t mode;\n};\n#pragma pack(pop)\n"));
+        }
+
+        let output = generate_for(Compiler::Armcc);
+        assert!(output.contains(" */\n__packed struct Status\n"));
+        assert!(output.contains("    uint8_t mode;\n};\n"));
+        assert!(!output.contains("__attribute__") && !output.contains("#pragma"));
+
+        let output = generate_for(Compiler::ArmccPragma);
+        assert!(output.contains("#pragma push\n#pragma pack(1)\n/**\n"));
+        assert!(output.contains("    uint8_t mode;\n};\n#pragma pop\n"));
+        assert!(!output.contains("__packed"));
+    }
+
+    #[test]
+    fn test_generate_without_traits() {
+        let json = r#"{
+            "packet_name": "Status",
+            "command_id": "0x0101",
+            "namespace": "Robot",
+            "emit_traits": false,
+            "fields": [{ "name": "mode", "type": "uint8_t", "bit_field": 3 }]
+        }"#;
+        let output = generate(json).unwrap();
+        assert!(output.contains("struct Status"));
+        assert!(!output.contains("PacketTraits"));
+        assert!(!output.contains("RPL/Meta"));
+        assert!(!output.contains("#include <tuple>"));
+        assert!(output.ends_with(
+            "} __attribute__((packed));\n\n} // namespace Robot\n\n#endif // RPL_STATUS_HPP\n"
+        ));
     }
-    out.push_str("};\n");
 
-    // End Namespace
-    if let Some(ns) = &config.namespace {
-        out.push_str(&format!("}} // namespace {}\n\n", ns));
+    #[test]
+    fn test_generate_custom_traits_template() {
+        let json = r#"{
+            "packet_name": "Status",
+            "command_id": "257",
+            "traits_template": "REGISTER_PACKET(${name}, ${cmd}, ${size}, ${hash});",
+            "fields": [{ "name": "mode", "type": "uint8_t" }]
+        }"#;
+        let output = generate(json).unwrap();
+        assert!(!output.contains("PacketTraits"));
+        assert!(!output.contains("RPL/Meta"));
+        let hash = format_layout_hash(layout_hash(&serde_json::from_str(json).unwrap()));
+        assert!(output.contains(&format!(
+            " */\nREGISTER_PACKET(Status, 0x0101, sizeof(Status), {});\n#endif // RPL_STATUS_HPP\n",
+            hash
+        )));
     }
 
-    out.push_str(&format!("#endif // {}\n", guard));
-    Ok(out)
-}
+    #[test]
+    fn test_generate_section_banners() {
+        let json = r#"{
+            "packet_name": "Status",
+            "command_id": "0x0101",
+            "fields": [
+                { "section": "IMU data" },
+                { "name": "gyro", "type": "float[3]", "comment": "角速度" },
+                { "name": "temp", "type": "int16_t" },
+                { "section": "Flags" },
+                { "name": "mode", "type": "uint8_t" }
+            ]
+        }"#;
+        let output = generate(json).unwrap();
+        assert!(output.contains(
+            "{
+    // ---- IMU data ----
+    std::array<float, 3> gyro; ///< 角速度
+    int16_t temp;
+
+    // ---- Flags ----
+    uint8_t mode;
+} __attribute__((packed));"
+        ));
+        // 分组不影响布局
+        assert!(output.contains("size = 15 bytes"));
+    }
 
-// New functionality to support generating multiple packets
-#[derive(Debug, Error)]
-pub enum MultiGenerateError {
-    #[error("JSON解析失败: {0}")]
-    JsonError(#[from] serde_json::Error),
-    #[error("配置验证未通过，请检查错误信息")]
-    ValidationError,
-    #[error("代码生成失败: {0}")]
-    GenerateError(#[from] GenerateError),
-}
+    #[test]
+    fn test_generate_nested_groups() {
+        let json = r#"{
+            "packet_name": "Status",
+            "command_id": "0x0101",
+            "fields": [
+                { "name": "mode", "type": "uint8_t" },
+                { "section": "IMU" },
+                { "name": "imu", "comment": "惯导数据", "group": [
+                    { "name": "gyro", "type": "float[3]", "comment": "角速度" },
+                    { "name": "temp", "type": "int16_t" }
+                ] },
+                { "group": [
+                    { "name": "ready", "type": "uint8_t", "bit_field": 1 },
+                    { "name": "error", "type": "uint8_t", "bit_field": 7 }
+                ] }
+            ]
+        }"#;
+        let output = generate(json).unwrap();
+        assert!(output.contains(
+            "{
+    uint8_t mode;
+
+    // ---- IMU ----
+    /// 惯导数据
+    struct __attribute__((packed))
+    {
+        std::array<float, 3> gyro; ///< 角速度
+        int16_t temp;
+    } imu;
+    struct __attribute__((packed))
+    {
+        uint8_t ready : 1;
+        uint8_t error : 7;
+    };
+} __attribute__((packed));"
+        ));
+        // 嵌套结构体不影响布局
+        assert!(output.contains("size = 16 bytes"));
+
+        let options = GenerateOptions {
+            compiler: Compiler::Msvc,
+            brace_style: BraceStyle::SameLine,
+            ..Default::default()
+        };
+        let output = generate_with_options(json, &options).unwrap();
+        assert!(output.contains("    struct {\n        std::array<float, 3> gyro;"));
+    }
 
-pub fn generate_multiple(json_input: &str) -> Result<Vec<(String, String)>, MultiGenerateError> {
-    // Try to parse as a single config first (for backward compatibility)
-    if let Ok(single_config) = serde_json::from_str::<Config>(json_input) {
-        let diags = validate(json_input);
-        for diag in diags {
-            if diag.severity == Severity::Error {
-                return Err(MultiGenerateError::ValidationError);
-            }
-        }
-        let output = generate(json_input)?;
-        return Ok(vec![(single_config.packet_name, output)]);
+    #[test]
+    fn test_generate_fixed_offsets() {
+        let json = r#"{
+            "packet_name": "Third",
+            "command_id": "0x0201",
+            "fields": [
+                { "name": "id", "type": "uint16_t", "comment": "ID" },
+                { "name": "temp", "type": "int32_t", "offset": 4 },
+                { "name": "flags", "type": "uint8_t", "bit_field": 4, "offset": 12 },
+                { "name": "mode", "type": "uint8_t", "bit_field": 4 }
+            ]
+        }"#;
+        // 位域字段不能固定偏移
+        assert!(generate(json).is_err());
+
+        let json = json.replace(r#""bit_field": 4, "offset": 12"#, r#""offset": 12"#);
+        let result = generate(&json).unwrap();
+        assert!(result.contains(
+            "    uint16_t id; ///< ID\n    std::array<uint8_t, 2> reserved_2; ///< 保留\n    int32_t temp;\n    std::array<uint8_t, 4> reserved_8; ///< 保留\n    uint8_t flags;\n"
+        ));
+        // BitLayout 与大小包含保留字节，布局哈希与展开前相同
+        assert!(result.contains("        Field<std::array<uint8_t, 2>, 16>,\n"));
+        assert!(result.contains("static constexpr size_t size = 14;"));
+        let config: Config = serde_json::from_str(&json).unwrap();
+        assert!(result.contains(&format_layout_hash(layout_hash(&config))));
     }
 
-    // If single config parsing fails, try to parse as an array of configs
-    let configs: Vec<Config> = serde_json::from_str(json_input)?;
-    let mut results = Vec::new();
+    #[test]
+    fn test_generate_reserved_entries() {
+        let json = r#"{
+            "packet_name": "Third",
+            "command_id": "0x0201",
+            "fields": [
+                { "name": "id", "type": "uint16_t", "comment": "ID" },
+                { "reserved": 2 },
+                { "name": "temp", "type": "int32_t", "comment": "T" },
+                { "reserved": 1, "comment": "对齐" }
+            ]
+        }"#;
+        let result = generate(json).unwrap();
+        assert!(result.contains(
+            "    uint16_t id; ///< ID\n    std::array<uint8_t, 2> _reserved0; ///< 保留\n    int32_t temp; ///< T\n    std::array<uint8_t, 1> _reserved1; ///< 对齐\n"
+        ));
+        assert!(result.contains("size = 9 bytes"));
+    }
 
-    for config in configs {
-        // Create JSON for each individual config to validate
-        let config_json = serde_json::to_string(&config)?;
-        let diags = validate(&config_json);
-        for diag in diags {
-            if diag.severity == Severity::Error {
-                return Err(MultiGenerateError::ValidationError);
-            }
-        }
+    #[test]
+    fn test_generate_limit_constants() {
+        let json = r#"{
+            "packet_name": "Gimbal",
+            "command_id": "0x0101",
+            "fields": [
+                { "name": "yaw", "type": "float", "comment": "Y", "min": -3.14, "max": 3.14 },
+                { "name": "pose", "comment": "P", "group": [
+                    { "name": "speed", "type": "uint16_t[2]", "comment": "S", "max": 300, "typical": 120 }
+                ] },
+                { "name": "stamp", "type": "uint64_t", "comment": "T", "max": 18446744073709551615 }
+            ]
+        }"#;
+        let output = generate(json).unwrap();
+        assert!(output.contains(
+            "    uint64_t stamp; ///< T
+
+    static constexpr float k_yaw_min = -3.14f;
+    static constexpr float k_yaw_max = 3.14f;
+    static constexpr uint16_t k_speed_max = 300;
+    static constexpr uint16_t k_speed_typical = 120;
+    static constexpr uint64_t k_stamp_max = 18446744073709551615ULL;
+} __attribute__((packed));"
+        ));
+        // 静态成员不影响布局
+        assert!(output.contains("size = 16 bytes"));
+        assert_eq!(limit_literal("double", &serde_json::Number::from(2)), "2.0");
+    }
 
-        // Generate output for this config
-        let output = generate(&config_json)?;
-        results.push((config.packet_name, output));
+    #[test]
+    fn test_generate_field_id_constants() {
+        let json = r#"{
+            "packet_name": "Gimbal",
+            "command_id": "0x0101",
+            "retired_field_ids": [2],
+            "fields": [
+                { "name": "yaw", "type": "float", "comment": "Y", "id": 1 },
+                { "name": "pitch", "type": "float", "comment": "P", "id": 3 }
+            ]
+        }"#;
+        let output = generate(json).unwrap();
+        assert!(output.contains(
+            "    float pitch; ///< P
+
+    static constexpr uint32_t k_yaw_id = 1;
+    static constexpr uint32_t k_pitch_id = 3;
+} __attribute__((packed));"
+        ));
+        assert!(output.contains("size = 8 bytes"));
     }
 
-    Ok(results)
-}
+    #[test]
+    fn test_generate_prunes_includes() {
+        let json = r#"{
+            "packet_name": "Plain",
+            "command_id": "0x0102",
+            "fields": [{ "name": "mode", "type": "uint8_t", "comment": "M" }]
+        }"#;
+        let result = generate(json).unwrap();
+        assert!(result.contains(
+            "#define RPL_PLAIN_HPP\n\n#include <cstdint>\n#include <RPL/Meta/PacketTraits.hpp>\n\n"
+        ));
+
+        // 数组字段才包含 <array>，关闭 traits 后不依赖 RPL::Meta
+        let array = json
+            .replace(r#""uint8_t""#, r#""uint8_t[4]""#)
+            .replace(r#""fields""#, r#""emit_traits": false, "fields""#);
+        let result = generate(&array).unwrap();
+        assert!(result.contains("#include <cstdint>\n#include <array>\n\n"));
+        assert!(!result.contains("RPL/Meta"));
+
+        // 位域包的 BitLayout 使用 std::tuple
+        let bits = json.replace(r#""comment": "M""#, r#""bit_field": 3, "comment": "M""#);
+        let result = generate(&bits).unwrap();
+        assert!(result.contains(
+            "#include <tuple>\n#include <RPL/Meta/BitstreamTraits.hpp>\n#include <RPL/Meta/PacketTraits.hpp>\n"
+        ));
+        assert!(!result.contains("#include <span>"));
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_generate_value_lookups() {
+        let json = r#"{
+            "packet_name": "Chassis",
+            "command_id": "0x0102",
+            "fields": [
+                { "name": "mode", "type": "uint8_t", "comment": "M", "values": {
+                    "2": { "zh": "小陀螺", "en": "Spin" }, "0": "空闲", "0x10": "\"急停\""
+                } },
+                { "name": "speed", "type": "int16_t", "comment": "S" }
+            ]
+        }"#;
+        let output = generate(json).unwrap();
+        assert!(output.contains("#include <cstdint>\n#include <string_view>\n"));
+        assert!(!output.contains("#include <array>"));
+        assert!(output.contains(
+            "    int16_t speed; ///< S
+
+    /// mode 的显示名称，未列出的值返回空字符串
+    static constexpr std::string_view mode_name(uint8_t value)
+    {
+        switch (value)
+        {
+        case 0: return \"空闲\";
+        case 2: return \"小陀螺\";
+        case 16: return \"\\\"急停\\\"\";
+        default: return {};
+        }
+    }
+} __attribute__((packed));"
+        ));
+
+        let options = GenerateOptions {
+            brace_style: BraceStyle::SameLine,
+            comment_language: Some("en".to_string()),
+            ..Default::default()
+        };
+        let output = generate_with_options(json, &options).unwrap();
+        assert!(output.contains(
+            "mode_name(uint8_t value) {\n        switch (value) {\n        case 0: return \"空闲\";\n        case 2: return \"Spin\";"
+        ));
+        assert!(
+            !generate(
+                r#"{"packet_name":"A","command_id":"1","fields":[{"name":"x","type":"uint8_t"}]}"#
+            )
+            .unwrap()
+            .contains("string_view")
+        );
+        assert_eq!(c_string_literal("a\\b\n\u{7}"), r#""a\\b\n\007""#);
+        assert_eq!(case_literal(u64::MAX.into()), "18446744073709551615ULL");
+    }
 
     #[test]
-    fn test_generate_basic_packet() {
+    fn test_generate_enums() {
         let json = r#"{
-            "packet_name": "BasicPacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": "RPL_BASICPACKET_HPP",
+            "packet_name": "Chassis",
+            "command_id": "0x0102",
+            "namespace": "Robot",
+            "serialize": true,
+            "enums": [{ "name": "Mode", "type": "uint8_t", "comment": "底盘模式", "values": [
+                { "name": "idle", "value": 0, "comment": "空闲" },
+                { "name": "spin", "value": 2 }
+            ] }],
             "fields": [
-                {
-                    "name": "field1",
-                    "type": "uint8_t",
-                    "comment": "First field"
-                },
-                {
-                    "name": "field2",
-                    "type": "float",
-                    "comment": "Second field"
-                }
+                { "name": "mode", "type": "Mode", "comment": "M" },
+                { "name": "history", "type": "Mode[3]", "comment": "H" },
+                { "name": "speed", "type": "int16_t", "comment": "S" }
             ]
         }"#;
+        let output = generate(json).unwrap();
+        assert!(output.contains(
+            "namespace Robot {
+
+/// 底盘模式
+enum class Mode : uint8_t
+{
+    idle = 0, ///< 空闲
+    spin = 2,
+};
+
+/**"
+        ));
+        assert!(output.contains(
+            "    Mode mode; ///< M\n    std::array<Mode, 3> history; ///< H\n    int16_t speed; ///< S\n"
+        ));
+        assert!(output.contains("packet.mode = rplc_wire::load<Mode>(data.data() + 0, false);"));
+        assert!(output.contains("static constexpr size_t size = sizeof(Chassis);"));
+    }
 
-        let result = generate(json).unwrap();
+    #[test]
+    fn test_generate_packet_typed_fields() {
+        let json = r#"{"rplc_schema": 2, "packets": [
+            { "packet_name": "Gimbal", "command_id": "0x0102", "namespace": "Robot", "comment": "G",
+              "fields": [
+                { "name": "mode", "type": "uint8_t", "comment": "M" },
+                { "name": "imu", "type": "Imu[2]", "comment": "I" },
+                { "name": "pose", "type": "Pose", "comment": "P" }
+              ] },
+            { "packet_name": "Imu", "command_id": "0x0101", "namespace": "Robot", "comment": "I",
+              "fields": [{ "name": "pose", "type": "Pose", "comment": "P" }] },
+            { "packet_name": "Pose", "command_id": "0x0103", "comment": "P",
+              "fields": [{ "name": "yaw", "type": "float", "comment": "Y" }] }
+        ]}"#;
+        let options = GenerateOptions {
+            packet_includes: BTreeMap::from([("Imu".to_string(), "robot/Imu.hpp".to_string())]),
+            ..GenerateOptions::default()
+        };
+        let outputs = generate_multiple_with_options(json, &options).unwrap();
+        let gimbal = &outputs[0].1;
+        // 只包含直接引用的包
+        assert!(gimbal.contains(
+            "#include <RPL/Meta/PacketTraits.hpp>\n#include \"robot/Imu.hpp\"\n#include \"Pose.hpp\"\n\n"
+        ));
+        // 同一命名空间中直接使用包名，其他命名空间写出限定名
+        assert!(gimbal.contains("    std::array<Imu, 2> imu; ///< I\n    ::Pose pose; ///< P\n"));
+        // 紧凑布局：1 + 2 × 4 + 4
+        assert!(gimbal.contains("size = 13 bytes"));
+        assert!(!outputs[1].1.contains("Imu.hpp"));
+        assert!(outputs[1].1.contains("#include \"Pose.hpp\"\n"));
+    }
 
-        assert!(result.contains("#ifndef RPL_BASICPACKET_HPP"));
-        assert!(result.contains("#define RPL_BASICPACKET_HPP"));
-        assert!(result.contains("struct BasicPacket"));
-        assert!(result.contains("} __attribute__((packed));"));
-        assert!(result.contains("uint8_t field1; ///< First field"));
-        assert!(result.contains("float field2; ///< Second field"));
-        assert!(result.contains("static constexpr uint16_t cmd = 0x0104;"));
-        assert!(result.contains("static constexpr size_t size = sizeof(BasicPacket)"));
-        assert!(result.contains("#endif // RPL_BASICPACKET_HPP"));
+    #[test]
+    fn test_generate_wire_accessors() {
+        let packet = |layout: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "Status",
+                    "command_id": "0x0101",
+                    {}
+                    "fields": [
+                        {{ "name": "mode", "type": "uint8_t", "bit_field": 3, "comment": "模式" }},
+                        {{ "name": "speed", "type": "int16_t", "bit_field": 12 }},
+                        {{ "name": "on", "type": "bool", "bit_field": 1 }},
+                        {{ "name": "x", "type": "uint8_t" }}
+                    ]
+                }}"#,
+                layout
+            )
+        };
+
+        let msb = generate(&packet(r#""bitfield_layout": "msb_first","#)).unwrap();
+        assert!(msb.contains(
+            "/// Status 的线上字节访问函数，位域按 msb_first 位序逐位读写\nstruct StatusWire\n{\n"
+        ));
+        assert!(msb.contains("value = (value << 1) | ((data[pos / 8] >> (7 - pos % 8)) & 1u);"));
+        assert!(msb.contains("const bool bit = (value >> (bits - 1 - i)) & 1u;"));
+        assert!(msb.contains(
+            "    /// 模式\n    static constexpr uint8_t mode(const uint8_t* data)\n    {\n        return static_cast<uint8_t>(read_bits(data, 0, 3));\n    }"
+        ));
+        assert!(
+            msb.contains("return static_cast<int16_t>(sign_extend(read_bits(data, 3, 12), 12));")
+        );
+        assert!(msb.contains("return read_bits(data, 15, 1) != 0;"));
+        assert!(msb.contains(
+            "static constexpr void set_speed(uint8_t* data, int16_t value)\n    {\n        write_bits(data, 3, 12, static_cast<uint64_t>(value));"
+        ));
+        // 普通字段没有访问函数，Wire 结构体位于特化之前
+        assert!(!msb.contains(" x(const uint8_t* data)"));
+        assert!(msb.find("struct StatusWire").unwrap() < msb.find("template <>").unwrap());
+
+        let lsb = generate(&packet(r#""bitfield_layout": "lsb_first","#)).unwrap();
+        assert!(
+            lsb.contains("value |= static_cast<uint64_t>((data[pos / 8] >> (pos % 8)) & 1u) << i;")
+        );
+        assert!(lsb.contains("const uint8_t mask = static_cast<uint8_t>(1u << (pos % 8));"));
+
+        assert!(!generate(&packet("")).unwrap().contains("Wire"));
+
+        let same_line = GenerateOptions {
+            brace_style: BraceStyle::SameLine,
+            ..Default::default()
+        };
+        let result =
+            generate_with_options(&packet(r#""bitfield_layout": "lsb_first","#), &same_line)
+                .unwrap();
+        assert!(result.contains("struct StatusWire {\n"));
+        assert!(result.contains("for (uint32_t i = 0; i < bits; ++i) {\n"));
     }
 
     #[test]
-    fn test_generate_with_namespace() {
+    fn test_generate_wire_accessors_without_signed_fields() {
         let json = r#"{
-            "packet_name": "NamespacePacket",
-            "command_id": "0xABCD",
-            "namespace": "Robot::Sensors",
-            "packed": true,
-            "header_guard": "RPL_NAMESPACEPACKET_HPP",
+            "packet_name": "Flags",
+            "command_id": "0x0102",
+            "bitfield_layout": "msb_first",
             "fields": [
-                {
-                    "name": "sensor_id",
-                    "type": "uint16_t",
-                    "comment": "Sensor identifier"
-                }
+                { "name": "a", "type": "uint8_t", "bit_field": 4 },
+                { "name": "b", "type": "uint8_t", "bit_field": 4 }
             ]
         }"#;
-
         let result = generate(json).unwrap();
-
-        assert!(result.contains("namespace Robot::Sensors {"));
-        assert!(result.contains("struct NamespacePacket"));
-        assert!(result.contains("} __attribute__((packed))"));
-        assert!(result.contains("uint16_t sensor_id; ///< Sensor identifier"));
-        assert!(result.contains("// namespace Robot::Sensors"));
-        assert!(result.contains("static constexpr uint16_t cmd = 0xABCD;"));
+        assert!(result.contains("static constexpr uint8_t b(const uint8_t* data)"));
+        assert!(!result.contains("sign_extend"));
     }
 
     #[test]
@@ -496,6 +3213,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_generate_canonical_cmd_from_separated_hex() {
+        let json = r#"{
+            "packet_name": "Separated",
+            "command_id": "0X01_0a",
+            "fields": [{ "name": "value", "type": "uint8_t", "comment": "V" }]
+        }"#;
+        let result = generate(json).unwrap();
+        assert!(result.contains("static constexpr uint16_t cmd = 0x010A;"));
+        assert!(result.contains("@details cmd = 0x010A"));
+    }
+
+    #[test]
+    fn test_generate_array_input_is_validation_error() {
+        let json = r#"[{ "packet_name": "A", "command_id": "0x0101", "fields": [] }]"#;
+        assert!(matches!(
+            generate(json),
+            Err(GenerateError::ValidationError)
+        ));
+    }
+
+    #[test]
+    fn test_generate_malformed_inputs_return_errors() {
+        let inputs = [
+            "",
+            r#"{ "packet_name": "P", "command_id": "0x0101", "fields": {} }"#,
+            r#"{ "packet_name": "P", "command_id": 257, "fields": [] }"#,
+            r#"{ "packet_name": "P", "command_id": "0x0101", "align": 0, "fields": [] }"#,
+            r#"{
+                "packet_name": "P",
+                "command_id": "0x0101",
+                "fields": [{ "name": "a", "type": "uint8_t", "bit_field": 300 }]
+            }"#,
+        ];
+        for input in inputs {
+            assert!(generate(input).is_err(), "input = {}", input);
+            assert!(generate_multiple(input).is_err(), "input = {}", input);
+        }
+    }
+
     #[test]
     fn test_generate_with_bit_fields() {
         let json = r#"{
@@ -796,6 +3553,280 @@ mod tests {
         assert!(output.contains("uint8_t flag : 4; ///< Flag field"));
     }
 
+    #[test]
+    fn test_generate_multiple_packet_set() {
+        let json = r#"{
+            "protocol_version": "2.3.0",
+            "packets": [
+                {
+                    "packet_name": "PacketA",
+                    "command_id": "0x0101",
+                    "fields": [
+                        { "name": "field_a", "type": "uint8_t", "comment": "Field A" }
+                    ]
+                },
+                {
+                    "packet_name": "PacketB",
+                    "command_id": "0x0102",
+                    "namespace": "Test::Ns",
+                    "fields": [
+                        { "name": "field_b", "type": "uint16_t", "comment": "Field B" }
+                    ]
+                }
+            ]
+        }"#;
+
+        let results = generate_multiple(json).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "PacketA");
+        assert_eq!(results[1].0, "PacketB");
+
+        let registry = generate_registry(json, &GenerateOptions::default())
+            .unwrap()
+            .unwrap();
+        assert!(registry.contains("#ifndef RPL_PROTOCOL_REGISTRY_HPP"));
+        assert!(registry.contains("#include \"PacketA.hpp\""));
+        assert!(registry.contains("#include \"PacketB.hpp\""));
+        assert!(registry.contains("inline constexpr uint16_t version_major = 2;"));
+        assert!(registry.contains("inline constexpr uint16_t version_minor = 3;"));
+        assert!(registry.contains("inline constexpr uint16_t version_patch = 0;"));
+        assert!(registry.contains("inline constexpr const char* version_string = \"2.3.0\";"));
+        assert!(registry.contains("    PacketA,\n    Test::Ns::PacketB\n>;"));
+    }
+
+    #[test]
+    fn test_generate_multiple_packet_set_invalid_version() {
+        let json = r#"{
+            "protocol_version": "two",
+            "packets": [
+                {
+                    "packet_name": "PacketA",
+                    "command_id": "0x0101",
+                    "fields": [
+                        { "name": "field_a", "type": "uint8_t", "comment": "Field A" }
+                    ]
+                }
+            ]
+        }"#;
+
+        assert!(matches!(
+            generate_multiple(json),
+            Err(MultiGenerateError::ValidationError)
+        ));
+        assert!(generate_registry(json, &GenerateOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_generate_compat_shims_for_appended_field() {
+        let old = r#"{
+            "packet_name": "SensorData",
+            "command_id": "0x0104",
+            "namespace": "Robot",
+            "fields": [
+                { "name": "sensor_id", "type": "uint8_t", "comment": "传感器ID" },
+                { "name": "values", "type": "float[2]", "comment": "读数" }
+            ]
+        }"#;
+        let new = r#"{
+            "packet_name": "SensorData",
+            "command_id": "0x0104",
+            "namespace": "Robot",
+            "fields": [
+                { "name": "sensor_id", "type": "uint8_t", "comment": "传感器ID" },
+                { "name": "values", "type": "float[2]", "comment": "读数" },
+                { "name": "humidity", "type": "float", "comment": "湿度" }
+            ]
+        }"#;
+
+        let shims = generate_compat_shims(old, new, &GenerateOptions::default()).unwrap();
+        assert_eq!(shims.len(), 1);
+        let (name, shim) = &shims[0];
+        assert_eq!(name, "SensorData");
+        assert!(shim.contains("#ifndef RPL_SENSORDATA_COMPAT_HPP"));
+        assert!(shim.contains("#include \"SensorData.hpp\""));
+        assert!(shim.contains("namespace Robot {"));
+        assert!(shim.contains("struct SensorDataPrevious\n{"));
+        assert!(shim.contains("std::array<float, 2> values; ///< 读数"));
+        assert!(!shim.contains("float humidity;"));
+        assert!(shim.contains("inline SensorData upgrade(const SensorDataPrevious& previous)"));
+        assert!(shim.contains("    SensorData current{};\n"));
+        assert!(shim.contains("    current.values = previous.values;\n"));
+        assert!(!shim.contains("current.humidity"));
+        assert!(shim.contains("inline SensorDataPrevious downgrade(const SensorData& current)"));
+        assert!(shim.contains("    previous.sensor_id = current.sensor_id;\n"));
+    }
+
+    #[test]
+    fn test_generate_compat_shims_skips_breaking_and_unchanged() {
+        let old = r#"[
+            {
+                "packet_name": "Unchanged",
+                "command_id": "0x0101",
+                "fields": [ { "name": "a", "type": "uint8_t", "comment": "A" } ]
+            },
+            {
+                "packet_name": "Broken",
+                "command_id": "0x0102",
+                "fields": [ { "name": "a", "type": "uint8_t", "comment": "A" } ]
+            }
+        ]"#;
+        let new = r#"[
+            {
+                "packet_name": "Unchanged",
+                "command_id": "0x0101",
+                "fields": [ { "name": "a", "type": "uint8_t", "comment": "A" } ]
+            },
+            {
+                "packet_name": "Broken",
+                "command_id": "0x0102",
+                "fields": [
+                    { "name": "b", "type": "uint8_t", "comment": "B" },
+                    { "name": "a", "type": "uint8_t", "comment": "A" }
+                ]
+            }
+        ]"#;
+
+        assert!(
+            generate_compat_shims(old, new, &GenerateOptions::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_generate_integer_type_includes() {
+        let json = r#"{
+            "packet_name": "Status",
+            "command_id": "0x0102",
+            "includes": ["my_types.h", "<stdint.h>"],
+            "fields": [{ "name": "mode", "type": "uint8_t", "comment": "M" }]
+        }"#;
+        let freestanding = GenerateOptions {
+            freestanding: true,
+            ..Default::default()
+        };
+        for options in [GenerateOptions::default(), freestanding.clone()] {
+            let result = generate_with_options(json, &options).unwrap();
+            assert!(result.contains(
+                "#define RPL_STATUS_HPP\n\n#include \"my_types.h\"\n#include <stdint.h>\n#include <RPL/Meta/PacketTraits.hpp>\n"
+            ));
+            assert!(!result.contains("<cstdint>"));
+            assert!(!result.contains("typedef"));
+        }
+
+        // 没有声明 includes 时写出后备 typedef
+        let plain = json.replace(r#""includes": ["my_types.h", "<stdint.h>"],"#, "");
+        let result = generate_with_options(&plain, &freestanding).unwrap();
+        assert!(!result.contains("<cstdint>"));
+        assert!(result.contains(
+            "#include <RPL/Meta/PacketTraits.hpp>\n\n\
+             #if !defined(RPLC_FIXED_WIDTH_TYPES) && !defined(INT8_MAX)\n\
+             #define RPLC_FIXED_WIDTH_TYPES\n"
+        ));
+        assert!(result.contains("typedef unsigned char uint8_t;\n"));
+        // 关闭 traits 的标量包不包含任何头文件；数组字段仍依赖 <array>
+        let bare = plain.replace(r#""fields""#, r#""emit_traits": false, "fields""#);
+        let result = generate_with_options(&bare, &freestanding).unwrap();
+        assert!(!result.contains("#include"));
+        let array = bare.replace(r#""uint8_t""#, r#""uint8_t[4]""#);
+        let result = generate_with_options(&array, &freestanding).unwrap();
+        assert!(result.contains("#define RPL_STATUS_HPP\n\n#include <array>\n\n#if "));
+        assert!(!result.contains("RPL/Meta"));
+        assert!(result.contains("static_assert(sizeof(int32_t) == 4, "));
+        assert!(result.contains("#endif // RPLC_FIXED_WIDTH_TYPES\n\n"));
+        assert!(
+            generate(&plain)
+                .unwrap()
+                .contains("#define RPL_STATUS_HPP\n\n#include <cstdint>\n")
+        );
+
+        // 注册表合并各包的 includes
+        let set = format!(
+            r#"{{ "protocol_version": "1.0.0", "packets": [{}, {}] }}"#,
+            json,
+            plain.replace("Status", "Other").replace("0x0102", "0x0103")
+        );
+        let registry = generate_registry(&set, &GenerateOptions::default())
+            .unwrap()
+            .unwrap();
+        assert!(
+            registry.contains("#include \"my_types.h\"\n#include <stdint.h>\n#include <tuple>\n")
+        );
+        assert!(!registry.contains("<cstdint>"));
+    }
+
+    #[test]
+    fn test_generate_registry_without_version() {
+        let json = r#"[
+            { "packet_name": "PacketA", "command_id": "0x0101", "fields": [] }
+        ]"#;
+
+        assert!(
+            generate_registry(json, &GenerateOptions::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_generate_umbrella() {
+        let json = r#"[
+            { "packet_name": "PacketA", "command_id": "0x0101", "namespace": "Robot::Chassis", "fields": [] },
+            { "packet_name": "PacketB", "command_id": "0x0102", "fields": [] }
+        ]"#;
+        let options = GenerateOptions {
+            namespace_alias: Some("chassis".to_string()),
+            ..GenerateOptions::default()
+        };
+
+        assert!(
+            generate_umbrella(json, &GenerateOptions::default())
+                .unwrap()
+                .is_none()
+        );
+        let umbrella = generate_umbrella(json, &options).unwrap().unwrap();
+        assert_eq!(
+            umbrella,
+            "#ifndef RPL_CHASSIS_HPP\n#define RPL_CHASSIS_HPP\n\n\
+             #include \"PacketA.hpp\"\n#include \"PacketB.hpp\"\n\n\
+             namespace chassis = Robot::Chassis;\n\n#endif // RPL_CHASSIS_HPP\n"
+        );
+
+        // 按输出布局记录的路径包含各包的头文件
+        let placed = GenerateOptions {
+            packet_includes: [(
+                "PacketA".to_string(),
+                "Robot/Chassis/PacketA.hpp".to_string(),
+            )]
+            .into_iter()
+            .collect(),
+            ..options.clone()
+        };
+        let umbrella = generate_umbrella(json, &placed).unwrap().unwrap();
+        assert!(
+            umbrella.contains("#include \"Robot/Chassis/PacketA.hpp\"\n#include \"PacketB.hpp\"\n")
+        );
+        let set = format!(r#"{{ "protocol_version": "1.0.0", "packets": {} }}"#, json);
+        let registry = generate_registry(&set, &placed).unwrap().unwrap();
+        assert!(registry.contains("#include \"Robot/Chassis/PacketA.hpp\"\n"));
+
+        // 同一个别名不能指向两个命名空间
+        let conflicting = json.replace(
+            r#""packet_name": "PacketB", "#,
+            r#""packet_name": "PacketB", "namespace": "Robot::Gimbal", "#,
+        );
+        for result in [
+            generate_umbrella(&conflicting, &options).map(|_| ()),
+            generate_multiple_with_options(&conflicting, &options).map(|_| ()),
+        ] {
+            assert!(matches!(
+                result,
+                Err(MultiGenerateError::NamespaceAliasConflict(alias, a, b))
+                    if alias == "chassis" && a == "Robot::Chassis" && b == "Robot::Gimbal"
+            ));
+        }
+    }
+
     #[test]
     fn test_generate_multiple_backwards_compatibility() {
         // Test that single packet still works with generate_multiple
@@ -860,6 +3891,12 @@ mod tests {
         assert!(result.contains("std::array<uint8_t, 8> data; ///< 数据数组"));
         assert!(result.contains("uint16_t single_field; ///< 单值字段"));
         assert!(result.contains("struct ArrayPacket"));
+
+        let with_len = json.replace(
+            r#""type": "uint8_t[8]","#,
+            r#""type": "uint8_t", "array_len": 8,"#,
+        );
+        assert_eq!(generate(&with_len).unwrap(), result);
     }
 
     #[test]
@@ -1034,19 +4071,19 @@ mod tests {
 
         // 检查 std::array 格式的结构体字段
         assert!(result.contains("std::array<uint8_t, 3> figure_name; ///< 图形名称"));
-        
+
         // 检查 BitLayout 生成
         assert!(result.contains("using BitLayout = std::tuple<"));
-        
+
         // 检查数组字段的 Field<std::array<T, N>, bits> 格式
         assert!(result.contains("Field<std::array<uint8_t, 3>, 24>"));
-        
+
         // 检查位域字段
         assert!(result.contains("Field<uint32_t, 3>"));
-        
+
         // 检查普通字段
         assert!(result.contains("Field<uint8_t, 8>"));
-        
+
         // 检查 size 计算：24 + 3 + 8 = 35 bits，向上取整为 5 bytes
         assert!(result.contains("static constexpr size_t size = 5;"));
     }
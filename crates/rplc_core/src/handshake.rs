@@ -0,0 +1,341 @@
+//! 连接建立时交换的协议握手包 `ProtocolInfo`
+//!
+//! 握手包列出本端每个包的命令 ID、大小与布局哈希，双方比较后即可在连接时发现协议不一致，
+//! 而不是在收到错位的数据后才发现
+
+use crate::config::{Config, ConfigOrArray, Field, ProtocolVersion};
+use crate::diagnostics::Severity;
+use crate::fingerprint::{format_layout_hash, layout_hash};
+use crate::generator::{
+    BraceStyle, GenerateOptions, MultiGenerateError, push_fallback_typedefs, push_integer_includes,
+    push_struct, shared_includes,
+};
+use crate::layout::compute_layout;
+use crate::validator::{format_command_id, parse_command_id, validate_multiple};
+
+const GUARD: &str = "RPL_PROTOCOL_INFO_HPP";
+const NAMESPACE: &str = "RPL::Protocol";
+
+/// `PacketInfo` 的大小：cmd (2) + size (4) + layout_hash (4)
+const PACKET_INFO_SIZE: usize = 10;
+/// `ProtocolInfo` 中包列表之前的字段大小：三个版本号与 packet_count，各 2 字节
+const PROTOCOL_INFO_HEADER_SIZE: usize = 8;
+
+/// 为一组包生成握手包头文件，握手包使用 `command_id` 作为命令 ID
+///
+/// 版本号取自包集合的 `protocol_version`，未声明时为 0.0.0；
+/// 握手包的命令 ID 无效或与已有的包相同时返回错误
+pub fn generate_protocol_info(
+    json_input: &str,
+    command_id: &str,
+    options: &GenerateOptions,
+) -> Result<String, MultiGenerateError> {
+    if validate_multiple(json_input)
+        .iter()
+        .any(|d| d.severity == Severity::Error)
+    {
+        return Err(MultiGenerateError::ValidationError);
+    }
+    let cmd_id = parse_command_id(command_id)
+        .map_err(|_| MultiGenerateError::InvalidHandshakeCommand(command_id.to_string()))?;
+
+    let parsed: ConfigOrArray = serde_json::from_str(json_input)?;
+    let version = match parsed.protocol_version() {
+        Some(version) => version
+            .parse()
+            .map_err(|_| MultiGenerateError::ValidationError)?,
+        None => ProtocolVersion {
+            major: 0,
+            minor: 0,
+            patch: 0,
+        },
+    };
+
+    let packets = parsed.into_packets();
+    let includes = shared_includes(&packets);
+    let mut entries = Vec::new();
+    for packet in packets {
+        let cmd = parse_command_id(&packet.command_id)
+            .map_err(|_| MultiGenerateError::ValidationError)?;
+        if cmd == cmd_id {
+            return Err(MultiGenerateError::HandshakeCommandConflict(
+                format_command_id(cmd_id),
+                packet.packet_name,
+            ));
+        }
+        let layout = compute_layout(&packet).ok_or(MultiGenerateError::ValidationError)?;
+        entries.push((cmd, layout.size, layout_hash(&packet)));
+    }
+
+    let info = protocol_info_config(cmd_id, entries.len());
+    let size = PROTOCOL_INFO_HEADER_SIZE + PACKET_INFO_SIZE * entries.len();
+
+    let mut out = String::new();
+    out.push_str(&format!("#ifndef {}\n", GUARD));
+    out.push_str(&format!("#define {}\n\n", GUARD));
+    push_integer_includes(&mut out, includes.as_deref(), "cstdint", options);
+    out.push_str("#include <array>\n");
+    out.push_str("#include <RPL/Meta/PacketTraits.hpp>\n\n");
+    push_fallback_typedefs(&mut out, includes.as_deref(), "static_assert", options);
+    out.push_str(&format!("{}\n\n", options.namespace_open(NAMESPACE)));
+
+    push_struct(
+        &mut out,
+        &packet_info_config(),
+        "PacketInfo",
+        "/**\n * @brief 单个包的握手信息\n */\n",
+        options,
+    );
+    let doc = format!(
+        "/**\n * @brief 协议握手包，连接建立时双方互相发送\n * @details cmd = {}, size = {} bytes\n */\n",
+        format_command_id(cmd_id),
+        size
+    );
+    push_struct(&mut out, &info, "ProtocolInfo", &doc, options);
+    push_local_info(&mut out, &version, &entries, options);
+    push_helpers(&mut out, options);
+    out.push_str(&format!("{}\n\n", options.namespace_close(NAMESPACE)));
+
+    // 特化需要位于 RPL::Meta 的外层命名空间中，因此放在命名空间之外
+    let indent = options.indent(1);
+    out.push_str(&doc);
+    out.push_str("template <>\n");
+    out.push_str(&format!(
+        "struct RPL::Meta::PacketTraits<{ns}::ProtocolInfo> : PacketTraitsBase<PacketTraits<{ns}::ProtocolInfo>>{}",
+        options.open_brace(),
+        ns = NAMESPACE
+    ));
+    out.push_str(&format!(
+        "{}static constexpr uint16_t cmd = {};\n",
+        indent,
+        format_command_id(cmd_id)
+    ));
+    out.push_str(&format!(
+        "{}static constexpr size_t size = sizeof({}::ProtocolInfo);\n",
+        indent, NAMESPACE
+    ));
+    out.push_str(&format!(
+        "{}static constexpr uint32_t layout_hash = {};\n",
+        indent,
+        format_layout_hash(layout_hash(&info))
+    ));
+    out.push_str("};\n\n");
+
+    out.push_str(&format!("#endif // {}\n", GUARD));
+    Ok(out)
+}
+
+fn field(name: &str, ty: &str, comment: &str) -> Field {
+    Field {
+        name: name.to_string(),
+        ty: ty.to_string(),
+        comment: Some(comment.into()),
+        ..Field::default()
+    }
+}
+
+fn packet_info_config() -> Config {
+    Config {
+        packet_name: "PacketInfo".to_string(),
+        fields: vec![
+            field("cmd", "uint16_t", "命令 ID"),
+            field("size", "uint32_t", "包大小（字节）"),
+            field("layout_hash", "uint32_t", "布局哈希"),
+        ],
+        ..Config::default()
+    }
+}
+
+/// 握手包本身的定义，其布局哈希随包的数量变化
+fn protocol_info_config(cmd_id: u16, packet_count: usize) -> Config {
+    Config {
+        packet_name: "ProtocolInfo".to_string(),
+        command_id: format_command_id(cmd_id),
+        fields: vec![
+            field("version_major", "uint16_t", "协议主版本号"),
+            field("version_minor", "uint16_t", "协议次版本号"),
+            field("version_patch", "uint16_t", "协议修订号"),
+            field("packet_count", "uint16_t", "packets 中有效的项数"),
+            field(
+                "packets",
+                &format!("PacketInfo[{}]", packet_count),
+                "每个包的握手信息",
+            ),
+        ],
+        ..Config::default()
+    }
+}
+
+fn push_local_info(
+    out: &mut String,
+    version: &ProtocolVersion,
+    entries: &[(u16, u32, u32)],
+    options: &GenerateOptions,
+) {
+    let i1 = options.indent(1);
+    let i2 = options.indent(2);
+    out.push_str("/// 本端的协议信息，握手时发送给对端\n");
+    out.push_str(&format!(
+        "{} ProtocolInfo local_protocol_info{{\n",
+        options.constexpr_variable()
+    ));
+    out.push_str(&format!(
+        "{i1}{}, {}, {}, {},\n",
+        version.major,
+        version.minor,
+        version.patch,
+        entries.len()
+    ));
+    out.push_str(&format!("{i1}{{{{\n"));
+    for (cmd, size, hash) in entries {
+        out.push_str(&format!(
+            "{i2}PacketInfo{{{}, {}, {}}},\n",
+            format_command_id(*cmd),
+            size,
+            format_layout_hash(*hash)
+        ));
+    }
+    out.push_str(&format!("{i1}}}}}\n}};\n\n"));
+}
+
+fn push_helpers(out: &mut String, options: &GenerateOptions) {
+    let i1 = options.indent(1);
+    let i2 = options.indent(2);
+    let i3 = options.indent(3);
+    let brace = options.open_brace();
+    let nested = |indent: &str| match options.brace_style {
+        BraceStyle::NextLine => format!("\n{indent}{{\n"),
+        BraceStyle::SameLine => " {\n".to_string(),
+    };
+    let b1 = nested(&i1);
+    let b2 = nested(&i2);
+
+    out.push_str("/// 本端的包与对端声明的比较结果\n");
+    out.push_str(&format!("enum class PacketStatus : uint8_t{brace}"));
+    out.push_str(&format!("{i1}Match,          ///< 大小与布局哈希都一致\n"));
+    out.push_str(&format!(
+        "{i1}Missing,        ///< 本端或对端没有声明该命令 ID\n"
+    ));
+    out.push_str(&format!("{i1}SizeMismatch,   ///< 大小不一致\n"));
+    out.push_str(&format!(
+        "{i1}LayoutMismatch, ///< 大小一致但布局哈希不一致\n"
+    ));
+    out.push_str("};\n\n");
+
+    out.push_str("/// 在协议信息中查找命令 ID 为 cmd 的包，不存在时返回 nullptr\n");
+    out.push_str(&format!(
+        "inline constexpr const PacketInfo* find_packet(const ProtocolInfo& info, uint16_t cmd){brace}"
+    ));
+    out.push_str(&format!(
+        "{i1}for (uint16_t i = 0; i < info.packet_count && i < info.packets.size(); ++i){b1}"
+    ));
+    out.push_str(&format!("{i2}if (info.packets[i].cmd == cmd){b2}"));
+    out.push_str(&format!("{i3}return &info.packets[i];\n{i2}}}\n{i1}}}\n"));
+    out.push_str(&format!("{i1}return nullptr;\n}}\n\n"));
+
+    out.push_str("/// 比较本端与对端命令 ID 为 cmd 的包\n");
+    out.push_str(&format!(
+        "inline constexpr PacketStatus compare_packet(const ProtocolInfo& peer, uint16_t cmd){brace}"
+    ));
+    out.push_str(&format!(
+        "{i1}const PacketInfo* ours = find_packet(local_protocol_info, cmd);\n"
+    ));
+    out.push_str(&format!(
+        "{i1}const PacketInfo* theirs = find_packet(peer, cmd);\n"
+    ));
+    out.push_str(&format!(
+        "{i1}if (ours == nullptr || theirs == nullptr){b1}{i2}return PacketStatus::Missing;\n{i1}}}\n"
+    ));
+    out.push_str(&format!(
+        "{i1}if (ours->size != theirs->size){b1}{i2}return PacketStatus::SizeMismatch;\n{i1}}}\n"
+    ));
+    out.push_str(&format!(
+        "{i1}if (ours->layout_hash != theirs->layout_hash){b1}{i2}return PacketStatus::LayoutMismatch;\n{i1}}}\n"
+    ));
+    out.push_str(&format!("{i1}return PacketStatus::Match;\n}}\n\n"));
+
+    out.push_str("/// 对端的主版本号与本端相同，且本端的每个包都与对端的声明一致\n");
+    out.push_str(&format!(
+        "inline constexpr bool matches(const ProtocolInfo& peer){brace}"
+    ));
+    out.push_str(&format!(
+        "{i1}if (peer.version_major != local_protocol_info.version_major){b1}{i2}return false;\n{i1}}}\n"
+    ));
+    out.push_str(&format!(
+        "{i1}for (uint16_t i = 0; i < local_protocol_info.packet_count; ++i){b1}"
+    ));
+    out.push_str(&format!(
+        "{i2}if (compare_packet(peer, local_protocol_info.packets[i].cmd) != PacketStatus::Match){b2}"
+    ));
+    out.push_str(&format!("{i3}return false;\n{i2}}}\n{i1}}}\n"));
+    out.push_str(&format!("{i1}return true;\n}}\n\n"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SET: &str = r#"{
+        "protocol_version": "2.3.0",
+        "packets": [
+            { "packet_name": "Status", "command_id": "0x0101",
+              "fields": [{ "name": "mode", "type": "uint8_t" }, { "name": "speed", "type": "float" }] },
+            { "packet_name": "Flags", "command_id": "0x0102",
+              "fields": [{ "name": "a", "type": "uint8_t", "bit_field": 3 },
+                         { "name": "b", "type": "uint8_t", "bit_field": 7 }] }
+        ]
+    }"#;
+
+    #[test]
+    fn test_protocol_info_lists_packets() {
+        let output = generate_protocol_info(SET, "0x00FF", &GenerateOptions::default()).unwrap();
+        let packets: Vec<Config> = serde_json::from_str::<ConfigOrArray>(SET)
+            .unwrap()
+            .into_packets();
+
+        assert!(output.starts_with("#ifndef RPL_PROTOCOL_INFO_HPP\n"));
+        assert!(output.contains("    std::array<PacketInfo, 2> packets; ///< 每个包的握手信息\n"));
+        assert!(output.contains(" * @details cmd = 0x00FF, size = 28 bytes\n"));
+        assert!(output.contains("    2, 3, 0, 2,\n"));
+        // 位域包的大小按位流计算
+        assert!(output.contains(&format!(
+            "        PacketInfo{{0x0101, 5, {}}},\n        PacketInfo{{0x0102, 2, {}}},\n",
+            format_layout_hash(layout_hash(&packets[0])),
+            format_layout_hash(layout_hash(&packets[1]))
+        )));
+        assert!(output.contains(
+            "struct RPL::Meta::PacketTraits<RPL::Protocol::ProtocolInfo> : PacketTraitsBase<PacketTraits<RPL::Protocol::ProtocolInfo>>\n{\n    static constexpr uint16_t cmd = 0x00FF;\n"
+        ));
+        assert!(output.contains("inline constexpr bool matches(const ProtocolInfo& peer)\n{\n"));
+        assert!(output.ends_with("#endif // RPL_PROTOCOL_INFO_HPP\n"));
+    }
+
+    #[test]
+    fn test_protocol_info_same_line_braces() {
+        let options = GenerateOptions {
+            brace_style: BraceStyle::SameLine,
+            ..GenerateOptions::default()
+        };
+        let output = generate_protocol_info(SET, "255", &options).unwrap();
+        assert!(output.contains("    for (uint16_t i = 0; i < info.packet_count && i < info.packets.size(); ++i) {\n        if (info.packets[i].cmd == cmd) {\n"));
+        assert!(!output.contains("\n    {\n"));
+    }
+
+    #[test]
+    fn test_protocol_info_command_errors() {
+        let options = GenerateOptions::default();
+        assert!(matches!(
+            generate_protocol_info(SET, "0x0102", &options),
+            Err(MultiGenerateError::HandshakeCommandConflict(cmd, name))
+                if cmd == "0x0102" && name == "Flags"
+        ));
+        assert!(matches!(
+            generate_protocol_info(SET, "handshake", &options),
+            Err(MultiGenerateError::InvalidHandshakeCommand(_))
+        ));
+        assert!(matches!(
+            generate_protocol_info(&SET.replace("float", "real"), "0x00FF", &options),
+            Err(MultiGenerateError::ValidationError)
+        ));
+    }
+}
@@ -0,0 +1,1239 @@
+//! 从手写的 C 头文件导入包定义
+//!
+//! 旧的协议头文件中的 packed 结构体用一个轻量的解析器转换为 rplc 定义：只识别结构体、`typedef`、
+//! `#define` 与 `#pragma pack`，不展开其他宏，也不处理条件编译。无法表示的结构体被跳过并给出说明
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::c_backend::upper_snake;
+use crate::config::CURRENT_SCHEMA;
+use crate::scaffold::{INITIAL_PROTOCOL_VERSION, ScaffoldError, to_pretty_json};
+use crate::table_import::ImportError;
+use crate::validator::{c_type_to_bit_field_size, format_command_id, parse_command_id};
+
+/// 嵌入式代码中常见的类型别名，头文件中没有给出 `typedef` 时按此解析
+const LEGACY_TYPES: &[(&str, &str)] = &[
+    ("u8", "uint8_t"),
+    ("u16", "uint16_t"),
+    ("u32", "uint32_t"),
+    ("u64", "uint64_t"),
+    ("s8", "int8_t"),
+    ("s16", "int16_t"),
+    ("s32", "int32_t"),
+    ("s64", "int64_t"),
+    ("i8", "int8_t"),
+    ("i16", "int16_t"),
+    ("i32", "int32_t"),
+    ("i64", "int64_t"),
+    ("uint8", "uint8_t"),
+    ("uint16", "uint16_t"),
+    ("uint32", "uint32_t"),
+    ("uint64", "uint64_t"),
+    ("int8", "int8_t"),
+    ("int16", "int16_t"),
+    ("int32", "int32_t"),
+    ("int64", "int64_t"),
+    ("fp32", "float"),
+    ("fp64", "double"),
+    ("f32", "float"),
+    ("f64", "double"),
+];
+
+/// 导入选项
+#[derive(Debug, Clone)]
+pub struct HeaderImport {
+    /// 找不到命令 ID 宏的结构体从该 ID 开始依次编号，跳过已被宏占用的 ID
+    pub first_command_id: String,
+}
+
+impl Default for HeaderImport {
+    fn default() -> Self {
+        Self {
+            first_command_id: "0x0001".to_string(),
+        }
+    }
+}
+
+/// 导入结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedHeader {
+    /// 只有一个结构体时为单个包，否则为包集合
+    pub json: String,
+    /// 导入的包名
+    pub packets: Vec<String>,
+    /// 跳过的结构体、自动分配的命令 ID 等需要人工确认的地方
+    pub notes: Vec<String>,
+}
+
+/// 将头文件中的结构体转换为包定义 JSON
+///
+/// - `struct __attribute__((packed))`、`__packed struct` 与 `#pragma pack(1)` 中的结构体为 packed，其余的写出 `"packed": false`
+/// - 包名取 `typedef` 的名称（去掉 `_t` 后缀）或结构体标签
+/// - 命令 ID 取 `<包名>_CMD`、`<包名>_CMD_ID` 或 `<包名>_ID` 宏，包名可以写成 `GIMBAL_COMMAND` 或 `GIMBALCOMMAND`
+/// - 成员行尾的注释或成员之前的注释成为字段注释，结构体之前的注释成为包注释；`// ---- 标题 ----` 成为分组标题
+pub fn import_header(source: &str, options: &HeaderImport) -> Result<ImportedHeader, ImportError> {
+    let first_command_id = parse_command_id(&options.first_command_id).map_err(|_| {
+        ScaffoldError::InvalidCommandId(options.first_command_id.trim().to_string())
+    })?;
+    let tokens = tokenize(source.trim_start_matches('\u{feff}'))?;
+    let mut parser = Parser::new(tokens);
+    parser.parse();
+
+    let Parser {
+        structs,
+        defines,
+        mut notes,
+        ..
+    } = parser;
+    let mut packets = Vec::new();
+    for parsed in structs {
+        match parsed.skipped {
+            _ if parsed.name.is_empty() => notes.push("已跳过没有名称的结构体".to_string()),
+            Some(reason) => notes.push(format!("已跳过结构体 '{}'：{}", parsed.name, reason)),
+            None => packets.push(parsed),
+        }
+    }
+    if packets.is_empty() {
+        return Err(ImportError::NoStructs);
+    }
+
+    // 先取宏中的命令 ID，剩下的包依次编号
+    let command_ids: Vec<Option<u16>> = packets
+        .iter()
+        .map(|packet| command_id_macro(&packet.name, &defines))
+        .collect();
+    let mut next_id = Some(first_command_id);
+    let mut imported = Vec::new();
+    for (packet, command_id) in packets.into_iter().zip(&command_ids) {
+        let command_id = match command_id {
+            Some(id) => *id,
+            None => {
+                while next_id.is_some_and(|id| command_ids.contains(&Some(id))) {
+                    next_id = next_id.and_then(|id| id.checked_add(1));
+                }
+                let id = next_id.ok_or_else(|| {
+                    ScaffoldError::InvalidCommandId(options.first_command_id.clone())
+                })?;
+                next_id = id.checked_add(1);
+                notes.push(format!(
+                    "结构体 '{}' 没有对应的命令 ID 宏，已分配 {}",
+                    packet.name,
+                    format_command_id(id)
+                ));
+                id
+            }
+        };
+        for field in &packet.fields {
+            if let Entry::Field(field) = field
+                && !is_supported_type(&field.ty)
+            {
+                notes.push(format!(
+                    "字段 '{}.{}' 的类型 '{}' 无法识别，已原样保留",
+                    packet.name, field.name, field.ty
+                ));
+            }
+        }
+        imported.push(ImportedPacket {
+            rplc_schema: None,
+            packet_name: packet.name,
+            command_id: format_command_id(command_id),
+            packed: (!packet.packed).then_some(false),
+            align: packet.align,
+            comment: packet.comment,
+            fields: packet.fields,
+        });
+    }
+
+    let names = imported.iter().map(|p| p.packet_name.clone()).collect();
+    let json = if imported.len() == 1 {
+        let mut packet = imported.remove(0);
+        packet.rplc_schema = Some(CURRENT_SCHEMA);
+        to_pretty_json(&packet)
+    } else {
+        to_pretty_json(&ImportedSet {
+            rplc_schema: CURRENT_SCHEMA,
+            protocol_version: INITIAL_PROTOCOL_VERSION,
+            packets: imported,
+        })
+    };
+    Ok(ImportedHeader {
+        json,
+        packets: names,
+        notes,
+    })
+}
+
+#[derive(Serialize)]
+struct ImportedSet {
+    rplc_schema: u32,
+    protocol_version: &'static str,
+    packets: Vec<ImportedPacket>,
+}
+
+#[derive(Serialize)]
+struct ImportedPacket {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rplc_schema: Option<u32>,
+    packet_name: String,
+    command_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    packed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    align: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    fields: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum Entry {
+    Section { section: String },
+    Field(ImportedField),
+}
+
+#[derive(Debug, Serialize)]
+struct ImportedField {
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bit_field: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Debug)]
+struct ParsedStruct {
+    name: String,
+    packed: bool,
+    align: Option<u32>,
+    comment: Option<String>,
+    fields: Vec<Entry>,
+    /// 无法导入的原因
+    skipped: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(String),
+    /// 字符串与字符字面量，只在 `extern "C"` 中出现
+    Literal,
+    Punct(char),
+    Pragma(Pack),
+    Define(String, String),
+    /// 其他预处理指令
+    Directive,
+}
+
+/// `#pragma pack` 与 ARMCC 的 `#pragma push` / `#pragma pop`
+#[derive(Debug, Clone, PartialEq)]
+enum Pack {
+    Push(Option<u32>),
+    Pop,
+    Set(Option<u32>),
+}
+
+#[derive(Debug)]
+struct Spanned {
+    token: Token,
+    line: usize,
+}
+
+#[derive(Debug)]
+struct Comment {
+    text: String,
+    line: usize,
+    /// 之前的代码记号数，即注释之后第一个记号的下标
+    before: usize,
+    /// `///<` 与 `/**<` 形式，总是属于之前的记号
+    trailing: bool,
+}
+
+struct Tokens {
+    code: Vec<Spanned>,
+    comments: Vec<Comment>,
+}
+
+fn tokenize(source: &str) -> Result<Tokens, ImportError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut code = Vec::new();
+    let mut comments = Vec::new();
+    let mut line = 1;
+    let mut line_start = true;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '\n' {
+            line += 1;
+            line_start = true;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '/' && next == Some('/') {
+            let end = chars[i..]
+                .iter()
+                .position(|&c| c == '\n')
+                .map_or(chars.len(), |n| i + n);
+            let raw: String = chars[i + 2..end].iter().collect();
+            comments.push(comment(&raw, line, code.len()));
+            i = end;
+            continue;
+        }
+        if c == '/' && next == Some('*') {
+            let end = (i + 2..chars.len().saturating_sub(1))
+                .find(|&j| chars[j] == '*' && chars[j + 1] == '/')
+                .ok_or(ImportError::UnterminatedComment(line))?;
+            let raw: String = chars[i + 2..end].iter().collect();
+            comments.push(comment(&raw, line, code.len()));
+            line += raw.matches('\n').count();
+            i = end + 2;
+            continue;
+        }
+        if c == '#' && line_start {
+            // 预处理指令可以用反斜杠续行
+            let mut directive = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != '\n' {
+                if chars[i] == '\\' && chars.get(i + 1) == Some(&'\n') {
+                    line += 1;
+                    i += 2;
+                    continue;
+                }
+                directive.push(chars[i]);
+                i += 1;
+            }
+            code.push(Spanned {
+                token: directive_token(&directive),
+                line,
+            });
+            continue;
+        }
+        line_start = false;
+        let start = i;
+        let token = if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            Token::Word(chars[start..i].iter().collect())
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            Token::Number(chars[start..i].iter().collect())
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != c && chars[i] != '\n' {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i += 1;
+            Token::Literal
+        } else {
+            i += 1;
+            Token::Punct(c)
+        };
+        code.push(Spanned { token, line });
+    }
+    Ok(Tokens { code, comments })
+}
+
+fn comment(raw: &str, line: usize, before: usize) -> Comment {
+    let trailing = raw.starts_with("/<") || raw.starts_with("*<") || raw.starts_with("!<");
+    let raw = raw.trim_start_matches(['/', '*', '!', '<']);
+    // 去掉块注释每行开头的星号，丢弃 Doxygen 标签行，只保留 @brief 的内容
+    let mut lines = Vec::new();
+    for text in raw.lines() {
+        let text = text.trim().trim_start_matches('*').trim();
+        if let Some(brief) = text
+            .strip_prefix("@brief")
+            .or_else(|| text.strip_prefix("\\brief"))
+        {
+            lines.push(brief.trim());
+        } else if text.chars().any(char::is_alphanumeric)
+            && !text.starts_with('@')
+            && !text.starts_with('\\')
+        {
+            lines.push(text);
+        }
+    }
+    Comment {
+        text: lines.join(" "),
+        line,
+        before,
+        trailing,
+    }
+}
+
+fn directive_token(directive: &str) -> Token {
+    // 指令行尾的注释不属于宏的值
+    let directive = directive.split("//").next().unwrap_or_default();
+    let directive = directive.split("/*").next().unwrap_or_default().trim();
+    define_token(directive)
+        .or_else(|| pragma_token(directive))
+        .unwrap_or(Token::Directive)
+}
+
+fn define_token(directive: &str) -> Option<Token> {
+    let rest = directive.strip_prefix("define")?;
+    let mut parts = rest.trim().splitn(2, char::is_whitespace);
+    let name = parts.next()?;
+    // 带参数的宏不是常量
+    if name.is_empty() || name.contains('(') {
+        return None;
+    }
+    Some(Token::Define(
+        name.to_string(),
+        parts.next().unwrap_or_default().trim().to_string(),
+    ))
+}
+
+fn pragma_token(directive: &str) -> Option<Token> {
+    let pragma = directive.strip_prefix("pragma")?.trim();
+    match pragma {
+        "push" => return Some(Token::Pragma(Pack::Push(None))),
+        "pop" => return Some(Token::Pragma(Pack::Pop)),
+        _ => {}
+    }
+    let args = pragma
+        .strip_prefix("pack")?
+        .trim()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+    let args: Vec<&str> = args.split(',').map(str::trim).collect();
+    let value = args.iter().find_map(|arg| arg.parse().ok());
+    Some(Token::Pragma(match args[0] {
+        "push" => Pack::Push(value),
+        "pop" => Pack::Pop,
+        _ => Pack::Set(value),
+    }))
+}
+
+struct Parser {
+    code: Vec<Spanned>,
+    /// 记号下标 → 之前的注释
+    leading: HashMap<usize, Vec<String>>,
+    /// 记号下标 → 同一行之后的注释
+    trailing: HashMap<usize, String>,
+    pos: usize,
+    pack: Option<u32>,
+    pack_stack: Vec<Option<u32>>,
+    defines: HashMap<String, String>,
+    aliases: HashMap<String, String>,
+    structs: Vec<ParsedStruct>,
+    notes: Vec<String>,
+}
+
+impl Parser {
+    fn new(tokens: Tokens) -> Self {
+        let mut leading: HashMap<usize, Vec<String>> = HashMap::new();
+        let mut trailing: HashMap<usize, String> = HashMap::new();
+        for comment in tokens.comments {
+            if comment.text.is_empty() {
+                continue;
+            }
+            let previous = comment.before.checked_sub(1);
+            match previous {
+                Some(previous)
+                    if comment.trailing || tokens.code[previous].line == comment.line =>
+                {
+                    let text = trailing.entry(previous).or_default();
+                    if !text.is_empty() {
+                        text.push(' ');
+                    }
+                    text.push_str(&comment.text);
+                }
+                _ => leading
+                    .entry(comment.before)
+                    .or_default()
+                    .push(comment.text),
+            }
+        }
+        Self {
+            code: tokens.code,
+            leading,
+            trailing,
+            pos: 0,
+            pack: None,
+            pack_stack: Vec::new(),
+            defines: HashMap::new(),
+            aliases: HashMap::new(),
+            structs: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.code.get(self.pos).map(|t| &t.token)
+    }
+
+    fn peek_word(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Word(word)) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn parse(&mut self) {
+        while let Some(token) = self.peek().cloned() {
+            let start = self.pos;
+            match token {
+                Token::Pragma(_) | Token::Define(..) | Token::Directive => self.directive(),
+                Token::Punct('{' | '}' | ';') => self.pos += 1,
+                Token::Word(word) => match word.as_str() {
+                    "typedef" => {
+                        self.pos += 1;
+                        self.typedef(start);
+                    }
+                    "struct" | "__packed" => {
+                        if let Some(parsed) = self.struct_definition(start) {
+                            self.structs.push(parsed);
+                        }
+                        self.skip_declaration();
+                    }
+                    "enum" => self.enumeration(),
+                    // extern "C" { 只是包裹，内部的声明照常处理
+                    "extern"
+                        if self.code.get(self.pos + 1).map(|t| &t.token)
+                            == Some(&Token::Literal) =>
+                    {
+                        self.pos += 2;
+                    }
+                    _ => self.skip_declaration(),
+                },
+                _ => self.skip_declaration(),
+            }
+        }
+    }
+
+    fn directive(&mut self) {
+        match self.peek().cloned() {
+            Some(Token::Pragma(pack)) => match pack {
+                Pack::Push(value) => {
+                    self.pack_stack.push(self.pack);
+                    if value.is_some() {
+                        self.pack = value;
+                    }
+                }
+                Pack::Pop => self.pack = self.pack_stack.pop().flatten(),
+                Pack::Set(value) => self.pack = value,
+            },
+            Some(Token::Define(name, value)) => {
+                self.defines.insert(name, value);
+            }
+            _ => {}
+        }
+        self.pos += 1;
+    }
+
+    /// 跳过到声明结束的分号；函数体等花括号块在闭合处结束
+    fn skip_declaration(&mut self) {
+        let mut depth = 0usize;
+        while let Some(token) = self.peek().cloned() {
+            self.pos += 1;
+            match token {
+                Token::Punct(';') if depth == 0 => return,
+                Token::Punct('{') => depth += 1,
+                Token::Punct('}') => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn typedef(&mut self, start: usize) {
+        match self.peek_word() {
+            Some("struct" | "__packed") => {
+                if let Some(mut parsed) = self.struct_definition(start) {
+                    // typedef struct Tag { ... } Name;
+                    self.attributes();
+                    if let Some(name) = self.peek_word() {
+                        parsed.name = name.strip_suffix("_t").unwrap_or(name).to_string();
+                    }
+                    self.structs.push(parsed);
+                }
+                self.skip_declaration();
+            }
+            Some("union") => {
+                self.pos += 1;
+                let name = self.peek_word().unwrap_or("<匿名>").to_string();
+                self.notes.push(format!("已跳过联合体 '{}'", name));
+                self.skip_declaration();
+            }
+            Some("enum") => self.enumeration(),
+            _ => {
+                // typedef unsigned char u8;
+                let mut words = Vec::new();
+                while let Some(Token::Word(word)) = self.peek() {
+                    words.push(word.clone());
+                    self.pos += 1;
+                }
+                if self.is_punct(';')
+                    && let Some(alias) = words.pop()
+                    && !words.is_empty()
+                {
+                    let ty = self.resolve_type(&words);
+                    self.aliases.insert(alias, ty);
+                }
+                self.skip_declaration();
+            }
+        }
+    }
+
+    /// 枚举常量与宏一样可以给出命令 ID 与数组长度，例如裁判系统头文件中的 `GAME_STATUS_CMD_ID = 0x0001`
+    fn enumeration(&mut self) {
+        self.pos += 1;
+        while !self.is_punct('{') {
+            if self.peek().is_none() || self.is_punct(';') {
+                self.skip_declaration();
+                return;
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        let mut next = Some(0u64);
+        while let Some(Token::Word(name)) = self.peek().cloned() {
+            self.pos += 1;
+            if self.is_punct('=') {
+                self.pos += 1;
+                next = self.peek().and_then(|t| self.constant(t));
+                while !self.is_punct(',') && !self.is_punct('}') && self.peek().is_some() {
+                    self.pos += 1;
+                }
+            }
+            if let Some(value) = next {
+                self.defines.insert(name, value.to_string());
+            }
+            next = next.and_then(|n| n.checked_add(1));
+            if self.is_punct(',') {
+                self.pos += 1;
+            }
+        }
+        self.skip_declaration();
+    }
+
+    /// 解析到结构体的右花括号为止；没有成员列表的声明返回 None
+    fn struct_definition(&mut self, start: usize) -> Option<ParsedStruct> {
+        let mut attributes = self.attributes();
+        if self.peek_word() != Some("struct") {
+            return None;
+        }
+        self.pos += 1;
+        attributes.merge(self.attributes());
+        let tag = match self.peek() {
+            Some(Token::Word(tag)) => {
+                let tag = tag.clone();
+                self.pos += 1;
+                Some(tag)
+            }
+            _ => None,
+        };
+        attributes.merge(self.attributes());
+        if !self.is_punct('{') {
+            return None;
+        }
+        self.pos += 1;
+
+        let pack = self.pack;
+        let (fields, mut skipped) = self.members();
+        // 右花括号之后的 __attribute__((packed))
+        attributes.merge(self.attributes());
+
+        let packed = attributes.packed || pack == Some(1);
+        if let Some(pack) = pack.filter(|&pack| pack > 1 && !attributes.packed) {
+            skipped.get_or_insert(format!("#pragma pack({}) 的布局无法表示", pack));
+        }
+        if fields.is_empty() {
+            skipped.get_or_insert("没有成员".to_string());
+        }
+        let comment = self
+            .leading
+            .get(&start)
+            .map(|lines| lines.join(" "))
+            .filter(|text| !is_section_banner(text));
+        Some(ParsedStruct {
+            name: tag.unwrap_or_default(),
+            packed,
+            align: attributes.align,
+            comment,
+            fields,
+            skipped,
+        })
+    }
+
+    /// `__attribute__((packed, aligned(N)))` 与 `__packed` 等
+    fn attributes(&mut self) -> Attributes {
+        let mut attributes = Attributes::default();
+        while let Some(word) = self.peek_word() {
+            match word {
+                "__packed" | "__PACKED" | "PACKED" => {
+                    attributes.packed = true;
+                    self.pos += 1;
+                }
+                "__attribute__" | "__attribute" | "__declspec" => {
+                    self.pos += 1;
+                    let group = self.balanced_group();
+                    for (i, token) in group.iter().enumerate() {
+                        match token {
+                            Token::Word(w) if w.trim_matches('_') == "packed" => {
+                                attributes.packed = true;
+                            }
+                            Token::Word(w) if w.trim_matches('_') == "aligned" => {
+                                if let Some(Token::Number(n)) = group.get(i + 2) {
+                                    attributes.align =
+                                        parse_integer(n).and_then(|n| n.try_into().ok());
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+        attributes
+    }
+
+    /// 成对括号中的记号
+    fn balanced_group(&mut self) -> Vec<Token> {
+        let mut group = Vec::new();
+        let mut depth = 0usize;
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Punct('(') => depth += 1,
+                Token::Punct(')') => depth = depth.saturating_sub(1),
+                _ if depth == 0 => break,
+                _ => {}
+            }
+            self.pos += 1;
+            group.push(token);
+            if depth == 0 {
+                break;
+            }
+        }
+        group
+    }
+
+    /// 解析成员直到结构体的右花括号，返回字段与无法导入的原因
+    fn members(&mut self) -> (Vec<Entry>, Option<String>) {
+        let mut fields = Vec::new();
+        let mut skipped = None;
+        let mut reserved = 0;
+        loop {
+            let start = self.pos;
+            match self.peek() {
+                None => {
+                    skipped.get_or_insert("结构体没有闭合".to_string());
+                    return (fields, skipped);
+                }
+                Some(Token::Punct('}')) => {
+                    self.pos += 1;
+                    return (fields, skipped);
+                }
+                Some(Token::Pragma(_) | Token::Define(..) | Token::Directive) => {
+                    self.directive();
+                    continue;
+                }
+                Some(Token::Punct(';')) => {
+                    self.pos += 1;
+                    continue;
+                }
+                Some(Token::Word(word)) if matches!(word.as_str(), "struct" | "union" | "enum") => {
+                    skipped.get_or_insert(format!("包含嵌套的 {}", word));
+                    self.skip_member();
+                    continue;
+                }
+                _ => {}
+            }
+
+            if let Some(lines) = self.leading.get(&start) {
+                for line in lines {
+                    if let Some(section) = section_banner(line) {
+                        fields.push(Entry::Section {
+                            section: section.to_string(),
+                        });
+                    }
+                }
+            }
+            let leading = self.leading.get(&start).and_then(|lines| {
+                let text: Vec<&str> = lines
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|line| !is_section_banner(line))
+                    .collect();
+                (!text.is_empty()).then(|| text.join(" "))
+            });
+
+            let (tokens, end) = self.skip_member();
+            let mut declarators = tokens.split(|t| *t == Token::Punct(','));
+            let first = declarators.next().unwrap_or_default();
+            let words = first
+                .iter()
+                .take_while(|t| matches!(t, Token::Word(_)))
+                .count();
+            // 匿名位域 `uint8_t : 3;` 中所有的单词都属于类型
+            let anonymous = first.get(words) == Some(&Token::Punct(':'))
+                && match &first[..words] {
+                    [_] => true,
+                    [.., Token::Word(last)] => self.is_type_word(last),
+                    _ => false,
+                };
+            let type_len = if anonymous {
+                words
+            } else {
+                words.saturating_sub(1)
+            };
+            let type_words: Vec<String> = first[..type_len]
+                .iter()
+                .filter_map(|t| match t {
+                    Token::Word(word) => Some(word.clone()),
+                    _ => None,
+                })
+                .collect();
+            if type_words.is_empty() {
+                skipped.get_or_insert("无法解析的成员".to_string());
+                continue;
+            }
+            let ty = self.resolve_type(&type_words);
+            let trailing = end.and_then(|end| self.trailing.get(&end).cloned());
+            let comment = trailing.or(leading);
+
+            let declarators = std::iter::once(&first[type_len..]).chain(declarators);
+            for declarator in declarators {
+                match self.declarator(declarator, &ty) {
+                    Ok((name, ty, bit_field)) => {
+                        let name = name.unwrap_or_else(|| {
+                            reserved += 1;
+                            format!("reserved{}", reserved)
+                        });
+                        fields.push(Entry::Field(ImportedField {
+                            name,
+                            ty,
+                            bit_field,
+                            comment: comment.clone(),
+                        }));
+                    }
+                    Err(reason) => {
+                        skipped.get_or_insert(reason);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 跳过一个成员，返回其中的记号（不含结尾分号）与分号的下标
+    fn skip_member(&mut self) -> (Vec<Token>, Option<usize>) {
+        let mut tokens = Vec::new();
+        let mut depth = 0usize;
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Punct(';') if depth == 0 => {
+                    self.pos += 1;
+                    return (tokens, Some(self.pos - 1));
+                }
+                Token::Punct('}') if depth == 0 => return (tokens, None),
+                Token::Punct('{') => depth += 1,
+                Token::Punct('}') => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+            tokens.push(token);
+        }
+        (tokens, None)
+    }
+
+    /// `name`、`name[N]` 或 `name : bits`
+    fn declarator(
+        &self,
+        tokens: &[Token],
+        ty: &str,
+    ) -> Result<(Option<String>, String, Option<u64>), String> {
+        let mut tokens = tokens.iter().peekable();
+        let name = match tokens.peek() {
+            Some(Token::Word(name)) => {
+                tokens.next();
+                Some(name.clone())
+            }
+            _ => None,
+        };
+        let describe = || name.as_deref().unwrap_or("<匿名>").to_string();
+        let mut ty = ty.to_string();
+        let mut bit_field = None;
+        let mut dims = 0;
+        while let Some(token) = tokens.next() {
+            match token {
+                Token::Punct('[') => {
+                    let len = tokens
+                        .next()
+                        .and_then(|t| self.constant(t))
+                        .filter(|_| tokens.next() == Some(&Token::Punct(']')))
+                        .ok_or_else(|| format!("成员 '{}' 的数组长度无法解析", describe()))?;
+                    dims += 1;
+                    if dims > 1 {
+                        return Err(format!("成员 '{}' 是多维数组", describe()));
+                    }
+                    ty = format!("{}[{}]", ty, len);
+                }
+                Token::Punct(':') => {
+                    bit_field = Some(
+                        tokens
+                            .next()
+                            .and_then(|t| self.constant(t))
+                            .ok_or_else(|| format!("成员 '{}' 的位宽无法解析", describe()))?,
+                    );
+                }
+                Token::Punct('*') => return Err(format!("成员 '{}' 是指针", describe())),
+                Token::Word(word) if word.starts_with("__attribute") => {
+                    return Err(format!("成员 '{}' 带有属性", describe()));
+                }
+                _ => return Err(format!("无法解析成员 '{}'", describe())),
+            }
+        }
+        if name.is_none() && bit_field.is_none() {
+            return Err("无法解析的成员".to_string());
+        }
+        Ok((name, ty, bit_field))
+    }
+
+    /// 数字或展开为数字的宏
+    fn constant(&self, token: &Token) -> Option<u64> {
+        match token {
+            Token::Number(n) => parse_integer(n),
+            Token::Word(name) => {
+                let mut value = self.defines.get(name)?.as_str();
+                // 宏可以再引用一层宏，例如 #define LEN (BASE_LEN)
+                for _ in 0..8 {
+                    value = value
+                        .trim()
+                        .trim_start_matches('(')
+                        .trim_end_matches(')')
+                        .trim();
+                    if let Some(n) = parse_integer(value) {
+                        return Some(n);
+                    }
+                    value = self.defines.get(value)?.as_str();
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn is_type_word(&self, word: &str) -> bool {
+        matches!(
+            word,
+            "int" | "char" | "short" | "long" | "unsigned" | "signed"
+        ) || is_supported_type(&self.resolve_type(&[word.to_string()]))
+    }
+
+    /// 去掉限定符并展开别名
+    fn resolve_type(&self, words: &[String]) -> String {
+        let words: Vec<&str> = words
+            .iter()
+            .map(String::as_str)
+            .filter(|w| !matches!(*w, "const" | "volatile"))
+            .collect();
+        let mut ty = match words.as_slice() {
+            ["unsigned"] => "unsigned int".to_string(),
+            ["signed"] => "signed int".to_string(),
+            // long int → long，unsigned short int → unsigned short
+            [rest @ .., "int"] if rest.iter().any(|w| matches!(*w, "long" | "short")) => {
+                rest.join(" ")
+            }
+            _ => words.join(" "),
+        };
+        for _ in 0..8 {
+            match self.aliases.get(&ty) {
+                Some(aliased) => ty = aliased.clone(),
+                None => break,
+            }
+        }
+        if !is_supported_type(&ty)
+            && let Some((_, known)) = LEGACY_TYPES.iter().find(|(alias, _)| *alias == ty)
+        {
+            ty = known.to_string();
+        }
+        ty
+    }
+}
+
+#[derive(Debug, Default)]
+struct Attributes {
+    packed: bool,
+    align: Option<u32>,
+}
+
+impl Attributes {
+    fn merge(&mut self, other: Attributes) {
+        self.packed |= other.packed;
+        self.align = other.align.or(self.align);
+    }
+}
+
+fn parse_integer(text: &str) -> Option<u64> {
+    let text = text.trim_end_matches(['u', 'U', 'l', 'L']);
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+fn is_supported_type(ty: &str) -> bool {
+    let base = ty.split('[').next().unwrap_or_default();
+    c_type_to_bit_field_size(base).is_some() || matches!(base, "float" | "double" | "long double")
+}
+
+/// `---- 标题 ----` 形式的分隔注释
+fn section_banner(text: &str) -> Option<&str> {
+    let title = text.strip_prefix("--")?.strip_suffix("--")?;
+    let title = title.trim_matches('-').trim();
+    (!title.is_empty()).then_some(title)
+}
+
+fn is_section_banner(text: &str) -> bool {
+    section_banner(text).is_some()
+}
+
+/// 按包名查找命令 ID 宏
+fn command_id_macro(name: &str, defines: &HashMap<String, String>) -> Option<u16> {
+    let prefixes = [upper_snake(name), name.to_uppercase()];
+    prefixes.iter().find_map(|prefix| {
+        ["_CMD", "_CMD_ID", "_ID"].iter().find_map(|suffix| {
+            let value = defines.get(&format!("{}{}", prefix, suffix))?;
+            let value = value.trim().trim_start_matches('(').trim_end_matches(')');
+            let value = value.trim_end_matches(['u', 'U']);
+            parse_command_id(value).ok()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::c_backend::generate_c;
+    use crate::compiler::Compiler;
+    use crate::config::{Config, PacketSet};
+    use crate::generator::GenerateOptions;
+    use crate::validator::{validate, validate_multiple};
+
+    const LEGACY: &str = r#"
+/* Copyright (c) 2023 DLMU CONE */
+#ifndef PROTOCOL_H
+#define PROTOCOL_H
+
+#include <stdint.h>
+
+typedef unsigned char u8;
+typedef float fp32;
+
+#define GIMBAL_CMD_ID 0x0104
+#define NAME_LEN (8)
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum {
+    GAME_STATUS_CMD_ID = 0x0001,
+    ROBOT_HP_CMD_ID,
+} referee_cmd_id_t;
+
+#pragma pack(push, 1)
+/// 云台控制指令
+typedef struct {
+    fp32 yaw;             ///< 偏航角
+    fp32 pitch;           // 俯仰角
+    // ---- 状态 ----
+    u8 mode : 3, fire : 1;
+    unsigned char : 4;
+    /** 操作手名称 */
+    char name[NAME_LEN];
+} gimbal_t;
+
+typedef struct game_status {
+    uint8_t game_type : 4;
+    uint8_t game_progress : 4;
+    uint16_t stage_remain_time;
+} game_status_t;
+#pragma pack(pop)
+
+struct __attribute__((packed, aligned(4))) Chassis {
+    int16_t vx, vy;
+    const unsigned short int wz;
+};
+
+struct Internal {
+    uint32_t ticks;
+    double gain[2][2];
+};
+
+typedef union {
+    uint8_t raw[4];
+    float value;
+} float_bytes_t;
+
+void protocol_init(void);
+static inline uint8_t checksum(const uint8_t *data) { return data[0]; }
+
+#ifdef __cplusplus
+}
+#endif
+#endif
+"#;
+
+    #[test]
+    fn test_import_legacy_header() {
+        let imported = import_header(LEGACY, &HeaderImport::default()).unwrap();
+        assert_eq!(imported.packets, vec!["gimbal", "game_status", "Chassis"]);
+        assert_eq!(
+            imported.notes,
+            vec![
+                "已跳过联合体 '<匿名>'",
+                "已跳过结构体 'Internal'：成员 'gain' 是多维数组",
+                "结构体 'Chassis' 没有对应的命令 ID 宏，已分配 0x0002",
+            ]
+        );
+
+        let set: PacketSet = serde_json::from_str(&imported.json).unwrap();
+        let gimbal = &set.packets[0];
+        assert_eq!(gimbal.command_id, "0x0104");
+        assert!(gimbal.packed);
+        assert_eq!(gimbal.comment.as_ref().unwrap().as_str(), "云台控制指令");
+        let fields: Vec<_> = gimbal
+            .fields
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.ty.as_str(),
+                    f.bit_field,
+                    f.comment.as_ref().map(|c| c.as_str()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("yaw", "float", None, Some("偏航角")),
+                ("pitch", "float", None, Some("俯仰角")),
+                ("mode", "unsigned char", Some(3), None),
+                ("fire", "unsigned char", Some(1), None),
+                ("reserved1", "unsigned char", Some(4), None),
+                ("name", "char[8]", None, Some("操作手名称")),
+            ]
+        );
+        assert_eq!(gimbal.fields[2].section.as_deref(), Some("状态"));
+
+        // 枚举常量给出命令 ID
+        assert_eq!(set.packets[1].command_id, "0x0001");
+        assert_eq!(set.packets[1].comment, None);
+
+        let chassis = &set.packets[2];
+        assert!(chassis.packed);
+        assert_eq!(chassis.align, Some(4));
+        assert_eq!(chassis.fields[2].ty, "unsigned short");
+        assert_eq!(
+            chassis
+                .fields
+                .iter()
+                .map(|f| f.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["vx", "vy", "wz"]
+        );
+
+        assert!(
+            validate_multiple(&imported.json)
+                .iter()
+                .all(|d| d.severity == crate::Severity::Warning),
+            "{:?}",
+            validate_multiple(&imported.json)
+        );
+    }
+
+    #[test]
+    fn test_import_generated_c_header_round_trip() {
+        let json = r#"{
+            "packet_name": "GimbalCommand",
+            "command_id": "0x0104",
+            "comment": "云台控制",
+            "fields": [
+                { "name": "yaw", "type": "float", "comment": "偏航角" },
+                { "section": "标志位" },
+                { "name": "mode", "type": "uint8_t", "bit_field": 3, "comment": "模式" },
+                { "name": "fire", "type": "uint8_t", "bit_field": 5, "comment": "开火" },
+                { "name": "ids", "type": "uint16_t[4]", "comment": "目标" }
+            ]
+        }"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        for compiler in [
+            Compiler::Gcc,
+            Compiler::Msvc,
+            Compiler::Armcc,
+            Compiler::ArmccPragma,
+        ] {
+            let options = GenerateOptions {
+                compiler,
+                ..GenerateOptions::default()
+            };
+            let header = generate_c(&config, &options).unwrap();
+            let imported = import_header(&header, &HeaderImport::default()).unwrap();
+            assert!(imported.notes.is_empty(), "{:?}", imported.notes);
+            assert!(validate(&imported.json).is_empty(), "{}", imported.json);
+
+            let round_trip: Config = serde_json::from_str(&imported.json).unwrap();
+            assert_eq!(round_trip.packet_name, config.packet_name);
+            assert_eq!(round_trip.command_id, config.command_id);
+            assert_eq!(round_trip.comment, config.comment);
+            assert_eq!(round_trip.fields, config.fields, "{:?}", compiler);
+        }
+    }
+
+    #[test]
+    fn test_import_header_single_packet_and_errors() {
+        let imported = import_header(
+            "struct Ping { uint32_t seq; };",
+            &HeaderImport {
+                first_command_id: "0x0200".to_string(),
+            },
+        )
+        .unwrap();
+        assert!(imported.json.starts_with("{\n  \"rplc_schema\": 2,\n"));
+        assert!(imported.json.contains(r#""command_id": "0x0200""#));
+        assert!(imported.json.contains(r#""packed": false"#));
+
+        assert_eq!(
+            import_header("struct Ping; int x;", &HeaderImport::default()),
+            Err(ImportError::NoStructs)
+        );
+        assert_eq!(
+            import_header("struct A { int a; };\n/* x", &HeaderImport::default()),
+            Err(ImportError::UnterminatedComment(2))
+        );
+        assert!(matches!(
+            import_header(
+                "struct A { int a; };",
+                &HeaderImport {
+                    first_command_id: "0x10000".to_string()
+                }
+            ),
+            Err(ImportError::Scaffold(ScaffoldError::InvalidCommandId(_)))
+        ));
+        let unknown = import_header("struct A { vec3_t pos; };", &HeaderImport::default()).unwrap();
+        assert!(unknown.notes[1].contains("'vec3_t'"), "{:?}", unknown.notes);
+    }
+}
@@ -0,0 +1,570 @@
+//! 以 minijinja 渲染 C++ 头文件的整体结构；内置模板生成的头文件与之前的硬编码拼接完全相同
+//!
+//! 用户模板运行在受限的环境中：不能读取文件系统（没有加载器，环境中只有模板自身，`include` 等语句找不到其他模板），
+//! 没有时间、随机数等不确定的函数，执行的指令数、递归深度与输出大小都有上限，
+//! 出错的模板报告错误而不会挂起 CI 或产生不确定的输出
+
+use std::fmt;
+use std::io;
+use std::sync::{Arc, OnceLock};
+
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+use minijinja::{AutoEscape, Environment, ErrorKind, UndefinedBehavior};
+use serde::{Serialize, Serializer};
+
+use crate::compiler::Packing;
+use crate::config::Config;
+use crate::fingerprint::{format_layout_hash, layout_hash};
+use crate::generator::{
+    BitLayoutField, BitLayoutPlan, GenerateOptions, HeaderBlocks, MemberLine, custom_traits,
+    struct_members,
+};
+use crate::layout::compute_layout;
+use crate::validator::{format_command_id, parse_array_type};
+
+/// 内置的头文件模板，`rplc template` 输出它作为自定义模板的起点
+pub const DEFAULT_HEADER_TEMPLATE: &str = include_str!("../templates/header.hpp.jinja");
+
+const DEFAULT_TEMPLATE_NAME: &str = "header.hpp.jinja";
+
+/// 每次渲染可执行的指令数，内置模板只需要几十条
+const TEMPLATE_FUEL: u64 = 1_000_000;
+
+/// 宏与循环的嵌套层数上限
+const TEMPLATE_RECURSION_LIMIT: usize = 64;
+
+/// 渲染结果的字节数上限，与定义文件的输入上限同一数量级
+const TEMPLATE_OUTPUT_LIMIT: usize = 16 * 1024 * 1024;
+
+/// 用户提供的头文件模板；`name` 通常是模板文件的路径，用于错误信息
+///
+/// 模板在创建时编译一次，之后每个包的头文件都复用编译结果
+#[derive(Debug, Clone)]
+pub struct HeaderTemplate {
+    name: String,
+    source: String,
+    env: Arc<Environment<'static>>,
+}
+
+impl HeaderTemplate {
+    /// 读取时即检查模板语法，语法错误指向模板文件中的位置
+    pub fn new(name: impl Into<String>, source: impl Into<String>) -> Result<Self, TemplateError> {
+        let (name, source) = (name.into(), source.into());
+        let env = compile(&name, &source)?;
+        Ok(HeaderTemplate {
+            name,
+            source,
+            env: Arc::new(env),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl PartialEq for HeaderTemplate {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.source == other.source
+    }
+}
+
+impl Eq for HeaderTemplate {}
+
+/// 构建报告中只记录模板的名称，模板内容由报告的输入文件哈希记录
+impl Serialize for HeaderTemplate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name)
+    }
+}
+
+/// 模板可以读取的变量
+#[derive(Debug, Serialize)]
+pub(crate) struct HeaderContext<'a> {
+    pub guard: &'a str,
+    /// 定宽整数类型与标准库的 `#include`，每行以换行结尾
+    pub includes: String,
+    /// 独立环境下定宽整数类型的后备 typedef
+    pub typedefs: String,
+    #[serde(flatten)]
+    pub blocks: HeaderBlocks,
+    /// 一级缩进，与 `--indent-width`、`--use-tabs` 一致
+    pub indent: String,
+    /// 结构体与函数的左花括号（连同之前的空格或换行），与 `--brace-style` 一致
+    pub open_brace: &'static str,
+    pub packet: PacketContext<'a>,
+}
+
+/// 包的结构化信息，内置模板由此生成结构体与 traits
+#[derive(Debug, Serialize)]
+pub(crate) struct PacketContext<'a> {
+    name: &'a str,
+    command_id: String,
+    namespace: Option<&'a str>,
+    comment: Option<&'a str>,
+    /// traits 中 size 的写法，含位域时为字节数，否则为 `sizeof(包名)`
+    size: String,
+    /// 布局计算得到的字节数，布局无法计算时为 none
+    size_bytes: Option<u32>,
+    layout_hash: String,
+    /// 紧凑结构体的写法：`attribute`、`pragma`、`keyword` 或 `armcc_pragma`，非紧凑时为 none
+    packing: Option<Packing>,
+    align: Option<u32>,
+    fields: Vec<FieldContext<'a>>,
+    /// 最后一个字段之后、右花括号之前的内容：嵌套结构体的结尾、范围常量、字段 ID 与取值查找函数
+    members_tail: String,
+    emit_traits: bool,
+    /// `traits_template` 的渲染结果，以换行结尾；未设置时为 none
+    custom_traits: Option<String>,
+    /// 含位域时 `BitLayout` 的各项，否则为 none
+    bit_layout: Option<&'a [BitLayoutField]>,
+}
+
+#[derive(Debug, Serialize)]
+struct FieldContext<'a> {
+    name: &'a str,
+    /// 元素类型：引用枚举的字段为枚举名，数组不含长度
+    #[serde(rename = "type")]
+    ty: &'a str,
+    array_len: Option<u32>,
+    bit_field: Option<u8>,
+    comment: Option<&'a str>,
+    unit: Option<&'a str>,
+    /// 由 `reserved` 条目或固定偏移的空隙生成的保留字节
+    reserved: bool,
+    /// 在包中的字节偏移，位流布局中不按字节对齐或布局无法计算时为 none
+    offset: Option<u32>,
+    /// 在包中的位偏移与位数，布局无法计算时为 none
+    offset_bits: Option<u32>,
+    size_bits: Option<u32>,
+    /// 成员行的各部分：`preamble`、`indent`、`cpp_type` 与 `declarator`
+    #[serde(flatten)]
+    member: MemberLine,
+}
+
+impl<'a> PacketContext<'a> {
+    /// `config` 已补齐保留字节并按输出语言选定注释
+    pub fn new(
+        config: &'a Config,
+        cmd_id: u16,
+        size: String,
+        bit_layout_plan: Option<&'a BitLayoutPlan>,
+        options: &GenerateOptions,
+    ) -> Self {
+        let layout = compute_layout(config);
+        let packing = config.packed.then(|| options.compiler.packing());
+        let members = struct_members(config, packing, options);
+        let fields = config
+            .fields
+            .iter()
+            .zip(members.lines)
+            .enumerate()
+            .map(|(index, (field, member))| {
+                let (base_type, array_len) =
+                    parse_array_type(&field.ty).unwrap_or((field.ty.as_str(), None));
+                let field_layout = layout.as_ref().map(|layout| &layout.fields[index]);
+                let offset_bits = field_layout.map(|field| field.offset_bits);
+                FieldContext {
+                    name: &field.name,
+                    ty: field.enum_type.as_deref().unwrap_or(base_type),
+                    array_len,
+                    bit_field: field.bit_field,
+                    comment: field.comment.as_ref().map(|comment| comment.as_str()),
+                    unit: field.unit.as_deref(),
+                    reserved: field.reserved.is_some(),
+                    offset: offset_bits
+                        .filter(|bits| bits % 8 == 0)
+                        .map(|bits| bits / 8),
+                    offset_bits,
+                    size_bits: field_layout.map(|field| field.size_bits),
+                    member,
+                }
+            })
+            .collect();
+        PacketContext {
+            name: &config.packet_name,
+            command_id: format_command_id(cmd_id),
+            namespace: config.namespace.as_deref(),
+            comment: config.comment.as_ref().map(|comment| comment.as_str()),
+            custom_traits: custom_traits(config, cmd_id, &size),
+            size,
+            size_bytes: layout.as_ref().map(|layout| layout.size),
+            layout_hash: format_layout_hash(layout_hash(config)),
+            packing,
+            align: config.align,
+            fields,
+            members_tail: members.tail,
+            emit_traits: config.emit_traits,
+            bit_layout: bit_layout_plan.map(|plan| plan.fields.as_slice()),
+        }
+    }
+}
+
+/// 以 `template` 渲染头文件，未指定时使用内置模板
+pub(crate) fn render_header(
+    template: Option<&HeaderTemplate>,
+    context: &HeaderContext,
+) -> Result<String, TemplateError> {
+    let (env, name, source) = match template {
+        Some(t) => (t.env.as_ref(), t.name.as_str(), t.source.as_str()),
+        None => (
+            default_environment(),
+            DEFAULT_TEMPLATE_NAME,
+            DEFAULT_HEADER_TEMPLATE,
+        ),
+    };
+    let mut out = LimitedOutput::default();
+    env.get_template(name)
+        .and_then(|template| template.render_captured_to(context, &mut out).map(|_| ()))
+        .map_err(|e| TemplateError::new(name, source, e))?;
+    String::from_utf8(out.buf).map_err(|_| TemplateError {
+        name: name.to_string(),
+        source: source.to_string(),
+        kind: TemplateErrorKind::Render,
+        reason: "输出不是有效的 UTF-8".to_string(),
+        span: None,
+    })
+}
+
+/// 只含一个模板的受限环境；模板以自身的名称注册，`include` 等语句找不到其他模板
+fn compile(name: &str, source: &str) -> Result<Environment<'static>, TemplateError> {
+    let mut env = sandbox();
+    env.add_template_owned(name.to_string(), source.to_string())
+        .map_err(|e| TemplateError::new(name, source, e))?;
+    Ok(env)
+}
+
+/// 内置模板在进程中只编译一次
+fn default_environment() -> &'static Environment<'static> {
+    static ENV: OnceLock<Environment<'static>> = OnceLock::new();
+    ENV.get_or_init(|| {
+        compile(DEFAULT_TEMPLATE_NAME, DEFAULT_HEADER_TEMPLATE).expect("内置模板的语法正确")
+    })
+}
+
+/// 渲染用户模板的环境：不转义输出，未定义的变量报错而不是输出空串
+fn sandbox() -> Environment<'static> {
+    let mut env = Environment::new();
+    env.set_keep_trailing_newline(true);
+    env.set_auto_escape_callback(|_| AutoEscape::None);
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    env.set_fuel(Some(TEMPLATE_FUEL));
+    env.set_recursion_limit(TEMPLATE_RECURSION_LIMIT);
+    env.set_debug(true);
+    env
+}
+
+/// 超过 `TEMPLATE_OUTPUT_LIMIT` 时写入失败，渲染随之中止
+#[derive(Default)]
+struct LimitedOutput {
+    buf: Vec<u8>,
+}
+
+impl io::Write for LimitedOutput {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + data.len() > TEMPLATE_OUTPUT_LIMIT {
+            return Err(io::Error::other(OutputTooLarge));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct OutputTooLarge;
+
+impl fmt::Display for OutputTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "输出超过 {} MiB", TEMPLATE_OUTPUT_LIMIT / (1024 * 1024))
+    }
+}
+
+impl std::error::Error for OutputTooLarge {}
+
+/// 模板错误的类别，决定诊断代码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateErrorKind {
+    /// 语法错误，读取模板时即报告
+    Syntax,
+    /// 渲染时的错误，例如未定义的变量或类型不符的运算
+    Render,
+    /// 超出指令数、递归深度或输出大小的上限
+    Limit,
+}
+
+/// 头文件模板的错误，位置指向模板文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    name: String,
+    source: String,
+    kind: TemplateErrorKind,
+    reason: String,
+    span: Option<(usize, usize)>,
+}
+
+impl TemplateError {
+    fn new(name: &str, source: &str, error: minijinja::Error) -> Self {
+        let output_too_large = std::error::Error::source(&error)
+            .and_then(|e| e.downcast_ref::<io::Error>())
+            .and_then(|e| e.get_ref())
+            .is_some_and(|e| e.is::<OutputTooLarge>());
+        let kind = match error.kind() {
+            ErrorKind::SyntaxError | ErrorKind::TemplateNotFound => TemplateErrorKind::Syntax,
+            ErrorKind::OutOfFuel => TemplateErrorKind::Limit,
+            ErrorKind::InvalidOperation if error.detail() == Some("recursion limit exceeded") => {
+                TemplateErrorKind::Limit
+            }
+            _ if output_too_large => TemplateErrorKind::Limit,
+            _ => TemplateErrorKind::Render,
+        };
+        let reason = match (kind, error.kind()) {
+            (TemplateErrorKind::Limit, ErrorKind::OutOfFuel) => {
+                format!("执行的指令超过 {} 条", TEMPLATE_FUEL)
+            }
+            (TemplateErrorKind::Limit, _) if output_too_large => OutputTooLarge.to_string(),
+            (TemplateErrorKind::Limit, _) => {
+                format!("嵌套超过 {} 层", TEMPLATE_RECURSION_LIMIT)
+            }
+            (_, ErrorKind::TemplateNotFound) => "模板不能包含其他文件".to_string(),
+            _ => match error.detail() {
+                Some(detail) => format!("{}: {}", error.kind(), detail),
+                None => error.kind().to_string(),
+            },
+        };
+        // 只有错误来自这个模板时位置才有意义
+        let span = error
+            .range()
+            .filter(|_| error.name() == Some(name))
+            .map(|range| (range.start, range.end - range.start));
+        TemplateError {
+            name: name.to_string(),
+            source: source.to_string(),
+            kind,
+            reason,
+            span,
+        }
+    }
+
+    pub fn kind(&self) -> TemplateErrorKind {
+        self.kind
+    }
+
+    /// 错误在模板源文本中的位置（起点，长度）
+    pub fn span(&self) -> Option<(usize, usize)> {
+        self.span
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "头文件模板 {} 有误: {}", self.name, self.reason)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl Diagnostic for TemplateError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let code = match self.kind {
+            TemplateErrorKind::Syntax => "rplc::template::syntax",
+            TemplateErrorKind::Render => "rplc::template::render",
+            TemplateErrorKind::Limit => "rplc::template::limit",
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = match self.kind {
+            TemplateErrorKind::Syntax => {
+                "模板使用 Jinja 语法，不能 include、import 或 extends 其他文件"
+            }
+            TemplateErrorKind::Render => {
+                "可用的变量见配置格式文档的“头文件模板”一节，rplc template 输出内置模板"
+            }
+            TemplateErrorKind::Limit => {
+                "检查模板中的循环与宏递归；生成头文件的模板不需要这么多计算"
+            }
+        };
+        Some(Box::new(help))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let (start, len) = self.span?;
+        Some(Box::new(std::iter::once(LabeledSpan::new_with_span(
+            None,
+            (start, len),
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{GenerateError, GenerateOptions, generate, generate_with_options};
+
+    const JSON: &str = r#"{
+        "packet_name": "Gimbal",
+        "command_id": "0x0104",
+        "namespace": "Robot",
+        "fields": [
+            { "name": "yaw", "type": "float", "comment": "偏航角", "unit": "rad" },
+            { "name": "gains", "type": "int16_t[3]" }
+        ]
+    }"#;
+
+    fn with_template(source: &str) -> GenerateOptions {
+        GenerateOptions {
+            header_template: Some(HeaderTemplate::new("custom.hpp.jinja", source).unwrap()),
+            ..GenerateOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_default_template_matches_builtin_output() {
+        let builtin = generate(JSON).unwrap();
+        let copied = generate_with_options(JSON, &with_template(DEFAULT_HEADER_TEMPLATE)).unwrap();
+        assert_eq!(copied, builtin);
+        assert!(builtin.starts_with("#ifndef RPL_GIMBAL_HPP\n"));
+        // 编译结果在复制的选项之间共享，不随每个包重新编译
+        let template = HeaderTemplate::new("custom.hpp.jinja", DEFAULT_HEADER_TEMPLATE).unwrap();
+        assert!(Arc::ptr_eq(&template.env, &template.clone().env));
+        assert!(builtin.ends_with("#endif // RPL_GIMBAL_HPP\n"));
+    }
+
+    /// 之前只拼接各段代码的模板，内置模板由包的结构化信息生成的结构体与 traits 应当与之相同
+    const BLOCKS_TEMPLATE: &str = "#ifndef {{ guard }}\n#define {{ guard }}\n\n{{ includes }}\n\
+        {{ typedefs }}{{ helpers }}{{ namespace_begin }}{{ enums }}{{ definition }}{{ size_assert }}\
+        {{ accessors }}{{ traits }}{{ namespace_end }}#endif // {{ guard }}\n";
+
+    #[test]
+    fn test_default_template_renders_struct_and_traits_from_packet() {
+        use crate::compiler::Compiler;
+        use crate::generator::BraceStyle;
+
+        let packets = [
+            JSON,
+            r#"{"packet_name":"Status","command_id":"2","packed":false,"align":8,
+                "comment":"状态","expected_size":8,
+                "fields":[{"name":"a","type":"uint8_t","comment":"A","min":0,"max":9},
+                    {"name":"b","type":"uint32_t","values":{"0":"空闲"}}]}"#,
+            r#"{"packet_name":"Flags","command_id":"3","comment":"标志",
+                "fields":[{"name":"mode","type":"uint8_t","bit_field":3,"comment":"模式"},
+                    {"name":"level","type":"uint16_t","bit_field":9},
+                    {"name":"raw","type":"uint8_t[2]"}]}"#,
+            r#"{"packet_name":"Pose","command_id":"4","namespace":"Robot::Nav",
+                "enums":[{"name":"Mode","type":"uint8_t","values":[{"name":"idle","value":0}]}],
+                "fields":[{"section":"位置"},{"name":"mode","type":"Mode","comment":"模式"},
+                    {"name":"pos","comment":"P","group":[{"name":"x","type":"float"},
+                        {"name":"y","type":"float","comment":"Y"}]},
+                    {"group":[{"name":"z","type":"int16_t"}]},
+                    {"reserved":2},{"section":"姿态"},{"name":"yaw","type":"float","offset":16}]}"#,
+            r#"{"packet_name":"Custom","command_id":"5",
+                "traits_template":"REGISTER(${name}, ${cmd}, ${size})",
+                "fields":[{"name":"x","type":"uint8_t"}]}"#,
+            r#"{"packet_name":"Plain","command_id":"6","emit_traits":false,
+                "fields":[{"name":"x","type":"uint8_t"}]}"#,
+        ];
+        let variants = [
+            GenerateOptions::default(),
+            GenerateOptions {
+                compiler: Compiler::Msvc,
+                align_fields: true,
+                ..GenerateOptions::default()
+            },
+            GenerateOptions {
+                compiler: Compiler::Armcc,
+                brace_style: BraceStyle::SameLine,
+                use_tabs: true,
+                ..GenerateOptions::default()
+            },
+            GenerateOptions {
+                compiler: Compiler::ArmccPragma,
+                indent_width: 2,
+                ..GenerateOptions::default()
+            },
+        ];
+        for json in packets {
+            for options in &variants {
+                let blocks = GenerateOptions {
+                    header_template: Some(
+                        HeaderTemplate::new("blocks.hpp.jinja", BLOCKS_TEMPLATE).unwrap(),
+                    ),
+                    ..options.clone()
+                };
+                assert_eq!(
+                    generate_with_options(json, options).expect(json),
+                    generate_with_options(json, &blocks).unwrap(),
+                    "{} {:?}",
+                    json,
+                    options.compiler
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_template_reads_packet_fields() {
+        let template = "#pragma once\n{{ includes }}\
+            {%- for field in packet.fields %}\n\
+            // {{ field.name }}: {{ field.type }}{% if field.array_len %}[{{ field.array_len }}]{% endif %} @ {{ field.offset_bits // 8 }}\
+            {%- endfor %}\n// {{ packet.command_id }} {{ packet.size }}\n";
+        let header = generate_with_options(JSON, &with_template(template)).unwrap();
+        assert!(header.starts_with("#pragma once\n#include <cstdint>\n"));
+        assert!(header.contains("// yaw: float @ 0\n// gains: int16_t[3] @ 4\n"));
+        assert!(header.ends_with("// 0x0104 sizeof(Gimbal)\n"));
+    }
+
+    fn template_error(source: &str) -> TemplateError {
+        match generate_with_options(JSON, &with_template(source)) {
+            Err(GenerateError::Template(e)) => e,
+            other => panic!("{} 应当报告模板错误: {:?}", source, other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_template_errors_point_into_template() {
+        let err = HeaderTemplate::new("bad.jinja", "#pragma once\n{% for x in %}").unwrap_err();
+        assert_eq!(err.kind(), TemplateErrorKind::Syntax);
+        assert!(err.span().is_some());
+
+        // 没有加载器，不能读取文件系统
+        let err = template_error("{% include \"/etc/passwd\" %}");
+        assert_eq!(err.kind(), TemplateErrorKind::Syntax);
+        assert!(err.to_string().contains("不能包含其他文件"));
+
+        let source = "{{ guard }}\n{{ packet.nmae }}\n";
+        let err = template_error(source);
+        assert_eq!(err.kind(), TemplateErrorKind::Render);
+        let (start, len) = err.span().unwrap();
+        assert_eq!(&source[start..start + len], "packet.nmae");
+        assert_eq!(err.code().unwrap().to_string(), "rplc::template::render");
+    }
+
+    #[test]
+    fn test_template_limits() {
+        let cases = [
+            // 指令数
+            "{% for a in range(100000) %}{% for b in range(100000) %}{% endfor %}{% endfor %}",
+            // 递归
+            "{% macro f(n) %}{{ f(n + 1) }}{% endmacro %}{{ f(0) }}",
+            // 输出大小
+            "{% for a in range(100000) %}{{ 'x' * 1000 }}{% endfor %}",
+        ];
+        for source in cases {
+            let err = template_error(source);
+            assert_eq!(err.kind(), TemplateErrorKind::Limit, "{}", err);
+        }
+    }
+}
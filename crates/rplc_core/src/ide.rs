@@ -0,0 +1,394 @@
+//! 编辑器支持：语言服务器按光标位置查询的悬停信息与补全候选
+//!
+//! 补全发生在编辑途中，此时文档往往还不是合法的 JSON，因此按括号与字符串逐字扫描，不做完整解析
+
+use crate::baseline::diagnostic_location;
+use crate::config::ConfigOrArray;
+use crate::layout::{PacketLayout, compute_layout};
+use crate::suggest::{
+    ENUM_KEYS, ENUMERATOR_KEYS, FIELD_KEYS, PACKET_KEYS, PACKET_SET_KEYS, SUPPORTED_TYPES,
+};
+
+/// 光标所在的包及其布局
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoverInfo {
+    pub packet: String,
+    pub layout: PacketLayout,
+    /// 光标位于字段条目中时，该字段在 `layout.fields` 中的下标
+    pub field: Option<usize>,
+}
+
+/// 补全候选的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    /// 对象中的键
+    Key,
+    /// `type` 的值：基础类型、文档中的枚举与包
+    Type,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub kind: CompletionKind,
+    /// 候选文本，不含引号
+    pub candidates: Vec<String>,
+    /// 光标已位于字符串中；否则插入时需要补上引号
+    pub in_string: bool,
+}
+
+/// 光标处的包与字段；文档无法解析或布局无法计算时返回 `None`
+pub fn hover_at(json_input: &str, offset: usize) -> Option<HoverInfo> {
+    let (packet, field) = diagnostic_location(json_input, Some((offset, 0)));
+    let packet = packet?;
+    let parsed: ConfigOrArray = serde_json::from_str(json_input).ok()?;
+    let config = parsed
+        .into_packets()
+        .into_iter()
+        .find(|config| config.packet_name == packet)?;
+    let layout = compute_layout(&config)?;
+    // 光标位于具名嵌套结构体上时没有对应的字段，只显示包的布局
+    let field = field.and_then(|name| layout.fields.iter().position(|f| f.name == name));
+    Some(HoverInfo {
+        packet,
+        layout,
+        field,
+    })
+}
+
+/// 光标处可以补全的键或类型；`allowed_types` 与 `ValidateOptions::allowed_types` 含义相同
+pub fn completion_at(
+    json_input: &str,
+    offset: usize,
+    allowed_types: Option<&[String]>,
+) -> Option<Completion> {
+    let scan = Scanner::scan(json_input, offset);
+    let (kind, in_string) = match scan.context? {
+        Context::Key {
+            scope,
+            present,
+            in_string,
+        } => {
+            let keys: Vec<String> = scope
+                .keys()
+                .into_iter()
+                .filter(|key| !present.iter().any(|p| p == key))
+                .map(str::to_string)
+                .collect();
+            return Some(Completion {
+                kind: CompletionKind::Key,
+                candidates: keys,
+                in_string,
+            });
+        }
+        Context::Type { scope, in_string } => (scope, in_string),
+    };
+    let mut candidates: Vec<String> = SUPPORTED_TYPES
+        .iter()
+        .filter(|ty| allowed_types.is_none_or(|allowed| allowed.iter().any(|a| a == *ty)))
+        .map(|ty| ty.to_string())
+        .collect();
+    // 枚举的底层类型只能是基础类型
+    if kind == Scope::Field {
+        candidates.extend(scan.enum_names);
+        candidates.extend(scan.packet_names);
+    }
+    Some(Completion {
+        kind: CompletionKind::Type,
+        candidates,
+        in_string,
+    })
+}
+
+/// 对象在定义中的位置，决定其中可以出现哪些键
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// 顶层对象：单个包或包集合
+    Root,
+    Packet,
+    Field,
+    Enum,
+    Enumerator,
+    Other,
+}
+
+impl Scope {
+    fn keys(self) -> Vec<&'static str> {
+        let mut keys: Vec<&'static str> = match self {
+            Scope::Root => PACKET_SET_KEYS.iter().chain(PACKET_KEYS).copied().collect(),
+            Scope::Packet => PACKET_KEYS.to_vec(),
+            Scope::Field => FIELD_KEYS
+                .iter()
+                .chain(&["group", "section", "reserved"])
+                .copied()
+                .collect(),
+            Scope::Enum => ENUM_KEYS.to_vec(),
+            Scope::Enumerator => ENUMERATOR_KEYS.to_vec(),
+            Scope::Other => Vec::new(),
+        };
+        let mut seen = Vec::new();
+        keys.retain(|key| {
+            let new = !seen.contains(key);
+            seen.push(*key);
+            new
+        });
+        keys
+    }
+
+    /// 该作用域的对象中，`key` 对应的数组里的元素所在的作用域
+    fn element_scope(self, key: Option<&str>) -> Scope {
+        match (self, key) {
+            (Scope::Root, Some("packets")) => Scope::Packet,
+            (Scope::Root | Scope::Packet, Some("fields")) | (Scope::Field, Some("group")) => {
+                Scope::Field
+            }
+            (Scope::Root | Scope::Packet, Some("enums")) => Scope::Enum,
+            (Scope::Enum, Some("values")) => Scope::Enumerator,
+            _ => Scope::Other,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Context {
+    Key {
+        scope: Scope,
+        /// 对象中光标之前已经写出的键
+        present: Vec<String>,
+        in_string: bool,
+    },
+    Type {
+        scope: Scope,
+        in_string: bool,
+    },
+}
+
+#[derive(Debug)]
+struct Frame {
+    object: bool,
+    /// 对象自身的作用域；数组为其元素的作用域
+    scope: Scope,
+    /// 对象中最近一个键，遇到 `,` 时清空
+    key: Option<String>,
+    expecting_key: bool,
+    keys: Vec<String>,
+}
+
+impl Frame {
+    fn new(object: bool, scope: Scope) -> Frame {
+        Frame {
+            object,
+            scope,
+            key: None,
+            expecting_key: object,
+            keys: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Scanner {
+    stack: Vec<Frame>,
+    context: Option<Context>,
+    packet_names: Vec<String>,
+    enum_names: Vec<String>,
+}
+
+impl Scanner {
+    /// 扫描整个文档：记录光标处的上下文，并收集文档中所有的包名与枚举名
+    fn scan(json_input: &str, offset: usize) -> Scanner {
+        let bytes = json_input.as_bytes();
+        let mut scanner = Scanner::default();
+        let mut i = 0;
+        while i < bytes.len() {
+            if scanner.context.is_none() && i >= offset {
+                scanner.context = scanner.context_here(false);
+            }
+            match bytes[i] {
+                b'"' => {
+                    let start = i;
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != b'"' {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                    let end = i.min(bytes.len());
+                    if scanner.context.is_none() && start < offset && offset <= end {
+                        scanner.context = scanner.context_here(true);
+                    }
+                    let text = json_input.get(start + 1..end).unwrap_or_default();
+                    scanner.string(text);
+                }
+                b'{' => scanner.open(true),
+                b'[' => scanner.open(false),
+                b'}' | b']' => {
+                    scanner.stack.pop();
+                }
+                b':' => {
+                    if let Some(frame) = scanner.stack.last_mut() {
+                        frame.expecting_key = false;
+                    }
+                }
+                b',' => {
+                    if let Some(frame) = scanner.stack.last_mut()
+                        && frame.object
+                    {
+                        frame.expecting_key = true;
+                        frame.key = None;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        if scanner.context.is_none() && offset >= bytes.len() {
+            scanner.context = scanner.context_here(false);
+        }
+        scanner
+    }
+
+    fn open(&mut self, object: bool) {
+        let scope = match self.stack.last() {
+            None if object => Scope::Root,
+            None => Scope::Packet,
+            Some(frame) if frame.object => {
+                if object {
+                    Scope::Other
+                } else {
+                    frame.scope.element_scope(frame.key.as_deref())
+                }
+            }
+            Some(frame) if object => frame.scope,
+            Some(_) => Scope::Other,
+        };
+        self.stack.push(Frame::new(object, scope));
+    }
+
+    fn string(&mut self, text: &str) {
+        let Some(frame) = self.stack.last_mut() else {
+            return;
+        };
+        if !frame.object {
+            return;
+        }
+        if frame.expecting_key {
+            frame.key = Some(text.to_string());
+            frame.keys.push(text.to_string());
+            return;
+        }
+        match (frame.scope, frame.key.as_deref()) {
+            (Scope::Root | Scope::Packet, Some("packet_name")) => {
+                self.packet_names.push(text.to_string())
+            }
+            (Scope::Enum, Some("name")) => self.enum_names.push(text.to_string()),
+            _ => {}
+        }
+    }
+
+    fn context_here(&self, in_string: bool) -> Option<Context> {
+        let frame = self.stack.last()?;
+        if !frame.object || frame.scope == Scope::Other {
+            return None;
+        }
+        if frame.expecting_key {
+            return frame.key.is_none().then(|| Context::Key {
+                scope: frame.scope,
+                present: frame.keys.clone(),
+                in_string,
+            });
+        }
+        let is_type = frame.key.as_deref() == Some("type")
+            && matches!(frame.scope, Scope::Field | Scope::Enum);
+        is_type.then_some(Context::Type {
+            scope: frame.scope,
+            in_string,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"{
+        "packet_name": "Gimbal",
+        "command_id": "0x0101",
+        "enums": [ { "name": "Mode", "type": "uint8_t", "values": [] } ],
+        "fields": [
+            { "name": "mode", "type": "Mode" },
+            { "name": "yaw", "type": "float", "comment": "偏航角" },
+            { "name": "imu", "group": [ { "name": "gyro", "type": "int16_t" } ] }
+        ]
+    }"#;
+
+    fn after(json: &str, needle: &str) -> usize {
+        json.find(needle).unwrap() + needle.len()
+    }
+
+    #[test]
+    fn test_hover_at() {
+        let hover = hover_at(JSON, after(JSON, r#""偏航"#)).unwrap();
+        assert_eq!(hover.packet, "Gimbal");
+        let field = &hover.layout.fields[hover.field.unwrap()];
+        assert_eq!((field.name.as_str(), field.byte_offset()), ("yaw", 1));
+        assert_eq!(field.size_bits, 32);
+
+        let gyro = hover_at(JSON, after(JSON, r#""gy"#)).unwrap();
+        assert_eq!(gyro.layout.fields[gyro.field.unwrap()].name, "gyro");
+
+        // 具名嵌套结构体与包级别的键只显示包的布局
+        let imu = hover_at(JSON, after(JSON, r#""im"#)).unwrap();
+        assert_eq!(imu.field, None);
+        let packet = hover_at(JSON, after(JSON, r#""0x01"#)).unwrap();
+        assert_eq!((packet.field, packet.layout.size), (None, 7));
+
+        assert_eq!(hover_at(&JSON[..JSON.len() - 2], 20), None);
+    }
+
+    #[test]
+    fn test_completion_keys() {
+        // 编辑途中的文档：最后一个字段还没有写完
+        let editing = r#"{ "packet_name": "A", "fields": [ { "name": "x", "ty"#;
+        let completion = completion_at(editing, editing.len(), None).unwrap();
+        assert_eq!(completion.kind, CompletionKind::Key);
+        assert!(completion.in_string);
+        assert!(completion.candidates.contains(&"type".to_string()));
+        assert!(!completion.candidates.contains(&"name".to_string()));
+        assert!(!completion.candidates.contains(&"packet_name".to_string()));
+
+        let packet = r#"{ "packet_name": "A", "#;
+        let completion = completion_at(packet, packet.len(), None).unwrap();
+        assert!(!completion.in_string);
+        assert!(completion.candidates.contains(&"fields".to_string()));
+        assert!(completion.candidates.contains(&"packets".to_string()));
+        assert!(!completion.candidates.contains(&"packet_name".to_string()));
+
+        let set = r#"{ "packets": [ { "packet_name": "A", "enums": [ { "values": [ { "#;
+        let completion = completion_at(set, set.len(), None).unwrap();
+        assert_eq!(completion.candidates, ["name", "value", "comment"]);
+
+        // 值的位置与未知对象中没有键可以补全
+        let value = r#"{ "packet_name": "#;
+        assert_eq!(completion_at(value, value.len(), None), None);
+        let other = r#"{ "fields": [ { "values": { "#;
+        assert_eq!(completion_at(other, other.len(), None), None);
+    }
+
+    #[test]
+    fn test_completion_types() {
+        let completion = completion_at(JSON, after(JSON, r#""type": "flo"#), None).unwrap();
+        assert_eq!(completion.kind, CompletionKind::Type);
+        assert!(completion.in_string);
+        assert_eq!(completion.candidates[0], "uint8_t");
+        assert!(completion.candidates.contains(&"Mode".to_string()));
+        assert!(completion.candidates.contains(&"Gimbal".to_string()));
+
+        // 枚举的底层类型不提供枚举与包
+        let underlying = completion_at(JSON, after(JSON, r#""type": "uint8"#), None).unwrap();
+        assert!(!underlying.candidates.contains(&"Mode".to_string()));
+
+        let allowed = vec!["uint8_t".to_string(), "float".to_string()];
+        let editing = r#"{ "fields": [ { "name": "x", "type": "#;
+        let completion = completion_at(editing, editing.len(), Some(&allowed)).unwrap();
+        assert!(!completion.in_string);
+        assert_eq!(completion.candidates, ["uint8_t", "float"]);
+    }
+}
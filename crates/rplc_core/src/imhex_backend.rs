@@ -0,0 +1,351 @@
+//! 生成 ImHex 的模式语言（`.hexpat`），在十六进制编辑器中交互地查看抓取的二进制日志
+//!
+//! 结构体按共享的布局计算逐个字段写出，对齐填充、固定偏移留下的空隙与保留字节写成 `padding`；
+//! 含位域的包按位流排列，整个包写成一个 `bitfield`
+
+use crate::config::{BitOrder, Config, Endianness};
+use crate::generator::{GenerateError, GenerateOptions};
+use crate::layout::{compute_layout, with_reserved_gaps};
+use crate::validator::{format_command_id, parse_array_type, parse_command_id};
+
+const BACKEND: &str = "ImHex";
+
+/// 模式语言的关键字与内置类型名，字段名与之相同时加下划线后缀
+const IMHEX_KEYWORDS: &[&str] = &[
+    "be",
+    "bitfield",
+    "bool",
+    "break",
+    "char",
+    "char16",
+    "continue",
+    "double",
+    "else",
+    "enum",
+    "false",
+    "float",
+    "fn",
+    "for",
+    "if",
+    "import",
+    "in",
+    "le",
+    "match",
+    "namespace",
+    "null",
+    "out",
+    "padding",
+    "parent",
+    "return",
+    "s8",
+    "s16",
+    "s32",
+    "s64",
+    "s128",
+    "str",
+    "struct",
+    "this",
+    "true",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "union",
+    "using",
+    "while",
+];
+
+/// C/C++ 基础类型在模式语言中的类型
+fn imhex_type(ty: &str) -> Result<&'static str, &'static str> {
+    match ty {
+        "float" => Ok("float"),
+        "double" => Ok("double"),
+        "long double" => Err("long double 的宽度依赖平台，且没有对应的类型"),
+        "_Bool" | "bool" => Ok("bool"),
+        "char" => Ok("char"),
+        "signed char" | "int8_t" => Ok("s8"),
+        "unsigned char" | "uint8_t" => Ok("u8"),
+        "short" | "signed short" | "int16_t" => Ok("s16"),
+        "unsigned short" | "uint16_t" => Ok("u16"),
+        "int" | "signed int" | "int32_t" => Ok("s32"),
+        "unsigned int" | "uint32_t" => Ok("u32"),
+        "long" | "signed long" | "long long" | "signed long long" | "int64_t" => Ok("s64"),
+        "unsigned long" | "unsigned long long" | "uint64_t" => Ok("u64"),
+        _ => Err("不支持的类型"),
+    }
+}
+
+pub(crate) fn generate_imhex(
+    config: &Config,
+    options: &GenerateOptions,
+) -> Result<String, GenerateError> {
+    let config = &with_reserved_gaps(config);
+    if let Some(field) = config
+        .fields
+        .iter()
+        .find(|f| f.group.as_ref().is_some_and(|g| g.name.is_some()))
+    {
+        return Err(unsupported(format!(
+            "字段 '{}' 位于具名嵌套结构体中，ImHex 后端尚不支持",
+            field.member_path()
+        )));
+    }
+    let cmd_id =
+        parse_command_id(&config.command_id).map_err(|_| GenerateError::ValidationError)?;
+    let layout = compute_layout(config).ok_or(GenerateError::ValidationError)?;
+    let packet_endianness = config.endianness.unwrap_or_default();
+    let name = identifier(&config.packet_name);
+    let i1 = options.indent(1);
+
+    let mut out = String::from("// 由 rplc 生成，请勿手动修改\n");
+    out.push_str(&format!(
+        "// {}：命令码 {}，数据段 {} 字节\n\n",
+        config.packet_name,
+        format_command_id(cmd_id),
+        layout.size
+    ));
+    out.push_str("#pragma once\n");
+    out.push_str(&format!(
+        "#pragma endian {}\n\n",
+        match packet_endianness {
+            Endianness::Little => "little",
+            Endianness::Big => "big",
+        }
+    ));
+
+    if layout.bitstream {
+        // 位流中的字段不一定从字节边界开始，只能整体写成 bitfield，每个字段按位宽读取
+        if config.bitfield_layout == Some(BitOrder::MsbFirst) {
+            return Err(unsupported(
+                "按 msb_first 排列的位域与 ImHex 的 bitfield 位序不同".to_string(),
+            ));
+        }
+        if packet_endianness == Endianness::Big {
+            return Err(unsupported(
+                "大端包中的位域与 ImHex 的 bitfield 位序不同".to_string(),
+            ));
+        }
+        out.push_str(&format!("bitfield {} {{\n", name));
+        let mut cursor = 0;
+        for (field, field_layout) in config.fields.iter().zip(&layout.fields) {
+            let (base_type, _) =
+                parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+            if field_layout.array_len.is_some() || matches!(base_type, "float" | "double") {
+                return Err(unsupported(format!(
+                    "字段 '{}' 位于位流中，ImHex 的 bitfield 只能按位宽读取整数",
+                    field.name
+                )));
+            }
+            push_bit_padding(&mut out, &i1, field_layout.offset_bits - cursor);
+            cursor = field_layout.offset_bits + field_layout.size_bits;
+            if field.reserved.is_some() {
+                push_bit_padding(&mut out, &i1, field_layout.size_bits);
+                continue;
+            }
+            out.push_str(&format!(
+                "{i1}{} : {}{};\n",
+                identifier(&field.name),
+                field_layout.size_bits,
+                comment_attribute(field.comment.as_ref().map(|c| c.as_str()))
+            ));
+        }
+        push_bit_padding(&mut out, &i1, layout.size * 8 - cursor);
+    } else {
+        for def in &config.enums {
+            let (base_type, _) = parse_array_type(&def.ty).ok_or(GenerateError::ValidationError)?;
+            let ty = imhex_type(base_type)
+                .map_err(|reason| unsupported(format!("枚举 '{}': {}", def.name, reason)))?;
+            out.push_str(&format!("enum {} : {} {{\n", identifier(&def.name), ty));
+            for value in &def.values {
+                out.push_str(&format!(
+                    "{i1}{} = {},\n",
+                    identifier(&value.name),
+                    value.value
+                ));
+            }
+            out.push_str("};\n\n");
+        }
+
+        out.push_str(&format!("struct {} {{\n", name));
+        let mut cursor = 0;
+        for (field, field_layout) in config.fields.iter().zip(&layout.fields) {
+            let offset = field_layout.offset_bits / 8;
+            push_padding(&mut out, &i1, offset - cursor);
+            cursor = offset + field_layout.size_bits / 8;
+            if field.reserved.is_some() {
+                push_padding(&mut out, &i1, field_layout.size_bits / 8);
+                continue;
+            }
+            let (base_type, array_len) =
+                parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+            let mut ty = match &field.enum_type {
+                Some(enum_name) => identifier(enum_name),
+                None => imhex_type(base_type)
+                    .map_err(|reason| unsupported(format!("字段 '{}': {}", field.name, reason)))?
+                    .to_string(),
+            };
+            let field_endianness = config.field_endianness(field);
+            if field_endianness != packet_endianness {
+                let prefix = match field_endianness {
+                    Endianness::Little => "le",
+                    Endianness::Big => "be",
+                };
+                ty = format!("{} {}", prefix, ty);
+            }
+            let len = array_len
+                .map(|len| format!("[{}]", len))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "{i1}{} {}{}{};\n",
+                ty,
+                identifier(&field.name),
+                len,
+                comment_attribute(field.comment.as_ref().map(|c| c.as_str()))
+            ));
+        }
+        push_padding(&mut out, &i1, layout.size - cursor);
+    }
+    out.push_str(&format!(
+        "}}{};\n\n",
+        comment_attribute(config.comment.as_ref().map(|c| c.as_str()))
+    ));
+
+    out.push_str("// 单独查看一个数据段时取消注释：\n");
+    out.push_str(&format!("// {} packet @ 0x00;\n", name));
+    Ok(out)
+}
+
+fn push_padding(out: &mut String, indent: &str, bytes: u32) {
+    if bytes > 0 {
+        out.push_str(&format!("{}padding[{}];\n", indent, bytes));
+    }
+}
+
+fn push_bit_padding(out: &mut String, indent: &str, bits: u32) {
+    if bits > 0 {
+        out.push_str(&format!("{}padding : {};\n", indent, bits));
+    }
+}
+
+/// 注释写成 `[[comment(...)]]` 属性，在 ImHex 的模式数据视图中显示
+fn comment_attribute(comment: Option<&str>) -> String {
+    match comment {
+        Some(text) if !text.trim().is_empty() => {
+            format!(" [[comment({})]]", imhex_string(text.trim()))
+        }
+        _ => String::new(),
+    }
+}
+
+/// 与关键字或内置类型名冲突的名称加下划线后缀
+fn identifier(name: &str) -> String {
+    if IMHEX_KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn imhex_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn unsupported(reason: String) -> GenerateError {
+    GenerateError::Unsupported {
+        backend: BACKEND,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(json: &str) -> Result<String, GenerateError> {
+        let config: Config = serde_json::from_str(json).unwrap();
+        generate_imhex(&config, &GenerateOptions::default())
+    }
+
+    #[test]
+    fn test_generate_imhex_struct() {
+        let code = generate(
+            r#"{
+                "packet_name": "GimbalCommand",
+                "command_id": "0x0104",
+                "packed": false,
+                "comment": "云台控制",
+                "enums": [{ "name": "Mode", "type": "uint8_t", "values": [
+                    { "name": "idle", "value": 0 }, { "name": "in", "value": 1 }] }],
+                "fields": [
+                    { "name": "mode", "type": "Mode", "comment": "模式 \"A\"" },
+                    { "name": "yaw", "type": "float" },
+                    { "name": "count", "type": "uint16_t", "endianness": "big" },
+                    { "name": "padding", "type": "char[3]" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(code.starts_with(
+            "// 由 rplc 生成，请勿手动修改\n// GimbalCommand：命令码 0x0104，数据段 16 字节\n\n#pragma once\n#pragma endian little\n\n"
+        ));
+        assert!(code.contains("enum Mode : u8 {\n    idle = 0,\n    in_ = 1,\n};\n"));
+        assert!(code.contains(
+            "struct GimbalCommand {\n    Mode mode [[comment(\"模式 \\\"A\\\"\")]];\n    padding[3];\n    float yaw;\n"
+        ));
+        assert!(code.contains("    be u16 count;\n    char padding_[3];\n    padding[3];\n}"));
+        assert!(code.contains("} [[comment(\"云台控制\")]];\n"));
+        assert!(code.ends_with("// GimbalCommand packet @ 0x00;\n"));
+    }
+
+    #[test]
+    fn test_generate_imhex_bitfield() {
+        let code = generate(
+            r#"{
+                "packet_name": "Flags",
+                "command_id": "2",
+                "fields": [
+                    { "name": "armed", "type": "uint8_t", "bit_field": 1, "comment": "解锁" },
+                    { "name": "mode", "type": "uint8_t", "bit_field": 3 },
+                    { "name": "speed", "type": "uint16_t" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(code.contains(
+            "bitfield Flags {\n    armed : 1 [[comment(\"解锁\")]];\n    mode : 3;\n    speed : 16;\n    padding : 4;\n};\n"
+        ));
+
+        let msb_first = r#"{"packet_name":"A","command_id":"1","bitfield_layout":"msb_first",
+            "fields":[{"name":"flags","type":"uint8_t","bit_field":3}]}"#;
+        let float_in_stream = r#"{"packet_name":"A","command_id":"1",
+            "fields":[{"name":"flags","type":"uint8_t","bit_field":3},{"name":"x","type":"float"}]}"#;
+        let long_double = r#"{"packet_name":"A","command_id":"1",
+            "fields":[{"name":"x","type":"long double"}]}"#;
+        for json in [msb_first, float_in_stream, long_double] {
+            assert!(
+                matches!(
+                    generate(json),
+                    Err(GenerateError::Unsupported {
+                        backend: "ImHex",
+                        ..
+                    })
+                ),
+                "{}",
+                json
+            );
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! 编辑器集成与监视模式使用的增量校验
+//!
+//! 按上一个版本中每个包的位置找到新版本中内容未变的包，直接复用上一次的诊断；
+//! 跨包检查只依赖包名与命令 ID，每次都对整个文档重新进行
+
+use tracing::debug;
+
+use crate::diagnostics::RplcDiagnostic;
+use crate::validator::{
+    PacketSource, ValidateOptions, check_input_size, validate_multiple_with, validate_packet,
+};
+
+/// 上一个版本中的一个包
+#[derive(Debug)]
+struct PacketRecord {
+    start: usize,
+    len: usize,
+    inherited_schema: Option<u32>,
+    /// 相对于包源文本的诊断
+    diags: Vec<RplcDiagnostic>,
+}
+
+/// 增量校验器，结果与 `validate_multiple_with_options` 相同
+#[derive(Debug)]
+pub struct IncrementalValidator {
+    options: ValidateOptions,
+    text: String,
+    packets: Vec<PacketRecord>,
+    revalidated: usize,
+}
+
+impl IncrementalValidator {
+    pub fn new(options: ValidateOptions) -> Self {
+        Self {
+            options,
+            text: String::new(),
+            packets: Vec::new(),
+            revalidated: 0,
+        }
+    }
+
+    /// 更换校验选项，之后的第一次校验会重新校验所有包
+    pub fn set_options(&mut self, options: ValidateOptions) {
+        self.options = options;
+        self.packets.clear();
+    }
+
+    /// 上一次 `update` 中重新校验的包数
+    pub fn revalidated(&self) -> usize {
+        self.revalidated
+    }
+
+    /// 校验文档的新版本
+    pub fn update(&mut self, json_input: &str) -> Vec<RplcDiagnostic> {
+        if let Some(diag) = check_input_size(json_input, self.options.max_input_size) {
+            self.text.clear();
+            self.packets.clear();
+            self.revalidated = 0;
+            return vec![diag];
+        }
+        let previous = std::mem::take(&mut self.packets);
+        let old_text = std::mem::take(&mut self.text);
+        let options = &self.options;
+        let mut packets = Vec::new();
+        let mut revalidated = 0;
+
+        // 编辑点之前的包位置不变，之后的包整体移动了文档长度的变化量
+        let candidates = |start: usize| {
+            [
+                Some(start),
+                (start + old_text.len()).checked_sub(json_input.len()),
+            ]
+        };
+        let diags = validate_multiple_with(json_input, &mut |packet| {
+            let reused = candidates(packet.start)
+                .into_iter()
+                .flatten()
+                .find_map(|start| {
+                    let index = previous
+                        .binary_search_by_key(&start, |record| record.start)
+                        .ok()?;
+                    let record = &previous[index];
+                    let unchanged = record.inherited_schema == packet.inherited_schema
+                        && old_text.get(start..start + record.len) == Some(packet.text);
+                    unchanged.then(|| record.diags.clone())
+                });
+            let diags = reused.unwrap_or_else(|| {
+                revalidated += 1;
+                validate_packet(packet.text, options, packet.inherited_schema)
+            });
+            packets.push(record(&packet, diags.clone()));
+            diags
+        });
+
+        debug!(packets = packets.len(), revalidated, "增量校验");
+        self.text = json_input.to_string();
+        self.packets = packets;
+        self.revalidated = revalidated;
+        diags
+    }
+}
+
+fn record(packet: &PacketSource, diags: Vec<RplcDiagnostic>) -> PacketRecord {
+    PacketRecord {
+        start: packet.start,
+        len: packet.text.len(),
+        inherited_schema: packet.inherited_schema,
+        diags,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::ValidationCode;
+    use crate::validator::validate_multiple_with_options;
+
+    fn packet(name: &str, cmd: &str, ty: &str) -> String {
+        format!(
+            r#"{{"packet_name":"{}","command_id":"{}","comment":"c","fields":[{{"name":"a","type":"{}","comment":"c"}}]}}"#,
+            name, cmd, ty
+        )
+    }
+
+    fn document(packets: &[String]) -> String {
+        format!("[\n  {}\n]", packets.join(",\n  "))
+    }
+
+    fn packets(count: usize) -> Vec<String> {
+        (1..=count)
+            .map(|i| packet(&format!("P{}", i), &format!("0x{:04X}", i), "uint8_t"))
+            .collect()
+    }
+
+    /// 增量结果必须与完整校验逐项相同，包括位置
+    fn check(validator: &mut IncrementalValidator, json: &str) -> Vec<RplcDiagnostic> {
+        let diags = validator.update(json);
+        assert_eq!(
+            diags,
+            validate_multiple_with_options(json, &ValidateOptions::default())
+        );
+        diags
+    }
+
+    #[test]
+    fn test_incremental_revalidates_edited_packet_only() {
+        let mut validator = IncrementalValidator::new(ValidateOptions::default());
+        let mut list = packets(5);
+        assert!(check(&mut validator, &document(&list)).is_empty());
+        assert_eq!(validator.revalidated(), 5);
+
+        // 修改中间的包，长度改变后其后的包整体移动
+        list[2] = packet("P3", "0x0003", "uint42_t");
+        let json = document(&list);
+        let diags = check(&mut validator, &json);
+        assert_eq!(validator.revalidated(), 1);
+        let (offset, len) = diags[0].span.unwrap();
+        assert_eq!(&json[offset..offset + len], r#""uint42_t""#);
+
+        // 内容不变时不重新校验任何包
+        check(&mut validator, &json);
+        assert_eq!(validator.revalidated(), 0);
+
+        // 在开头插入新包，原有的包都只是移动了位置
+        list.insert(0, packet("P0", "0x0100", "float"));
+        check(&mut validator, &document(&list));
+        assert_eq!(validator.revalidated(), 1);
+    }
+
+    #[test]
+    fn test_incremental_reruns_cross_packet_rules() {
+        let mut validator = IncrementalValidator::new(ValidateOptions::default());
+        let mut list = packets(4);
+        check(&mut validator, &document(&list));
+
+        list[3] = list[3].replace("0x0004", "0x0001");
+        let json = document(&list);
+        let diags = check(&mut validator, &json);
+        assert_eq!(validator.revalidated(), 1);
+        assert_eq!(
+            diags.iter().map(|d| &d.code).collect::<Vec<_>>(),
+            vec![&ValidationCode::DuplicateCommandId(
+                "0x0001".to_string(),
+                "P1".to_string()
+            )]
+        );
+
+        // 修复后跨包错误随之消失，其他包仍然复用
+        list[0] = list[0].replace("0x0001", "0x0009");
+        assert!(check(&mut validator, &document(&list)).is_empty());
+        assert_eq!(validator.revalidated(), 1);
+    }
+
+    #[test]
+    fn test_incremental_set_schema_change_revalidates_all() {
+        let mut validator = IncrementalValidator::new(ValidateOptions::default());
+        let list = packets(3);
+        let set = |schema: u32| {
+            format!(
+                r#"{{"rplc_schema":{},"packets":[{}]}}"#,
+                schema,
+                list.join(",")
+            )
+        };
+        check(&mut validator, &set(1));
+        check(&mut validator, &set(2));
+        assert_eq!(validator.revalidated(), 3);
+
+        validator.set_options(ValidateOptions {
+            max_payload_size: Some(1),
+            ..ValidateOptions::default()
+        });
+        validator.update(&set(2));
+        assert_eq!(validator.revalidated(), 3);
+    }
+}
@@ -0,0 +1,313 @@
+//! 定义文件格式的 JSON Schema，供编辑器在运行 rplc 之前补全键与检查结构
+//!
+//! Schema 只描述结构与取值范围；类型能否在目标语言中表示、位域是否越过存储单元等需要计算布局的检查仍由 rplc 完成
+
+use serde_json::{Value, json};
+
+use crate::config::CURRENT_SCHEMA;
+use crate::suggest::SUPPORTED_TYPES;
+
+const IDENTIFIER_PATTERN: &str = "^[A-Za-z_][A-Za-z0-9_]*$";
+
+/// JSON Schema（draft 2020-12）文本：顶层可以是单个包、包的数组或带 `protocol_version` 的包集合
+pub fn config_schema() -> String {
+    let schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "rplc 协议定义",
+        "description": "单个包、包的数组，或带 protocol_version 的包集合",
+        "anyOf": [
+            { "$ref": "#/$defs/packet" },
+            { "type": "array", "items": { "$ref": "#/$defs/packet" }, "minItems": 1 },
+            { "$ref": "#/$defs/packetSet" }
+        ],
+        "$defs": {
+            "packetSet": packet_set(),
+            "packet": packet(),
+            "fieldEntry": {
+                "description": "字段、保留字节、分组标题或嵌套结构体",
+                "anyOf": [
+                    { "$ref": "#/$defs/field" },
+                    { "$ref": "#/$defs/reserved" },
+                    { "$ref": "#/$defs/section" },
+                    { "$ref": "#/$defs/group" }
+                ]
+            },
+            "field": field(),
+            "reserved": {
+                "type": "object",
+                "description": "有意留空的字节，生成 uint8_t 数组 _reserved<序号>",
+                "properties": {
+                    "reserved": { "type": "integer", "minimum": 1, "description": "保留的字节数" },
+                    "comment": { "$ref": "#/$defs/comment" }
+                },
+                "required": ["reserved"],
+                "additionalProperties": false
+            },
+            "section": {
+                "type": "object",
+                "description": "分组标题，写入其后第一个字段之前的注释与文档",
+                "properties": { "section": { "type": "string" } },
+                "required": ["section"],
+                "additionalProperties": false
+            },
+            "group": {
+                "type": "object",
+                "description": "嵌套结构体，只用于组织代码；紧凑布局与把其中的字段直接写在包中相同，省略 name 时生成匿名结构体",
+                "properties": {
+                    "name": identifier("成员名称"),
+                    "comment": { "$ref": "#/$defs/comment" },
+                    "group": { "type": "array", "items": { "$ref": "#/$defs/fieldEntry" }, "minItems": 1 }
+                },
+                "required": ["group"],
+                "additionalProperties": false
+            },
+            "enum": {
+                "type": "object",
+                "description": "具名枚举，生成 enum class 名称 : 底层类型",
+                "properties": {
+                    "name": identifier("枚举名，字段的 type 可以引用"),
+                    "type": {
+                        "description": "底层整数类型，决定引用它的字段的大小",
+                        "enum": SUPPORTED_TYPES
+                            .iter()
+                            .filter(|ty| !matches!(**ty, "float" | "double" | "long double" | "bool" | "_Bool"))
+                            .collect::<Vec<_>>()
+                    },
+                    "comment": { "$ref": "#/$defs/comment" },
+                    "values": { "type": "array", "items": { "$ref": "#/$defs/enumerator" }, "minItems": 1 }
+                },
+                "required": ["name", "type", "values"],
+                "additionalProperties": false
+            },
+            "enumerator": {
+                "type": "object",
+                "properties": {
+                    "name": identifier("枚举项名称"),
+                    "value": { "type": "integer", "description": "取值，必须显式写出" },
+                    "comment": { "$ref": "#/$defs/comment" }
+                },
+                "required": ["name", "value"],
+                "additionalProperties": false
+            },
+            "comment": {
+                "description": "注释文本，或按语言代码给出的多语言文本，例如 {\"zh\": \"偏航角\", \"en\": \"Yaw angle\"}",
+                "anyOf": [
+                    { "type": ["string", "null"] },
+                    { "type": "object", "additionalProperties": { "type": "string" }, "minProperties": 1 }
+                ]
+            }
+        }
+    });
+    let mut text = serde_json::to_string_pretty(&schema).expect("JSON 值总能写出");
+    text.push('\n');
+    text
+}
+
+fn identifier(description: &str) -> Value {
+    json!({ "type": "string", "pattern": IDENTIFIER_PATTERN, "description": description })
+}
+
+fn packet_set() -> Value {
+    json!({
+        "type": "object",
+        "description": "带集合级元数据的多包定义",
+        "properties": {
+            "rplc_schema": schema_version(),
+            "protocol_version": {
+                "type": "string",
+                "pattern": "^[0-9]+\\.[0-9]+\\.[0-9]+$",
+                "description": "协议版本号 主版本.次版本.修订号，写入注册表与握手包"
+            },
+            "packets": { "type": "array", "items": { "$ref": "#/$defs/packet" } }
+        },
+        "required": ["packets"],
+        "additionalProperties": false
+    })
+}
+
+fn schema_version() -> Value {
+    json!({
+        "type": "integer",
+        "enum": (1..=CURRENT_SCHEMA).collect::<Vec<_>>(),
+        "description": format!("定义文件的格式版本，省略时为 1，当前为 {}", CURRENT_SCHEMA)
+    })
+}
+
+fn packet() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "rplc_schema": schema_version(),
+            "packet_name": identifier("生成的 C++ 结构体名称"),
+            "command_id": {
+                "type": "string",
+                "pattern": "^\\s*(0[xX][0-9A-Fa-f]+(_[0-9A-Fa-f]+)*|[0-9]+)\\s*$",
+                "description": "16 位命令 ID，十进制或 0x 开头的十六进制，例如 \"0x0104\""
+            },
+            "namespace": {
+                "type": ["string", "null"],
+                "pattern": "^[A-Za-z_][A-Za-z0-9_]*(::[A-Za-z_][A-Za-z0-9_]*)*$",
+                "description": "C++ 命名空间，例如 Robot::Sensors；null 表示全局命名空间"
+            },
+            "packed": {
+                "type": "boolean",
+                "default": true,
+                "description": "为 true 时字段按字节连续排列，生成 __attribute__((packed)) 或所选编译器的等价写法；\
+    为 false 时按自然对齐插入填充。含位域的包总是按位流连续排列"
+            },
+            "header_guard": { "type": ["string", "null"], "description": "头文件保护宏，默认由包名生成" },
+            "includes": {
+                "type": "array",
+                "items": { "type": "string", "minLength": 1 },
+                "description": "替代 <cstdint> 提供定宽整数类型的头文件，<...> 形式按原样包含，其余写成 \"...\""
+            },
+            "align": {
+                "type": ["integer", "null"],
+                "minimum": 1,
+                "description": "结构体的对齐要求（字节），生成 alignas(N)；须为 2 的幂"
+            },
+            "expected_size": {
+                "type": "integer",
+                "minimum": 0,
+                "description": "期望的结构体字节数，生成 static_assert，与计算出的布局不符时报错"
+            },
+            "bitfield_layout": {
+                "enum": ["lsb_first", "msb_first"],
+                "description": "位域在线上字节中的位序；声明后额外生成按该位序用掩码读写字节的 <包名>Wire 访问函数，与编译器无关"
+            },
+            "volatile_view": { "type": "boolean", "default": false, "description": "额外生成读取 DMA 缓冲区的 volatile 视图" },
+            "serialize": { "type": "boolean", "default": false, "description": "额外生成按 endianness 读写缓冲区的 serialize 与 deserialize" },
+            "endianness": endianness("线上数据的字节序，默认 little，只影响 serialize 与 deserialize"),
+            "crc": { "enum": ["dji"], "description": "帧校验方式，dji 额外生成按裁判系统协议打包整帧的 pack_frame_<包名> 与校验函数" },
+            "crc_append": {
+                "type": "boolean",
+                "default": true,
+                "description": "为 true 时在数据之后追加 CRC16，为 false 时由最后一个校验字段承载帧尾"
+            },
+            "emit_traits": {
+                "type": "boolean",
+                "default": true,
+                "description": "为 false 时不生成 RPL::Meta::PacketTraits 特化，也不引用 RPL 的头文件"
+            },
+            "traits_template": {
+                "type": ["string", "null"],
+                "description": "替代默认 PacketTraits 特化的代码模板，支持 ${name}、${cmd}、${size}、${hash} 占位符"
+            },
+            "comment": { "$ref": "#/$defs/comment" },
+            "enums": { "type": "array", "items": { "$ref": "#/$defs/enum" } },
+            "retired_field_ids": {
+                "type": "array",
+                "items": { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+                "description": "已删除字段曾使用的 ID，不能再分配给其他字段"
+            },
+            "fields": { "type": "array", "items": { "$ref": "#/$defs/fieldEntry" } }
+        },
+        "required": ["packet_name", "command_id", "fields"],
+        "additionalProperties": false
+    })
+}
+
+fn endianness(description: &str) -> Value {
+    json!({ "enum": ["little", "big"], "description": description })
+}
+
+fn field() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": identifier("结构体成员名称"),
+            "id": {
+                "type": "integer",
+                "minimum": 0,
+                "maximum": u32::MAX,
+                "description": "稳定的字段 ID，生成 k_<字段>_id 常量"
+            },
+            "type": {
+                "description": "C/C++ 基础类型、本包的枚举名或同一文件中其他包的名称，可在末尾写 [N] 表示数组",
+                "anyOf": [
+                    { "enum": SUPPORTED_TYPES },
+                    { "type": "string", "pattern": "^[A-Za-z_][A-Za-z0-9_ ]*(\\[[1-9][0-9]*\\])?$" }
+                ]
+            },
+            "array_len": { "type": "integer", "minimum": 1, "description": "数组长度，与在 type 后写 [N] 等价" },
+            "bit_field": {
+                "type": "integer",
+                "minimum": 1,
+                "maximum": 64,
+                "description": "位域宽度（位），不能超过基础类型的位数，只能用于整数类型。\
+    含位域的包按 BitLayout 位流连续排列，不受 packed 与自然对齐影响；原生位域的位序由编译器决定，\
+    与线上格式互通时请声明包的 bitfield_layout"
+            },
+            "offset": { "type": "integer", "minimum": 0, "description": "固定的起始字节，与前一字段之间的空隙由保留字节填充" },
+            "endianness": endianness("该字段的字节序，覆盖包的 endianness"),
+            "comment": { "$ref": "#/$defs/comment" },
+            "unit": { "type": "string", "description": "物理单位，例如 rad、m/s，只写入文档" },
+            "min": { "type": "number", "description": "遥测范围的最小值，生成 k_<字段>_min 常量" },
+            "max": { "type": "number", "description": "遥测范围的最大值，生成 k_<字段>_max 常量" },
+            "typical": { "type": "number", "description": "正常工作时的典型值" },
+            "values": {
+                "type": "object",
+                "description": "取值的显示名称，键为十进制或 0x 开头的十六进制整数，生成 <字段>_name 查找函数",
+                "propertyNames": { "pattern": "^\\s*(-?[0-9]+|0[xX][0-9A-Fa-f]+)\\s*$" },
+                "additionalProperties": { "$ref": "#/$defs/comment" }
+            }
+        },
+        "required": ["name", "type"],
+        "additionalProperties": false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suggest::{FIELD_KEYS, PACKET_KEYS, PACKET_SET_KEYS};
+
+    fn keys(schema: &Value, def: &str) -> Vec<String> {
+        let mut keys: Vec<String> = schema["$defs"][def]["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    fn sorted(keys: &[&str]) -> Vec<String> {
+        let mut keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_config_schema_matches_known_keys() {
+        let schema: Value = serde_json::from_str(&config_schema()).unwrap();
+        assert_eq!(
+            schema["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        // dependencies 由 rplc 在拆分多包时填入，不需要手写
+        let packet_keys: Vec<&str> = PACKET_KEYS
+            .iter()
+            .copied()
+            .filter(|key| *key != "dependencies")
+            .collect();
+        assert_eq!(keys(&schema, "packet"), sorted(&packet_keys));
+        assert_eq!(keys(&schema, "field"), sorted(FIELD_KEYS));
+        assert_eq!(keys(&schema, "packetSet"), sorted(PACKET_SET_KEYS));
+
+        let packet = &schema["$defs"]["packet"];
+        assert_eq!(packet["properties"]["packed"]["default"], true);
+        assert_eq!(
+            packet["properties"]["rplc_schema"]["enum"],
+            json!((1..=CURRENT_SCHEMA).collect::<Vec<_>>())
+        );
+        let field_types = &schema["$defs"]["field"]["properties"]["type"]["anyOf"][0]["enum"];
+        assert_eq!(field_types[0], "uint8_t");
+        let enum_types = schema["$defs"]["enum"]["properties"]["type"]["enum"]
+            .as_array()
+            .unwrap();
+        assert!(!enum_types.contains(&json!("float")));
+        assert!(config_schema().ends_with("}\n"));
+    }
+}
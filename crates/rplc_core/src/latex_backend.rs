@@ -0,0 +1,302 @@
+//! 生成技术报告用的 LaTeX 字段表格，每个包一个 `longtable`，超过一页时自动跨页并重复表头
+//!
+//! 表格使用 booktabs 与 longtable 宏包；中文注释需要 ctex 等支持中文的文档类
+
+use serde::Serialize;
+
+use crate::config::{Config, Field};
+use crate::generator::{GenerateError, GenerateOptions};
+use crate::layout::{FieldLayout, compute_layout, with_reserved_gaps};
+use crate::validator::{format_command_id, parse_command_id};
+
+/// LaTeX 表格的列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatexColumn {
+    /// 字段名
+    Name,
+    /// 字段类型，位域附带位宽，枚举字段写枚举名
+    Type,
+    /// 字节偏移，含位域的包以位为单位
+    Offset,
+    /// 占用的位数，数组为所有元素的位数之和
+    Bits,
+    /// 物理单位
+    Unit,
+    /// 取值范围与典型值
+    Range,
+    /// 注释
+    Description,
+}
+
+impl LatexColumn {
+    /// 未指定列时输出的列
+    pub const DEFAULT: [LatexColumn; 5] = [
+        LatexColumn::Name,
+        LatexColumn::Type,
+        LatexColumn::Bits,
+        LatexColumn::Unit,
+        LatexColumn::Description,
+    ];
+
+    pub const ALL: [LatexColumn; 7] = [
+        LatexColumn::Name,
+        LatexColumn::Type,
+        LatexColumn::Offset,
+        LatexColumn::Bits,
+        LatexColumn::Unit,
+        LatexColumn::Range,
+        LatexColumn::Description,
+    ];
+
+    /// 列的短名称，用于命令行参数
+    pub fn name(self) -> &'static str {
+        match self {
+            LatexColumn::Name => "name",
+            LatexColumn::Type => "type",
+            LatexColumn::Offset => "offset",
+            LatexColumn::Bits => "bits",
+            LatexColumn::Unit => "unit",
+            LatexColumn::Range => "range",
+            LatexColumn::Description => "description",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LatexColumn> {
+        let name = name.trim();
+        LatexColumn::ALL
+            .into_iter()
+            .find(|column| column.name().eq_ignore_ascii_case(name))
+    }
+
+    fn header(self, bitstream: bool) -> &'static str {
+        match self {
+            LatexColumn::Name => "字段",
+            LatexColumn::Type => "类型",
+            LatexColumn::Offset if bitstream => "偏移（位）",
+            LatexColumn::Offset => "偏移（字节）",
+            LatexColumn::Bits => "位数",
+            LatexColumn::Unit => "单位",
+            LatexColumn::Range => "范围",
+            LatexColumn::Description => "说明",
+        }
+    }
+
+    /// 数值列右对齐，说明列固定宽度并自动折行
+    fn spec(self) -> &'static str {
+        match self {
+            LatexColumn::Offset | LatexColumn::Bits => "r",
+            LatexColumn::Description => "p{0.4\\linewidth}",
+            _ => "l",
+        }
+    }
+
+    fn cell(self, field: &Field, placed: &FieldLayout, bitstream: bool) -> String {
+        match self {
+            LatexColumn::Name => format!("\\texttt{{{}}}", escape(&field.member_path())),
+            LatexColumn::Type => {
+                let ty = field.enum_type.as_deref().unwrap_or(&field.ty);
+                let ty = match field.bit_field {
+                    Some(bits) => format!("{} : {}", ty, bits),
+                    None => ty.to_string(),
+                };
+                format!("\\texttt{{{}}}", escape(&ty))
+            }
+            LatexColumn::Offset if bitstream => placed.offset_bits.to_string(),
+            LatexColumn::Offset => placed.byte_offset().to_string(),
+            LatexColumn::Bits => placed.size_bits.to_string(),
+            LatexColumn::Unit => field.unit.as_deref().map(escape).unwrap_or_default(),
+            LatexColumn::Range => {
+                let range = match (&field.min, &field.max) {
+                    (Some(min), Some(max)) => format!("{} \\textasciitilde{{}} {}", min, max),
+                    (Some(min), None) => format!("$\\geq$ {}", min),
+                    (None, Some(max)) => format!("$\\leq$ {}", max),
+                    (None, None) => String::new(),
+                };
+                match &field.typical {
+                    Some(typical) if range.is_empty() => format!("典型 {}", typical),
+                    Some(typical) => format!("{}（典型 {}）", range, typical),
+                    None => range,
+                }
+            }
+            LatexColumn::Description => field
+                .comment
+                .as_ref()
+                .map(|comment| escape(&single_line(comment.as_str())))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+pub(crate) fn generate_latex(
+    config: &Config,
+    options: &GenerateOptions,
+) -> Result<String, GenerateError> {
+    let config = &with_reserved_gaps(config);
+    let layout = compute_layout(config).ok_or(GenerateError::ValidationError)?;
+    let command_id = parse_command_id(&config.command_id)
+        .map(format_command_id)
+        .map_err(|_| GenerateError::ValidationError)?;
+    let columns = options
+        .latex_columns
+        .as_deref()
+        .unwrap_or(&LatexColumn::DEFAULT);
+    let spec: String = columns.iter().map(|column| column.spec()).collect();
+    let i1 = options.indent(1);
+
+    let mut caption = format!("\\texttt{{{}}}", escape(&config.packet_name));
+    if let Some(comment) = &config.comment {
+        caption.push('：');
+        caption.push_str(&escape(&single_line(comment.as_str())));
+    }
+    caption.push_str(&format!(
+        "（命令码 \\texttt{{{}}}，{} 字节）",
+        command_id, layout.size
+    ));
+    let header = columns
+        .iter()
+        .map(|column| column.header(layout.bitstream))
+        .collect::<Vec<_>>()
+        .join(" & ");
+
+    let mut out = String::from("% 由 rplc 生成，请勿手动修改\n");
+    out.push_str("% 需要 \\usepackage{booktabs} 与 \\usepackage{longtable}\n");
+    out.push_str(&format!("\\begin{{longtable}}{{{}}}\n", spec));
+    out.push_str(&format!(
+        "{i1}\\caption{{{}}}\\label{{tab:{}}} \\\\\n",
+        caption,
+        label(&config.packet_name)
+    ));
+    // 第一页与后续各页的表头相同，后续页不重复标题
+    for end in ["\\endfirsthead", "\\endhead"] {
+        out.push_str(&format!("{i1}\\toprule\n"));
+        out.push_str(&format!("{i1}{} \\\\\n", header));
+        out.push_str(&format!("{i1}\\midrule\n"));
+        out.push_str(&format!("{i1}{}\n", end));
+    }
+    out.push_str(&format!("{i1}\\bottomrule\n"));
+    out.push_str(&format!("{i1}\\endlastfoot\n"));
+    for (field, placed) in config.fields.iter().zip(&layout.fields) {
+        if let Some(section) = &field.section {
+            out.push_str(&format!(
+                "{i1}\\multicolumn{{{}}}{{l}}{{\\textbf{{{}}}}} \\\\\n",
+                columns.len(),
+                escape(section.trim())
+            ));
+        }
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| column.cell(field, placed, layout.bitstream))
+            .collect();
+        out.push_str(&format!("{i1}{} \\\\\n", cells.join(" & ")));
+    }
+    out.push_str("\\end{longtable}\n");
+    Ok(out)
+}
+
+/// 转义 LaTeX 的特殊字符
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '\\' => out.push_str("\\textbackslash{}"),
+            '<' => out.push_str("\\textless{}"),
+            '>' => out.push_str("\\textgreater{}"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 交叉引用的标签只保留字母、数字与连字符，例如 `tab:rplc-GimbalCommand`
+fn label(packet_name: &str) -> String {
+    let name: String = packet_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("rplc-{}", name)
+}
+
+/// 多行注释合并为一行，表格的单元格中不能有空行
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"{
+        "packet_name": "GimbalCommand",
+        "command_id": "0x0104",
+        "packed": true,
+        "comment": "云台控制 & 射击",
+        "fields": [
+            { "name": "yaw", "type": "float", "comment": "偏航角 50% 以内", "unit": "rad" },
+            { "section": "射击" },
+            { "name": "fire_mode", "type": "uint8_t", "bit_field": 3, "min": 0, "max": 5 },
+            { "name": "note", "type": "char[2]", "comment": "a_b {x} ~ \\ ^" }
+        ]
+    }"#;
+
+    fn generate(json: &str, columns: Option<Vec<LatexColumn>>) -> String {
+        let config: Config = serde_json::from_str(json).unwrap();
+        let options = GenerateOptions {
+            latex_columns: columns,
+            ..GenerateOptions::default()
+        };
+        generate_latex(&config, &options).unwrap()
+    }
+
+    #[test]
+    fn test_generate_latex_table() {
+        let tex = generate(JSON, None);
+        assert!(tex.contains("\\begin{longtable}{llrlp{0.4\\linewidth}}\n"));
+        assert!(tex.contains(
+            "    \\caption{\\texttt{GimbalCommand}：云台控制 \\& 射击（命令码 \\texttt{0x0104}，7 字节）}\\label{tab:rplc-GimbalCommand} \\\\\n"
+        ));
+        assert_eq!(
+            tex.matches("    字段 & 类型 & 位数 & 单位 & 说明 \\\\\n")
+                .count(),
+            2
+        );
+        assert!(
+            tex.contains(
+                "    \\texttt{yaw} & \\texttt{float} & 32 & rad & 偏航角 50\\% 以内 \\\\\n"
+            )
+        );
+        assert!(tex.contains("    \\multicolumn{5}{l}{\\textbf{射击}} \\\\\n"));
+        assert!(
+            tex.contains("    \\texttt{fire\\_mode} & \\texttt{uint8\\_t : 3} & 3 &  &  \\\\\n")
+        );
+        assert!(tex.contains(
+            "a\\_b \\{x\\} \\textasciitilde{} \\textbackslash{} \\textasciicircum{} \\\\\n"
+        ));
+        assert!(tex.ends_with("\\end{longtable}\n"));
+    }
+
+    #[test]
+    fn test_generate_latex_columns() {
+        let tex = generate(
+            JSON,
+            Some(vec![
+                LatexColumn::Name,
+                LatexColumn::Offset,
+                LatexColumn::Range,
+            ]),
+        );
+        assert!(tex.contains("\\begin{longtable}{lrl}\n"));
+        assert!(tex.contains("    字段 & 偏移（位） & 范围 \\\\\n"));
+        assert!(tex.contains("    \\texttt{fire\\_mode} & 32 & 0 \\textasciitilde{} 5 \\\\\n"));
+        assert!(tex.contains("    \\multicolumn{3}{l}{\\textbf{射击}} \\\\\n"));
+
+        assert_eq!(LatexColumn::from_name(" Unit "), Some(LatexColumn::Unit));
+        assert_eq!(LatexColumn::from_name("width"), None);
+    }
+}
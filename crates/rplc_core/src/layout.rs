@@ -0,0 +1,557 @@
+use serde::Serialize;
+
+use crate::config::{Comment, Config, Field, ReservedKind};
+use crate::validator::parse_array_type;
+
+/// 单个字段在包中的位置，偏移与长度均以位为单位
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldLayout {
+    pub name: String,
+    pub ty: String,
+    pub offset_bits: u32,
+    pub size_bits: u32,
+    pub array_len: Option<u32>,
+    pub bit_field: Option<u8>,
+    /// 与前一字段末尾（或包的开头）之间的填充位数，含对齐填充与固定偏移留下的空隙
+    pub padding_bits: u32,
+}
+
+impl FieldLayout {
+    /// 字段所在的字节偏移
+    pub fn byte_offset(&self) -> u32 {
+        self.offset_bits / 8
+    }
+
+    /// 字段在起始字节内的位偏移，只有位流布局中可能不为 0
+    pub fn bit_offset(&self) -> u32 {
+        self.offset_bits % 8
+    }
+}
+
+/// 整个包的内存布局
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PacketLayout {
+    pub fields: Vec<FieldLayout>,
+    /// 包的总字节数（含尾部填充）
+    pub size: u32,
+    /// 结构体的对齐要求（字节）
+    pub align: u32,
+    /// 含位域时按 BitLayout 位流计算布局
+    pub bitstream: bool,
+    /// 最后一个字段之后到包末尾的填充位数
+    pub tail_padding_bits: u32,
+}
+
+/// C/C++ 类型的字节大小，包括浮点类型
+pub fn c_type_size(ty: &str) -> Option<u32> {
+    match ty {
+        "float" => Some(4),
+        "double" => Some(8),
+        "long double" => Some(16),
+        _ => crate::validator::c_type_to_bit_field_size(ty).map(u32::from),
+    }
+}
+
+/// 计算包的内存布局：
+/// - 带 `offset` 的字段固定在该字节处，之前的空隙计为保留字节
+/// - 含位域时，所有字段按位流连续排列（与 BitLayout 一致）
+/// - packed 时，字段按字节连续排列
+/// - 否则按自然对齐插入填充
+///
+/// 类型为其他包的字段按 `dependencies` 中该包的大小与对齐排列；
+/// 存在无法识别的类型时返回 `None`
+pub fn compute_layout(config: &Config) -> Option<PacketLayout> {
+    compute_layout_in(config, &config.dependencies, 0)
+}
+
+/// 引用的包在 `dependencies` 中查找；嵌套层数超过依赖的个数说明引用成环
+pub(crate) fn compute_layout_in(
+    config: &Config,
+    dependencies: &[Config],
+    depth: usize,
+) -> Option<PacketLayout> {
+    if depth > dependencies.len() {
+        return None;
+    }
+    let bitstream = config.fields.iter().any(|f| f.bit_field.is_some());
+
+    let mut fields = Vec::with_capacity(config.fields.len());
+    let mut offset_bits: u32 = 0;
+    let mut align: u32 = 1;
+
+    for field in &config.fields {
+        let (base_type, array_len) = parse_array_type(&field.ty)?;
+        let (elem_size, elem_align) = match c_type_size(base_type) {
+            Some(size) => (size, size.min(8)),
+            None => {
+                let packet = dependencies.iter().find(|p| p.packet_name == base_type)?;
+                let nested = compute_layout_in(packet, dependencies, depth + 1)?;
+                (nested.size, nested.align)
+            }
+        };
+        let size_bits = match (array_len, field.bit_field) {
+            (Some(len), _) => elem_size.checked_mul(len)?.checked_mul(8)?,
+            (None, Some(bits)) => u32::from(bits),
+            (None, None) => elem_size * 8,
+        };
+
+        let end_bits = offset_bits;
+        if let Some(offset) = field.offset {
+            // 固定偏移之前的空隙由保留字节填充，与前面的字段重叠时无法给出布局
+            let pinned = offset.checked_mul(8)?;
+            if pinned < offset_bits {
+                return None;
+            }
+            offset_bits = pinned;
+        }
+
+        if !bitstream && !config.packed {
+            align = align.max(elem_align);
+            offset_bits = (offset_bits / 8)
+                .checked_next_multiple_of(elem_align)?
+                .checked_mul(8)?;
+        }
+
+        fields.push(FieldLayout {
+            name: field.name.clone(),
+            ty: base_type.to_string(),
+            offset_bits,
+            size_bits,
+            array_len,
+            bit_field: field.bit_field,
+            padding_bits: offset_bits - end_bits,
+        });
+        offset_bits = offset_bits.checked_add(size_bits)?;
+    }
+
+    if let Some(explicit) = config.align {
+        // 未经验证的输入可能带有非法的 align，此时无法给出布局
+        if !explicit.is_power_of_two() {
+            return None;
+        }
+        align = align.max(explicit);
+    }
+    let size = offset_bits.div_ceil(8).checked_next_multiple_of(align)?;
+
+    Some(PacketLayout {
+        fields,
+        size,
+        align,
+        bitstream,
+        tail_padding_bits: size.checked_mul(8)? - offset_bits,
+    })
+}
+
+/// 把包的布局格式化为对照协议文档用的文本表格，布局无法计算时返回 `None`
+///
+/// 偏移以字节为单位，位域等不从字节边界开始的字段另列字节内的位偏移；
+/// 大小与填充是整字节时以 `B` 为单位，否则以 `bit` 为单位
+pub fn layout_table(config: &Config) -> Option<String> {
+    let layout = compute_layout(config)?;
+    let mode = if layout.bitstream {
+        "位流"
+    } else if config.packed {
+        "紧凑"
+    } else {
+        "自然对齐"
+    };
+    let mut out = format!(
+        "{}  命令码 {}  共 {} 字节（{}，对齐 {}）\n",
+        config.packet_name, config.command_id, layout.size, mode, layout.align
+    );
+    let rows: Vec<[String; 6]> = config
+        .fields
+        .iter()
+        .zip(&layout.fields)
+        .map(|(field, placed)| {
+            let ty = match field.bit_field {
+                Some(bits) => format!("{} : {}", field.ty, bits),
+                None => field.ty.clone(),
+            };
+            [
+                placed.byte_offset().to_string(),
+                placed.bit_offset().to_string(),
+                format_bits(placed.size_bits),
+                match placed.padding_bits {
+                    0 => "-".to_string(),
+                    bits => format_bits(bits),
+                },
+                field.member_path(),
+                ty,
+            ]
+        })
+        .collect();
+    let header = ["偏移", "位", "大小", "填充", "字段", "类型"].map(String::from);
+    let widths: Vec<usize> = (0..6)
+        .map(|column| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .map(|row| display_width(&row[column]))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    for row in std::iter::once(&header).chain(&rows) {
+        let mut line = String::new();
+        for (column, cell) in row.iter().enumerate() {
+            let pad = " ".repeat(widths[column] - display_width(cell));
+            // 数值列右对齐，名称列左对齐
+            if column < 4 {
+                line.push_str(&format!("{}{}  ", pad, cell));
+            } else {
+                line.push_str(&format!("{}{}  ", cell, pad));
+            }
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    if layout.tail_padding_bits > 0 {
+        out.push_str(&format!(
+            "尾部填充 {}\n",
+            format_bits(layout.tail_padding_bits)
+        ));
+    }
+    Some(out)
+}
+
+fn format_bits(bits: u32) -> String {
+    if bits.is_multiple_of(8) {
+        format!("{} B", bits / 8)
+    } else {
+        format!("{} bit", bits)
+    }
+}
+
+/// 终端中的显示宽度，中日韩字符占两列
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if c >= '\u{1100}' { 2 } else { 1 })
+        .sum()
+}
+
+/// 把 `offset` 之前的空隙展开为 `reserved_<起始字节>` 保留字段，生成结构体成员时使用
+///
+/// 保留字段属于其后字段所在的嵌套结构体（前一字段也在其中时），布局无法计算时原样返回
+pub(crate) fn with_reserved_gaps(config: &Config) -> Config {
+    let mut expanded = config.clone();
+    let Some(layout) = compute_layout(config) else {
+        return expanded;
+    };
+    expanded.fields.clear();
+    let mut end_bits: u32 = 0;
+    for (field, placed) in config.fields.iter().zip(&layout.fields) {
+        let start = end_bits.div_ceil(8);
+        let gap = (placed.offset_bits / 8).saturating_sub(start);
+        if field.offset.is_some() && gap > 0 {
+            let previous_group = expanded.fields.last().and_then(|f| f.group.as_ref());
+            let group = field
+                .group
+                .clone()
+                .filter(|group| previous_group.map(|g| g.id) == Some(group.id));
+            expanded.fields.push(Field {
+                name: format!("reserved_{}", start),
+                ty: format!("uint8_t[{}]", gap),
+                comment: Some(Comment::Text("保留".to_string())),
+                group,
+                reserved: Some(ReservedKind::Gap),
+                ..Default::default()
+            });
+        }
+        end_bits = placed.offset_bits + placed.size_bits;
+        expanded.fields.push(field.clone());
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(json: &str) -> Config {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_packed_layout() {
+        let layout = compute_layout(&config(
+            r#"{
+                "packet_name": "P",
+                "command_id": "0x0101",
+                "fields": [
+                    { "name": "a", "type": "uint8_t" },
+                    { "name": "b", "type": "float" },
+                    { "name": "c", "type": "uint16_t[3]" }
+                ]
+            }"#,
+        ))
+        .unwrap();
+
+        assert_eq!(layout.size, 11);
+        assert_eq!(layout.fields[1].offset_bits, 8);
+        assert_eq!(layout.fields[2].offset_bits, 40);
+        assert_eq!(layout.fields[2].size_bits, 48);
+        assert_eq!(layout.fields[2].array_len, Some(3));
+    }
+
+    #[test]
+    fn test_unpacked_layout_aligns_fields() {
+        let layout = compute_layout(&config(
+            r#"{
+                "packet_name": "P",
+                "command_id": "0x0101",
+                "packed": false,
+                "fields": [
+                    { "name": "a", "type": "uint8_t" },
+                    { "name": "b", "type": "uint32_t" },
+                    { "name": "c", "type": "uint8_t" }
+                ]
+            }"#,
+        ))
+        .unwrap();
+
+        assert_eq!(layout.fields[1].offset_bits, 32);
+        assert_eq!(layout.fields[2].offset_bits, 64);
+        assert_eq!(layout.align, 4);
+        assert_eq!(layout.size, 12);
+        assert_eq!(layout.fields[1].padding_bits, 24);
+        assert_eq!(layout.fields[2].padding_bits, 0);
+        assert_eq!(layout.tail_padding_bits, 24);
+    }
+
+    #[test]
+    fn test_bit_field_layout_is_bitstream() {
+        let layout = compute_layout(&config(
+            r#"{
+                "packet_name": "P",
+                "command_id": "0x0101",
+                "fields": [
+                    { "name": "a", "type": "uint32_t", "bit_field": 12 },
+                    { "name": "b", "type": "uint32_t", "bit_field": 12 },
+                    { "name": "c", "type": "uint8_t" }
+                ]
+            }"#,
+        ))
+        .unwrap();
+
+        assert!(layout.bitstream);
+        assert_eq!(layout.fields[1].offset_bits, 12);
+        assert_eq!(layout.fields[1].byte_offset(), 1);
+        assert_eq!(layout.fields[1].bit_offset(), 4);
+        assert_eq!(layout.fields[2].offset_bits, 24);
+        assert_eq!(layout.size, 4);
+        assert_eq!(layout.tail_padding_bits, 0);
+    }
+
+    #[test]
+    fn test_explicit_align_pads_packed_struct() {
+        let layout = compute_layout(&config(
+            r#"{
+                "packet_name": "P",
+                "command_id": "0x0101",
+                "align": 4,
+                "fields": [
+                    { "name": "a", "type": "uint8_t" },
+                    { "name": "b", "type": "uint16_t" }
+                ]
+            }"#,
+        ))
+        .unwrap();
+
+        assert_eq!(layout.fields[1].offset_bits, 8);
+        assert_eq!(layout.align, 4);
+        assert_eq!(layout.size, 4);
+    }
+
+    #[test]
+    fn test_fixed_offsets_leave_reserved_gaps() {
+        let cfg = config(
+            r#"{
+                "packet_name": "P",
+                "command_id": "0x0101",
+                "fields": [
+                    { "name": "a", "type": "uint16_t" },
+                    { "name": "b", "type": "float", "offset": 4 },
+                    { "name": "c", "type": "uint8_t", "offset": 8 },
+                    { "name": "d", "type": "uint8_t" }
+                ]
+            }"#,
+        );
+        let layout = compute_layout(&cfg).unwrap();
+        assert_eq!(layout.fields[1].offset_bits, 32);
+        assert_eq!(layout.fields[2].offset_bits, 64);
+        assert_eq!(layout.fields[3].offset_bits, 72);
+        assert_eq!(layout.size, 10);
+        assert_eq!(layout.fields[1].padding_bits, 16);
+
+        // 没有空隙的 offset 不插入保留字段
+        let expanded = with_reserved_gaps(&cfg);
+        let members: Vec<(&str, &str, bool)> = expanded
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.ty.as_str(), f.reserved.is_some()))
+            .collect();
+        assert_eq!(
+            members,
+            vec![
+                ("a", "uint16_t", false),
+                ("reserved_2", "uint8_t[2]", true),
+                ("b", "float", false),
+                ("c", "uint8_t", false),
+                ("d", "uint8_t", false),
+            ]
+        );
+        let expanded_layout = compute_layout(&expanded).unwrap();
+        assert_eq!(expanded_layout.size, layout.size);
+        // 空隙改由保留字段占据，字段本身的位置不变
+        let position = |f: &FieldLayout| (f.offset_bits, f.size_bits);
+        assert_eq!(
+            position(&expanded_layout.fields[2]),
+            position(&layout.fields[1])
+        );
+        assert_eq!(expanded_layout.fields[2].padding_bits, 0);
+    }
+
+    #[test]
+    fn test_layout_table() {
+        let table = layout_table(&config(
+            r#"{
+                "packet_name": "Status",
+                "command_id": "0x0201",
+                "packed": false,
+                "fields": [
+                    { "name": "mode", "type": "uint8_t" },
+                    { "name": "hp", "type": "uint16_t" },
+                    { "name": "yaw", "type": "float" },
+                    { "name": "flag", "type": "uint8_t" }
+                ]
+            }"#,
+        ))
+        .unwrap();
+        assert_eq!(
+            table,
+            "Status  命令码 0x0201  共 12 字节（自然对齐，对齐 4）\n\
+             偏移  位  大小  填充  字段  类型\n\
+             \x20  0   0   1 B     -  mode  uint8_t\n\
+             \x20  2   0   2 B   1 B  hp    uint16_t\n\
+             \x20  4   0   4 B     -  yaw   float\n\
+             \x20  8   0   1 B     -  flag  uint8_t\n\
+             尾部填充 3 B\n"
+        );
+
+        let bits = layout_table(&config(
+            r#"{"packet_name":"F","command_id":"1",
+                "fields":[{"name":"a","type":"uint8_t","bit_field":3},{"name":"b","type":"uint8_t","bit_field":2}]}"#,
+        ))
+        .unwrap();
+        assert!(
+            bits.contains("   0   3  2 bit     -  b     uint8_t : 2\n"),
+            "{}",
+            bits
+        );
+        assert!(bits.ends_with("尾部填充 3 bit\n"));
+    }
+
+    #[test]
+    fn test_reserved_gap_joins_enclosing_group() {
+        let cfg = config(
+            r#"{
+                "packet_name": "P",
+                "command_id": "0x0101",
+                "fields": [
+                    { "name": "a", "type": "uint8_t" },
+                    { "name": "g", "group": [
+                        { "name": "b", "type": "uint8_t", "offset": 2 },
+                        { "name": "c", "type": "uint8_t", "offset": 4 }
+                    ] }
+                ]
+            }"#,
+        );
+        let expanded = with_reserved_gaps(&cfg);
+        let groups: Vec<(&str, Option<usize>)> = expanded
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.group.as_ref().map(|g| g.id)))
+            .collect();
+        let id = cfg.fields[1].group.as_ref().unwrap().id;
+        assert_eq!(
+            groups,
+            vec![
+                ("a", None),
+                ("reserved_1", None),
+                ("b", Some(id)),
+                ("reserved_3", Some(id)),
+                ("c", Some(id)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overlapping_offset_has_no_layout() {
+        let cfg = config(
+            r#"{
+                "packet_name": "P",
+                "command_id": "0x0101",
+                "fields": [
+                    { "name": "a", "type": "uint32_t" },
+                    { "name": "b", "type": "uint8_t", "offset": 2 }
+                ]
+            }"#,
+        );
+        assert!(compute_layout(&cfg).is_none());
+        assert_eq!(with_reserved_gaps(&cfg), cfg);
+    }
+
+    #[test]
+    fn test_invalid_align_has_no_layout() {
+        for align in ["0", "3", "6"] {
+            let cfg = config(&format!(
+                r#"{{ "packet_name": "P", "command_id": "0x0101", "align": {}, "fields": [] }}"#,
+                align
+            ));
+            assert!(compute_layout(&cfg).is_none(), "align = {}", align);
+        }
+    }
+
+    #[test]
+    fn test_packet_typed_field_layout() {
+        let mut imu = config(
+            r#"{"packet_name":"Imu","command_id":"1","packed":false,"fields":[
+                {"name":"id","type":"uint8_t"},{"name":"gyro","type":"float[3]"}]}"#,
+        );
+        let mut gimbal = config(
+            r#"{"packet_name":"Gimbal","command_id":"2","packed":false,"fields":[
+                {"name":"mode","type":"uint8_t"},{"name":"imu","type":"Imu[2]"}]}"#,
+        );
+        assert!(compute_layout(&gimbal).is_none());
+
+        gimbal.dependencies = vec![imu.clone()];
+        let layout = compute_layout(&gimbal).unwrap();
+        // Imu 占 16 字节、按 4 字节对齐
+        assert_eq!(layout.fields[1].offset_bits, 32);
+        assert_eq!(layout.fields[1].size_bits, 2 * 16 * 8);
+        assert_eq!((layout.size, layout.align), (36, 4));
+
+        // 成环的引用没有布局
+        imu.fields[0].ty = "Gimbal".to_string();
+        gimbal.dependencies = vec![
+            imu.clone(),
+            Config {
+                dependencies: Vec::new(),
+                ..gimbal.clone()
+            },
+        ];
+        assert!(compute_layout(&gimbal).is_none());
+    }
+
+    #[test]
+    fn test_unknown_type_has_no_layout() {
+        let cfg = config(
+            r#"{
+                "packet_name": "P",
+                "command_id": "0x0101",
+                "fields": [ { "name": "a", "type": "Custom" } ]
+            }"#,
+        );
+        assert!(compute_layout(&cfg).is_none());
+    }
+}
@@ -1,9 +1,107 @@
+mod audit;
+mod backend;
+mod baseline;
+mod bench;
+mod bridge;
+mod c_backend;
+mod cache;
+mod canonical;
+mod chunked;
+mod compiler;
 mod config;
+mod cpp_includes;
 mod diagnostics;
+mod diff;
+mod duplicate_keys;
+mod encoding;
+mod explain;
+mod fingerprint;
+mod format;
 mod generator;
+mod handshake;
+mod header_import;
+mod header_template;
+mod ide;
+mod imhex_backend;
+mod incremental;
+mod json_schema;
+mod latex_backend;
+mod layout;
+mod markdown;
+mod matlab_backend;
+mod migrate;
+mod output_path;
+mod plugin;
+mod probe;
+mod project;
+#[cfg(test)]
+mod proptests;
+mod python_backend;
+mod rules;
+mod rust_backend;
+mod scaffold;
+mod suggest;
+mod svg_backend;
+mod table_import;
+mod target;
+mod toml;
+mod transcript;
 mod validator;
+mod yaml;
 
-pub use config::{Config, ConfigOrArray};
-pub use diagnostics::{Severity, ValidationCode};
-pub use generator::{GenerateError, MultiGenerateError, generate, generate_multiple};
-pub use validator::{validate, validate_multiple};
+pub use audit::{DeterminismMismatch, audit_determinism};
+pub use backend::{Backend, generate_backends};
+pub use baseline::{BaselineEntry, DiagnosticBaseline, diagnostic_location};
+pub use bench::{BenchBaseline, BenchComparison, Workload, bench_workloads, compare_bench};
+pub use bridge::generate_python_bridge;
+pub use cache::{CacheStats, ValidationCache};
+pub use canonical::canonicalize;
+pub use chunked::{ChunkedValidation, DiagnosticBatch};
+pub use compiler::{Compiler, CppStandard};
+pub use config::{
+    BitOrder, CURRENT_SCHEMA, Comment, Config, ConfigOrArray, CrcKind, DEFAULT_COMMENT_LANGUAGE,
+    Endianness, EnumDef, Enumerator, LEGACY_SCHEMA, PacketSet, ProtocolVersion, ReservedKind,
+};
+pub use diagnostics::{RplcDiagnostic, Severity, ValidationCode};
+pub use diff::{
+    Change, ChangeKind, ChangeReport, Compatibility, LayoutBreak, LayoutBreakKind, VersionBump,
+    VersionBumpError, check_version_bump, diff, diff_configs, layout_breaks,
+};
+pub use encoding::decode_source;
+pub use explain::{explain, explained_codes};
+pub use fingerprint::layout_hash;
+pub use format::format_definition;
+pub use generator::{
+    BraceStyle, GenerateError, GenerateOptions, MultiGenerateError, generate,
+    generate_compat_shims, generate_multiple, generate_multiple_with_options, generate_registry,
+    generate_umbrella, generate_with_options,
+};
+pub use handshake::generate_protocol_info;
+pub use header_import::{HeaderImport, ImportedHeader, import_header};
+pub use header_template::{
+    DEFAULT_HEADER_TEMPLATE, HeaderTemplate, TemplateError, TemplateErrorKind,
+};
+pub use ide::{Completion, CompletionKind, HoverInfo, completion_at, hover_at};
+pub use incremental::IncrementalValidator;
+pub use json_schema::config_schema;
+pub use latex_backend::LatexColumn;
+pub use layout::{FieldLayout, PacketLayout, compute_layout, layout_table};
+pub use migrate::{Migration, migrate_definition};
+pub use output_path::{FileNameCase, OutputLayout, OutputPaths, include_path, sanitize_file_stem};
+pub use plugin::{
+    PLUGIN_PREFIX, PLUGIN_PROTOCOL, PluginError, PluginFile, PluginPacket, PluginRequest,
+    PluginResponse, is_plugin_name, parse_plugin_response, plugin_request,
+};
+pub use probe::{LayoutMismatch, check_layout_probe, generate_layout_probe};
+pub use project::{PROJECT_CONFIG_FILE, ProjectConfig, WarningBudget};
+pub use rules::{Rule, rules, rules_markdown};
+pub use scaffold::{ScaffoldError, new_packet_set_template, new_packet_template};
+pub use table_import::{ColumnMap, ImportError, TableImport, import_table};
+pub use target::Target;
+pub use toml::{generate_from_toml, validate_toml};
+pub use transcript::{InputFormat, Transcript, validate_source};
+pub use validator::{
+    ValidateOptions, canonical_command_id, check_input_size, validate, validate_multiple,
+    validate_multiple_with_options, validate_with_options,
+};
+pub use yaml::{generate_from_yaml, validate_yaml};
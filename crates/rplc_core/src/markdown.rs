@@ -0,0 +1,386 @@
+//! 生成包的字段布局说明文档
+
+use crate::config::{Comment, Config, Field};
+use crate::fingerprint::{format_layout_hash, layout_hash};
+use crate::layout::{compute_layout, with_reserved_gaps};
+use crate::validator::{format_command_id, parse_array_type, parse_command_id};
+
+/// 指定 `language` 时只输出该语言的注释；否则注释中出现多种语言时每种语言各占一列说明
+pub(crate) fn generate_markdown(config: &Config, language: Option<&str>) -> String {
+    let config = &with_reserved_gaps(config);
+    let languages = match language {
+        Some(language) => vec![Some(language.to_string())],
+        None => {
+            let languages = config.comment_languages();
+            if languages.len() > 1 {
+                languages.into_iter().map(Some).collect()
+            } else {
+                vec![None]
+            }
+        }
+    };
+    let languages: Vec<Option<&str>> = languages.iter().map(Option::as_deref).collect();
+    // 只有存在范围标注时才输出范围列
+    let limits = config
+        .fields
+        .iter()
+        .any(|field| field.limits().next().is_some());
+
+    let mut out = format!("## {}\n\n", config.packet_name);
+    if let Some(comment) = &config.comment {
+        let mut paragraphs: Vec<&str> = languages
+            .iter()
+            .map(|&language| comment.text(language).trim())
+            .collect();
+        paragraphs.dedup();
+        for paragraph in paragraphs {
+            out.push_str(paragraph);
+            out.push_str("\n\n");
+        }
+    }
+
+    let command_id = parse_command_id(&config.command_id)
+        .map(format_command_id)
+        .unwrap_or_else(|_| config.command_id.clone());
+    out.push_str(&format!("- 命令 ID: `{}`\n", command_id));
+
+    let Some(layout) = compute_layout(config) else {
+        for (index, field) in config.fields.iter().enumerate() {
+            push_table_start(&mut out, index, field, "字节", &languages, limits);
+            out.push_str(&format!(
+                "| `{}` | {} | - | - | {}{} |\n",
+                field.member_path(),
+                type_cell(config, field),
+                range_cell(field, limits),
+                description_cells(config, field, &languages)
+            ));
+        }
+        return out;
+    };
+
+    let mode = if layout.bitstream {
+        "位流（BitLayout）"
+    } else if config.packed {
+        "紧凑"
+    } else {
+        "自然对齐"
+    };
+    out.push_str(&format!("- 大小: {} 字节\n", layout.size));
+    out.push_str(&format!("- 布局: {}\n", mode));
+    out.push_str(&format!(
+        "- 布局哈希: `{}`\n",
+        format_layout_hash(layout_hash(config))
+    ));
+
+    // 位流布局中的偏移不一定按字节对齐，统一以位为单位
+    let (unit, scale) = if layout.bitstream {
+        ("位", 1)
+    } else {
+        ("字节", 8)
+    };
+    for (index, (field, field_layout)) in config.fields.iter().zip(&layout.fields).enumerate() {
+        push_table_start(&mut out, index, field, unit, &languages, limits);
+        out.push_str(&format!(
+            "| `{}` | {} | {} | {} | {}{} |\n",
+            field.member_path(),
+            type_cell(config, field),
+            field_layout.offset_bits / scale,
+            field_layout.size_bits / scale,
+            range_cell(field, limits),
+            description_cells(config, field, &languages)
+        ));
+    }
+    out
+}
+
+/// 每个分组作为小节并开始新的表格，首个分组之前的字段直接列在包的表格中
+fn push_table_start(
+    out: &mut String,
+    index: usize,
+    field: &Field,
+    unit: &str,
+    languages: &[Option<&str>],
+    limits: bool,
+) {
+    if let Some(section) = &field.section {
+        out.push_str(&format!("\n### {}\n", section.trim()));
+        out.push_str(&table_header(unit, languages, limits));
+    } else if index == 0 {
+        out.push_str(&table_header(unit, languages, limits));
+    }
+}
+
+fn table_header(unit: &str, languages: &[Option<&str>], limits: bool) -> String {
+    let columns: Vec<String> = languages
+        .iter()
+        .map(|language| match language {
+            Some(language) if languages.len() > 1 => format!("说明（{}）", language),
+            _ => "说明".to_string(),
+        })
+        .collect();
+    let (range, separator) = if limits {
+        ("范围 | ", " --- |")
+    } else {
+        ("", "")
+    };
+    format!(
+        "\n| 字段 | 类型 | 偏移（{0}） | 长度（{0}） | {1}{2} |\n| --- | --- | --- | --- |{3}{4}\n",
+        unit,
+        range,
+        columns.join(" | "),
+        separator,
+        " --- |".repeat(languages.len())
+    )
+}
+
+/// 类型列的单元格，枚举字段写出枚举名与底层类型，例如 `Mode`（`uint8_t`）
+fn type_cell(config: &Config, field: &Field) -> String {
+    if let Some(def) = config.field_enum(field) {
+        let len = parse_array_type(&field.ty)
+            .and_then(|(_, len)| len)
+            .map(|len| format!("[{}]", len))
+            .unwrap_or_default();
+        return format!("`{}{}`（`{}`）", def.name, len, def.ty);
+    }
+    match field.bit_field {
+        Some(bits) => format!("`{} : {}`", field.ty, bits),
+        None => format!("`{}`", field.ty),
+    }
+}
+
+/// 范围列的单元格（连同分隔符），例如 `0 ~ 300（典型 120）`；表格没有范围列时为空
+fn range_cell(field: &Field, limits: bool) -> String {
+    if !limits {
+        return String::new();
+    }
+    let range = match (&field.min, &field.max) {
+        (Some(min), Some(max)) => format!("{} ~ {}", min, max),
+        (Some(min), None) => format!("≥ {}", min),
+        (None, Some(max)) => format!("≤ {}", max),
+        (None, None) => String::new(),
+    };
+    let cell = match (&field.typical, range.is_empty()) {
+        (Some(typical), true) => format!("典型 {}", typical),
+        (Some(typical), false) => format!("{}（典型 {}）", range, typical),
+        (None, true) => "-".to_string(),
+        (None, false) => range,
+    };
+    format!("{} | ", cell)
+}
+
+/// 字段的说明，列出取值时附在注释之后，例如 `模式；取值：`0` 空闲，`1` 运行`；
+/// 枚举字段列出枚举项，例如 `模式；取值：`0` idle（空闲），`2` spin`
+fn description_cells(config: &Config, field: &Field, languages: &[Option<&str>]) -> String {
+    let names = field.value_names();
+    let enumeration = config.field_enum(field);
+    if names.is_empty() && enumeration.is_none() {
+        return comment_cells(field.comment.as_ref(), languages);
+    }
+    languages
+        .iter()
+        .map(|&language| {
+            let values = match enumeration {
+                Some(def) => def
+                    .values
+                    .iter()
+                    .map(
+                        |item| match cell(item.comment.as_ref().map(|c| c.text(language))) {
+                            comment if comment.is_empty() => {
+                                format!("`{}` {}", item.value, item.name)
+                            }
+                            comment => format!("`{}` {}（{}）", item.value, item.name, comment),
+                        },
+                    )
+                    .collect::<Vec<_>>(),
+                None => names
+                    .iter()
+                    .map(|(value, name)| format!("`{}` {}", value, cell(Some(name.text(language)))))
+                    .collect::<Vec<_>>(),
+            }
+            .join("，");
+            match cell(field.comment.as_ref().map(|c| c.text(language))) {
+                comment if comment.is_empty() => format!("取值：{}", values),
+                comment => format!("{}；取值：{}", comment, values),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// 每种语言一个单元格
+fn comment_cells(comment: Option<&Comment>, languages: &[Option<&str>]) -> String {
+    languages
+        .iter()
+        .map(|&language| cell(comment.map(|c| c.text(language))))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// 表格单元格中不能出现换行与未转义的竖线
+fn cell(text: Option<&str>) -> String {
+    text.unwrap_or_default()
+        .trim()
+        .replace('|', "\\|")
+        .replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(json: &str) -> String {
+        generate_markdown(&serde_json::from_str(json).unwrap(), None)
+    }
+
+    #[test]
+    fn test_markdown_byte_layout() {
+        let doc = generate(
+            r#"{"packet_name":"GimbalCommand","command_id":"260","comment":"云台控制","packed":false,
+            "fields":[
+                {"name":"mode","type":"uint8_t","comment":"0 | 1"},
+                {"name":"yaw","type":"float","comment":"偏航角"}
+            ]}"#,
+        );
+        assert_eq!(
+            doc,
+            "## GimbalCommand
+
+云台控制
+
+- 命令 ID: `0x0104`
+- 大小: 8 字节
+- 布局: 自然对齐
+- 布局哈希: `0x06840CCC`
+
+| 字段 | 类型 | 偏移（字节） | 长度（字节） | 说明 |
+| --- | --- | --- | --- | --- |
+| `mode` | `uint8_t` | 0 | 1 | 0 \\| 1 |
+| `yaw` | `float` | 4 | 4 | 偏航角 |
+"
+        );
+    }
+
+    #[test]
+    fn test_markdown_sections() {
+        let doc = generate(
+            r#"{"packet_name":"Status","command_id":"1","fields":[
+                {"name":"seq","type":"uint8_t"},
+                {"section":"IMU data"},
+                {"name":"gyro","type":"float[3]"},
+                {"section":"Flags"},
+                {"name":"mode","type":"uint8_t"}
+            ]}"#,
+        );
+        assert!(doc.ends_with(
+            "
+| 字段 | 类型 | 偏移（字节） | 长度（字节） | 说明 |
+| --- | --- | --- | --- | --- |
+| `seq` | `uint8_t` | 0 | 1 |  |
+
+### IMU data
+
+| 字段 | 类型 | 偏移（字节） | 长度（字节） | 说明 |
+| --- | --- | --- | --- | --- |
+| `gyro` | `float[3]` | 1 | 12 |  |
+
+### Flags
+
+| 字段 | 类型 | 偏移（字节） | 长度（字节） | 说明 |
+| --- | --- | --- | --- | --- |
+| `mode` | `uint8_t` | 13 | 1 |  |
+"
+        ));
+    }
+
+    #[test]
+    fn test_markdown_bitstream_layout() {
+        let doc = generate(
+            r#"{"packet_name":"Flags","command_id":"0x0002","packed":true,
+            "fields":[
+                {"name":"a","type":"uint8_t","bit_field":3},
+                {"name":"b","type":"uint16_t[2]"}
+            ]}"#,
+        );
+        assert!(doc.contains("- 大小: 5 字节\n- 布局: 位流（BitLayout）\n"));
+        assert!(doc.contains("| `a` | `uint8_t : 3` | 0 | 3 |  |\n"));
+        assert!(doc.contains("| `b` | `uint16_t[2]` | 3 | 32 |  |\n"));
+    }
+
+    #[test]
+    fn test_markdown_bilingual_comments() {
+        let json = r#"{"packet_name":"Gimbal","command_id":"0x0104",
+            "comment":{"en":"Gimbal control","zh":"云台控制"},
+            "fields":[
+                {"name":"yaw","type":"float","comment":{"zh":"偏航角","en":"Yaw | rad"}},
+                {"name":"mode","type":"uint8_t","comment":"0/1"},
+                {"name":"seq","type":"uint8_t"}
+            ]}"#;
+        let doc = generate(json);
+        assert!(doc.starts_with("## Gimbal\n\n云台控制\n\nGimbal control\n\n"));
+        assert!(doc.ends_with(
+            "
+| 字段 | 类型 | 偏移（字节） | 长度（字节） | 说明（zh） | 说明（en） |
+| --- | --- | --- | --- | --- | --- |
+| `yaw` | `float` | 0 | 4 | 偏航角 | Yaw \\| rad |
+| `mode` | `uint8_t` | 4 | 1 | 0/1 | 0/1 |
+| `seq` | `uint8_t` | 5 | 1 |  |  |
+"
+        ));
+
+        // 指定语言时只输出一列
+        let doc = generate_markdown(&serde_json::from_str(json).unwrap(), Some("en"));
+        assert!(doc.starts_with("## Gimbal\n\nGimbal control\n\n- 命令 ID"));
+        assert!(doc.contains("| 长度（字节） | 说明 |\n"));
+        assert!(doc.contains("| `yaw` | `float` | 0 | 4 | Yaw \\| rad |\n"));
+    }
+
+    #[test]
+    fn test_markdown_limits_column() {
+        let doc = generate(
+            r#"{"packet_name":"Gimbal","command_id":"1","fields":[
+                {"name":"yaw","type":"float","comment":"Y","min":-3.14,"max":3.14},
+                {"name":"speed","type":"uint16_t","comment":"S","max":300,"typical":120},
+                {"name":"mode","type":"uint8_t","comment":"M"}
+            ]}"#,
+        );
+        assert!(
+            doc.contains("| 长度（字节） | 范围 | 说明 |\n| --- | --- | --- | --- | --- | --- |\n")
+        );
+        assert!(doc.contains("| `yaw` | `float` | 0 | 4 | -3.14 ~ 3.14 | Y |\n"));
+        assert!(doc.contains("| `speed` | `uint16_t` | 4 | 2 | ≤ 300（典型 120） | S |\n"));
+        assert!(doc.contains("| `mode` | `uint8_t` | 6 | 1 | - | M |\n"));
+    }
+
+    #[test]
+    fn test_markdown_enums() {
+        let config = r#"{"packet_name":"Chassis","command_id":"1",
+            "enums":[{"name":"Mode","type":"uint8_t","values":[
+                {"name":"idle","value":0,"comment":{"zh":"空闲","en":"Idle"}},
+                {"name":"spin","value":2}]}],
+            "fields":[{"name":"mode","type":"Mode","comment":{"zh":"模式","en":"Mode"}},
+                {"name":"history","type":"Mode[2]"}]}"#;
+        let doc = generate(config);
+        assert!(doc.contains(
+            "| `mode` | `Mode`（`uint8_t`） | 0 | 1 | 模式；取值：`0` idle（空闲），`2` spin \
+             | Mode；取值：`0` idle（Idle），`2` spin |\n"
+        ));
+        assert!(doc.contains(
+            "| `history` | `Mode[2]`（`uint8_t`） | 1 | 2 | 取值：`0` idle（空闲），`2` spin \
+             | 取值：`0` idle（Idle），`2` spin |\n"
+        ));
+    }
+
+    #[test]
+    fn test_markdown_value_names() {
+        let config = r#"{"packet_name":"Chassis","command_id":"1","fields":[
+            {"name":"mode","type":"uint8_t","comment":{"zh":"模式","en":"Mode"},
+             "values":{"1":{"zh":"运行","en":"Run"},"0":"空闲"}},
+            {"name":"flag","type":"uint8_t","values":{"0":"否"}}
+        ]}"#;
+        let doc = generate(config);
+        assert!(
+            doc.contains("| 模式；取值：`0` 空闲，`1` 运行 | Mode；取值：`0` 空闲，`1` Run |\n")
+        );
+        assert!(doc.contains("| `flag` | `uint8_t` | 1 | 1 | 取值：`0` 否 | 取值：`0` 否 |\n"));
+    }
+}
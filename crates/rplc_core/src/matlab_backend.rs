@@ -0,0 +1,364 @@
+//! 生成 MATLAB 的 classdef：Simulink 总线定义与按字节解码的静态方法，控制组可以直接回放记录的遥测数据
+//!
+//! 解码使用 `typecast`，假定运行 MATLAB 的主机为小端；大端字段解码后再 `swapbytes`
+
+use crate::config::{Config, Endianness};
+use crate::fingerprint::{format_layout_hash, layout_hash};
+use crate::generator::{GenerateError, GenerateOptions};
+use crate::layout::{c_type_size, compute_layout, with_reserved_gaps};
+use crate::target::Target;
+use crate::validator::{format_command_id, is_identifier, parse_array_type, parse_command_id};
+
+const BACKEND: &str = "MATLAB";
+
+/// MATLAB 关键字（`iskeyword` 的结果），字段名与之相同时加下划线后缀
+const MATLAB_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "classdef",
+    "continue",
+    "else",
+    "elseif",
+    "end",
+    "for",
+    "function",
+    "global",
+    "if",
+    "otherwise",
+    "parfor",
+    "persistent",
+    "return",
+    "spmd",
+    "switch",
+    "try",
+    "while",
+];
+
+/// MATLAB 标识符的最大长度（`namelengthmax`）
+const MAX_IDENTIFIER_LEN: usize = 63;
+
+/// 解码结果中的一个字段
+struct Member {
+    name: String,
+    class: &'static str,
+    offset: u32,
+    elem_size: u32,
+    len: u32,
+    swap: bool,
+    comment: Option<String>,
+}
+
+pub(crate) fn generate_matlab(
+    config: &Config,
+    options: &GenerateOptions,
+) -> Result<String, GenerateError> {
+    let config = &with_reserved_gaps(config);
+    if let Some(field) = config.fields.iter().find(|f| f.bit_field.is_some()) {
+        return Err(unsupported(format!(
+            "字段 '{}' 使用了位域，MATLAB 后端尚不支持",
+            field.name
+        )));
+    }
+    if let Some(field) = config
+        .fields
+        .iter()
+        .find(|f| f.group.as_ref().is_some_and(|g| g.name.is_some()))
+    {
+        return Err(unsupported(format!(
+            "字段 '{}' 位于具名嵌套结构体中，MATLAB 后端尚不支持",
+            field.member_path()
+        )));
+    }
+    let name = &config.packet_name;
+    if !is_matlab_identifier(name) || MATLAB_KEYWORDS.contains(&name.as_str()) {
+        return Err(unsupported(format!(
+            "包名 '{}' 不是合法的 MATLAB 类名（需以字母开头、不超过 {} 个字符且不是关键字）",
+            name, MAX_IDENTIFIER_LEN
+        )));
+    }
+    let cmd_id =
+        parse_command_id(&config.command_id).map_err(|_| GenerateError::ValidationError)?;
+    let layout = compute_layout(config).ok_or(GenerateError::ValidationError)?;
+
+    let mut members = Vec::new();
+    for (field, field_layout) in config.fields.iter().zip(&layout.fields) {
+        if field.reserved.is_some() {
+            continue;
+        }
+        let (base_type, array_len) =
+            parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+        let class = Target::Matlab
+            .map_type(base_type)
+            .map_err(|reason| unsupported(format!("字段 '{}': {}", field.name, reason)))?;
+        let elem_size = c_type_size(base_type).ok_or(GenerateError::ValidationError)?;
+        members.push(Member {
+            name: identifier(&field.name)?,
+            class,
+            offset: field_layout.offset_bits / 8,
+            elem_size,
+            len: array_len.unwrap_or(1),
+            swap: elem_size > 1 && config.field_endianness(field) == Endianness::Big,
+            comment: field.comment.as_ref().map(|c| c.as_str().to_string()),
+        });
+    }
+
+    let i1 = options.indent(1);
+    let i2 = options.indent(2);
+    let i3 = options.indent(3);
+    let i4 = options.indent(4);
+    let mut out = String::from("% 由 rplc 生成，请勿手动修改\n");
+    out.push_str(&format!("classdef {}\n", name));
+    let summary = config
+        .comment
+        .as_ref()
+        .map(|c| single_line(c.as_str()))
+        .unwrap_or_default();
+    match summary.as_str() {
+        "" => out.push_str(&format!("{i1}% {}\n", name)),
+        summary => out.push_str(&format!("{i1}% {} {}\n", name, summary)),
+    }
+    out.push_str(&format!("{i1}%\n"));
+    out.push_str(&format!(
+        "{i1}%   s = {}.decode(payload) 把数据段解码为结构体；payload 为 SIZE 个字节，\n",
+        name
+    ));
+    out.push_str(&format!(
+        "{i1}%   或每行一个包的 N×SIZE 矩阵，此时每个字段有 N 行\n"
+    ));
+    out.push_str(&format!(
+        "{i1}%   assignin('base', '{}Bus', {}.bus()) 定义 Simulink 总线\n\n",
+        name, name
+    ));
+
+    out.push_str(&format!("{i1}properties (Constant)\n"));
+    out.push_str(&format!("{i2}CMD = {}u16\n", format_command_id(cmd_id)));
+    out.push_str(&format!(
+        "{i2}LAYOUT_HASH = {}u32\n",
+        format_layout_hash(layout_hash(config))
+    ));
+    out.push_str(&format!("{i2}SIZE = {}\n", layout.size));
+    out.push_str(&format!("{i1}end\n\n"));
+
+    out.push_str(&format!("{i1}methods (Static)\n"));
+    out.push_str(&format!("{i2}function s = decode(payload)\n"));
+    out.push_str(&format!(
+        "{i3}% 按 C 结构体的内存布局解码，payload 的列数必须为 SIZE\n"
+    ));
+    out.push_str(&format!(
+        "{i3}if isvector(payload) && numel(payload) == {}.SIZE\n",
+        name
+    ));
+    out.push_str(&format!("{i4}payload = reshape(payload, 1, []);\n"));
+    out.push_str(&format!("{i3}end\n"));
+    out.push_str(&format!("{i3}if size(payload, 2) ~= {}.SIZE\n", name));
+    out.push_str(&format!(
+        "{i4}error('rplc:size', '{} 的数据段应为 %d 字节，实际为 %d 字节', {}.SIZE, size(payload, 2));\n",
+        name, name
+    ));
+    out.push_str(&format!("{i3}end\n"));
+    out.push_str(&format!("{i3}payload = uint8(payload);\n"));
+    out.push_str(&format!("{i3}n = size(payload, 1);\n"));
+    out.push_str(&format!("{i3}s = struct();\n"));
+    for member in &members {
+        if let Some(comment) = &member.comment {
+            for line in comment.lines().map(str::trim_end) {
+                match line {
+                    "" => out.push_str(&format!("{i3}%\n")),
+                    line => out.push_str(&format!("{i3}% {}\n", line)),
+                }
+            }
+        }
+        let first = member.offset + 1;
+        let last = member.offset + member.elem_size * member.len;
+        let bytes = format!("payload(:, {}:{})", first, last);
+        let value = match member.class {
+            "uint8" => bytes,
+            "logical" => format!("{} ~= 0", bytes),
+            class => {
+                let mut value = format!("typecast(reshape({}.', 1, []), '{}')", bytes, class);
+                if member.swap {
+                    value = format!("swapbytes({})", value);
+                }
+                format!("reshape({}, {}, n).'", value, member.len)
+            }
+        };
+        out.push_str(&format!("{i3}s.{} = {};\n", member.name, value));
+    }
+    out.push_str(&format!("{i2}end\n\n"));
+
+    out.push_str(&format!("{i2}function bus = bus()\n"));
+    out.push_str(&format!(
+        "{i3}% Simulink 总线定义，元素与 decode 返回的结构体字段一一对应\n"
+    ));
+    out.push_str(&format!("{i3}elements = Simulink.BusElement.empty;\n"));
+    for member in &members {
+        out.push_str(&format!("{i3}e = Simulink.BusElement;\n"));
+        out.push_str(&format!("{i3}e.Name = '{}';\n", member.name));
+        out.push_str(&format!(
+            "{i3}e.DataType = '{}';\n",
+            bus_data_type(member.class)
+        ));
+        out.push_str(&format!("{i3}e.Dimensions = {};\n", member.len));
+        if let Some(comment) = &member.comment {
+            out.push_str(&format!(
+                "{i3}e.Description = {};\n",
+                matlab_string(&single_line(comment))
+            ));
+        }
+        out.push_str(&format!("{i3}elements(end + 1) = e;\n"));
+    }
+    out.push_str(&format!("{i3}bus = Simulink.Bus;\n"));
+    if !summary.is_empty() {
+        out.push_str(&format!(
+            "{i3}bus.Description = {};\n",
+            matlab_string(&summary)
+        ));
+    }
+    out.push_str(&format!("{i3}bus.Elements = elements;\n"));
+    out.push_str(&format!("{i2}end\n"));
+    out.push_str(&format!("{i1}end\n"));
+    out.push_str("end\n");
+    Ok(out)
+}
+
+/// 总线元素的数据类型与 MATLAB 类名相同，只有逻辑值写作 `boolean`
+fn bus_data_type(class: &str) -> &str {
+    match class {
+        "logical" => "boolean",
+        class => class,
+    }
+}
+
+/// MATLAB 的名称不能以下划线开头，长度也有限制
+fn is_matlab_identifier(name: &str) -> bool {
+    name.len() <= MAX_IDENTIFIER_LEN
+        && name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && is_identifier(name)
+}
+
+/// 与 MATLAB 关键字冲突的名称加下划线后缀，以下划线开头或过长的名称无法生成
+fn identifier(name: &str) -> Result<String, GenerateError> {
+    let name = if MATLAB_KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    };
+    if !is_matlab_identifier(&name) {
+        return Err(unsupported(format!(
+            "字段 '{}' 不是合法的 MATLAB 字段名（需以字母开头且不超过 {} 个字符）",
+            name, MAX_IDENTIFIER_LEN
+        )));
+    }
+    Ok(name)
+}
+
+/// 多行注释合并为一行，用于 H1 行与总线描述
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn matlab_string(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn unsupported(reason: String) -> GenerateError {
+    GenerateError::Unsupported {
+        backend: BACKEND,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(json: &str) -> Result<String, GenerateError> {
+        let config: Config = serde_json::from_str(json).unwrap();
+        generate_matlab(&config, &GenerateOptions::default())
+    }
+
+    #[test]
+    fn test_generate_matlab_classdef() {
+        let code = generate(
+            r#"{
+                "packet_name": "GimbalCommand",
+                "command_id": "0x0104",
+                "packed": true,
+                "comment": "云台控制",
+                "fields": [
+                    { "name": "yaw", "type": "float", "comment": "偏航角" },
+                    { "name": "gains", "type": "int16_t[3]" },
+                    { "name": "mode", "type": "uint8_t", "comment": "it's" },
+                    { "name": "armed", "type": "bool" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(code.starts_with("% 由 rplc 生成，请勿手动修改\nclassdef GimbalCommand\n"));
+        assert!(code.contains("    % GimbalCommand 云台控制\n"));
+        assert!(code.contains("        CMD = 0x0104u16\n"));
+        assert!(code.contains("        SIZE = 12\n"));
+        assert!(code.contains(
+            "            % 偏航角\n            s.yaw = reshape(typecast(reshape(payload(:, 1:4).', 1, []), 'single'), 1, n).';\n"
+        ));
+        assert!(code.contains(
+            "            s.gains = reshape(typecast(reshape(payload(:, 5:10).', 1, []), 'int16'), 3, n).';\n"
+        ));
+        assert!(code.contains("            s.mode = payload(:, 11:11);\n"));
+        assert!(code.contains("            s.armed = payload(:, 12:12) ~= 0;\n"));
+        assert!(code.contains("            e.Name = 'gains';\n            e.DataType = 'int16';\n            e.Dimensions = 3;\n"));
+        assert!(code.contains("            e.DataType = 'boolean';\n"));
+        assert!(code.contains("            e.Description = 'it''s';\n"));
+        assert!(code.contains("            bus.Description = '云台控制';\n"));
+        assert!(code.ends_with("        end\n    end\nend\n"));
+    }
+
+    #[test]
+    fn test_generate_matlab_layout() {
+        // 非紧凑结构体按对齐后的偏移读取，大端字段解码后交换字节序，关键字加下划线后缀
+        let code = generate(
+            r#"{
+                "packet_name": "Status",
+                "command_id": "2",
+                "packed": false,
+                "fields": [
+                    { "name": "end", "type": "uint8_t" },
+                    { "name": "count", "type": "uint32_t", "endianness": "big" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(code.contains("        SIZE = 8\n"));
+        assert!(code.contains("            s.end_ = payload(:, 1:1);\n"));
+        assert!(code.contains(
+            "            s.count = reshape(swapbytes(typecast(reshape(payload(:, 5:8).', 1, []), 'uint32')), 1, n).';\n"
+        ));
+        assert!(!code.contains("bus.Description"));
+    }
+
+    #[test]
+    fn test_generate_matlab_unsupported() {
+        let bit_field = r#"{"packet_name":"A","command_id":"1",
+            "fields":[{"name":"flags","type":"uint8_t","bit_field":3}]}"#;
+        let underscore = r#"{"packet_name":"A","command_id":"1",
+            "fields":[{"name":"_x","type":"uint8_t"}]}"#;
+        let class_name = r#"{"packet_name":"_A","command_id":"1",
+            "fields":[{"name":"x","type":"uint8_t"}]}"#;
+        let long_double = r#"{"packet_name":"A","command_id":"1",
+            "fields":[{"name":"x","type":"long double"}]}"#;
+        for json in [bit_field, underscore, class_name, long_double] {
+            assert!(
+                matches!(
+                    generate(json),
+                    Err(GenerateError::Unsupported {
+                        backend: "MATLAB",
+                        ..
+                    })
+                ),
+                "{}",
+                json
+            );
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! 把旧版本的定义文件升级到当前的 `rplc_schema`
+
+use serde_json::{Map, Value};
+
+use crate::config::{CURRENT_SCHEMA, LEGACY_SCHEMA};
+use crate::diagnostics::{RplcDiagnostic, Severity, ValidationCode};
+use crate::format::{json_syntax_diagnostic, normalize_fields, write_definition};
+
+/// 一次迁移的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    /// 迁移前文件中最旧的格式版本，等于 `CURRENT_SCHEMA` 时无需迁移
+    pub from: u32,
+    /// 迁移后的定义，按 `format_definition` 的规范格式输出
+    pub output: String,
+}
+
+/// 迁移步骤：下标 N 把一个包从版本 N + 1 升级到 N + 2
+const STEPS: &[fn(&mut Map<String, Value>)] = &[bit_fields_as_numbers];
+
+/// 将定义升级到 `CURRENT_SCHEMA`，并写入 `rplc_schema`：
+/// - 单个包与包数组中的每个包写在包上，包集合写在集合上
+/// - 比当前版本更新或无法识别的 `rplc_schema` 返回错误，不做任何改动
+pub fn migrate_definition(json_input: &str) -> Result<Migration, Vec<RplcDiagnostic>> {
+    let mut root: Value = serde_json::from_str(json_input)
+        .map_err(|e| vec![json_syntax_diagnostic(json_input, &e)])?;
+
+    let mut from = CURRENT_SCHEMA;
+    match &mut root {
+        Value::Array(packets) => {
+            for packet in packets {
+                from = from.min(migrate_packet(packet, LEGACY_SCHEMA, true)?);
+            }
+        }
+        Value::Object(set) if set.contains_key("packets") => {
+            let default = schema_of(set)?.unwrap_or(LEGACY_SCHEMA);
+            from = from.min(default);
+            if let Some(Value::Array(packets)) = set.get_mut("packets") {
+                // 集合中的包默认继承集合的版本，只更新包上已有的 rplc_schema
+                for packet in packets {
+                    from = from.min(migrate_packet(packet, default, false)?);
+                }
+            }
+            set.insert("rplc_schema".to_string(), Value::from(CURRENT_SCHEMA));
+        }
+        packet => from = migrate_packet(packet, LEGACY_SCHEMA, true)?,
+    }
+
+    Ok(Migration {
+        from,
+        output: write_definition(&root),
+    })
+}
+
+fn migrate_packet(
+    packet: &mut Value,
+    default: u32,
+    stamp: bool,
+) -> Result<u32, Vec<RplcDiagnostic>> {
+    let Value::Object(map) = packet else {
+        return Ok(default);
+    };
+    let version = schema_of(map)?.unwrap_or(default);
+    for step in &STEPS[(version - LEGACY_SCHEMA) as usize..] {
+        step(map);
+    }
+    if stamp || map.contains_key("rplc_schema") {
+        map.insert("rplc_schema".to_string(), Value::from(CURRENT_SCHEMA));
+    }
+    Ok(version)
+}
+
+/// 对象上声明的格式版本，省略或为 null 时返回 `None`
+fn schema_of(map: &Map<String, Value>) -> Result<Option<u32>, Vec<RplcDiagnostic>> {
+    let version = match map.get("rplc_schema") {
+        None | Some(Value::Null) => return Ok(None),
+        Some(version) => version,
+    };
+    version
+        .as_u64()
+        .and_then(|v| u32::try_from(v).ok())
+        .filter(|v| (LEGACY_SCHEMA..=CURRENT_SCHEMA).contains(v))
+        .map(Some)
+        .ok_or_else(|| {
+            let text = match version {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            vec![RplcDiagnostic {
+                code: ValidationCode::UnsupportedSchemaVersion(text, CURRENT_SCHEMA),
+                severity: Severity::Error,
+                span: None,
+                help: None,
+            }]
+        })
+}
+
+/// 1 → 2：字符串形式的位域改写为数字
+fn bit_fields_as_numbers(packet: &mut Map<String, Value>) {
+    if let Some(Value::Array(fields)) = packet.get_mut("fields") {
+        normalize_fields(fields);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::{validate, validate_multiple};
+
+    const LEGACY_PACKET: &str = r#"{
+        "packet_name": "Status",
+        "command_id": "0x0101",
+        "comment": "S",
+        "fields": [{ "name": "mode", "type": "uint8_t", "bit_field": "4", "comment": "M" }]
+    }"#;
+
+    #[test]
+    fn test_migrate_legacy_packet() {
+        let migration = migrate_definition(LEGACY_PACKET).unwrap();
+        assert_eq!(migration.from, 1);
+        assert!(
+            migration
+                .output
+                .starts_with("{\n  \"rplc_schema\": 2,\n  \"packet_name\": \"Status\",")
+        );
+        assert!(migration.output.contains(r#""bit_field": 4"#));
+        assert!(validate(&migration.output).is_empty());
+
+        // 迁移是幂等的
+        let again = migrate_definition(&migration.output).unwrap();
+        assert_eq!(again.from, CURRENT_SCHEMA);
+        assert_eq!(again.output, migration.output);
+    }
+
+    #[test]
+    fn test_migrate_packet_set() {
+        let json = format!(
+            r#"{{ "protocol_version": "1.0.0", "packets": [{}, {}] }}"#,
+            LEGACY_PACKET,
+            LEGACY_PACKET
+                .replace("Status", "Other")
+                .replace("0x0101", "0x0102")
+                .replace(
+                    "{\n        \"packet_name\"",
+                    "{ \"rplc_schema\": 1, \"packet_name\""
+                )
+        );
+        let migration = migrate_definition(&json).unwrap();
+        assert_eq!(migration.from, 1);
+        assert!(migration.output.starts_with("{\n  \"rplc_schema\": 2,\n"));
+        // 只有原本声明了版本的包保留 rplc_schema
+        assert_eq!(migration.output.matches("\"rplc_schema\"").count(), 2);
+        assert!(!migration.output.contains(r#""bit_field": "4""#));
+        assert!(validate_multiple(&migration.output).is_empty());
+    }
+
+    #[test]
+    fn test_migrate_rejects_unknown_schema() {
+        let json = LEGACY_PACKET.replace("\"comment\": \"S\"", "\"rplc_schema\": 9");
+        let diags = migrate_definition(&json).unwrap_err();
+        assert_eq!(
+            diags[0].code,
+            ValidationCode::UnsupportedSchemaVersion("9".to_string(), CURRENT_SCHEMA)
+        );
+    }
+}
@@ -0,0 +1,479 @@
+//! 生成文件的命名：把包名转换为在各平台上都合法的文件名，并避免同一次生成中的文件互相覆盖
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::backend::Backend;
+
+/// 文件名的大小写策略
+///
+/// Windows 与 macOS 的默认文件系统不区分大小写，因此无论采用哪种策略，
+/// 冲突检测都按忽略大小写的方式进行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum FileNameCase {
+    /// 保留包名的大小写（默认）
+    #[default]
+    Preserve,
+    /// 全部转换为小写
+    Lower,
+}
+
+/// Windows 保留的设备名，无论扩展名是什么都不能作为文件名
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 将名称转换为合法的文件名主干（不含扩展名）：
+/// - 路径分隔符、Windows 不允许的字符与控制字符替换为 `_`，其他 UTF-8 字符保留
+/// - 去掉 Windows 会自动删除的结尾空格与句点
+/// - 与设备名相同时追加 `_`，空名称变为 `_`
+pub fn sanitize_file_stem(name: &str, case: FileNameCase) -> String {
+    let mut stem: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    if case == FileNameCase::Lower {
+        stem = stem.to_lowercase();
+    }
+
+    let trimmed_len = stem.trim_end_matches(['.', ' ']).len();
+    stem.truncate(trimmed_len);
+    if stem.is_empty() {
+        return "_".to_string();
+    }
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        stem.push('_');
+    }
+    stem
+}
+
+/// 布局模板支持的占位符
+pub const LAYOUT_PLACEHOLDERS: &[&str] = &["target", "namespace_path", "file"];
+
+/// 输出目录内的布局模板，例如 `{target}/{namespace_path}/{file}`
+///
+/// - `{target}`：后端的短名称，如 `cpp`、`c`、`python`
+/// - `{namespace_path}`：包的命名空间，每一级为一层目录，没有命名空间时省略这一层；必须独占一级
+/// - `{file}`：文件名，必须出现在最后一级
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLayout {
+    components: Vec<String>,
+}
+
+impl OutputLayout {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let template = template.trim();
+        if template.starts_with(['/', '\\']) || template.contains(':') {
+            return Err("必须是输出目录内的相对路径".to_string());
+        }
+        let components: Vec<String> = template
+            .split(['/', '\\'])
+            .filter(|c| !c.is_empty())
+            .map(str::to_string)
+            .collect();
+        let mut file_count = 0;
+        for (index, component) in components.iter().enumerate() {
+            if component == "." || component == ".." {
+                return Err(format!("不能包含 '{}'", component));
+            }
+            for name in placeholders(component)? {
+                match name {
+                    "file" if index + 1 == components.len() => file_count += 1,
+                    "file" => return Err("{file} 必须位于最后一级".to_string()),
+                    "namespace_path" if component != "{namespace_path}" => {
+                        return Err("{namespace_path} 必须独占一级目录".to_string());
+                    }
+                    name if LAYOUT_PLACEHOLDERS.contains(&name) => {}
+                    name => {
+                        return Err(format!(
+                            "未知的占位符 {{{}}}，可用的占位符为 {}",
+                            name,
+                            LAYOUT_PLACEHOLDERS
+                                .iter()
+                                .map(|p| format!("{{{}}}", p))
+                                .collect::<Vec<_>>()
+                                .join("、")
+                        ));
+                    }
+                }
+            }
+        }
+        if file_count != 1 {
+            return Err("必须包含且只包含一个 {file}".to_string());
+        }
+        Ok(Self { components })
+    }
+
+    /// 文件相对于输出目录的路径，`namespace` 为已转换为目录名的各级命名空间
+    fn resolve(&self, target: &str, namespace: &[String], file: &str) -> PathBuf {
+        let mut path = PathBuf::new();
+        for component in &self.components {
+            if component == "{namespace_path}" {
+                path.extend(namespace);
+            } else {
+                path.push(
+                    component
+                        .replace("{target}", target)
+                        .replace("{file}", file),
+                );
+            }
+        }
+        path
+    }
+}
+
+/// 组件中 `{...}` 占位符的名称
+fn placeholders(component: &str) -> Result<Vec<&str>, String> {
+    let mut names = Vec::new();
+    let mut rest = component;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            return Err(format!("'{}' 中的占位符没有闭合", component));
+        };
+        names.push(&rest[start + 1..start + len]);
+        rest = &rest[start + len + 1..];
+    }
+    if rest.contains('}') {
+        return Err(format!("'{}' 中有多余的 '}}'", component));
+    }
+    Ok(names)
+}
+
+/// `from` 中 `#include` 文件 `to` 时使用的相对路径，两者都应位于同一输出目录中
+pub fn include_path(from: &Path, to: &Path) -> String {
+    let from_dir: Vec<_> = from
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from_dir.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<String> = vec!["..".to_string(); from_dir.len() - common];
+    parts.extend(
+        to[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    parts.join("/")
+}
+
+/// 一次生成中所有输出文件的路径
+///
+/// 文件放在输出目录中，未指定时放在输入文件旁边；设置了布局模板时按模板放入子目录。
+/// 路径忽略大小写后与已分配的文件相同时依次追加 `_2`、`_3` 等后缀
+#[derive(Debug, Clone)]
+pub struct OutputPaths {
+    dir: PathBuf,
+    input_stem: String,
+    case: FileNameCase,
+    layout: Option<OutputLayout>,
+    used: HashSet<String>,
+}
+
+impl OutputPaths {
+    pub fn new(input: &Path, output_dir: Option<&Path>, case: FileNameCase) -> Self {
+        let dir = match output_dir {
+            Some(dir) => dir.to_path_buf(),
+            None => input.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+        Self {
+            dir,
+            input_stem: input
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            case,
+            layout: None,
+            used: HashSet::new(),
+        }
+    }
+
+    /// 按布局模板把文件放入输出目录的子目录，`None` 时所有文件直接放在输出目录中
+    pub fn with_layout(mut self, layout: Option<OutputLayout>) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// 单包模式的输出，以输入文件名命名
+    pub fn for_input(&mut self, backend: Backend, namespace: Option<&str>) -> PathBuf {
+        let stem = self.input_stem.clone();
+        self.claim(&stem, backend, namespace)
+    }
+
+    /// 多包模式中单个包的输出，以包名命名
+    pub fn for_packet(
+        &mut self,
+        packet_name: &str,
+        backend: Backend,
+        namespace: Option<&str>,
+    ) -> PathBuf {
+        self.claim(packet_name, backend, namespace)
+    }
+
+    /// 多包模式的命令注册表
+    pub fn registry(&mut self) -> PathBuf {
+        let stem = format!("{}_registry", self.input_stem);
+        self.claim(&stem, Backend::Cpp, None)
+    }
+
+    /// 设置命名空间别名时包含所有包的汇总头文件
+    pub fn umbrella(&mut self) -> PathBuf {
+        let stem = format!("{}_packets", self.input_stem);
+        self.claim(&stem, Backend::Cpp, None)
+    }
+
+    /// 握手包 `ProtocolInfo` 的头文件
+    pub fn protocol_info(&mut self) -> PathBuf {
+        let stem = format!("{}_protocol_info", self.input_stem);
+        self.claim(&stem, Backend::Cpp, None)
+    }
+
+    /// 地面站遥测桥脚本
+    pub fn bridge(&mut self) -> PathBuf {
+        let stem = format!("{}_bridge", self.input_stem);
+        self.claim(&stem, Backend::Python, None)
+    }
+
+    /// 外部插件返回的文件，路径相对于输出目录；与已分配的路径相同时返回 `None`
+    pub fn plugin(&mut self, relative: &str) -> Option<PathBuf> {
+        self.used
+            .insert(relative.replace('\\', "/").to_lowercase())
+            .then(|| self.dir.join(relative))
+    }
+
+    fn claim(&mut self, name: &str, backend: Backend, namespace: Option<&str>) -> PathBuf {
+        // MATLAB 要求 classdef 文件名与类名相同，不转换大小写
+        let stem_case = match backend {
+            Backend::Matlab => FileNameCase::Preserve,
+            _ => self.case,
+        };
+        let stem = sanitize_file_stem(name, stem_case);
+        let extension = backend.extension();
+        let namespace: Vec<String> = namespace
+            .into_iter()
+            .flat_map(|ns| ns.split("::"))
+            .map(str::trim)
+            .filter(|level| !level.is_empty())
+            .map(|level| sanitize_file_stem(level, self.case))
+            .collect();
+        let mut file_name = format!("{}.{}", stem, extension);
+        let mut suffix = 2;
+        loop {
+            let relative = match &self.layout {
+                Some(layout) => layout.resolve(backend.name(), &namespace, &file_name),
+                None => PathBuf::from(&file_name),
+            };
+            if self
+                .used
+                .insert(relative.to_string_lossy().replace('\\', "/").to_lowercase())
+            {
+                return self.dir.join(relative);
+            }
+            file_name = format!("{}_{}.{}", stem, suffix, extension);
+            suffix += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_file_stem() {
+        let preserve = FileNameCase::Preserve;
+        assert_eq!(
+            sanitize_file_stem("GimbalCommand", preserve),
+            "GimbalCommand"
+        );
+        assert_eq!(sanitize_file_stem("a/b\\c:d*e?", preserve), "a_b_c_d_e_");
+        assert_eq!(sanitize_file_stem("云台控制", preserve), "云台控制");
+        assert_eq!(sanitize_file_stem("tab\there", preserve), "tab_here");
+        assert_eq!(sanitize_file_stem("name. ", preserve), "name");
+        assert_eq!(sanitize_file_stem("...", preserve), "_");
+        assert_eq!(sanitize_file_stem("", preserve), "_");
+        assert_eq!(sanitize_file_stem("con", preserve), "con_");
+        assert_eq!(sanitize_file_stem("COM1", preserve), "COM1_");
+        assert_eq!(sanitize_file_stem("Console", preserve), "Console");
+        assert_eq!(
+            sanitize_file_stem("GimbalCommand", FileNameCase::Lower),
+            "gimbalcommand"
+        );
+    }
+
+    #[test]
+    fn test_output_paths_next_to_input() {
+        let mut paths =
+            OutputPaths::new(Path::new("proto/robot.json"), None, FileNameCase::Preserve);
+        assert_eq!(
+            paths.for_input(Backend::Cpp, Some("Robot")),
+            PathBuf::from("proto/robot.hpp")
+        );
+        assert_eq!(paths.registry(), PathBuf::from("proto/robot_registry.hpp"));
+        assert_eq!(
+            paths.protocol_info(),
+            PathBuf::from("proto/robot_protocol_info.hpp")
+        );
+        assert_eq!(paths.umbrella(), PathBuf::from("proto/robot_packets.hpp"));
+        assert_eq!(paths.bridge(), PathBuf::from("proto/robot_bridge.py"));
+        assert_eq!(
+            paths.plugin("msg/Imu.msg"),
+            Some(PathBuf::from("proto/msg/Imu.msg"))
+        );
+        assert_eq!(paths.plugin("ROBOT.hpp"), None);
+
+        let mut paths = OutputPaths::new(Path::new("robot.json"), None, FileNameCase::Preserve);
+        assert_eq!(
+            paths.for_input(Backend::Cpp, None),
+            PathBuf::from("robot.hpp")
+        );
+    }
+
+    #[test]
+    fn test_output_paths_resolve_case_insensitive_collisions() {
+        let mut paths = OutputPaths::new(
+            Path::new("robot.json"),
+            Some(Path::new("out")),
+            FileNameCase::Preserve,
+        );
+        assert_eq!(
+            paths.for_packet("Status", Backend::Cpp, None),
+            PathBuf::from("out/Status.hpp")
+        );
+        assert_eq!(
+            paths.for_packet("STATUS", Backend::Cpp, None),
+            PathBuf::from("out/STATUS_2.hpp")
+        );
+        assert_eq!(
+            paths.for_packet("status", Backend::Cpp, None),
+            PathBuf::from("out/status_3.hpp")
+        );
+        // 扩展名不同的文件互不冲突
+        assert_eq!(
+            paths.for_packet("Status", Backend::Rust, None),
+            PathBuf::from("out/Status.rs")
+        );
+
+        let mut paths = OutputPaths::new(Path::new("robot.json"), None, FileNameCase::Lower);
+        assert_eq!(
+            paths.for_packet("Status", Backend::Cpp, None),
+            PathBuf::from("status.hpp")
+        );
+        assert_eq!(
+            paths.for_packet("STATUS", Backend::Cpp, None),
+            PathBuf::from("status_2.hpp")
+        );
+        assert_eq!(
+            paths.for_packet("Status", Backend::Matlab, None),
+            PathBuf::from("Status.m")
+        );
+    }
+
+    #[test]
+    fn test_registry_does_not_overwrite_packet() {
+        let mut paths = OutputPaths::new(Path::new("robot.json"), None, FileNameCase::Preserve);
+        assert_eq!(
+            paths.for_packet("robot_registry", Backend::Cpp, None),
+            PathBuf::from("robot_registry.hpp")
+        );
+        assert_eq!(paths.registry(), PathBuf::from("robot_registry_2.hpp"));
+    }
+
+    #[test]
+    fn test_include_path() {
+        let path = |s: &str| PathBuf::from(s);
+        assert_eq!(
+            include_path(&path("out/a.hpp"), &path("out/b.hpp")),
+            "b.hpp"
+        );
+        assert_eq!(include_path(&path("a.hpp"), &path("b.hpp")), "b.hpp");
+        assert_eq!(
+            include_path(&path("out/cpp/reg.hpp"), &path("out/cpp/Robot/A.hpp")),
+            "Robot/A.hpp"
+        );
+        assert_eq!(
+            include_path(&path("out/cpp/reg.hpp"), &path("out/Robot/cpp/A.hpp")),
+            "../Robot/cpp/A.hpp"
+        );
+    }
+
+    #[test]
+    fn test_output_layout_parse() {
+        assert!(OutputLayout::parse("{file}").is_ok());
+        assert!(OutputLayout::parse("{target}/{namespace_path}/{file}").is_ok());
+        assert!(OutputLayout::parse("gen/{target}/rplc_{file}").is_ok());
+
+        for (template, reason) in [
+            ("/abs/{file}", "必须是输出目录内的相对路径"),
+            ("C:/{file}", "必须是输出目录内的相对路径"),
+            ("../{file}", "不能包含 '..'"),
+            ("{target}", "必须包含且只包含一个 {file}"),
+            ("{file}/{target}", "{file} 必须位于最后一级"),
+            (
+                "ns_{namespace_path}/{file}",
+                "{namespace_path} 必须独占一级目录",
+            ),
+            (
+                "{lang}/{file}",
+                "未知的占位符 {lang}，可用的占位符为 {target}、{namespace_path}、{file}",
+            ),
+            ("{target/{file}", "'{target' 中的占位符没有闭合"),
+        ] {
+            assert_eq!(
+                OutputLayout::parse(template).unwrap_err(),
+                reason,
+                "{}",
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn test_output_paths_with_layout() {
+        let layout = OutputLayout::parse("{target}/{namespace_path}/{file}").unwrap();
+        let mut paths = OutputPaths::new(
+            Path::new("robot.json"),
+            Some(Path::new("out")),
+            FileNameCase::Preserve,
+        )
+        .with_layout(Some(layout));
+        assert_eq!(
+            paths.for_packet("Status", Backend::Cpp, Some("Robot::Referee")),
+            PathBuf::from("out/cpp/Robot/Referee/Status.hpp")
+        );
+        assert_eq!(
+            paths.for_packet("Status", Backend::C, Some("Robot::Referee")),
+            PathBuf::from("out/c/Robot/Referee/Status.h")
+        );
+        // 没有命名空间时省略这一层
+        assert_eq!(
+            paths.for_packet("Status", Backend::Python, None),
+            PathBuf::from("out/python/Status.py")
+        );
+        assert_eq!(
+            paths.registry(),
+            PathBuf::from("out/cpp/robot_registry.hpp")
+        );
+        // 不同目录中的同名文件互不冲突，同一目录中仍按忽略大小写的方式检测
+        assert_eq!(
+            paths.for_packet("Status", Backend::Cpp, Some("Other")),
+            PathBuf::from("out/cpp/Other/Status.hpp")
+        );
+        assert_eq!(
+            paths.for_packet("STATUS", Backend::Cpp, Some("robot::referee")),
+            PathBuf::from("out/cpp/robot/referee/STATUS_2.hpp")
+        );
+    }
+}
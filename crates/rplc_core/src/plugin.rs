@@ -0,0 +1,301 @@
+//! 外部后端插件的子进程协议，队伍可以编写私有后端而不必修改 rplc
+//!
+//! 插件是名为 `rplc-backend-<名称>` 的可执行文件：rplc 把校验通过的定义及其布局作为一个 JSON 对象
+//! 写到插件的标准输入，插件在标准输出返回 `{"files": [{"path": ..., "content": ...}]}`，
+//! 以非零状态退出表示失败，错误信息写到标准错误
+
+use std::collections::HashSet;
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::config::{Config, ConfigOrArray, Field};
+use crate::diagnostics::Severity;
+use crate::fingerprint::{format_layout_hash, layout_hash};
+use crate::generator::{GenerateOptions, MultiGenerateError};
+use crate::layout::{FieldLayout, compute_layout, with_reserved_gaps};
+use crate::validator::{parse_command_id, validate};
+
+/// 协议版本，请求的格式有不兼容的修改时递增
+pub const PLUGIN_PROTOCOL: u32 = 1;
+
+/// 插件可执行文件名的前缀
+pub const PLUGIN_PREFIX: &str = "rplc-backend-";
+
+/// 写到插件标准输入的请求
+#[derive(Debug, Serialize)]
+pub struct PluginRequest<'a> {
+    pub protocol: u32,
+    pub rplc_version: &'static str,
+    /// 插件名称，即 `--target` 的值
+    pub target: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol_version: Option<String>,
+    pub options: &'a GenerateOptions,
+    pub packets: Vec<PluginPacket>,
+}
+
+/// 请求中的单个包
+#[derive(Debug, Serialize)]
+pub struct PluginPacket {
+    /// 定义原文，分组与嵌套结构体保持书写时的形式
+    pub definition: Config,
+    pub command_id: u16,
+    /// 生成代码中的写法，例如 `0x1A2B3C4D`
+    pub layout_hash: String,
+    /// 包的总字节数（含尾部填充）
+    pub size: u32,
+    pub align: u32,
+    /// 含位域时按位流排列
+    pub bitstream: bool,
+    /// 按顺序排列的所有字段及其位置，`offset` 留下的空隙与 `{"reserved": n}` 展开为保留字段
+    pub fields: Vec<PluginField>,
+}
+
+/// 请求中的单个字段：定义中的键，加上分组信息与计算出的位置
+#[derive(Debug, Serialize)]
+pub struct PluginField {
+    /// 引用枚举的字段，`type` 为枚举的底层类型
+    #[serde(flatten)]
+    pub definition: Field,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enum_type: Option<String>,
+    pub reserved: bool,
+    /// 从该字段开始的分组标题
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// 所在具名嵌套结构体的成员名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub layout: FieldLayout,
+}
+
+/// 插件在标准输出返回的结果
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginResponse {
+    pub files: Vec<PluginFile>,
+}
+
+/// 插件要写出的文件
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginFile {
+    /// 相对于输出目录的路径，使用 `/` 分隔
+    pub path: String,
+    pub content: String,
+    /// 文件所属的包，用于构建报告与产物清单
+    #[serde(default)]
+    pub packet: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("插件的输出不是有效的 JSON 结果: {0}")]
+    InvalidResponse(#[from] serde_json::Error),
+    #[error("插件返回的路径 '{0}' 无效，必须是输出目录中的相对路径")]
+    InvalidPath(String),
+    #[error("插件返回了重复的路径 '{0}'")]
+    DuplicatePath(String),
+}
+
+/// 插件名称只能包含 ASCII 字母、数字、`-` 与 `_`，保证可执行文件名在各平台上合法
+pub fn is_plugin_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// 构造写到插件标准输入的请求，定义有错误时返回 `ValidationError`
+pub fn plugin_request(
+    json_input: &str,
+    target: &str,
+    options: &GenerateOptions,
+) -> Result<String, MultiGenerateError> {
+    let parsed: ConfigOrArray = serde_json::from_str(json_input)?;
+    let protocol_version = parsed.protocol_version().map(str::to_string);
+    let mut packets = Vec::new();
+    for packet in parsed.into_packets() {
+        let packet_json = serde_json::to_string(&packet)?;
+        if validate(&packet_json)
+            .iter()
+            .any(|diag| diag.severity == Severity::Error)
+        {
+            return Err(MultiGenerateError::ValidationError);
+        }
+        let command_id = parse_command_id(&packet.command_id)
+            .map_err(|_| MultiGenerateError::ValidationError)?;
+        let layout_hash = format_layout_hash(layout_hash(&packet));
+        let expanded = with_reserved_gaps(&packet);
+        let layout = compute_layout(&expanded).ok_or(MultiGenerateError::ValidationError)?;
+        let fields = expanded
+            .fields
+            .into_iter()
+            .zip(layout.fields)
+            .map(|(field, layout)| PluginField {
+                enum_type: field.enum_type.clone(),
+                reserved: field.reserved.is_some(),
+                section: field.section.clone(),
+                group: field.group.as_ref().and_then(|group| group.name.clone()),
+                definition: field,
+                layout,
+            })
+            .collect();
+        // 被引用的包同样在请求中，不再重复写出依赖
+        let mut definition = packet;
+        definition.dependencies.clear();
+        packets.push(PluginPacket {
+            definition,
+            command_id,
+            layout_hash,
+            size: layout.size,
+            align: layout.align,
+            bitstream: layout.bitstream,
+            fields,
+        });
+    }
+    let request = PluginRequest {
+        protocol: PLUGIN_PROTOCOL,
+        rplc_version: env!("CARGO_PKG_VERSION"),
+        target,
+        protocol_version,
+        options,
+        packets,
+    };
+    Ok(serde_json::to_string(&request)?)
+}
+
+/// 解析插件的标准输出，检查每个文件都留在输出目录中且路径不重复
+pub fn parse_plugin_response(output: &str) -> Result<Vec<PluginFile>, PluginError> {
+    let response: PluginResponse = serde_json::from_str(output)?;
+    let mut seen = HashSet::new();
+    for file in &response.files {
+        let path = Path::new(&file.path);
+        let relative = !file.path.contains('\\')
+            && path.components().next().is_some()
+            && path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !relative {
+            return Err(PluginError::InvalidPath(file.path.clone()));
+        }
+        if !seen.insert(file.path.to_lowercase()) {
+            return Err(PluginError::DuplicatePath(file.path.clone()));
+        }
+    }
+    Ok(response.files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_request() {
+        let json = r#"{
+            "protocol_version": "1.2.0",
+            "packets": [
+                { "packet_name": "Imu", "command_id": "0x0101", "comment": "IMU", "fields": [
+                    { "name": "id", "type": "uint8_t", "comment": "A" },
+                    { "section": "姿态" },
+                    { "name": "yaw", "type": "float", "offset": 4, "comment": "B", "unit": "rad" }
+                ] },
+                { "packet_name": "Status", "command_id": "258", "comment": "S", "fields": [
+                    { "name": "imu", "type": "Imu", "comment": "C" }
+                ] }
+            ]
+        }"#;
+        let request = plugin_request(json, "ros2", &GenerateOptions::default()).unwrap();
+        let request: serde_json::Value = serde_json::from_str(&request).unwrap();
+        assert_eq!(request["protocol"], 1);
+        assert_eq!(request["target"], "ros2");
+        assert_eq!(request["protocol_version"], "1.2.0");
+        assert_eq!(request["options"]["indent_width"], 4);
+
+        let imu = &request["packets"][0];
+        assert_eq!(imu["command_id"], 0x0101);
+        assert_eq!(imu["size"], 8);
+        assert!(imu["layout_hash"].as_str().unwrap().starts_with("0x"));
+        assert_eq!(imu["definition"]["fields"].as_array().unwrap().len(), 3);
+        let fields = imu["fields"].as_array().unwrap();
+        let names: Vec<_> = fields
+            .iter()
+            .map(|field| {
+                (
+                    field["name"].as_str().unwrap(),
+                    field["reserved"].as_bool().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(names, [("id", false), ("reserved_1", true), ("yaw", false)]);
+        assert_eq!(fields[2]["type"], "float");
+        assert_eq!(fields[2]["unit"], "rad");
+        assert_eq!(fields[2]["section"], "姿态");
+        assert_eq!(fields[2]["layout"]["offset_bits"], 32);
+
+        let status = &request["packets"][1];
+        assert_eq!(status["command_id"], 258);
+        assert_eq!(status["size"], 8);
+        assert!(status["definition"].get("dependencies").is_none());
+
+        let invalid = r#"{ "packet_name": "A", "command_id": "0x1", "fields": [
+            { "name": "x", "type": "flaot" } ] }"#;
+        assert!(matches!(
+            plugin_request(invalid, "ros2", &GenerateOptions::default()),
+            Err(MultiGenerateError::ValidationError)
+        ));
+    }
+
+    #[test]
+    fn test_parse_plugin_response() {
+        let files = parse_plugin_response(
+            r#"{ "files": [
+                { "path": "msg/Imu.msg", "content": "float32 yaw\n", "packet": "Imu" },
+                { "path": "CMakeLists.txt", "content": "" }
+            ] }"#,
+        )
+        .unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].packet.as_deref(), Some("Imu"));
+        assert_eq!(files[1].packet, None);
+
+        for path in [
+            "",
+            "/etc/passwd",
+            "../up.txt",
+            "a/../../b",
+            "./a",
+            "C:\\\\a",
+            "a\\\\b",
+        ] {
+            let output = format!(
+                r#"{{ "files": [{{ "path": "{}", "content": "" }}] }}"#,
+                path
+            );
+            assert!(
+                matches!(
+                    parse_plugin_response(&output),
+                    Err(PluginError::InvalidPath(_))
+                ),
+                "{}",
+                path
+            );
+        }
+        assert!(matches!(
+            parse_plugin_response(
+                r#"{ "files": [{ "path": "a.h", "content": "" }, { "path": "A.h", "content": "" }] }"#
+            ),
+            Err(PluginError::DuplicatePath(_))
+        ));
+        assert!(matches!(
+            parse_plugin_response("generated a.h"),
+            Err(PluginError::InvalidResponse(_))
+        ));
+
+        assert!(is_plugin_name("ros2-msg_v1"));
+        assert!(!is_plugin_name("../evil"));
+        assert!(!is_plugin_name(""));
+    }
+}
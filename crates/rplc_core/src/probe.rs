@@ -0,0 +1,219 @@
+//! 用真实编译器检查结构体布局
+//!
+//! 生成一个包含各个包的 C 头文件的探测程序，打印每个结构体的 `sizeof` 与成员的 `offsetof`，
+//! 再与布局引擎的预测比较，发现编译器的紧凑语义与 rplc 的模型不一致的情况
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::backend::Backend;
+use crate::config::{Config, ConfigOrArray};
+use crate::generator::{GenerateOptions, MultiGenerateError};
+use crate::layout::compute_layout;
+
+/// 探测程序输出中表示结构体大小的成员名
+const SIZEOF: &str = "sizeof";
+
+/// 编译器的实际布局与预测不一致之处
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LayoutMismatch {
+    pub packet: String,
+    /// 成员的访问路径；为 None 时比较的是结构体大小
+    pub member: Option<String>,
+    /// 布局引擎预测的字节数
+    pub expected: u32,
+    /// 探测程序打印的字节数，输出中缺少该项时为 None
+    pub actual: Option<u32>,
+}
+
+impl fmt::Display for LayoutMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match &self.member {
+            Some(member) => format!("包 '{}' 的成员 '{}' 的偏移", self.packet, member),
+            None => format!("包 '{}' 的大小", self.packet),
+        };
+        match self.actual {
+            Some(actual) => write!(
+                f,
+                "{}为 {} 字节，rplc 预测为 {} 字节",
+                what, actual, self.expected
+            ),
+            None => write!(f, "探测程序没有输出{}", what),
+        }
+    }
+}
+
+/// 生成布局探测程序（C11 源文件）
+///
+/// 程序内联各个包的 C 头文件，不依赖 RPL 的头文件；每行输出 `包名\t成员\t字节数`，
+/// 结构体大小的成员名为 `sizeof`。含位域的包按位流布局，只比较结构体大小
+pub fn generate_layout_probe(
+    json_input: &str,
+    options: &GenerateOptions,
+) -> Result<String, MultiGenerateError> {
+    let packets = serde_json::from_str::<ConfigOrArray>(json_input)?.into_packets();
+
+    let mut out = String::from("/* rplc 布局探测程序，由 rplc --verify-layout 生成 */\n");
+    out.push_str("#include <stddef.h>\n#include <stdio.h>\n\n");
+    for config in &packets {
+        let header = Backend::C.generate(&serde_json::to_string(config)?, options)?;
+        out.push_str(&header);
+        out.push('\n');
+    }
+
+    out.push_str("int main(void)\n{\n");
+    for config in &packets {
+        let type_name = format!("{}_t", config.packet_name);
+        out.push_str(&format!(
+            "    printf(\"%s\\t%s\\t%u\\n\", \"{}\", \"{}\", (unsigned)sizeof({}));\n",
+            config.packet_name, SIZEOF, type_name
+        ));
+        for member in probed_members(config) {
+            out.push_str(&format!(
+                "    printf(\"%s\\t%s\\t%u\\n\", \"{}\", \"{}\", (unsigned)offsetof({}, {}));\n",
+                config.packet_name, member, type_name, member
+            ));
+        }
+    }
+    out.push_str("    return 0;\n}\n");
+    Ok(out)
+}
+
+/// 比较探测程序的输出与布局引擎的预测，返回所有不一致之处
+pub fn check_layout_probe(
+    json_input: &str,
+    probe_output: &str,
+) -> Result<Vec<LayoutMismatch>, MultiGenerateError> {
+    let packets = serde_json::from_str::<ConfigOrArray>(json_input)?.into_packets();
+    let actual: HashMap<(&str, &str), u32> = probe_output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.trim_end().split('\t');
+            let packet = parts.next()?;
+            let member = parts.next()?;
+            let value = parts.next()?.parse().ok()?;
+            Some(((packet, member), value))
+        })
+        .collect();
+
+    let mut mismatches = Vec::new();
+    for config in &packets {
+        let layout = compute_layout(config).ok_or(MultiGenerateError::ValidationError)?;
+        let mut expected = vec![(None, layout.size)];
+        if !layout.bitstream {
+            expected.extend(
+                config
+                    .fields
+                    .iter()
+                    .zip(&layout.fields)
+                    .map(|(field, layout)| (Some(field.member_path()), layout.offset_bits / 8)),
+            );
+        }
+        for (member, expected) in expected {
+            let key = (
+                config.packet_name.as_str(),
+                member.as_deref().unwrap_or(SIZEOF),
+            );
+            let actual = actual.get(&key).copied();
+            if actual != Some(expected) {
+                mismatches.push(LayoutMismatch {
+                    packet: config.packet_name.clone(),
+                    member,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    Ok(mismatches)
+}
+
+/// 需要打印偏移的成员；位域没有地址，含位域的包按位流布局，不比较成员偏移
+fn probed_members(config: &Config) -> Vec<String> {
+    if config.fields.iter().any(|field| field.bit_field.is_some()) {
+        return Vec::new();
+    }
+    config
+        .fields
+        .iter()
+        .map(|field| field.member_path())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKETS: &str = r#"[
+        {
+            "packet_name": "Gimbal",
+            "command_id": "0x0101",
+            "fields": [
+                { "name": "mode", "type": "uint8_t", "comment": "M" },
+                { "name": "yaw", "type": "float", "comment": "Y" }
+            ]
+        },
+        {
+            "packet_name": "Flags",
+            "command_id": "0x0102",
+            "fields": [
+                { "name": "a", "type": "uint8_t", "bit_field": 3, "comment": "A" },
+                { "name": "b", "type": "uint16_t", "bit_field": 9, "comment": "B" }
+            ]
+        }
+    ]"#;
+
+    #[test]
+    fn test_generate_layout_probe() {
+        let probe = generate_layout_probe(PACKETS, &GenerateOptions::default()).unwrap();
+        assert!(probe.contains("#pragma pack(push, 1)\n"));
+        assert!(probe.contains(
+            r#"printf("%s\t%s\t%u\n", "Gimbal", "yaw", (unsigned)offsetof(Gimbal_t, yaw));"#
+        ));
+        assert!(probe.contains(r#""Flags", "sizeof", (unsigned)sizeof(Flags_t)"#));
+        assert!(!probe.contains("offsetof(Flags_t"));
+        assert!(probe.ends_with("    return 0;\n}\n"));
+    }
+
+    #[test]
+    fn test_check_layout_probe() {
+        let output = "Gimbal\tsizeof\t5\nGimbal\tmode\t0\nGimbal\tyaw\t1\nFlags\tsizeof\t2\n";
+        assert!(check_layout_probe(PACKETS, output).unwrap().is_empty());
+
+        // 编译器没有按 packed 处理时 yaw 被对齐到 4 字节
+        let output = "Gimbal\tsizeof\t8\r\nGimbal\tmode\t0\r\nGimbal\tyaw\t4\r\n";
+        let mismatches = check_layout_probe(PACKETS, output).unwrap();
+        assert_eq!(
+            mismatches
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "包 'Gimbal' 的大小为 8 字节，rplc 预测为 5 字节",
+                "包 'Gimbal' 的成员 'yaw' 的偏移为 4 字节，rplc 预测为 1 字节",
+                "探测程序没有输出包 'Flags' 的大小",
+            ]
+        );
+        assert!(check_layout_probe("not json", "").is_err());
+    }
+
+    #[test]
+    fn test_probe_named_group_members() {
+        let json = r#"{
+            "packet_name": "Status",
+            "command_id": "0x0103",
+            "fields": [
+                { "name": "id", "type": "uint8_t", "comment": "I" },
+                { "name": "pose", "comment": "P", "group": [
+                    { "name": "x", "type": "float", "comment": "X" }
+                ] }
+            ]
+        }"#;
+        let probe = generate_layout_probe(json, &GenerateOptions::default()).unwrap();
+        assert!(probe.contains("offsetof(Status_t, pose.x)"));
+        let output = "Status\tsizeof\t5\nStatus\tid\t0\nStatus\tpose.x\t1\n";
+        assert!(check_layout_probe(json, output).unwrap().is_empty());
+    }
+}
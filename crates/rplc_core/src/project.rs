@@ -0,0 +1,392 @@
+//! 项目配置 `rplc.toml`：同一目录树中的多次生成共用的设置
+//!
+//! ```toml
+//! [output]
+//! layout = "{target}/{namespace_path}/{file}"
+//!
+//! [warnings]
+//! "rplc::doc::missing" = 10
+//!
+//! [types]
+//! allowed = ["uint8_t", "int16_t", "float"]
+//!
+//! [compilers]
+//! profiles = ["armcc", "msvc"]
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use miette::Diagnostic;
+use toml_edit::{Document, Item};
+
+use crate::compiler::Compiler;
+use crate::diagnostics::{RplcDiagnostic, Severity};
+use crate::output_path::OutputLayout;
+use crate::rules::rules;
+use crate::suggest::{SUPPORTED_TYPES, closest, did_you_mean};
+
+/// 项目配置的文件名
+pub const PROJECT_CONFIG_FILE: &str = "rplc.toml";
+
+/// 项目配置中的表与键
+const OUTPUT_KEYS: &[&str] = &["layout"];
+const TYPES_KEYS: &[&str] = &["allowed"];
+const COMPILERS_KEYS: &[&str] = &["profiles"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectConfig {
+    /// 输出目录内的布局模板，见 [`OutputLayout`]
+    pub output_layout: Option<OutputLayout>,
+    /// CI 模式下各条警告规则允许出现的次数，键为诊断代码；未列出的规则不限制
+    pub warning_budgets: BTreeMap<String, usize>,
+    /// 字段允许使用的基础类型，见 [`crate::ValidateOptions::allowed_types`]
+    pub allowed_types: Option<Vec<String>>,
+    /// 使用这份协议的所有编译器，见 [`crate::ValidateOptions::compiler_profiles`]
+    pub compiler_profiles: Vec<Compiler>,
+}
+
+/// 一条警告规则的预算使用情况
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarningBudget {
+    pub code: String,
+    /// 本次校验报告的次数
+    pub count: usize,
+    /// rplc.toml 中允许的次数
+    pub allowed: usize,
+}
+
+impl WarningBudget {
+    pub fn exceeded(&self) -> bool {
+        self.count > self.allowed
+    }
+}
+
+impl ProjectConfig {
+    /// 解析配置文件的内容；未知的表与键视为错误，避免拼写错误被静默忽略
+    pub fn parse(toml: &str) -> Result<Self, String> {
+        let document = Document::parse(toml).map_err(|e| e.message().trim().to_string())?;
+        let mut config = ProjectConfig::default();
+        for (key, item) in document.iter() {
+            if key == "warnings" {
+                config.warning_budgets = parse_warning_budgets(item)?;
+                continue;
+            }
+            if key == "types" {
+                config.allowed_types = parse_allowed_types(item)?;
+                continue;
+            }
+            if key == "compilers" {
+                config.compiler_profiles = parse_compiler_profiles(item)?;
+                continue;
+            }
+            if key != "output" {
+                return Err(format!("未知的表 [{}]", key));
+            }
+            let Item::Table(output) = item else {
+                return Err("output 必须是表".to_string());
+            };
+            for (key, item) in output.iter() {
+                if !OUTPUT_KEYS.contains(&key) {
+                    return Err(format!("[output] 中未知的键 '{}'", key));
+                }
+                let layout = item
+                    .as_str()
+                    .ok_or_else(|| "output.layout 必须是字符串".to_string())?;
+                config.output_layout = Some(
+                    OutputLayout::parse(layout)
+                        .map_err(|reason| format!("output.layout 无效：{}", reason))?,
+                );
+            }
+        }
+        Ok(config)
+    }
+
+    /// 按诊断代码统计警告，列出每条有预算的规则的使用情况
+    pub fn warning_budget_usage(&self, diagnostics: &[RplcDiagnostic]) -> Vec<WarningBudget> {
+        self.warning_budgets
+            .iter()
+            .map(|(code, &allowed)| WarningBudget {
+                code: code.clone(),
+                count: diagnostics
+                    .iter()
+                    .filter(|diag| diag.severity == Severity::Warning)
+                    .filter(|diag| diag.code.code().is_some_and(|c| c.to_string() == *code))
+                    .count(),
+                allowed,
+            })
+            .collect()
+    }
+
+    /// 从 `start` 所在的目录开始逐级向上查找 `rplc.toml`
+    pub fn discover(start: &Path) -> Option<PathBuf> {
+        let dir = if start.is_dir() {
+            start
+        } else {
+            start.parent()?
+        };
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let dir = dir.canonicalize().ok()?;
+        dir.ancestors()
+            .map(|ancestor| ancestor.join(PROJECT_CONFIG_FILE))
+            .find(|candidate| candidate.is_file())
+    }
+}
+
+/// `[warnings]` 表：键必须是默认级别为警告的规则，值是非负整数
+fn parse_warning_budgets(item: &Item) -> Result<BTreeMap<String, usize>, String> {
+    let Item::Table(table) = item else {
+        return Err("warnings 必须是表".to_string());
+    };
+    let warning_codes: Vec<String> = rules()
+        .into_iter()
+        .filter(|rule| rule.severity == Severity::Warning)
+        .map(|rule| rule.code)
+        .collect();
+    let mut budgets = BTreeMap::new();
+    for (key, item) in table.iter() {
+        if !warning_codes.iter().any(|code| code == key) {
+            let candidates: Vec<&str> = warning_codes.iter().map(String::as_str).collect();
+            let hint = closest(key, &candidates)
+                .map(|code| format!("，{}", did_you_mean(code)))
+                .unwrap_or_default();
+            return Err(format!("[warnings] 中未知的警告规则 '{}'{}", key, hint));
+        }
+        let allowed = item
+            .as_integer()
+            .and_then(|n| usize::try_from(n).ok())
+            .ok_or_else(|| format!("warnings.\"{}\" 必须是非负整数", key))?;
+        budgets.insert(key.to_string(), allowed);
+    }
+    Ok(budgets)
+}
+
+/// `[types]` 表：`allowed` 是基础类型的列表，未知的类型报错并给出拼写建议
+fn parse_allowed_types(item: &Item) -> Result<Option<Vec<String>>, String> {
+    let Item::Table(table) = item else {
+        return Err("types 必须是表".to_string());
+    };
+    let mut allowed = None;
+    for (key, item) in table.iter() {
+        if !TYPES_KEYS.contains(&key) {
+            return Err(format!("[types] 中未知的键 '{}'", key));
+        }
+        let array = item
+            .as_array()
+            .ok_or_else(|| "types.allowed 必须是字符串数组".to_string())?;
+        let mut types = Vec::new();
+        for value in array.iter() {
+            let ty = value
+                .as_str()
+                .ok_or_else(|| "types.allowed 必须是字符串数组".to_string())?;
+            if !SUPPORTED_TYPES.contains(&ty) {
+                let hint = closest(ty, SUPPORTED_TYPES)
+                    .map(|ty| format!("，{}", did_you_mean(ty)))
+                    .unwrap_or_default();
+                return Err(format!("types.allowed 中未知的类型 '{}'{}", ty, hint));
+            }
+            types.push(ty.to_string());
+        }
+        allowed = Some(types);
+    }
+    Ok(allowed)
+}
+
+/// `[compilers]` 表：`profiles` 是编译器的列表，写法与 `--compiler` 相同
+fn parse_compiler_profiles(item: &Item) -> Result<Vec<Compiler>, String> {
+    let Item::Table(table) = item else {
+        return Err("compilers 必须是表".to_string());
+    };
+    let mut profiles = Vec::new();
+    for (key, item) in table.iter() {
+        if !COMPILERS_KEYS.contains(&key) {
+            return Err(format!("[compilers] 中未知的键 '{}'", key));
+        }
+        let array = item
+            .as_array()
+            .ok_or_else(|| "compilers.profiles 必须是字符串数组".to_string())?;
+        for value in array.iter() {
+            let id = value
+                .as_str()
+                .ok_or_else(|| "compilers.profiles 必须是字符串数组".to_string())?;
+            let compiler = Compiler::from_id(id).ok_or_else(|| {
+                let ids = Compiler::ALL.map(Compiler::id);
+                let hint = closest(id, &ids)
+                    .map(|id| format!("，{}", did_you_mean(id)))
+                    .unwrap_or_default();
+                format!("compilers.profiles 中未知的编译器 '{}'{}", id, hint)
+            })?;
+            profiles.push(compiler);
+        }
+    }
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_project_config() {
+        assert_eq!(ProjectConfig::parse("").unwrap(), ProjectConfig::default());
+        let config = ProjectConfig::parse(
+            "# 按目标语言分目录\n[output]\nlayout = \"{target}/{namespace_path}/{file}\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.output_layout,
+            Some(OutputLayout::parse("{target}/{namespace_path}/{file}").unwrap())
+        );
+
+        assert_eq!(
+            ProjectConfig::parse("[ouput]\nlayout = \"{file}\"").unwrap_err(),
+            "未知的表 [ouput]"
+        );
+        assert_eq!(
+            ProjectConfig::parse("[output]\nlayot = \"{file}\"").unwrap_err(),
+            "[output] 中未知的键 'layot'"
+        );
+        assert_eq!(
+            ProjectConfig::parse("[output]\nlayout = 1").unwrap_err(),
+            "output.layout 必须是字符串"
+        );
+        assert_eq!(
+            ProjectConfig::parse("[output]\nlayout = \"{target}\"").unwrap_err(),
+            "output.layout 无效：必须包含且只包含一个 {file}"
+        );
+        assert!(ProjectConfig::parse("[output").is_err());
+    }
+
+    #[test]
+    fn test_parse_warning_budgets() {
+        let config = ProjectConfig::parse(
+            "[warnings]\n\"rplc::doc::missing\" = 10\n\"rplc::style::field\" = 0\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.warning_budgets,
+            BTreeMap::from([
+                ("rplc::doc::missing".to_string(), 10),
+                ("rplc::style::field".to_string(), 0)
+            ])
+        );
+
+        assert_eq!(
+            ProjectConfig::parse("[warnings]\n\"rplc::doc::mising\" = 1").unwrap_err(),
+            "[warnings] 中未知的警告规则 'rplc::doc::mising'，是否应为 'rplc::doc::missing'？"
+        );
+        // 错误不能靠预算放行
+        assert!(
+            ProjectConfig::parse("[warnings]\n\"rplc::invalid_field_type\" = 1")
+                .unwrap_err()
+                .starts_with("[warnings] 中未知的警告规则")
+        );
+        assert_eq!(
+            ProjectConfig::parse("[warnings]\n\"rplc::doc::missing\" = -1").unwrap_err(),
+            "warnings.\"rplc::doc::missing\" 必须是非负整数"
+        );
+    }
+
+    #[test]
+    fn test_parse_allowed_types() {
+        let config = ProjectConfig::parse("[types]\nallowed = [\"uint8_t\", \"float\"]\n").unwrap();
+        assert_eq!(
+            config.allowed_types,
+            Some(vec!["uint8_t".to_string(), "float".to_string()])
+        );
+        assert_eq!(ProjectConfig::parse("[types]").unwrap().allowed_types, None);
+
+        assert_eq!(
+            ProjectConfig::parse("[types]\nallowed = [\"flaot\"]").unwrap_err(),
+            "types.allowed 中未知的类型 'flaot'，是否应为 'float'？"
+        );
+        assert_eq!(
+            ProjectConfig::parse("[types]\nallowed = \"float\"").unwrap_err(),
+            "types.allowed 必须是字符串数组"
+        );
+        assert_eq!(
+            ProjectConfig::parse("[types]\nallow = []").unwrap_err(),
+            "[types] 中未知的键 'allow'"
+        );
+    }
+
+    #[test]
+    fn test_parse_compiler_profiles() {
+        let config =
+            ProjectConfig::parse("[compilers]\nprofiles = [\"armcc-pragma\", \"msvc\"]\n").unwrap();
+        assert_eq!(
+            config.compiler_profiles,
+            vec![Compiler::ArmccPragma, Compiler::Msvc]
+        );
+        assert!(
+            ProjectConfig::parse("")
+                .unwrap()
+                .compiler_profiles
+                .is_empty()
+        );
+
+        assert_eq!(
+            ProjectConfig::parse("[compilers]\nprofiles = [\"msvcc\"]").unwrap_err(),
+            "compilers.profiles 中未知的编译器 'msvcc'，是否应为 'msvc'？"
+        );
+        assert_eq!(
+            ProjectConfig::parse("[compilers]\nprofiles = \"gcc\"").unwrap_err(),
+            "compilers.profiles 必须是字符串数组"
+        );
+        assert_eq!(
+            ProjectConfig::parse("[compilers]\nprofile = []").unwrap_err(),
+            "[compilers] 中未知的键 'profile'"
+        );
+    }
+
+    #[test]
+    fn test_warning_budget_usage() {
+        let config = ProjectConfig::parse(
+            "[warnings]\n\"rplc::doc::missing\" = 1\n\"rplc::style::field\" = 3",
+        )
+        .unwrap();
+        let diags = crate::validate(
+            r#"{"packet_name":"A","command_id":"1","comment":"A","fields":[
+                {"name":"a","type":"uint8_t"},{"name":"b","type":"uint8_t"}]}"#,
+        );
+        let usage = config.warning_budget_usage(&diags);
+        assert_eq!(
+            usage,
+            vec![
+                WarningBudget {
+                    code: "rplc::doc::missing".to_string(),
+                    count: 2,
+                    allowed: 1
+                },
+                WarningBudget {
+                    code: "rplc::style::field".to_string(),
+                    count: 0,
+                    allowed: 3
+                },
+            ]
+        );
+        assert!(usage[0].exceeded());
+        assert!(!usage[1].exceeded());
+    }
+
+    #[test]
+    fn test_discover_project_config() {
+        let root = std::env::temp_dir().join(format!("rplc_project_{}", std::process::id()));
+        let nested = root.join("proto/robot");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join(PROJECT_CONFIG_FILE), "").unwrap();
+
+        let found = ProjectConfig::discover(&nested.join("packets.json")).unwrap();
+        assert_eq!(
+            found,
+            root.canonicalize().unwrap().join(PROJECT_CONFIG_FILE)
+        );
+        assert_eq!(ProjectConfig::discover(&nested).unwrap(), found);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,188 @@
+//! 随机生成 `Config` 的属性测试，检查布局与代码生成在各种输入下保持的不变量
+//!
+//! 目前还没有序列化后端，encode/decode 往返测试待后端加入后补充
+
+use proptest::prelude::*;
+
+use crate::config::{Config, Field};
+use crate::diagnostics::Severity;
+use crate::generator::generate;
+use crate::layout::{c_type_size, compute_layout};
+use crate::validator::validate;
+
+const SCALAR_TYPES: &[&str] = &[
+    "bool", "uint8_t", "int8_t", "uint16_t", "int16_t", "uint32_t", "int32_t", "uint64_t",
+    "int64_t", "float", "double",
+];
+
+const BIT_FIELD_TYPES: &[&str] = &["uint8_t", "uint16_t", "uint32_t"];
+
+/// 普通字段或数组字段的类型
+fn plain_type() -> impl Strategy<Value = String> {
+    (
+        prop::sample::select(SCALAR_TYPES),
+        prop::option::of(1u32..=16),
+    )
+        .prop_map(|(ty, len)| match len {
+            Some(len) => format!("{}[{}]", ty, len),
+            None => ty.to_string(),
+        })
+}
+
+/// 位域字段的 (类型, 位宽)
+fn bit_field_type() -> impl Strategy<Value = (String, u8)> {
+    prop::sample::select(BIT_FIELD_TYPES).prop_flat_map(|ty| {
+        let bits = c_type_size(ty).unwrap() as u8 * 8;
+        (Just(ty.to_string()), 1..=bits)
+    })
+}
+
+/// 字段名带有序号前缀，保证唯一且不是关键字
+fn fields_from(kinds: Vec<(String, Option<u8>)>) -> Vec<Field> {
+    kinds
+        .into_iter()
+        .enumerate()
+        .map(|(i, (ty, bit_field))| Field {
+            name: format!("f{}_value", i),
+            ty,
+            array_len: None,
+            enum_type: None,
+            bit_field,
+            comment: Some(format!("字段 {}", i).into()),
+            unit: None,
+            id: None,
+            min: None,
+            max: None,
+            typical: None,
+            values: None,
+            offset: None,
+            endianness: None,
+            reserved: None,
+            section: None,
+            group: None,
+        })
+        .collect()
+}
+
+fn config_with(fields: Vec<Field>, packed: bool) -> Config {
+    Config {
+        packet_name: "RandomPacket".to_string(),
+        command_id: "0x0101".to_string(),
+        packed,
+        comment: Some("随机生成的包".into()),
+        fields,
+        ..Default::default()
+    }
+}
+
+/// 不含位域的包，紧凑与否随机
+fn plain_config() -> impl Strategy<Value = Config> {
+    (
+        prop::collection::vec(plain_type().prop_map(|ty| (ty, None)), 1..12),
+        any::<bool>(),
+    )
+        .prop_map(|(kinds, packed)| config_with(fields_from(kinds), packed))
+}
+
+/// 含位域的紧凑包，位域与普通字段混合
+fn bit_field_config() -> impl Strategy<Value = Config> {
+    let kind = prop_oneof![
+        plain_type().prop_map(|ty| (ty, None)),
+        bit_field_type().prop_map(|(ty, bits)| (ty, Some(bits))),
+    ];
+    (prop::collection::vec(kind, 1..12), bit_field_type()).prop_map(|(mut kinds, (ty, bits))| {
+        kinds.push((ty, Some(bits)));
+        config_with(fields_from(kinds), true)
+    })
+}
+
+fn any_config() -> impl Strategy<Value = Config> {
+    prop_oneof![plain_config(), bit_field_config()]
+}
+
+/// 字段在生成的结构体中的声明位置
+fn declaration_position(code: &str, field: &Field) -> Option<usize> {
+    let body = code.find("struct ")?;
+    let decl = match field.bit_field {
+        Some(bits) => format!(" {} : {};", field.name, bits),
+        None => format!(" {};", field.name),
+    };
+    code[body..].find(&decl).map(|pos| body + pos)
+}
+
+proptest! {
+    #[test]
+    fn prop_random_configs_validate_and_generate(config in any_config()) {
+        let json = serde_json::to_string(&config).unwrap();
+        let errors: Vec<_> = validate(&json)
+            .into_iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| d.code.to_string())
+            .collect();
+        prop_assert!(errors.is_empty(), "{:?}", errors);
+        prop_assert!(generate(&json).is_ok());
+    }
+
+    #[test]
+    fn prop_generated_field_order_matches_input(config in any_config()) {
+        let json = serde_json::to_string(&config).unwrap();
+        let code = generate(&json).unwrap();
+
+        let positions: Vec<_> = config
+            .fields
+            .iter()
+            .map(|field| declaration_position(&code, field))
+            .collect();
+        prop_assert!(positions.iter().all(Option::is_some), "{}", code);
+        prop_assert!(positions.windows(2).all(|w| w[0] < w[1]), "{}", code);
+    }
+
+    #[test]
+    fn prop_packed_layout_is_sum_of_sizes(config in plain_config()) {
+        let config = Config { packed: true, ..config };
+        let layout = compute_layout(&config).unwrap();
+
+        let mut expected_offset = 0;
+        for field in &layout.fields {
+            prop_assert_eq!(field.offset_bits, expected_offset);
+            expected_offset += field.size_bits;
+        }
+        prop_assert_eq!(layout.size * 8, expected_offset);
+        prop_assert_eq!(layout.align, 1);
+    }
+
+    #[test]
+    fn prop_unpacked_layout_follows_padding_rules(config in plain_config()) {
+        let config = Config { packed: false, ..config };
+        let layout = compute_layout(&config).unwrap();
+
+        let mut end = 0;
+        let mut max_align = 1;
+        for field in &layout.fields {
+            let field_align = c_type_size(&field.ty).unwrap().min(8);
+            max_align = max_align.max(field_align);
+            // 每个字段按自身对齐，且只插入必要的填充
+            prop_assert!((field.offset_bits / 8).is_multiple_of(field_align));
+            prop_assert!(field.offset_bits >= end);
+            prop_assert!(field.offset_bits - end < field_align * 8);
+            end = field.offset_bits + field.size_bits;
+        }
+        prop_assert_eq!(layout.align, max_align);
+        prop_assert!(layout.size.is_multiple_of(layout.align));
+        prop_assert!(layout.size * 8 >= end);
+        prop_assert!(layout.size * 8 - end < layout.align * 8);
+    }
+
+    #[test]
+    fn prop_bitstream_layout_is_contiguous(config in bit_field_config()) {
+        let layout = compute_layout(&config).unwrap();
+        prop_assert!(layout.bitstream);
+
+        let mut expected_offset = 0;
+        for field in &layout.fields {
+            prop_assert_eq!(field.offset_bits, expected_offset);
+            expected_offset += field.size_bits;
+        }
+        prop_assert_eq!(layout.size, expected_offset.div_ceil(8));
+    }
+}
@@ -0,0 +1,620 @@
+//! 生成 Python 的 dataclass 与 `struct` 格式字符串，地面站工具可以直接解析同一份定义的数据包
+//!
+//! 格式字符串以 `<` 开头（小端、标准大小、不自动对齐），非紧凑结构体的填充与保留字节写成 `x`
+
+use crate::config::{Config, Endianness};
+use crate::fingerprint::{format_layout_hash, layout_hash};
+use crate::generator::{GenerateError, GenerateOptions};
+use crate::layout::{c_type_size, compute_layout, with_reserved_gaps};
+use crate::target::Target;
+use crate::validator::{format_command_id, parse_array_type, parse_command_id};
+
+const BACKEND: &str = "Python";
+
+/// Python 关键字，字段名与之相同时加下划线后缀
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// 生成的类中已有的成员，字段不能与之重名
+pub(crate) const GENERATED_MEMBERS: &[&str] =
+    &["CMD", "SIZE", "FORMAT", "LAYOUT_HASH", "pack", "unpack"];
+
+/// 生成的模块导入的名称，枚举类不能与之重名
+const IMPORTED_NAMES: &[&str] = &[
+    "annotations",
+    "enum",
+    "struct",
+    "dataclass",
+    "field",
+    "ClassVar",
+    "Optional",
+];
+
+/// 一个 dataclass 字段及其在 `struct.unpack` 结果中的位置
+struct Member {
+    name: String,
+    ty: &'static str,
+    /// 字段引用的枚举类名与首个枚举项，作为类型注解与默认值
+    enumeration: Option<(String, String)>,
+    array_len: Option<u32>,
+    comment: Option<String>,
+}
+
+pub(crate) fn generate_python(
+    config: &Config,
+    options: &GenerateOptions,
+) -> Result<String, GenerateError> {
+    let config = &with_reserved_gaps(config);
+    if let Some(field) = config.fields.iter().find(|f| f.bit_field.is_some()) {
+        return Err(unsupported(format!(
+            "字段 '{}' 使用了位域，struct 模块的格式字符串无法表示",
+            field.name
+        )));
+    }
+    if let Some(field) = config
+        .fields
+        .iter()
+        .find(|f| f.group.as_ref().is_some_and(|g| g.name.is_some()))
+    {
+        return Err(unsupported(format!(
+            "字段 '{}' 位于具名嵌套结构体中，Python 后端尚不支持",
+            field.member_path()
+        )));
+    }
+    let packet_endianness = config.endianness.unwrap_or_default();
+    if let Some(field) = config.fields.iter().find(|f| {
+        config.field_endianness(f) != packet_endianness
+            && parse_array_type(&f.ty)
+                .and_then(|(base, _)| c_type_size(base))
+                .is_some_and(|size| size > 1)
+    }) {
+        return Err(unsupported(format!(
+            "字段 '{}' 的字节序与包不同，struct 模块的格式字符串只能使用一种字节序",
+            field.name
+        )));
+    }
+    let cmd_id =
+        parse_command_id(&config.command_id).map_err(|_| GenerateError::ValidationError)?;
+    let layout = compute_layout(config).ok_or(GenerateError::ValidationError)?;
+
+    // 格式字符串按布局逐个字段写出，字段之间、保留字节与末尾的空隙合并写成填充字节
+    let mut format = String::from(match packet_endianness {
+        Endianness::Little => "<",
+        Endianness::Big => ">",
+    });
+    let mut members = Vec::new();
+    let mut cursor = 0;
+    let mut padding = 0;
+    for (field, field_layout) in config.fields.iter().zip(&layout.fields) {
+        let offset = field_layout.offset_bits / 8;
+        padding += offset - cursor;
+        cursor = offset + field_layout.size_bits / 8;
+        if field.reserved.is_some() {
+            padding += field_layout.size_bits / 8;
+            continue;
+        }
+        push_padding(&mut format, std::mem::take(&mut padding));
+
+        let (base_type, array_len) =
+            parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+        let code = Target::Python
+            .map_type(base_type)
+            .map_err(|reason| unsupported(format!("字段 '{}': {}", field.name, reason)))?;
+        if let Some(len) = array_len {
+            format.push_str(&len.to_string());
+        }
+        format.push_str(code);
+        let enumeration = match config.field_enum(field) {
+            Some(def) => Some((
+                enum_identifier(&def.name)?,
+                enum_identifier(&def.values[0].name)?,
+            )),
+            None => None,
+        };
+        members.push(Member {
+            name: identifier(&field.name)?,
+            ty: python_type(code),
+            enumeration,
+            array_len,
+            comment: field.comment.as_ref().map(|c| c.as_str().to_string()),
+        });
+    }
+    push_padding(&mut format, padding + layout.size - cursor);
+
+    let i1 = options.indent(1);
+    let i2 = options.indent(2);
+    let name = &config.packet_name;
+    let mut out = String::from("# 由 rplc 生成，请勿手动修改\n\n");
+    out.push_str("from __future__ import annotations\n\n");
+    if !config.enums.is_empty() {
+        out.push_str("import enum\n");
+    }
+    out.push_str("import struct\n");
+    // 只导入用到的名称：field 用于数组字段的默认值，Optional 用于 values 生成的查询方法
+    if members.iter().any(|m| m.array_len.is_some()) {
+        out.push_str("from dataclasses import dataclass, field\n");
+    } else {
+        out.push_str("from dataclasses import dataclass\n");
+    }
+    let has_value_names = config
+        .fields
+        .iter()
+        .any(|f| f.reserved.is_none() && !f.value_names().is_empty());
+    if has_value_names {
+        out.push_str("from typing import ClassVar, Optional\n\n\n");
+    } else {
+        out.push_str("from typing import ClassVar\n\n\n");
+    }
+    for def in &config.enums {
+        if IMPORTED_NAMES.contains(&def.name.as_str()) || def.name == *name {
+            return Err(unsupported(format!(
+                "枚举 '{}' 与模块中的其他名称重名",
+                def.name
+            )));
+        }
+        out.push_str(&format!(
+            "class {}(enum.IntEnum):\n",
+            enum_identifier(&def.name)?
+        ));
+        if let Some(comment) = &def.comment {
+            out.push_str(&format!(
+                "{i1}\"\"\"{}\"\"\"\n\n",
+                docstring(comment.as_str())
+            ));
+        }
+        for item in &def.values {
+            if let Some(comment) = &item.comment {
+                push_comment(&mut out, &i1, comment.as_str());
+            }
+            out.push_str(&format!(
+                "{i1}{} = {}\n",
+                enum_identifier(&item.name)?,
+                item.value
+            ));
+        }
+        out.push_str("\n\n");
+    }
+    out.push_str("@dataclass\n");
+    out.push_str(&format!("class {}:\n", name));
+    if let Some(comment) = &config.comment {
+        out.push_str(&format!(
+            "{i1}\"\"\"{}\"\"\"\n\n",
+            docstring(comment.as_str())
+        ));
+    }
+
+    out.push_str(&format!(
+        "{i1}CMD: ClassVar[int] = {}\n",
+        format_command_id(cmd_id)
+    ));
+    out.push_str(&format!(
+        "{i1}LAYOUT_HASH: ClassVar[int] = {}\n",
+        format_layout_hash(layout_hash(config))
+    ));
+    out.push_str(&format!("{i1}FORMAT: ClassVar[str] = \"{}\"\n", format));
+    out.push_str(&format!("{i1}SIZE: ClassVar[int] = {}\n", layout.size));
+    for field in config.fields.iter().filter(|f| f.reserved.is_none()) {
+        let (base_type, _) = parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+        let is_float = matches!(base_type, "float" | "double");
+        for (key, value) in field.limits() {
+            let (ty, literal) = if is_float {
+                ("float", format!("{:?}", value.as_f64().unwrap_or_default()))
+            } else {
+                ("int", value.to_string())
+            };
+            out.push_str(&format!(
+                "{i1}{}_{}: ClassVar[{}] = {}\n",
+                field.name.to_uppercase(),
+                key.to_uppercase(),
+                ty,
+                literal
+            ));
+        }
+    }
+
+    if !members.is_empty() {
+        out.push('\n');
+    }
+    for member in &members {
+        if let Some(comment) = &member.comment {
+            push_comment(&mut out, &i1, comment);
+        }
+        let (ty, default) = match &member.enumeration {
+            Some((enum_name, first)) => (enum_name.clone(), format!("{}.{}", enum_name, first)),
+            None => (member.ty.to_string(), default_value(member.ty).to_string()),
+        };
+        let (annotation, default) = match member.array_len {
+            Some(len) => (
+                format!("list[{}]", ty),
+                format!("field(default_factory=lambda: [{}] * {})", default, len),
+            ),
+            None => (ty, default),
+        };
+        out.push_str(&format!(
+            "{i1}{}: {} = {}\n",
+            member.name, annotation, default
+        ));
+    }
+
+    // pack(): 数组字段展开为多个参数
+    let args: Vec<String> = members
+        .iter()
+        .map(|m| match m.array_len {
+            Some(_) => format!("*self.{}", m.name),
+            None => format!("self.{}", m.name),
+        })
+        .collect();
+    out.push_str(&format!("\n{i1}def pack(self) -> bytes:\n"));
+    out.push_str(&format!(
+        "{i2}\"\"\"按 C 结构体的内存布局编码为 SIZE 个字节\"\"\"\n"
+    ));
+    if args.is_empty() {
+        out.push_str(&format!("{i2}return struct.pack(self.FORMAT)\n"));
+    } else {
+        out.push_str(&format!(
+            "{i2}return struct.pack(self.FORMAT, {})\n",
+            args.join(", ")
+        ));
+    }
+
+    // unpack(): 按字段在结果元组中的位置取值，数组字段取连续的一段，枚举字段转换为枚举类
+    let mut index = 0;
+    let values: Vec<String> = members
+        .iter()
+        .map(|m| match (m.array_len, &m.enumeration) {
+            (Some(len), None) => {
+                index += len;
+                format!("list(values[{}:{}])", index - len, index)
+            }
+            (Some(len), Some((enum_name, _))) => {
+                index += len;
+                format!(
+                    "[{}(v) for v in values[{}:{}]]",
+                    enum_name,
+                    index - len,
+                    index
+                )
+            }
+            (None, None) => {
+                index += 1;
+                format!("values[{}]", index - 1)
+            }
+            (None, Some((enum_name, _))) => {
+                index += 1;
+                format!("{}(values[{}])", enum_name, index - 1)
+            }
+        })
+        .collect();
+    out.push_str(&format!("\n{i1}@classmethod\n"));
+    out.push_str(&format!(
+        "{i1}def unpack(cls, data: bytes, offset: int = 0) -> {}:\n",
+        name
+    ));
+    out.push_str(&format!(
+        "{i2}\"\"\"从 data 的 offset 处解码一个包，剩余字节不足 SIZE 时抛出 struct.error\"\"\"\n"
+    ));
+    if values.is_empty() {
+        out.push_str(&format!(
+            "{i2}struct.unpack_from(cls.FORMAT, data, offset)\n"
+        ));
+        out.push_str(&format!("{i2}return cls()\n"));
+    } else {
+        out.push_str(&format!(
+            "{i2}values = struct.unpack_from(cls.FORMAT, data, offset)\n"
+        ));
+        out.push_str(&format!("{i2}return cls({})\n", values.join(", ")));
+    }
+
+    for field in config.fields.iter().filter(|f| f.reserved.is_none()) {
+        let names = field.value_names();
+        if names.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n{i1}@staticmethod\n"));
+        out.push_str(&format!(
+            "{i1}def {}_name(value: int) -> Optional[str]:\n",
+            field.name
+        ));
+        out.push_str(&format!(
+            "{i2}\"\"\"{} 的显示名称，未列出的值返回 None\"\"\"\n",
+            field.name
+        ));
+        let entries: Vec<String> = names
+            .iter()
+            .map(|(value, name)| format!("{}: {}", value, python_string(name.as_str())))
+            .collect();
+        out.push_str(&format!(
+            "{i2}return {{{}}}.get(value)\n",
+            entries.join(", ")
+        ));
+    }
+    Ok(out)
+}
+
+/// `#:` 开头的注释行，Sphinx 等工具将其识别为属性的文档
+fn push_comment(out: &mut String, indent: &str, comment: &str) {
+    for line in comment.lines().map(str::trim_end) {
+        match line {
+            "" => out.push_str(&format!("{indent}#:\n")),
+            line => out.push_str(&format!("{indent}#: {}\n", line)),
+        }
+    }
+}
+
+fn push_padding(format: &mut String, bytes: u32) {
+    match bytes {
+        0 => {}
+        1 => format.push('x'),
+        n => format.push_str(&format!("{}x", n)),
+    }
+}
+
+/// `struct` 格式字符对应的 Python 类型
+fn python_type(code: &str) -> &'static str {
+    match code {
+        "f" | "d" => "float",
+        "?" => "bool",
+        _ => "int",
+    }
+}
+
+fn default_value(ty: &str) -> &'static str {
+    match ty {
+        "float" => "0.0",
+        "bool" => "False",
+        _ => "0",
+    }
+}
+
+/// 与 Python 关键字冲突的名称加下划线后缀，与生成的成员重名时无法生成
+fn identifier(name: &str) -> Result<String, GenerateError> {
+    if GENERATED_MEMBERS.contains(&name) {
+        return Err(unsupported(format!("字段 '{}' 与生成的类成员重名", name)));
+    }
+    if PYTHON_KEYWORDS.contains(&name) {
+        Ok(format!("{}_", name))
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+/// 枚举类与枚举项的名称，与 Python 关键字冲突时加下划线后缀
+fn enum_identifier(name: &str) -> Result<String, GenerateError> {
+    // 以下划线开头与结尾的名称被 enum 模块保留
+    if name.starts_with('_') && name.ends_with('_') {
+        return Err(unsupported(format!(
+            "枚举名称 '{}' 被 Python 的 enum 模块保留",
+            name
+        )));
+    }
+    if PYTHON_KEYWORDS.contains(&name) {
+        Ok(format!("{}_", name))
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+fn docstring(text: &str) -> String {
+    text.replace('\\', "\\\\").replace("\"\"\"", "\\\"\\\"\\\"")
+}
+
+fn python_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn unsupported(reason: String) -> GenerateError {
+    GenerateError::Unsupported {
+        backend: BACKEND,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(json: &str) -> Result<String, GenerateError> {
+        let config: Config = serde_json::from_str(json).unwrap();
+        generate_python(&config, &GenerateOptions::default())
+    }
+
+    #[test]
+    fn test_generate_python_dataclass() {
+        let code = generate(
+            r#"{"packet_name":"GimbalCommand","command_id":"260","packed":true,
+            "comment":"云台控制","namespace":"Robot::Gimbal",
+            "fields":[
+                {"name":"yaw","type":"float","comment":"偏航角"},
+                {"name":"from","type":"uint8_t"},
+                {"name":"gains","type":"int16_t[3]"},
+                {"name":"enabled","type":"bool"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            code,
+            r#"# 由 rplc 生成，请勿手动修改
+
+from __future__ import annotations
+
+import struct
+from dataclasses import dataclass, field
+from typing import ClassVar
+
+
+@dataclass
+class GimbalCommand:
+    """云台控制"""
+
+    CMD: ClassVar[int] = 0x0104
+    LAYOUT_HASH: ClassVar[int] = 0xC5BFD7DF
+    FORMAT: ClassVar[str] = "<fB3h?"
+    SIZE: ClassVar[int] = 12
+
+    #: 偏航角
+    yaw: float = 0.0
+    from_: int = 0
+    gains: list[int] = field(default_factory=lambda: [0] * 3)
+    enabled: bool = False
+
+    def pack(self) -> bytes:
+        """按 C 结构体的内存布局编码为 SIZE 个字节"""
+        return struct.pack(self.FORMAT, self.yaw, self.from_, *self.gains, self.enabled)
+
+    @classmethod
+    def unpack(cls, data: bytes, offset: int = 0) -> GimbalCommand:
+        """从 data 的 offset 处解码一个包，剩余字节不足 SIZE 时抛出 struct.error"""
+        values = struct.unpack_from(cls.FORMAT, data, offset)
+        return cls(values[0], values[1], list(values[2:5]), values[5])
+"#
+        );
+    }
+
+    #[test]
+    fn test_generate_python_padding() {
+        // 非紧凑结构体的对齐填充、固定偏移留下的空隙与保留条目都写成填充字节
+        let code = generate(
+            r#"{"packet_name":"A","command_id":"1","packed":false,"fields":[
+            {"name":"a","type":"uint8_t"},{"name":"b","type":"uint32_t"},
+            {"name":"c","type":"uint16_t"}]}"#,
+        )
+        .unwrap();
+        assert!(code.contains("FORMAT: ClassVar[str] = \"<B3xIH2x\"\n"));
+        assert!(code.contains("from dataclasses import dataclass\n"));
+        assert!(code.contains("SIZE: ClassVar[int] = 12\n"));
+
+        let code = generate(
+            r#"{"packet_name":"A","command_id":"1","fields":[
+            {"name":"a","type":"uint8_t"},{"reserved":2},
+            {"name":"b","type":"uint16_t","offset":6}]}"#,
+        )
+        .unwrap();
+        assert!(code.contains("FORMAT: ClassVar[str] = \"<B5xH\"\n"));
+        assert!(code.contains("return cls(values[0], values[1])\n"));
+        assert!(!code.contains("_reserved"));
+
+        let code = generate(
+            r#"{"packet_name":"A","command_id":"1","endianness":"big","fields":[
+            {"name":"a","type":"uint16_t"}]}"#,
+        )
+        .unwrap();
+        assert!(code.contains("FORMAT: ClassVar[str] = \">H\"\n"));
+
+        // 单字节字段的字节序不影响格式字符串
+        let code = generate(
+            r#"{"packet_name":"A","command_id":"1","endianness":"big","fields":[
+            {"name":"a","type":"uint16_t"},{"name":"b","type":"uint8_t","endianness":"little"}]}"#,
+        )
+        .unwrap();
+        assert!(code.contains("FORMAT: ClassVar[str] = \">HB\"\n"));
+    }
+
+    #[test]
+    fn test_generate_python_enums() {
+        let code = generate(
+            r#"{"packet_name":"ChassisState","command_id":"1",
+            "enums":[{"name":"DriveMode","type":"int8_t","comment":"底盘模式","values":[
+                {"name":"idle","value":0,"comment":"空闲"},{"name":"None","value":-1}]}],
+            "fields":[{"name":"mode","type":"DriveMode"},{"name":"history","type":"DriveMode[2]"}]}"#,
+        )
+        .unwrap();
+        assert!(code.contains("import enum\nimport struct\n"));
+        assert!(code.contains(
+            r#"class DriveMode(enum.IntEnum):
+    """底盘模式"""
+
+    #: 空闲
+    idle = 0
+    None_ = -1
+
+
+@dataclass
+"#
+        ));
+        assert!(code.contains(
+            "    mode: DriveMode = DriveMode.idle
+    history: list[DriveMode] = field(default_factory=lambda: [DriveMode.idle] * 2)
+"
+        ));
+        assert!(
+            code.contains(
+                "return cls(DriveMode(values[0]), [DriveMode(v) for v in values[1:3]])\n"
+            )
+        );
+
+        // 枚举类不能遮蔽模块导入的名称
+        let shadowing = r#"{"packet_name":"A","command_id":"1",
+            "enums":[{"name":"field","type":"uint8_t","values":[{"name":"a","value":0}]}],
+            "fields":[{"name":"x","type":"field"}]}"#;
+        assert!(matches!(
+            generate(shadowing),
+            Err(GenerateError::Unsupported {
+                backend: "Python",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_generate_python_limits_and_values() {
+        let code = generate(
+            r#"{"packet_name":"A","command_id":"1","fields":[
+            {"name":"yaw","type":"float","max":3},
+            {"name":"mode","type":"uint8_t","min":0,"values":{"1":"运行\"中\"","0":"空闲"}}]}"#,
+        )
+        .unwrap();
+        assert!(code.contains("from typing import ClassVar, Optional\n"));
+        assert!(
+            code.contains("    YAW_MAX: ClassVar[float] = 3.0\n    MODE_MIN: ClassVar[int] = 0\n")
+        );
+        assert!(code.contains(
+            r#"    @staticmethod
+    def mode_name(value: int) -> Optional[str]:
+        """mode 的显示名称，未列出的值返回 None"""
+        return {0: "空闲", 1: "运行\"中\""}.get(value)
+"#
+        ));
+    }
+
+    #[test]
+    fn test_generate_python_unsupported() {
+        let bit_field = r#"{"packet_name":"A","command_id":"1",
+            "fields":[{"name":"flags","type":"uint8_t","bit_field":3}]}"#;
+        let member = r#"{"packet_name":"A","command_id":"1",
+            "fields":[{"name":"pack","type":"uint8_t"}]}"#;
+        let long_double = r#"{"packet_name":"A","command_id":"1",
+            "fields":[{"name":"x","type":"long double"}]}"#;
+        let mixed_endianness = r#"{"packet_name":"A","command_id":"1",
+            "fields":[{"name":"x","type":"uint16_t","endianness":"big"}]}"#;
+        for json in [bit_field, member, long_double, mixed_endianness] {
+            assert!(
+                matches!(
+                    generate(json),
+                    Err(GenerateError::Unsupported {
+                        backend: "Python",
+                        ..
+                    })
+                ),
+                "{}",
+                json
+            );
+        }
+    }
+}
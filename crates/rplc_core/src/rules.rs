@@ -0,0 +1,231 @@
+//! 校验规则清单，由诊断代码生成，供团队规范文档与 `--emit rules.md` 使用
+
+use miette::Diagnostic;
+use serde::Serialize;
+
+use crate::diagnostics::{Severity, ValidationCode};
+use crate::explain::explain;
+
+/// 一条校验规则
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Rule {
+    pub code: String,
+    pub severity: Severity,
+    /// 诊断附带的修复建议
+    pub help: Option<String>,
+    /// `rplc explain` 的详细说明
+    pub explanation: Option<&'static str>,
+}
+
+/// 每种诊断的一个实例，参数只用于渲染修复建议中的占位
+pub(crate) fn registry() -> Vec<ValidationCode> {
+    let s = || "…".to_string();
+    vec![
+        ValidationCode::InvalidPacketName(s()),
+        ValidationCode::InvalidFieldName(s()),
+        ValidationCode::KeywordCollision(s()),
+        ValidationCode::GeneratedNameCollision(s(), s(), s()),
+        ValidationCode::DuplicateFieldName(s()),
+        ValidationCode::DuplicatePacketName(s()),
+        ValidationCode::DuplicateCommandId(s(), s()),
+        ValidationCode::InvalidCommandId(s()),
+        ValidationCode::InvalidFieldType(s()),
+        ValidationCode::InvalidBitField(s()),
+        ValidationCode::BitFieldOnInvalidType(s(), s()),
+        ValidationCode::BitFieldLengthOverflow(s(), 0, 0),
+        ValidationCode::BitFieldStraddleBoundaryWithoutPacked(s(), s(), 0, 0, 0),
+        ValidationCode::InvalidArrayType(s()),
+        ValidationCode::BitFieldOnArray(s()),
+        ValidationCode::InvalidArrayLen(s()),
+        ValidationCode::ArrayLenConflict(s(), s()),
+        ValidationCode::InvalidEnum(s(), s()),
+        ValidationCode::DuplicateEnum(s()),
+        ValidationCode::InvalidEnumerator(s(), s(), s()),
+        ValidationCode::DuplicateEnumerator(s(), s()),
+        ValidationCode::EnumValueOutOfRange(s(), s(), s(), s()),
+        ValidationCode::DuplicateEnumValue(s(), s(), s()),
+        ValidationCode::PacketReferenceCycle(s()),
+        ValidationCode::PacketReferenceInBitstream(s()),
+        ValidationCode::InvalidFieldLimit(s(), s(), s()),
+        ValidationCode::InvalidFieldValues(s(), s()),
+        ValidationCode::InvalidFieldOffset(s(), s()),
+        ValidationCode::InvalidReserved(s()),
+        ValidationCode::InvalidProtocolVersion(s()),
+        ValidationCode::ArrayExceedsPayloadBudget(s(), 0, 0),
+        ValidationCode::InvalidJson(s()),
+        ValidationCode::InvalidYaml(s()),
+        ValidationCode::InvalidToml(s()),
+        ValidationCode::DuplicateKey(s(), 0),
+        ValidationCode::UnsupportedSchemaVersion(s(), 0),
+        ValidationCode::RemovedInSchema(s(), 0),
+        ValidationCode::Utf16Input(s()),
+        ValidationCode::InvalidUtf8(0),
+        ValidationCode::InputTooLarge(0, 0),
+        ValidationCode::MultiPacketInput(0),
+        ValidationCode::InvalidFields,
+        ValidationCode::InvalidAlign(s()),
+        ValidationCode::InvalidBitFieldLayout(s()),
+        ValidationCode::InvalidEndianness(s()),
+        ValidationCode::FieldEndiannessUnsupported(s(), s()),
+        ValidationCode::RedundantEndianness(s(), s()),
+        ValidationCode::SerializeUnsupportedField(s(), s()),
+        ValidationCode::CppStandardTooOld(s(), s(), s()),
+        ValidationCode::InvalidCrc(s()),
+        ValidationCode::CrcPayloadTooLarge(s(), 0, 0),
+        ValidationCode::ChecksumFieldNotLast(s()),
+        ValidationCode::ChecksumFieldWidth(s(), s()),
+        ValidationCode::ChecksumFieldDuplicated(s()),
+        ValidationCode::ChecksumFieldMissing(s()),
+        ValidationCode::AlignBelowNatural(0, 0),
+        ValidationCode::InvalidExpectedSize(s()),
+        ValidationCode::ExpectedSizeMismatch(s(), 0, 0),
+        ValidationCode::InvalidInclude(s(), s()),
+        ValidationCode::InvalidFieldId(s()),
+        ValidationCode::DuplicateFieldId(s(), 0, s()),
+        ValidationCode::RetiredFieldIdReused(s(), 0),
+        ValidationCode::InvalidTraitsTemplate(s()),
+        ValidationCode::InvalidSection(s()),
+        ValidationCode::InvalidGroup(s(), s()),
+        ValidationCode::GroupBitFieldBoundary(s(), 0),
+        ValidationCode::UnsupportedTypeForTarget(s(), s(), s(), s()),
+        ValidationCode::TypeNotAllowed(s(), s()),
+        ValidationCode::InvalidLocalizedComment(s()),
+        ValidationCode::NamingConventionPacket(s()),
+        ValidationCode::NamingConventionField(s()),
+        ValidationCode::MissingComment(s()),
+        ValidationCode::BitFieldMissingPackedAttr(s()),
+        ValidationCode::BitFieldStraddleBoundary(s()),
+        ValidationCode::EmptyComment(s()),
+        ValidationCode::TooManyBitFieldsPerUnit(s(), 0, 0),
+        ValidationCode::TinyBitFieldsWithFloat(0, 0),
+        ValidationCode::UnalignedFloatInPacked(s(), 0, 0),
+        ValidationCode::BitFieldAsString(s(), 4),
+        ValidationCode::MixedBitFieldTypes(s(), s(), s()),
+        ValidationCode::PlainBitFieldUnsigned(s(), s(), s()),
+        ValidationCode::BitFieldOrderMismatch(s(), s(), s(), s()),
+        ValidationCode::NativeBitFieldsAcrossCompilers(s(), s()),
+        ValidationCode::UnusedTraitsTemplate(s()),
+        ValidationCode::EmptySection(s()),
+        ValidationCode::MissingFieldId(s()),
+        ValidationCode::UnknownKey(s()),
+    ]
+}
+
+/// 所有校验规则，错误在前，同一级别内按代码排序
+pub fn rules() -> Vec<Rule> {
+    let mut rules: Vec<Rule> = registry()
+        .iter()
+        .filter_map(|code| {
+            let name = code.code()?.to_string();
+            Some(Rule {
+                severity: code.default_severity(),
+                help: code.help().map(|help| help.to_string()),
+                explanation: explain(&name),
+                code: name,
+            })
+        })
+        .collect();
+    rules.sort_by(|a, b| {
+        (a.severity != Severity::Error, &a.code).cmp(&(b.severity != Severity::Error, &b.code))
+    });
+    rules
+}
+
+/// 规则清单的 Markdown 文档：总表之后逐条列出修复建议与详细说明
+pub fn rules_markdown() -> String {
+    let rules = rules();
+    let mut out = String::from("# rplc 校验规则\n\n");
+    out.push_str("> 由 `rplc_cli --emit rules.md` 根据诊断代码生成，请勿手动修改\n\n");
+    out.push_str("| 代码 | 默认级别 | 修复建议 |\n| --- | --- | --- |\n");
+    for rule in &rules {
+        out.push_str(&format!(
+            "| [`{}`](#{}) | {} | {} |\n",
+            rule.code,
+            anchor(&rule.code),
+            severity_name(rule.severity),
+            rule.help.as_deref().unwrap_or("").replace('|', "\\|")
+        ));
+    }
+    for rule in &rules {
+        out.push_str(&format!("\n## {}\n\n", rule.code));
+        out.push_str(&format!("- 默认级别：{}\n", severity_name(rule.severity)));
+        if let Some(help) = &rule.help {
+            out.push_str(&format!("- 修复建议：{}\n", help));
+        }
+        let Some(explanation) = rule.explanation else {
+            continue;
+        };
+        out.push('\n');
+        for line in explanation.lines() {
+            match ["错误示例", "正确示例"]
+                .iter()
+                .find_map(|label| Some((label, line.strip_prefix(label)?.strip_prefix(':')?)))
+            {
+                Some((label, example)) => {
+                    out.push_str(&format!("- {}：`{}`\n", label, example.trim()))
+                }
+                None => {
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    out
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "错误",
+        Severity::Warning => "警告",
+    }
+}
+
+/// 标题 `## rplc::bit_field::length_overflow` 在 GitHub 上生成的锚点
+fn anchor(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_cover_every_code() {
+        let rules = rules();
+        assert_eq!(rules.len(), registry().len());
+        assert!(rules.iter().all(|rule| rule.explanation.is_some()));
+        let first_warning = rules
+            .iter()
+            .position(|rule| rule.severity == Severity::Warning)
+            .unwrap();
+        assert!(
+            rules[first_warning..]
+                .iter()
+                .all(|rule| rule.severity == Severity::Warning)
+        );
+
+        let rule = rules
+            .iter()
+            .find(|rule| rule.code == "rplc::bit_field::string_value")
+            .unwrap();
+        assert_eq!(
+            rule.help.as_deref(),
+            Some("建议改为数字形式: \"bit_field\": 4")
+        );
+    }
+
+    #[test]
+    fn test_rules_markdown() {
+        let doc = rules_markdown();
+        assert!(doc.starts_with("# rplc 校验规则\n"));
+        assert!(doc.contains(
+            "| [`rplc::bit_field::length_overflow`](#rplcbit_fieldlength_overflow) | 错误 |"
+        ));
+        assert!(doc.contains("\n## rplc::unknown_key\n\n- 默认级别：警告\n"));
+        assert!(doc.contains("- 错误示例：`\"packet_name\": \"gimbal-cmd\"`\n"));
+        assert!(!doc.contains("\n错误示例"));
+    }
+}
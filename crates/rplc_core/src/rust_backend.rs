@@ -0,0 +1,485 @@
+//! 生成与 C++ 结构体内存布局一致的 Rust `#[repr(C)]` 结构体，并实现 `rplc_runtime::Packet`
+
+use crate::config::{Config, Endianness};
+use crate::fingerprint::{format_layout_hash, layout_hash};
+use crate::generator::{GenerateError, GenerateOptions, push_section_banner};
+use crate::layout::{c_type_size, compute_layout, with_reserved_gaps};
+use crate::target::Target;
+use crate::validator::{format_command_id, parse_array_type, parse_command_id};
+
+const BACKEND: &str = "Rust";
+
+/// 可以写成 `r#name` 的 Rust 关键字
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+/// 不能作为原始标识符使用的关键字
+const RESERVED_PATH_KEYWORDS: &[&str] = &["self", "Self", "super", "crate", "_"];
+
+pub(crate) fn generate_rust(
+    config: &Config,
+    options: &GenerateOptions,
+) -> Result<String, GenerateError> {
+    let config = &with_reserved_gaps(config);
+    if let Some(field) = config.fields.iter().find(|f| f.bit_field.is_some()) {
+        return Err(unsupported(format!(
+            "字段 '{}' 使用了位域，Rust 没有对应的语法",
+            field.name
+        )));
+    }
+    // 匿名嵌套结构体的成员在 C++ 中可以直接访问，展开后布局与访问方式都不变
+    if let Some(field) = config
+        .fields
+        .iter()
+        .find(|f| f.group.as_ref().is_some_and(|g| g.name.is_some()))
+    {
+        return Err(unsupported(format!(
+            "字段 '{}' 位于具名嵌套结构体中，Rust 后端尚不支持",
+            field.member_path()
+        )));
+    }
+    let repr = match (config.packed, config.align) {
+        (true, Some(_)) => {
+            return Err(unsupported(
+                "Rust 不允许同时使用 packed 与 align".to_string(),
+            ));
+        }
+        (true, None) => "C, packed".to_string(),
+        (false, Some(align)) => format!("C, align({})", align),
+        (false, None) => "C".to_string(),
+    };
+    let cmd_id =
+        parse_command_id(&config.command_id).map_err(|_| GenerateError::ValidationError)?;
+
+    let modules: Vec<String> = config
+        .namespace
+        .iter()
+        .flat_map(|ns| ns.split("::"))
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| identifier(&segment.to_lowercase()))
+        .collect::<Result<_, _>>()?;
+    let depth = modules.len();
+
+    let mut out = String::from("// 由 rplc 生成，请勿手动修改\n\n");
+    for (level, module) in modules.iter().enumerate() {
+        out.push_str(&format!("{}pub mod {} {{\n", options.indent(level), module));
+    }
+
+    let outer = options.indent(depth);
+    let inner = options.indent(depth + 1);
+    let name = identifier(&config.packet_name)?;
+    push_enums(&mut out, config, &outer, &inner)?;
+    if let Some(comment) = &config.comment {
+        push_doc(&mut out, &outer, comment.as_str());
+    }
+    out.push_str(&format!("{}#[repr({})]\n", outer, repr));
+    out.push_str(&format!(
+        "{}#[derive(Debug, Clone, Copy, PartialEq)]\n",
+        outer
+    ));
+    out.push_str(&format!("{}pub struct {} {{\n", outer, name));
+    for field in &config.fields {
+        let (base_type, array_len) =
+            parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+        let ty = match config.field_enum(field) {
+            Some(def) => identifier(&def.name)?,
+            None => Target::Rust
+                .map_type(base_type)
+                .map_err(|reason| unsupported(format!("字段 '{}': {}", field.name, reason)))?
+                .to_string(),
+        };
+        let ty = match array_len {
+            Some(len) => format!("[{}; {}]", ty, len),
+            None => ty,
+        };
+        push_section_banner(&mut out, field, &inner);
+        if let Some(comment) = &field.comment {
+            push_doc(&mut out, &inner, comment.as_str());
+        }
+        out.push_str(&format!(
+            "{}pub {}: {},\n",
+            inner,
+            identifier(&field.name)?,
+            ty
+        ));
+    }
+    out.push_str(&format!("{}}}\n\n", outer));
+
+    out.push_str(&format!("{}impl {} {{\n", outer, name));
+    out.push_str(&format!(
+        "{}pub const CMD: u16 = {};\n",
+        inner,
+        format_command_id(cmd_id)
+    ));
+    out.push_str(&format!(
+        "{}pub const SIZE: usize = core::mem::size_of::<Self>();\n",
+        inner
+    ));
+    out.push_str(&format!(
+        "{}pub const LAYOUT_HASH: u32 = {};\n",
+        inner,
+        format_layout_hash(layout_hash(config))
+    ));
+    for field in &config.fields {
+        let (base_type, _) = parse_array_type(&field.ty).ok_or(GenerateError::ValidationError)?;
+        let ty = Target::Rust
+            .map_type(base_type)
+            .map_err(|reason| unsupported(format!("字段 '{}': {}", field.name, reason)))?;
+        for (key, value) in field.limits() {
+            let literal = if ty.starts_with('f') {
+                format!("{:?}", value.as_f64().unwrap_or_default())
+            } else {
+                value.to_string()
+            };
+            out.push_str(&format!(
+                "{}pub const {}_{}: {} = {};\n",
+                inner,
+                field.name.to_uppercase(),
+                key.to_uppercase(),
+                ty,
+                literal
+            ));
+        }
+        let names = field.value_names();
+        if names.is_empty() {
+            continue;
+        }
+        push_doc(
+            &mut out,
+            &inner,
+            &format!("{} 的显示名称，未列出的值返回 None", field.name),
+        );
+        out.push_str(&format!(
+            "{}pub fn {}_name(value: {}) -> Option<&'static str> {{\n",
+            inner, field.name, ty
+        ));
+        let (i2, i3) = (options.indent(depth + 2), options.indent(depth + 3));
+        out.push_str(&format!("{i2}match value {{\n"));
+        for (value, name) in names {
+            out.push_str(&format!("{i3}{} => Some({:?}),\n", value, name.as_str()));
+        }
+        out.push_str(&format!("{i3}_ => None,\n{i2}}}\n{inner}}}\n"));
+    }
+    out.push_str(&format!("{}}}\n", outer));
+
+    match runtime_blocker(config) {
+        None => {
+            out.push_str(&format!(
+                "\n{outer}// SAFETY: repr(C) 结构体，没有填充字节，成员只有整数、浮点数与它们的数组\n"
+            ));
+            out.push_str(&format!(
+                "{outer}unsafe impl rplc_runtime::Packet for {name} {{\n"
+            ));
+            out.push_str(&format!("{inner}const CMD: u16 = Self::CMD;\n"));
+            out.push_str(&format!(
+                "{inner}const LAYOUT_HASH: u32 = Self::LAYOUT_HASH;\n{outer}}}\n"
+            ));
+        }
+        Some(reason) => out.push_str(&format!(
+            "\n{outer}// 未实现 rplc_runtime::Packet：{reason}\n"
+        )),
+    }
+
+    for level in (0..depth).rev() {
+        out.push_str(&format!("{}}}\n", options.indent(level)));
+    }
+    Ok(out)
+}
+
+/// 具名枚举生成底层整数的 `#[repr(transparent)]` 新类型与关联常量：
+/// Rust 的 enum 遇到未列出的取值是未定义行为，新类型保证任意字节都是合法的包
+fn push_enums(
+    out: &mut String,
+    config: &Config,
+    outer: &str,
+    inner: &str,
+) -> Result<(), GenerateError> {
+    for def in &config.enums {
+        let (base_type, _) = parse_array_type(&def.ty).ok_or(GenerateError::ValidationError)?;
+        let ty = Target::Rust
+            .map_type(base_type)
+            .map_err(|reason| unsupported(format!("枚举 '{}': {}", def.name, reason)))?;
+        let name = identifier(&def.name)?;
+        if let Some(comment) = &def.comment {
+            push_doc(out, outer, comment.as_str());
+        }
+        out.push_str(&format!("{}#[repr(transparent)]\n", outer));
+        out.push_str(&format!(
+            "{}#[derive(Debug, Clone, Copy, PartialEq, Eq)]\n",
+            outer
+        ));
+        out.push_str(&format!("{}pub struct {}(pub {});\n\n", outer, name, ty));
+        out.push_str(&format!("{}impl {} {{\n", outer, name));
+        let mut constants: Vec<String> = Vec::new();
+        for item in &def.values {
+            let constant = item.name.to_uppercase();
+            if constants.contains(&constant) {
+                return Err(unsupported(format!(
+                    "枚举 '{}' 的枚举项 '{}' 转换为常量名后重复",
+                    def.name, item.name
+                )));
+            }
+            if let Some(comment) = &item.comment {
+                push_doc(out, inner, comment.as_str());
+            }
+            out.push_str(&format!(
+                "{}pub const {}: Self = Self({});\n",
+                inner, constant, item.value
+            ));
+            constants.push(constant);
+        }
+        out.push_str(&format!("{}}}\n\n", outer));
+    }
+    Ok(())
+}
+
+/// 结构体不能按内存直接读写时的原因，见 `rplc_runtime::Packet` 的安全约定
+fn runtime_blocker(config: &Config) -> Option<String> {
+    let layout = compute_layout(config)?;
+    let used_bits: u32 = layout.fields.iter().map(|field| field.size_bits).sum();
+    if used_bits != layout.size * 8 {
+        return Some("结构体含有对齐产生的填充字节".to_string());
+    }
+    for field in &config.fields {
+        let (base_type, _) = parse_array_type(&field.ty)?;
+        if matches!(base_type, "bool" | "_Bool") {
+            return Some(format!("字段 '{}' 是 bool，不是任意字节都合法", field.name));
+        }
+        if c_type_size(base_type).is_some_and(|size| size > 1)
+            && config.field_endianness(field) == Endianness::Big
+        {
+            return Some(format!("字段 '{}' 在线上为大端字节序", field.name));
+        }
+    }
+    None
+}
+
+fn push_doc(out: &mut String, indent: &str, comment: &str) {
+    for line in comment.lines().map(str::trim_end) {
+        if line.is_empty() {
+            out.push_str(&format!("{}///\n", indent));
+        } else {
+            out.push_str(&format!("{}/// {}\n", indent, line));
+        }
+    }
+}
+
+/// 与 Rust 关键字冲突的名称改写为原始标识符
+fn identifier(name: &str) -> Result<String, GenerateError> {
+    if RESERVED_PATH_KEYWORDS.contains(&name) {
+        return Err(unsupported(format!("'{}' 不能作为 Rust 标识符", name)));
+    }
+    if RUST_KEYWORDS.contains(&name) {
+        Ok(format!("r#{}", name))
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+fn unsupported(reason: String) -> GenerateError {
+    GenerateError::Unsupported {
+        backend: BACKEND,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(json: &str) -> Result<String, GenerateError> {
+        let config: Config = serde_json::from_str(json).unwrap();
+        generate_rust(&config, &GenerateOptions::default())
+    }
+
+    #[test]
+    fn test_generate_rust_struct() {
+        let code = generate(
+            r#"{"packet_name":"GimbalCommand","command_id":"260","packed":true,
+            "comment":"云台控制","namespace":"Robot::Gimbal",
+            "fields":[
+                {"name":"yaw","type":"float","comment":"偏航角"},
+                {"name":"type","type":"uint8_t"},
+                {"name":"gains","type":"int16_t[3]"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            code,
+            "// 由 rplc 生成，请勿手动修改
+
+pub mod robot {
+    pub mod gimbal {
+        /// 云台控制
+        #[repr(C, packed)]
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct GimbalCommand {
+            /// 偏航角
+            pub yaw: f32,
+            pub r#type: u8,
+            pub gains: [i16; 3],
+        }
+
+        impl GimbalCommand {
+            pub const CMD: u16 = 0x0104;
+            pub const SIZE: usize = core::mem::size_of::<Self>();
+            pub const LAYOUT_HASH: u32 = 0x72445D8B;
+        }
+
+        // SAFETY: repr(C) 结构体，没有填充字节，成员只有整数、浮点数与它们的数组
+        unsafe impl rplc_runtime::Packet for GimbalCommand {
+            const CMD: u16 = Self::CMD;
+            const LAYOUT_HASH: u32 = Self::LAYOUT_HASH;
+        }
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_generate_rust_align() {
+        let code = generate(
+            r#"{"packet_name":"A","command_id":"1","packed":false,"align":8,
+            "fields":[{"name":"x","type":"uint32_t"}]}"#,
+        )
+        .unwrap();
+        assert!(code.starts_with("// 由 rplc 生成，请勿手动修改\n\n#[repr(C, align(8))]\n"));
+    }
+
+    #[test]
+    fn test_generate_rust_limit_constants() {
+        let code = generate(
+            r#"{"packet_name":"A","command_id":"1","fields":[
+            {"name":"yaw","type":"float","max":3},
+            {"name":"speed","type":"uint16_t","min":10,"typical":120}]}"#,
+        )
+        .unwrap();
+        assert!(code.contains("    pub const YAW_MAX: f32 = 3.0;\n"));
+        assert!(code.contains(
+            "    pub const SPEED_MIN: u16 = 10;\n    pub const SPEED_TYPICAL: u16 = 120;\n}\n"
+        ));
+    }
+
+    #[test]
+    fn test_generate_rust_value_lookups() {
+        let code = generate(
+            r#"{"packet_name":"A","command_id":"1","fields":[
+            {"name":"mode","type":"uint8_t","values":{"1":"运行","0":"空闲"}}]}"#,
+        )
+        .unwrap();
+        assert!(code.contains(
+            r#"    /// mode 的显示名称，未列出的值返回 None
+    pub fn mode_name(value: u8) -> Option<&'static str> {
+        match value {
+            0 => Some("空闲"),
+            1 => Some("运行"),
+            _ => None,
+        }
+    }
+}
+"#
+        ));
+    }
+
+    #[test]
+    fn test_generate_rust_enums() {
+        let code = generate(
+            r#"{"packet_name":"ChassisState","command_id":"1","packed":true,
+            "enums":[{"name":"DriveMode","type":"int8_t","comment":"底盘模式","values":[
+                {"name":"idle","value":0,"comment":"空闲"},{"name":"reverse","value":-1}]}],
+            "fields":[{"name":"mode","type":"DriveMode"},{"name":"history","type":"DriveMode[2]"}]}"#,
+        )
+        .unwrap();
+        assert!(code.starts_with(
+            "// 由 rplc 生成，请勿手动修改
+
+/// 底盘模式
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DriveMode(pub i8);
+
+impl DriveMode {
+    /// 空闲
+    pub const IDLE: Self = Self(0);
+    pub const REVERSE: Self = Self(-1);
+}
+
+#[repr(C, packed)]
+"
+        ));
+        assert!(code.contains("    pub mode: DriveMode,\n    pub history: [DriveMode; 2],\n"));
+        // 新类型的任意取值都合法，仍然可以按内存读写
+        assert!(code.contains("unsafe impl rplc_runtime::Packet for ChassisState"));
+
+        let colliding = r#"{"packet_name":"A","command_id":"1",
+            "enums":[{"name":"Mode","type":"uint8_t","values":[
+                {"name":"idle","value":0},{"name":"IDLE","value":1}]}],
+            "fields":[{"name":"mode","type":"Mode"}]}"#;
+        assert!(matches!(
+            generate(colliding),
+            Err(GenerateError::Unsupported {
+                backend: "Rust",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_generate_rust_runtime_blockers() {
+        for (json, reason) in [
+            (
+                r#"{"packet_name":"A","command_id":"1","packed":false,
+                "fields":[{"name":"a","type":"uint8_t"},{"name":"b","type":"uint32_t"}]}"#,
+                "结构体含有对齐产生的填充字节",
+            ),
+            (
+                r#"{"packet_name":"A","command_id":"1",
+                "fields":[{"name":"ok","type":"bool"}]}"#,
+                "字段 'ok' 是 bool，不是任意字节都合法",
+            ),
+            (
+                r#"{"packet_name":"A","command_id":"1","endianness":"big",
+                "fields":[{"name":"a","type":"uint8_t"},{"name":"b","type":"uint16_t"}]}"#,
+                "字段 'b' 在线上为大端字节序",
+            ),
+        ] {
+            let code = generate(json).unwrap();
+            assert!(!code.contains("unsafe impl"), "{}", json);
+            assert!(
+                code.ends_with(&format!("\n// 未实现 rplc_runtime::Packet：{}\n", reason)),
+                "{}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_rust_unsupported() {
+        let bit_field = r#"{"packet_name":"A","command_id":"1","packed":true,
+            "fields":[{"name":"flags","type":"uint8_t","bit_field":3}]}"#;
+        let packed_align = r#"{"packet_name":"A","command_id":"1","packed":true,"align":4,
+            "fields":[{"name":"x","type":"uint8_t"}]}"#;
+        let reserved = r#"{"packet_name":"A","command_id":"1",
+            "fields":[{"name":"self","type":"uint8_t"}]}"#;
+        for json in [bit_field, packed_align, reserved] {
+            assert!(
+                matches!(
+                    generate(json),
+                    Err(GenerateError::Unsupported {
+                        backend: "Rust",
+                        ..
+                    })
+                ),
+                "{}",
+                json
+            );
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::config::CURRENT_SCHEMA;
+use crate::validator::{canonical_command_id, is_cpp_keyword, is_identifier};
+
+/// 新建包定义时初始的协议版本
+pub(crate) const INITIAL_PROTOCOL_VERSION: &str = "0.1.0";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ScaffoldError {
+    #[error("Packet名称 '{0}' 无效，必须符合 C++ 标识符规范")]
+    InvalidPacketName(String),
+    #[error("Command ID '{0}' 格式错误，必须是 0-65535 的整数或十六进制")]
+    InvalidCommandId(String),
+}
+
+// 字段顺序即输出的键顺序，与文档中的示例保持一致
+#[derive(Serialize)]
+struct PacketTemplate {
+    /// 包集合中的包继承集合的版本，不单独写出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rplc_schema: Option<u32>,
+    packet_name: String,
+    command_id: String,
+    comment: &'static str,
+    fields: [FieldTemplate; 1],
+}
+
+#[derive(Serialize)]
+struct FieldTemplate {
+    name: &'static str,
+    #[serde(rename = "type")]
+    ty: &'static str,
+    comment: &'static str,
+}
+
+#[derive(Serialize)]
+struct PacketSetTemplate {
+    rplc_schema: u32,
+    protocol_version: &'static str,
+    packets: [PacketTemplate; 1],
+}
+
+/// 生成单个包定义的骨架，命令 ID 输出为规范写法
+pub fn new_packet_template(name: &str, command_id: &str) -> Result<String, ScaffoldError> {
+    Ok(to_pretty_json(&PacketTemplate {
+        rplc_schema: Some(CURRENT_SCHEMA),
+        ..packet(name, command_id)?
+    }))
+}
+
+/// 生成带 `protocol_version` 的包集合骨架，其中包含一个包
+pub fn new_packet_set_template(name: &str, command_id: &str) -> Result<String, ScaffoldError> {
+    Ok(to_pretty_json(&PacketSetTemplate {
+        rplc_schema: CURRENT_SCHEMA,
+        protocol_version: INITIAL_PROTOCOL_VERSION,
+        packets: [packet(name, command_id)?],
+    }))
+}
+
+fn packet(name: &str, command_id: &str) -> Result<PacketTemplate, ScaffoldError> {
+    let (packet_name, command_id) = packet_identity(name, command_id)?;
+    Ok(PacketTemplate {
+        rplc_schema: None,
+        packet_name,
+        command_id,
+        comment: "TODO: 包的用途",
+        fields: [FieldTemplate {
+            name: "value",
+            ty: "uint8_t",
+            comment: "TODO: 字段说明",
+        }],
+    })
+}
+
+/// 检查新建包的包名与命令 ID，返回去掉首尾空白的包名与规范写法的命令 ID
+pub(crate) fn packet_identity(
+    name: &str,
+    command_id: &str,
+) -> Result<(String, String), ScaffoldError> {
+    let name = name.trim();
+    if !is_identifier(name) || is_cpp_keyword(name) {
+        return Err(ScaffoldError::InvalidPacketName(name.to_string()));
+    }
+    let command_id = canonical_command_id(command_id)
+        .ok_or_else(|| ScaffoldError::InvalidCommandId(command_id.to_string()))?;
+    Ok((name.to_string(), command_id))
+}
+
+pub(crate) fn to_pretty_json<T: Serialize>(value: &T) -> String {
+    // 模板只包含字符串、数字与数组，序列化不会失败
+    let mut json = serde_json::to_string_pretty(value).unwrap_or_default();
+    json.push('\n');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::{validate, validate_multiple};
+    use crate::{generate, generate_multiple};
+
+    #[test]
+    fn test_packet_template_is_valid() {
+        let json = new_packet_template("GimbalCommand", "0x01_02").unwrap();
+        assert!(validate(&json).is_empty(), "{}", json);
+        assert!(generate(&json).is_ok());
+        assert!(json.contains(r#""command_id": "0x0102""#));
+        assert!(json.starts_with("{\n  \"rplc_schema\": 2,\n"));
+
+        // 键顺序与文档示例一致
+        let name_pos = json.find("packet_name").unwrap();
+        let fields_pos = json.find("fields").unwrap();
+        assert!(name_pos < fields_pos);
+    }
+
+    #[test]
+    fn test_packet_set_template_is_valid() {
+        let json = new_packet_set_template("GimbalCommand", "258").unwrap();
+        assert!(validate_multiple(&json).is_empty(), "{}", json);
+        assert!(generate_multiple(&json).is_ok());
+        assert!(json.contains(r#""protocol_version": "0.1.0""#));
+        assert_eq!(json.matches("rplc_schema").count(), 1);
+        assert!(json.contains(r#""command_id": "0x0102""#));
+    }
+
+    #[test]
+    fn test_template_rejects_invalid_input() {
+        assert_eq!(
+            new_packet_template("1Bad", "0x0101"),
+            Err(ScaffoldError::InvalidPacketName("1Bad".to_string()))
+        );
+        assert_eq!(
+            new_packet_template("class", "0x0101"),
+            Err(ScaffoldError::InvalidPacketName("class".to_string()))
+        );
+        assert_eq!(
+            new_packet_set_template("Good", "0x10000"),
+            Err(ScaffoldError::InvalidCommandId("0x10000".to_string()))
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! 拼写建议：为未知的字段类型与键找出最接近的合法写法
+
+use json_spanned_value as jsv;
+
+use crate::diagnostics::{RplcDiagnostic, Severity, ValidationCode};
+use crate::validator::parse_array_type;
+
+type Object = jsv::Map<jsv::Spanned<String>, jsv::Spanned<jsv::Value>>;
+
+/// 包对象中的键
+pub(crate) const PACKET_KEYS: &[&str] = &[
+    "rplc_schema",
+    "packet_name",
+    "command_id",
+    "namespace",
+    "packed",
+    "header_guard",
+    "includes",
+    "align",
+    "expected_size",
+    "bitfield_layout",
+    "volatile_view",
+    "serialize",
+    "endianness",
+    "crc",
+    "crc_append",
+    "emit_traits",
+    "traits_template",
+    "comment",
+    "enums",
+    "retired_field_ids",
+    "fields",
+    "dependencies",
+];
+
+/// 枚举定义中的键
+pub(crate) const ENUM_KEYS: &[&str] = &["name", "type", "comment", "values"];
+
+/// 枚举项中的键
+pub(crate) const ENUMERATOR_KEYS: &[&str] = &["name", "value", "comment"];
+
+/// 字段对象中的键
+pub(crate) const FIELD_KEYS: &[&str] = &[
+    "name",
+    "id",
+    "type",
+    "array_len",
+    "bit_field",
+    "offset",
+    "endianness",
+    "comment",
+    "unit",
+    "min",
+    "max",
+    "typical",
+    "values",
+];
+
+/// 嵌套结构体条目中的键；type、bit_field 与 section 由嵌套结构体的检查单独报告
+const GROUP_KEYS: &[&str] = &["name", "comment", "group", "type", "bit_field", "section"];
+
+/// 包集合对象中的键
+pub(crate) const PACKET_SET_KEYS: &[&str] = &["rplc_schema", "protocol_version", "packets"];
+
+/// 字段可以使用的基础类型，按常用程度排列，距离相同时靠前的优先
+pub(crate) const SUPPORTED_TYPES: &[&str] = &[
+    "uint8_t",
+    "uint16_t",
+    "uint32_t",
+    "uint64_t",
+    "int8_t",
+    "int16_t",
+    "int32_t",
+    "int64_t",
+    "float",
+    "double",
+    "bool",
+    "char",
+    "int",
+    "short",
+    "long",
+    "long long",
+    "unsigned char",
+    "unsigned short",
+    "unsigned int",
+    "unsigned long",
+    "unsigned long long",
+    "signed char",
+    "signed short",
+    "signed int",
+    "signed long",
+    "signed long long",
+    "long double",
+    "_Bool",
+];
+
+/// 其他语言中常见的类型写法，编辑距离无法给出合理建议
+const FOREIGN_TYPES: &[(&str, &str)] = &[
+    ("u8", "uint8_t"),
+    ("u16", "uint16_t"),
+    ("u32", "uint32_t"),
+    ("u64", "uint64_t"),
+    ("i8", "int8_t"),
+    ("i16", "int16_t"),
+    ("i32", "int32_t"),
+    ("i64", "int64_t"),
+    ("f32", "float"),
+    ("f64", "double"),
+    ("byte", "uint8_t"),
+    ("boolean", "bool"),
+];
+
+/// 与 `input` 最接近的候选项；忽略大小写，相邻字符交换算作一次编辑，
+/// 距离超过输入长度的三分之一（至少允许 1）时不给出建议
+pub(crate) fn closest<'a>(input: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let input = input.to_lowercase();
+    let limit = (input.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(|&candidate| (edit_distance(&input, &candidate.to_lowercase()), candidate))
+        .filter(|&(distance, _)| distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// 类型的建议写法，数组类型保留长度
+pub(crate) fn suggest_type(ty: &str) -> Option<String> {
+    let (base, len) = parse_array_type(ty.trim())?;
+    let lower = base.to_lowercase();
+    let suggestion = FOREIGN_TYPES
+        .iter()
+        .find(|(foreign, _)| *foreign == lower)
+        .map(|(_, ty)| *ty)
+        .or_else(|| closest(base, SUPPORTED_TYPES))?;
+    Some(match len {
+        Some(len) => format!("{}[{}]", suggestion, len),
+        None => suggestion.to_string(),
+    })
+}
+
+pub(crate) fn did_you_mean(suggestion: &str) -> String {
+    format!("是否应为 '{}'？", suggestion)
+}
+
+/// 对象中不在 `known` 之中的键，诊断指向键本身
+pub(crate) fn unknown_keys(map: &Object, known: &[&str]) -> Vec<RplcDiagnostic> {
+    map.keys()
+        .filter(|key| !known.contains(&key.as_str()))
+        .map(|key| {
+            let (start, end) = key.span();
+            RplcDiagnostic {
+                code: ValidationCode::UnknownKey(key.to_string()),
+                severity: Severity::Warning,
+                span: Some((start, end - start)),
+                help: closest(key, known).map(did_you_mean),
+            }
+        })
+        .collect()
+}
+
+/// 包对象及其枚举、字段、嵌套结构体中的未知键；分组标题与保留条目的多余键由各自的检查报告
+pub(crate) fn unknown_packet_keys(packet: &Object) -> Vec<RplcDiagnostic> {
+    let mut diags = unknown_keys(packet, PACKET_KEYS);
+    let enums = packet.get("enums").and_then(|enums| enums.as_array());
+    for def in enums
+        .into_iter()
+        .flatten()
+        .filter_map(|def| def.as_object())
+    {
+        diags.extend(unknown_keys(def, ENUM_KEYS));
+        let values = def.get("values").and_then(|values| values.as_array());
+        for item in values
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item.as_object())
+        {
+            diags.extend(unknown_keys(item, ENUMERATOR_KEYS));
+        }
+    }
+    let mut entries: Vec<&jsv::Spanned<jsv::Value>> = packet
+        .get("fields")
+        .and_then(|fields| fields.as_array())
+        .map(|fields| fields.iter().collect())
+        .unwrap_or_default();
+    while let Some(entry) = entries.pop() {
+        let Some(map) = entry.as_object() else {
+            continue;
+        };
+        if let Some(members) = map.get("group") {
+            diags.extend(unknown_keys(map, GROUP_KEYS));
+            entries.extend(members.as_array().into_iter().flatten());
+        } else if !map.contains_key("section") && !map.contains_key("reserved") {
+            diags.extend(unknown_keys(map, FIELD_KEYS));
+        }
+    }
+    diags.sort_by_key(|diag| diag.span);
+    diags
+}
+
+/// 为非法类型与不在允许列表中的类型附上建议的写法
+pub(crate) fn attach_type_suggestions(
+    json_input: &str,
+    diags: &mut [RplcDiagnostic],
+    allowed_types: Option<&[String]>,
+) {
+    for diag in diags {
+        if let ValidationCode::InvalidFieldType(_) = diag.code
+            && let Some((start, len)) = diag.span
+            && let Ok(ty) = serde_json::from_str::<String>(&json_input[start..start + len])
+        {
+            diag.help = suggest_type(&ty).map(|ty| did_you_mean(&ty));
+        }
+        if let ValidationCode::TypeNotAllowed(_, ty) = &diag.code
+            && let Some(allowed) = allowed_types
+        {
+            let candidates: Vec<&str> = allowed.iter().map(String::as_str).collect();
+            if let Some(suggestion) = closest(ty, &candidates) {
+                diag.help = Some(did_you_mean(suggestion));
+            }
+        }
+    }
+}
+
+/// 允许相邻字符交换的编辑距离（optimal string alignment）
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::{c_type_to_bit_field_size, validate};
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("flaot", "float"), 1);
+        assert_eq!(edit_distance("uint16", "uint16_t"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_supported_types_are_valid() {
+        for ty in SUPPORTED_TYPES {
+            assert!(
+                c_type_to_bit_field_size(ty).is_some()
+                    || matches!(*ty, "float" | "double" | "long double"),
+                "{}",
+                ty
+            );
+        }
+    }
+
+    #[test]
+    fn test_suggest_type() {
+        assert_eq!(suggest_type("uint16").as_deref(), Some("uint16_t"));
+        assert_eq!(suggest_type("unit8_t").as_deref(), Some("uint8_t"));
+        assert_eq!(suggest_type("Float").as_deref(), Some("float"));
+        assert_eq!(suggest_type("f32[4]").as_deref(), Some("float[4]"));
+        assert_eq!(suggest_type("uint42_t").as_deref(), Some("uint32_t"));
+        assert_eq!(suggest_type("std::string"), None);
+        assert_eq!(closest("packet", PACKET_KEYS), Some("packed"));
+        assert_eq!(closest("cmd_id", PACKET_KEYS), None);
+        assert_eq!(closest("comand_id", PACKET_KEYS), Some("command_id"));
+    }
+
+    #[test]
+    fn test_unknown_keys_and_type_suggestions() {
+        let json = r#"{
+            "packet_name": "Gimbal",
+            "comand_id": "0x0104",
+            "command_id": "0x0104",
+            "comment": "C",
+            "fields": [
+                { "name": "yaw", "typ": "float", "type": "float", "comment": "Y" },
+                { "name": "pitch", "type": "flaot", "comment": "P", "color": "red" },
+                { "section": "Flags" },
+                { "name": "flags", "comment": "F", "group": [
+                    { "name": "mode", "type": "uint8_t", "bit_feild": 4, "comment": "M" }
+                ] }
+            ]
+        }"#;
+        let diags = validate(json);
+        let found: Vec<_> = diags
+            .iter()
+            .map(|d| {
+                let (start, len) = d.span.unwrap();
+                (&json[start..start + len], d.severity, d.help.as_deref())
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    r#""comand_id""#,
+                    Severity::Warning,
+                    Some("是否应为 'command_id'？")
+                ),
+                (r#""typ""#, Severity::Warning, Some("是否应为 'type'？")),
+                (r#""color""#, Severity::Warning, None),
+                (
+                    r#""bit_feild""#,
+                    Severity::Warning,
+                    Some("是否应为 'bit_field'？")
+                ),
+                (r#""flaot""#, Severity::Error, Some("是否应为 'float'？")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! 生成包的字节与位布局图（SVG），用于文档与答辩展示
+//!
+//! 每行 4 字节、每列 1 位，字段按布局引擎计算出的位置着色，保留字节为灰色，对齐填充画斜线；
+//! 图下方的图例列出每个字段的类型与位置，放不下名称的窄字段同样可以对照
+
+use crate::config::Config;
+use crate::generator::{GenerateError, GenerateOptions};
+use crate::layout::{compute_layout, with_reserved_gaps};
+use crate::validator::{format_command_id, parse_command_id};
+
+/// 每行的位数
+const ROW_BITS: u32 = 32;
+const BIT_WIDTH: u32 = 16;
+const ROW_HEIGHT: u32 = 36;
+const MARGIN: u32 = 16;
+/// 左侧字节偏移标签的宽度
+const LABEL_WIDTH: u32 = 48;
+const TITLE_HEIGHT: u32 = 52;
+const HEADER_HEIGHT: u32 = 20;
+const LEGEND_LINE: u32 = 20;
+
+/// 字段依次循环使用的填充色
+const PALETTE: [&str; 8] = [
+    "#8ecae6", "#ffb703", "#90be6d", "#f4a261", "#cdb4db", "#a8dadc", "#f28482", "#b5e48c",
+];
+const RESERVED_COLOR: &str = "#e0e0e0";
+
+/// 位区间在各行中的片段：(行, 行内起始位, 位数)
+fn segments(offset_bits: u32, size_bits: u32) -> Vec<(u32, u32, u32)> {
+    let mut out = Vec::new();
+    let (mut start, end) = (offset_bits, offset_bits + size_bits);
+    while start < end {
+        let row = start / ROW_BITS;
+        let bits = (end - start).min(ROW_BITS - start % ROW_BITS);
+        out.push((row, start % ROW_BITS, bits));
+        start += bits;
+    }
+    out
+}
+
+/// 按 12 px 字号估计文本宽度，CJK 字符按全角计算
+fn text_width(text: &str) -> u32 {
+    text.chars()
+        .map(|c| if c.is_ascii() { 7 } else { 12 })
+        .sum()
+}
+
+/// 转义 XML 文本与属性中的特殊字符
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 位置的可读写法，按字节对齐的区间写字节，否则写位
+fn span_text(offset_bits: u32, size_bits: u32) -> String {
+    let last = offset_bits + size_bits - 1;
+    if offset_bits.is_multiple_of(8) && size_bits.is_multiple_of(8) {
+        format!("字节 {}–{}，{} 位", offset_bits / 8, last / 8, size_bits)
+    } else {
+        format!("位 {}–{}，{} 位", offset_bits, last, size_bits)
+    }
+}
+
+pub(crate) fn generate_svg(
+    config: &Config,
+    options: &GenerateOptions,
+) -> Result<String, GenerateError> {
+    let config = &with_reserved_gaps(config);
+    let layout = compute_layout(config).ok_or(GenerateError::ValidationError)?;
+    let command_id = parse_command_id(&config.command_id)
+        .map(format_command_id)
+        .map_err(|_| GenerateError::ValidationError)?;
+    let i1 = options.indent(1);
+
+    let total_bits = layout.size * 8;
+    let rows = total_bits.div_ceil(ROW_BITS).max(1);
+    let grid_x = MARGIN + LABEL_WIDTH;
+    let grid_y = TITLE_HEIGHT + HEADER_HEIGHT;
+    let legend_y = grid_y + rows * ROW_HEIGHT + MARGIN;
+
+    // 对齐填充与尾部填充的区间
+    let mut padding: Vec<(u32, u32)> = layout
+        .fields
+        .iter()
+        .filter(|placed| placed.padding_bits > 0)
+        .map(|placed| {
+            (
+                placed.offset_bits - placed.padding_bits,
+                placed.padding_bits,
+            )
+        })
+        .collect();
+    if layout.tail_padding_bits > 0 {
+        padding.push((
+            total_bits - layout.tail_padding_bits,
+            layout.tail_padding_bits,
+        ));
+    }
+
+    let sections = config.fields.iter().filter(|f| f.section.is_some()).count() as u32;
+    let legend_lines = config.fields.len() as u32 + sections + u32::from(!padding.is_empty());
+    let width = grid_x + ROW_BITS * BIT_WIDTH + MARGIN;
+    let height = legend_y + legend_lines * LEGEND_LINE + MARGIN;
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<!-- 由 rplc 生成，请勿手动修改 -->\n");
+    out.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"12\">\n",
+        w = width,
+        h = height
+    ));
+    out.push_str(&format!(
+        "{i1}<defs><pattern id=\"rplc-padding\" width=\"6\" height=\"6\" patternUnits=\"userSpaceOnUse\" patternTransform=\"rotate(45)\"><rect width=\"6\" height=\"6\" fill=\"#ffffff\"/><line x1=\"0\" y1=\"0\" x2=\"0\" y2=\"6\" stroke=\"#bbbbbb\" stroke-width=\"2\"/></pattern></defs>\n"
+    ));
+    out.push_str(&format!(
+        "{i1}<rect width=\"{}\" height=\"{}\" fill=\"#ffffff\"/>\n",
+        width, height
+    ));
+
+    // 标题：包名、命令码、大小与注释
+    out.push_str(&format!(
+        "{i1}<text x=\"{}\" y=\"24\" font-size=\"16\" font-weight=\"bold\">{}</text>\n",
+        MARGIN,
+        escape(&config.packet_name)
+    ));
+    let mut subtitle = format!("命令码 {}，{} 字节", command_id, layout.size);
+    if layout.bitstream {
+        subtitle.push_str("，位流布局");
+    }
+    if let Some(comment) = &config.comment {
+        subtitle.push('，');
+        subtitle.push_str(
+            &comment
+                .as_str()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+    }
+    out.push_str(&format!(
+        "{i1}<text x=\"{}\" y=\"42\" fill=\"#555555\">{}</text>\n",
+        MARGIN,
+        escape(&subtitle)
+    ));
+
+    // 表头：每个字节列的相对偏移
+    for byte in 0..ROW_BITS / 8 {
+        out.push_str(&format!(
+            "{i1}<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" fill=\"#555555\">+{}</text>\n",
+            grid_x + (byte * 8 + 4) * BIT_WIDTH,
+            grid_y - 6,
+            byte
+        ));
+    }
+
+    // 字段与填充
+    let row_y = |row: u32| grid_y + row * ROW_HEIGHT;
+    let seg_x = |bit: u32| grid_x + bit * BIT_WIDTH;
+    let mut labels = Vec::new();
+    for (index, (field, placed)) in config.fields.iter().zip(&layout.fields).enumerate() {
+        let name = field.member_path();
+        let color = if field.reserved.is_some() {
+            RESERVED_COLOR
+        } else {
+            PALETTE[index % PALETTE.len()]
+        };
+        let ty = field.enum_type.as_deref().unwrap_or(&field.ty);
+        let tooltip = format!(
+            "{}: {}，{}",
+            name,
+            ty,
+            span_text(placed.offset_bits, placed.size_bits)
+        );
+        let pieces = segments(placed.offset_bits, placed.size_bits);
+        for &(row, start, bits) in &pieces {
+            out.push_str(&format!(
+                "{i1}<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"><title>{}</title></rect>\n",
+                seg_x(start),
+                row_y(row),
+                bits * BIT_WIDTH,
+                ROW_HEIGHT,
+                color,
+                escape(&tooltip)
+            ));
+        }
+        // 名称写在最宽的（同宽时取第一个）片段中，放不下时只出现在图例里
+        if let Some(&(row, start, bits)) = pieces.iter().rev().max_by_key(|(_, _, bits)| *bits)
+            && text_width(&name) + 4 <= bits * BIT_WIDTH
+        {
+            labels.push((
+                seg_x(start) + bits * BIT_WIDTH / 2,
+                row_y(row) + ROW_HEIGHT / 2 + 4,
+                name,
+            ));
+        }
+    }
+    for &(offset, bits) in &padding {
+        for (row, start, row_bits) in segments(offset, bits) {
+            out.push_str(&format!(
+                "{i1}<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"url(#rplc-padding)\"><title>填充，{}</title></rect>\n",
+                seg_x(start),
+                row_y(row),
+                row_bits * BIT_WIDTH,
+                ROW_HEIGHT,
+                span_text(offset, bits)
+            ));
+        }
+    }
+
+    // 位线：字节边界较深，其余每一位较浅；最后一行只画到包的末尾
+    for row in 0..rows {
+        let row_bits = total_bits.saturating_sub(row * ROW_BITS).min(ROW_BITS);
+        let y = row_y(row);
+        out.push_str(&format!(
+            "{i1}<text x=\"{}\" y=\"{}\" text-anchor=\"end\" fill=\"#555555\">{}</text>\n",
+            grid_x - 8,
+            y + ROW_HEIGHT / 2 + 4,
+            row * ROW_BITS / 8
+        ));
+        for bit in 1..row_bits {
+            let stroke = if bit.is_multiple_of(8) {
+                "#666666"
+            } else {
+                "#cccccc"
+            };
+            out.push_str(&format!(
+                "{i1}<line x1=\"{x}\" y1=\"{}\" x2=\"{x}\" y2=\"{}\" stroke=\"{}\"/>\n",
+                y,
+                y + ROW_HEIGHT,
+                stroke,
+                x = seg_x(bit)
+            ));
+        }
+        out.push_str(&format!(
+            "{i1}<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"#333333\"/>\n",
+            grid_x,
+            y,
+            row_bits.max(1) * BIT_WIDTH,
+            ROW_HEIGHT
+        ));
+    }
+    for (x, y, name) in labels {
+        out.push_str(&format!(
+            "{i1}<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+            x,
+            y,
+            escape(&name)
+        ));
+    }
+
+    // 图例
+    let mut y = legend_y;
+    for (index, (field, placed)) in config.fields.iter().zip(&layout.fields).enumerate() {
+        if let Some(section) = &field.section {
+            out.push_str(&format!(
+                "{i1}<text x=\"{}\" y=\"{}\" font-weight=\"bold\">{}</text>\n",
+                MARGIN,
+                y + 12,
+                escape(section.trim())
+            ));
+            y += LEGEND_LINE;
+        }
+        let color = if field.reserved.is_some() {
+            RESERVED_COLOR
+        } else {
+            PALETTE[index % PALETTE.len()]
+        };
+        let ty = field.enum_type.as_deref().unwrap_or(&field.ty);
+        let ty = match field.bit_field {
+            Some(bits) => format!("{} : {}", ty, bits),
+            None => ty.to_string(),
+        };
+        out.push_str(&format!(
+            "{i1}<rect x=\"{}\" y=\"{}\" width=\"12\" height=\"12\" fill=\"{}\" stroke=\"#333333\"/>\n",
+            MARGIN,
+            y + 2,
+            color
+        ));
+        out.push_str(&format!(
+            "{i1}<text x=\"{}\" y=\"{}\">{}: {}，{}</text>\n",
+            MARGIN + 18,
+            y + 12,
+            escape(&field.member_path()),
+            escape(&ty),
+            span_text(placed.offset_bits, placed.size_bits)
+        ));
+        y += LEGEND_LINE;
+    }
+    if !padding.is_empty() {
+        let bits: u32 = padding.iter().map(|(_, bits)| bits).sum();
+        out.push_str(&format!(
+            "{i1}<rect x=\"{}\" y=\"{}\" width=\"12\" height=\"12\" fill=\"url(#rplc-padding)\" stroke=\"#333333\"/>\n",
+            MARGIN,
+            y + 2
+        ));
+        out.push_str(&format!(
+            "{i1}<text x=\"{}\" y=\"{}\">填充，共 {} 位</text>\n",
+            MARGIN + 18,
+            y + 12,
+            bits
+        ));
+    }
+    out.push_str("</svg>\n");
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(json: &str) -> String {
+        let config: Config = serde_json::from_str(json).unwrap();
+        generate_svg(&config, &GenerateOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_generate_svg_byte_map() {
+        let svg = generate(
+            r#"{
+                "packet_name": "Imu",
+                "command_id": "0x0101",
+                "packed": false,
+                "comment": "姿态 <IMU> & 温度",
+                "fields": [
+                    { "name": "id", "type": "uint8_t" },
+                    { "name": "yaw", "type": "float" },
+                    { "section": "温度" },
+                    { "name": "temp", "type": "int16_t" }
+                ]
+            }"#,
+        );
+        assert!(svg.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(svg.contains("命令码 0x0101，12 字节，姿态 &lt;IMU&gt; &amp; 温度"));
+        // yaw 对齐到第 4 字节，独占第二行
+        assert!(svg.contains(
+            "    <rect x=\"64\" y=\"108\" width=\"512\" height=\"36\" fill=\"#ffb703\"><title>yaw: float，字节 4–7，32 位</title></rect>\n"
+        ));
+        assert!(svg.contains("<text x=\"320\" y=\"130\" text-anchor=\"middle\">yaw</text>"));
+        // id 之后 3 字节与 temp 之后 2 字节的填充
+        assert!(svg.contains(
+            "<rect x=\"192\" y=\"72\" width=\"384\" height=\"36\" fill=\"url(#rplc-padding)\"><title>填充，字节 1–3，24 位</title></rect>"
+        ));
+        assert!(svg.contains("<title>填充，字节 10–11，16 位</title>"));
+        assert!(svg.contains("填充，共 40 位</text>"));
+        assert!(svg.contains("font-weight=\"bold\">温度</text>"));
+        assert_eq!(svg.matches("stroke=\"#333333\"/>").count(), 3 + 4);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn test_generate_svg_bit_lanes() {
+        let svg = generate(
+            r#"{
+                "packet_name": "Flags",
+                "command_id": "0x0102",
+                "fields": [
+                    { "name": "mode", "type": "uint8_t", "bit_field": 3 },
+                    { "name": "armed", "type": "uint8_t", "bit_field": 1 },
+                    { "reserved": 1 },
+                    { "name": "counter", "type": "uint32_t" }
+                ]
+            }"#,
+        );
+        assert!(svg.contains("命令码 0x0102，6 字节，位流布局"));
+        assert!(svg.contains("<title>mode: uint8_t，位 0–2，3 位</title>"));
+        assert!(svg.contains("mode: uint8_t : 3，位 0–2，3 位</text>"));
+        // 窄字段的名称只出现在图例里
+        assert!(!svg.contains("text-anchor=\"middle\">armed</text>"));
+        assert!(svg.contains(&format!("fill=\"{}\"><title>_reserved0", RESERVED_COLOR)));
+        // counter 跨越两行
+        assert!(svg.contains("<rect x=\"256\" y=\"72\" width=\"320\" height=\"36\" fill"));
+        assert!(svg.contains("<rect x=\"64\" y=\"108\" width=\"192\" height=\"36\" fill"));
+        // 第二行只有 16 位
+        assert!(svg.contains(
+            "<rect x=\"64\" y=\"108\" width=\"256\" height=\"36\" fill=\"none\" stroke=\"#333333\"/>"
+        ));
+    }
+}
@@ -0,0 +1,385 @@
+//! 从表格（CSV）导入字段列表
+//!
+//! 机械与电控组的成员通常在表格中维护字段，导出为 CSV 后按列映射转换为包定义
+
+use std::str::FromStr;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::config::CURRENT_SCHEMA;
+use crate::scaffold::{ScaffoldError, packet_identity, to_pretty_json};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ImportError {
+    #[error(transparent)]
+    Scaffold(#[from] ScaffoldError),
+    #[error("列映射 '{0}' 无效，应写成 name=A,type=B,comment=C 的形式")]
+    InvalidColumnMap(String),
+    #[error("列映射缺少 {0}")]
+    MissingColumn(&'static str),
+    #[error("找不到列 '{0}'：既不是表头中的列名，也不是 A、B、AA 形式的列号")]
+    UnknownColumn(String),
+    #[error("第 {0} 行的引号没有闭合")]
+    UnterminatedQuote(usize),
+    #[error("第 {row} 行缺少 {key}")]
+    MissingCell { row: usize, key: &'static str },
+    #[error("表格中没有字段")]
+    EmptyTable,
+    #[error("第 {0} 行的注释没有闭合")]
+    UnterminatedComment(usize),
+    #[error("头文件中没有可以导入的结构体")]
+    NoStructs,
+}
+
+/// 列映射中可以使用的键
+const COLUMN_KEYS: &[&str] = &["name", "type", "comment", "bit_field"];
+
+/// 字段的各个键取自哪一列；列可以写表头中的列名，或 A、B、AA 形式的列号
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMap {
+    pub name: String,
+    pub ty: String,
+    pub comment: Option<String>,
+    pub bit_field: Option<String>,
+}
+
+impl Default for ColumnMap {
+    fn default() -> Self {
+        Self {
+            name: "A".to_string(),
+            ty: "B".to_string(),
+            comment: Some("C".to_string()),
+            bit_field: None,
+        }
+    }
+}
+
+impl FromStr for ColumnMap {
+    type Err = ImportError;
+
+    /// 例如 `name=A,type=B,comment=说明`
+    fn from_str(text: &str) -> Result<Self, ImportError> {
+        let invalid = || ImportError::InvalidColumnMap(text.to_string());
+        let mut columns: [Option<String>; 4] = Default::default();
+        for pair in text.split(',') {
+            let (key, column) = pair.split_once('=').ok_or_else(invalid)?;
+            let column = column.trim();
+            let index = COLUMN_KEYS
+                .iter()
+                .position(|k| *k == key.trim())
+                .ok_or_else(invalid)?;
+            if column.is_empty() || columns[index].is_some() {
+                return Err(invalid());
+            }
+            columns[index] = Some(column.to_string());
+        }
+        let [name, ty, comment, bit_field] = columns;
+        Ok(Self {
+            name: name.ok_or(ImportError::MissingColumn("name"))?,
+            ty: ty.ok_or(ImportError::MissingColumn("type"))?,
+            comment,
+            bit_field,
+        })
+    }
+}
+
+/// 导入选项
+#[derive(Debug, Clone)]
+pub struct TableImport {
+    pub packet_name: String,
+    pub command_id: String,
+    pub columns: ColumnMap,
+    /// 第一行为表头，不作为字段导入
+    pub header: bool,
+}
+
+#[derive(Serialize)]
+struct ImportedPacket {
+    rplc_schema: u32,
+    packet_name: String,
+    command_id: String,
+    fields: Vec<ImportedField>,
+}
+
+#[derive(Serialize)]
+struct ImportedField {
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+    /// 不是整数的位域原样写出，交给 `validate` 报告
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bit_field: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+/// 将 CSV 表格转换为包定义 JSON；全空的行被跳过，单元格的内容去掉首尾空白。
+/// 分隔符按第一行自动识别为逗号、分号或制表符
+pub fn import_table(text: &str, options: &TableImport) -> Result<String, ImportError> {
+    let (packet_name, command_id) = packet_identity(&options.packet_name, &options.command_id)?;
+    let rows = parse_csv(text.trim_start_matches('\u{feff}'))?;
+
+    let (header, body) = match rows.split_first() {
+        Some((header, body)) if options.header => (Some(header.as_slice()), body),
+        _ => (None, rows.as_slice()),
+    };
+    let first_row = if options.header { 2 } else { 1 };
+    let resolve = |column: &String| resolve_column(column, header);
+    let name = resolve(&options.columns.name)?;
+    let ty = resolve(&options.columns.ty)?;
+    let comment = options.columns.comment.as_ref().map(resolve).transpose()?;
+    let bit_field = options
+        .columns
+        .bit_field
+        .as_ref()
+        .map(resolve)
+        .transpose()?;
+
+    let mut fields = Vec::new();
+    for (index, row) in body.iter().enumerate() {
+        if row.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        let row_number = first_row + index;
+        let cell = |column: usize| {
+            row.get(column)
+                .map(|cell| cell.trim())
+                .filter(|cell| !cell.is_empty())
+        };
+        let required = |column, key| {
+            cell(column).ok_or(ImportError::MissingCell {
+                row: row_number,
+                key,
+            })
+        };
+        fields.push(ImportedField {
+            name: required(name, "name")?.to_string(),
+            ty: required(ty, "type")?.to_string(),
+            bit_field: bit_field
+                .and_then(cell)
+                .map(|bits| match bits.parse::<u64>() {
+                    Ok(bits) => bits.into(),
+                    Err(_) => bits.into(),
+                }),
+            comment: comment.and_then(cell).map(str::to_string),
+        });
+    }
+    if fields.is_empty() {
+        return Err(ImportError::EmptyTable);
+    }
+
+    Ok(to_pretty_json(&ImportedPacket {
+        rplc_schema: CURRENT_SCHEMA,
+        packet_name,
+        command_id,
+        fields,
+    }))
+}
+
+/// 表头中的列名优先，其次是列号
+fn resolve_column(column: &str, header: Option<&[String]>) -> Result<usize, ImportError> {
+    if let Some(index) = header.and_then(|header| {
+        header
+            .iter()
+            .position(|cell| cell.trim().eq_ignore_ascii_case(column))
+    }) {
+        return Ok(index);
+    }
+    column_letter(column).ok_or_else(|| ImportError::UnknownColumn(column.to_string()))
+}
+
+/// `A` → 0，`Z` → 25，`AA` → 26
+fn column_letter(column: &str) -> Option<usize> {
+    if column.is_empty() || column.len() > 3 || !column.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let number = column
+        .to_ascii_uppercase()
+        .bytes()
+        .fold(0, |number, c| number * 26 + usize::from(c - b'A' + 1));
+    Some(number - 1)
+}
+
+/// RFC 4180 形式的 CSV：双引号包裹的单元格中可以包含分隔符、换行与 `""` 转义的引号
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, ImportError> {
+    let delimiter = detect_delimiter(text);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut quote_line = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line += 1;
+        }
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    cell.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if cell.trim().is_empty() => {
+                cell.clear();
+                in_quotes = true;
+                quote_line = line;
+            }
+            _ if in_quotes => cell.push(c),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ if c == delimiter => row.push(std::mem::take(&mut cell)),
+            _ => cell.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(ImportError::UnterminatedQuote(quote_line));
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// 第一行中出现最多的分隔符，中文版 Excel 与 WPS 可能导出分号或制表符分隔的文件
+fn detect_delimiter(text: &str) -> char {
+    let first_line = text.lines().next().unwrap_or_default();
+    [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|&d| (first_line.matches(d).count(), d == ','))
+        .unwrap_or(',')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::ValidationCode;
+    use crate::validator::validate;
+
+    fn options(columns: &str) -> TableImport {
+        TableImport {
+            packet_name: "Gimbal".to_string(),
+            command_id: "260".to_string(),
+            columns: columns.parse().unwrap(),
+            header: true,
+        }
+    }
+
+    #[test]
+    fn test_column_map_parse() {
+        let map: ColumnMap = "name=A, type=B,bit_field=位宽".parse().unwrap();
+        assert_eq!(map.name, "A");
+        assert_eq!(map.comment, None);
+        assert_eq!(map.bit_field.as_deref(), Some("位宽"));
+        assert!(matches!(
+            "name=A,kind=B".parse::<ColumnMap>(),
+            Err(ImportError::InvalidColumnMap(_))
+        ));
+        assert!(matches!(
+            "name=A,name=B".parse::<ColumnMap>(),
+            Err(ImportError::InvalidColumnMap(_))
+        ));
+        assert_eq!(
+            "name=A".parse::<ColumnMap>(),
+            Err(ImportError::MissingColumn("type"))
+        );
+        assert_eq!(column_letter("A"), Some(0));
+        assert_eq!(column_letter("z"), Some(25));
+        assert_eq!(column_letter("AA"), Some(26));
+        assert_eq!(column_letter("A1"), None);
+    }
+
+    #[test]
+    fn test_import_table() {
+        let csv = "序号,字段名,类型,位宽,说明\r\n\
+                   1,yaw,float,,\"偏航角, 弧度\"\r\n\
+                   ,,,,\r\n\
+                   2,mode,uint8_t,3,\"模式 \"\"0/1\"\"\"\r\n\
+                   3,flags,uint8_t,5,\n";
+        let json = import_table(
+            csv,
+            &options("name=字段名,type=C,comment=说明,bit_field=位宽"),
+        )
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{
+  "rplc_schema": 2,
+  "packet_name": "Gimbal",
+  "command_id": "0x0104",
+  "fields": [
+    {
+      "name": "yaw",
+      "type": "float",
+      "comment": "偏航角, 弧度"
+    },
+    {
+      "name": "mode",
+      "type": "uint8_t",
+      "bit_field": 3,
+      "comment": "模式 \"0/1\""
+    },
+    {
+      "name": "flags",
+      "type": "uint8_t",
+      "bit_field": 5
+    }
+  ]
+}
+"#
+        );
+        let codes: Vec<_> = validate(&json).into_iter().map(|d| d.code).collect();
+        assert_eq!(
+            codes,
+            vec![ValidationCode::MissingComment("flags".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_import_table_delimiters_and_errors() {
+        let mut no_header = options("name=A,type=B");
+        no_header.header = false;
+        let json = import_table("a;uint8_t\nb;int16_t", &no_header).unwrap();
+        assert!(
+            validate(&json)
+                .iter()
+                .all(|d| matches!(d.code, ValidationCode::MissingComment(_)))
+        );
+        assert!(json.contains(r#""type": "int16_t""#));
+        assert!(import_table("a\tuint8_t\tx", &no_header).is_ok());
+
+        assert_eq!(
+            import_table("name,type\nyaw,float\npitch,\n", &options("name=A,type=B")),
+            Err(ImportError::MissingCell {
+                row: 3,
+                key: "type"
+            })
+        );
+        assert_eq!(
+            import_table("name,type\n", &options("name=A,type=B")),
+            Err(ImportError::EmptyTable)
+        );
+        assert_eq!(
+            import_table("name,type\n", &options("name=名称,type=B")),
+            Err(ImportError::UnknownColumn("名称".to_string()))
+        );
+        assert_eq!(
+            import_table("name,type\n\"yaw,float\n", &options("name=A,type=B")),
+            Err(ImportError::UnterminatedQuote(2))
+        );
+        let mut bad_name = options("name=A,type=B");
+        bad_name.packet_name = "1Bad".to_string();
+        assert!(matches!(
+            import_table("a,uint8_t", &bad_name),
+            Err(ImportError::Scaffold(_))
+        ));
+    }
+}
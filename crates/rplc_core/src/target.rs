@@ -0,0 +1,183 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// 代码生成的目标语言
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+pub enum Target {
+    Cpp,
+    C,
+    Rust,
+    Python,
+    TypeScript,
+    Matlab,
+}
+
+impl Target {
+    pub const ALL: [Target; 6] = [
+        Target::Cpp,
+        Target::C,
+        Target::Rust,
+        Target::Python,
+        Target::TypeScript,
+        Target::Matlab,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::Cpp => "C++",
+            Target::C => "C",
+            Target::Rust => "Rust",
+            Target::Python => "Python",
+            Target::TypeScript => "TypeScript",
+            Target::Matlab => "MATLAB",
+        }
+    }
+
+    /// 将 C/C++ 基础类型映射为目标语言中的类型
+    ///
+    /// 无法表示时返回原因说明
+    pub fn map_type(self, base_type: &str) -> Result<&'static str, &'static str> {
+        match self {
+            Target::Cpp | Target::C => cpp_type(base_type),
+            Target::Rust => rust_type(base_type),
+            Target::Python => python_type(base_type),
+            Target::TypeScript => typescript_type(base_type),
+            Target::Matlab => matlab_type(base_type),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+const UNKNOWN_TYPE: &str = "不支持的类型";
+const NO_LONG_DOUBLE: &str = "long double 的宽度依赖平台，且没有对应的类型";
+
+fn cpp_type(ty: &str) -> Result<&'static str, &'static str> {
+    match ty {
+        "float" => Ok("float"),
+        "double" => Ok("double"),
+        "long double" => Ok("long double"),
+        _ if crate::validator::c_type_to_bit_field_size(ty).is_some() => Ok("integer"),
+        _ => Err(UNKNOWN_TYPE),
+    }
+}
+
+fn rust_type(ty: &str) -> Result<&'static str, &'static str> {
+    match ty {
+        "float" => Ok("f32"),
+        "double" => Ok("f64"),
+        "long double" => Err(NO_LONG_DOUBLE),
+        "_Bool" | "bool" => Ok("bool"),
+        "char" | "signed char" | "int8_t" => Ok("i8"),
+        "unsigned char" | "uint8_t" => Ok("u8"),
+        "short" | "signed short" | "int16_t" => Ok("i16"),
+        "unsigned short" | "uint16_t" => Ok("u16"),
+        "int" | "signed int" | "int32_t" => Ok("i32"),
+        "unsigned int" | "uint32_t" => Ok("u32"),
+        "long" | "signed long" | "long long" | "signed long long" | "int64_t" => Ok("i64"),
+        "unsigned long" | "unsigned long long" | "uint64_t" => Ok("u64"),
+        _ => Err(UNKNOWN_TYPE),
+    }
+}
+
+/// Python 使用 `struct` 模块的格式字符
+fn python_type(ty: &str) -> Result<&'static str, &'static str> {
+    match ty {
+        "float" => Ok("f"),
+        "double" => Ok("d"),
+        "long double" => Err("struct 模块没有 long double 对应的格式字符"),
+        "_Bool" | "bool" => Ok("?"),
+        "char" | "signed char" | "int8_t" => Ok("b"),
+        "unsigned char" | "uint8_t" => Ok("B"),
+        "short" | "signed short" | "int16_t" => Ok("h"),
+        "unsigned short" | "uint16_t" => Ok("H"),
+        "int" | "signed int" | "int32_t" => Ok("i"),
+        "unsigned int" | "uint32_t" => Ok("I"),
+        "long" | "signed long" | "long long" | "signed long long" | "int64_t" => Ok("q"),
+        "unsigned long" | "unsigned long long" | "uint64_t" => Ok("Q"),
+        _ => Err(UNKNOWN_TYPE),
+    }
+}
+
+/// TypeScript 使用 `DataView` 读取，64 位整数映射为 bigint
+fn typescript_type(ty: &str) -> Result<&'static str, &'static str> {
+    match ty {
+        "float" | "double" => Ok("number"),
+        "long double" => Err("DataView 无法读取 long double"),
+        "_Bool" | "bool" => Ok("boolean"),
+        "long" | "signed long" | "long long" | "signed long long" | "int64_t" | "unsigned long"
+        | "unsigned long long" | "uint64_t" => Ok("bigint"),
+        _ if crate::validator::c_type_to_bit_field_size(ty).is_some() => Ok("number"),
+        _ => Err(UNKNOWN_TYPE),
+    }
+}
+
+/// MATLAB 使用 `typecast` 支持的数值类名
+fn matlab_type(ty: &str) -> Result<&'static str, &'static str> {
+    match ty {
+        "float" => Ok("single"),
+        "double" => Ok("double"),
+        "long double" => Err(NO_LONG_DOUBLE),
+        "_Bool" | "bool" => Ok("logical"),
+        "char" | "signed char" | "int8_t" => Ok("int8"),
+        "unsigned char" | "uint8_t" => Ok("uint8"),
+        "short" | "signed short" | "int16_t" => Ok("int16"),
+        "unsigned short" | "uint16_t" => Ok("uint16"),
+        "int" | "signed int" | "int32_t" => Ok("int32"),
+        "unsigned int" | "uint32_t" => Ok("uint32"),
+        "long" | "signed long" | "long long" | "signed long long" | "int64_t" => Ok("int64"),
+        "unsigned long" | "unsigned long long" | "uint64_t" => Ok("uint64"),
+        _ => Err(UNKNOWN_TYPE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_integer_type_maps_in_every_target() {
+        let integers = [
+            "bool",
+            "char",
+            "uint8_t",
+            "int16_t",
+            "unsigned int",
+            "long",
+            "uint64_t",
+        ];
+        for target in Target::ALL {
+            for ty in integers {
+                assert!(target.map_type(ty).is_ok(), "{} should map {}", target, ty);
+            }
+            assert!(target.map_type("float").is_ok());
+            assert!(target.map_type("double").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_long_double_only_maps_in_c_family() {
+        assert_eq!(Target::Cpp.map_type("long double"), Ok("long double"));
+        assert!(Target::C.map_type("long double").is_ok());
+        assert!(Target::Rust.map_type("long double").is_err());
+        assert!(Target::Python.map_type("long double").is_err());
+        assert!(Target::TypeScript.map_type("long double").is_err());
+        assert!(Target::Matlab.map_type("long double").is_err());
+    }
+
+    #[test]
+    fn test_target_specific_mappings() {
+        assert_eq!(Target::Rust.map_type("uint16_t"), Ok("u16"));
+        assert_eq!(Target::Python.map_type("int32_t"), Ok("i"));
+        assert_eq!(Target::TypeScript.map_type("uint64_t"), Ok("bigint"));
+        assert_eq!(Target::TypeScript.map_type("uint32_t"), Ok("number"));
+        assert_eq!(Target::Matlab.map_type("float"), Ok("single"));
+        assert!(Target::Rust.map_type("Custom").is_err());
+        assert_eq!(Target::TypeScript.to_string(), "TypeScript");
+    }
+}
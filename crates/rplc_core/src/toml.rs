@@ -0,0 +1,326 @@
+//! TOML 格式的协议定义
+//!
+//! TOML 文档按值转写为 JSON：整数、浮点数、布尔值与字符串对应同类的 JSON 值，表与内联表为对象，
+//! 数组与表数组（`[[fields]]`）为数组；日期时间没有对应的 JSON 类型，按原文作为字符串
+
+use std::ops::Range;
+
+use toml_edit::{Document, Item, Key, Table, Value};
+
+use crate::diagnostics::{RplcDiagnostic, Severity, Span, ValidationCode};
+use crate::generator::{GenerateError, GenerateOptions, generate_with_options};
+use crate::transcript::{InputFormat, SpanMapping, Transcript, validate_source};
+use crate::validator::ValidateOptions;
+
+/// 校验 TOML 格式的定义，诊断位置相对于 TOML 文本
+pub fn validate_toml(toml: &str) -> Vec<RplcDiagnostic> {
+    validate_source(toml, InputFormat::Toml, &ValidateOptions::default())
+}
+
+/// 从 TOML 格式的单包定义生成 C++ 头文件，校验规则与 `generate` 相同
+pub fn generate_from_toml(toml: &str, options: &GenerateOptions) -> Result<String, GenerateError> {
+    let transcript = toml_to_json(toml).map_err(|_| GenerateError::ValidationError)?;
+    generate_with_options(transcript.json(), options)
+}
+
+pub(crate) fn toml_to_json(toml: &str) -> Result<Transcript, Vec<RplcDiagnostic>> {
+    let document = Document::parse(toml).map_err(|e| {
+        let span = e.span().map_or((0, 0), |span| (span.start, span.len()));
+        vec![invalid_toml(e.message().trim().to_string(), span)]
+    })?;
+
+    let mut writer = Writer {
+        toml,
+        json: String::new(),
+        spans: Vec::new(),
+    };
+    let root = 0..toml.trim_end().len();
+    writer
+        .table(&document, root)
+        .map_err(|(reason, span)| vec![invalid_toml(reason, span)])?;
+    Ok(Transcript::new(writer.json, writer.spans))
+}
+
+fn invalid_toml(reason: String, span: Span) -> RplcDiagnostic {
+    RplcDiagnostic {
+        code: ValidationCode::InvalidToml(reason),
+        severity: Severity::Error,
+        span: Some(span),
+        help: None,
+    }
+}
+
+/// 转写失败的原因与位置
+type TomlError = (String, Span);
+
+struct Writer<'a> {
+    toml: &'a str,
+    json: String,
+    spans: Vec<SpanMapping>,
+}
+
+impl Writer<'_> {
+    /// 写入一个值并记录它在 TOML 中的位置，`content` 为逐字节相同的字符串内容的起点
+    fn record(&mut self, json_start: usize, source: Range<usize>, content: Option<usize>) {
+        let json = json_start..self.json.len();
+        let content = content.map(|source_start| (json.start + 1, source_start));
+        self.spans.push(SpanMapping {
+            json,
+            source,
+            content,
+        });
+    }
+
+    /// 表的位置是它的表头，根表与隐式的表使用外层传入的位置
+    fn table(&mut self, table: &Table, source: Range<usize>) -> Result<(), TomlError> {
+        let json_start = self.json.len();
+        self.json.push('{');
+        for (index, (name, _)) in table.iter().enumerate() {
+            if index > 0 {
+                self.json.push(',');
+            }
+            let (key, item) = table.get_key_value(name).expect("键来自同一个表");
+            self.key(key, &source);
+            self.json.push(':');
+            let fallback = key.span().unwrap_or(source.clone());
+            self.item(item, fallback)?;
+        }
+        self.json.push('}');
+        self.record(json_start, table.span().unwrap_or(source), None);
+        Ok(())
+    }
+
+    fn key(&mut self, key: &Key, table: &Range<usize>) {
+        let json_start = self.json.len();
+        self.json.push_str(&json_string(key.get()));
+        let source = key.span().unwrap_or(table.clone());
+        let content = verbatim_start(self.toml, &source, key.get());
+        self.record(json_start, source, content);
+    }
+
+    fn item(&mut self, item: &Item, fallback: Range<usize>) -> Result<(), TomlError> {
+        match item {
+            Item::None => {
+                self.json.push_str("null");
+                Ok(())
+            }
+            Item::Value(value) => self.value(value, fallback),
+            Item::Table(table) => self.table(table, fallback),
+            Item::ArrayOfTables(tables) => {
+                let json_start = self.json.len();
+                self.json.push('[');
+                for (index, table) in tables.iter().enumerate() {
+                    if index > 0 {
+                        self.json.push(',');
+                    }
+                    self.table(table, fallback.clone())?;
+                }
+                self.json.push(']');
+                self.record(json_start, tables.span().unwrap_or(fallback), None);
+                Ok(())
+            }
+        }
+    }
+
+    fn value(&mut self, value: &Value, fallback: Range<usize>) -> Result<(), TomlError> {
+        let source = value.span().unwrap_or(fallback);
+        let json_start = self.json.len();
+        let mut content = None;
+        match value {
+            Value::String(s) => {
+                let json = json_string(s.value());
+                if json.len() == s.value().len() + 2 {
+                    content = verbatim_start(self.toml, &source, s.value());
+                }
+                self.json.push_str(&json);
+            }
+            Value::Integer(n) => self.json.push_str(&n.value().to_string()),
+            Value::Float(f) => {
+                let Some(number) = serde_json::Number::from_f64(*f.value()) else {
+                    return Err((
+                        "不支持 inf 与 nan".to_string(),
+                        (source.start, source.len()),
+                    ));
+                };
+                self.json.push_str(&number.to_string());
+            }
+            Value::Boolean(b) => self.json.push_str(&b.value().to_string()),
+            Value::Datetime(dt) => self.json.push_str(&json_string(&dt.value().to_string())),
+            Value::Array(array) => {
+                self.json.push('[');
+                for (index, element) in array.iter().enumerate() {
+                    if index > 0 {
+                        self.json.push(',');
+                    }
+                    self.value(element, source.clone())?;
+                }
+                self.json.push(']');
+            }
+            Value::InlineTable(table) => {
+                self.json.push('{');
+                for (index, (name, _)) in table.iter().enumerate() {
+                    if index > 0 {
+                        self.json.push(',');
+                    }
+                    let (key, item) = table.get_key_value(name).expect("键来自同一个表");
+                    self.key(key, &source);
+                    self.json.push(':');
+                    self.item(item, key.span().unwrap_or(source.clone()))?;
+                }
+                self.json.push('}');
+            }
+        }
+        self.record(json_start, source, content);
+        Ok(())
+    }
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
+
+/// 键或字符串的源文本（或去掉引号后）与值相同时，返回值在源文本中的起点
+fn verbatim_start(toml: &str, source: &Range<usize>, value: &str) -> Option<usize> {
+    let text = toml.get(source.clone())?;
+    if text == value {
+        return Some(source.start);
+    }
+    ["\"\"\"", "'''", "\"", "'"].iter().find_map(|quote| {
+        let inner = text.strip_prefix(quote)?.strip_suffix(quote)?;
+        (inner == value).then_some(source.start + quote.len())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate;
+
+    const PACKET: &str = r#"# 云台状态
+packet_name = "GimbalStatus"
+command_id = "0x0104"
+comment = "云台状态"
+
+[[fields]]
+name = "yaw"
+type = "float"
+comment = "偏航角"
+
+[[fields]]
+name = "mode"
+type = "uint8_t"
+bit_field = 4
+comment = '模式'
+
+[[fields]]
+name = "flags"
+type = "uint8_t"
+bit_field = 4
+comment = "标志"
+"#;
+
+    #[test]
+    fn test_generate_from_toml_matches_json() {
+        let json = r#"{"packet_name":"GimbalStatus","command_id":"0x0104","comment":"云台状态",
+            "fields":[{"name":"yaw","type":"float","comment":"偏航角"},
+            {"name":"mode","type":"uint8_t","bit_field":4,"comment":"模式"},
+            {"name":"flags","type":"uint8_t","bit_field":4,"comment":"标志"}]}"#;
+        assert_eq!(
+            generate_from_toml(PACKET, &GenerateOptions::default()).unwrap(),
+            generate(json).unwrap()
+        );
+        assert!(validate_toml(PACKET).is_empty());
+    }
+
+    #[test]
+    fn test_toml_values() {
+        let toml = "a = 0x10\nb = 1.5\nc = true\nd = [1, 'x']\ne = { f = 2 }\ng = 1979-05-27\n\
+                    [h.i]\nj = 3\n";
+        let transcript = toml_to_json(toml).unwrap();
+        let value: serde_json::Value = serde_json::from_str(transcript.json()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "a": 16, "b": 1.5, "c": true, "d": [1, "x"], "e": {"f": 2},
+                "g": "1979-05-27", "h": {"i": {"j": 3}}
+            })
+        );
+    }
+
+    #[test]
+    fn test_toml_diagnostics_point_into_toml() {
+        let toml = PACKET.replace("type = \"float\"", "type = \"flaot\"");
+        let diags = validate_toml(&toml);
+        assert_eq!(diags.len(), 1);
+        assert!(matches!(diags[0].code, ValidationCode::InvalidFieldType(_)));
+        let (start, len) = diags[0].span.unwrap();
+        assert_eq!(&toml[start..start + len], "\"flaot\"");
+
+        // 未知的键指向 TOML 中的键
+        let toml = PACKET.replace("comment = \"偏航角\"", "coment = \"偏航角\"");
+        let diag = validate_toml(&toml)
+            .into_iter()
+            .find(|d| matches!(d.code, ValidationCode::UnknownKey(_)))
+            .unwrap();
+        let (start, len) = diag.span.unwrap();
+        assert_eq!(&toml[start..start + len], "coment");
+
+        // 内联表中的值同样可以定位
+        let toml = "packet_name = \"A\"\ncommand_id = \"0x0101\"\n\
+                    fields = [{ name = \"x\", type = \"uint8_t\", bit_field = 9 }]\n";
+        let diag = validate_toml(toml).remove(0);
+        let (start, len) = diag.span.unwrap();
+        assert_eq!(&toml[start..start + len], "9");
+    }
+
+    #[test]
+    fn test_toml_placeholder_span_inside_string() {
+        let toml = PACKET.replace(
+            "comment = \"云台状态\"\n",
+            "comment = \"云台状态\"\ntraits_template = 'REG(${nmae})'\n",
+        );
+        let diag = validate_toml(&toml).remove(0);
+        let (start, len) = diag.span.unwrap();
+        assert_eq!(&toml[start..start + len], "${nmae}");
+    }
+
+    #[test]
+    fn test_toml_syntax_errors() {
+        let diags = validate_toml("packet_name = \"A\"\npacket_name = \"B\"\n");
+        assert!(matches!(diags[0].code, ValidationCode::InvalidToml(_)));
+        let (start, _) = diags[0].span.unwrap();
+        assert!(start >= "packet_name = \"A\"\n".len());
+
+        let diags = validate_toml("a = [1\n");
+        assert!(matches!(diags[0].code, ValidationCode::InvalidToml(_)));
+
+        let diags = validate_toml("a = nan\n");
+        assert_eq!(
+            diags[0].code,
+            ValidationCode::InvalidToml("不支持 inf 与 nan".to_string())
+        );
+    }
+
+    #[test]
+    fn test_toml_packet_set() {
+        let toml = r#"protocol_version = "1.0.0"
+
+[[packets]]
+packet_name = "A"
+command_id = "0x0101"
+fields = [{ name = "x", type = "uint8_t" }]
+
+[[packets]]
+packet_name = "A"
+command_id = "0x0102"
+fields = [{ name = "y", type = "uint8_t" }]
+"#;
+        let diag = validate_toml(toml)
+            .into_iter()
+            .find(|d| matches!(d.code, ValidationCode::DuplicatePacketName(..)))
+            .unwrap();
+        let (start, len) = diag.span.unwrap();
+        assert_eq!(&toml[start..start + len], "\"A\"");
+        assert!(toml[..start].contains("0x0101"));
+    }
+}
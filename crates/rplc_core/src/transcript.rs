@@ -0,0 +1,191 @@
+//! 非 JSON 格式的协议定义：先转写为等价的 JSON 文本再校验与生成，诊断位置换算回源文件
+
+use std::ops::Range;
+use std::path::Path;
+
+use crate::config::ConfigOrArray;
+use crate::diagnostics::{RplcDiagnostic, Span};
+use crate::toml::toml_to_json;
+use crate::validator::{
+    ValidateOptions, check_input_size, validate_multiple_with_options, validate_with_options,
+};
+use crate::yaml::yaml_to_json;
+
+/// 协议定义文件的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl InputFormat {
+    /// 按扩展名判断：`.yaml` 与 `.yml` 为 YAML，`.toml` 为 TOML，其余按 JSON 处理
+    pub fn from_path(path: &Path) -> InputFormat {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("yaml" | "yml") => InputFormat::Yaml,
+            Some("toml") => InputFormat::Toml,
+            _ => InputFormat::Json,
+        }
+    }
+
+    /// 转写为 JSON；JSON 输入原样保留，语法错误由校验器报告
+    pub fn transcribe(self, input: &str) -> Result<Transcript, Vec<RplcDiagnostic>> {
+        match self {
+            InputFormat::Json => Ok(Transcript {
+                json: input.to_string(),
+                spans: None,
+            }),
+            InputFormat::Yaml => yaml_to_json(input),
+            InputFormat::Toml => toml_to_json(input),
+        }
+    }
+}
+
+/// 源文件中的一个值与其 JSON 写法的对应关系
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SpanMapping {
+    pub json: Range<usize>,
+    pub source: Range<usize>,
+    /// 字符串内容与源文件逐字节相同时，两者内容的起点；诊断指向字符串中的一部分时按相对位置换算
+    pub content: Option<(usize, usize)>,
+}
+
+/// 转写得到的 JSON 文本
+///
+/// YAML 转写得到的 JSON 中每个值的行号与它在源文件中的行号相同，诊断消息中的行号因此无需换算；
+/// TOML 不允许重复的键，不会产生带行号的诊断
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transcript {
+    json: String,
+    /// 为 `None` 时 JSON 就是源文件本身
+    spans: Option<Vec<SpanMapping>>,
+}
+
+impl Transcript {
+    pub(crate) fn new(json: String, spans: Vec<SpanMapping>) -> Self {
+        Transcript {
+            json,
+            spans: Some(spans),
+        }
+    }
+
+    pub fn json(&self) -> &str {
+        &self.json
+    }
+
+    /// 把 JSON 文本中的位置换算为源文件中的位置：取包含该位置的最内层的值
+    pub fn map_span(&self, (start, len): Span) -> Span {
+        let Some(spans) = &self.spans else {
+            return (start, len);
+        };
+        let end = start + len;
+        let Some(mapping) = spans
+            .iter()
+            .filter(|m| m.json.start <= start && end <= m.json.end)
+            .min_by_key(|m| m.json.len())
+        else {
+            return (0, 0);
+        };
+        if let Some((json_content, source_content)) = mapping.content
+            && json_content <= start
+            && end < mapping.json.end
+        {
+            return (source_content + (start - json_content), len);
+        }
+        (mapping.source.start, mapping.source.len())
+    }
+
+    pub fn map_diagnostics(&self, diags: Vec<RplcDiagnostic>) -> Vec<RplcDiagnostic> {
+        diags
+            .into_iter()
+            .map(|mut diag| {
+                diag.span = diag.span.map(|span| self.map_span(span));
+                diag
+            })
+            .collect()
+    }
+}
+
+/// 校验任意格式的定义，顶层为数组或包集合时按多包校验；诊断位置相对于源文件
+pub fn validate_source(
+    input: &str,
+    format: InputFormat,
+    options: &ValidateOptions,
+) -> Vec<RplcDiagnostic> {
+    // 转写本身也要分配内存，先按源文本的大小检查
+    if let Some(diag) = check_input_size(input, options.max_input_size) {
+        return vec![diag];
+    }
+    let transcript = match format.transcribe(input) {
+        Ok(transcript) => transcript,
+        Err(diags) => return diags,
+    };
+    let json = transcript.json();
+    let diags = if ConfigOrArray::is_multi_input(json) {
+        validate_multiple_with_options(json, options)
+    } else {
+        validate_with_options(json, options)
+    };
+    transcript.map_diagnostics(diags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_format_from_path() {
+        assert_eq!(
+            InputFormat::from_path(Path::new("a/b.yaml")),
+            InputFormat::Yaml
+        );
+        assert_eq!(
+            InputFormat::from_path(Path::new("b.YML")),
+            InputFormat::Yaml
+        );
+        assert_eq!(
+            InputFormat::from_path(Path::new("b.toml")),
+            InputFormat::Toml
+        );
+        assert_eq!(
+            InputFormat::from_path(Path::new("b.json")),
+            InputFormat::Json
+        );
+        assert_eq!(InputFormat::from_path(Path::new("b")), InputFormat::Json);
+    }
+
+    #[test]
+    fn test_json_transcript_is_identity() {
+        let transcript = InputFormat::Json.transcribe("{ not json").unwrap();
+        assert_eq!(transcript.json(), "{ not json");
+        assert_eq!(transcript.map_span((3, 4)), (3, 4));
+    }
+
+    #[test]
+    fn test_map_span_prefers_innermost_value() {
+        let transcript = Transcript::new(
+            r#"{"a":"xyz"}"#.to_string(),
+            vec![
+                SpanMapping {
+                    json: 0..11,
+                    source: 0..6,
+                    content: None,
+                },
+                SpanMapping {
+                    json: 5..10,
+                    source: 3..6,
+                    content: Some((6, 3)),
+                },
+            ],
+        );
+        assert_eq!(transcript.map_span((5, 5)), (3, 3));
+        assert_eq!(transcript.map_span((7, 1)), (4, 1));
+        assert_eq!(transcript.map_span((0, 11)), (0, 6));
+        assert_eq!(transcript.map_span((20, 1)), (0, 0));
+    }
+}
@@ -1,9 +1,23 @@
 use json_spanned_value as jsv;
 use regex::Regex;
-use std::collections::HashSet;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::collections::{HashMap, HashSet};
 
-use crate::config::Config;
-use crate::diagnostics::{RplcDiagnostic, Severity, ValidationCode};
+use crate::canonical::canonical_type;
+use crate::compiler::{Compiler, CppStandard};
+use crate::config::{
+    BitOrder, CURRENT_SCHEMA, Config, Field, LEGACY_SCHEMA, ProtocolVersion, TRAITS_PLACEHOLDERS,
+    parse_value_key, template_placeholders,
+};
+use crate::diagnostics::{RplcDiagnostic, Severity, Span, ValidationCode};
+use crate::duplicate_keys::duplicate_key_diagnostics;
+use crate::format::json_syntax_diagnostic;
+use crate::generator::{endian_accessor_field, wants_endian_accessors};
+use crate::python_backend::GENERATED_MEMBERS;
+use crate::suggest::{PACKET_SET_KEYS, attach_type_suggestions, unknown_keys, unknown_packet_keys};
+use crate::target::Target;
 
 /// 解析数组类型，返回 (基础类型, 数组大小)
 /// 例如: "float[3]" -> Some(("float", Some(3)))
@@ -39,13 +53,6 @@ pub fn parse_array_type(ty: &str) -> Option<(&str, Option<u32>)> {
     }
 }
 
-/// 获取数组类型的基础类型
-/// 例如: "float[3]" -> "float"
-///       "uint8_t" -> "uint8_t"
-pub fn get_array_base_type(ty: &str) -> Option<&str> {
-    parse_array_type(ty).map(|(base, _)| base)
-}
-
 const CPP_KEYWORDS: &[&str] = &[
     "alignas",
     "alignof",
@@ -147,1221 +154,5962 @@ const CPP_KEYWORDS: &[&str] = &[
     "xor_eq",
 ];
 
+/// 校验选项，用于调整各项检查的阈值
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct ValidateOptions {
+    /// 单个存储单元内允许的最多位域数量，超过时发出警告
+    pub max_bit_fields_per_unit: usize,
+    /// 与浮点字段共存时允许的最多窄位域数量，超过时发出警告
+    pub max_tiny_bit_fields_with_float: usize,
+    /// 链路允许的最大载荷字节数，为 `None` 时仅受帧长度字段的上限约束
+    pub max_payload_size: Option<u32>,
+    /// 需要支持的目标语言，字段类型必须能在每个目标中表示
+    pub targets: Vec<Target>,
+    /// 目标编译器，用于检查依赖编译器的位域布局
+    pub compiler: Compiler,
+    /// 使用这份协议的所有编译器，取自 rplc.toml 的 `[compilers]`；
+    /// 包含两个及以上的编译器时，建议含原生位域的包改用按掩码读写的访问函数
+    pub compiler_profiles: Vec<Compiler>,
+    /// 生成的 C++ 代码遵循的语言标准，用于检查需要更高标准的选项
+    pub cpp_standard: CppStandard,
+    /// 输入的最大字节数，超过时只报告 `rplc::input::too_large` 而不解析；为 `None` 时不限制
+    pub max_input_size: Option<usize>,
+    /// 字段允许使用的基础类型，等价的写法视为同一类型；为 `None` 时不限制
+    pub allowed_types: Option<Vec<String>>,
+}
+
+impl Default for ValidateOptions {
+    fn default() -> Self {
+        Self {
+            max_bit_fields_per_unit: 8,
+            max_tiny_bit_fields_with_float: 4,
+            max_payload_size: None,
+            targets: vec![Target::Cpp],
+            compiler: Compiler::Gcc,
+            compiler_profiles: Vec::new(),
+            cpp_standard: CppStandard::default(),
+            max_input_size: None,
+            allowed_types: None,
+        }
+    }
+}
+
+/// 未显式写出 signed 的有符号整数类型，其位域的符号由编译器决定
+const PLAIN_SIGNED_TYPES: &[&str] = &["int", "short", "long", "long long"];
+
+/// 不超过该位数的位域视为窄位域
+const TINY_BIT_FIELD_BITS: u8 = 4;
+
+/// 帧头中的载荷长度字段为 uint16_t，单个包的载荷不能超过该值
+const MAX_FRAME_PAYLOAD_SIZE: u32 = u16::MAX as u32;
+
+/// 裁判系统一帧最长 128 字节，去掉 5 字节帧头、2 字节命令码与 2 字节 CRC16 后的数据段上限
+pub(crate) const REFEREE_MAX_PAYLOAD_SIZE: u32 = 119;
+
+/// 设置 `crc` 后，这些名称的字段被视为承载帧尾 CRC16 的校验字段
+pub(crate) const CHECKSUM_FIELD_NAMES: &[&str] = &["crc", "crc16", "checksum"];
+
+/// `align` 允许的最大值
+const MAX_ALIGN: u64 = 4096;
+
 pub fn validate(json_input: &str) -> Vec<RplcDiagnostic> {
-    let mut diags = Vec::new();
-    let identifier_re = Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]*$").unwrap();
+    validate_with_options(json_input, &ValidateOptions::default())
+}
+
+#[tracing::instrument(level = "debug", name = "validate", skip_all)]
+pub fn validate_with_options(json_input: &str, options: &ValidateOptions) -> Vec<RplcDiagnostic> {
+    if let Some(diag) = check_input_size(json_input, options.max_input_size) {
+        return vec![diag];
+    }
+    validate_packet(json_input, options, None)
+}
 
-    let root: jsv::Value = match jsv::from_str(json_input) {
+/// 输入超过 `limit` 字节时返回 `rplc::input::too_large` 错误，调用方应直接报告而不再解析输入
+pub fn check_input_size(input: &str, limit: Option<usize>) -> Option<RplcDiagnostic> {
+    let limit = limit?;
+    (input.len() > limit).then_some(RplcDiagnostic {
+        code: ValidationCode::InputTooLarge(input.len(), limit),
+        severity: Severity::Error,
+        span: None,
+        help: None,
+    })
+}
+
+/// 包对象的键与值
+type Object = jsv::Map<jsv::Spanned<String>, jsv::Spanned<jsv::Value>>;
+
+/// 校验单个包的源文本
+///
+/// `inherited_schema` 为 `Some` 表示该包位于多包文档中：未声明 `rplc_schema` 时使用继承的版本，
+/// 重复的键由调用方对整个文档统一检查
+pub(crate) fn validate_packet(
+    json_input: &str,
+    options: &ValidateOptions,
+    inherited_schema: Option<u32>,
+) -> Vec<RplcDiagnostic> {
+    let root: jsv::Spanned<jsv::Value> = match parse_spanned(json_input) {
         Ok(v) => v,
-        Err(_) => return vec![],
+        Err(error) => return vec![json_syntax_diagnostic(json_input, &error)],
+    };
+    let mut check = PacketCheck {
+        options,
+        inherited_schema,
+        diags: Vec::new(),
     };
+    if inherited_schema.is_none() {
+        check.diags.extend(duplicate_key_diagnostics(json_input));
+    }
+
+    let mut template_diags = Vec::new();
+    // 声明的期望大小与其位置，其余检查都通过后再与布局比较
+    let mut expected_size: Option<(u32, Span)> = None;
+    // 字段名第一次出现的位置，用于报告与生成成员重名的字段
+    let mut name_spans: HashMap<String, Span> = HashMap::new();
+
+    // 多包定义误用单包模式时给出明确提示，而不是后续的字段缺失错误
+    if let Some(count) = multi_packet_count(&root) {
+        check.add(
+            Severity::Error,
+            ValidationCode::MultiPacketInput(count),
+            &root,
+        );
+    } else if let jsv::Value::Object(map) = root.into_inner() {
+        check.diags.extend(unknown_packet_keys(&map));
+        let settings = check.packet_settings(json_input, &map);
+
+        // 非紧凑结构体的自然对齐，用于检查 align
+        let mut natural_align: u32 = 1;
+        if let Some(field_nodes) = map.get("fields") {
+            let state = check.fields(&map, field_nodes, &settings);
+            natural_align = state.natural_align;
+            name_spans = state.name_spans;
+        }
+
+        check.check_align(&map, settings.is_packed, natural_align);
+        expected_size = check.expected_size(&map);
+        template_diags = check.traits_template(json_input, &map);
+    }
 
-    let mut add_diag = |severity: Severity, code, span_node: &jsv::Spanned<jsv::Value>| {
-        let span = span_node.span();
+    let mut diags = check.diags;
+    diags.extend(template_diags);
+    // 定义有错误时布局不确定，只在没有错误时比较期望大小
+    if let Some((expected, span)) = expected_size
+        && !diags.iter().any(|d| d.severity == Severity::Error)
+        && let Ok(config) = serde_json::from_str::<Config>(json_input)
+        && let Some(layout) = crate::layout::compute_layout(&config)
+        && layout.size != expected
+    {
         diags.push(RplcDiagnostic {
+            code: ValidationCode::ExpectedSizeMismatch(config.packet_name, expected, layout.size),
+            severity: Severity::Error,
+            span: Some(span),
+            help: None,
+        });
+    }
+    diags.extend(generated_name_diagnostics(json_input, options, &name_spans));
+    attach_type_suggestions(json_input, &mut diags, options.allowed_types.as_deref());
+    diags
+}
+
+/// 单个包的校验：各项检查按定义中键的顺序进行，诊断依次收集
+struct PacketCheck<'o> {
+    options: &'o ValidateOptions,
+    /// 见 `validate_packet`
+    inherited_schema: Option<u32>,
+    diags: Vec<RplcDiagnostic>,
+}
+
+/// 字段检查依赖的包级别设置
+struct PacketSettings<'a> {
+    schema: u32,
+    is_packed: bool,
+    bit_order: Option<BitOrder>,
+    bit_order_node: Option<&'a jsv::Spanned<jsv::Value>>,
+    serialize: bool,
+    crc_node: Option<&'a jsv::Spanned<jsv::Value>>,
+    crc_enabled: bool,
+    crc_append: bool,
+    /// 具名枚举，字段的 type 可以引用其名称（名称 → 底层类型）
+    enum_types: HashMap<&'a str, &'a str>,
+    /// 字段可以引用的其他包（名称 → 大小与对齐）
+    packet_types: HashMap<String, (u32, u32)>,
+    payload_budget: u32,
+    /// 已停用的字段 ID，不能再分配给字段
+    retired_ids: HashSet<u32>,
+}
+
+/// 展开嵌套结构体后的字段条目
+struct FieldEntries<'a> {
+    entries: Vec<&'a jsv::Spanned<jsv::Value>>,
+    /// 嵌套结构体的起止字段下标，用于检查其边界是否按字节对齐
+    group_boundaries: Vec<(usize, String, &'a jsv::Spanned<jsv::Value>)>,
+    /// 具名嵌套结构体的名称，与字段名的重复在其第一个字段处报告
+    group_names: Vec<(usize, &'a str, &'a jsv::Spanned<jsv::Value>)>,
+}
+
+/// 按顺序检查字段时累积的状态
+#[derive(Default)]
+struct FieldState<'a> {
+    seen_fields: HashSet<String>,
+    /// 字段名第一次出现的位置
+    name_spans: HashMap<String, Span>,
+    /// 有效位域的 (字段名, 字段类型, 类型位数, 位域位数)
+    bit_fields: Vec<(String, String, u8, u8)>,
+    /// 位域所在的字段下标与节点，用于划分存储单元
+    bit_field_positions: Vec<(usize, &'a jsv::Spanned<jsv::Value>)>,
+    has_float: bool,
+    /// long double 字段的类型节点，serialize 无法按字节序读写
+    long_double_nodes: Vec<(String, &'a jsv::Spanned<jsv::Value>)>,
+    /// 引用其他包的字段的类型节点，serialize 与位流布局不支持
+    packet_ref_fields: Vec<(String, &'a jsv::Spanned<jsv::Value>)>,
+    /// 校验字段的 (下标, 名称, 名称节点)，设置 crc 时检查其位置与宽度
+    checksum_fields: Vec<(usize, String, &'a jsv::Spanned<jsv::Value>)>,
+    /// 已累计的载荷位数
+    payload_bits: u64,
+    payload_budget_reported: bool,
+    /// 为 offset 之前的空隙自动生成的保留字段名，字段全部检查完后再检查重名
+    reserved_names: Vec<(String, String, &'a jsv::Spanned<jsv::Value>)>,
+    /// 已出现的 `{"reserved": n}` 条目数，用于得到展开后的字段名
+    reserved_entries: usize,
+    /// 已分配的字段 ID 及其字段名
+    field_ids: HashMap<u32, String>,
+    /// 没有 ID 的字段（名称与节点）
+    fields_without_id: Vec<(String, &'a jsv::Spanned<jsv::Value>)>,
+    /// 非紧凑结构体的自然对齐
+    natural_align: u32,
+}
+
+/// 字段类型的检查结果
+#[derive(Default)]
+struct FieldType<'a> {
+    /// 字段的完整类型，数组保留长度；类型无效时为 `None`
+    ty: Option<&'a str>,
+    is_array: bool,
+    /// 字段占用的位数，大小未知时为 `None`
+    bits: Option<u64>,
+    /// 浮点字段的字节数
+    float_size: Option<u32>,
+    is_packet_ref: bool,
+}
+
+impl PacketCheck<'_> {
+    fn add(&mut self, severity: Severity, code: ValidationCode, node: &jsv::Spanned<jsv::Value>) {
+        let span = node.span();
+        self.diags.push(RplcDiagnostic {
             code,
-            severity, // 使用传入的参数
+            severity,
             span: Some((span.0, span.1 - span.0)),
+            help: None,
         });
-    };
+    }
 
-    if let jsv::Value::Object(map) = root {
-        // Packet name
-        if let Some(name_node) = map.get("packet_name") {
-            if let Some(name) = name_node.as_string() {
-                if !identifier_re.is_match(name) {
-                    add_diag(
-                        Severity::Error,
-                        ValidationCode::InvalidPacketName(name.to_string()),
-                        name_node,
-                    );
-                } else if name
-                    .chars()
-                    .next()
-                    .map(|c| c.is_lowercase())
-                    .unwrap_or(false)
-                {
-                    add_diag(
-                        Severity::Warning,
-                        ValidationCode::NamingConventionPacket(name.to_string()),
-                        name_node,
-                    );
-                }
+    /// 包级别的键，返回字段检查依赖的设置
+    fn packet_settings<'a>(&mut self, json_input: &str, map: &'a Object) -> PacketSettings<'a> {
+        let schema = self.schema(map);
+        self.check_packet_name(map);
+        self.check_command_id(map);
+        if let Some(comment_node) = map.get("comment") {
+            self.check_comment_languages(Some(comment_node));
+            // 检查注释是否为空或只包含空白字符
+            if comment_text(comment_node).is_some_and(|c| c.trim().is_empty()) {
+                self.add(
+                    Severity::Warning,
+                    ValidationCode::EmptyComment("packet".to_string()),
+                    comment_node,
+                );
             }
         }
+        let is_packed = map.get("packed").and_then(|n| n.as_bool()).unwrap_or(true);
+        let bit_order_node = map.get("bitfield_layout").filter(|node| !node.is_null());
+        let bit_order = bit_order_node.and_then(|node| self.bit_order(node));
+        if let Some(node) = map.get("endianness").filter(|node| !node.is_null()) {
+            self.check_endianness(node);
+        }
+        let serialize = self.serialize(map);
+        let crc_node = map.get("crc").filter(|node| !node.is_null());
+        let crc_enabled = crc_node.is_some_and(|node| self.crc(node));
+        let crc_append = map
+            .get("crc_append")
+            .and_then(|n| n.as_bool())
+            .unwrap_or(true);
+        let enum_types = match map.get("enums") {
+            Some(node) => self.enums(node),
+            None => HashMap::new(),
+        };
+        // 由 rplc 拆分多包文档时填入 dependencies；多包文档中的包尚未拆分，引用是否存在由跨包检查确认
+        let packet_types = map
+            .get("dependencies")
+            .map(|node| {
+                let (start, end) = node.span();
+                dependency_layouts(&json_input[start..end])
+            })
+            .unwrap_or_default();
+        let payload_budget = self
+            .options
+            .max_payload_size
+            .map_or(MAX_FRAME_PAYLOAD_SIZE, |size| {
+                size.min(MAX_FRAME_PAYLOAD_SIZE)
+            });
+        if let Some(includes_node) = map.get("includes") {
+            self.check_includes(includes_node);
+        }
+        let retired_ids = match map.get("retired_field_ids") {
+            Some(node) => self.retired_field_ids(node),
+            None => HashSet::new(),
+        };
 
-        // Command ID
-        if let Some(id_node) = map.get("command_id") {
-            if let Some(id_str) = id_node.as_string() {
-                if crate::validator::parse_command_id(id_str).is_err() {
-                    add_diag(
-                        Severity::Error,
-                        ValidationCode::InvalidCommandId(id_str.to_string()),
-                        id_node,
-                    );
-                }
-            }
+        PacketSettings {
+            schema,
+            is_packed,
+            bit_order,
+            bit_order_node,
+            serialize,
+            crc_node,
+            crc_enabled,
+            crc_append,
+            enum_types,
+            packet_types,
+            payload_budget,
+            retired_ids,
         }
+    }
 
-        // Comment
-        if let Some(comment_node) = map.get("comment") {
-            if let Some(comment) = comment_node.as_string() {
-                // 检查注释是否为空或只包含空白字符
-                if comment.trim().is_empty() {
-                    add_diag(
-                        Severity::Warning,
-                        ValidationCode::EmptyComment("packet".to_string()),
-                        comment_node,
-                    );
-                }
+    /// 格式版本，决定是否还接受旧格式中的兼容写法
+    fn schema(&mut self, map: &Object) -> u32 {
+        let schema_node = map.get("rplc_schema");
+        let default_schema = self.inherited_schema.unwrap_or(LEGACY_SCHEMA);
+        schema_version(schema_node, default_schema).unwrap_or_else(|text| {
+            if let Some(node) = schema_node {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::UnsupportedSchemaVersion(text, CURRENT_SCHEMA),
+                    node,
+                );
             }
+            CURRENT_SCHEMA
+        })
+    }
+
+    /// 包名必须是标识符，建议以大写字母开头
+    fn check_packet_name(&mut self, map: &Object) {
+        let Some((name_node, name)) = map
+            .get("packet_name")
+            .and_then(|node| Some((node, node.as_string()?)))
+        else {
+            return;
+        };
+        if !is_identifier(name) {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidPacketName(name.to_string()),
+                name_node,
+            );
+        } else if name.starts_with(char::is_lowercase) {
+            self.add(
+                Severity::Warning,
+                ValidationCode::NamingConventionPacket(name.to_string()),
+                name_node,
+            );
         }
+    }
 
-        // Packed
-        let is_packed = map.get("packed").and_then(|n| n.as_bool()).unwrap_or(true);
+    fn check_command_id(&mut self, map: &Object) {
+        if let Some(id_node) = map.get("command_id")
+            && let Some(id_str) = id_node.as_string()
+            && parse_command_id(id_str).is_err()
+        {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidCommandId(id_str.to_string()),
+                id_node,
+            );
+        }
+    }
 
-        // Fields
-        if let Some(field_nodes) = map.get("fields") {
-            let fields = field_nodes.as_array().unwrap();
-            let mut seen_fields = HashSet::new();
-
-            // 存储位域信息用于后续检查
-            let mut bit_field_info: Vec<(String, String, u8, u8)> = Vec::new(); // (field_name, field_type, type_bits, bit_field_bits)
-
-            for field_node in fields {
-                let mut field_name: String = "".to_string();
-
-                if let Some(field_map) = field_node.as_object() {
-                    if let Some(name_node) = field_map.get("name") {
-                        if let Some(name) = name_node.as_string() {
-                            // Format
-                            if !identifier_re.is_match(name) {
-                                add_diag(
-                                    Severity::Error,
-                                    ValidationCode::InvalidFieldName(name.to_string()),
-                                    name_node,
-                                );
-                            }
-
-                            // Keyword
-                            if is_cpp_keyword(name) {
-                                add_diag(
-                                    Severity::Error,
-                                    ValidationCode::KeywordCollision(name.to_string()),
-                                    name_node,
-                                );
-                            }
-
-                            // Repeat
-                            if !seen_fields.insert(name.to_string()) {
-                                add_diag(
-                                    Severity::Error,
-                                    ValidationCode::DuplicateFieldName(name.to_string()),
-                                    name_node,
-                                );
-                            }
-                            field_name = name.to_string();
-                        }
-                    }
-                    // Type
-                    let mut ty: Option<&str> = None;
-                    let mut is_array_type = false;
-                    if let Some(ty_node) = field_map.get("type") {
-                        if let Some(ty_str) = ty_node.as_string() {
-                            // 解析数组类型
-                            if let Some((base_type, arr_size)) = parse_array_type(ty_str) {
-                                // 验证基础类型是否有效
-                                let base_type_valid = c_type_to_bit_field_size(base_type).is_some()
-                                    || matches!(base_type, "float" | "double" | "long double");
-
-                                if !base_type_valid {
-                                    add_diag(
-                                        Severity::Error,
-                                        ValidationCode::InvalidFieldType(field_name.clone()),
-                                        ty_node,
-                                    );
-                                }
-
-                                ty = Some(ty_str);
-                                if arr_size.is_some() {
-                                    is_array_type = true;
-                                }
-                            } else {
-                                // 数组格式无效
-                                add_diag(
-                                    Severity::Error,
-                                    ValidationCode::InvalidArrayType(field_name.clone()),
-                                    ty_node,
-                                );
-                            }
-                        } else {
-                            add_diag(
-                                Severity::Error,
-                                ValidationCode::InvalidFieldType(field_name.clone()),
-                                ty_node,
-                            )
-                        }
-                    } else {
-                        add_diag(
-                            Severity::Error,
-                            ValidationCode::InvalidFieldType(field_name.clone()),
-                            field_node,
-                        )
-                    }
+    /// 多语言注释中无效的语言标签或非字符串的文本
+    fn check_comment_languages(&mut self, comment_node: Option<&jsv::Spanned<jsv::Value>>) {
+        for (language, node) in comment_node.into_iter().flat_map(invalid_comment_entries) {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidLocalizedComment(language),
+                node,
+            );
+        }
+    }
 
-                    // Bit-Field - 数组类型不允许使用位域
-                    let has_bit_field = if let Some(bit_field_node) = field_map.get("bit_field") {
-                        // Check if the bit_field value is explicitly null (meaning no bit field)
-                        // 这个检查必须在最前面，因为 serde 重新序列化时会将 None 转为 null
-                        if bit_field_node.is_null() {
-                            false // No bit field
-                        }
-                        // 检查是否为数组类型
-                        else if is_array_type {
-                            add_diag(
-                                Severity::Error,
-                                ValidationCode::BitFieldOnArray(field_name.clone()),
-                                bit_field_node,
-                            );
-                            false
-                        } else if let Some(bit_field_num) = bit_field_node.as_number() {
-                            // 检查位域值是否为整数
-                            if !bit_field_num.is_i64() {
-                                add_diag(
-                                    Severity::Error,
-                                    ValidationCode::InvalidBitField(field_name.clone()),
-                                    bit_field_node,
-                                );
-                                false
-                            } else if let Some(bit_field_value) = bit_field_num.as_i64() {
-                                // 检查位域值是否为正数
-                                if bit_field_value <= 0 {
-                                    add_diag(
-                                        Severity::Error,
-                                        ValidationCode::InvalidBitField(field_name.clone()),
-                                        bit_field_node,
-                                    );
-                                    false
-                                } else {
-                                    // 检查类型是否支持位域
-                                    if let Some(field_type) = ty {
-                                        // 对于数组类型，使用基础类型检查位域
-                                        let type_to_check = if is_array_type {
-                                            get_array_base_type(field_type).unwrap_or(field_type)
-                                        } else {
-                                            field_type
-                                        };
-
-                                        let type_size = c_type_to_bit_field_size(type_to_check);
-                                        if type_size.is_none() {
-                                            add_diag(
-                                                Severity::Error,
-                                                ValidationCode::BitFieldOnInvalidType(
-                                                    field_name.clone(),
-                                                    field_type.to_string(),
-                                                ),
-                                                bit_field_node,
-                                            );
-                                            false
-                                        } else {
-                                            // 检查位域长度是否超过类型本身的大小
-                                            let type_bits = type_size.unwrap() * 8;
-                                            let bit_field_value_u8 = bit_field_value as u8;
-                                            if bit_field_value_u8 > type_bits {
-                                                add_diag(
-                                                    Severity::Error,
-                                                    ValidationCode::BitFieldLengthOverflow(
-                                                        field_name.clone(),
-                                                        bit_field_value_u8,
-                                                        type_bits,
-                                                    ),
-                                                    bit_field_node,
-                                                );
-                                                false
-                                            } else {
-                                                // 记录位域信息用于后续检查
-                                                bit_field_info.push((
-                                                    field_name.clone(),
-                                                    field_type.to_string(),
-                                                    type_bits,
-                                                    bit_field_value_u8,
-                                                ));
-                                                true // 有效的位域
-                                            }
-                                        }
-                                    } else {
-                                        add_diag(
-                                            Severity::Error,
-                                            ValidationCode::InvalidFieldType(field_name.clone()),
-                                            field_node,
-                                        );
-                                        false
-                                    }
-                                }
-                            } else {
-                                add_diag(
-                                    Severity::Error,
-                                    ValidationCode::InvalidBitField(field_name.clone()),
-                                    bit_field_node,
-                                );
-                                false
-                            }
-                        } else {
-                            add_diag(
-                                Severity::Error,
-                                ValidationCode::InvalidBitField(field_name.clone()),
-                                bit_field_node,
-                            );
-                            false
-                        }
-                    } else {
-                        false
-                    };
+    /// 位序，只接受 `lsb_first` 与 `msb_first`
+    fn bit_order(&mut self, node: &jsv::Spanned<jsv::Value>) -> Option<BitOrder> {
+        let order = match node.as_string() {
+            Some("lsb_first") => Some(BitOrder::LsbFirst),
+            Some("msb_first") => Some(BitOrder::MsbFirst),
+            _ => None,
+        };
+        if order.is_none() {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidBitFieldLayout(string_text(node)),
+                node,
+            );
+        }
+        order
+    }
 
-                    if has_bit_field && !is_packed {
-                        add_diag(
-                            Severity::Warning,
-                            ValidationCode::BitFieldMissingPackedAttr(field_name.clone()),
-                            field_node,
-                        );
-                    }
+    /// 包或字段的字节序，只接受 `little` 与 `big`；返回是否有效
+    fn check_endianness(&mut self, node: &jsv::Spanned<jsv::Value>) -> bool {
+        let valid = matches!(node.as_string(), Some("little" | "big"));
+        if !valid {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidEndianness(string_text(node)),
+                node,
+            );
+        }
+        valid
+    }
 
-                    // Comment
-                    let has_comment = field_map
-                        .get("comment")
-                        .and_then(|c| c.as_string())
-                        .map(|s| !s.trim().is_empty())
-                        .unwrap_or(false);
-
-                    if !has_comment {
-                        let target_node = field_map.get("name").unwrap_or(field_node);
-                        let field_name = field_map
-                            .get("name")
-                            .and_then(|n| n.as_string())
-                            .unwrap_or("unknown");
-                        add_diag(
-                            Severity::Warning,
-                            ValidationCode::MissingComment(field_name.to_string()),
-                            target_node,
-                        );
-                    }
-                }
+    /// 是否生成 serialize；deserialize 返回 std::optional，需要 C++17
+    fn serialize(&mut self, map: &Object) -> bool {
+        let serialize = map
+            .get("serialize")
+            .and_then(|n| n.as_bool())
+            .unwrap_or(false);
+        if serialize
+            && self.options.cpp_standard < CppStandard::Cpp17
+            && self.options.targets.contains(&Target::Cpp)
+            && let Some(node) = map.get("serialize")
+        {
+            let code = ValidationCode::CppStandardTooOld(
+                "serialize".to_string(),
+                CppStandard::Cpp17.to_string(),
+                self.options.cpp_standard.to_string(),
+            );
+            self.add(Severity::Error, code, node);
+        }
+        serialize
+    }
+
+    /// 帧校验，目前只支持 `dji`；返回是否有效
+    fn crc(&mut self, node: &jsv::Spanned<jsv::Value>) -> bool {
+        let valid = node.as_string() == Some("dji");
+        if !valid {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidCrc(string_text(node)),
+                node,
+            );
+        }
+        valid
+    }
+
+    /// 具名枚举，返回可供字段引用的枚举（名称 → 底层类型）
+    fn enums<'a>(&mut self, node: &'a jsv::Spanned<jsv::Value>) -> HashMap<&'a str, &'a str> {
+        let (enum_types, enum_diags) = check_enums(node);
+        for (severity, code, node) in enum_diags {
+            self.add(severity, code, node);
+        }
+        enum_types
+    }
+
+    /// 替代 <cstdint> 的头文件
+    fn check_includes(&mut self, includes_node: &jsv::Spanned<jsv::Value>) {
+        let Some(items) = includes_node.as_array() else {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidInclude(
+                    align_node_text(includes_node),
+                    "必须是字符串数组".to_string(),
+                ),
+                includes_node,
+            );
+            return;
+        };
+        for item in items {
+            let reason = match item.as_string() {
+                Some(include) => include_error(include),
+                None => Some("必须是字符串"),
+            };
+            if let Some(reason) = reason {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::InvalidInclude(align_node_text(item), reason.to_string()),
+                    item,
+                );
             }
+        }
+    }
 
-            // 检查跨存储单元边界的位域
-            if !is_packed && bit_field_info.len() > 1 {
-                for i in 1..bit_field_info.len() {
-                    let (prev_field_name, _prev_field_type, _prev_type_bits, prev_bit_field_bits) =
-                        &bit_field_info[i - 1];
-                    let (field_name, _field_type, type_bits, bit_field_bits) = &bit_field_info[i];
-
-                    // 如果前一个位域和当前位域的总和超过类型位数，则存在跨边界问题
-                    if prev_bit_field_bits + bit_field_bits > *type_bits {
-                        add_diag(
-                            Severity::Error,
-                            ValidationCode::BitFieldStraddleBoundaryWithoutPacked(
-                                prev_field_name.clone(),
-                                field_name.clone(),
-                                *prev_bit_field_bits,
-                                *bit_field_bits,
-                                *type_bits,
-                            ),
-                            field_nodes, // 使用整个fields数组作为节点
-                        );
-                    }
+    fn retired_field_ids(&mut self, retired_node: &jsv::Spanned<jsv::Value>) -> HashSet<u32> {
+        let mut retired_ids = HashSet::new();
+        let Some(items) = retired_node.as_array() else {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidFieldId(align_node_text(retired_node)),
+                retired_node,
+            );
+            return retired_ids;
+        };
+        for item in items {
+            match field_id(item) {
+                Some(id) => {
+                    retired_ids.insert(id);
                 }
+                None => self.add(
+                    Severity::Error,
+                    ValidationCode::InvalidFieldId(align_node_text(item)),
+                    item,
+                ),
             }
+        }
+        retired_ids
+    }
 
-            // 检查单个位域是否跨越边界
-            for (field_name, _field_type, type_bits, bit_field_bits) in &bit_field_info {
-                if *bit_field_bits == *type_bits && !is_packed {
-                    add_diag(
-                        Severity::Warning,
-                        ValidationCode::BitFieldStraddleBoundary(field_name.clone()),
-                        field_nodes, // 使用整个fields数组作为节点
+    /// 字段列表：逐个检查字段并累积布局，之后进行依赖整个列表的检查
+    fn fields<'a>(
+        &mut self,
+        map: &'a Object,
+        field_nodes: &'a jsv::Spanned<jsv::Value>,
+        settings: &PacketSettings<'a>,
+    ) -> FieldState<'a> {
+        let fields = match field_nodes.as_array() {
+            Some(fields) => fields.as_slice(),
+            None => {
+                self.add(Severity::Error, ValidationCode::InvalidFields, field_nodes);
+                &[]
+            }
+        };
+        let layout = self.field_entries(fields, settings.is_packed);
+        let mut state = FieldState {
+            natural_align: 1,
+            ..Default::default()
+        };
+
+        let field_count = layout.entries.len();
+        for (index, field_node) in layout.entries.iter().copied().enumerate() {
+            for (_, name, name_node) in layout
+                .group_names
+                .iter()
+                .filter(|(start, _, _)| *start == index)
+            {
+                if !state.seen_fields.insert(name.to_string()) {
+                    self.add(
+                        Severity::Error,
+                        ValidationCode::DuplicateFieldName(name.to_string()),
+                        name_node,
                     );
                 }
             }
+            self.check_group_boundaries(&layout.group_boundaries, index, state.payload_bits);
+
+            let Some(field_map) = field_node.as_object() else {
+                continue;
+            };
+            match field_map.get("reserved") {
+                Some(reserved_node) => {
+                    self.reserved_entry(&mut state, settings, field_map, field_node, reserved_node)
+                }
+                None => self.field(&mut state, settings, index, field_map, field_node),
+            }
+        }
+        self.check_group_boundaries(&layout.group_boundaries, field_count, state.payload_bits);
+
+        // 只要有一个字段声明了 ID，包的数据就按 ID 记录，其余字段同样需要 ID
+        if !state.field_ids.is_empty() {
+            for (name, node) in &state.fields_without_id {
+                self.add(
+                    Severity::Warning,
+                    ValidationCode::MissingFieldId(name.clone()),
+                    node,
+                );
+            }
+        }
+
+        for (reserved, field_name, offset_node) in &state.reserved_names {
+            if state.seen_fields.contains(reserved) {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::InvalidFieldOffset(
+                        field_name.clone(),
+                        format!("自动填充的保留字段 '{}' 与已有的名称重复", reserved),
+                    ),
+                    offset_node,
+                );
+            }
+        }
+
+        self.check_bit_field_boundaries(field_nodes, settings, &state);
+        self.check_compiler_bit_fields(map, settings, &state);
+        self.check_bit_field_units(field_nodes, &state);
+        if settings.crc_enabled {
+            self.check_checksum_fields(map, settings, &state, field_count);
         }
+        self.check_scalar_access(settings, &state);
+        state
     }
 
-    diags
-}
+    /// 展开嵌套结构体并检查分组条目；分组条目只影响生成的注释与文档，不参与字段检查与布局
+    fn field_entries<'a>(
+        &mut self,
+        fields: &'a [jsv::Spanned<jsv::Value>],
+        is_packed: bool,
+    ) -> FieldEntries<'a> {
+        let mut layout = FieldEntries {
+            entries: Vec::with_capacity(fields.len()),
+            group_boundaries: Vec::new(),
+            group_names: Vec::new(),
+        };
+        let mut pending_section: Option<(String, &jsv::Spanned<jsv::Value>)> = None;
+        for node in fields {
+            if let Some(group_map) = node.as_object()
+                && let Some(group_node) = group_map.get("group")
+            {
+                pending_section = None;
+                self.group(&mut layout, is_packed, node, group_map, group_node);
+                continue;
+            }
 
-// New functionality to support validating multiple packets
-pub fn validate_multiple(json_input: &str) -> Vec<RplcDiagnostic> {
-    // Try to parse as a single config first (for backward compatibility)
-    if let Ok(_) = serde_json::from_str::<Config>(json_input) {
-        // If it's a single config, validate it normally
-        return validate(json_input);
+            let Some(section_node) = node.as_object().and_then(|m| m.get("section")) else {
+                pending_section = None;
+                layout.entries.push(node);
+                continue;
+            };
+            if let Some((title, node)) = pending_section.take() {
+                self.add(Severity::Warning, ValidationCode::EmptySection(title), node);
+            }
+            pending_section = self.section(node, section_node);
+        }
+        if let Some((title, node)) = pending_section {
+            self.add(Severity::Warning, ValidationCode::EmptySection(title), node);
+        }
+        layout
     }
 
-    // If single config parsing fails, try to parse as an array of configs
-    if let Ok(configs) = serde_json::from_str::<Vec<Config>>(json_input) {
-        let mut all_diags = Vec::new();
+    /// 嵌套结构体：检查名称与成员，成员展开到字段条目中
+    fn group<'a>(
+        &mut self,
+        layout: &mut FieldEntries<'a>,
+        is_packed: bool,
+        node: &'a jsv::Spanned<jsv::Value>,
+        group_map: &'a Object,
+        group_node: &'a jsv::Spanned<jsv::Value>,
+    ) {
+        let name_node = group_map.get("name");
+        let label = name_node
+            .and_then(|n| n.as_string())
+            .unwrap_or("匿名")
+            .to_string();
+        self.check_comment_languages(group_map.get("comment"));
+        let invalid =
+            |reason: &str| ValidationCode::InvalidGroup(label.clone(), reason.to_string());
+        if ["type", "bit_field", "section"]
+            .iter()
+            .any(|key| group_map.contains_key(*key))
+        {
+            self.add(
+                Severity::Error,
+                invalid("不能包含 type、bit_field 或 section"),
+                node,
+            );
+        }
+        if !is_packed {
+            self.add(Severity::Error, invalid("只能用于紧凑结构体"), node);
+        }
+        let members = group_node.as_array().map(|m| m.as_slice()).unwrap_or(&[]);
+        if members.is_empty() {
+            self.add(
+                Severity::Error,
+                invalid("group 必须是非空的字段数组"),
+                group_node,
+            );
+        }
+        if let Some(name_node) = name_node {
+            match name_node.as_string() {
+                Some(name) if !is_identifier(name) => self.add(
+                    Severity::Error,
+                    ValidationCode::InvalidFieldName(name.to_string()),
+                    name_node,
+                ),
+                Some(name) if is_cpp_keyword(name) => self.add(
+                    Severity::Error,
+                    ValidationCode::KeywordCollision(name.to_string()),
+                    name_node,
+                ),
+                Some(name) => layout
+                    .group_names
+                    .push((layout.entries.len(), name, name_node)),
+                None => self.add(
+                    Severity::Error,
+                    ValidationCode::InvalidFieldName(align_node_text(name_node)),
+                    name_node,
+                ),
+            }
+        }
+
+        layout
+            .group_boundaries
+            .push((layout.entries.len(), label.clone(), node));
+        for member in members {
+            let nested = member
+                .as_object()
+                .is_some_and(|m| m.contains_key("group") || m.contains_key("section"));
+            if nested {
+                self.add(
+                    Severity::Error,
+                    invalid("不能再包含嵌套结构体或分组条目"),
+                    member,
+                );
+            } else {
+                layout.entries.push(member);
+            }
+        }
+        layout
+            .group_boundaries
+            .push((layout.entries.len(), label, node));
+    }
 
-        for config in configs {
-            // Create JSON for each individual config to validate
-            let config_json = serde_json::to_string(&config).unwrap_or_default();
-            let diags = validate(&config_json);
-            all_diags.extend(diags);
+    /// 分组标题；有效时返回标题，之后没有字段时发出警告
+    fn section<'a>(
+        &mut self,
+        node: &'a jsv::Spanned<jsv::Value>,
+        section_node: &'a jsv::Spanned<jsv::Value>,
+    ) -> Option<(String, &'a jsv::Spanned<jsv::Value>)> {
+        let extra_keys = node.as_object().is_some_and(|m| m.len() > 1);
+        match section_node.as_string().map(str::trim) {
+            Some(title) if !title.is_empty() && !extra_keys => {
+                return Some((title.to_string(), node));
+            }
+            Some(_) if extra_keys => self.add(
+                Severity::Error,
+                ValidationCode::InvalidSection("分组条目只能包含 section".to_string()),
+                node,
+            ),
+            _ => self.add(
+                Severity::Error,
+                ValidationCode::InvalidSection("分组标题必须是非空字符串".to_string()),
+                section_node,
+            ),
         }
+        None
+    }
 
-        return all_diags;
+    /// 嵌套结构体在第 `index` 个字段处开始或结束时，之前的位域必须填满整字节
+    fn check_group_boundaries(
+        &mut self,
+        boundaries: &[(usize, String, &jsv::Spanned<jsv::Value>)],
+        index: usize,
+        payload_bits: u64,
+    ) {
+        for (_, label, group_node) in boundaries.iter().filter(|(at, _, _)| *at == index) {
+            if !payload_bits.is_multiple_of(8) {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::GroupBitFieldBoundary(label.clone(), payload_bits),
+                    group_node,
+                );
+            }
+        }
     }
 
-    // If both attempts fail, return an empty diagnostics vector
-    // (since the input is neither a single config nor an array of configs)
-    vec![]
-}
+    /// 保留条目：匿名的字节数组，不需要注释
+    fn reserved_entry<'a>(
+        &mut self,
+        state: &mut FieldState<'a>,
+        settings: &PacketSettings<'a>,
+        field_map: &'a Object,
+        field_node: &'a jsv::Spanned<jsv::Value>,
+        reserved_node: &'a jsv::Spanned<jsv::Value>,
+    ) {
+        let name = format!("_reserved{}", state.reserved_entries);
+        state.reserved_entries += 1;
+        let invalid = |reason: &str| ValidationCode::InvalidReserved(reason.to_string());
+        if let Some(bit_field_node) = field_map.get("bit_field") {
+            self.add(
+                Severity::Error,
+                invalid("保留条目不能使用位域"),
+                bit_field_node,
+            );
+        }
+        if field_map
+            .keys()
+            .any(|key| !matches!(key.as_str(), "reserved" | "comment" | "bit_field"))
+        {
+            self.add(
+                Severity::Error,
+                invalid("保留条目只能包含 reserved 与 comment"),
+                field_node,
+            );
+        }
+        if !state.seen_fields.insert(name.clone()) {
+            let reason = format!("展开得到的字段 '{}' 与已有的名称重复", name);
+            self.add(Severity::Error, invalid(&reason), field_node);
+        }
+        self.check_comment_languages(field_map.get("comment"));
 
-pub fn parse_command_id(id: &str) -> Result<u16, ()> {
-    let clean = id.trim();
-    if clean.to_lowercase().starts_with("0x") {
-        u16::from_str_radix(&clean[2..], 16).map_err(|_| ())
-    } else {
-        clean.parse::<u16>().map_err(|_| ())
+        let bytes = reserved_node
+            .as_number()
+            .and_then(|n| n.as_u64())
+            .filter(|bytes| (1..=u64::from(u32::MAX)).contains(bytes));
+        let Some(bytes) = bytes else {
+            self.add(
+                Severity::Error,
+                invalid("reserved 必须是正整数"),
+                reserved_node,
+            );
+            return;
+        };
+        state.payload_bits = state.payload_bits.saturating_add(bytes * 8);
+        self.check_payload_budget(state, settings.payload_budget, name, reserved_node);
     }
-}
 
-pub fn is_cpp_keyword(name: &str) -> bool {
-    CPP_KEYWORDS.contains(&name)
-}
+    /// 累计的载荷超出上限时报告一次，指向第一个越界的数组字段或保留条目
+    fn check_payload_budget(
+        &mut self,
+        state: &mut FieldState,
+        payload_budget: u32,
+        name: String,
+        node: &jsv::Spanned<jsv::Value>,
+    ) {
+        let payload_size = state.payload_bits.div_ceil(8);
+        if !state.payload_budget_reported && payload_size > u64::from(payload_budget) {
+            self.add(
+                Severity::Error,
+                ValidationCode::ArrayExceedsPayloadBudget(name, payload_size, payload_budget),
+                node,
+            );
+            state.payload_budget_reported = true;
+        }
+    }
 
-pub fn c_type_to_bit_field_size(ty: &str) -> Option<u8> {
-    match ty {
-        "unsigned int" | "signed int" | "int" => Some(4),
-        "_Bool" | "bool" => Some(1),
+    /// 普通字段，按键依次检查并累积载荷位数
+    fn field<'a>(
+        &mut self,
+        state: &mut FieldState<'a>,
+        settings: &PacketSettings<'a>,
+        index: usize,
+        field_map: &'a Object,
+        field_node: &'a jsv::Spanned<jsv::Value>,
+    ) {
+        let field_name = self.field_name(state, settings, index, field_map);
+        self.check_stable_id(state, settings, &field_name, field_map, field_node);
+        let field_type = self.field_type(state, settings, &field_name, field_map, field_node);
+        let bit_field = field_map
+            .get("bit_field")
+            .and_then(|node| self.bit_field(settings, &field_name, &field_type, node, field_node))
+            .map(|(ty, type_bits, bits)| {
+                state
+                    .bit_fields
+                    .push((field_name.clone(), ty.to_string(), type_bits, bits));
+                state.bit_field_positions.push((index, field_node));
+                bits
+            });
+        let has_bit_field = bit_field.is_some();
 
-        "unsigned char" | "signed char" | "char" => Some(1),
-        "unsigned short" | "signed short" | "short" => Some(2),
-        "unsigned long" | "signed long" | "long" => Some(8),
-        "unsigned long long" | "signed long long" | "long long" => Some(8),
+        if has_bit_field && !settings.is_packed {
+            self.add(
+                Severity::Warning,
+                ValidationCode::BitFieldMissingPackedAttr(field_name.clone()),
+                field_node,
+            );
+        }
+        if let Some(endian_node) = field_map.get("endianness").filter(|n| !n.is_null()) {
+            self.check_field_endianness(
+                settings,
+                &field_name,
+                &field_type,
+                has_bit_field,
+                endian_node,
+            );
+        }
+        if let Some(offset_node) = field_map.get("offset").filter(|n| !n.is_null()) {
+            self.field_offset(state, settings, &field_name, has_bit_field, offset_node);
+        }
 
-        "uint8_t" | "int8_t" => Some(1),
-        "uint16_t" | "int16_t" => Some(2),
-        "uint32_t" | "int32_t" => Some(4),
-        "uint64_t" | "int64_t" => Some(8),
+        // 紧凑结构体中未对齐的浮点字段
+        if settings.is_packed
+            && let Some(size) = field_type.float_size
+            && !state.payload_bits.is_multiple_of(u64::from(size) * 8)
+            && let Some(ty_node) = field_map.get("type")
+        {
+            self.add(
+                Severity::Warning,
+                ValidationCode::UnalignedFloatInPacked(
+                    field_name.clone(),
+                    state.payload_bits,
+                    size,
+                ),
+                ty_node,
+            );
+        }
 
-        "float" | "double" | "long double" => None,
-        "void*" | "char*" | "int*" => None,
-        "struct" | "union" => None,
+        let field_bits = bit_field.map(u64::from).or(field_type.bits);
+        state.payload_bits = state.payload_bits.saturating_add(field_bits.unwrap_or(0));
+        if field_type.is_array
+            && let Some(ty_node) = field_map.get("type")
+        {
+            self.check_payload_budget(state, settings.payload_budget, field_name.clone(), ty_node);
+        }
 
-        _ => None,
+        self.check_field_annotations(settings, &field_name, field_type.ty, bit_field, field_map);
+        self.check_field_comment(field_map, field_node);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::diagnostics::Severity;
+    /// 字段名的格式、关键字与重复；返回字段名，没有名称时为空字符串
+    fn field_name<'a>(
+        &mut self,
+        state: &mut FieldState<'a>,
+        settings: &PacketSettings<'a>,
+        index: usize,
+        field_map: &'a Object,
+    ) -> String {
+        let Some((name_node, name)) = field_map
+            .get("name")
+            .and_then(|node| Some((node, node.as_string()?)))
+        else {
+            return String::new();
+        };
+        if !is_identifier(name) {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidFieldName(name.to_string()),
+                name_node,
+            );
+        }
+        if is_cpp_keyword(name) {
+            self.add(
+                Severity::Error,
+                ValidationCode::KeywordCollision(name.to_string()),
+                name_node,
+            );
+        }
+        if !state.seen_fields.insert(name.to_string()) {
+            self.add(
+                Severity::Error,
+                ValidationCode::DuplicateFieldName(name.to_string()),
+                name_node,
+            );
+        }
+        let span = name_node.span();
+        state
+            .name_spans
+            .entry(name.to_string())
+            .or_insert((span.0, span.1 - span.0));
 
-    #[test]
-    fn test_parse_command_id_hex_valid() {
-        assert_eq!(parse_command_id("0x0104"), Ok(260)); // 0x0104 = 260 decimal
-        assert_eq!(parse_command_id("0xABCD"), Ok(43981)); // 0xABCD = 43981 decimal
-        assert_eq!(parse_command_id("0xffff"), Ok(65535)); // Maximum 16-bit value
-        assert_eq!(parse_command_id("0x0"), Ok(0)); // Minimum hex value
+        if settings.crc_enabled && CHECKSUM_FIELD_NAMES.contains(&name) {
+            state
+                .checksum_fields
+                .push((index, name.to_string(), name_node));
+            if let Some(ty_node) = field_map.get("type")
+                && let Some(ty) = ty_node.as_string()
+                && canonical_type(ty) != "uint16_t"
+            {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::ChecksumFieldWidth(name.to_string(), ty.to_string()),
+                    ty_node,
+                );
+            }
+        }
+        name.to_string()
     }
 
-    #[test]
-    fn test_parse_command_id_decimal_valid() {
-        assert_eq!(parse_command_id("260"), Ok(260));
-        assert_eq!(parse_command_id("65535"), Ok(65535)); // Maximum 16-bit value
-        assert_eq!(parse_command_id("0"), Ok(0)); // Minimum decimal value
+    /// 稳定字段 ID：不能重复，也不能使用已停用的 ID
+    fn check_stable_id<'a>(
+        &mut self,
+        state: &mut FieldState<'a>,
+        settings: &PacketSettings<'a>,
+        field_name: &str,
+        field_map: &'a Object,
+        field_node: &'a jsv::Spanned<jsv::Value>,
+    ) {
+        let Some(id_node) = field_map.get("id").filter(|node| !node.is_null()) else {
+            state
+                .fields_without_id
+                .push((field_name.to_string(), field_node));
+            return;
+        };
+        match field_id(id_node) {
+            Some(id) if settings.retired_ids.contains(&id) => self.add(
+                Severity::Error,
+                ValidationCode::RetiredFieldIdReused(field_name.to_string(), id),
+                id_node,
+            ),
+            Some(id) => match state.field_ids.get(&id) {
+                Some(other) => self.add(
+                    Severity::Error,
+                    ValidationCode::DuplicateFieldId(field_name.to_string(), id, other.clone()),
+                    id_node,
+                ),
+                None => {
+                    state.field_ids.insert(id, field_name.to_string());
+                }
+            },
+            None => self.add(
+                Severity::Error,
+                ValidationCode::InvalidFieldId(align_node_text(id_node)),
+                id_node,
+            ),
+        }
     }
 
-    #[test]
-    fn test_parse_command_id_invalid_formats() {
-        // Test invalid hex values
-        assert!(parse_command_id("0xGHIJ").is_err()); // Invalid hex digits
-        assert!(parse_command_id("0x12345").is_err()); // More than 4 hex digits (exceeds 16-bit range)
-        assert!(parse_command_id("0xFFFFFFFF").is_err()); // Much bigger than 16-bit
+    /// 字段类型：数组写法、枚举、引用其他包、允许的类型与各目标的类型映射
+    fn field_type<'a>(
+        &mut self,
+        state: &mut FieldState<'a>,
+        settings: &PacketSettings<'a>,
+        field_name: &str,
+        field_map: &'a Object,
+        field_node: &'a jsv::Spanned<jsv::Value>,
+    ) -> FieldType<'a> {
+        let mut field_type = FieldType::default();
+        // 数组长度的另一种写法
+        let array_len_node = field_map.get("array_len").filter(|n| !n.is_null());
+        let array_len = array_len_node.and_then(|node| {
+            let len = node
+                .as_number()
+                .and_then(|n| n.as_u64())
+                .filter(|len| *len > 0)
+                .and_then(|len| u32::try_from(len).ok());
+            if len.is_none() {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::InvalidArrayLen(field_name.to_string()),
+                    node,
+                );
+            }
+            len
+        });
 
-        // Test invalid decimal values
-        assert!(parse_command_id("65536").is_err()); // Exceeds 16-bit range
-        assert!(parse_command_id("invalid").is_err()); // Non-numeric
-        assert!(parse_command_id("").is_err()); // Empty string
-        assert!(parse_command_id("  ").is_err()); // Whitespace only
+        let Some(ty_node) = field_map.get("type") else {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidFieldType(field_name.to_string()),
+                field_node,
+            );
+            return field_type;
+        };
+        let Some(ty_str) = ty_node.as_string() else {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidFieldType(field_name.to_string()),
+                ty_node,
+            );
+            return field_type;
+        };
+        let Some((base_type, arr_size)) = parse_array_type(ty_str) else {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidArrayType(field_name.to_string()),
+                ty_node,
+            );
+            return field_type;
+        };
+        let arr_size = match (arr_size, array_len_node) {
+            (Some(_), Some(node)) => {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::ArrayLenConflict(field_name.to_string(), ty_str.to_string()),
+                    node,
+                );
+                arr_size
+            }
+            (None, _) => array_len,
+            (Some(_), None) => arr_size,
+        };
+        let count = u64::from(arr_size.unwrap_or(1));
+
+        // 枚举字段按底层类型检查
+        let base_type = settings
+            .enum_types
+            .get(base_type)
+            .copied()
+            .unwrap_or(base_type);
+        let base_type_valid = c_type_to_bit_field_size(base_type).is_some()
+            || matches!(base_type, "float" | "double" | "long double");
+        let packet_type = settings.packet_types.get(base_type).copied();
+        let is_packet_ref = !base_type_valid
+            && (packet_type.is_some()
+                || self.inherited_schema.is_some()
+                    && is_identifier(base_type)
+                    && !is_cpp_keyword(base_type));
+
+        if is_packet_ref {
+            field_type.is_packet_ref = true;
+            state
+                .packet_ref_fields
+                .push((field_name.to_string(), ty_node));
+            if let Some((size, align)) = packet_type {
+                state.natural_align = state.natural_align.max(align);
+                field_type.bits = Some(u64::from(size) * count * 8);
+            }
+            let options = self.options;
+            for target in options.targets.iter().filter(|t| **t != Target::Cpp) {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::UnsupportedTypeForTarget(
+                        field_name.to_string(),
+                        base_type.to_string(),
+                        target.to_string(),
+                        "引用其他包的字段只支持 C++".to_string(),
+                    ),
+                    ty_node,
+                );
+            }
+        } else if !base_type_valid {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidFieldType(field_name.to_string()),
+                ty_node,
+            );
+        } else {
+            self.check_type_support(field_name, base_type, ty_node);
+        }
+
+        if matches!(base_type, "float" | "double" | "long double") {
+            state.has_float = true;
+            field_type.float_size = crate::layout::c_type_size(base_type);
+        }
+        if base_type == "long double" {
+            state
+                .long_double_nodes
+                .push((field_name.to_string(), ty_node));
+        }
+
+        field_type.ty = Some(ty_str);
+        field_type.is_array = arr_size.is_some();
+        if let Some(size) = crate::layout::c_type_size(base_type) {
+            state.natural_align = state.natural_align.max(size.min(8));
+            field_type.bits = Some(u64::from(size) * count * 8);
+        }
+        field_type
     }
 
-    #[test]
-    fn test_parse_command_id_case_insensitive_hex() {
-        assert_eq!(parse_command_id("0xABCD"), Ok(43981));
-        assert_eq!(parse_command_id("0xabcd"), Ok(43981));
-        assert_eq!(parse_command_id("0xAbCd"), Ok(43981));
+    /// 基础类型是否在允许的类型之中，以及能否在每个目标语言中表示
+    fn check_type_support(
+        &mut self,
+        field_name: &str,
+        base_type: &str,
+        ty_node: &jsv::Spanned<jsv::Value>,
+    ) {
+        let options = self.options;
+        if let Some(allowed) = &options.allowed_types
+            && !allowed
+                .iter()
+                .any(|ty| canonical_type(ty) == canonical_type(base_type))
+        {
+            self.add(
+                Severity::Error,
+                ValidationCode::TypeNotAllowed(field_name.to_string(), base_type.to_string()),
+                ty_node,
+            );
+        }
+        for target in &options.targets {
+            if let Err(reason) = target.map_type(base_type) {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::UnsupportedTypeForTarget(
+                        field_name.to_string(),
+                        base_type.to_string(),
+                        target.to_string(),
+                        reason.to_string(),
+                    ),
+                    ty_node,
+                );
+            }
+        }
     }
 
-    #[test]
-    fn test_validate_valid_config() {
-        let json = r#"{
-            "packet_name": "ValidPacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
-            "fields": [
-                {
-                    "name": "valid_field",
-                    "type": "uint8_t",
-                    "comment": "A valid field"
-                },
+    /// 位域：有效时返回字段类型、类型位数与位域位数，位域为 null 或无效时为 `None`
+    fn bit_field<'a>(
+        &mut self,
+        settings: &PacketSettings,
+        field_name: &str,
+        field_type: &FieldType<'a>,
+        bit_field_node: &jsv::Spanned<jsv::Value>,
+        field_node: &jsv::Spanned<jsv::Value>,
+    ) -> Option<(&'a str, u8, u8)> {
+        // serde 重新序列化时会将 None 转为 null，null 表示没有位域
+        if bit_field_node.is_null() {
+            return None;
+        }
+        if field_type.is_array {
+            self.add(
+                Severity::Error,
+                ValidationCode::BitFieldOnArray(field_name.to_string()),
+                bit_field_node,
+            );
+            return None;
+        }
+        let Some(bits) = bit_field_integer(bit_field_node) else {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidBitField(field_name.to_string()),
+                bit_field_node,
+            );
+            return None;
+        };
+        if bit_field_node.as_string().is_some() {
+            if settings.schema >= 2 {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::RemovedInSchema(
+                        format!("字段 '{}' 的位域写成了字符串", field_name),
+                        settings.schema,
+                    ),
+                    bit_field_node,
+                );
+            } else {
+                self.add(
+                    Severity::Warning,
+                    ValidationCode::BitFieldAsString(field_name.to_string(), bits),
+                    bit_field_node,
+                );
+            }
+        }
+        if bits <= 0 {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidBitField(field_name.to_string()),
+                bit_field_node,
+            );
+            return None;
+        }
+        let Some(ty) = field_type.ty else {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidFieldType(field_name.to_string()),
+                field_node,
+            );
+            return None;
+        };
+        let Some(type_size) = c_type_to_bit_field_size(ty) else {
+            self.add(
+                Severity::Error,
+                ValidationCode::BitFieldOnInvalidType(field_name.to_string(), ty.to_string()),
+                bit_field_node,
+            );
+            return None;
+        };
+        // 位域长度不能超过类型本身的大小
+        let type_bits = type_size * 8;
+        if bits > i64::from(type_bits) {
+            self.add(
+                Severity::Error,
+                ValidationCode::BitFieldLengthOverflow(
+                    field_name.to_string(),
+                    bits as u64,
+                    type_bits,
+                ),
+                bit_field_node,
+            );
+            return None;
+        }
+        // 已确认不超过类型位宽，转换不会截断
+        Some((ty, type_bits, bits as u8))
+    }
+
+    /// 字段的字节序，覆盖包的 endianness
+    fn check_field_endianness(
+        &mut self,
+        settings: &PacketSettings,
+        field_name: &str,
+        field_type: &FieldType,
+        has_bit_field: bool,
+        endian_node: &jsv::Spanned<jsv::Value>,
+    ) {
+        if !self.check_endianness(endian_node) {
+            return;
+        }
+        let elem_type = field_type
+            .ty
+            .and_then(get_array_base_type_or_self)
+            .map(|base| settings.enum_types.get(base).copied().unwrap_or(base));
+        let unsupported = |reason: &str| {
+            ValidationCode::FieldEndiannessUnsupported(field_name.to_string(), reason.to_string())
+        };
+        if has_bit_field {
+            self.add(
+                Severity::Error,
+                unsupported("位域成员没有独立的字节位置"),
+                endian_node,
+            );
+        } else if field_type.is_packet_ref {
+            self.add(
+                Severity::Error,
+                unsupported("引用其他包的字段按被引用包自身的声明读写"),
+                endian_node,
+            );
+        } else if let Some(elem_type) = elem_type
+            && crate::layout::c_type_size(elem_type) == Some(1)
+        {
+            self.add(
+                Severity::Warning,
+                ValidationCode::RedundantEndianness(field_name.to_string(), elem_type.to_string()),
+                endian_node,
+            );
+        }
+    }
+
+    /// 固定偏移：只能在紧凑结构体中向后跳过，跳过的空隙生成保留字段
+    fn field_offset<'a>(
+        &mut self,
+        state: &mut FieldState<'a>,
+        settings: &PacketSettings<'a>,
+        field_name: &str,
+        has_bit_field: bool,
+        offset_node: &'a jsv::Spanned<jsv::Value>,
+    ) {
+        let payload_bits = state.payload_bits;
+        let reason = match offset_node.as_number().and_then(|n| n.as_u64()) {
+            None => Some("必须是非负整数".to_string()),
+            Some(_) if !settings.is_packed => Some("只能用于紧凑结构体".to_string()),
+            Some(_) if has_bit_field => Some("不能用于位域字段".to_string()),
+            Some(_) if !payload_bits.is_multiple_of(8) => {
+                Some("之前的位域没有填满整字节".to_string())
+            }
+            Some(offset) if offset.saturating_mul(8) < payload_bits => Some(format!(
+                "与前面的字段重叠，前面的字段结束于第 {} 字节",
+                payload_bits / 8
+            )),
+            Some(offset) if offset >= u64::from(settings.payload_budget) => {
+                Some(format!("超出了 {} 字节的载荷上限", settings.payload_budget))
+            }
+            Some(offset) => {
+                if offset * 8 > payload_bits {
+                    state.reserved_names.push((
+                        format!("reserved_{}", payload_bits / 8),
+                        field_name.to_string(),
+                        offset_node,
+                    ));
+                }
+                state.payload_bits = offset * 8;
+                None
+            }
+        };
+        if let Some(reason) = reason {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidFieldOffset(field_name.to_string(), reason),
+                offset_node,
+            );
+        }
+    }
+
+    /// 遥测范围标注与取值的显示名称
+    fn check_field_annotations(
+        &mut self,
+        settings: &PacketSettings,
+        field_name: &str,
+        ty: Option<&str>,
+        bit_field: Option<u8>,
+        field_map: &Object,
+    ) {
+        let element = ty.and_then(get_array_base_type_or_self);
+        let enum_type = element.filter(|base| settings.enum_types.contains_key(base));
+        let base_type = element.map(|base| settings.enum_types.get(base).copied().unwrap_or(base));
+        for (key, reason, node) in field_limit_errors(field_map, base_type, bit_field) {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidFieldLimit(field_name.to_string(), key.to_string(), reason),
+                node,
+            );
+        }
+
+        let Some(values_node) = field_map.get("values") else {
+            return;
+        };
+        if enum_type.is_some() && !values_node.is_null() {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidFieldValues(
+                    field_name.to_string(),
+                    "枚举字段的取值名称由枚举定义给出".to_string(),
+                ),
+                values_node,
+            );
+            return;
+        }
+        for (reason, node) in field_value_errors(values_node, base_type, bit_field) {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidFieldValues(field_name.to_string(), reason),
+                node,
+            );
+        }
+    }
+
+    /// 字段注释，缺少注释或只有空白时发出警告
+    fn check_field_comment(&mut self, field_map: &Object, field_node: &jsv::Spanned<jsv::Value>) {
+        let comment_node = field_map.get("comment");
+        self.check_comment_languages(comment_node);
+        let has_comment = comment_node
+            .and_then(comment_text)
+            .is_some_and(|c| !c.trim().is_empty());
+        if !has_comment {
+            let target_node = field_map.get("name").unwrap_or(field_node);
+            let field_name = field_map
+                .get("name")
+                .and_then(|n| n.as_string())
+                .unwrap_or("unknown");
+            self.add(
+                Severity::Warning,
+                ValidationCode::MissingComment(field_name.to_string()),
+                target_node,
+            );
+        }
+    }
+
+    /// 非紧凑结构体中跨越存储单元边界的位域
+    fn check_bit_field_boundaries(
+        &mut self,
+        field_nodes: &jsv::Spanned<jsv::Value>,
+        settings: &PacketSettings,
+        state: &FieldState,
+    ) {
+        if settings.is_packed {
+            return;
+        }
+        // 相邻两个位域的总和超过类型位数时跨越边界；诊断指向整个 fields 数组
+        for pair in state.bit_fields.windows(2) {
+            let (prev_field_name, _, _, prev_bits) = &pair[0];
+            let (field_name, _, type_bits, bits) = &pair[1];
+            if prev_bits + bits > *type_bits {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::BitFieldStraddleBoundaryWithoutPacked(
+                        prev_field_name.clone(),
+                        field_name.clone(),
+                        *prev_bits,
+                        *bits,
+                        *type_bits,
+                    ),
+                    field_nodes,
+                );
+            }
+        }
+        for (field_name, _, type_bits, bits) in &state.bit_fields {
+            if bits == type_bits {
+                self.add(
+                    Severity::Warning,
+                    ValidationCode::BitFieldStraddleBoundary(field_name.clone()),
+                    field_nodes,
+                );
+            }
+        }
+    }
+
+    /// 原生位域的布局由编译器决定：合并规则、默认符号与位序
+    fn check_compiler_bit_fields(
+        &mut self,
+        map: &Object,
+        settings: &PacketSettings,
+        state: &FieldState,
+    ) {
+        let compiler = self.options.compiler;
+        let bit_fields = &state.bit_fields;
+        let positions = &state.bit_field_positions;
+
+        // 部分编译器不会合并基础类型大小不同的相邻位域
+        if !compiler.merges_mixed_bit_fields() {
+            for i in 1..bit_fields.len() {
+                let (prev_field_name, _, prev_type_bits, _) = &bit_fields[i - 1];
+                let (field_name, _, type_bits, _) = &bit_fields[i];
+                let (prev_index, _) = positions[i - 1];
+                let (index, field_node) = positions[i];
+                if index == prev_index + 1 && prev_type_bits != type_bits {
+                    self.add(
+                        Severity::Warning,
+                        ValidationCode::MixedBitFieldTypes(
+                            prev_field_name.clone(),
+                            field_name.clone(),
+                            compiler.to_string(),
+                        ),
+                        field_node,
+                    );
+                }
+            }
+        }
+
+        // 部分编译器把未显式声明符号的整数位域当作无符号
+        if compiler.plain_bit_fields_unsigned() {
+            for ((field_name, field_type, _, _), (_, field_node)) in
+                bit_fields.iter().zip(positions)
+            {
+                if PLAIN_SIGNED_TYPES.contains(&field_type.as_str()) {
+                    self.add(
+                        Severity::Warning,
+                        ValidationCode::PlainBitFieldUnsigned(
+                            field_name.clone(),
+                            field_type.clone(),
+                            compiler.to_string(),
+                        ),
+                        field_node,
+                    );
+                }
+            }
+        }
+
+        // 原生位域按编译器的位序分配，与声明的位序不一致时只有 Wire 访问函数符合线上格式
+        let compiler_order = compiler.bit_field_order();
+        if let (Some(order), Some(order_node)) = (settings.bit_order, settings.bit_order_node)
+            && !bit_fields.is_empty()
+            && order != compiler_order
+        {
+            self.add(
+                Severity::Warning,
+                ValidationCode::BitFieldOrderMismatch(
+                    packet_name(map).to_string(),
+                    order.as_str().to_string(),
+                    compiler.to_string(),
+                    compiler_order.as_str().to_string(),
+                ),
+                order_node,
+            );
+        }
+
+        // 多个编译器共用同一结构体时，原生位域的布局不再由单个编译器决定
+        let mut profiles: Vec<&str> = Vec::new();
+        for compiler in &self.options.compiler_profiles {
+            if !profiles.contains(&compiler.name()) {
+                profiles.push(compiler.name());
+            }
+        }
+        if settings.bit_order_node.is_none()
+            && profiles.len() >= 2
+            && let Some((_, field_node)) = positions.first()
+        {
+            let code = ValidationCode::NativeBitFieldsAcrossCompilers(
+                packet_name(map).to_string(),
+                profiles.join("、"),
+            );
+            self.add(Severity::Warning, code, field_node);
+        }
+    }
+
+    /// 同一存储单元内的位域数量，以及窄位域与浮点字段混用
+    fn check_bit_field_units(
+        &mut self,
+        field_nodes: &jsv::Spanned<jsv::Value>,
+        state: &FieldState,
+    ) {
+        let options = self.options;
+        let bit_fields = &state.bit_fields;
+        let positions = &state.bit_field_positions;
+
+        // 相邻、同类型且未溢出的位域共享一个存储单元
+        let mut unit_start = 0;
+        while unit_start < bit_fields.len() {
+            let (_, unit_type, type_bits, first_bits) = &bit_fields[unit_start];
+            let mut used_bits = u32::from(*first_bits);
+            let mut unit_end = unit_start + 1;
+            while unit_end < bit_fields.len() {
+                let (_, next_type, _, next_bits) = &bit_fields[unit_end];
+                let adjacent = positions[unit_end].0 == positions[unit_end - 1].0 + 1;
+                if !adjacent
+                    || next_type != unit_type
+                    || used_bits + u32::from(*next_bits) > u32::from(*type_bits)
+                {
+                    break;
+                }
+                used_bits += u32::from(*next_bits);
+                unit_end += 1;
+            }
+
+            let count = unit_end - unit_start;
+            if count > options.max_bit_fields_per_unit {
+                self.add(
+                    Severity::Warning,
+                    ValidationCode::TooManyBitFieldsPerUnit(
+                        bit_fields[unit_start].0.clone(),
+                        count,
+                        options.max_bit_fields_per_unit,
+                    ),
+                    positions[unit_start].1,
+                );
+            }
+            unit_start = unit_end;
+        }
+
+        let tiny_count = bit_fields
+            .iter()
+            .filter(|(_, _, _, bits)| *bits <= TINY_BIT_FIELD_BITS)
+            .count();
+        if state.has_float && tiny_count > options.max_tiny_bit_fields_with_float {
+            self.add(
+                Severity::Warning,
+                ValidationCode::TinyBitFieldsWithFloat(
+                    tiny_count,
+                    options.max_tiny_bit_fields_with_float,
+                ),
+                field_nodes,
+            );
+        }
+    }
+
+    /// 帧校验：校验字段承载帧尾，数据段不含帧尾
+    fn check_checksum_fields(
+        &mut self,
+        map: &Object,
+        settings: &PacketSettings,
+        state: &FieldState,
+        field_count: usize,
+    ) {
+        for (index, name, name_node) in &state.checksum_fields {
+            if index + 1 != field_count {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::ChecksumFieldNotLast(name.clone()),
+                    name_node,
+                );
+            }
+            if settings.crc_append {
+                self.add(
+                    Severity::Warning,
+                    ValidationCode::ChecksumFieldDuplicated(name.clone()),
+                    name_node,
+                );
+            }
+        }
+        let packet_name = packet_name(map);
+        if !settings.crc_append
+            && state.checksum_fields.is_empty()
+            && let Some(node) = map.get("crc_append")
+        {
+            self.add(
+                Severity::Error,
+                ValidationCode::ChecksumFieldMissing(packet_name.to_string()),
+                node,
+            );
+        }
+        let tail = if settings.crc_append || state.checksum_fields.is_empty() {
+            0
+        } else {
+            2
+        };
+        let data_size = state.payload_bits.div_ceil(8).saturating_sub(tail);
+        if data_size > u64::from(REFEREE_MAX_PAYLOAD_SIZE)
+            && let Some(node) = settings.crc_node
+        {
+            self.add(
+                Severity::Warning,
+                ValidationCode::CrcPayloadTooLarge(
+                    packet_name.to_string(),
+                    data_size,
+                    REFEREE_MAX_PAYLOAD_SIZE,
+                ),
+                node,
+            );
+        }
+    }
+
+    /// 逐个标量读写的代码：serialize 不支持位域、long double 与引用其他包的字段；
+    /// 含位域的包按位流排列，BitLayout 只能描述标量成员
+    fn check_scalar_access(&mut self, settings: &PacketSettings, state: &FieldState) {
+        if settings.serialize {
+            let unsupported = |field_name: &str, reason: &str| {
+                ValidationCode::SerializeUnsupportedField(
+                    field_name.to_string(),
+                    reason.to_string(),
+                )
+            };
+            for ((field_name, _, _, _), (_, field_node)) in
+                state.bit_fields.iter().zip(&state.bit_field_positions)
+            {
+                self.add(
+                    Severity::Error,
+                    unsupported(field_name, "位域成员没有独立的字节位置"),
+                    field_node,
+                );
+            }
+            for (field_name, ty_node) in &state.long_double_nodes {
+                self.add(
+                    Severity::Error,
+                    unsupported(field_name, "long double 的大小与表示随平台变化"),
+                    ty_node,
+                );
+            }
+            for (field_name, ty_node) in &state.packet_ref_fields {
+                self.add(
+                    Severity::Error,
+                    unsupported(field_name, "引用其他包的字段没有逐个标量的读写函数"),
+                    ty_node,
+                );
+            }
+        }
+
+        if !state.bit_fields.is_empty() {
+            for (field_name, ty_node) in &state.packet_ref_fields {
+                self.add(
+                    Severity::Error,
+                    ValidationCode::PacketReferenceInBitstream(field_name.clone()),
+                    ty_node,
+                );
+            }
+        }
+    }
+
+    fn check_align(&mut self, map: &Object, is_packed: bool, natural_align: u32) {
+        let Some(align_node) = map.get("align").filter(|node| !node.is_null()) else {
+            return;
+        };
+        match align_node
+            .as_number()
+            .and_then(|n| n.as_u64())
+            .filter(|n| n.is_power_of_two() && *n <= MAX_ALIGN)
+        {
+            None => self.add(
+                Severity::Error,
+                ValidationCode::InvalidAlign(align_node_text(align_node)),
+                align_node,
+            ),
+            Some(align) if !is_packed && align < u64::from(natural_align) => self.add(
+                Severity::Error,
+                ValidationCode::AlignBelowNatural(align, natural_align),
+                align_node,
+            ),
+            Some(_) => {}
+        }
+    }
+
+    /// 声明的期望大小与其位置
+    fn expected_size(&mut self, map: &Object) -> Option<(u32, Span)> {
+        let size_node = map.get("expected_size").filter(|node| !node.is_null())?;
+        let size = size_node
+            .as_number()
+            .and_then(|n| n.as_u64())
+            .filter(|n| *n > 0)
+            .and_then(|n| u32::try_from(n).ok());
+        if size.is_none() {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidExpectedSize(align_node_text(size_node)),
+                size_node,
+            );
+        }
+        let span = size_node.span();
+        size.map(|size| (size, (span.0, span.1 - span.0)))
+    }
+
+    /// 自定义 traits 模板；模板中占位符的诊断单独返回，排在其他诊断之后
+    fn traits_template(&mut self, json_input: &str, map: &Object) -> Vec<RplcDiagnostic> {
+        let Some(template_node) = map.get("traits_template").filter(|node| !node.is_null()) else {
+            return Vec::new();
+        };
+        let Some(template) = template_node.as_string() else {
+            self.add(
+                Severity::Error,
+                ValidationCode::InvalidTraitsTemplate("必须是字符串".to_string()),
+                template_node,
+            );
+            return Vec::new();
+        };
+        if map.get("emit_traits").and_then(|n| n.as_bool()) == Some(false) {
+            self.add(
+                Severity::Warning,
+                ValidationCode::UnusedTraitsTemplate(packet_name(map).to_string()),
+                template_node,
+            );
+        }
+        template_placeholder_diagnostics(json_input, template_node, template, TRAITS_PLACEHOLDERS)
+    }
+}
+
+/// 诊断中使用的包名，缺失时为 `unknown`
+fn packet_name(map: &Object) -> &str {
+    map.get("packet_name")
+        .and_then(|n| n.as_string())
+        .unwrap_or("unknown")
+}
+
+/// 取值无效时诊断中显示的文本
+fn string_text(node: &jsv::Spanned<jsv::Value>) -> String {
+    node.as_string()
+        .map_or_else(|| "<non-string>".to_string(), str::to_string)
+}
+
+/// 与生成代码中的辅助成员重名的字段，每个名称只报告一次，并建议一个未被占用的新名称
+fn generated_name_diagnostics(
+    json_input: &str,
+    options: &ValidateOptions,
+    name_spans: &HashMap<String, Span>,
+) -> Vec<RplcDiagnostic> {
+    let Ok(config) = serde_json::from_str::<Config>(json_input) else {
+        return Vec::new();
+    };
+    let mut diags = Vec::new();
+    let mut reported = HashSet::new();
+    for (name, target) in generated_name_collisions(&config, &options.targets) {
+        let Some(&span) = name_spans.get(&name) else {
+            continue;
+        };
+        if !reported.insert(name.clone()) {
+            continue;
+        }
+        let suggestion = (1..)
+            .map(|n| match n {
+                1 => format!("{}_value", name),
+                n => format!("{}_value{}", name, n),
+            })
+            .find(|candidate| !name_spans.contains_key(candidate))
+            .expect("总能找到未使用的名称");
+        diags.push(RplcDiagnostic {
+            code: ValidationCode::GeneratedNameCollision(
+                name,
+                target.name().to_string(),
+                suggestion,
+            ),
+            severity: Severity::Error,
+            span: Some(span),
+            help: None,
+        });
+    }
+    diags
+}
+/// 与生成代码中的辅助成员同名的字段及对应的目标，按字段顺序排列
+///
+/// C++ 检查结构体中的范围常量、字段 ID 常量与 `_name()` 查找函数，以及 `Wire`、`Endian`
+/// 访问结构体中的函数；Python 检查 dataclass 的类变量与方法
+fn generated_name_collisions(config: &Config, targets: &[Target]) -> Vec<(String, Target)> {
+    let fields: Vec<&Field> = config
+        .fields
+        .iter()
+        .filter(|field| field.reserved.is_none())
+        .collect();
+    // 每个作用域中的成员名，以及该作用域中的字段
+    let mut scopes: Vec<(Target, Vec<String>, Vec<&str>)> = Vec::new();
+    let all_names: Vec<&str> = fields.iter().map(|field| field.name.as_str()).collect();
+
+    if targets.contains(&Target::Cpp) {
+        let mut members = Vec::new();
+        for field in &fields {
+            for (key, _) in field.limits() {
+                members.push(format!("k_{}_{}", field.name, key));
+            }
+            if field.id.is_some() {
+                members.push(format!("k_{}_id", field.name));
+            }
+            if !field.value_names().is_empty() {
+                members.push(format!("{}_name", field.name));
+            }
+        }
+        scopes.push((Target::Cpp, members, all_names.clone()));
+
+        let bit_fields: Vec<&str> = fields
+            .iter()
+            .filter(|field| field.bit_field.is_some())
+            .map(|field| field.name.as_str())
+            .collect();
+        if config.bitfield_layout.is_some() && !bit_fields.is_empty() {
+            let mut members: Vec<String> = ["read_bits", "write_bits", "sign_extend"]
+                .map(str::to_string)
+                .into();
+            members.extend(bit_fields.iter().map(|name| format!("set_{}", name)));
+            scopes.push((Target::Cpp, members, bit_fields));
+        }
+
+        if wants_endian_accessors(config) {
+            let accessed: Vec<&str> = fields
+                .iter()
+                .filter(|field| endian_accessor_field(field).is_some())
+                .map(|field| field.name.as_str())
+                .collect();
+            let members = accessed
+                .iter()
+                .map(|name| format!("set_{}", name))
+                .collect();
+            scopes.push((Target::Cpp, members, accessed));
+        }
+    }
+
+    if targets.contains(&Target::Python) {
+        let mut members: Vec<String> = GENERATED_MEMBERS.iter().map(|m| m.to_string()).collect();
+        for field in &fields {
+            for (key, _) in field.limits() {
+                members.push(format!(
+                    "{}_{}",
+                    field.name.to_uppercase(),
+                    key.to_uppercase()
+                ));
+            }
+            if !field.value_names().is_empty() {
+                members.push(format!("{}_name", field.name));
+            }
+        }
+        scopes.push((Target::Python, members, all_names));
+    }
+
+    let mut collisions = Vec::new();
+    for field in &fields {
+        for (target, members, names) in &scopes {
+            if names.contains(&field.name.as_str()) && members.contains(&field.name) {
+                collisions.push((field.name.clone(), *target));
+            }
+        }
+    }
+    collisions
+}
+
+/// 模板中未知或未闭合的占位符
+fn template_placeholder_diagnostics(
+    json_input: &str,
+    template_node: &jsv::Spanned<jsv::Value>,
+    template: &str,
+    known: &[&str],
+) -> Vec<RplcDiagnostic> {
+    template_placeholders(template)
+        .into_iter()
+        .filter(|placeholder| !placeholder.closed || !known.contains(&placeholder.name))
+        .map(|placeholder| {
+            let reason = if placeholder.closed {
+                format!("未知的占位符 ${{{}}}", placeholder.name)
+            } else {
+                format!("占位符 ${{{} 缺少右花括号", placeholder.name)
+            };
+            RplcDiagnostic {
+                code: ValidationCode::InvalidTraitsTemplate(reason),
+                severity: Severity::Error,
+                span: Some(string_content_span(
+                    json_input,
+                    template_node,
+                    placeholder.start..placeholder.end,
+                )),
+                help: None,
+            }
+        })
+        .collect()
+}
+
+/// JSON 字符串节点中解码后的 `range` 对应的源文本范围；转义序列按源文本中的长度计算，
+/// 无法对应时返回整个节点
+fn string_content_span(
+    json_input: &str,
+    node: &jsv::Spanned<jsv::Value>,
+    range: std::ops::Range<usize>,
+) -> Span {
+    let (node_start, node_end) = node.span();
+    let whole = (node_start, node_end - node_start);
+    let Some(raw) = json_input.get(node_start + 1..node_end.saturating_sub(1)) else {
+        return whole;
+    };
+
+    // 源文本中每个解码字符的起点：(解码偏移, 源文本偏移)
+    let mut decoded = 0;
+    let mut start = None;
+    let mut chars = raw.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        if decoded == range.start {
+            start = Some(offset);
+        }
+        if decoded == range.end {
+            return match start {
+                Some(start) => (node_start + 1 + start, offset - start),
+                None => whole,
+            };
+        }
+        decoded += match c {
+            '\\' => match chars.next() {
+                Some((_, 'u')) => {
+                    let hex: String = (0..4)
+                        .filter_map(|_| chars.next())
+                        .map(|(_, c)| c)
+                        .collect();
+                    let unit = u32::from_str_radix(&hex, 16).unwrap_or(0xFFFD);
+                    if (0xD800..0xDC00).contains(&unit) {
+                        // 代理对的低位部分属于同一个字符
+                        for _ in 0..6 {
+                            chars.next();
+                        }
+                        4
+                    } else {
+                        char::from_u32(unit).map_or(3, char::len_utf8)
+                    }
+                }
+                _ => 1,
+            },
+            c => c.len_utf8(),
+        };
+    }
+    match start {
+        Some(start) if decoded == range.end => (node_start + 1 + start, raw.len() - start),
+        _ => whole,
+    }
+}
+
+/// 顶层为数组或带 `packets` 数组的包集合时返回其中的包数
+fn multi_packet_count(root: &jsv::Spanned<jsv::Value>) -> Option<usize> {
+    match root.get_ref() {
+        jsv::Value::Array(packets) => Some(packets.len()),
+        jsv::Value::Object(map) => map
+            .get("packets")
+            .and_then(|packets| packets.as_array())
+            .map(|packets| packets.len()),
+        _ => None,
+    }
+}
+
+/// `includes` 中的头文件不能写入 `#include` 时的原因
+fn include_error(include: &str) -> Option<&'static str> {
+    let path = include
+        .strip_prefix('<')
+        .map(|rest| rest.strip_suffix('>').ok_or("尖括号不成对"))
+        .unwrap_or(Ok(include));
+    match path {
+        Err(reason) => Some(reason),
+        Ok(path) if path.trim().is_empty() => Some("不能为空"),
+        Ok(path) if path.contains(['"', '<', '>', '\n', '\r']) => {
+            Some("不能包含引号、尖括号或换行")
+        }
+        Ok(_) => None,
+    }
+}
+
+/// 字段 ID 节点的值，不是 u32 范围内的整数时为 None
+fn field_id(node: &jsv::Spanned<jsv::Value>) -> Option<u32> {
+    node.as_number()
+        .and_then(|n| n.as_u64())
+        .and_then(|id| u32::try_from(id).ok())
+}
+
+fn align_node_text(node: &jsv::Spanned<jsv::Value>) -> String {
+    match node.get_ref() {
+        jsv::Value::Number(n) => n.to_string(),
+        jsv::Value::String(s) => s.clone(),
+        _ => "<non-number>".to_string(),
+    }
+}
+
+// New functionality to support validating multiple packets
+pub fn validate_multiple(json_input: &str) -> Vec<RplcDiagnostic> {
+    validate_multiple_with_options(json_input, &ValidateOptions::default())
+}
+
+pub fn validate_multiple_with_options(
+    json_input: &str,
+    options: &ValidateOptions,
+) -> Vec<RplcDiagnostic> {
+    if let Some(diag) = check_input_size(json_input, options.max_input_size) {
+        return vec![diag];
+    }
+    validate_multiple_with(json_input, &mut |packet| {
+        validate_packet(packet.text, options, packet.inherited_schema)
+    })
+}
+
+/// 多包文档中的一个包
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PacketSource<'a> {
+    /// 包对象的源文本
+    pub text: &'a str,
+    /// `text` 在整个文档中的字节偏移
+    pub start: usize,
+    /// 见 `validate_packet`；整个文档就是一个包时为 `None`
+    pub inherited_schema: Option<u32>,
+}
+
+/// 多包校验的流程，每个包由 `validate_packet` 校验并返回相对于包源文本的诊断
+///
+/// `ValidationCache` 与 `IncrementalValidator` 借此复用未改变的包的结果
+pub(crate) fn validate_multiple_with(
+    json_input: &str,
+    validate_packet: &mut dyn FnMut(PacketSource) -> Vec<RplcDiagnostic>,
+) -> Vec<RplcDiagnostic> {
+    let document = PacketDocument::split(json_input);
+    let mut all_diags = document.preamble.clone();
+    for index in 0..document.packets.len() {
+        all_diags.extend(document.validate(json_input, index, validate_packet));
+    }
+    all_diags.extend(cross_packet_diagnostics(&document.identities));
+    all_diags
+}
+
+/// 拆分为各个包的多包文档，包只记录位置，不借用源文本
+#[derive(Debug, Default)]
+pub(crate) struct PacketDocument {
+    /// 与具体包无关的诊断：重复键与包集合头部
+    pub preamble: Vec<RplcDiagnostic>,
+    /// 每个包在文档中的字节范围
+    pub packets: Vec<(usize, usize)>,
+    /// 见 `validate_packet`；整个文档就是一个包时为 `None`
+    pub inherited_schema: Option<u32>,
+    pub identities: Vec<PacketIdentity>,
+}
+
+impl PacketDocument {
+    pub fn split(json_input: &str) -> Self {
+        // Try to parse as a single config first (for backward compatibility)
+        if serde_json::from_str::<Config>(json_input).is_ok() {
+            return Self {
+                packets: vec![(0, json_input.len())],
+                ..Self::default()
+            };
+        }
+
+        if serde_json::from_str::<IgnoredAny>(json_input).is_err() {
+            return Self::default();
+        }
+        let mut preamble = duplicate_key_diagnostics(json_input);
+        // 各个包只借用源文本中的片段，不为整个文档构建带位置的语法树，
+        // 峰值内存只与最大的单个包有关
+        let (packets, inherited_schema) = match json_input.trim_start().as_bytes().first() {
+            Some(b'[') => (
+                serde_json::from_str::<Vec<&RawValue>>(json_input).unwrap_or_default(),
+                LEGACY_SCHEMA,
+            ),
+            // Packet set with set-level metadata: { "protocol_version": ..., "packets": [...] }
+            Some(b'{') => match serde_json::from_str::<RawPacketSet>(json_input) {
+                Ok(RawPacketSet {
+                    packets: Some(packets),
+                    rplc_schema,
+                }) => {
+                    preamble.extend(validate_packet_set_header(json_input));
+                    let schema_node = rplc_schema
+                        .and_then(|raw| parse_spanned::<jsv::Spanned<jsv::Value>>(raw.get()).ok());
+                    let schema = schema_version(schema_node.as_ref(), LEGACY_SCHEMA)
+                        .unwrap_or(CURRENT_SCHEMA);
+                    (packets, schema)
+                }
+                _ => {
+                    return Self {
+                        preamble,
+                        ..Self::default()
+                    };
+                }
+            },
+            _ => {
+                return Self {
+                    preamble,
+                    ..Self::default()
+                };
+            }
+        };
+
+        Self {
+            preamble,
+            packets: packets
+                .iter()
+                .map(|raw| raw_span(json_input, raw))
+                .collect(),
+            inherited_schema: Some(inherited_schema),
+            identities: packets
+                .iter()
+                .map(|raw| PacketIdentity::of(json_input, raw))
+                .collect(),
+        }
+    }
+
+    /// 校验第 `index` 个包，诊断的位置换算回整个文档
+    pub fn validate(
+        &self,
+        json_input: &str,
+        index: usize,
+        validate_packet: &mut dyn FnMut(PacketSource) -> Vec<RplcDiagnostic>,
+    ) -> Vec<RplcDiagnostic> {
+        let (start, end) = self.packets[index];
+        let diags = validate_packet(PacketSource {
+            text: &json_input[start..end],
+            start,
+            inherited_schema: self.inherited_schema,
+        });
+        diags.into_iter().map(|diag| diag.offset(start)).collect()
+    }
+}
+
+/// 参与跨包检查的包名、命令 ID 与对其他包的引用，位置为源文档中的偏移
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct PacketIdentity {
+    pub name: Option<(String, Span)>,
+    pub command_id: Option<(u16, Span)>,
+    pub references: Vec<PacketReference>,
+}
+
+/// 类型不是基础类型也不是本包枚举的字段，类型应为同一文档中另一个包的名称
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PacketReference {
+    pub field: String,
+    /// 字段的完整类型，数组保留长度
+    pub ty: String,
+    pub span: Span,
+}
+
+impl PacketIdentity {
+    fn of(json_input: &str, packet: &RawValue) -> Self {
+        let Ok(raw) = serde_json::from_str::<RawIdentity>(packet.get()) else {
+            return Self::default();
+        };
+        let string_of = |raw: &RawValue| serde_json::from_str::<String>(raw.get()).ok();
+        let span_of = |raw: &RawValue| {
+            let (start, end) = raw_span(json_input, raw);
+            (start, end - start)
+        };
+        Self {
+            name: raw
+                .packet_name
+                .and_then(|n| Some((string_of(n)?, span_of(n)))),
+            command_id: raw.command_id.and_then(|n| {
+                let id = parse_command_id(&string_of(n)?).ok()?;
+                Some((id, span_of(n)))
+            }),
+            references: raw
+                .fields
+                .map(|fields| packet_references(fields, raw.enums, &span_of))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// 字段（含嵌套结构体的成员）中引用其他包的类型；无法解析的条目由包自身的校验报告
+fn packet_references(
+    fields: &RawValue,
+    enums: Option<&RawValue>,
+    span_of: &dyn Fn(&RawValue) -> Span,
+) -> Vec<PacketReference> {
+    #[derive(Deserialize)]
+    struct RawEntry<'a> {
+        name: Option<String>,
+        #[serde(rename = "type", borrow)]
+        ty: Option<&'a RawValue>,
+        #[serde(borrow)]
+        group: Option<Vec<&'a RawValue>>,
+    }
+    let enum_names: Vec<String> = enums
+        .and_then(|enums| serde_json::from_str::<Vec<serde_json::Value>>(enums.get()).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|def| def.get("name")?.as_str().map(str::to_string))
+        .collect();
+    let mut entries: Vec<&RawValue> = serde_json::from_str(fields.get()).unwrap_or_default();
+    entries.reverse();
+    let mut references = Vec::new();
+    while let Some(entry) = entries.pop() {
+        let Ok(entry) = serde_json::from_str::<RawEntry>(entry.get()) else {
+            continue;
+        };
+        if let Some(members) = entry.group {
+            entries.extend(members.into_iter().rev());
+            continue;
+        }
+        let (Some(field), Some(ty_raw)) = (entry.name, entry.ty) else {
+            continue;
+        };
+        let Ok(ty) = serde_json::from_str::<String>(ty_raw.get()) else {
+            continue;
+        };
+        let Some((base, _)) = parse_array_type(&ty) else {
+            continue;
+        };
+        if crate::layout::c_type_size(base).is_none()
+            && is_identifier(base)
+            && !is_cpp_keyword(base)
+            && !enum_names.iter().any(|name| name == base)
+        {
+            references.push(PacketReference {
+                field,
+                ty: ty.clone(),
+                span: span_of(ty_raw),
+            });
+        }
+    }
+    references
+}
+
+/// 包集合的顶层，只借用各个包的源文本
+#[derive(Deserialize)]
+struct RawPacketSet<'a> {
+    #[serde(borrow)]
+    packets: Option<Vec<&'a RawValue>>,
+    #[serde(borrow)]
+    rplc_schema: Option<&'a RawValue>,
+}
+
+/// 跨包检查需要的键，其他键不解析
+#[derive(Deserialize)]
+struct RawIdentity<'a> {
+    #[serde(borrow)]
+    packet_name: Option<&'a RawValue>,
+    #[serde(borrow)]
+    command_id: Option<&'a RawValue>,
+    #[serde(borrow)]
+    enums: Option<&'a RawValue>,
+    #[serde(borrow)]
+    fields: Option<&'a RawValue>,
+}
+
+/// 借用的 JSON 片段在源文本中的起止偏移
+fn raw_span(json_input: &str, raw: &RawValue) -> (usize, usize) {
+    let start = raw.get().as_ptr() as usize - json_input.as_ptr() as usize;
+    (start, start + raw.get().len())
+}
+
+/// 跨包检查：引用的包必须存在且不能成环；包名与命令 ID 在文档中必须唯一，错误报告在后出现的包上
+pub(crate) fn cross_packet_diagnostics(identities: &[PacketIdentity]) -> Vec<RplcDiagnostic> {
+    let mut diags = packet_reference_diagnostics(identities);
+    let mut names: HashSet<&str> = HashSet::new();
+    let mut commands: HashMap<u16, &str> = HashMap::new();
+    for identity in identities {
+        let name = identity.name.as_ref().map_or("", |(name, _)| name.as_str());
+        if let Some((name, span)) = &identity.name
+            && !names.insert(name)
+        {
+            diags.push(RplcDiagnostic {
+                code: ValidationCode::DuplicatePacketName(name.clone()),
+                severity: Severity::Error,
+                span: Some(*span),
+                help: None,
+            });
+        }
+        if let Some((id, span)) = identity.command_id {
+            if let Some(first) = commands.get(&id) {
+                diags.push(RplcDiagnostic {
+                    code: ValidationCode::DuplicateCommandId(
+                        format_command_id(id),
+                        first.to_string(),
+                    ),
+                    severity: Severity::Error,
+                    span: Some(span),
+                    help: None,
+                });
+            } else {
+                commands.insert(id, name);
+            }
+        }
+    }
+    diags
+}
+
+/// 引用不存在的包报告在字段的类型上；每个环报告一次，位置是使环闭合的引用
+fn packet_reference_diagnostics(identities: &[PacketIdentity]) -> Vec<RplcDiagnostic> {
+    let mut diags = Vec::new();
+    let names: Vec<&str> = identities
+        .iter()
+        .filter_map(|identity| identity.name.as_ref().map(|(name, _)| name.as_str()))
+        .collect();
+    // 重名的包只取第一个，与生成时一致
+    let packet = |name: &str| {
+        identities
+            .iter()
+            .find(|identity| identity.name.as_ref().is_some_and(|(n, _)| n == name))
+    };
+    for reference in identities.iter().flat_map(|identity| &identity.references) {
+        let base = get_array_base_type_or_self(&reference.ty).unwrap_or(&reference.ty);
+        if !names.contains(&base) {
+            diags.push(RplcDiagnostic {
+                code: ValidationCode::InvalidFieldType(reference.field.clone()),
+                severity: Severity::Error,
+                span: Some(reference.span),
+                help: crate::suggest::closest(base, &names)
+                    .map(str::to_string)
+                    .or_else(|| crate::suggest::suggest_type(&reference.ty))
+                    .map(|ty| crate::suggest::did_you_mean(&ty)),
+            });
+        }
+    }
+
+    // 深度优先搜索，遇到仍在栈中的包即找到一个环
+    fn visit<'a>(
+        name: &'a str,
+        packet: &dyn Fn(&str) -> Option<&'a PacketIdentity>,
+        stack: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+        diags: &mut Vec<RplcDiagnostic>,
+    ) {
+        let Some(identity) = packet(name) else {
+            return;
+        };
+        stack.push(name);
+        for reference in &identity.references {
+            let base = get_array_base_type_or_self(&reference.ty).unwrap_or(&reference.ty);
+            if let Some(start) = stack.iter().position(|n| *n == base) {
+                let mut path = stack[start..].to_vec();
+                path.push(base);
+                diags.push(RplcDiagnostic {
+                    code: ValidationCode::PacketReferenceCycle(path.join(" → ")),
+                    severity: Severity::Error,
+                    span: Some(reference.span),
+                    help: None,
+                });
+            } else if !done.contains(base)
+                && let Some(target) = packet(base)
+                && let Some((target, _)) = &target.name
+            {
+                visit(target, packet, stack, done, diags);
+            }
+        }
+        stack.pop();
+        done.insert(name);
+    }
+    let mut done = HashSet::new();
+    for name in &names {
+        if !done.contains(name) {
+            visit(name, &packet, &mut Vec::new(), &mut done, &mut diags);
+        }
+    }
+    diags
+}
+
+/// `dependencies` 中各个包的大小与对齐；无法解析或没有确定布局的包不列出
+fn dependency_layouts(json: &str) -> HashMap<String, (u32, u32)> {
+    let Ok(dependencies) = serde_json::from_str::<Vec<Config>>(json) else {
+        return HashMap::new();
+    };
+    dependencies
+        .iter()
+        .filter_map(|packet| {
+            let layout = crate::layout::compute_layout_in(packet, &dependencies, 0)?;
+            Some((packet.packet_name.clone(), (layout.size, layout.align)))
+        })
+        .collect()
+}
+
+/// 带位置信息地解析 JSON；重复的键由 `duplicate_key_diagnostics` 单独报告，这里不作为解析失败
+pub(crate) fn parse_spanned<T: serde::de::DeserializeOwned>(
+    json_input: &str,
+) -> Result<T, serde_json::Error> {
+    let settings = jsv::Settings {
+        allow_duplicate_keys: true,
+        ..Default::default()
+    };
+    jsv::from_str_with_settings(json_input, &settings)
+}
+
+/// 检查包集合的顶层元数据（如 `protocol_version`）
+fn validate_packet_set_header(json_input: &str) -> Vec<RplcDiagnostic> {
+    let mut diags = Vec::new();
+
+    let Ok(jsv::Value::Object(map)) = parse_spanned::<jsv::Value>(json_input) else {
+        return diags;
+    };
+    diags.extend(unknown_keys(&map, PACKET_SET_KEYS));
+
+    if let Some(schema_node) = map.get("rplc_schema")
+        && let Err(text) = schema_version(Some(schema_node), LEGACY_SCHEMA)
+    {
+        let span = schema_node.span();
+        diags.push(RplcDiagnostic {
+            code: ValidationCode::UnsupportedSchemaVersion(text, CURRENT_SCHEMA),
+            severity: Severity::Error,
+            span: Some((span.0, span.1 - span.0)),
+            help: None,
+        });
+    }
+
+    if let Some(version_node) = map.get("protocol_version") {
+        let valid = match version_node.as_string() {
+            Some(version) => version.parse::<ProtocolVersion>().is_ok(),
+            None => version_node.is_null(),
+        };
+        if !valid {
+            let span = version_node.span();
+            diags.push(RplcDiagnostic {
+                code: ValidationCode::InvalidProtocolVersion(
+                    version_node
+                        .as_string()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| "<non-string>".to_string()),
+                ),
+                severity: Severity::Error,
+                span: Some((span.0, span.1 - span.0)),
+                help: None,
+            });
+        }
+    }
+
+    diags
+}
+
+/// 读取 `rplc_schema`：省略或为 null 时为 `default`，不受支持的值返回其文本
+/// 注释的文本，多语言注释为各语言文本的拼接，用于判断注释是否为空；不是字符串或对象时返回 `None`
+fn comment_text(node: &jsv::Spanned<jsv::Value>) -> Option<String> {
+    match node.get_ref() {
+        jsv::Value::String(text) => Some(text.clone()),
+        jsv::Value::Object(texts) => Some(
+            texts
+                .values()
+                .filter_map(|text| text.as_string())
+                .collect::<Vec<_>>()
+                .concat(),
+        ),
+        _ => None,
+    }
+}
+
+/// 多语言注释中键不是语言代码或值不是字符串的条目
+fn invalid_comment_entries(
+    node: &jsv::Spanned<jsv::Value>,
+) -> Vec<(String, &jsv::Spanned<jsv::Value>)> {
+    let Some(texts) = node.as_object() else {
+        return Vec::new();
+    };
+    let language_re = Regex::new(r"^[a-z]{2,3}(-[A-Za-z0-9]{2,8})*$").unwrap();
+    texts
+        .iter()
+        .filter(|(language, text)| !language_re.is_match(language) || text.as_string().is_none())
+        .map(|(language, text)| (language.to_string(), text))
+        .collect()
+}
+
+fn schema_version(node: Option<&jsv::Spanned<jsv::Value>>, default: u32) -> Result<u32, String> {
+    let Some(node) = node.filter(|node| !node.is_null()) else {
+        return Ok(default);
+    };
+    node.as_number()
+        .and_then(|num| num.as_u64())
+        .and_then(|version| u32::try_from(version).ok())
+        .filter(|version| (LEGACY_SCHEMA..=CURRENT_SCHEMA).contains(version))
+        .ok_or_else(|| align_node_text(node))
+}
+
+/// 数组字段的元素类型，其他字段的类型本身；类型不合法时为 None
+fn get_array_base_type_or_self(ty: &str) -> Option<&str> {
+    parse_array_type(ty).map(|(base, _)| base)
+}
+
+/// 遥测范围标注中的问题：(键, 原因, 节点)
+///
+/// 整数字段的值必须是该类型（位域则为该位宽）可以表示的整数；`char` 的符号由编译器决定，只接受 0..=127
+fn field_limit_errors<'a>(
+    field_map: &'a jsv::Map<jsv::Spanned<String>, jsv::Spanned<jsv::Value>>,
+    base_type: Option<&str>,
+    bits: Option<u8>,
+) -> Vec<(&'static str, String, &'a jsv::Spanned<jsv::Value>)> {
+    let mut errors = Vec::new();
+    let mut values = Vec::new();
+    for key in ["min", "max", "typical"] {
+        let Some(node) = field_map.get(key).filter(|node| !node.is_null()) else {
+            continue;
+        };
+        let Some(number) = node.as_number() else {
+            errors.push((key, "必须是数字".to_string(), node));
+            continue;
+        };
+        let value = number.as_f64().unwrap_or_default();
+        match base_type {
+            None => {}
+            Some("bool" | "_Bool") => {
+                errors.push((key, "布尔字段不能标注范围".to_string(), node));
+                continue;
+            }
+            Some("float") if value.abs() > f64::from(f32::MAX) => {
+                errors.push((key, format!("{} 超出了 float 的取值范围", value), node));
+                continue;
+            }
+            Some(ty) => {
+                if let Some((low, high)) = integer_range(ty, bits) {
+                    let integer = number
+                        .as_i64()
+                        .map(i128::from)
+                        .or_else(|| number.as_u64().map(i128::from));
+                    let Some(integer) = integer else {
+                        errors.push((key, "整数字段的范围必须是整数".to_string(), node));
+                        continue;
+                    };
+                    if integer < low || integer > high {
+                        let ty = match bits {
+                            Some(bits) => format!("{} : {}", ty, bits),
+                            None => ty.to_string(),
+                        };
+                        errors.push((
+                            key,
+                            format!("{} 超出了 {} 的取值范围 {}..={}", integer, ty, low, high),
+                            node,
+                        ));
+                        continue;
+                    }
+                }
+            }
+        }
+        values.push((key, value, node));
+    }
+
+    let value = |key| values.iter().find(|v| v.0 == key).map(|v| (v.1, v.2));
+    if let (Some((min, min_node)), Some((max, _))) = (value("min"), value("max"))
+        && min > max
+    {
+        errors.push(("min", format!("{} 大于 max {}", min, max), min_node));
+    }
+    if let Some((typical, node)) = value("typical") {
+        if let Some((min, _)) = value("min").filter(|(min, _)| typical < *min) {
+            errors.push(("typical", format!("{} 小于 min {}", typical, min), node));
+        } else if let Some((max, _)) = value("max").filter(|(max, _)| typical > *max) {
+            errors.push(("typical", format!("{} 大于 max {}", typical, max), node));
+        }
+    }
+    errors
+}
+
+type EnumDiagnostic<'a> = (Severity, ValidationCode, &'a jsv::Spanned<jsv::Value>);
+
+/// 具名枚举的定义：返回可供字段引用的枚举（名称 → 底层类型）与定义中的问题
+fn check_enums<'a>(
+    enums_node: &'a jsv::Spanned<jsv::Value>,
+) -> (HashMap<&'a str, &'a str>, Vec<EnumDiagnostic<'a>>) {
+    let mut enum_types = HashMap::new();
+    let mut diags = Vec::new();
+    if enums_node.is_null() {
+        return (enum_types, diags);
+    }
+    let Some(defs) = enums_node.as_array() else {
+        let code = ValidationCode::InvalidEnum("enums".to_string(), "必须是数组".to_string());
+        return (enum_types, vec![(Severity::Error, code, enums_node)]);
+    };
+    let invalid = |name: &str, reason: &str| {
+        ValidationCode::InvalidEnum(name.to_string(), reason.to_string())
+    };
+
+    for def_node in defs {
+        let Some(def) = def_node.as_object() else {
+            diags.push((Severity::Error, invalid("unknown", "必须是对象"), def_node));
+            continue;
+        };
+        let Some((name_node, name)) = def
+            .get("name")
+            .and_then(|node| Some((node, node.as_string()?)))
+        else {
+            diags.push((Severity::Error, invalid("unknown", "缺少名称"), def_node));
+            continue;
+        };
+        if !is_identifier(name) || is_cpp_keyword(name) {
+            let reason = "名称必须是合法的 C++ 标识符且不能是关键字";
+            diags.push((Severity::Error, invalid(name, reason), name_node));
+        } else if c_type_to_bit_field_size(name).is_some() || matches!(name, "float" | "double") {
+            diags.push((
+                Severity::Error,
+                invalid(name, "名称不能与基础类型相同"),
+                name_node,
+            ));
+        } else if enum_types.contains_key(name) {
+            let code = ValidationCode::DuplicateEnum(name.to_string());
+            diags.push((Severity::Error, code, name_node));
+        }
+
+        let ty_node = def.get("type");
+        let underlying = ty_node.and_then(|node| node.as_string()).filter(|ty| {
+            c_type_to_bit_field_size(ty).is_some() && !matches!(*ty, "bool" | "_Bool")
+        });
+        match underlying {
+            Some(ty) => {
+                enum_types.entry(name).or_insert(ty);
+            }
+            None => {
+                let reason = "底层类型必须是整数类型，例如 uint8_t";
+                diags.push((
+                    Severity::Error,
+                    invalid(name, reason),
+                    ty_node.unwrap_or(def_node),
+                ));
+            }
+        }
+        for (language, node) in def
+            .get("comment")
+            .into_iter()
+            .flat_map(invalid_comment_entries)
+        {
+            diags.push((
+                Severity::Error,
+                ValidationCode::InvalidLocalizedComment(language),
+                node,
+            ));
+        }
+
+        let values_node = def.get("values");
+        let values = match values_node.and_then(|node| node.as_array()) {
+            Some(values) if !values.is_empty() => values,
+            Some(_) => {
+                let node = values_node.unwrap_or(def_node);
+                diags.push((Severity::Error, invalid(name, "至少需要一个枚举项"), node));
+                continue;
+            }
+            None => {
+                let node = values_node.unwrap_or(def_node);
+                diags.push((Severity::Error, invalid(name, "values 必须是数组"), node));
+                continue;
+            }
+        };
+        let range = underlying.and_then(|ty| integer_range(ty, None));
+        let mut seen_names = HashSet::new();
+        let mut seen_values: HashMap<i128, &str> = HashMap::new();
+        for item_node in values {
+            let enumerator = |item: &str, reason: &str| {
+                ValidationCode::InvalidEnumerator(
+                    name.to_string(),
+                    item.to_string(),
+                    reason.to_string(),
+                )
+            };
+            let Some(item) = item_node.as_object() else {
+                diags.push((
+                    Severity::Error,
+                    enumerator("unknown", "必须是对象"),
+                    item_node,
+                ));
+                continue;
+            };
+            let Some((item_name_node, item_name)) = item
+                .get("name")
+                .and_then(|node| Some((node, node.as_string()?)))
+            else {
+                diags.push((
+                    Severity::Error,
+                    enumerator("unknown", "缺少名称"),
+                    item_node,
+                ));
+                continue;
+            };
+            if !is_identifier(item_name) || is_cpp_keyword(item_name) {
+                let reason = "名称必须是合法的 C++ 标识符且不能是关键字";
+                diags.push((
+                    Severity::Error,
+                    enumerator(item_name, reason),
+                    item_name_node,
+                ));
+            } else if !seen_names.insert(item_name) {
+                let code =
+                    ValidationCode::DuplicateEnumerator(name.to_string(), item_name.to_string());
+                diags.push((Severity::Error, code, item_name_node));
+            }
+            for (language, node) in item
+                .get("comment")
+                .into_iter()
+                .flat_map(invalid_comment_entries)
+            {
+                diags.push((
+                    Severity::Error,
+                    ValidationCode::InvalidLocalizedComment(language),
+                    node,
+                ));
+            }
+
+            let value_node = item.get("value");
+            let value = value_node
+                .and_then(|node| node.as_number())
+                .and_then(|number| {
+                    number
+                        .as_i64()
+                        .map(i128::from)
+                        .or_else(|| number.as_u64().map(i128::from))
+                });
+            let Some(value) = value else {
+                let node = value_node.unwrap_or(item_node);
+                diags.push((
+                    Severity::Error,
+                    enumerator(item_name, "取值必须是整数"),
+                    node,
+                ));
+                continue;
+            };
+            let value_node = value_node.unwrap_or(item_node);
+            if let (Some((low, high)), Some(ty)) = (range, underlying)
+                && (value < low || value > high)
+            {
+                let code = ValidationCode::EnumValueOutOfRange(
+                    name.to_string(),
+                    item_name.to_string(),
+                    value.to_string(),
+                    ty.to_string(),
+                );
+                diags.push((Severity::Error, code, value_node));
+            } else if let Some(previous) = seen_values.insert(value, item_name) {
+                let code = ValidationCode::DuplicateEnumValue(
+                    name.to_string(),
+                    item_name.to_string(),
+                    previous.to_string(),
+                );
+                diags.push((Severity::Warning, code, value_node));
+            }
+        }
+    }
+    (enum_types, diags)
+}
+
+/// 取值显示名称中的问题：(原因, 节点)
+fn field_value_errors<'a>(
+    values_node: &'a jsv::Spanned<jsv::Value>,
+    base_type: Option<&str>,
+    bits: Option<u8>,
+) -> Vec<(String, &'a jsv::Spanned<jsv::Value>)> {
+    if values_node.is_null() {
+        return Vec::new();
+    }
+    let Some(values) = values_node.as_object() else {
+        return vec![("必须是对象".to_string(), values_node)];
+    };
+    let range = match base_type {
+        Some("bool" | "_Bool") => None,
+        Some(ty) => integer_range(ty, bits),
+        None => return Vec::new(),
+    };
+    let Some((low, high)) = range else {
+        return vec![("只有整数字段可以列出取值".to_string(), values_node)];
+    };
+
+    let mut errors = Vec::new();
+    let mut seen: HashMap<i128, &str> = HashMap::new();
+    for (key, name) in values.iter() {
+        match parse_value_key(key) {
+            None => errors.push((format!("'{}' 不是整数", key.as_str()), name)),
+            Some(value) if value < low || value > high => {
+                errors.push((format!("{} 超出了取值范围 {}..={}", value, low, high), name))
+            }
+            Some(value) => {
+                if let Some(previous) = seen.insert(value, key.as_str()) {
+                    errors.push((
+                        format!("'{}' 与 '{}' 是同一个值", key.as_str(), previous),
+                        name,
+                    ));
+                }
+            }
+        }
+        let blank = match name.get_ref() {
+            jsv::Value::String(text) => text.trim().is_empty(),
+            jsv::Value::Object(texts) => texts
+                .values()
+                .all(|text| text.as_string().is_none_or(|text| text.trim().is_empty())),
+            _ => {
+                errors.push((format!("'{}' 的显示名称必须是字符串", key.as_str()), name));
+                continue;
+            }
+        };
+        if blank {
+            errors.push((format!("'{}' 的显示名称为空", key.as_str()), name));
+        }
+        for (language, node) in invalid_comment_entries(name) {
+            errors.push((
+                format!("'{}' 的显示名称中 '{}' 无效", key.as_str(), language),
+                node,
+            ));
+        }
+    }
+    errors
+}
+
+/// 整数类型（或其位域）可以表示的范围，浮点与未知类型返回 None
+fn integer_range(ty: &str, bits: Option<u8>) -> Option<(i128, i128)> {
+    let bits = u32::from(bits.unwrap_or(c_type_to_bit_field_size(ty)? * 8));
+    if ty == "char" {
+        return Some((0, 127));
+    }
+    let unsigned = ty.starts_with("uint") || ty.starts_with("unsigned");
+    Some(if unsigned {
+        (0, (1i128 << bits) - 1)
+    } else {
+        (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+    })
+}
+
+/// 位域值：整数，或从表格导出时常见的字符串形式整数（如 `"4"`）
+fn bit_field_integer(node: &jsv::Spanned<jsv::Value>) -> Option<i64> {
+    match node.as_string() {
+        Some(text) => text.trim().parse().ok(),
+        None => node.as_number().and_then(|num| num.as_i64()),
+    }
+}
+
+/// 解析命令 ID：十进制，或以 `0x`/`0X` 开头的十六进制
+///
+/// 十六进制数字之间允许用 `_` 分隔以便阅读，例如 `0x01_04`
+pub fn parse_command_id(id: &str) -> Result<u16, ()> {
+    let clean = id.trim();
+    if let Some(hex) = clean
+        .strip_prefix("0x")
+        .or_else(|| clean.strip_prefix("0X"))
+    {
+        if hex.starts_with('_') || hex.ends_with('_') || hex.contains("__") {
+            return Err(());
+        }
+        let digits: String = hex.chars().filter(|&c| c != '_').collect();
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(());
+        }
+        u16::from_str_radix(&digits, 16).map_err(|_| ())
+    } else {
+        clean.parse::<u16>().map_err(|_| ())
+    }
+}
+
+/// 命令 ID 的规范写法，例如 `0x0104`
+pub fn format_command_id(id: u16) -> String {
+    format!("0x{:04X}", id)
+}
+
+/// 将任意合法写法的命令 ID 转换为规范写法，无法解析时返回 `None`
+pub fn canonical_command_id(id: &str) -> Option<String> {
+    parse_command_id(id).ok().map(format_command_id)
+}
+
+pub fn is_cpp_keyword(name: &str) -> bool {
+    CPP_KEYWORDS.contains(&name)
+}
+
+/// 以字母或下划线开头、只包含 ASCII 字母、数字与下划线的名称；不检查关键字
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn c_type_to_bit_field_size(ty: &str) -> Option<u8> {
+    match ty {
+        "unsigned int" | "signed int" | "int" => Some(4),
+        "_Bool" | "bool" => Some(1),
+
+        "unsigned char" | "signed char" | "char" => Some(1),
+        "unsigned short" | "signed short" | "short" => Some(2),
+        "unsigned long" | "signed long" | "long" => Some(8),
+        "unsigned long long" | "signed long long" | "long long" => Some(8),
+
+        "uint8_t" | "int8_t" => Some(1),
+        "uint16_t" | "int16_t" => Some(2),
+        "uint32_t" | "int32_t" => Some(4),
+        "uint64_t" | "int64_t" => Some(8),
+
+        "float" | "double" | "long double" => None,
+        "void*" | "char*" | "int*" => None,
+        "struct" | "union" => None,
+
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Severity;
+
+    #[test]
+    fn test_parse_command_id_hex_valid() {
+        assert_eq!(parse_command_id("0x0104"), Ok(260)); // 0x0104 = 260 decimal
+        assert_eq!(parse_command_id("0xABCD"), Ok(43981)); // 0xABCD = 43981 decimal
+        assert_eq!(parse_command_id("0xffff"), Ok(65535)); // Maximum 16-bit value
+        assert_eq!(parse_command_id("0x0"), Ok(0)); // Minimum hex value
+    }
+
+    #[test]
+    fn test_parse_command_id_decimal_valid() {
+        assert_eq!(parse_command_id("260"), Ok(260));
+        assert_eq!(parse_command_id("65535"), Ok(65535)); // Maximum 16-bit value
+        assert_eq!(parse_command_id("0"), Ok(0)); // Minimum decimal value
+    }
+
+    #[test]
+    fn test_parse_command_id_invalid_formats() {
+        // Test invalid hex values
+        assert!(parse_command_id("0xGHIJ").is_err()); // Invalid hex digits
+        assert!(parse_command_id("0x12345").is_err()); // More than 4 hex digits (exceeds 16-bit range)
+        assert!(parse_command_id("0xFFFFFFFF").is_err()); // Much bigger than 16-bit
+
+        // Test invalid decimal values
+        assert!(parse_command_id("65536").is_err()); // Exceeds 16-bit range
+        assert!(parse_command_id("invalid").is_err()); // Non-numeric
+        assert!(parse_command_id("").is_err()); // Empty string
+        assert!(parse_command_id("  ").is_err()); // Whitespace only
+    }
+
+    #[test]
+    fn test_parse_command_id_with_separators() {
+        assert_eq!(parse_command_id("0x01_04"), Ok(0x0104));
+        assert_eq!(parse_command_id("0X01_04"), Ok(0x0104));
+        assert_eq!(parse_command_id("0xA_B_C_D"), Ok(0xABCD));
+        assert!(parse_command_id("0x_0104").is_err());
+        assert!(parse_command_id("0x0104_").is_err());
+        assert!(parse_command_id("0x01__04").is_err());
+        assert!(parse_command_id("0x_").is_err());
+        assert!(parse_command_id("0x+1").is_err());
+        // 十进制不接受分隔符
+        assert!(parse_command_id("2_60").is_err());
+    }
+
+    #[test]
+    fn test_format_command_id() {
+        assert_eq!(format_command_id(0x0104), "0x0104");
+        assert_eq!(
+            format_command_id(parse_command_id("0x1_2ab").unwrap()),
+            "0x12AB"
+        );
+    }
+
+    #[test]
+    fn test_parse_command_id_case_insensitive_hex() {
+        assert_eq!(parse_command_id("0xABCD"), Ok(43981));
+        assert_eq!(parse_command_id("0xabcd"), Ok(43981));
+        assert_eq!(parse_command_id("0xAbCd"), Ok(43981));
+    }
+
+    #[test]
+    fn test_validate_valid_config() {
+        let json = r#"{
+            "packet_name": "ValidPacket",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "valid_field",
+                    "type": "uint8_t",
+                    "comment": "A valid field"
+                },
+                {
+                    "name": "another_field",
+                    "type": "float",
+                    "comment": "Another valid field"
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1); // Only the unaligned float warning
+        assert_eq!(
+            diags[0].code,
+            ValidationCode::UnalignedFloatInPacked("another_field".to_string(), 8, 4)
+        );
+        assert_eq!(diags[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_invalid_packet_name() {
+        let json = r#"{
+            "packet_name": "invalid-packet-name",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": []
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1); // Should have error only (not a valid identifier to check naming convention)
+        assert!(matches!(
+            diags[0].code,
+            ValidationCode::InvalidPacketName(_)
+        ));
+        assert_eq!(diags[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_validate_lowercase_packet_name_warning() {
+        let json = r#"{
+            "packet_name": "lowercase_packet",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": []
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1); // Should have naming convention warning
+        assert!(matches!(
+            diags[0].code,
+            ValidationCode::NamingConventionPacket(_)
+        ));
+        assert_eq!(diags[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_invalid_command_id() {
+        let json = r#"{
+            "packet_name": "ValidPacket",
+            "command_id": "invalid-id",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": []
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1); // Should have command ID error
+        assert!(matches!(diags[0].code, ValidationCode::InvalidCommandId(_)));
+        assert_eq!(diags[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_validate_invalid_field_name() {
+        let json = r#"{
+            "packet_name": "ValidPacket",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "invalid-field",
+                    "type": "uint8_t",
+                    "comment": "Invalid field"
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1); // Should have field name error
+        assert!(matches!(diags[0].code, ValidationCode::InvalidFieldName(_)));
+        assert_eq!(diags[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_validate_keyword_collision() {
+        let json = r#"{
+            "packet_name": "ValidPacket",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "class",
+                    "type": "uint8_t",
+                    "comment": "Class field"
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1); // Should have keyword collision error only (comment is present)
+        assert!(matches!(diags[0].code, ValidationCode::KeywordCollision(_)));
+        assert_eq!(diags[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_validate_duplicate_field_names() {
+        let json = r#"{
+            "packet_name": "ValidPacket",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "duplicate_field",
+                    "type": "uint8_t",
+                    "comment": "First field"
+                },
+                {
+                    "name": "duplicate_field",
+                    "type": "float",
+                    "comment": "Second field"
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 2); // Should have duplicate field error (only for the second occurrence)
+        assert!(matches!(
+            diags[0].code,
+            ValidationCode::DuplicateFieldName(_)
+        ));
+        assert_eq!(diags[0].severity, Severity::Error);
+        assert_eq!(
+            diags[1].code,
+            ValidationCode::UnalignedFloatInPacked("duplicate_field".to_string(), 8, 4)
+        );
+    }
+
+    #[test]
+    fn test_validate_missing_comment_warning() {
+        let json = r#"{
+            "packet_name": "ValidPacket",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "field_without_comment",
+                    "type": "uint8_t",
+                    "comment": null
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1); // Should have missing comment warning
+        assert!(matches!(diags[0].code, ValidationCode::MissingComment(_)));
+        assert_eq!(diags[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_empty_comment_warning() {
+        let json = r#"{
+            "packet_name": "ValidPacket",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "field_with_empty_comment",
+                    "type": "uint8_t",
+                    "comment": ""
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1); // Should have missing comment warning
+        assert!(matches!(diags[0].code, ValidationCode::MissingComment(_)));
+        assert_eq!(diags[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_whitespace_only_comment_warning() {
+        let json = r#"{
+            "packet_name": "ValidPacket",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "field_with_whitespace_comment",
+                    "type": "uint8_t",
+                    "comment": "   \t\n  "
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1); // Should have missing comment warning
+        assert!(matches!(diags[0].code, ValidationCode::MissingComment(_)));
+        assert_eq!(diags[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_valid_bit_field() {
+        let json = r#"{
+            "packet_name": "BitFieldPacket",
+            "command_id": "0x0105",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "status",
+                    "type": "uint8_t",
+                    "bit_field": 4,
+                    "comment": "Status field"
+                },
+                {
+                    "name": "flag",
+                    "type": "uint8_t",
+                    "bit_field": 3,
+                    "comment": "Flag field"
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert!(diags.is_empty()); // Should have no diagnostics for valid bit fields
+    }
+
+    #[test]
+    fn test_validate_invalid_bit_field_value() {
+        let json = r#"{
+            "packet_name": "InvalidBitFieldPacket",
+            "command_id": "0x0105",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "invalid_bit_field",
+                    "type": "uint8_t",
+                    "bit_field": -1,
+                    "comment": "Invalid bit_field value"
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1);
+        assert!(matches!(diags[0].code, ValidationCode::InvalidBitField(_)));
+        assert_eq!(diags[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_validate_field_limits() {
+        let limits = |ty: &str, extra: &str, limits: &str| {
+            let json = format!(
+                r#"{{"packet_name":"P","command_id":"1","comment":"C","fields":[
+                {{"name":"x","type":"{}"{},"comment":"X",{}}}]}}"#,
+                ty, extra, limits
+            );
+            validate(&json)
+                .into_iter()
+                .map(|d| d.code.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert!(limits("float", "", r#""min":-1.5,"max":1.5,"typical":0"#).is_empty());
+        assert!(limits("uint8_t[4]", "", r#""max":255"#).is_empty());
+        assert_eq!(
+            limits("uint8_t", "", r#""min":-1,"max":256"#),
+            vec![
+                "字段 'x' 的 min 无效：-1 超出了 uint8_t 的取值范围 0..=255",
+                "字段 'x' 的 max 无效：256 超出了 uint8_t 的取值范围 0..=255",
+            ]
+        );
+        assert_eq!(
+            limits("uint8_t", r#","bit_field":3"#, r#""max":8"#),
+            vec!["字段 'x' 的 max 无效：8 超出了 uint8_t : 3 的取值范围 0..=7"]
+        );
+        assert_eq!(
+            limits("int16_t", "", r#""min":10,"max":5,"typical":1.5"#),
+            vec![
+                "字段 'x' 的 typical 无效：整数字段的范围必须是整数",
+                "字段 'x' 的 min 无效：10 大于 max 5",
+            ]
+        );
+        assert_eq!(
+            limits("float", "", r#""min":0,"typical":-1"#),
+            vec!["字段 'x' 的 typical 无效：-1 小于 min 0"]
+        );
+        assert_eq!(
+            limits("bool", "", r#""max":"1""#),
+            vec!["字段 'x' 的 max 无效：必须是数字"]
+        );
+        assert_eq!(
+            limits("bool", "", r#""max":1"#),
+            vec!["字段 'x' 的 max 无效：布尔字段不能标注范围"]
+        );
+    }
+
+    #[test]
+    fn test_validate_field_values() {
+        let values = |ty: &str, extra: &str, values: &str| {
+            let json = format!(
+                r#"{{"packet_name":"P","command_id":"1","comment":"C","fields":[
+                {{"name":"x","type":"{}"{},"comment":"X","values":{}}}]}}"#,
+                ty, extra, values
+            );
+            validate(&json)
+                .into_iter()
+                .map(|d| d.code.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert!(
+            values(
+                "uint8_t",
+                "",
+                r#"{"0":"空闲","0x10":{"zh":"运行","en":"Run"}}"#
+            )
+            .is_empty()
+        );
+        assert!(values("int8_t[2]", "", r#"{"-128":"下限"}"#).is_empty());
+        assert_eq!(
+            values("float", "", r#"{"0":"零"}"#),
+            vec!["字段 'x' 的 values 无效：只有整数字段可以列出取值"]
+        );
+        assert_eq!(
+            values("uint8_t", "", r#"["空闲"]"#),
+            vec!["字段 'x' 的 values 无效：必须是对象"]
+        );
+        assert_eq!(
+            values(
+                "uint8_t",
+                r#","bit_field":2"#,
+                r#"{"idle":"空闲","4":"溢出","1":"一","0x01":" ","2":3}"#
+            ),
+            vec![
+                "字段 'x' 的 values 无效：'0x01' 的显示名称为空",
+                "字段 'x' 的 values 无效：'1' 与 '0x01' 是同一个值",
+                "字段 'x' 的 values 无效：'2' 的显示名称必须是字符串",
+                "字段 'x' 的 values 无效：4 超出了取值范围 0..=3",
+                "字段 'x' 的 values 无效：'idle' 不是整数",
+            ]
+        );
+        assert_eq!(
+            values("uint8_t", "", r#"{"0":{"Chinese":"空闲"}}"#),
+            vec!["字段 'x' 的 values 无效：'0' 的显示名称中 'Chinese' 无效"]
+        );
+    }
+
+    #[test]
+    fn test_validate_invalid_bit_field_type() {
+        let json = r#"{
+            "packet_name": "InvalidBitFieldType",
+            "command_id": "0x0105",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "float_bit_field",
+                    "type": "float",
+                    "bit_field": 5,
+                    "comment": "Bitfield on float type"
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1);
+        assert!(matches!(
+            diags[0].code,
+            ValidationCode::BitFieldOnInvalidType(_, _)
+        ));
+        assert_eq!(diags[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_validate_bit_field_length_overflow() {
+        let json = r#"{
+            "packet_name": "OverflowBitField",
+            "command_id": "0x0105",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "overflow_field",
+                    "type": "uint8_t",
+                    "bit_field": 10,
+                    "comment": "Bitfield exceeding type size"
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1);
+        assert!(matches!(
+            diags[0].code,
+            ValidationCode::BitFieldLengthOverflow(_, _, _)
+        ));
+        assert_eq!(diags[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_validate_bit_field_missing_packed_attr_warning() {
+        let json = r#"{
+            "packet_name": "UnpackedBitField",
+            "command_id": "0x0105",
+            "namespace": null,
+            "packed": false,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "status",
+                    "type": "uint8_t",
+                    "bit_field": 4,
+                    "comment": "Status field"
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 1);
+        assert!(matches!(
+            diags[0].code,
+            ValidationCode::BitFieldMissingPackedAttr(_)
+        ));
+        assert_eq!(diags[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_bit_field_straddle_boundary_without_packed_error() {
+        let json = r#"{
+            "packet_name": "StraddleBoundary",
+            "command_id": "0x0105",
+            "namespace": null,
+            "packed": false,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "field1",
+                    "type": "uint8_t",
+                    "bit_field": 5,
+                    "comment": "First field"
+                },
+                {
+                    "name": "field2",
+                    "type": "uint8_t",
+                    "bit_field": 4,
+                    "comment": "Second field"
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert!(diags.len() >= 2); // At least 2: one for missing packed attr (for each field) and one for straddle boundary
+        let cross_boundary_errors: Vec<_> = diags
+            .iter()
+            .filter(|d| {
+                matches!(
+                    d.code,
+                    ValidationCode::BitFieldStraddleBoundaryWithoutPacked(_, _, _, _, _)
+                )
+            })
+            .collect();
+        assert_eq!(cross_boundary_errors.len(), 1);
+        assert_eq!(cross_boundary_errors[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_validate_bit_field_straddle_boundary_warning() {
+        let json = r#"{
+            "packet_name": "FullBitField",
+            "command_id": "0x0105",
+            "namespace": null,
+            "packed": false,
+            "header_guard": null,
+            "fields": [
+                {
+                    "name": "full_field",
+                    "type": "uint8_t",
+                    "bit_field": 8,
+                    "comment": "Full bit_field"
+                }
+            ]
+        }"#;
+
+        let diags = validate(json);
+        assert_eq!(diags.len(), 2); // One for missing packed attribute, one for straddle boundary
+        let bit_field_warnings: Vec<_> = diags
+            .iter()
+            .filter(|d| matches!(d.code, ValidationCode::BitFieldStraddleBoundary(_)))
+            .collect();
+        assert_eq!(bit_field_warnings.len(), 1);
+        assert_eq!(bit_field_warnings[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_c_type_to_bit_field_size() {
+        // Test valid types
+        assert_eq!(c_type_to_bit_field_size("uint8_t"), Some(1));
+        assert_eq!(c_type_to_bit_field_size("int8_t"), Some(1));
+        assert_eq!(c_type_to_bit_field_size("uint16_t"), Some(2));
+        assert_eq!(c_type_to_bit_field_size("int16_t"), Some(2));
+        assert_eq!(c_type_to_bit_field_size("uint32_t"), Some(4));
+        assert_eq!(c_type_to_bit_field_size("int32_t"), Some(4));
+        assert_eq!(c_type_to_bit_field_size("uint64_t"), Some(8));
+        assert_eq!(c_type_to_bit_field_size("int64_t"), Some(8));
+        assert_eq!(c_type_to_bit_field_size("int"), Some(4));
+        assert_eq!(c_type_to_bit_field_size("char"), Some(1));
+        assert_eq!(c_type_to_bit_field_size("bool"), Some(1));
+
+        // Test invalid types
+        assert_eq!(c_type_to_bit_field_size("float"), None);
+        assert_eq!(c_type_to_bit_field_size("double"), None);
+        assert_eq!(c_type_to_bit_field_size("void*"), None);
+        assert_eq!(c_type_to_bit_field_size("invalid_type"), None);
+    }
+
+    #[test]
+    fn test_validate_multiple_packets_valid() {
+        let json = r#"[
+            {
+                "packet_name": "PacketA",
+                "command_id": "0x0101",
+                "namespace": null,
+                "packed": true,
+                "header_guard": "RPL_PACKETA_HPP",
+                "fields": [
+                    {
+                        "name": "field_a",
+                        "type": "uint8_t",
+                        "comment": "Field A"
+                    }
+                ]
+            },
+            {
+                "packet_name": "PacketB",
+                "command_id": "0x0102",
+                "namespace": "Test::Ns",
+                "packed": false,
+                "header_guard": "RPL_PACKETB_HPP",
+                "fields": [
+                    {
+                        "name": "field_b",
+                        "type": "uint16_t",
+                        "comment": "Field B"
+                    }
+                ]
+            }
+        ]"#;
+
+        let diags = validate_multiple(json);
+        assert!(diags.is_empty()); // Should have no diagnostics for valid packets
+    }
+
+    #[test]
+    fn test_validate_multiple_packets_with_errors() {
+        let json = r#"[
+            {
+                "packet_name": "ValidPacket",
+                "command_id": "0x0101",
+                "namespace": null,
+                "packed": true,
+                "header_guard": "RPL_VALIDPACKET_HPP",
+                "fields": [
+                    {
+                        "name": "valid_field",
+                        "type": "uint8_t",
+                        "comment": "Valid field"
+                    }
+                ]
+            },
+            {
+                "packet_name": "InvalidPacket",
+                "command_id": "invalid-command-id",
+                "namespace": null,
+                "packed": true,
+                "header_guard": "RPL_INVALIDPACKET_HPP",
+                "fields": [
+                    {
+                        "name": "field",
+                        "type": "uint8_t",
+                        "comment": "Field"
+                    }
+                ]
+            }
+        ]"#;
+
+        let diags = validate_multiple(json);
+        assert!(!diags.is_empty()); // Should have diagnostics because of invalid command ID
+
+        let error_count = diags
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .count();
+        assert_eq!(error_count, 1); // Should have 1 error for the invalid command ID
+    }
+
+    #[test]
+    fn test_validate_multiple_backwards_compatibility() {
+        // Test that single packet still works with validate_multiple
+        let json = r#"{
+            "packet_name": "SinglePacket",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": "RPL_SINGLEPACKET_HPP",
+            "fields": [
                 {
-                    "name": "another_field",
-                    "type": "float",
-                    "comment": "Another valid field"
+                    "name": "field",
+                    "type": "uint8_t",
+                    "comment": "A field"
+                }
+            ]
+        }"#;
+
+        let diags = validate_multiple(json);
+        assert!(diags.is_empty()); // Should have no diagnostics for valid single packet
+    }
+
+    #[test]
+    fn test_validate_multiple_packet_set() {
+        let json = r#"{
+            "protocol_version": "2.3.0",
+            "packets": [
+                {
+                    "packet_name": "PacketA",
+                    "command_id": "0x0101",
+                    "fields": [
+                        { "name": "field", "type": "uint8_t", "comment": "A field" }
+                    ]
+                }
+            ]
+        }"#;
+
+        let diags = validate_multiple(json);
+        assert!(diags.is_empty());
+    }
+
+    #[test]
+    fn test_validate_multiple_invalid_protocol_version() {
+        let json = r#"{
+            "protocol_version": "2.3",
+            "packets": [
+                {
+                    "packet_name": "PacketA",
+                    "command_id": "0x0101",
+                    "fields": [
+                        { "name": "field", "type": "uint8_t", "comment": "A field" }
+                    ]
+                }
+            ]
+        }"#;
+
+        let diags = validate_multiple(json);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].severity, Severity::Error);
+        assert_eq!(
+            diags[0].code,
+            ValidationCode::InvalidProtocolVersion("2.3".to_string())
+        );
+        let (offset, len) = diags[0].span.unwrap();
+        assert_eq!(&json[offset..offset + len], "\"2.3\"");
+    }
+
+    #[test]
+    fn test_validate_multiple_spans_and_cross_packet_rules() {
+        let json = r#"[
+  { "packet_name": "PacketA", "command_id": "260", "comment": "A",
+    "fields": [{ "name": "a", "type": "uint8_t", "comment": "A" }] },
+  { "packet_name": "PacketB", "command_id": "0x0104", "comment": "B",
+    "fields": [{ "name": "b", "type": "uint42_t", "comment": "B" }] },
+  { "packet_name": "PacketA", "command_id": "0x0105", "comment": "A",
+    "fields": [{ "name": "a", "type": "uint8_t", "comment": "A" }] }
+]"#;
+        let diags: Vec<_> = validate_multiple(json)
+            .into_iter()
+            .map(|d| {
+                let (offset, len) = d.span.unwrap();
+                (d.code, &json[offset..offset + len])
+            })
+            .collect();
+        // 诊断位置指向源文档中的包，而不是重新序列化后的文本
+        assert_eq!(
+            diags,
+            vec![
+                (
+                    ValidationCode::InvalidFieldType("b".to_string()),
+                    r#""uint42_t""#
+                ),
+                (
+                    ValidationCode::DuplicateCommandId("0x0104".to_string(), "PacketA".to_string()),
+                    r#""0x0104""#
+                ),
+                (
+                    ValidationCode::DuplicatePacketName("PacketA".to_string()),
+                    r#""PacketA""#
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_packet_comment() {
+        let json = r#"{
+            "packet_name": "CommentedPacket",
+            "command_id": "0x0104",
+            "namespace": null,
+            "packed": true,
+            "header_guard": null,
+            "comment": "这是一个带注释的数据包",
+            "fields": [
+                {
+                    "name": "field",
+                    "type": "uint8_t",
+                    "comment": "A field"
                 }
             ]
         }"#;
 
         let diags = validate(json);
-        assert!(diags.is_empty()); // Should have no diagnostics
+        assert_eq!(diags.len(), 0); // Should have no diagnostics
     }
 
     #[test]
-    fn test_validate_invalid_packet_name() {
+    fn test_validate_empty_packet_comment() {
         let json = r#"{
-            "packet_name": "invalid-packet-name",
+            "packet_name": "EmptyCommentPacket",
             "command_id": "0x0104",
             "namespace": null,
             "packed": true,
             "header_guard": null,
-            "fields": []
+            "comment": "",
+            "fields": [
+                {
+                    "name": "field",
+                    "type": "uint8_t",
+                    "comment": "A field"
+                }
+            ]
         }"#;
 
         let diags = validate(json);
-        assert_eq!(diags.len(), 1); // Should have error only (not a valid identifier to check naming convention)
-        assert!(matches!(
-            diags[0].code,
-            ValidationCode::InvalidPacketName(_)
-        ));
-        assert_eq!(diags[0].severity, Severity::Error);
+        assert_eq!(diags.len(), 1); // Should have empty comment warning
+        assert!(matches!(diags[0].code, ValidationCode::EmptyComment(_)));
+        assert_eq!(diags[0].severity, Severity::Warning);
     }
 
     #[test]
-    fn test_validate_lowercase_packet_name_warning() {
+    fn test_validate_localized_comments() {
         let json = r#"{
-            "packet_name": "lowercase_packet",
+            "packet_name": "Gimbal",
+            "command_id": "0x0104",
+            "comment": { "zh": "云台", "en": "Gimbal" },
+            "fields": [
+                { "name": "yaw", "type": "float", "comment": { "zh": "偏航角", "zh-TW": "偏航角" } },
+                { "name": "mode", "type": "uint8_t", "comment": { "zh": " ", "en": "" } },
+                { "name": "pitch", "type": "int8_t", "comment": { "Chinese": "俯仰角", "en": 1 } }
+            ]
+        }"#;
+        let diags = validate(json);
+        let codes: Vec<_> = diags.iter().map(|d| &d.code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                &ValidationCode::MissingComment("mode".to_string()),
+                &ValidationCode::InvalidLocalizedComment("Chinese".to_string()),
+                &ValidationCode::InvalidLocalizedComment("en".to_string()),
+            ]
+        );
+        let (offset, len) = diags[2].span.unwrap();
+        assert_eq!(&json[offset..offset + len], "1");
+
+        let diags =
+            validate(r#"{ "packet_name": "A", "command_id": "1", "comment": {}, "fields": [] }"#);
+        assert!(matches!(diags[0].code, ValidationCode::EmptyComment(_)));
+    }
+
+    #[test]
+    fn test_validate_whitespace_packet_comment() {
+        let json = r#"{
+            "packet_name": "WhitespaceCommentPacket",
             "command_id": "0x0104",
             "namespace": null,
             "packed": true,
             "header_guard": null,
-            "fields": []
+            "comment": "   ",
+            "fields": [
+                {
+                    "name": "field",
+                    "type": "uint8_t",
+                    "comment": "A field"
+                }
+            ]
         }"#;
 
         let diags = validate(json);
-        assert_eq!(diags.len(), 1); // Should have naming convention warning
-        assert!(matches!(
-            diags[0].code,
-            ValidationCode::NamingConventionPacket(_)
-        ));
+        assert_eq!(diags.len(), 1); // Should have empty comment warning
+        assert!(matches!(diags[0].code, ValidationCode::EmptyComment(_)));
         assert_eq!(diags[0].severity, Severity::Warning);
     }
 
+    // ---- Array Type Tests ----
+
+    #[test]
+    fn test_parse_array_type_valid() {
+        assert_eq!(parse_array_type("float[3]"), Some(("float", Some(3))));
+        assert_eq!(parse_array_type("uint8_t[10]"), Some(("uint8_t", Some(10))));
+        assert_eq!(parse_array_type("int[256]"), Some(("int", Some(256))));
+        assert_eq!(parse_array_type("double[1]"), Some(("double", Some(1))));
+    }
+
+    #[test]
+    fn test_parse_array_type_non_array() {
+        assert_eq!(parse_array_type("float"), Some(("float", None)));
+        assert_eq!(parse_array_type("uint8_t"), Some(("uint8_t", None)));
+        assert_eq!(parse_array_type("int"), Some(("int", None)));
+    }
+
+    #[test]
+    fn test_parse_array_type_invalid() {
+        // Empty size
+        assert_eq!(parse_array_type("float[]"), None);
+        // Invalid size
+        assert_eq!(parse_array_type("float[abc]"), None);
+        // Negative size
+        assert_eq!(parse_array_type("float[-1]"), None);
+        // Zero size
+        assert_eq!(parse_array_type("float[0]"), None);
+        // Missing closing bracket
+        assert_eq!(parse_array_type("float[3"), None);
+        // Empty base type
+        assert_eq!(parse_array_type("[3]"), None);
+    }
+
     #[test]
-    fn test_validate_invalid_command_id() {
+    fn test_validate_valid_array_type() {
         let json = r#"{
-            "packet_name": "ValidPacket",
-            "command_id": "invalid-id",
+            "packet_name": "ArrayPacket",
+            "command_id": "0x0104",
             "namespace": null,
             "packed": true,
             "header_guard": null,
-            "fields": []
+            "fields": [
+                {
+                    "name": "temperature",
+                    "type": "float[3]",
+                    "comment": "温度数组"
+                },
+                {
+                    "name": "data",
+                    "type": "uint8_t[8]",
+                    "comment": "数据数组"
+                }
+            ]
         }"#;
 
         let diags = validate(json);
-        assert_eq!(diags.len(), 1); // Should have command ID error
-        assert!(matches!(diags[0].code, ValidationCode::InvalidCommandId(_)));
-        assert_eq!(diags[0].severity, Severity::Error);
+        assert!(diags.is_empty());
     }
 
     #[test]
-    fn test_validate_invalid_field_name() {
+    fn test_validate_invalid_array_format() {
         let json = r#"{
-            "packet_name": "ValidPacket",
+            "packet_name": "InvalidArrayPacket",
             "command_id": "0x0104",
             "namespace": null,
             "packed": true,
             "header_guard": null,
             "fields": [
                 {
-                    "name": "invalid-field",
-                    "type": "uint8_t",
-                    "comment": "Invalid field"
+                    "name": "bad_array",
+                    "type": "float[]",
+                    "comment": "无效数组"
                 }
             ]
         }"#;
 
         let diags = validate(json);
-        assert_eq!(diags.len(), 1); // Should have field name error
-        assert!(matches!(diags[0].code, ValidationCode::InvalidFieldName(_)));
+        assert_eq!(diags.len(), 1);
+        assert!(matches!(diags[0].code, ValidationCode::InvalidArrayType(_)));
         assert_eq!(diags[0].severity, Severity::Error);
     }
 
     #[test]
-    fn test_validate_keyword_collision() {
+    fn test_validate_array_invalid_base_type() {
         let json = r#"{
-            "packet_name": "ValidPacket",
+            "packet_name": "InvalidBaseTypePacket",
             "command_id": "0x0104",
             "namespace": null,
             "packed": true,
             "header_guard": null,
             "fields": [
                 {
-                    "name": "class",
-                    "type": "uint8_t",
-                    "comment": "Class field"
+                    "name": "bad_base",
+                    "type": "invalid_type[3]",
+                    "comment": "无效基础类型"
                 }
             ]
         }"#;
 
         let diags = validate(json);
-        assert_eq!(diags.len(), 1); // Should have keyword collision error only (comment is present)
-        assert!(matches!(diags[0].code, ValidationCode::KeywordCollision(_)));
+        assert_eq!(diags.len(), 1);
+        assert!(matches!(diags[0].code, ValidationCode::InvalidFieldType(_)));
         assert_eq!(diags[0].severity, Severity::Error);
     }
 
     #[test]
-    fn test_validate_duplicate_field_names() {
+    fn test_validate_array_with_bitfield_error() {
         let json = r#"{
-            "packet_name": "ValidPacket",
+            "packet_name": "ArrayBitFieldPacket",
             "command_id": "0x0104",
             "namespace": null,
             "packed": true,
             "header_guard": null,
             "fields": [
                 {
-                    "name": "duplicate_field",
-                    "type": "uint8_t",
-                    "comment": "First field"
-                },
-                {
-                    "name": "duplicate_field",
-                    "type": "float",
-                    "comment": "Second field"
+                    "name": "array_field",
+                    "type": "uint8_t[3]",
+                    "bit_field": 4,
+                    "comment": "数组位域"
                 }
             ]
         }"#;
 
         let diags = validate(json);
-        assert_eq!(diags.len(), 1); // Should have duplicate field error (only for the second occurrence)
+        assert_eq!(diags.len(), 1);
+        assert!(matches!(diags[0].code, ValidationCode::BitFieldOnArray(_)));
+        assert_eq!(diags[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_validate_array_len() {
+        let packet = |field: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "ArrayLenPacket",
+                    "command_id": "0x0104",
+                    "comment": "A",
+                    "fields": [{}]
+                }}"#,
+                field
+            )
+        };
+        let codes = |json: &str| -> Vec<ValidationCode> {
+            validate(json).into_iter().map(|d| d.code).collect()
+        };
+
+        assert!(
+            codes(&packet(
+                r#"{ "name": "data", "type": "uint8_t", "array_len": 16, "comment": "D" }"#
+            ))
+            .is_empty()
+        );
+        for len in ["0", "-1", "1.5", "\"16\"", "4294967296"] {
+            assert_eq!(
+                codes(&packet(&format!(
+                    r#"{{ "name": "data", "type": "uint8_t", "array_len": {len}, "comment": "D" }}"#
+                ))),
+                vec![ValidationCode::InvalidArrayLen("data".to_string())],
+                "{}",
+                len
+            );
+        }
+        let json =
+            packet(r#"{ "name": "data", "type": "uint8_t[4]", "array_len": 4, "comment": "D" }"#);
+        let diags = validate(&json);
+        assert_eq!(
+            diags.iter().map(|d| d.code.clone()).collect::<Vec<_>>(),
+            vec![ValidationCode::ArrayLenConflict(
+                "data".to_string(),
+                "uint8_t[4]".to_string()
+            )]
+        );
+        let (start, len) = diags[0].span.unwrap();
+        assert_eq!(&json[start..start + len], "4");
+
+        // 与 Type[N] 一样不能使用位域，并计入载荷
+        assert_eq!(
+            codes(&packet(
+                r#"{ "name": "flags", "type": "uint8_t", "array_len": 2, "bit_field": 3, "comment": "F" }"#
+            )),
+            vec![ValidationCode::BitFieldOnArray("flags".to_string())]
+        );
         assert!(matches!(
+            codes(&packet(
+                r#"{ "name": "data", "type": "uint8_t", "array_len": 70000, "comment": "D" }"#
+            ))[..],
+            [ValidationCode::ArrayExceedsPayloadBudget(..)]
+        ));
+    }
+
+    #[test]
+    fn test_validate_enums() {
+        let packet = |enums: &str, field: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "EnumPacket",
+                    "command_id": "0x0104",
+                    "comment": "E",
+                    "enums": [{}],
+                    "fields": [{}]
+                }}"#,
+                enums, field
+            )
+        };
+        let mode = r#"{ "name": "Mode", "type": "uint8_t", "values": [
+            { "name": "idle", "value": 0, "comment": "空闲" },
+            { "name": "run", "value": 1 }
+        ] }"#;
+        let codes = |json: &str| -> Vec<ValidationCode> {
+            validate(json).into_iter().map(|d| d.code).collect()
+        };
+        let s = |text: &str| text.to_string();
+
+        assert!(
+            codes(&packet(
+                mode,
+                r#"{ "name": "mode", "type": "Mode", "min": 0, "max": 1, "comment": "M" },
+                   { "name": "history", "type": "Mode", "array_len": 4, "comment": "H" }"#
+            ))
+            .is_empty()
+        );
+
+        // 枚举定义本身的问题
+        let cases = [
+            (
+                r#"{ "name": "1Mode", "type": "uint8_t", "values": [{ "name": "a", "value": 0 }] }"#,
+                ValidationCode::InvalidEnum(
+                    s("1Mode"),
+                    s("名称必须是合法的 C++ 标识符且不能是关键字"),
+                ),
+            ),
+            (
+                r#"{ "name": "uint8_t", "type": "uint8_t", "values": [{ "name": "a", "value": 0 }] }"#,
+                ValidationCode::InvalidEnum(s("uint8_t"), s("名称不能与基础类型相同")),
+            ),
+            (
+                r#"{ "name": "Mode", "type": "float", "values": [{ "name": "a", "value": 0 }] }"#,
+                ValidationCode::InvalidEnum(s("Mode"), s("底层类型必须是整数类型，例如 uint8_t")),
+            ),
+            (
+                r#"{ "name": "Mode", "type": "uint8_t", "values": [] }"#,
+                ValidationCode::InvalidEnum(s("Mode"), s("至少需要一个枚举项")),
+            ),
+            (
+                r#"{ "name": "Mode", "type": "uint8_t", "values": [{ "name": "class", "value": 0 }] }"#,
+                ValidationCode::InvalidEnumerator(
+                    s("Mode"),
+                    s("class"),
+                    s("名称必须是合法的 C++ 标识符且不能是关键字"),
+                ),
+            ),
+            (
+                r#"{ "name": "Mode", "type": "uint8_t", "values": [{ "name": "a", "value": 0.5 }] }"#,
+                ValidationCode::InvalidEnumerator(s("Mode"), s("a"), s("取值必须是整数")),
+            ),
+            (
+                r#"{ "name": "Mode", "type": "uint8_t", "values": [
+                    { "name": "a", "value": 0 }, { "name": "a", "value": 1 }] }"#,
+                ValidationCode::DuplicateEnumerator(s("Mode"), s("a")),
+            ),
+            (
+                r#"{ "name": "Mode", "type": "int8_t", "values": [{ "name": "a", "value": 128 }] }"#,
+                ValidationCode::EnumValueOutOfRange(s("Mode"), s("a"), s("128"), s("int8_t")),
+            ),
+        ];
+        for (def, expected) in cases {
+            assert_eq!(
+                codes(&packet(
+                    def,
+                    r#"{ "name": "id", "type": "uint8_t", "comment": "I" }"#
+                )),
+                vec![expected],
+                "{}",
+                def
+            );
+        }
+        assert_eq!(
+            codes(&packet(
+                &format!("{}, {}", mode, mode),
+                r#"{ "name": "mode", "type": "Mode", "comment": "M" }"#
+            )),
+            vec![ValidationCode::DuplicateEnum(s("Mode"))]
+        );
+
+        // 取值相同只是警告，诊断指向后出现的取值
+        let json = packet(
+            r#"{ "name": "Mode", "type": "uint8_t", "values": [
+                { "name": "start", "value": 1 }, { "name": "run", "value": 1 }] }"#,
+            r#"{ "name": "mode", "type": "Mode", "comment": "M" }"#,
+        );
+        let diags = validate(&json);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(
             diags[0].code,
-            ValidationCode::DuplicateFieldName(_)
+            ValidationCode::DuplicateEnumValue(s("Mode"), s("run"), s("start"))
+        );
+        assert_eq!(diags[0].severity, Severity::Warning);
+
+        // 枚举字段按底层类型检查范围，不能使用位域或 values
+        assert_eq!(
+            codes(&packet(
+                mode,
+                r#"{ "name": "mode", "type": "Mode", "max": 300, "comment": "M" }"#
+            )),
+            vec![ValidationCode::InvalidFieldLimit(
+                s("mode"),
+                s("max"),
+                s("300 超出了 uint8_t 的取值范围 0..=255")
+            )]
+        );
+        assert!(matches!(
+            codes(&packet(
+                mode,
+                r#"{ "name": "mode", "type": "Mode", "bit_field": 2, "comment": "M" }"#
+            ))[..],
+            [ValidationCode::BitFieldOnInvalidType(..)]
         ));
-        assert_eq!(diags[0].severity, Severity::Error);
+        assert!(matches!(
+            codes(&packet(
+                mode,
+                r#"{ "name": "mode", "type": "Mode", "values": { "0": "空闲" }, "comment": "M" }"#
+            ))[..],
+            [ValidationCode::InvalidFieldValues(..)]
+        ));
+        assert_eq!(
+            codes(&packet(
+                mode,
+                r#"{ "name": "mode", "type": "Mdoe", "comment": "M" }"#
+            )),
+            vec![ValidationCode::InvalidFieldType(s("mode"))]
+        );
     }
 
     #[test]
-    fn test_validate_missing_comment_warning() {
+    fn test_validate_packet_references() {
+        let json = r#"[
+  { "packet_name": "Gimbal", "command_id": "1", "comment": "G",
+    "fields": [{ "name": "imu", "type": "Imu[2]", "comment": "I" },
+               { "name": "pose", "type": "Pos", "comment": "P" }] },
+  { "packet_name": "Imu", "command_id": "2", "comment": "I",
+    "fields": [{ "name": "owner", "type": "Gimbal", "comment": "O" }] },
+  { "packet_name": "Pose", "command_id": "3", "comment": "P",
+    "fields": [{ "name": "next", "type": "Pose", "comment": "N" },
+               { "name": "yaw", "type": "flaot", "comment": "Y" }] }
+]"#;
+        let diags: Vec<_> = validate_multiple(json)
+            .into_iter()
+            .map(|d| {
+                let (offset, len) = d.span.unwrap();
+                (d.code, &json[offset..offset + len], d.help)
+            })
+            .collect();
+        let s = |text: &str| text.to_string();
+        assert_eq!(
+            diags,
+            vec![
+                (
+                    ValidationCode::InvalidFieldType(s("pose")),
+                    r#""Pos""#,
+                    Some(s("是否应为 'Pose'？"))
+                ),
+                (
+                    ValidationCode::InvalidFieldType(s("yaw")),
+                    r#""flaot""#,
+                    Some(s("是否应为 'float'？"))
+                ),
+                (
+                    ValidationCode::PacketReferenceCycle(s("Gimbal → Imu → Gimbal")),
+                    r#""Gimbal""#,
+                    None
+                ),
+                (
+                    ValidationCode::PacketReferenceCycle(s("Pose → Pose")),
+                    r#""Pose""#,
+                    None
+                ),
+            ]
+        );
+
+        // 单个包中只能引用 dependencies 给出的包
+        let packet = |extra: &str, fields: &str| {
+            format!(
+                r#"{{ "packet_name": "Gimbal", "command_id": "1", "comment": "G", {}
+                    "fields": [{}],
+                    "dependencies": [{{ "packet_name": "Imu", "command_id": "2", "packed": false,
+                        "fields": [{{ "name": "id", "type": "uint8_t" }}, {{ "name": "yaw", "type": "float" }}] }}] }}"#,
+                extra, fields
+            )
+        };
+        let codes = |json: &str, options: &ValidateOptions| -> Vec<ValidationCode> {
+            validate_with_options(json, options)
+                .into_iter()
+                .map(|d| d.code)
+                .collect()
+        };
+        let imu = r#"{ "name": "imu", "type": "Imu", "comment": "I" }"#;
+        let defaults = ValidateOptions::default();
+        assert!(codes(&packet("", imu), &defaults).is_empty());
+        assert_eq!(
+            codes(
+                &packet("", r#"{ "name": "pose", "type": "Pose", "comment": "P" }"#),
+                &defaults
+            ),
+            vec![ValidationCode::InvalidFieldType(s("pose"))]
+        );
+        // 引用的包按其对齐参与 align 检查
+        assert_eq!(
+            codes(&packet(r#""packed": false, "align": 2,"#, imu), &defaults),
+            vec![ValidationCode::AlignBelowNatural(2, 4)]
+        );
+        assert_eq!(
+            codes(&packet(r#""serialize": true,"#, imu), &defaults),
+            vec![ValidationCode::SerializeUnsupportedField(
+                s("imu"),
+                s("引用其他包的字段没有逐个标量的读写函数")
+            )]
+        );
+        assert_eq!(
+            codes(
+                &packet(
+                    "",
+                    &format!(
+                        r#"{{ "name": "flag", "type": "uint8_t", "bit_field": 1, "comment": "F" }}, {}"#,
+                        imu
+                    )
+                ),
+                &defaults
+            ),
+            vec![ValidationCode::PacketReferenceInBitstream(s("imu"))]
+        );
+        let rust = ValidateOptions {
+            targets: vec![Target::Cpp, Target::Rust],
+            ..Default::default()
+        };
+        assert_eq!(
+            codes(&packet("", imu), &rust),
+            vec![ValidationCode::UnsupportedTypeForTarget(
+                s("imu"),
+                s("Imu"),
+                s("Rust"),
+                s("引用其他包的字段只支持 C++")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_validate_array_valid_with_valid_base_types() {
+        // 测试各种支持的数组类型
         let json = r#"{
-            "packet_name": "ValidPacket",
+            "packet_name": "ValidArraysPacket",
             "command_id": "0x0104",
             "namespace": null,
             "packed": true,
             "header_guard": null,
             "fields": [
-                {
-                    "name": "field_without_comment",
-                    "type": "uint8_t",
-                    "comment": null
-                }
+                { "name": "float_arr", "type": "float[3]", "comment": "浮点数组" },
+                { "name": "double_arr", "type": "double[2]", "comment": "双精度数组" },
+                { "name": "uint8_arr", "type": "uint8_t[16]", "comment": "8位无符号数组" },
+                { "name": "int32_arr", "type": "int32_t[8]", "comment": "32位有符号数组" }
             ]
         }"#;
 
         let diags = validate(json);
-        assert_eq!(diags.len(), 1); // Should have missing comment warning
-        assert!(matches!(diags[0].code, ValidationCode::MissingComment(_)));
-        assert_eq!(diags[0].severity, Severity::Warning);
+        // 类型都有效，只有 double 数组位于第 12 字节、未按 8 字节对齐
+        assert_eq!(diags.len(), 1);
+        assert_eq!(
+            diags[0].code,
+            ValidationCode::UnalignedFloatInPacked("double_arr".to_string(), 96, 8)
+        );
+    }
+
+    fn flag_fields(count: usize, ty: &str, bits: u8) -> String {
+        (0..count)
+            .map(|i| {
+                format!(
+                    r#"{{ "name": "flag{i}", "type": "{ty}", "bit_field": {bits}, "comment": "F" }}"#
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",")
     }
 
     #[test]
-    fn test_validate_empty_comment_warning() {
+    fn test_validate_too_many_bit_fields_per_unit_warning() {
+        let json = format!(
+            r#"{{
+                "packet_name": "Flags",
+                "command_id": "0x0101",
+                "fields": [ {} ]
+            }}"#,
+            flag_fields(10, "uint32_t", 1)
+        );
+
+        let result = validate(&json);
+        let warnings: Vec<_> = result
+            .iter()
+            .filter(|d| matches!(d.code, ValidationCode::TooManyBitFieldsPerUnit(..)))
+            .collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].code,
+            ValidationCode::TooManyBitFieldsPerUnit("flag0".to_string(), 10, 8)
+        );
+        assert_eq!(warnings[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_bit_fields_split_across_units() {
+        // 8 个 uint8_t 单比特位域恰好占满一个存储单元，第 9 个开启新的存储单元
+        let json = format!(
+            r#"{{
+                "packet_name": "Flags",
+                "command_id": "0x0101",
+                "fields": [ {} ]
+            }}"#,
+            flag_fields(9, "uint8_t", 1)
+        );
+
+        let result = validate(&json);
+        assert!(
+            !result
+                .iter()
+                .any(|d| matches!(d.code, ValidationCode::TooManyBitFieldsPerUnit(..)))
+        );
+    }
+
+    #[test]
+    fn test_validate_bit_fields_per_unit_threshold_configurable() {
+        let json = format!(
+            r#"{{
+                "packet_name": "Flags",
+                "command_id": "0x0101",
+                "fields": [ {} ]
+            }}"#,
+            flag_fields(4, "uint8_t", 2)
+        );
+
+        assert!(
+            !validate(&json)
+                .iter()
+                .any(|d| matches!(d.code, ValidationCode::TooManyBitFieldsPerUnit(..)))
+        );
+
+        let options = ValidateOptions {
+            max_bit_fields_per_unit: 3,
+            ..Default::default()
+        };
+        assert!(
+            validate_with_options(&json, &options)
+                .iter()
+                .any(|d| d.code
+                    == ValidationCode::TooManyBitFieldsPerUnit("flag0".to_string(), 4, 3))
+        );
+    }
+
+    #[test]
+    fn test_validate_tiny_bit_fields_with_float_warning() {
+        let json = format!(
+            r#"{{
+                "packet_name": "Mixed",
+                "command_id": "0x0101",
+                "fields": [
+                    {{ "name": "speed", "type": "float", "comment": "S" }},
+                    {}
+                ]
+            }}"#,
+            flag_fields(5, "uint8_t", 1)
+        );
+
+        let result = validate(&json);
+        assert!(
+            result
+                .iter()
+                .any(|d| d.code == ValidationCode::TinyBitFieldsWithFloat(5, 4))
+        );
+
+        // 无浮点字段时不警告
+        let json = format!(
+            r#"{{
+                "packet_name": "Mixed",
+                "command_id": "0x0101",
+                "fields": [ {} ]
+            }}"#,
+            flag_fields(5, "uint8_t", 1)
+        );
+        assert!(
+            !validate(&json)
+                .iter()
+                .any(|d| matches!(d.code, ValidationCode::TinyBitFieldsWithFloat(..)))
+        );
+    }
+
+    #[test]
+    fn test_validate_mixed_bit_field_types_for_msvc() {
         let json = r#"{
-            "packet_name": "ValidPacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
+            "packet_name": "Mixed",
+            "command_id": "0x0101",
             "fields": [
-                {
-                    "name": "field_with_empty_comment",
-                    "type": "uint8_t",
-                    "comment": ""
-                }
+                { "name": "mode", "type": "uint8_t", "bit_field": 3, "comment": "M" },
+                { "name": "flag", "type": "int8_t", "bit_field": 1, "comment": "F" },
+                { "name": "level", "type": "uint16_t", "bit_field": 9, "comment": "L" }
             ]
         }"#;
+        let mixed = |options: &ValidateOptions| -> Vec<ValidationCode> {
+            validate_with_options(json, options)
+                .into_iter()
+                .map(|d| d.code)
+                .filter(|code| matches!(code, ValidationCode::MixedBitFieldTypes(..)))
+                .collect()
+        };
 
-        let diags = validate(json);
-        assert_eq!(diags.len(), 1); // Should have missing comment warning
-        assert!(matches!(diags[0].code, ValidationCode::MissingComment(_)));
-        assert_eq!(diags[0].severity, Severity::Warning);
+        assert!(mixed(&ValidateOptions::default()).is_empty());
+        let options = ValidateOptions {
+            compiler: Compiler::Msvc,
+            ..Default::default()
+        };
+        // 大小相同、符号不同的 uint8_t 与 int8_t 可以共享存储单元
+        assert_eq!(
+            mixed(&options),
+            vec![ValidationCode::MixedBitFieldTypes(
+                "flag".to_string(),
+                "level".to_string(),
+                "MSVC".to_string()
+            )]
+        );
     }
 
     #[test]
-    fn test_validate_whitespace_only_comment_warning() {
+    fn test_validate_plain_bit_field_signedness_for_armcc() {
         let json = r#"{
-            "packet_name": "ValidPacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
+            "packet_name": "Offsets",
+            "command_id": "0x0101",
             "fields": [
-                {
-                    "name": "field_with_whitespace_comment",
-                    "type": "uint8_t",
-                    "comment": "   \t\n  "
-                }
+                { "name": "x", "type": "int", "bit_field": 5, "comment": "X" },
+                { "name": "y", "type": "int32_t", "bit_field": 5, "comment": "Y" },
+                { "name": "z", "type": "signed int", "bit_field": 5, "comment": "Z" }
             ]
         }"#;
+        let plain = |compiler| -> Vec<ValidationCode> {
+            let options = ValidateOptions {
+                compiler,
+                ..Default::default()
+            };
+            validate_with_options(json, &options)
+                .into_iter()
+                .map(|d| d.code)
+                .filter(|code| matches!(code, ValidationCode::PlainBitFieldUnsigned(..)))
+                .collect()
+        };
 
-        let diags = validate(json);
-        assert_eq!(diags.len(), 1); // Should have missing comment warning
-        assert!(matches!(diags[0].code, ValidationCode::MissingComment(_)));
+        assert!(plain(Compiler::Gcc).is_empty());
+        for compiler in [Compiler::Armcc, Compiler::ArmccPragma] {
+            assert_eq!(
+                plain(compiler),
+                vec![ValidationCode::PlainBitFieldUnsigned(
+                    "x".to_string(),
+                    "int".to_string(),
+                    "ARMCC 5".to_string()
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_reserved_entries() {
+        let packet = |entries: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "Third",
+                    "command_id": "0x0201",
+                    "comment": "T",
+                    "fields": [
+                        {{ "name": "id", "type": "uint32_t", "comment": "I" }},
+                        {}
+                    ]
+                }}"#,
+                entries
+            )
+        };
+        let reserved_errors = |json: &str| -> Vec<String> {
+            validate(json)
+                .into_iter()
+                .filter_map(|d| match d.code {
+                    ValidationCode::InvalidReserved(reason) => Some(reason),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        // 保留条目不需要注释，之后的字段从保留字节之后继续
+        let valid = packet(
+            r#"{ "reserved": 3 },
+               { "name": "a", "type": "uint8_t", "offset": 7, "comment": "A" }"#,
+        );
+        assert!(validate(&valid).is_empty());
+
+        let cases = [
+            (r#"{ "reserved": 0 }"#, "reserved 必须是正整数"),
+            (r#"{ "reserved": "3" }"#, "reserved 必须是正整数"),
+            (
+                r#"{ "reserved": 1, "bit_field": 4 }"#,
+                "保留条目不能使用位域",
+            ),
+            (
+                r#"{ "reserved": 1, "name": "pad" }"#,
+                "保留条目只能包含 reserved 与 comment",
+            ),
+            (
+                r#"{ "name": "_reserved0", "type": "uint8_t", "comment": "R" }, { "reserved": 1 }"#,
+                "展开得到的字段 '_reserved0' 与已有的名称重复",
+            ),
+        ];
+        for (entries, reason) in cases {
+            assert_eq!(
+                reserved_errors(&packet(entries)),
+                vec![reason],
+                "{}",
+                entries
+            );
+        }
+
+        // 保留字节计入载荷，与已有字段重名时报告后出现的字段
+        let json = packet(
+            r#"{ "reserved": 4 }, { "name": "a", "type": "uint8_t", "offset": 6, "comment": "A" }"#,
+        );
+        assert!(
+            validate(&json)
+                .iter()
+                .any(|d| matches!(d.code, ValidationCode::InvalidFieldOffset(..)))
+        );
+        let json = packet(
+            r#"{ "reserved": 4 }, { "name": "_reserved0", "type": "uint8_t", "comment": "R" }"#,
+        );
+        assert!(
+            validate(&json)
+                .iter()
+                .any(|d| d.code == ValidationCode::DuplicateFieldName("_reserved0".to_string()))
+        );
+        let json = packet(r#"{ "reserved": 70000 }"#);
+        assert!(
+            validate(&json)
+                .iter()
+                .any(|d| matches!(d.code, ValidationCode::ArrayExceedsPayloadBudget(..)))
+        );
+    }
+
+    #[test]
+    fn test_validate_field_offsets() {
+        let packet = |extra: &str, fields: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "Third",
+                    "command_id": "0x0201",
+                    "comment": "T",
+                    {}
+                    "fields": [
+                        {{ "name": "id", "type": "uint32_t", "comment": "I" }},
+                        {}
+                    ]
+                }}"#,
+                extra, fields
+            )
+        };
+        let offset_errors = |json: &str| -> Vec<String> {
+            validate(json)
+                .into_iter()
+                .filter_map(|d| match d.code {
+                    ValidationCode::InvalidFieldOffset(_, reason) => Some(reason),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let valid = packet(
+            "",
+            r#"{ "name": "a", "type": "uint8_t", "offset": 4, "comment": "A" },
+               { "name": "b", "type": "float", "offset": 8, "comment": "B" }"#,
+        );
+        assert!(validate(&valid).is_empty());
+
+        let cases = [
+            (
+                "",
+                r#"{ "name": "a", "type": "uint8_t", "offset": 2, "comment": "A" }"#,
+                "与前面的字段重叠，前面的字段结束于第 4 字节",
+            ),
+            (
+                "",
+                r#"{ "name": "a", "type": "uint8_t", "offset": -1, "comment": "A" }"#,
+                "必须是非负整数",
+            ),
+            (
+                r#""packed": false,"#,
+                r#"{ "name": "a", "type": "uint8_t", "offset": 8, "comment": "A" }"#,
+                "只能用于紧凑结构体",
+            ),
+            (
+                "",
+                r#"{ "name": "a", "type": "uint8_t", "bit_field": 3, "offset": 8, "comment": "A" }"#,
+                "不能用于位域字段",
+            ),
+            (
+                "",
+                r#"{ "name": "a", "type": "uint8_t", "bit_field": 3, "comment": "A" },
+                   { "name": "b", "type": "uint8_t", "offset": 8, "comment": "B" }"#,
+                "之前的位域没有填满整字节",
+            ),
+            (
+                "",
+                r#"{ "name": "a", "type": "uint8_t", "offset": 70000, "comment": "A" }"#,
+                "超出了 65535 字节的载荷上限",
+            ),
+            (
+                "",
+                r#"{ "name": "a", "type": "uint8_t", "offset": 8, "comment": "A" },
+                   { "name": "reserved_4", "type": "uint8_t", "comment": "R" }"#,
+                "自动填充的保留字段 'reserved_4' 与已有的名称重复",
+            ),
+        ];
+        for (extra, fields, reason) in cases {
+            assert_eq!(
+                offset_errors(&packet(extra, fields)),
+                vec![reason],
+                "{}",
+                fields
+            );
+        }
+
+        // 之后的字段从固定偏移处继续累计（保留条目同样占用字节）
+        let json = packet(
+            "",
+            r#"{ "name": "a", "type": "uint8_t", "offset": 8, "comment": "A" },
+               { "name": "b", "type": "float", "comment": "B" }"#,
+        );
+        assert!(
+            validate(&json)
+                .iter()
+                .any(|d| matches!(d.code, ValidationCode::UnalignedFloatInPacked(_, 72, 4)))
+        );
+    }
+
+    #[test]
+    fn test_validate_bitfield_layout() {
+        let packet = |layout: &str, bit_field: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "Status",
+                    "command_id": "0x0101",
+                    "comment": "S",
+                    "bitfield_layout": {},
+                    "fields": [{{ "name": "mode", "type": "uint8_t", {} "comment": "M" }}]
+                }}"#,
+                layout, bit_field
+            )
+        };
+        let codes = |json: &str| -> Vec<ValidationCode> {
+            validate(json).into_iter().map(|d| d.code).collect()
+        };
+
+        assert!(codes(&packet(r#""lsb_first""#, r#""bit_field": 3,"#)).is_empty());
+        assert!(codes(&packet("null", r#""bit_field": 3,"#)).is_empty());
+        // 没有位域时位序不影响布局
+        assert!(codes(&packet(r#""msb_first""#, "")).is_empty());
+        assert_eq!(
+            codes(&packet(r#""msb_first""#, r#""bit_field": 3,"#)),
+            vec![ValidationCode::BitFieldOrderMismatch(
+                "Status".to_string(),
+                "msb_first".to_string(),
+                "GCC".to_string(),
+                "lsb_first".to_string()
+            )]
+        );
+        assert_eq!(
+            codes(&packet(r#""big_endian""#, "")),
+            vec![ValidationCode::InvalidBitFieldLayout(
+                "big_endian".to_string()
+            )]
+        );
+        assert_eq!(
+            codes(&packet("1", "")),
+            vec![ValidationCode::InvalidBitFieldLayout(
+                "<non-string>".to_string()
+            )]
+        );
+
+        let json = packet(r#""msb_first""#, r#""bit_field": 3,"#);
+        let diag = validate(&json).remove(0);
+        assert_eq!(diag.severity, Severity::Warning);
+        let (start, len) = diag.span.unwrap();
+        assert_eq!(&json[start..start + len], r#""msb_first""#);
+    }
+
+    #[test]
+    fn test_validate_native_bit_fields_across_compilers() {
+        let packet = |layout: &str, bit_field: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "Status",
+                    "command_id": "0x0101",
+                    "comment": "S",
+                    {}
+                    "fields": [
+                        {{ "name": "speed", "type": "float", "comment": "V" }},
+                        {{ "name": "mode", "type": "uint8_t", {} "comment": "M" }}
+                    ]
+                }}"#,
+                layout, bit_field
+            )
+        };
+        let codes = |json: &str, profiles: &[Compiler]| -> Vec<ValidationCode> {
+            let options = ValidateOptions {
+                compiler_profiles: profiles.to_vec(),
+                ..ValidateOptions::default()
+            };
+            validate_with_options(json, &options)
+                .into_iter()
+                .map(|d| d.code)
+                .collect()
+        };
+
+        let bits = packet("", r#""bit_field": 3,"#);
+        let profiles = [Compiler::Armcc, Compiler::Msvc, Compiler::ArmccPragma];
+        assert_eq!(
+            codes(&bits, &profiles),
+            vec![ValidationCode::NativeBitFieldsAcrossCompilers(
+                "Status".to_string(),
+                "ARMCC 5、MSVC".to_string()
+            )]
+        );
+        let diag = validate_with_options(
+            &bits,
+            &ValidateOptions {
+                compiler_profiles: profiles.to_vec(),
+                ..ValidateOptions::default()
+            },
+        )
+        .remove(0);
+        assert_eq!(diag.severity, Severity::Warning);
+        let (start, len) = diag.span.unwrap();
+        assert!(bits[start..start + len].contains(r#""bit_field": 3"#));
+
+        // 只有一个编译器、同一编译器的两种写法、没有位域或已声明位序时不提示
+        assert!(codes(&bits, &[]).is_empty());
+        assert!(codes(&bits, &[Compiler::Gcc]).is_empty());
+        assert!(codes(&bits, &[Compiler::Armcc, Compiler::ArmccPragma]).is_empty());
+        assert!(codes(&packet("", ""), &profiles).is_empty());
+        let declared = packet(r#""bitfield_layout": "lsb_first","#, r#""bit_field": 3,"#);
+        assert!(codes(&declared, &profiles).is_empty());
+    }
+
+    #[test]
+    fn test_validate_field_endianness() {
+        let packet = |fields: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "Status",
+                    "command_id": "0x0101",
+                    "comment": "S",
+                    "enums": [{{ "name": "Mode", "type": "uint8_t", "values": [{{ "name": "idle", "value": 0 }}] }}],
+                    "fields": [{}]
+                }}"#,
+                fields
+            )
+        };
+        let diags = |json: &str| -> Vec<(ValidationCode, Severity)> {
+            validate(json)
+                .into_iter()
+                .map(|d| (d.code, d.severity))
+                .collect()
+        };
+        let s = |text: &str| text.to_string();
+
+        assert!(
+            diags(&packet(
+                r#"{ "name": "speed", "type": "int16_t", "endianness": "big", "comment": "V" },
+                   { "name": "gyro", "type": "int32_t[3]", "endianness": "little", "comment": "G" }"#
+            ))
+            .is_empty()
+        );
+        assert_eq!(
+            diags(&packet(
+                r#"{ "name": "speed", "type": "int16_t", "endianness": "network", "comment": "V" }"#
+            )),
+            vec![(
+                ValidationCode::InvalidEndianness(s("network")),
+                Severity::Error
+            )]
+        );
+        // 单字节的字段、数组与枚举
+        assert_eq!(
+            diags(&packet(
+                r#"{ "name": "id", "type": "uint8_t", "endianness": "big", "comment": "I" },
+                   { "name": "raw", "type": "char[4]", "endianness": "big", "comment": "R" },
+                   { "name": "mode", "type": "Mode", "endianness": "little", "comment": "M" }"#
+            )),
+            vec![
+                (
+                    ValidationCode::RedundantEndianness(s("id"), s("uint8_t")),
+                    Severity::Warning
+                ),
+                (
+                    ValidationCode::RedundantEndianness(s("raw"), s("char")),
+                    Severity::Warning
+                ),
+                (
+                    ValidationCode::RedundantEndianness(s("mode"), s("uint8_t")),
+                    Severity::Warning
+                ),
+            ]
+        );
+        assert_eq!(
+            diags(&packet(
+                r#"{ "name": "flags", "type": "uint16_t", "bit_field": 9, "endianness": "big", "comment": "F" }"#
+            )),
+            vec![(
+                ValidationCode::FieldEndiannessUnsupported(
+                    s("flags"),
+                    s("位域成员没有独立的字节位置")
+                ),
+                Severity::Error
+            )]
+        );
+    }
+
+    #[test]
+    fn test_validate_serialize() {
+        let packet = |extra: &str, fields: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "Status",
+                    "command_id": "0x0101",
+                    "comment": "S",
+                    {}
+                    "fields": [{}]
+                }}"#,
+                extra, fields
+            )
+        };
+        let codes = |json: &str| -> Vec<ValidationCode> {
+            validate(json).into_iter().map(|d| d.code).collect()
+        };
+        let plain = r#"{ "name": "speed", "type": "float", "comment": "V" }"#;
+
+        assert!(codes(&packet(r#""serialize": true, "endianness": "big","#, plain)).is_empty());
+        assert!(codes(&packet(r#""endianness": "little","#, plain)).is_empty());
+        assert_eq!(
+            codes(&packet(r#""endianness": "network","#, plain)),
+            vec![ValidationCode::InvalidEndianness("network".to_string())]
+        );
+        assert_eq!(
+            codes(&packet(r#""endianness": 1,"#, plain)),
+            vec![ValidationCode::InvalidEndianness(
+                "<non-string>".to_string()
+            )]
+        );
+
+        // 位域与 long double 只在启用 serialize 时报告
+        let fields = r#"{ "name": "mode", "type": "uint8_t", "bit_field": 3, "comment": "M" },
+            { "name": "value", "type": "long double", "comment": "V" }"#;
+        let errors = |json: &str| -> Vec<RplcDiagnostic> {
+            validate(json)
+                .into_iter()
+                .filter(|d| d.severity == Severity::Error)
+                .collect()
+        };
+        assert!(errors(&packet("", fields)).is_empty());
+        let json = packet(r#""serialize": true,"#, fields);
+        let diags = errors(&json);
+        assert_eq!(
+            diags.iter().map(|d| d.code.clone()).collect::<Vec<_>>(),
+            vec![
+                ValidationCode::SerializeUnsupportedField(
+                    "mode".to_string(),
+                    "位域成员没有独立的字节位置".to_string()
+                ),
+                ValidationCode::SerializeUnsupportedField(
+                    "value".to_string(),
+                    "long double 的大小与表示随平台变化".to_string()
+                ),
+            ]
+        );
+        let (start, len) = diags[1].span.unwrap();
+        assert_eq!(&json[start..start + len], r#""long double""#);
+    }
+
+    #[test]
+    fn test_validate_crc() {
+        let packet = |extra: &str, fields: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "Referee",
+                    "command_id": "0x0301",
+                    "comment": "R",
+                    {}
+                    "fields": [{}]
+                }}"#,
+                extra, fields
+            )
+        };
+        let codes = |json: &str| -> Vec<ValidationCode> {
+            validate(json).into_iter().map(|d| d.code).collect()
+        };
+        let plain = r#"{ "name": "hp", "type": "uint16_t", "comment": "H" }"#;
+        let with_crc = r#"{ "name": "hp", "type": "uint16_t", "comment": "H" },
+            { "name": "crc16", "type": "uint16_t", "comment": "C" }"#;
+
+        assert!(codes(&packet(r#""crc": "dji","#, plain)).is_empty());
+        assert!(codes(&packet(r#""crc": "dji", "crc_append": false,"#, with_crc)).is_empty());
+        // 未启用 crc 时名为 crc16 的字段只是普通字段
+        assert!(codes(&packet("", with_crc)).is_empty());
+        assert_eq!(
+            codes(&packet(r#""crc": "crc32","#, plain)),
+            vec![ValidationCode::InvalidCrc("crc32".to_string())]
+        );
+        assert_eq!(
+            codes(&packet(r#""crc": "dji","#, with_crc)),
+            vec![ValidationCode::ChecksumFieldDuplicated("crc16".to_string())]
+        );
+        assert_eq!(
+            codes(&packet(r#""crc": "dji", "crc_append": false,"#, plain)),
+            vec![ValidationCode::ChecksumFieldMissing("Referee".to_string())]
+        );
+
+        let misplaced = r#"{ "name": "checksum", "type": "uint8_t", "comment": "C" },
+            { "name": "hp", "type": "uint16_t", "comment": "H" }"#;
+        let json = packet(r#""crc": "dji", "crc_append": false,"#, misplaced);
+        let diags = validate(&json);
+        assert_eq!(
+            diags.iter().map(|d| d.code.clone()).collect::<Vec<_>>(),
+            vec![
+                ValidationCode::ChecksumFieldWidth("checksum".to_string(), "uint8_t".to_string()),
+                ValidationCode::ChecksumFieldNotLast("checksum".to_string()),
+            ]
+        );
+        let (start, len) = diags[0].span.unwrap();
+        assert_eq!(&json[start..start + len], r#""uint8_t""#);
+
+        // 数据段上限 119 字节；由字段承载的 CRC16 不计入数据段
+        let large = r#"{ "name": "data", "type": "uint8_t[120]", "comment": "D" }"#;
+        let json = packet(r#""crc": "dji","#, large);
+        let diags = validate(&json);
+        assert_eq!(
+            diags.iter().map(|d| d.code.clone()).collect::<Vec<_>>(),
+            vec![ValidationCode::CrcPayloadTooLarge(
+                "Referee".to_string(),
+                120,
+                119
+            )]
+        );
         assert_eq!(diags[0].severity, Severity::Warning);
+        let (start, len) = diags[0].span.unwrap();
+        assert_eq!(&json[start..start + len], r#""dji""#);
+        let fits = r#"{ "name": "data", "type": "uint8_t[119]", "comment": "D" },
+            { "name": "crc", "type": "uint16_t", "comment": "C" }"#;
+        assert!(codes(&packet(r#""crc": "dji", "crc_append": false,"#, fits)).is_empty());
     }
 
     #[test]
-    fn test_validate_valid_bit_field() {
-        let json = r#"{
-            "packet_name": "BitFieldPacket",
-            "command_id": "0x0105",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
-            "fields": [
-                {
-                    "name": "status",
-                    "type": "uint8_t",
-                    "bit_field": 4,
-                    "comment": "Status field"
-                },
-                {
-                    "name": "flag",
-                    "type": "uint8_t",
-                    "bit_field": 3,
-                    "comment": "Flag field"
-                }
+    fn test_validate_traits_template() {
+        let packet = |extra: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "Status",
+                    "command_id": "0x0101",
+                    "comment": "S",
+                    {}
+                    "fields": [{{ "name": "mode", "type": "uint8_t", "comment": "M" }}]
+                }}"#,
+                extra
+            )
+        };
+
+        let valid = packet(r#""traits_template": "MY_TRAITS(${name}, ${cmd}, ${size})","#);
+        assert!(validate(&valid).is_empty());
+
+        let json = packet(r#""traits_template": "ID<${name}> = ${id}","#);
+        let diags = validate(&json);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(
+            diags[0].code,
+            ValidationCode::InvalidTraitsTemplate("未知的占位符 ${id}".to_string())
+        );
+        let (start, len) = diags[0].span.unwrap();
+        assert_eq!(&json[start..start + len], "${id}");
+
+        // 转义序列之后的占位符仍然指向源文本中的位置
+        let json = packet(r#""traits_template": "\"\u4e2d\ud83d\ude00\n\t${ID} ${cmd} ${size","#);
+        let diags = validate(&json);
+        let found: Vec<_> = diags
+            .iter()
+            .map(|d| {
+                let (start, len) = d.span.unwrap();
+                (d.code.to_string(), &json[start..start + len])
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "traits_template 无效: 未知的占位符 ${ID}".to_string(),
+                    "${ID}"
+                ),
+                (
+                    "traits_template 无效: 占位符 ${size 缺少右花括号".to_string(),
+                    "${size"
+                ),
             ]
-        }"#;
+        );
 
-        let diags = validate(json);
-        assert!(diags.is_empty()); // Should have no diagnostics for valid bit fields
+        let diags = validate(&packet(r#""traits_template": 3,"#));
+        assert_eq!(
+            diags[0].code,
+            ValidationCode::InvalidTraitsTemplate("必须是字符串".to_string())
+        );
+
+        let diags = validate(&packet(
+            r#""emit_traits": false, "traits_template": "X<${name}>","#,
+        ));
+        assert_eq!(diags.len(), 1);
+        assert_eq!(
+            diags[0].code,
+            ValidationCode::UnusedTraitsTemplate("Status".to_string())
+        );
+        assert_eq!(diags[0].severity, Severity::Warning);
     }
 
     #[test]
-    fn test_validate_invalid_bit_field_value() {
+    fn test_validate_sections() {
         let json = r#"{
-            "packet_name": "InvalidBitFieldPacket",
-            "command_id": "0x0105",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
+            "packet_name": "Status",
+            "command_id": "0x0101",
+            "comment": "S",
             "fields": [
-                {
-                    "name": "invalid_bit_field",
-                    "type": "uint8_t",
-                    "bit_field": -1,
-                    "comment": "Invalid bit_field value"
-                }
+                { "section": "IMU" },
+                { "name": "a", "type": "uint8_t", "bit_field": 3, "comment": "A" },
+                { "section": "Flags" },
+                { "name": "b", "type": "uint8_t", "bit_field": 5, "comment": "B" }
             ]
         }"#;
+        assert!(validate(json).is_empty());
 
-        let diags = validate(json);
-        assert_eq!(diags.len(), 1);
-        assert!(matches!(diags[0].code, ValidationCode::InvalidBitField(_)));
-        assert_eq!(diags[0].severity, Severity::Error);
+        let json = r#"{
+            "packet_name": "Status",
+            "command_id": "0x0101",
+            "comment": "S",
+            "fields": [
+                { "section": "" },
+                { "section": "IMU", "name": "x" },
+                { "section": "Unused" },
+                { "section": "Motor" },
+                { "name": "a", "type": "uint8_t", "comment": "A" },
+                { "section": "Trailing" }
+            ]
+        }"#;
+        let codes: Vec<_> = validate(json).into_iter().map(|d| d.code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                ValidationCode::InvalidSection("分组标题必须是非空字符串".to_string()),
+                ValidationCode::InvalidSection("分组条目只能包含 section".to_string()),
+                ValidationCode::EmptySection("Unused".to_string()),
+                ValidationCode::EmptySection("Trailing".to_string()),
+            ]
+        );
     }
 
     #[test]
-    fn test_validate_invalid_bit_field_type() {
+    fn test_validate_groups() {
         let json = r#"{
-            "packet_name": "InvalidBitFieldType",
-            "command_id": "0x0105",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
+            "packet_name": "Status",
+            "command_id": "0x0101",
+            "comment": "S",
             "fields": [
-                {
-                    "name": "float_bit_field",
-                    "type": "float",
-                    "bit_field": 5,
-                    "comment": "Bitfield on float type"
-                }
+                { "name": "imu", "comment": "IMU", "group": [
+                    { "name": "a", "type": "uint8_t", "bit_field": 3, "comment": "A" },
+                    { "name": "b", "type": "uint8_t", "bit_field": 5, "comment": "B" }
+                ] },
+                { "group": [{ "name": "c", "type": "uint16_t", "comment": "C" }] }
             ]
         }"#;
+        assert!(validate(json).is_empty());
 
-        let diags = validate(json);
-        assert_eq!(diags.len(), 1);
-        assert!(matches!(
-            diags[0].code,
-            ValidationCode::BitFieldOnInvalidType(_, _)
-        ));
-        assert_eq!(diags[0].severity, Severity::Error);
-    }
-
-    #[test]
-    fn test_validate_bit_field_length_overflow() {
         let json = r#"{
-            "packet_name": "OverflowBitField",
-            "command_id": "0x0105",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
+            "packet_name": "Status",
+            "command_id": "0x0101",
+            "comment": "S",
             "fields": [
-                {
-                    "name": "overflow_field",
-                    "type": "uint8_t",
-                    "bit_field": 10,
-                    "comment": "Bitfield exceeding type size"
-                }
+                { "name": "a", "type": "uint8_t", "comment": "A" },
+                { "name": "a", "group": [{ "name": "b", "type": "uint8_t", "comment": "B" }] },
+                { "name": "empty", "type": "uint8_t", "group": [] },
+                { "group": [
+                    { "name": "c", "type": "uint8_t", "bit_field": 3, "comment": "C" },
+                    { "section": "Nested" }
+                ] },
+                { "name": "d", "type": "uint8_t", "bit_field": 5, "comment": "D" }
             ]
         }"#;
+        let codes: Vec<_> = validate(json).into_iter().map(|d| d.code).collect();
+        let invalid = |label: &str, reason: &str| {
+            ValidationCode::InvalidGroup(label.to_string(), reason.to_string())
+        };
+        assert_eq!(
+            codes,
+            vec![
+                invalid("empty", "不能包含 type、bit_field 或 section"),
+                invalid("empty", "group 必须是非空的字段数组"),
+                invalid("匿名", "不能再包含嵌套结构体或分组条目"),
+                ValidationCode::DuplicateFieldName("a".to_string()),
+                ValidationCode::GroupBitFieldBoundary("匿名".to_string(), 19),
+            ]
+        );
 
-        let diags = validate(json);
-        assert_eq!(diags.len(), 1);
-        assert!(matches!(
-            diags[0].code,
-            ValidationCode::BitFieldLengthOverflow(_, _, _)
-        ));
-        assert_eq!(diags[0].severity, Severity::Error);
+        let json = r#"{
+            "packet_name": "Status",
+            "command_id": "0x0101",
+            "comment": "S",
+            "packed": false,
+            "fields": [{ "group": [{ "name": "a", "type": "uint8_t", "comment": "A" }] }]
+        }"#;
+        let codes: Vec<_> = validate(json).into_iter().map(|d| d.code).collect();
+        assert_eq!(codes, vec![invalid("匿名", "只能用于紧凑结构体")]);
     }
 
     #[test]
-    fn test_validate_bit_field_missing_packed_attr_warning() {
+    fn test_validate_schema_version() {
+        let packet = |schema: &str| {
+            format!(
+                r#"{{ {} "packet_name": "Status", "command_id": "0x0101", "comment": "S",
+                    "fields": [{{ "name": "mode", "type": "uint8_t", "bit_field": "4", "comment": "M" }}] }}"#,
+                schema
+            )
+        };
+        // 未声明版本的旧文件只给出警告
+        let codes: Vec<_> = validate(&packet("")).into_iter().map(|d| d.code).collect();
+        assert_eq!(
+            codes,
+            vec![ValidationCode::BitFieldAsString("mode".to_string(), 4)]
+        );
+
+        let codes: Vec<_> = validate(&packet(r#""rplc_schema": 2,"#))
+            .into_iter()
+            .map(|d| d.code)
+            .collect();
+        assert_eq!(
+            codes,
+            vec![ValidationCode::RemovedInSchema(
+                "字段 'mode' 的位域写成了字符串".to_string(),
+                2
+            )]
+        );
+
+        for (schema, text) in [
+            (r#""rplc_schema": 3,"#, "3"),
+            (r#""rplc_schema": "2","#, "2"),
+        ] {
+            let diags = validate(&packet(schema));
+            assert_eq!(
+                diags[0].code,
+                ValidationCode::UnsupportedSchemaVersion(text.to_string(), CURRENT_SCHEMA)
+            );
+        }
+
+        // 包集合上的版本作用于其中的每个包
+        let json = format!(
+            r#"{{ "rplc_schema": 2, "packets": [{}] }}"#,
+            packet("").replace(r#""4""#, "4")
+        );
+        assert!(validate_multiple(&json).is_empty());
+        let json = r#"{ "rplc_schema": 0, "packets": [] }"#;
+        let codes: Vec<_> = validate_multiple(json)
+            .into_iter()
+            .map(|d| d.code)
+            .collect();
+        assert_eq!(
+            codes,
+            vec![ValidationCode::UnsupportedSchemaVersion(
+                "0".to_string(),
+                CURRENT_SCHEMA
+            )]
+        );
+    }
+
+    #[test]
+    fn test_validate_duplicate_keys() {
         let json = r#"{
-            "packet_name": "UnpackedBitField",
-            "command_id": "0x0105",
-            "namespace": null,
-            "packed": false,
-            "header_guard": null,
-            "fields": [
-                {
-                    "name": "status",
-                    "type": "uint8_t",
-                    "bit_field": 4,
-                    "comment": "Status field"
-                }
-            ]
+            "packet_name": "Status",
+            "command_id": "0x0101",
+            "comment": "S",
+            "fields": [{ "name": "mode", "type": "uint8_t", "type": "uint16_t", "comment": "M" }]
         }"#;
-
         let diags = validate(json);
         assert_eq!(diags.len(), 1);
-        assert!(matches!(
+        assert_eq!(
             diags[0].code,
-            ValidationCode::BitFieldMissingPackedAttr(_)
-        ));
-        assert_eq!(diags[0].severity, Severity::Warning);
+            ValidationCode::DuplicateKey("type".to_string(), 5)
+        );
+        assert_eq!(diags[0].severity, Severity::Error);
+        // 生成前的校验同样会拒绝
+        assert!(crate::generate(json).is_err());
+
+        // 多包模式在原始文本上检查，位置指向输入中的键
+        let json = format!(
+            r#"{{ "protocol_version": "1.0.0", "packets": [{}] }}"#,
+            json
+        );
+        let diags = validate_multiple(&json);
+        assert_eq!(diags.len(), 1);
+        let (offset, len) = diags[0].span.unwrap();
+        assert_eq!(&json[offset..offset + len], r#""type""#);
     }
 
     #[test]
-    fn test_validate_bit_field_straddle_boundary_without_packed_error() {
+    fn test_validate_multiple_with_options() {
+        let json = format!(
+            r#"[{{
+                "packet_name": "Flags",
+                "command_id": "0x0101",
+                "fields": [ {} ]
+            }}]"#,
+            flag_fields(3, "uint8_t", 1)
+        );
+        let options = ValidateOptions {
+            max_bit_fields_per_unit: 2,
+            ..Default::default()
+        };
+        assert!(
+            validate_multiple_with_options(&json, &options)
+                .iter()
+                .any(|d| matches!(d.code, ValidationCode::TooManyBitFieldsPerUnit(..)))
+        );
+    }
+
+    #[test]
+    fn test_validate_array_exceeds_frame_payload_limit() {
         let json = r#"{
-            "packet_name": "StraddleBoundary",
-            "command_id": "0x0105",
-            "namespace": null,
-            "packed": false,
-            "header_guard": null,
+            "packet_name": "Huge",
+            "command_id": "0x0101",
             "fields": [
-                {
-                    "name": "field1",
-                    "type": "uint8_t",
-                    "bit_field": 5,
-                    "comment": "First field"
-                },
-                {
-                    "name": "field2",
-                    "type": "uint8_t",
-                    "bit_field": 4,
-                    "comment": "Second field"
-                }
+                { "name": "header", "type": "uint8_t", "comment": "H" },
+                { "name": "samples", "type": "float[16384]", "comment": "S" }
             ]
         }"#;
 
-        let diags = validate(json);
-        assert!(diags.len() >= 2); // At least 2: one for missing packed attr (for each field) and one for straddle boundary
-        let cross_boundary_errors: Vec<_> = diags
-            .iter()
-            .filter(|d| {
-                matches!(
-                    d.code,
-                    ValidationCode::BitFieldStraddleBoundaryWithoutPacked(_, _, _, _, _)
-                )
-            })
-            .collect();
-        assert_eq!(cross_boundary_errors.len(), 1);
-        assert_eq!(cross_boundary_errors[0].severity, Severity::Error);
+        let result = validate(json);
+        assert!(result.iter().any(|d| d.code
+            == ValidationCode::ArrayExceedsPayloadBudget("samples".to_string(), 65537, 65535)
+            && d.severity == Severity::Error));
     }
 
     #[test]
-    fn test_validate_bit_field_straddle_boundary_warning() {
+    fn test_validate_array_exceeds_configured_payload_budget() {
         let json = r#"{
-            "packet_name": "FullBitField",
-            "command_id": "0x0105",
-            "namespace": null,
-            "packed": false,
-            "header_guard": null,
+            "packet_name": "Samples",
+            "command_id": "0x0101",
             "fields": [
-                {
-                    "name": "full_field",
-                    "type": "uint8_t",
-                    "bit_field": 8,
-                    "comment": "Full bit_field"
-                }
+                { "name": "count", "type": "uint16_t", "comment": "C" },
+                { "name": "samples", "type": "int16_t[64]", "comment": "S" },
+                { "name": "extra", "type": "uint8_t[8]", "comment": "E" }
             ]
         }"#;
 
-        let diags = validate(json);
-        assert_eq!(diags.len(), 2); // One for missing packed attribute, one for straddle boundary
-        let bit_field_warnings: Vec<_> = diags
-            .iter()
-            .filter(|d| matches!(d.code, ValidationCode::BitFieldStraddleBoundary(_)))
-            .collect();
-        assert_eq!(bit_field_warnings.len(), 1);
-        assert_eq!(bit_field_warnings[0].severity, Severity::Warning);
-    }
-
-    #[test]
-    fn test_c_type_to_bit_field_size() {
-        // Test valid types
-        assert_eq!(c_type_to_bit_field_size("uint8_t"), Some(1));
-        assert_eq!(c_type_to_bit_field_size("int8_t"), Some(1));
-        assert_eq!(c_type_to_bit_field_size("uint16_t"), Some(2));
-        assert_eq!(c_type_to_bit_field_size("int16_t"), Some(2));
-        assert_eq!(c_type_to_bit_field_size("uint32_t"), Some(4));
-        assert_eq!(c_type_to_bit_field_size("int32_t"), Some(4));
-        assert_eq!(c_type_to_bit_field_size("uint64_t"), Some(8));
-        assert_eq!(c_type_to_bit_field_size("int64_t"), Some(8));
-        assert_eq!(c_type_to_bit_field_size("int"), Some(4));
-        assert_eq!(c_type_to_bit_field_size("char"), Some(1));
-        assert_eq!(c_type_to_bit_field_size("bool"), Some(1));
-
-        // Test invalid types
-        assert_eq!(c_type_to_bit_field_size("float"), None);
-        assert_eq!(c_type_to_bit_field_size("double"), None);
-        assert_eq!(c_type_to_bit_field_size("void*"), None);
-        assert_eq!(c_type_to_bit_field_size("invalid_type"), None);
-    }
-
-    #[test]
-    fn test_validate_multiple_packets_valid() {
-        let json = r#"[
-            {
-                "packet_name": "PacketA",
-                "command_id": "0x0101",
-                "namespace": null,
-                "packed": true,
-                "header_guard": "RPL_PACKETA_HPP",
-                "fields": [
-                    {
-                        "name": "field_a",
-                        "type": "uint8_t",
-                        "comment": "Field A"
-                    }
-                ]
-            },
-            {
-                "packet_name": "PacketB",
-                "command_id": "0x0102",
-                "namespace": "Test::Ns",
-                "packed": false,
-                "header_guard": "RPL_PACKETB_HPP",
-                "fields": [
-                    {
-                        "name": "field_b",
-                        "type": "uint16_t",
-                        "comment": "Field B"
-                    }
-                ]
-            }
-        ]"#;
+        assert!(
+            !validate(json)
+                .iter()
+                .any(|d| matches!(d.code, ValidationCode::ArrayExceedsPayloadBudget(..)))
+        );
 
-        let diags = validate_multiple(json);
-        assert!(diags.is_empty()); // Should have no diagnostics for valid packets
+        let options = ValidateOptions {
+            max_payload_size: Some(128),
+            ..Default::default()
+        };
+        let budget_diags: Vec<_> = validate_with_options(json, &options)
+            .into_iter()
+            .filter(|d| matches!(d.code, ValidationCode::ArrayExceedsPayloadBudget(..)))
+            .collect();
+        // 只报告第一个越界的数组字段
+        assert_eq!(budget_diags.len(), 1);
+        assert_eq!(
+            budget_diags[0].code,
+            ValidationCode::ArrayExceedsPayloadBudget("samples".to_string(), 130, 128)
+        );
     }
 
     #[test]
-    fn test_validate_multiple_packets_with_errors() {
-        let json = r#"[
-            {
-                "packet_name": "ValidPacket",
-                "command_id": "0x0101",
-                "namespace": null,
-                "packed": true,
-                "header_guard": "RPL_VALIDPACKET_HPP",
-                "fields": [
-                    {
-                        "name": "valid_field",
-                        "type": "uint8_t",
-                        "comment": "Valid field"
-                    }
-                ]
-            },
-            {
-                "packet_name": "InvalidPacket",
-                "command_id": "invalid-command-id",
-                "namespace": null,
-                "packed": true,
-                "header_guard": "RPL_INVALIDPACKET_HPP",
-                "fields": [
-                    {
-                        "name": "field",
-                        "type": "uint8_t",
-                        "comment": "Field"
-                    }
-                ]
-            }
-        ]"#;
+    fn test_validate_align() {
+        let json = r#"{
+            "packet_name": "DmaPacket",
+            "command_id": "0x0101",
+            "align": 4,
+            "fields": [ { "name": "a", "type": "uint8_t", "comment": "A" } ]
+        }"#;
+        assert!(validate(json).is_empty());
 
-        let diags = validate_multiple(json);
-        assert!(!diags.is_empty()); // Should have diagnostics because of invalid command ID
+        let json = r#"{
+            "packet_name": "DmaPacket",
+            "command_id": "0x0101",
+            "align": null,
+            "fields": [ { "name": "a", "type": "uint8_t", "comment": "A" } ]
+        }"#;
+        assert!(validate(json).is_empty());
+    }
 
-        let error_count = diags
-            .iter()
-            .filter(|d| d.severity == Severity::Error)
-            .count();
-        assert_eq!(error_count, 1); // Should have 1 error for the invalid command ID
+    #[test]
+    fn test_validate_invalid_align() {
+        for align in ["3", "0", "-4", "\"4\"", "8192"] {
+            let json = format!(
+                r#"{{
+                    "packet_name": "DmaPacket",
+                    "command_id": "0x0101",
+                    "align": {align},
+                    "fields": [ {{ "name": "a", "type": "uint8_t", "comment": "A" }} ]
+                }}"#
+            );
+            let result = validate(&json);
+            assert!(
+                result
+                    .iter()
+                    .any(|d| matches!(d.code, ValidationCode::InvalidAlign(_))
+                        && d.severity == Severity::Error),
+                "align {align} should be rejected"
+            );
+        }
     }
 
     #[test]
-    fn test_validate_multiple_backwards_compatibility() {
-        // Test that single packet still works with validate_multiple
+    fn test_validate_align_below_natural_alignment() {
         let json = r#"{
-            "packet_name": "SinglePacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": "RPL_SINGLEPACKET_HPP",
-            "fields": [
-                {
-                    "name": "field",
-                    "type": "uint8_t",
-                    "comment": "A field"
-                }
-            ]
+            "packet_name": "DmaPacket",
+            "command_id": "0x0101",
+            "packed": false,
+            "align": 2,
+            "fields": [ { "name": "a", "type": "uint32_t", "comment": "A" } ]
         }"#;
+        let result = validate(json);
+        assert!(
+            result
+                .iter()
+                .any(|d| d.code == ValidationCode::AlignBelowNatural(2, 4))
+        );
 
-        let diags = validate_multiple(json);
-        assert!(diags.is_empty()); // Should have no diagnostics for valid single packet
+        // 紧凑结构体没有自然对齐要求
+        let json = r#"{
+            "packet_name": "DmaPacket",
+            "command_id": "0x0101",
+            "align": 2,
+            "fields": [ { "name": "a", "type": "uint32_t", "comment": "A" } ]
+        }"#;
+        assert!(validate(json).is_empty());
     }
 
     #[test]
-    fn test_validate_packet_comment() {
+    fn test_validate_expected_size() {
+        let packet = |packed: bool, expected: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "Imu",
+                    "command_id": "0x0101",
+                    "packed": {packed},
+                    "expected_size": {expected},
+                    "fields": [
+                        {{ "name": "id", "type": "uint8_t", "comment": "A" }},
+                        {{ "name": "ticks", "type": "uint32_t", "comment": "B" }}
+                    ]
+                }}"#
+            )
+        };
+        assert!(validate(&packet(true, "5")).is_empty());
+        assert!(validate(&packet(true, "null")).is_empty());
+
+        // 非紧凑时 ticks 前有 3 字节填充
+        let json = packet(false, "5");
+        let result = validate(&json);
+        assert_eq!(
+            result.iter().map(|d| &d.code).collect::<Vec<_>>(),
+            vec![&ValidationCode::ExpectedSizeMismatch(
+                "Imu".to_string(),
+                5,
+                8
+            )]
+        );
+        let (start, len) = result[0].span.unwrap();
+        assert_eq!(&json[start..start + len], "5");
+
+        for expected in ["0", "-1", "\"5\"", "4294967296"] {
+            let result = validate(&packet(true, expected));
+            assert!(
+                result
+                    .iter()
+                    .any(|d| matches!(d.code, ValidationCode::InvalidExpectedSize(_))),
+                "expected_size {expected} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_field_ids() {
+        let packet = |retired: &str, ids: [&str; 3]| {
+            format!(
+                r#"{{
+                    "packet_name": "Gimbal",
+                    "command_id": "0x0101",
+                    "retired_field_ids": {retired},
+                    "fields": [
+                        {{ "name": "yaw", "type": "float", "comment": "Y", "id": {} }},
+                        {{ "reserved": 4 }},
+                        {{ "name": "pitch", "type": "float", "comment": "P", "id": {} }},
+                        {{ "name": "mode", "type": "uint8_t", "comment": "M", "id": {} }}
+                    ]
+                }}"#,
+                ids[0], ids[1], ids[2]
+            )
+        };
+        let codes = |json: &str| {
+            validate(json)
+                .into_iter()
+                .map(|d| (d.severity, d.code))
+                .collect::<Vec<_>>()
+        };
+        // 保留条目不需要 ID
+        assert!(validate(&packet("[2]", ["1", "3", "4"])).is_empty());
+        assert!(validate(&packet("[]", ["null", "null", "null"])).is_empty());
+
+        let json = packet("[2]", ["1", "1", "2"]);
+        assert_eq!(
+            codes(&json),
+            [
+                (
+                    Severity::Error,
+                    ValidationCode::DuplicateFieldId("pitch".to_string(), 1, "yaw".to_string())
+                ),
+                (
+                    Severity::Error,
+                    ValidationCode::RetiredFieldIdReused("mode".to_string(), 2)
+                ),
+            ]
+        );
+        let (start, len) = validate(&json)[1].span.unwrap();
+        assert_eq!(&json[start..start + len], "2");
+
+        assert_eq!(
+            codes(&packet("[]", ["1", "null", "3"])),
+            [(
+                Severity::Warning,
+                ValidationCode::MissingFieldId("pitch".to_string())
+            )]
+        );
+        assert_eq!(
+            codes(&packet("[-1]", ["\"a\"", "2", "4294967296"])),
+            [
+                (
+                    Severity::Error,
+                    ValidationCode::InvalidFieldId("-1".to_string())
+                ),
+                (
+                    Severity::Error,
+                    ValidationCode::InvalidFieldId("a".to_string())
+                ),
+                (
+                    Severity::Error,
+                    ValidationCode::InvalidFieldId("4294967296".to_string())
+                ),
+            ]
+        );
+        assert_eq!(
+            codes(&packet("3", ["1", "2", "4"])),
+            [(
+                Severity::Error,
+                ValidationCode::InvalidFieldId("3".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn test_validate_generated_name_collision() {
         let json = r#"{
-            "packet_name": "CommentedPacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
-            "comment": "这是一个带注释的数据包",
+            "packet_name": "Gimbal",
+            "command_id": "0x0101",
             "fields": [
-                {
-                    "name": "field",
-                    "type": "uint8_t",
-                    "comment": "A field"
-                }
+                { "name": "yaw", "type": "float", "comment": "Y", "max": 3.14 },
+                { "name": "k_yaw_max", "type": "float", "comment": "K" },
+                { "name": "mode", "type": "uint8_t", "comment": "M", "values": { "0": "idle" } },
+                { "name": "mode_name", "type": "uint8_t", "comment": "N" },
+                { "name": "mode_name_value", "type": "uint8_t", "comment": "V" },
+                { "name": "pack", "type": "uint8_t", "comment": "P" }
             ]
         }"#;
+        let result = validate(json);
+        let codes: Vec<_> = result.iter().map(|d| d.code.clone()).collect();
+        assert_eq!(
+            codes,
+            [
+                ValidationCode::GeneratedNameCollision(
+                    "k_yaw_max".to_string(),
+                    "C++".to_string(),
+                    "k_yaw_max_value".to_string()
+                ),
+                ValidationCode::GeneratedNameCollision(
+                    "mode_name".to_string(),
+                    "C++".to_string(),
+                    "mode_name_value2".to_string()
+                ),
+            ]
+        );
+        assert!(result.iter().all(|d| d.severity == Severity::Error));
+        let (start, len) = result[0].span.unwrap();
+        assert_eq!(&json[start..start + len], "\"k_yaw_max\"");
 
-        let diags = validate(json);
-        assert_eq!(diags.len(), 0); // Should have no diagnostics
+        // Python 的类变量与方法只在目标包含 Python 时检查，同名字段只报告一次
+        let options = ValidateOptions {
+            targets: vec![Target::Cpp, Target::Python],
+            ..Default::default()
+        };
+        let collided: Vec<_> = validate_with_options(json, &options)
+            .into_iter()
+            .filter_map(|d| match d.code {
+                ValidationCode::GeneratedNameCollision(field, target, _) => Some((field, target)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            collided,
+            [
+                ("k_yaw_max".to_string(), "C++".to_string()),
+                ("mode_name".to_string(), "C++".to_string()),
+                ("pack".to_string(), "Python".to_string()),
+            ]
+        );
+
+        // 按位流读写的访问结构体只包含位域成员的函数
+        let json = r#"{
+            "packet_name": "Flags",
+            "command_id": "0x0102",
+            "bitfield_layout": "lsb_first",
+            "fields": [
+                { "name": "ready", "type": "uint8_t", "bit_field": 1, "comment": "R" },
+                { "name": "set_ready", "type": "uint8_t", "bit_field": 1, "comment": "S" },
+                { "name": "read_bits", "type": "uint8_t", "bit_field": 6, "comment": "B" },
+                { "name": "write_bits", "type": "uint8_t", "comment": "W" }
+            ]
+        }"#;
+        let collided: Vec<_> = validate(json)
+            .into_iter()
+            .filter_map(|d| match d.code {
+                ValidationCode::GeneratedNameCollision(field, ..) => Some(field),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(collided, ["set_ready", "read_bits"]);
     }
 
     #[test]
-    fn test_validate_empty_packet_comment() {
+    fn test_validate_unaligned_float_in_packed_warning() {
         let json = r#"{
-            "packet_name": "EmptyCommentPacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
-            "comment": "",
+            "packet_name": "Imu",
+            "command_id": "0x0101",
             "fields": [
-                {
-                    "name": "field",
-                    "type": "uint8_t",
-                    "comment": "A field"
-                }
+                { "name": "id", "type": "uint8_t", "comment": "I" },
+                { "name": "gyro", "type": "float[3]", "comment": "G" },
+                { "name": "yaw", "type": "double", "comment": "Y" }
             ]
         }"#;
 
-        let diags = validate(json);
-        assert_eq!(diags.len(), 1); // Should have empty comment warning
-        assert!(matches!(diags[0].code, ValidationCode::EmptyComment(_)));
-        assert_eq!(diags[0].severity, Severity::Warning);
+        let result = validate(json);
+        assert!(result.iter().any(|d| d.code
+            == ValidationCode::UnalignedFloatInPacked("gyro".to_string(), 8, 4)
+            && d.severity == Severity::Warning));
+        assert!(
+            result.iter().any(
+                |d| d.code == ValidationCode::UnalignedFloatInPacked("yaw".to_string(), 104, 8)
+            )
+        );
     }
 
     #[test]
-    fn test_validate_whitespace_packet_comment() {
+    fn test_validate_aligned_float_in_packed() {
         let json = r#"{
-            "packet_name": "WhitespaceCommentPacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
-            "comment": "   ",
+            "packet_name": "Imu",
+            "command_id": "0x0101",
             "fields": [
-                {
-                    "name": "field",
-                    "type": "uint8_t",
-                    "comment": "A field"
-                }
+                { "name": "id", "type": "uint32_t", "comment": "I" },
+                { "name": "gyro", "type": "float[3]", "comment": "G" }
             ]
         }"#;
+        assert!(validate(json).is_empty());
 
-        let diags = validate(json);
-        assert_eq!(diags.len(), 1); // Should have empty comment warning
-        assert!(matches!(diags[0].code, ValidationCode::EmptyComment(_)));
-        assert_eq!(diags[0].severity, Severity::Warning);
+        // 非紧凑结构体由编译器插入填充，不会出现未对齐的浮点成员
+        let json = r#"{
+            "packet_name": "Imu",
+            "command_id": "0x0101",
+            "packed": false,
+            "fields": [
+                { "name": "id", "type": "uint8_t", "comment": "I" },
+                { "name": "gyro", "type": "float", "comment": "G" }
+            ]
+        }"#;
+        assert!(validate(json).is_empty());
     }
 
-    // ---- Array Type Tests ----
-
     #[test]
-    fn test_parse_array_type_valid() {
-        assert_eq!(parse_array_type("float[3]"), Some(("float", Some(3))));
-        assert_eq!(parse_array_type("uint8_t[10]"), Some(("uint8_t", Some(10))));
-        assert_eq!(parse_array_type("int[256]"), Some(("int", Some(256))));
-        assert_eq!(parse_array_type("double[1]"), Some(("double", Some(1))));
+    fn test_validate_type_not_representable_in_target() {
+        let json = r#"{
+            "packet_name": "Precise",
+            "command_id": "0x0101",
+            "packed": false,
+            "fields": [
+                { "name": "value", "type": "long double", "comment": "V" },
+                { "name": "history", "type": "long double[2]", "comment": "H" }
+            ]
+        }"#;
+
+        // 默认仅检查 C++ 目标
+        assert!(validate(json).is_empty());
+
+        let options = ValidateOptions {
+            targets: vec![Target::Cpp, Target::Rust, Target::Python],
+            ..Default::default()
+        };
+        let result = validate_with_options(json, &options);
+        let rejected: Vec<_> = result
+            .iter()
+            .filter_map(|d| match &d.code {
+                ValidationCode::UnsupportedTypeForTarget(field, ty, target, _) => {
+                    Some((field.as_str(), ty.as_str(), target.as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            rejected,
+            vec![
+                ("value", "long double", "Rust"),
+                ("value", "long double", "Python"),
+                ("history", "long double", "Rust"),
+                ("history", "long double", "Python"),
+            ]
+        );
+        assert!(result.iter().all(|d| d.severity == Severity::Error));
     }
 
     #[test]
-    fn test_parse_array_type_non_array() {
-        assert_eq!(parse_array_type("float"), Some(("float", None)));
-        assert_eq!(parse_array_type("uint8_t"), Some(("uint8_t", None)));
-        assert_eq!(parse_array_type("int"), Some(("int", None)));
+    fn test_validate_includes() {
+        let packet = |includes: &str| {
+            format!(
+                r#"{{
+                    "packet_name": "Gimbal",
+                    "command_id": "0x0101",
+                    "includes": {includes},
+                    "fields": [{{ "name": "yaw", "type": "float", "comment": "Y" }}]
+                }}"#
+            )
+        };
+        assert!(validate(&packet(r#"["my_types.h", "<stdint.h>", "board/types.h"]"#)).is_empty());
+
+        let reasons = |includes: &str| {
+            validate(&packet(includes))
+                .into_iter()
+                .map(|d| match d.code {
+                    ValidationCode::InvalidInclude(include, reason) => (include, reason),
+                    other => panic!("{:?}", other),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            reasons(r#"["<stdint.h", "", "a\"b.h", 1]"#),
+            [
+                ("<stdint.h".to_string(), "尖括号不成对".to_string()),
+                ("".to_string(), "不能为空".to_string()),
+                (
+                    "a\"b.h".to_string(),
+                    "不能包含引号、尖括号或换行".to_string()
+                ),
+                ("1".to_string(), "必须是字符串".to_string()),
+            ]
+        );
+        assert_eq!(
+            reasons(r#""my_types.h""#),
+            [("my_types.h".to_string(), "必须是字符串数组".to_string())]
+        );
     }
 
     #[test]
-    fn test_parse_array_type_invalid() {
-        // Empty size
-        assert_eq!(parse_array_type("float[]"), None);
-        // Invalid size
-        assert_eq!(parse_array_type("float[abc]"), None);
-        // Negative size
-        assert_eq!(parse_array_type("float[-1]"), None);
-        // Zero size
-        assert_eq!(parse_array_type("float[0]"), None);
-        // Missing closing bracket
-        assert_eq!(parse_array_type("float[3"), None);
-        // Empty base type
-        assert_eq!(parse_array_type("[3]"), None);
+    fn test_validate_cpp_standard() {
+        let json = r#"{
+            "packet_name": "Gimbal",
+            "command_id": "0x0101",
+            "serialize": true,
+            "fields": [{ "name": "yaw", "type": "float", "comment": "Y" }]
+        }"#;
+        let with = |cpp_standard, targets| ValidateOptions {
+            cpp_standard,
+            targets,
+            ..Default::default()
+        };
+        assert!(
+            validate_with_options(json, &with(CppStandard::Cpp17, vec![Target::Cpp])).is_empty()
+        );
+
+        let result = validate_with_options(json, &with(CppStandard::Cpp14, vec![Target::Cpp]));
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].code,
+            ValidationCode::CppStandardTooOld(
+                "serialize".to_string(),
+                "C++17".to_string(),
+                "C++14".to_string()
+            )
+        );
+        let (start, len) = result[0].span.unwrap();
+        assert_eq!(&json[start..start + len], "true");
+
+        // 不生成 C++ 时不检查
+        assert!(
+            validate_with_options(json, &with(CppStandard::Cpp14, vec![Target::Rust])).is_empty()
+        );
     }
 
     #[test]
-    fn test_validate_valid_array_type() {
+    fn test_validate_allowed_types() {
         let json = r#"{
-            "packet_name": "ArrayPacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
+            "packet_name": "Gimbal",
+            "command_id": "0x0101",
+            "comment": "G",
+            "enums": [{ "name": "Mode", "type": "uint8_t", "values": [
+                { "name": "idle", "value": 0, "comment": "空闲" }
+            ] }],
             "fields": [
-                {
-                    "name": "temperature",
-                    "type": "float[3]",
-                    "comment": "温度数组"
-                },
-                {
-                    "name": "data",
-                    "type": "uint8_t[8]",
-                    "comment": "数据数组"
-                }
+                { "name": "yaw", "type": "double", "comment": "Y" },
+                { "name": "mode", "type": "Mode", "comment": "M" },
+                { "name": "id", "type": "signed short", "comment": "I" },
+                { "name": "gains", "type": "int32_t[3]", "comment": "G" }
             ]
         }"#;
+        assert!(validate(json).is_empty());
 
-        let diags = validate(json);
-        assert!(diags.is_empty());
+        let options = ValidateOptions {
+            allowed_types: Some(vec![
+                "uint8_t".to_string(),
+                "int16_t".to_string(),
+                "short".to_string(),
+                "float".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let result = validate_with_options(json, &options);
+        // 枚举字段不受限制，signed short 与 short 等价
+        assert_eq!(
+            result.iter().map(|d| d.code.clone()).collect::<Vec<_>>(),
+            vec![
+                ValidationCode::TypeNotAllowed("yaw".to_string(), "double".to_string()),
+                ValidationCode::TypeNotAllowed("gains".to_string(), "int32_t".to_string()),
+            ]
+        );
+        assert_eq!(result[0].help, None);
+        assert_eq!(result[1].help.as_deref(), Some("是否应为 'int16_t'？"));
+        let (start, len) = result[1].span.unwrap();
+        assert_eq!(&json[start..start + len], r#""int32_t[3]""#);
     }
 
     #[test]
-    fn test_validate_invalid_array_format() {
+    fn test_validate_fields_not_array() {
+        for fields in [r#"{ "name": "x" }"#, "42", r#""x""#, "null"] {
+            let json = format!(
+                r#"{{ "packet_name": "Bad", "command_id": "0x0101", "fields": {} }}"#,
+                fields
+            );
+            let result = validate(&json);
+            assert_eq!(result.len(), 1, "fields = {}", fields);
+            assert!(matches!(result[0].code, ValidationCode::InvalidFields));
+            assert_eq!(result[0].severity, Severity::Error);
+        }
+    }
+
+    #[test]
+    fn test_validate_bit_field_larger_than_u8() {
+        // 300 不能被截断为 44 后当作合法位域
         let json = r#"{
-            "packet_name": "InvalidArrayPacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
+            "packet_name": "Bits",
+            "command_id": "0x0101",
             "fields": [
-                {
-                    "name": "bad_array",
-                    "type": "float[]",
-                    "comment": "无效数组"
-                }
+                { "name": "flags", "type": "uint8_t", "bit_field": 300, "comment": "F" }
             ]
         }"#;
+        let result = validate(json);
+        assert!(
+            result
+                .iter()
+                .any(|d| matches!(d.code, ValidationCode::BitFieldLengthOverflow(_, 300, 8)))
+        );
+    }
 
-        let diags = validate(json);
-        assert_eq!(diags.len(), 1);
-        assert!(matches!(diags[0].code, ValidationCode::InvalidArrayType(_)));
-        assert_eq!(diags[0].severity, Severity::Error);
+    #[test]
+    fn test_input_size_limit() {
+        let json = r#"{"packet_name":"A","command_id":"0x0101","comment":"c",
+            "fields":[{"name":"x","type":"uint8_t","comment":"x"}]}"#;
+        let limited = |limit| ValidateOptions {
+            max_input_size: Some(limit),
+            ..Default::default()
+        };
+        assert!(validate_with_options(json, &limited(json.len())).is_empty());
+
+        let too_large = RplcDiagnostic {
+            code: ValidationCode::InputTooLarge(json.len(), 64),
+            severity: Severity::Error,
+            span: None,
+            help: None,
+        };
+        assert_eq!(
+            validate_with_options(json, &limited(64)),
+            vec![too_large.clone()]
+        );
+        let array = format!("[{}]", json);
+        assert_eq!(
+            validate_multiple_with_options(&array, &limited(64)),
+            vec![RplcDiagnostic {
+                code: ValidationCode::InputTooLarge(array.len(), 64),
+                ..too_large
+            }]
+        );
+        // 多包文档的各个包借用源文本拆分，位置与整个文档一致
+        let diags = validate_multiple_with_options(
+            &format!("[{}, {}]", json, json),
+            &ValidateOptions::default(),
+        );
+        assert!(matches!(
+            diags[0].code,
+            ValidationCode::DuplicatePacketName(_)
+        ));
     }
 
     #[test]
-    fn test_validate_array_invalid_base_type() {
+    fn test_malformed_inputs_do_not_panic() {
+        let inputs = [
+            "",
+            "{",
+            "[]",
+            "42",
+            r#""packet""#,
+            r#"{ "fields": {} }"#,
+            r#"{ "fields": [1, "x", null, []] }"#,
+            r#"{ "fields": [{ "name": 1, "type": 2, "bit_field": "3" }] }"#,
+            r#"{ "fields": [{ "name": "a", "type": "uint8_t", "bit_field": 9223372036854775807 }] }"#,
+            r#"{ "fields": [{ "name": "a", "type": "uint8_t[4294967295]" }] }"#,
+            r#"{ "packet_name": "P", "command_id": "0x", "fields": [] }"#,
+            r#"{ "packet_name": "P", "command_id": "0x0101", "align": 0, "fields": [] }"#,
+            r#"{ "packet_name": "P", "command_id": "0x0101", "align": 2147483648, "fields": [] }"#,
+            r#"[{ "packet_name": "P", "fields": {} }, 5]"#,
+        ];
+        for input in inputs {
+            let _ = validate(input);
+            let _ = validate_multiple(input);
+            let _ = validate_with_options(
+                input,
+                &ValidateOptions {
+                    targets: Target::ALL.to_vec(),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_bit_field_as_numeric_string() {
         let json = r#"{
-            "packet_name": "InvalidBaseTypePacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
+            "packet_name": "Flags",
+            "command_id": "0x0101",
             "fields": [
-                {
-                    "name": "bad_base",
-                    "type": "invalid_type[3]",
-                    "comment": "无效基础类型"
-                }
+                { "name": "mode", "type": "uint8_t", "bit_field": "4", "comment": "M" },
+                { "name": "level", "type": "uint8_t", "bit_field": 4, "comment": "L" }
             ]
         }"#;
-
-        let diags = validate(json);
-        assert_eq!(diags.len(), 1);
-        assert!(matches!(diags[0].code, ValidationCode::InvalidFieldType(_)));
-        assert_eq!(diags[0].severity, Severity::Error);
+        let result = validate(json);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].severity, Severity::Warning);
+        assert!(matches!(
+            &result[0].code,
+            ValidationCode::BitFieldAsString(name, 4) if name == "mode"
+        ));
+        assert!(crate::generator::generate(json).is_ok());
     }
 
     #[test]
-    fn test_validate_array_with_bitfield_error() {
+    fn test_validate_bit_field_string_still_checked() {
         let json = r#"{
-            "packet_name": "ArrayBitFieldPacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
+            "packet_name": "Flags",
+            "command_id": "0x0101",
             "fields": [
-                {
-                    "name": "array_field",
-                    "type": "uint8_t[3]",
-                    "bit_field": 4,
-                    "comment": "数组位域"
-                }
+                { "name": "a", "type": "uint8_t", "bit_field": "12", "comment": "A" },
+                { "name": "b", "type": "uint8_t", "bit_field": "x", "comment": "B" }
             ]
         }"#;
+        let result = validate(json);
+        assert!(
+            result
+                .iter()
+                .any(|d| matches!(d.code, ValidationCode::BitFieldLengthOverflow(_, 12, 8)))
+        );
+        assert!(result.iter().any(|d| matches!(
+            &d.code,
+            ValidationCode::InvalidBitField(name) if name == "b"
+        )));
+    }
 
-        let diags = validate(json);
-        assert_eq!(diags.len(), 1);
-        assert!(matches!(diags[0].code, ValidationCode::BitFieldOnArray(_)));
-        assert_eq!(diags[0].severity, Severity::Error);
+    #[test]
+    fn test_validate_array_input_in_single_mode() {
+        let json = r#"[
+            { "packet_name": "A", "command_id": "0x0101", "fields": [] },
+            { "packet_name": "B", "command_id": "0x0102", "fields": [] }
+        ]"#;
+        let result = validate(json);
+        assert_eq!(result.len(), 1);
+        assert!(matches!(
+            result[0].code,
+            ValidationCode::MultiPacketInput(2)
+        ));
+        assert_eq!(result[0].severity, Severity::Error);
+        assert_eq!(result[0].span, Some((0, json.len())));
+
+        // 多包模式下不应报告该错误
+        assert!(
+            validate_multiple(json)
+                .iter()
+                .all(|d| !matches!(d.code, ValidationCode::MultiPacketInput(_)))
+        );
     }
 
     #[test]
-    fn test_validate_array_valid_with_valid_base_types() {
-        // 测试各种支持的数组类型
+    fn test_validate_packet_set_in_single_mode() {
         let json = r#"{
-            "packet_name": "ValidArraysPacket",
-            "command_id": "0x0104",
-            "namespace": null,
-            "packed": true,
-            "header_guard": null,
-            "fields": [
-                { "name": "float_arr", "type": "float[3]", "comment": "浮点数组" },
-                { "name": "double_arr", "type": "double[2]", "comment": "双精度数组" },
-                { "name": "uint8_arr", "type": "uint8_t[16]", "comment": "8位无符号数组" },
-                { "name": "int32_arr", "type": "int32_t[8]", "comment": "32位有符号数组" }
-            ]
+            "protocol_version": "1.0.0",
+            "packets": [{ "packet_name": "A", "command_id": "0x0101", "fields": [] }]
         }"#;
-
-        let diags = validate(json);
-        assert!(diags.is_empty());
+        let result = validate(json);
+        assert_eq!(result.len(), 1);
+        assert!(matches!(
+            result[0].code,
+            ValidationCode::MultiPacketInput(1)
+        ));
     }
 }
@@ -0,0 +1,451 @@
+//! YAML 格式的协议定义
+//!
+//! YAML 被逐个事件转写为 JSON：带引号的标量是字符串，普通标量按 YAML 1.2 核心模式识别
+//! null、布尔值与十进制数字，其余（包括 `0x0101` 这样的十六进制写法）都作为字符串
+
+use std::collections::HashMap;
+
+use saphyr_parser::{Event, Parser, ScalarStyle, Span as YamlSpan, Tag};
+
+use crate::diagnostics::{RplcDiagnostic, Severity, Span, ValidationCode};
+use crate::generator::{GenerateError, GenerateOptions, generate_with_options};
+use crate::transcript::{InputFormat, SpanMapping, Transcript, validate_source};
+use crate::validator::ValidateOptions;
+
+/// 校验 YAML 格式的定义，诊断位置相对于 YAML 文本
+pub fn validate_yaml(yaml: &str) -> Vec<RplcDiagnostic> {
+    validate_source(yaml, InputFormat::Yaml, &ValidateOptions::default())
+}
+
+/// 从 YAML 格式的单包定义生成 C++ 头文件，校验规则与 `generate` 相同
+pub fn generate_from_yaml(yaml: &str, options: &GenerateOptions) -> Result<String, GenerateError> {
+    let transcript = yaml_to_json(yaml).map_err(|_| GenerateError::ValidationError)?;
+    generate_with_options(transcript.json(), options)
+}
+
+pub(crate) fn yaml_to_json(yaml: &str) -> Result<Transcript, Vec<RplcDiagnostic>> {
+    let events = Parser::new_from_str(yaml)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            let offset = byte_offset(yaml, e.marker().index());
+            vec![invalid_yaml(e.info().to_string(), (offset, 0))]
+        })?;
+
+    let mut writer = Writer {
+        yaml,
+        events: events.into_iter().peekable(),
+        json: String::new(),
+        spans: Vec::new(),
+        line: 1,
+        anchors: HashMap::new(),
+    };
+    writer
+        .document()
+        .map_err(|(reason, span)| vec![invalid_yaml(reason, span)])?;
+    Ok(Transcript::new(writer.json, writer.spans))
+}
+
+fn invalid_yaml(reason: String, span: Span) -> RplcDiagnostic {
+    RplcDiagnostic {
+        code: ValidationCode::InvalidYaml(reason),
+        severity: Severity::Error,
+        span: Some(span),
+        help: None,
+    }
+}
+
+/// 解析器的位置按字符计数，换算为字节偏移
+fn byte_offset(text: &str, char_index: usize) -> usize {
+    text.char_indices()
+        .nth(char_index)
+        .map_or(text.len(), |(offset, _)| offset)
+}
+
+/// 转写失败的原因与位置
+type YamlError = (String, Span);
+
+struct Writer<'a> {
+    yaml: &'a str,
+    events: std::iter::Peekable<std::vec::IntoIter<(Event<'a>, YamlSpan)>>,
+    json: String,
+    spans: Vec<SpanMapping>,
+    /// JSON 文本当前所在的行，写入值之前补齐换行使其与 YAML 中的行号一致
+    line: usize,
+    /// 锚点对应的 JSON 文本，别名处原样展开
+    anchors: HashMap<usize, String>,
+}
+
+impl<'a> Writer<'a> {
+    fn document(&mut self) -> Result<(), YamlError> {
+        while let Some((event, span)) = self.events.next() {
+            match event {
+                Event::StreamStart | Event::DocumentEnd => {}
+                Event::DocumentStart(_) if !self.json.is_empty() => {
+                    let offset = self.offset(span.start.index());
+                    return Err(("只支持单个 YAML 文档".to_string(), (offset, 0)));
+                }
+                Event::DocumentStart(_) => {
+                    if matches!(self.events.peek(), Some((Event::DocumentEnd, _))) {
+                        self.json.push_str("null");
+                    } else {
+                        self.value()?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        if self.json.is_empty() {
+            self.json.push_str("null");
+        }
+        Ok(())
+    }
+
+    fn offset(&self, char_index: usize) -> usize {
+        byte_offset(self.yaml, char_index)
+    }
+
+    fn source_range(&self, span: &YamlSpan) -> std::ops::Range<usize> {
+        let start = self.offset(span.start.index());
+        let end = self.offset(span.end.index()).max(start);
+        // 块结构的结束位置落在下一个记号之前，去掉其后的空白
+        let trimmed = self.yaml[start..end].trim_end().len();
+        start..start + trimmed
+    }
+
+    fn pad_to_line(&mut self, line: usize) {
+        while self.line < line {
+            self.json.push('\n');
+            self.line += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<(), YamlError> {
+        let Some((event, span)) = self.events.next() else {
+            return Ok(());
+        };
+        self.pad_to_line(span.start.line());
+        let json_start = self.json.len();
+        let mut source = self.source_range(&span);
+        let mut content = None;
+        let anchor = match event {
+            Event::Scalar(value, style, anchor, tag) => {
+                content = self.scalar(&value, style, tag.as_deref(), &source);
+                anchor
+            }
+            Event::SequenceStart(anchor, _) => {
+                self.json.push('[');
+                let mut first = true;
+                loop {
+                    if let Some((Event::SequenceEnd, end)) = self.events.peek() {
+                        let end = *end;
+                        source.end = self.source_range(&end).end.max(source.end);
+                        self.events.next();
+                        break;
+                    }
+                    if !first {
+                        self.json.push(',');
+                    }
+                    first = false;
+                    self.value()?;
+                }
+                self.json.push(']');
+                anchor
+            }
+            Event::MappingStart(anchor, _) => {
+                self.json.push('{');
+                let mut first = true;
+                loop {
+                    if let Some((Event::MappingEnd, end)) = self.events.peek() {
+                        let end = *end;
+                        source.end = self.source_range(&end).end.max(source.end);
+                        self.events.next();
+                        break;
+                    }
+                    if !first {
+                        self.json.push(',');
+                    }
+                    first = false;
+                    self.key()?;
+                    self.json.push(':');
+                    self.value()?;
+                }
+                self.json.push('}');
+                anchor
+            }
+            Event::Alias(id) => {
+                // 展开的文本只占一行，不打乱之后的行号
+                let text = self.anchors.get(&id).cloned().unwrap_or_default();
+                self.json.push_str(&text.replace('\n', " "));
+                0
+            }
+            _ => return Ok(()),
+        };
+        if anchor != 0 {
+            self.anchors
+                .insert(anchor, self.json[json_start..].to_string());
+        }
+        let json = json_start..self.json.len();
+        let content = content.map(|source_start| (json.start + 1, source_start));
+        self.spans.push(SpanMapping {
+            json,
+            source,
+            content,
+        });
+        Ok(())
+    }
+
+    /// 映射的键：必须是标量，总是写成 JSON 字符串
+    fn key(&mut self) -> Result<(), YamlError> {
+        let Some((event, span)) = self.events.next() else {
+            return Ok(());
+        };
+        self.pad_to_line(span.start.line());
+        let source = self.source_range(&span);
+        let Event::Scalar(value, ..) = event else {
+            return Err((
+                "映射的键必须是标量".to_string(),
+                (source.start, source.len()),
+            ));
+        };
+        let json_start = self.json.len();
+        self.json.push_str(&json_string(&value));
+        let content = verbatim_start(self.yaml, &source, &value)
+            .filter(|_| json_string(&value).len() == value.len() + 2)
+            .map(|source_start| (json_start + 1, source_start));
+        self.spans.push(SpanMapping {
+            json: json_start..self.json.len(),
+            source,
+            content,
+        });
+        Ok(())
+    }
+
+    /// 写入标量，返回逐字节相同的字符串内容在 YAML 中的起点
+    fn scalar(
+        &mut self,
+        value: &str,
+        style: ScalarStyle,
+        tag: Option<&Tag>,
+        source: &std::ops::Range<usize>,
+    ) -> Option<usize> {
+        let forced_string = tag.is_some_and(|tag| tag.is_yaml_core_schema() && tag.suffix == "str");
+        if style == ScalarStyle::Plain
+            && !forced_string
+            && let Some(json) = plain_scalar_json(value)
+        {
+            self.json.push_str(&json);
+            return None;
+        }
+        let json = json_string(value);
+        let verbatim = json.len() == value.len() + 2;
+        self.json.push_str(&json);
+        verbatim_start(self.yaml, source, value).filter(|_| verbatim)
+    }
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
+
+/// 标量的源文本（或去掉首尾引号后）与值相同时，返回值在源文本中的起点
+fn verbatim_start(yaml: &str, source: &std::ops::Range<usize>, value: &str) -> Option<usize> {
+    let text = yaml.get(source.clone())?;
+    if text == value {
+        return Some(source.start);
+    }
+    let quoted = text.len() >= 2
+        && (text.starts_with('"') && text.ends_with('"')
+            || text.starts_with('\'') && text.ends_with('\''));
+    (quoted && &text[1..text.len() - 1] == value).then_some(source.start + 1)
+}
+
+/// 普通标量中的 null、布尔值与十进制数字，其余返回 `None` 按字符串处理
+fn plain_scalar_json(value: &str) -> Option<String> {
+    match value {
+        "" | "~" | "null" | "Null" | "NULL" => return Some("null".to_string()),
+        "true" | "True" | "TRUE" => return Some("true".to_string()),
+        "false" | "False" | "FALSE" => return Some("false".to_string()),
+        _ => {}
+    }
+    let digits = value.strip_prefix(['+', '-']).unwrap_or(value);
+    if !digits.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    if digits.bytes().all(|b| b.is_ascii_digit()) {
+        return value.parse::<i128>().ok().map(|n| n.to_string());
+    }
+    let is_float = digits
+        .bytes()
+        .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'+' | b'-'));
+    if !is_float {
+        return None;
+    }
+    let number: f64 = value.parse().ok()?;
+    let json = serde_json::Number::from_f64(number)?.to_string();
+    // 与 JSON 写法相同时保留原文，避免 0.1 之类的值被改写
+    Some(
+        if serde_json::from_str::<serde_json::Number>(value).is_ok() {
+            value.to_string()
+        } else {
+            json
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate;
+
+    const PACKET: &str = "\
+# 云台状态
+packet_name: GimbalStatus
+command_id: 0x0104
+comment: 云台状态
+fields:
+  - name: yaw
+    type: float
+    comment: 偏航角
+  - name: mode
+    type: uint8_t
+    bit_field: 4
+    comment: '模式'
+  - {name: flags, type: uint8_t, bit_field: 4, comment: \"标志\"}
+";
+
+    #[test]
+    fn test_yaml_transcript_keeps_lines() {
+        let transcript = yaml_to_json(PACKET).unwrap();
+        let value: serde_json::Value = serde_json::from_str(transcript.json()).unwrap();
+        assert_eq!(value["command_id"], "0x0104");
+        assert_eq!(value["fields"][1]["bit_field"], 4);
+        assert_eq!(value["fields"][2]["comment"], "标志");
+        for (line, text) in transcript.json().lines().enumerate() {
+            if text.contains("\"mode\"") {
+                assert_eq!(line + 1, 9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_from_yaml_matches_json() {
+        let json = r#"{"packet_name":"GimbalStatus","command_id":"0x0104","comment":"云台状态",
+            "fields":[{"name":"yaw","type":"float","comment":"偏航角"},
+            {"name":"mode","type":"uint8_t","bit_field":4,"comment":"模式"},
+            {"name":"flags","type":"uint8_t","bit_field":4,"comment":"标志"}]}"#;
+        assert_eq!(
+            generate_from_yaml(PACKET, &GenerateOptions::default()).unwrap(),
+            generate(json).unwrap()
+        );
+        assert!(validate_yaml(PACKET).is_empty());
+    }
+
+    #[test]
+    fn test_yaml_diagnostics_point_into_yaml() {
+        let yaml = PACKET.replace("type: float", "type: flaot");
+        let diags = validate_yaml(&yaml);
+        assert_eq!(diags.len(), 1);
+        assert!(matches!(diags[0].code, ValidationCode::InvalidFieldType(_)));
+        let (start, len) = diags[0].span.unwrap();
+        assert_eq!(&yaml[start..start + len], "flaot");
+
+        // 未知的键指向 YAML 中的键
+        let yaml = PACKET.replace("    comment: 偏航角", "    coment: 偏航角");
+        let diag = validate_yaml(&yaml)
+            .into_iter()
+            .find(|d| matches!(d.code, ValidationCode::UnknownKey(_)))
+            .unwrap();
+        let (start, len) = diag.span.unwrap();
+        assert_eq!(&yaml[start..start + len], "coment");
+
+        // 重复的键报告 YAML 中的行号
+        let yaml = PACKET.replace("comment: 云台状态", "comment: 云台状态\ncomment: 重复");
+        let diag = validate_yaml(&yaml)
+            .into_iter()
+            .find(|d| matches!(d.code, ValidationCode::DuplicateKey(..)))
+            .unwrap();
+        assert_eq!(
+            diag.code,
+            ValidationCode::DuplicateKey("comment".to_string(), 4)
+        );
+        let (start, len) = diag.span.unwrap();
+        assert_eq!(&yaml[start..start + len], "comment");
+        assert!(yaml[..start].ends_with("云台状态\n"));
+    }
+
+    #[test]
+    fn test_yaml_placeholder_span_inside_quoted_string() {
+        let yaml = PACKET.replace(
+            "comment: 云台状态\n",
+            "comment: 云台状态\ntraits_template: \"REG(${nmae})\"\n",
+        );
+        let diag = validate_yaml(&yaml).remove(0);
+        let (start, len) = diag.span.unwrap();
+        assert_eq!(&yaml[start..start + len], "${nmae}");
+    }
+
+    #[test]
+    fn test_yaml_syntax_and_structure_errors() {
+        let diags = validate_yaml("packet_name: [GimbalStatus\n");
+        assert!(matches!(diags[0].code, ValidationCode::InvalidYaml(_)));
+        assert!(diags[0].span.is_some());
+
+        let diags = validate_yaml("? [a, b]\n: 1\n");
+        assert_eq!(
+            diags[0].code,
+            ValidationCode::InvalidYaml("映射的键必须是标量".to_string())
+        );
+
+        let diags = validate_yaml("a: 1\n---\nb: 2\n");
+        assert_eq!(
+            diags[0].code,
+            ValidationCode::InvalidYaml("只支持单个 YAML 文档".to_string())
+        );
+    }
+
+    #[test]
+    fn test_plain_scalar_resolution() {
+        let cases = [
+            ("~", Some("null")),
+            ("True", Some("true")),
+            ("12", Some("12")),
+            ("+12", Some("12")),
+            ("007", Some("7")),
+            ("-3.5", Some("-3.5")),
+            (".5", Some("0.5")),
+            ("1e3", Some("1e3")),
+            ("0x0104", None),
+            ("0o17", None),
+            (".inf", None),
+            ("uint8_t[4]", None),
+            ("1.2.3", None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(plain_scalar_json(value).as_deref(), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_yaml_anchors_and_aliases() {
+        let yaml = "\
+- &base
+  packet_name: A
+  command_id: 0x0101
+  fields: [{name: x, type: uint8_t}]
+- packet_name: B
+  command_id: 0x0102
+  fields: *fields
+";
+        let yaml = yaml.replace("fields: [", "fields: &fields [");
+        let transcript = yaml_to_json(&yaml).unwrap();
+        let value: serde_json::Value = serde_json::from_str(transcript.json()).unwrap();
+        assert_eq!(value[1]["fields"], value[0]["fields"]);
+        assert_eq!(transcript.json().lines().count(), yaml.lines().count());
+    }
+
+    #[test]
+    fn test_empty_yaml_document() {
+        assert_eq!(yaml_to_json("").unwrap().json(), "null");
+        assert_eq!(yaml_to_json("# 只有注释\n").unwrap().json(), "null");
+    }
+}
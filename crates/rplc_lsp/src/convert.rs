@@ -0,0 +1,157 @@
+//! rplc_core 的结果与 LSP 类型之间的转换；rplc_core 的位置是字节偏移，LSP 的列按 UTF-16 码元计数
+
+use miette::Diagnostic as _;
+use rplc_core::{Completion, CompletionKind, HoverInfo, RplcDiagnostic, Severity};
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, NumberOrString, Position,
+    Range,
+};
+
+/// 字节偏移对应的位置；落在多字节字符中间时取该字符的起点
+pub fn position(text: &str, offset: usize) -> Position {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+/// 位置对应的字节偏移；超出行尾或文档末尾时取行尾或文档末尾
+pub fn offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len(),
+        }
+    }
+    let line = &text[line_start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= position.character {
+            return line_start + i;
+        }
+        units += c.len_utf16() as u32;
+    }
+    line_start + line.len()
+}
+
+pub fn diagnostic(text: &str, diag: &RplcDiagnostic) -> Diagnostic {
+    // 没有位置的诊断（如输入过大）标在文档开头
+    let (start, len) = diag.span.unwrap_or((0, 0));
+    let mut message = diag.code.to_string();
+    if let Some(help) = &diag.help {
+        message.push_str("\n帮助: ");
+        message.push_str(help);
+    }
+    Diagnostic {
+        range: Range {
+            start: position(text, start),
+            end: position(text, start + len),
+        },
+        severity: Some(match diag.severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+        }),
+        code: diag
+            .code
+            .code()
+            .map(|code| NumberOrString::String(code.to_string())),
+        source: Some("rplc".to_string()),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+/// 悬停信息：字段的类型、偏移与大小，以及所在包的总大小
+pub fn hover_markdown(hover: &HoverInfo) -> String {
+    let layout = &hover.layout;
+    let packet = format!(
+        "包 `{}` 共 {} 字节，对齐 {}{}",
+        hover.packet,
+        layout.size,
+        layout.align,
+        if layout.bitstream { "（位流）" } else { "" }
+    );
+    let Some(field) = hover.field.map(|index| &layout.fields[index]) else {
+        return packet;
+    };
+    let mut offset = format!("{} 字节", field.byte_offset());
+    if field.bit_offset() != 0 {
+        offset.push_str(&format!(" + {} 位", field.bit_offset()));
+    }
+    let mut lines = vec![
+        format!("**{}**: `{}`", field.name, field.ty),
+        String::new(),
+        format!("- 偏移：{}", offset),
+        format!("- 大小：{}", bits(field.size_bits)),
+    ];
+    if let Some(len) = field.array_len {
+        lines.push(format!("- 数组长度：{}", len));
+    }
+    if field.padding_bits != 0 {
+        lines.push(format!("- 之前的填充：{}", bits(field.padding_bits)));
+    }
+    lines.push(String::new());
+    lines.push(packet);
+    lines.join("\n")
+}
+
+fn bits(bits: u32) -> String {
+    if bits.is_multiple_of(8) {
+        format!("{} 字节", bits / 8)
+    } else {
+        format!("{} 位", bits)
+    }
+}
+
+pub fn completion_items(completion: Completion) -> Vec<CompletionItem> {
+    let kind = match completion.kind {
+        CompletionKind::Key => CompletionItemKind::PROPERTY,
+        CompletionKind::Type => CompletionItemKind::TYPE_PARAMETER,
+    };
+    completion
+        .candidates
+        .into_iter()
+        .enumerate()
+        .map(|(index, label)| CompletionItem {
+            insert_text: (!completion.in_string).then(|| format!("\"{}\"", label)),
+            // 保持候选的排列顺序，常用的类型在前
+            sort_text: Some(format!("{:04}", index)),
+            label,
+            kind: Some(kind),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_round_trip() {
+        let text = "{\n  \"comment\": \"偏航😀\", \"x\": 1\n}";
+        let x = text.find("\"x\"").unwrap();
+        // 中文字符占一个 UTF-16 码元，😀 占两个
+        let pos = position(text, x);
+        assert_eq!((pos.line, pos.character), (1, 21));
+        assert_eq!(offset(text, pos), x);
+
+        assert_eq!(position(text, text.len() + 10), Position::new(2, 1));
+        assert_eq!(
+            offset(text, Position::new(1, 200)),
+            text.find("1\n").unwrap() + 1
+        );
+        assert_eq!(offset(text, Position::new(9, 0)), text.len());
+        // 落在多字节字符中间
+        let inside = text.find("偏").unwrap() + 1;
+        assert_eq!(position(text, inside), Position::new(1, 14));
+    }
+}
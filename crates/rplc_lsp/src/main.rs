@@ -0,0 +1,188 @@
+//! rplc 语言服务器：在编辑器中实时报告诊断，悬停显示字段的偏移与大小，补全键与类型
+//!
+//! 通过标准输入输出与编辑器通信；JSON、YAML 与 TOML 定义都报告诊断，悬停与补全只支持 JSON
+
+mod convert;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use rplc_core::{
+    InputFormat, ProjectConfig, ValidateOptions, completion_at, hover_at, validate_source,
+};
+use tower_lsp::jsonrpc::Result;
+use tower_lsp::lsp_types::{
+    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, InitializeParams, InitializeResult, MarkupContent, MarkupKind,
+    ServerCapabilities, ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+
+/// 编辑器中打开的定义文件
+struct Document {
+    text: String,
+    format: InputFormat,
+    /// 文件所在项目的 rplc.toml 中的类型列表与编译器
+    options: ValidateOptions,
+}
+
+impl Document {
+    fn new(uri: &Url, text: String) -> Document {
+        let path = uri.to_file_path().ok();
+        let format = path
+            .as_deref()
+            .map_or(InputFormat::Json, InputFormat::from_path);
+        let project = path.as_deref().and_then(project_config).unwrap_or_default();
+        Document {
+            text,
+            format,
+            options: ValidateOptions {
+                allowed_types: project.allowed_types,
+                compiler_profiles: project.compiler_profiles,
+                ..ValidateOptions::default()
+            },
+        }
+    }
+}
+
+/// 项目配置无法读取或无效时按默认设置校验，由命令行报告配置本身的错误
+fn project_config(path: &Path) -> Option<ProjectConfig> {
+    let config = ProjectConfig::discover(path)?;
+    let content = std::fs::read_to_string(config).ok()?;
+    ProjectConfig::parse(&content).ok()
+}
+
+struct Backend {
+    client: Client,
+    documents: Mutex<HashMap<Url, Document>>,
+}
+
+impl Backend {
+    /// 校验文档并发布诊断；锁只在校验期间持有，不跨越 await
+    async fn publish(&self, uri: Url, version: Option<i32>) {
+        let diagnostics = {
+            let documents = self.documents.lock().expect("文档表的锁未被毒化");
+            let Some(doc) = documents.get(&uri) else {
+                return;
+            };
+            validate_source(&doc.text, doc.format, &doc.options)
+                .iter()
+                .map(|diag| convert::diagnostic(&doc.text, diag))
+                .collect()
+        };
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec!["\"".to_string()]),
+                    ..CompletionOptions::default()
+                }),
+                ..ServerCapabilities::default()
+            },
+            server_info: Some(ServerInfo {
+                name: "rplc_lsp".to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+        })
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let doc = params.text_document;
+        let document = Document::new(&doc.uri, doc.text);
+        self.documents
+            .lock()
+            .expect("文档表的锁未被毒化")
+            .insert(doc.uri.clone(), document);
+        self.publish(doc.uri, Some(doc.version)).await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        let uri = params.text_document.uri;
+        // 全量同步：最后一次变更即为完整的新文本
+        let Some(change) = params.content_changes.into_iter().next_back() else {
+            return;
+        };
+        if let Some(doc) = self
+            .documents
+            .lock()
+            .expect("文档表的锁未被毒化")
+            .get_mut(&uri)
+        {
+            doc.text = change.text;
+        }
+        self.publish(uri, Some(params.text_document.version)).await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents
+            .lock()
+            .expect("文档表的锁未被毒化")
+            .remove(&uri);
+        self.client.publish_diagnostics(uri, Vec::new(), None).await;
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let at = params.text_document_position_params;
+        let documents = self.documents.lock().expect("文档表的锁未被毒化");
+        let Some(doc) = documents
+            .get(&at.text_document.uri)
+            .filter(|doc| doc.format == InputFormat::Json)
+        else {
+            return Ok(None);
+        };
+        let offset = convert::offset(&doc.text, at.position);
+        Ok(hover_at(&doc.text, offset).map(|hover| Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: convert::hover_markdown(&hover),
+            }),
+            range: None,
+        }))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let at = params.text_document_position;
+        let documents = self.documents.lock().expect("文档表的锁未被毒化");
+        let Some(doc) = documents
+            .get(&at.text_document.uri)
+            .filter(|doc| doc.format == InputFormat::Json)
+        else {
+            return Ok(None);
+        };
+        let offset = convert::offset(&doc.text, at.position);
+        Ok(
+            completion_at(&doc.text, offset, doc.options.allowed_types.as_deref())
+                .map(|completion| CompletionResponse::Array(convert::completion_items(completion))),
+        )
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let (service, socket) = LspService::new(|client| Backend {
+        client,
+        documents: Mutex::new(HashMap::new()),
+    });
+    Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
+        .serve(service)
+        .await;
+}
@@ -0,0 +1,91 @@
+//! DJI 裁判系统串口协议的 CRC 校验，与生成的 C++ 头文件中的 `rplc_dji` 相同
+//!
+//! 逐位计算，结果与官方查表实现一致
+
+pub const CRC8_INIT: u8 = 0xFF;
+pub const CRC16_INIT: u16 = 0xFFFF;
+
+/// CRC8（多项式 0x31，按位反转），用于帧头校验
+pub const fn crc8(data: &[u8], mut crc: u8) -> u8 {
+    let mut i = 0;
+    while i < data.len() {
+        crc ^= data[i];
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8C
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+/// CRC16（多项式 0x1021，按位反转），用于整帧校验
+pub const fn crc16(data: &[u8], mut crc: u16) -> u16 {
+    let mut i = 0;
+    while i < data.len() {
+        crc ^= data[i] as u16;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+/// `frame` 的最后一个字节是否为之前所有字节的 CRC8
+pub fn verify_crc8(frame: &[u8]) -> bool {
+    match frame.split_last() {
+        Some((&crc, data)) => crc8(data, CRC8_INIT) == crc,
+        None => false,
+    }
+}
+
+/// `frame` 的最后两个字节是否为之前所有字节的 CRC16（小端）
+pub fn verify_crc16(frame: &[u8]) -> bool {
+    if frame.len() <= 2 {
+        return false;
+    }
+    let (data, tail) = frame.split_at(frame.len() - 2);
+    crc16(data, CRC16_INIT).to_le_bytes() == [tail[0], tail[1]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc_check_values() {
+        // CRC-8/MAXIM 与 CRC-16/MCRF4XX 的标准校验值
+        assert_eq!(crc8(b"123456789", 0x00), 0xA1);
+        assert_eq!(crc16(b"123456789", CRC16_INIT), 0x6F91);
+    }
+
+    #[test]
+    fn test_verify_crc() {
+        let mut header = [0xA5, 0x0A, 0x00, 0x01, 0x00];
+        header[4] = crc8(&header[..4], CRC8_INIT);
+        assert!(verify_crc8(&header));
+        header[3] = 2;
+        assert!(!verify_crc8(&header));
+        assert!(!verify_crc8(&[]));
+
+        let mut frame = [1, 2, 3, 0, 0];
+        let crc = crc16(&frame[..3], CRC16_INIT).to_le_bytes();
+        frame[3..].copy_from_slice(&crc);
+        assert!(verify_crc16(&frame));
+        frame[0] = 9;
+        assert!(!verify_crc16(&frame));
+        assert!(!verify_crc16(&[0, 0]));
+    }
+}
@@ -0,0 +1,312 @@
+use core::fmt;
+
+use crate::crc::{CRC8_INIT, CRC16_INIT, crc8, crc16, verify_crc8, verify_crc16};
+use crate::packet::Packet;
+
+/// 帧起始字节
+pub const SOF: u8 = 0xA5;
+/// 帧头长度：SOF、数据段长度（2 字节）、包序号与 CRC8
+pub const HEADER_SIZE: usize = 5;
+/// 命令码长度
+pub const CMD_SIZE: usize = 2;
+/// 帧尾 CRC16 长度
+pub const TAIL_SIZE: usize = 2;
+
+/// 数据段为 `payload_len` 字节时的整帧长度
+pub const fn frame_size(payload_len: usize) -> usize {
+    HEADER_SIZE + CMD_SIZE + payload_len + TAIL_SIZE
+}
+
+/// 一帧经过校验的数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// 包序号
+    pub seq: u8,
+    /// 命令码
+    pub cmd: u16,
+    /// 数据段
+    pub payload: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    /// 按包类型解码数据段；命令码或长度不符时返回 `None`
+    pub fn decode<P: Packet>(&self) -> Option<P> {
+        if self.cmd != P::CMD {
+            return None;
+        }
+        P::from_bytes(self.payload)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameError {
+    /// 帧头的 CRC8 不符，已丢弃该起始字节并重新同步
+    HeaderCrc,
+    /// 整帧的 CRC16 不符
+    FrameCrc { seq: u8, cmd: u16 },
+    /// 帧长超过解析缓冲区
+    TooLong { payload_len: usize },
+    /// 打包时输出缓冲区不足
+    BufferTooSmall { needed: usize },
+    /// 数据段超过帧头中长度字段的范围
+    PayloadTooLarge { payload_len: usize },
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::HeaderCrc => write!(f, "帧头 CRC8 校验失败"),
+            FrameError::FrameCrc { seq, cmd } => {
+                write!(f, "帧 (序号 {}, 命令码 0x{:04X}) CRC16 校验失败", seq, cmd)
+            }
+            FrameError::TooLong { payload_len } => {
+                write!(f, "数据段长度 {} 超过解析缓冲区", payload_len)
+            }
+            FrameError::BufferTooSmall { needed } => {
+                write!(f, "输出缓冲区不足，需要 {} 字节", needed)
+            }
+            FrameError::PayloadTooLarge { payload_len } => {
+                write!(f, "数据段长度 {} 超过 65535 字节", payload_len)
+            }
+        }
+    }
+}
+
+impl core::error::Error for FrameError {}
+
+/// 把包打包为整帧写入 `out`，返回整帧长度
+pub fn encode_frame<P: Packet>(packet: &P, seq: u8, out: &mut [u8]) -> Result<usize, FrameError> {
+    let needed = frame_size(P::SIZE);
+    let frame = out
+        .get_mut(..needed)
+        .ok_or(FrameError::BufferTooSmall { needed })?;
+    packet.write_bytes(&mut frame[HEADER_SIZE + CMD_SIZE..]);
+    finish_frame(frame, seq, P::CMD, P::SIZE);
+    Ok(needed)
+}
+
+/// 把任意数据段打包为整帧写入 `out`，返回整帧长度
+pub fn encode_raw_frame(
+    cmd: u16,
+    seq: u8,
+    payload: &[u8],
+    out: &mut [u8],
+) -> Result<usize, FrameError> {
+    if payload.len() > usize::from(u16::MAX) {
+        return Err(FrameError::PayloadTooLarge {
+            payload_len: payload.len(),
+        });
+    }
+    let needed = frame_size(payload.len());
+    let frame = out
+        .get_mut(..needed)
+        .ok_or(FrameError::BufferTooSmall { needed })?;
+    frame[HEADER_SIZE + CMD_SIZE..needed - TAIL_SIZE].copy_from_slice(payload);
+    finish_frame(frame, seq, cmd, payload.len());
+    Ok(needed)
+}
+
+/// 数据段已写好时补全帧头、命令码与 CRC
+fn finish_frame(frame: &mut [u8], seq: u8, cmd: u16, payload_len: usize) {
+    let length = (payload_len as u16).to_le_bytes();
+    frame[..4].copy_from_slice(&[SOF, length[0], length[1], seq]);
+    frame[4] = crc8(&frame[..4], CRC8_INIT);
+    frame[HEADER_SIZE..HEADER_SIZE + CMD_SIZE].copy_from_slice(&cmd.to_le_bytes());
+    let end = frame.len() - TAIL_SIZE;
+    let crc = crc16(&frame[..end], CRC16_INIT);
+    frame[end..].copy_from_slice(&crc.to_le_bytes());
+}
+
+/// 逐字节接收串口数据并切分出完整的帧，缓冲区为 `N` 字节，不分配内存
+///
+/// 帧头校验失败时丢弃起始字节，从缓冲区中的下一个 SOF 重新同步，
+/// 因此数据段中恰好出现的 0xA5 不会导致后续的帧丢失
+pub struct FrameParser<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for FrameParser<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FrameParser<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// 丢弃已经缓存的字节
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    /// 依次处理 `data` 中的字节，每得到一帧或一个错误调用一次 `on_frame`
+    pub fn feed(&mut self, data: &[u8], mut on_frame: impl FnMut(Result<Frame<'_>, FrameError>)) {
+        for &byte in data {
+            if let Some(result) = self.push(byte) {
+                on_frame(result);
+            }
+        }
+    }
+
+    /// 处理一个字节；凑齐一帧时返回该帧，帧有误时返回错误
+    pub fn push(&mut self, byte: u8) -> Option<Result<Frame<'_>, FrameError>> {
+        if self.len == 0 && byte != SOF {
+            return None;
+        }
+        if self.len == N {
+            // 缓冲区不足以放下帧头，只能从头开始
+            self.len = 0;
+            return None;
+        }
+        self.buf[self.len] = byte;
+        self.len += 1;
+
+        if self.len < HEADER_SIZE {
+            return None;
+        }
+        if self.len == HEADER_SIZE && !verify_crc8(&self.buf[..HEADER_SIZE]) {
+            self.resync();
+            return Some(Err(FrameError::HeaderCrc));
+        }
+        let payload_len = usize::from(u16::from_le_bytes([self.buf[1], self.buf[2]]));
+        let total = frame_size(payload_len);
+        if total > N {
+            self.len = 0;
+            return Some(Err(FrameError::TooLong { payload_len }));
+        }
+        if self.len < total {
+            return None;
+        }
+
+        self.len = 0;
+        let frame = &self.buf[..total];
+        let seq = frame[3];
+        let cmd = u16::from_le_bytes([frame[HEADER_SIZE], frame[HEADER_SIZE + 1]]);
+        if !verify_crc16(frame) {
+            return Some(Err(FrameError::FrameCrc { seq, cmd }));
+        }
+        Some(Ok(Frame {
+            seq,
+            cmd,
+            payload: &frame[HEADER_SIZE + CMD_SIZE..total - TAIL_SIZE],
+        }))
+    }
+
+    /// 丢弃起始字节，把缓冲区中下一个 SOF 及其后的字节移到开头
+    fn resync(&mut self) {
+        let next = self.buf[1..self.len].iter().position(|&b| b == SOF);
+        match next {
+            Some(start) => {
+                self.buf.copy_within(start + 1..self.len, 0);
+                self.len -= start + 1;
+            }
+            None => self.len = 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::tests::Gimbal;
+
+    #[test]
+    fn test_encode_frame() {
+        let mut out = [0u8; 16];
+        let len = encode_frame(&Gimbal { mode: 1, yaw: 0.0 }, 7, &mut out).unwrap();
+        assert_eq!(len, 14);
+        assert_eq!(out[..4], [SOF, 5, 0, 7]);
+        assert!(verify_crc8(&out[..HEADER_SIZE]));
+        assert_eq!(out[5..7], [0x04, 0x01]);
+        assert_eq!(out[7..12], [1, 0, 0, 0, 0]);
+        assert!(verify_crc16(&out[..len]));
+
+        assert_eq!(
+            encode_frame(&Gimbal { mode: 1, yaw: 0.0 }, 7, &mut out[..13]),
+            Err(FrameError::BufferTooSmall { needed: 14 })
+        );
+        assert_eq!(encode_raw_frame(0x0301, 0, &[], &mut out), Ok(9));
+    }
+
+    #[test]
+    fn test_parse_frames() {
+        let packet = Gimbal { mode: 3, yaw: -2.0 };
+        let mut stream = [0u8; 40];
+        // 开头的噪声与两帧之间的垃圾字节都应被跳过
+        stream[0] = 0x42;
+        let first = encode_frame(&packet, 1, &mut stream[1..]).unwrap();
+        stream[1 + first] = 0x00;
+        let second = encode_raw_frame(0x0200, 2, &[9, 8], &mut stream[2 + first..]).unwrap();
+        let stream = &stream[..2 + first + second];
+
+        let mut parser = FrameParser::<64>::new();
+        let mut frames = 0;
+        parser.feed(stream, |result| {
+            let frame = result.unwrap();
+            match frames {
+                0 => {
+                    assert_eq!((frame.seq, frame.cmd), (1, Gimbal::CMD));
+                    assert_eq!(frame.decode::<Gimbal>(), Some(packet));
+                }
+                _ => {
+                    assert_eq!(
+                        (frame.seq, frame.cmd, frame.payload),
+                        (2, 0x0200, &[9, 8][..])
+                    );
+                    assert_eq!(frame.decode::<Gimbal>(), None);
+                }
+            }
+            frames += 1;
+        });
+        assert_eq!(frames, 2);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let mut frame = [0u8; 16];
+        let len = encode_frame(&Gimbal { mode: 3, yaw: 1.0 }, 1, &mut frame).unwrap();
+
+        let mut corrupted = frame;
+        corrupted[8] ^= 0xFF;
+        let mut parser = FrameParser::<64>::new();
+        let mut results = [None; 2];
+        let mut count = 0;
+        parser.feed(&corrupted[..len], |result| {
+            results[count] = Some(result.map(|f| f.cmd));
+            count += 1;
+        });
+        assert_eq!(
+            results[0],
+            Some(Err(FrameError::FrameCrc {
+                seq: 1,
+                cmd: 0x0104
+            }))
+        );
+
+        // 帧头损坏后从缓冲区中的下一个 SOF 继续，紧随其后的完整帧仍能解析
+        let mut stream = [0u8; 32];
+        stream[..4].copy_from_slice(&[SOF, 0x01, SOF, 0x00]);
+        stream[4..4 + len].copy_from_slice(&frame[..len]);
+        let mut parser = FrameParser::<64>::new();
+        let mut results = [None; 4];
+        let mut count = 0;
+        parser.feed(&stream[..4 + len], |result| {
+            results[count] = Some(result.map(|f| f.cmd));
+            count += 1;
+        });
+        assert_eq!(results[0], Some(Err(FrameError::HeaderCrc)));
+        assert_eq!(results[count - 1], Some(Ok(0x0104)));
+
+        // 缓冲区放不下的帧报告长度错误
+        let mut parser = FrameParser::<12>::new();
+        let mut result = None;
+        parser.feed(&frame[..len], |r| result = Some(r.map(|f| f.cmd)));
+        assert_eq!(result, Some(Err(FrameError::TooLong { payload_len: 5 })));
+    }
+}
@@ -0,0 +1,21 @@
+//! rplc 生成的 Rust 包的运行时，对应 C++ 侧的 RPL：
+//!
+//! - [`Packet`]：生成的结构体实现的 trait，提供命令码、布局哈希与按字节读写
+//! - [`FrameParser`] 与 [`encode_frame`]：DJI 裁判系统串口协议的整帧解析与打包（SOF、数据段长度、包序号、CRC8/CRC16）
+//! - [`Registry`] 与 [`registry!`]：按命令码把一帧解码为若干包类型之一
+//!
+//! 不依赖标准库，可以直接用于嵌入式目标
+
+#![no_std]
+
+pub mod crc;
+mod frame;
+mod packet;
+mod registry;
+
+pub use frame::{
+    CMD_SIZE, Frame, FrameError, FrameParser, HEADER_SIZE, SOF, TAIL_SIZE, encode_frame,
+    encode_raw_frame, frame_size,
+};
+pub use packet::Packet;
+pub use registry::{DecodeError, Registry, decode};
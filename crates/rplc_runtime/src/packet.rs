@@ -0,0 +1,79 @@
+use core::mem::size_of;
+use core::ptr;
+
+/// rplc 生成的包结构体实现的 trait，对应 C++ 侧的 `RPL::Meta::PacketTraits`
+///
+/// 实现只需要给出两个常量，字节读写由默认方法按内存布局完成，
+/// 因此既可以由生成的代码实现，也可以由派生宏实现
+///
+/// # Safety
+///
+/// 实现者必须是 `#[repr(C)]` 或 `#[repr(C, packed)]` 的结构体，并且：
+/// - 没有填充字节，`size_of::<Self>()` 等于各成员大小之和
+/// - 任意字节序列都是合法的值（成员只有整数、浮点数与它们的数组，没有 `bool`、引用或枚举）
+/// - 线上数据与目标平台的字节序一致
+///
+/// rplc 只为满足这些条件的包生成实现
+pub unsafe trait Packet: Copy + 'static {
+    /// 命令码
+    const CMD: u16;
+    /// 规范化定义的布局哈希，与 C++ 侧的 `layout_hash` 相同
+    const LAYOUT_HASH: u32;
+    /// 数据段的字节数
+    const SIZE: usize = size_of::<Self>();
+
+    /// 从数据段解码；长度与 [`Packet::SIZE`] 不同时返回 `None`
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::SIZE {
+            return None;
+        }
+        // SAFETY: 长度已检查，trait 的约定保证任意字节序列都是合法的值
+        Some(unsafe { ptr::read_unaligned(bytes.as_ptr().cast::<Self>()) })
+    }
+
+    /// 把包写入 `out` 的开头，返回写入的字节数；`out` 太短时返回 `None`
+    fn write_bytes(&self, out: &mut [u8]) -> Option<usize> {
+        let out = out.get_mut(..Self::SIZE)?;
+        // SAFETY: trait 的约定保证没有填充字节，结构体的每个字节都已初始化
+        unsafe {
+            ptr::copy_nonoverlapping(
+                (self as *const Self).cast::<u8>(),
+                out.as_mut_ptr(),
+                Self::SIZE,
+            );
+        }
+        Some(Self::SIZE)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// 与 Rust 后端为下面的定义生成的代码相同：
+    /// `{"packet_name":"Gimbal","command_id":"0x0104","fields":[{"name":"mode","type":"uint8_t"},{"name":"yaw","type":"float"}]}`
+    #[repr(C, packed)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Gimbal {
+        pub mode: u8,
+        pub yaw: f32,
+    }
+
+    unsafe impl Packet for Gimbal {
+        const CMD: u16 = 0x0104;
+        const LAYOUT_HASH: u32 = 0x1234_5678;
+    }
+
+    #[test]
+    fn test_packet_bytes_round_trip() {
+        let packet = Gimbal { mode: 2, yaw: 1.5 };
+        let mut buf = [0u8; 8];
+        assert_eq!(packet.write_bytes(&mut buf), Some(5));
+        assert_eq!(buf[..5], [2, 0x00, 0x00, 0xC0, 0x3F]);
+        assert_eq!(Gimbal::from_bytes(&buf[..5]), Some(packet));
+
+        assert_eq!(Gimbal::SIZE, 5);
+        assert_eq!(Gimbal::from_bytes(&buf[..4]), None);
+        assert_eq!(packet.write_bytes(&mut buf[..4]), None);
+    }
+}
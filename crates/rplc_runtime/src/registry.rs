@@ -0,0 +1,174 @@
+use core::fmt;
+
+use crate::frame::Frame;
+use crate::packet::Packet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// 注册表中没有该命令码
+    UnknownCommand(u16),
+    /// 数据段长度与包的大小不符
+    SizeMismatch {
+        cmd: u16,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnknownCommand(cmd) => write!(f, "未注册的命令码 0x{:04X}", cmd),
+            DecodeError::SizeMismatch {
+                cmd,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "命令码 0x{:04X} 的数据段应为 {} 字节，实际为 {} 字节",
+                cmd, expected, actual
+            ),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// 按命令码把数据段解码为单个包类型，命令码不符时返回 [`DecodeError::UnknownCommand`]
+pub fn decode<P: Packet>(cmd: u16, payload: &[u8]) -> Result<P, DecodeError> {
+    if cmd != P::CMD {
+        return Err(DecodeError::UnknownCommand(cmd));
+    }
+    P::from_bytes(payload).ok_or(DecodeError::SizeMismatch {
+        cmd,
+        expected: P::SIZE,
+        actual: payload.len(),
+    })
+}
+
+/// 一组包类型的注册表，对应 C++ 侧的 `RPL::Deserializer<Packets...>`
+///
+/// 通常由 [`registry!`](crate::registry!) 生成为每个包一个分支的枚举
+pub trait Registry: Sized {
+    /// 命令码对应的数据段长度，未注册时返回 `None`
+    fn payload_size(cmd: u16) -> Option<usize>;
+
+    /// 按命令码解码数据段
+    fn decode(cmd: u16, payload: &[u8]) -> Result<Self, DecodeError>;
+
+    /// 解码一帧经过校验的数据
+    fn decode_frame(frame: &Frame<'_>) -> Result<Self, DecodeError> {
+        Self::decode(frame.cmd, frame.payload)
+    }
+}
+
+/// 为一组生成的包定义注册表枚举，每个包一个同名分支：
+///
+/// ```
+/// # #[repr(C, packed)] #[derive(Clone, Copy)] pub struct Gimbal { pub yaw: f32 }
+/// # unsafe impl rplc_runtime::Packet for Gimbal { const CMD: u16 = 0x0104; const LAYOUT_HASH: u32 = 0; }
+/// # #[repr(C, packed)] #[derive(Clone, Copy)] pub struct Chassis { pub vx: i16 }
+/// # unsafe impl rplc_runtime::Packet for Chassis { const CMD: u16 = 0x0105; const LAYOUT_HASH: u32 = 0; }
+/// use rplc_runtime::Registry;
+///
+/// rplc_runtime::registry! {
+///     pub enum Message {
+///         Gimbal,
+///         Chassis,
+///     }
+/// }
+///
+/// assert_eq!(Message::payload_size(0x0105), Some(2));
+/// assert!(matches!(Message::decode(0x0105, &[1, 0]), Ok(Message::Chassis(_))));
+/// ```
+#[macro_export]
+macro_rules! registry {
+    ($(#[$meta:meta])* $vis:vis enum $name:ident { $($packet:ident),+ $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy)]
+        $vis enum $name {
+            $($packet($packet),)+
+        }
+
+        impl $crate::Registry for $name {
+            fn payload_size(cmd: u16) -> ::core::option::Option<usize> {
+                $(
+                    if cmd == <$packet as $crate::Packet>::CMD {
+                        return ::core::option::Option::Some(<$packet as $crate::Packet>::SIZE);
+                    }
+                )+
+                ::core::option::Option::None
+            }
+
+            fn decode(
+                cmd: u16,
+                payload: &[u8],
+            ) -> ::core::result::Result<Self, $crate::DecodeError> {
+                $(
+                    if cmd == <$packet as $crate::Packet>::CMD {
+                        return $crate::decode::<$packet>(cmd, payload).map($name::$packet);
+                    }
+                )+
+                ::core::result::Result::Err($crate::DecodeError::UnknownCommand(cmd))
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{FrameParser, encode_frame};
+    use crate::packet::tests::Gimbal;
+
+    #[repr(C, packed)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Heartbeat {
+        counter: u32,
+    }
+
+    unsafe impl Packet for Heartbeat {
+        const CMD: u16 = 0x0001;
+        const LAYOUT_HASH: u32 = 0;
+    }
+
+    registry! {
+        #[derive(Debug, PartialEq)]
+        enum Message {
+            Gimbal,
+            Heartbeat,
+        }
+    }
+
+    #[test]
+    fn test_registry_decode() {
+        assert_eq!(Message::payload_size(0x0104), Some(5));
+        assert_eq!(Message::payload_size(0x0002), None);
+        assert_eq!(
+            Message::decode(0x0001, &[1, 0, 0, 0]),
+            Ok(Message::Heartbeat(Heartbeat { counter: 1 }))
+        );
+        assert_eq!(
+            Message::decode(0x0002, &[]),
+            Err(DecodeError::UnknownCommand(0x0002))
+        );
+        assert_eq!(
+            Message::decode(0x0001, &[1, 0]),
+            Err(DecodeError::SizeMismatch {
+                cmd: 0x0001,
+                expected: 4,
+                actual: 2
+            })
+        );
+
+        let packet = Gimbal { mode: 1, yaw: 0.5 };
+        let mut out = [0u8; 16];
+        let len = encode_frame(&packet, 0, &mut out).unwrap();
+        let mut parser = FrameParser::<32>::new();
+        let mut decoded = None;
+        parser.feed(&out[..len], |frame| {
+            decoded = Some(Message::decode_frame(&frame.unwrap()));
+        });
+        assert_eq!(decoded, Some(Ok(Message::Gimbal(packet))));
+    }
+}
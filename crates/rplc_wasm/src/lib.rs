@@ -1,6 +1,31 @@
-use rplc_core::{generate, validate};
+use std::collections::BTreeMap;
+
+use miette::Diagnostic as _;
+use rplc_core::{
+    Backend, Change, ChangeReport, ChunkedValidation, GenerateOptions, RplcDiagnostic, Severity,
+    ValidateOptions, VersionBump, check_input_size, format_definition, generate, generate_backends,
+    generate_multiple, validate_multiple_with_options, validate_with_options,
+};
 use wasm_bindgen::prelude::*;
 
+/// Playground 接受的最大输入字节数，粘贴进来的超大文档只报告错误，不在浏览器中解析
+const PLAYGROUND_MAX_INPUT_SIZE: usize = 4 << 20;
+
+fn playground_options() -> ValidateOptions {
+    ValidateOptions {
+        max_input_size: Some(PLAYGROUND_MAX_INPUT_SIZE),
+        ..ValidateOptions::default()
+    }
+}
+
+/// 超过大小上限时的错误文本，供返回字符串错误的接口使用
+fn input_size_error(input: &str) -> Result<(), String> {
+    match check_input_size(input, Some(PLAYGROUND_MAX_INPUT_SIZE)) {
+        Some(diag) => Err(diag.code.to_string()),
+        None => Ok(()),
+    }
+}
+
 #[wasm_bindgen(start)]
 pub fn init() {
     console_error_panic_hook::set_once();
@@ -8,34 +33,379 @@ pub fn init() {
 
 #[derive(serde::Serialize)]
 pub struct JsDiagnostic {
-    pub severity: String,
+    /// `"Error"` 或 `"Warning"`
+    pub severity: Severity,
+    /// 诊断代码，与 `explain(code)` 及命令行输出中的相同，JSON 语法错误为 `rplc::json::syntax`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
     pub message: String,
     pub span: Option<(usize, usize)>,
+    /// 针对这一处问题的建议，例如拼写错误时最接近的合法写法；没有时为该规则的通用修复建议
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
 }
 
 // {
 // "severity": "Error",
-// "message": "Packet名称 'bad_name' 不合法",
-// "span": [15, 8]
+// "code": "rplc::invalid_field_type",
+// "message": "'yaw' 的 Type 无效",
+// "span": [58, 7],
+// "help": "是否应为 'float'？"
 // }
 
-#[wasm_bindgen]
-pub fn check_json(input: &str) -> JsValue {
-    let raw_diags = validate(input);
-
-    let js_diags: Vec<JsDiagnostic> = raw_diags
+fn js_diagnostics(raw_diags: Vec<RplcDiagnostic>) -> Vec<JsDiagnostic> {
+    raw_diags
         .into_iter()
         .map(|d| JsDiagnostic {
-            severity: format!("{:?}", d.severity),
+            severity: d.severity,
+            code: d.code.code().map(|code| code.to_string()),
             message: d.code.to_string(),
             span: d.span,
+            help: d
+                .help
+                .or_else(|| d.code.help().map(|help| help.to_string())),
         })
-        .collect();
+        .collect()
+}
+
+fn to_js_diagnostics(raw_diags: Vec<RplcDiagnostic>) -> JsValue {
+    serde_wasm_bindgen::to_value(&js_diagnostics(raw_diags)).unwrap()
+}
+
+fn check(input: &str) -> Vec<JsDiagnostic> {
+    js_diagnostics(validate_with_options(input, &playground_options()))
+}
+
+#[wasm_bindgen]
+pub fn check_json(input: &str) -> JsValue {
+    serde_wasm_bindgen::to_value(&check(input)).unwrap()
+}
+
+fn check_multi(input: &str) -> Vec<JsDiagnostic> {
+    js_diagnostics(validate_multiple_with_options(input, &playground_options()))
+}
+
+/// 校验顶层为数组或包集合的多包文档，诊断位置相对于整个文档；单个包同样可以校验
+#[wasm_bindgen]
+pub fn check_json_multi(input: &str) -> JsValue {
+    serde_wasm_bindgen::to_value(&check_multi(input)).unwrap()
+}
+
+#[derive(serde::Serialize)]
+pub struct JsDiagnosticBatch {
+    pub diagnostics: Vec<JsDiagnostic>,
+    pub validated: usize,
+    pub total: usize,
+}
 
-    serde_wasm_bindgen::to_value(&js_diags).unwrap()
+#[derive(serde::Serialize)]
+pub struct JsIteratorResult {
+    pub done: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<JsDiagnosticBatch>,
+}
+
+// const check = new ChunkedCheck(input, 20);
+// for (let r = check.next(); !r.done; r = check.next()) {
+//     show(r.value.diagnostics, r.value.validated / r.value.total);
+//     await new Promise((resolve) => setTimeout(resolve));
+// }
+
+/// 分批校验大型多包文档，`next()` 符合 JS 迭代器协议，
+/// 每次返回 `{ done, value: { diagnostics, validated, total } }`；
+/// 调用方可在两批之间让出主线程，或在 Worker 中逐批 `postMessage`
+#[wasm_bindgen]
+pub struct ChunkedCheck {
+    inner: ChunkedValidation,
+}
+
+#[wasm_bindgen]
+impl ChunkedCheck {
+    /// 每批最多校验 `chunk_size` 个包
+    #[wasm_bindgen(constructor)]
+    pub fn new(input: String, chunk_size: usize) -> ChunkedCheck {
+        ChunkedCheck {
+            inner: ChunkedValidation::new(input, playground_options(), chunk_size),
+        }
+    }
+
+    /// 文档中的包数
+    #[wasm_bindgen(getter)]
+    pub fn total(&self) -> usize {
+        self.inner.total()
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> JsValue {
+        let value = self.inner.next().map(|batch| JsDiagnosticBatch {
+            diagnostics: js_diagnostics(batch.diagnostics),
+            validated: batch.validated,
+            total: batch.total,
+        });
+        let result = JsIteratorResult {
+            done: value.is_none(),
+            value,
+        };
+        serde_wasm_bindgen::to_value(&result).unwrap()
+    }
 }
 
 #[wasm_bindgen]
 pub fn compile_cpp(input: &str) -> Result<String, String> {
+    input_size_error(input)?;
     generate(input).map_err(|e| e.to_string())
 }
+
+// {
+// "Gimbal": "#ifndef RPL_GIMBAL_HPP ...",
+// "Chassis": "#ifndef RPL_CHASSIS_HPP ..."
+// }
+
+fn compile_multi(input: &str) -> Result<Vec<(String, String)>, String> {
+    input_size_error(input)?;
+    generate_multiple(input).map_err(|e| e.to_string())
+}
+
+/// 多包文档逐包生成 C++ 头文件，返回 包名 → 头文件内容 的对象，按包在文档中的顺序排列；
+/// 任意一个包有错误时整个调用失败，诊断由 `check_json_multi` 给出
+#[wasm_bindgen]
+pub fn compile_cpp_multi(input: &str) -> Result<JsValue, String> {
+    let headers = compile_multi(input)?;
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    serde::Serializer::collect_map(&serializer, headers).map_err(|e| e.to_string())
+}
+
+/// 定义文件的 JSON Schema 文本，供网页编辑器补全键与检查结构
+#[wasm_bindgen]
+pub fn config_schema() -> String {
+    rplc_core::config_schema()
+}
+
+#[derive(serde::Serialize)]
+pub struct JsCompileOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// {
+// "cpp": { "code": "#ifndef RPL_A_HPP ..." },
+// "rust": { "error": "Rust 后端不支持该定义: ..." }
+// }
+
+fn unknown_target(name: &str) -> String {
+    let names: Vec<_> = Backend::ALL.iter().map(|backend| backend.name()).collect();
+    format!("未知的目标: {}，可用的目标: {}", name, names.join("、"))
+}
+
+/// 一次生成多个后端的输出，返回 目标 → { code } 或 { error } 的对象；
+/// 目标名称与 `Backend::ALL` 一致：cpp、c、rust、markdown、python、matlab、imhex、latex、svg，
+/// 未知名称时整个调用失败，错误中列出全部可用名称
+#[wasm_bindgen]
+pub fn compile(input: &str, targets: Vec<String>) -> Result<JsValue, String> {
+    input_size_error(input)?;
+    let backends = targets
+        .iter()
+        .map(|name| Backend::from_name(name).ok_or_else(|| unknown_target(name)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let outputs: BTreeMap<&str, JsCompileOutput> =
+        generate_backends(input, &backends, &GenerateOptions::default())
+            .into_iter()
+            .map(|(backend, result)| {
+                let output = match result {
+                    Ok(code) => JsCompileOutput {
+                        code: Some(code),
+                        error: None,
+                    },
+                    Err(e) => JsCompileOutput {
+                        code: None,
+                        error: Some(e.to_string()),
+                    },
+                };
+                (backend.name(), output)
+            })
+            .collect();
+
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    serde::Serialize::serialize(&outputs, &serializer).map_err(|e| e.to_string())
+}
+
+/// 新建包的 JSON 骨架，与 `rplc init` 使用同一模板
+#[wasm_bindgen]
+pub fn new_packet_template(name: &str, cmd: &str) -> Result<String, String> {
+    rplc_core::new_packet_template(name, cmd).map_err(|e| e.to_string())
+}
+
+/// 带 `protocol_version` 的多包骨架，与 `rplc init --set` 使用同一模板
+#[wasm_bindgen]
+pub fn new_packet_set_template(name: &str, cmd: &str) -> Result<String, String> {
+    rplc_core::new_packet_set_template(name, cmd).map_err(|e| e.to_string())
+}
+
+/// 规范化定义的格式，与 `rplc fmt` 使用同一引擎；JSON 语法错误时返回诊断数组
+#[wasm_bindgen]
+pub fn format_json(input: &str) -> Result<String, JsValue> {
+    if let Some(diag) = check_input_size(input, Some(PLAYGROUND_MAX_INPUT_SIZE)) {
+        return Err(to_js_diagnostics(vec![diag]));
+    }
+    format_definition(input).map_err(to_js_diagnostics)
+}
+
+#[derive(serde::Serialize)]
+pub struct JsChange {
+    #[serde(flatten)]
+    pub change: Change,
+    pub message: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct JsChangeReport {
+    pub changes: Vec<JsChange>,
+    pub required_bump: VersionBump,
+    pub breaking: bool,
+}
+
+impl From<ChangeReport> for JsChangeReport {
+    fn from(report: ChangeReport) -> Self {
+        Self {
+            required_bump: report.required_bump(),
+            breaking: report.has_breaking_changes(),
+            changes: report
+                .changes
+                .into_iter()
+                .map(|change| JsChange {
+                    message: change.to_string(),
+                    change,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn change_report(old: &str, new: &str) -> Result<JsChangeReport, String> {
+    input_size_error(old)?;
+    input_size_error(new)?;
+    let report = rplc_core::diff(old, new).map_err(|e| e.to_string())?;
+    Ok(JsChangeReport::from(report))
+}
+
+/// 比较两个版本的定义，与 `--previous` 使用同一变更检测
+#[wasm_bindgen]
+pub fn diff_configs(old: &str, new: &str) -> Result<JsValue, String> {
+    let report = change_report(old, new)?;
+    serde_wasm_bindgen::to_value(&report).map_err(|e| e.to_string())
+}
+
+/// 诊断代码的详细说明，与 `rplc explain` 相同；未知代码返回 `undefined`
+#[wasm_bindgen]
+pub fn explain(code: &str) -> Option<String> {
+    rplc_core::explain(code).map(str::to_string)
+}
+
+/// 所有校验规则的代码、默认级别、修复建议与详细说明，与 `--emit rules.md` 相同
+#[wasm_bindgen]
+pub fn rules() -> Result<JsValue, String> {
+    serde_wasm_bindgen::to_value(&rplc_core::rules()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIMBAL: &str = r#"{"packet_name":"Gimbal","command_id":"0x0104","fields":[
+        {"name":"yaw","type":"float","comment":"偏航角"}]}"#;
+
+    const SET: &str = r#"{"protocol_version":"1.0.0","packets":[
+        {"packet_name":"Gimbal","command_id":"0x0104","fields":[
+            {"name":"yaw","type":"float","comment":"偏航角"}]},
+        {"packet_name":"Chassis","command_id":"0x0105","fields":[
+            {"name":"speed","type":"int16_t","comment":"速度"}]}]}"#;
+
+    #[test]
+    fn test_diagnostic_fields() {
+        // 拼写错误：代码、针对性建议与消息都传给网页
+        let misspelled = GIMBAL.replace("\"float\"", "\"flaot\"");
+        let diags = check(&misspelled);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].severity, Severity::Error);
+        assert_eq!(diags[0].code.as_deref(), Some("rplc::invalid_field_type"));
+        assert_eq!(diags[0].message, "'yaw' 的 Type 无效");
+        assert_eq!(diags[0].help.as_deref(), Some("是否应为 'float'？"));
+        let (start, len) = diags[0].span.unwrap();
+        assert_eq!(&misspelled[start..start + len], "\"flaot\"");
+
+        // 没有针对性建议时回退到规则的通用修复建议
+        let diags = check(&GIMBAL.replace(",\"comment\":\"偏航角\"", ""));
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].severity, Severity::Warning);
+        assert_eq!(diags[0].code.as_deref(), Some("rplc::doc::missing"));
+        assert_eq!(diags[0].help.as_deref(), Some("添加注释有助于生成文档"));
+
+        // JSON 语法错误同样返回诊断，序列化后字段名与网页读取的一致
+        let value = serde_json::to_value(check("{\"packet_name\": 1,")).unwrap();
+        let diags = value.as_array().unwrap();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0]["severity"], "Error");
+        assert_eq!(diags[0]["code"], "rplc::json::syntax");
+        assert!(diags[0]["message"].as_str().unwrap().contains("JSON"));
+    }
+
+    #[test]
+    fn test_multi_packet_input() {
+        assert!(check_multi(SET).is_empty());
+        let headers = compile_multi(SET).unwrap();
+        let names: Vec<&str> = headers.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["Gimbal", "Chassis"]);
+        assert!(headers[1].1.starts_with("#ifndef RPL_CHASSIS_HPP\n"));
+
+        // 单个包同样可以校验与生成
+        assert!(check_multi(GIMBAL).is_empty());
+        assert_eq!(compile_multi(GIMBAL).unwrap().len(), 1);
+
+        // 第二个包的诊断位置相对于整个文档
+        let broken = SET.replace("\"int16_t\"", "\"int16\"");
+        let diags = check_multi(&broken);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].severity, Severity::Error);
+        let (start, len) = diags[0].span.unwrap();
+        assert_eq!(&broken[start..start + len], "\"int16\"");
+        assert!(compile_multi(&broken).is_err());
+    }
+
+    #[test]
+    fn test_diff_configs() {
+        let unchanged = change_report(GIMBAL, GIMBAL).unwrap();
+        assert!(unchanged.changes.is_empty());
+        assert_eq!(unchanged.required_bump, VersionBump::None);
+        assert!(!unchanged.breaking);
+
+        let retyped = GIMBAL.replace("\"float\"", "\"double\"");
+        let report = serde_json::to_value(change_report(GIMBAL, &retyped).unwrap()).unwrap();
+        assert_eq!(report["required_bump"], "Major");
+        assert_eq!(report["breaking"], true);
+        // 变更的字段展开在同一层，附带可读的说明
+        let change = &report["changes"][0];
+        assert_eq!(change["packet"], "Gimbal");
+        assert_eq!(change["field"], "yaw");
+        assert_eq!(change["compatibility"], "Breaking");
+        assert_eq!(
+            change["kind"]["FieldTypeChanged"],
+            serde_json::json!({ "old": "float", "new": "double" })
+        );
+        assert!(change["message"].as_str().unwrap().contains("yaw"));
+
+        let appended = GIMBAL.replace(
+            "\"comment\":\"偏航角\"}",
+            "\"comment\":\"偏航角\"},{\"name\":\"pitch\",\"type\":\"float\"}",
+        );
+        let report = change_report(GIMBAL, &appended).unwrap();
+        assert_eq!(report.required_bump, VersionBump::Minor);
+        assert!(!report.breaking);
+
+        assert!(change_report(GIMBAL, "{").is_err());
+        let oversized = " ".repeat(PLAYGROUND_MAX_INPUT_SIZE + 1);
+        assert!(change_report(GIMBAL, &oversized).is_err());
+    }
+}
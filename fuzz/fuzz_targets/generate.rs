@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// 校验通过的输入进入代码生成，两条路径都不能 panic
+fuzz_target!(|input: &str| {
+    let _ = rplc_core::generate(input);
+    let _ = rplc_core::generate_multiple(input);
+});
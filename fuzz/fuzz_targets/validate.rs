@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rplc_core::{Target, ValidateOptions};
+
+// 任意输入都必须返回诊断，而不是 panic
+fuzz_target!(|input: &str| {
+    let _ = rplc_core::validate(input);
+    let _ = rplc_core::validate_with_options(
+        input,
+        &ValidateOptions {
+            targets: Target::ALL.to_vec(),
+            ..Default::default()
+        },
+    );
+});